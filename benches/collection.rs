@@ -0,0 +1,148 @@
+//! Workloads over a whole collection, with a synthetic one
+//!
+//! `chef bench` runs the same on a real collection.
+
+use cooklang::{ingredient_list::IngredientList, Converter, CooklangParser, ScalableRecipe};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+const RECIPES: usize = 500;
+
+const INGREDIENTS: &[(&str, &str)] = &[
+    ("flour", "g"),
+    ("sugar", "g"),
+    ("butter", "g"),
+    ("milk", "ml"),
+    ("water", "ml"),
+    ("olive oil", "tbsp"),
+    ("salt", "tsp"),
+    ("pepper", "tsp"),
+    ("rice", "cups"),
+    ("onion", ""),
+    ("garlic", "cloves"),
+    ("tomatoes", "g"),
+    ("chicken", "kg"),
+    ("eggs", ""),
+    ("cream", "ml"),
+    ("cheese", "g"),
+    ("basil", "bunch"),
+    ("lemon", ""),
+    ("honey", "tbsp"),
+    ("stock", "l"),
+];
+
+/// A recipe like the ones in a real collection, different for each `i`
+fn synthetic_recipe(i: usize) -> String {
+    let mut text = format!(
+        "---\ntitle: Recipe {i}\nservings: 2|4|8\ntags: [tag{}, tag{}]\ntime: {} min\n---\n\n",
+        i % 7,
+        i % 13,
+        10 + i % 90
+    );
+    for section in 0..3 {
+        text += &format!("= Part {section}\n\n");
+        for step in 0..4 {
+            let n = i + section * 4 + step;
+            let (a, a_unit) = INGREDIENTS[n % INGREDIENTS.len()];
+            let (b, b_unit) = INGREDIENTS[(n * 7 + 3) % INGREDIENTS.len()];
+            text += &format!(
+                "Mix @{a}{{{}}} with @{b}{{{}}} in a #bowl{{}} and leave it for ~{{{}%minutes}}.\n\n",
+                quantity(1 + n % 5, a_unit),
+                quantity((n % 3) * 50 + 25, b_unit),
+                5 + n % 20
+            );
+        }
+    }
+    text
+}
+
+fn quantity(value: usize, unit: &str) -> String {
+    if unit.is_empty() {
+        value.to_string()
+    } else {
+        format!("{value}%{unit}")
+    }
+}
+
+fn collection(parser: &CooklangParser) -> Vec<ScalableRecipe> {
+    (0..RECIPES)
+        .map(|i| {
+            parser
+                .parse(&synthetic_recipe(i))
+                .into_output()
+                .expect("bench recipe has errors")
+        })
+        .collect()
+}
+
+/// Like `chef shopping-list`, without the preparations
+fn shopping_list(recipes: Vec<ScalableRecipe>, converter: &Converter) -> IngredientList {
+    let mut list = IngredientList::new();
+    for recipe in recipes {
+        let scaled = recipe.scale(4, converter);
+        for entry in scaled.group_ingredients(converter) {
+            if !entry.ingredient.modifiers().should_be_listed() {
+                continue;
+            }
+            list.add_ingredient(
+                entry.ingredient.display_name().to_string(),
+                &entry.quantity,
+                converter,
+            );
+        }
+    }
+    list
+}
+
+fn workloads(c: &mut Criterion) {
+    let parser = CooklangParser::default();
+    let converter = parser.converter();
+    let texts = (0..RECIPES).map(synthetic_recipe).collect::<Vec<_>>();
+    let default_scaled = collection(&parser)
+        .into_iter()
+        .map(|r| r.default_scale())
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("collection");
+    group.throughput(Throughput::Elements(RECIPES as u64));
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            for text in &texts {
+                parser.parse(text).into_output().unwrap();
+            }
+        })
+    });
+    group.bench_function("render_human", |b| {
+        b.iter_batched_ref(
+            || Vec::with_capacity(1 << 22),
+            |buf| {
+                for scaled in &default_scaled {
+                    cooklang_to_human::print_human(scaled, "Recipe", converter, &mut *buf).unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("scale", |b| {
+        // scaling consumes the recipes, each iteration gets a new collection
+        b.iter_batched(
+            || collection(&parser),
+            |recipes| {
+                for recipe in recipes {
+                    recipe.scale(4, converter);
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("shopping_list", |b| {
+        b.iter_batched(
+            || collection(&parser),
+            |recipes| shopping_list(recipes, converter),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, workloads);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+#ifndef COOKLANG_CHEF_H
+#define COOKLANG_CHEF_H
+
+/* Generated with cbindgen, do not edit. Regenerate with `cbindgen --config cbindgen.toml --output include/chef.h` */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parses a recipe
+ *
+ * Returns a JSON object with the `recipe`, `null` if it has errors, and the
+ * `diagnostics`, a list of objects with `severity` and `message`.
+ *
+ * # Safety
+ *
+ * `input` must be a valid NUL terminated string.
+ */
+char *chef_parse(const char *input);
+
+/**
+ * Renders a recipe as a standalone HTML document
+ *
+ * `servings` scales the recipe, 0 to keep the recipe as written.
+ *
+ * # Safety
+ *
+ * `input` and `name` must be valid NUL terminated strings.
+ */
+char *chef_render_html(const char *input, const char *name, uint32_t servings);
+
+/**
+ * Merges the ingredients of several recipes into a shopping list
+ *
+ * Returns a JSON array of objects with the ingredient `name` and a list of
+ * `quantity`.
+ *
+ * # Safety
+ *
+ * `recipes` must point to `len` valid NUL terminated strings. `servings` can
+ * be `NULL` or point to `len` numbers, 0 to keep a recipe as written.
+ */
+char *chef_shopping_list_json(const char *const *recipes, const uint32_t *servings, uintptr_t len);
+
+/**
+ * Message of the last error in this thread, or `NULL`
+ *
+ * The string is owned by the library and valid until the next call.
+ */
+const char *chef_last_error(void);
+
+/**
+ * Frees a string returned by the library
+ *
+ * # Safety
+ *
+ * `s` must be `NULL` or a string returned by a `chef_*` function that has not
+ * been freed yet.
+ */
+void chef_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* COOKLANG_CHEF_H */
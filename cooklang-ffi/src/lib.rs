@@ -0,0 +1,261 @@
+//! C bindings
+//!
+//! Every string going in or out is UTF-8 and NUL terminated. Returned strings
+//! are owned by the caller and must be freed with [`chef_string_free`]. On
+//! error, functions return `NULL` and the message can be read with
+//! [`chef_last_error`]. Panics are caught and reported as errors.
+//!
+//! Recipes are parsed with all the extensions and the bundled units, like
+//! `chef` does without configuration.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::LazyLock,
+};
+
+use cooklang::{
+    error::Severity, ingredient_list::IngredientList, CooklangParser, ScalableRecipe,
+    ScaledQuantity, ScaledRecipe,
+};
+use cooklang_render::{RecipeRenderer, RenderInput};
+use serde::Serialize;
+
+static PARSER: LazyLock<CooklangParser> = LazyLock::new(CooklangParser::default);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+#[derive(Serialize)]
+struct Parsed<'a> {
+    recipe: Option<&'a ScalableRecipe>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+struct Diagnostic {
+    severity: &'static str,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ListIngredient {
+    name: String,
+    quantity: Vec<ScaledQuantity>,
+}
+
+/// Parses a recipe
+///
+/// Returns a JSON object with the `recipe`, `null` if it has errors, and the
+/// `diagnostics`, a list of objects with `severity` and `message`.
+///
+/// # Safety
+///
+/// `input` must be a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chef_parse(input: *const c_char) -> *mut c_char {
+    ffi_result(|| {
+        let input = str_arg(input, "input")?;
+        let res = PARSER.parse(input);
+        let diagnostics = res
+            .report()
+            .iter()
+            .map(|d| Diagnostic {
+                severity: match d.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                },
+                message: d.message.to_string(),
+            })
+            .collect();
+        let recipe = res.valid_output();
+        let parsed = Parsed {
+            recipe,
+            diagnostics,
+        };
+        serde_json::to_string(&parsed).map_err(|e| e.to_string())
+    })
+}
+
+/// Renders a recipe as a standalone HTML document
+///
+/// `servings` scales the recipe, 0 to keep the recipe as written.
+///
+/// # Safety
+///
+/// `input` and `name` must be valid NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn chef_render_html(
+    input: *const c_char,
+    name: *const c_char,
+    servings: u32,
+) -> *mut c_char {
+    ffi_result(|| {
+        let input = str_arg(input, "input")?;
+        let name = str_arg(name, "name")?;
+        let recipe = parse_and_scale(input, servings)?;
+        let mut buf = Vec::new();
+        cooklang_render::Html
+            .render(
+                &mut buf,
+                &RenderInput::new(&recipe, name, PARSER.converter()),
+            )
+            .map_err(|e| e.to_string())?;
+        String::from_utf8(buf).map_err(|e| e.to_string())
+    })
+}
+
+/// Merges the ingredients of several recipes into a shopping list
+///
+/// Returns a JSON array of objects with the ingredient `name` and a list of
+/// `quantity`.
+///
+/// # Safety
+///
+/// `recipes` must point to `len` valid NUL terminated strings. `servings` can
+/// be `NULL` or point to `len` numbers, 0 to keep a recipe as written.
+#[no_mangle]
+pub unsafe extern "C" fn chef_shopping_list_json(
+    recipes: *const *const c_char,
+    servings: *const u32,
+    len: usize,
+) -> *mut c_char {
+    ffi_result(|| {
+        if recipes.is_null() && len > 0 {
+            return Err("recipes is NULL".into());
+        }
+        let mut list = IngredientList::new();
+        for i in 0..len {
+            let input = str_arg(*recipes.add(i), "recipe")?;
+            let servings = if servings.is_null() {
+                0
+            } else {
+                *servings.add(i)
+            };
+            let recipe =
+                parse_and_scale(input, servings).map_err(|e| format!("recipe {i}: {e}"))?;
+            list.add_recipe(&recipe, PARSER.converter());
+        }
+        let items = list
+            .into_iter()
+            .map(|(name, quantity)| ListIngredient {
+                name,
+                quantity: quantity.into_vec(),
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&items).map_err(|e| e.to_string())
+    })
+}
+
+/// Message of the last error in this thread, or `NULL`
+///
+/// The string is owned by the library and valid until the next call.
+#[no_mangle]
+pub extern "C" fn chef_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|e| e.as_ref().map_or(std::ptr::null(), |e| e.as_ptr()))
+}
+
+/// Frees a string returned by the library
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by a `chef_*` function that has not
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chef_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+fn parse_and_scale(input: &str, servings: u32) -> Result<ScaledRecipe, String> {
+    let recipe = PARSER
+        .parse(input)
+        .into_result()
+        .map(|(recipe, _warnings)| recipe)
+        .map_err(|report| {
+            report
+                .iter()
+                .map(|d| d.message.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+    Ok(if servings == 0 {
+        recipe.default_scale()
+    } else {
+        recipe.scale(servings, PARSER.converter())
+    })
+}
+
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err(format!("{name} is NULL"));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| format!("{name} is not valid UTF-8"))
+}
+
+fn ffi_result(f: impl FnOnce() -> Result<String, String>) -> *mut c_char {
+    // a panic can't unwind into C, it becomes an error
+    let res = catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic");
+            Err(format!("panic: {msg}"))
+        })
+        .and_then(|s| CString::new(s).map_err(|e| e.to_string()));
+    match res {
+        Ok(s) => {
+            LAST_ERROR.set(None);
+            s.into_raw()
+        }
+        Err(e) => {
+            // the message can't have NULs, they are just dropped
+            let e = CString::new(e.replace('\0', "")).unwrap_or_default();
+            LAST_ERROR.set(Some(e));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_render_and_free() {
+        let input = CString::new("Mix @flour{200%g} and @water{100%ml}.").unwrap();
+        unsafe {
+            let json = chef_parse(input.as_ptr());
+            assert!(!json.is_null());
+            let value: serde_json::Value =
+                serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            assert!(value["recipe"].is_object());
+            chef_string_free(json);
+
+            let recipes = [input.as_ptr(), input.as_ptr()];
+            let list = chef_shopping_list_json(recipes.as_ptr(), std::ptr::null(), 2);
+            assert!(!list.is_null());
+            assert!(CStr::from_ptr(list).to_str().unwrap().contains("flour"));
+            chef_string_free(list);
+
+            assert!(chef_render_html(std::ptr::null(), input.as_ptr(), 0).is_null());
+            let err = CStr::from_ptr(chef_last_error()).to_str().unwrap();
+            assert_eq!(err, "input is NULL");
+        }
+    }
+
+    #[test]
+    fn panic_is_an_error() {
+        let res = ffi_result(|| panic!("boom"));
+        assert!(res.is_null());
+        let err = unsafe { CStr::from_ptr(chef_last_error()) };
+        assert_eq!(err.to_str().unwrap(), "panic: boom");
+    }
+}
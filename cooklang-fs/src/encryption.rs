@@ -0,0 +1,66 @@
+use std::sync::OnceLock;
+
+use camino::Utf8Path;
+
+/// Extensions of encrypted recipe files
+///
+/// They go after the recipe extension, like `Stew.cook.age`.
+pub const ENCRYPTED_EXTENSIONS: &[&str] = &["age", "gpg"];
+
+type Decryptor = Box<dyn Fn(&Utf8Path) -> std::io::Result<String> + Send + Sync>;
+
+static DECRYPTOR: OnceLock<Decryptor> = OnceLock::new();
+
+/// Sets how encrypted recipes are read
+///
+/// This crate doesn't decrypt files itself. Without a decryptor, reading an
+/// encrypted recipe with [`RecipeEntry::read`](crate::RecipeEntry::read)
+/// fails. It can only be set once, returns `false` if it was already set.
+pub fn set_decryptor(
+    decryptor: impl Fn(&Utf8Path) -> std::io::Result<String> + Send + Sync + 'static,
+) -> bool {
+    DECRYPTOR.set(Box::new(decryptor)).is_ok()
+}
+
+/// Checks if a file has an encrypted extension
+pub fn is_encrypted(path: &Utf8Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ENCRYPTED_EXTENSIONS.contains(&ext))
+}
+
+/// Path without the encrypted extension, if any
+pub fn plain_path(path: &Utf8Path) -> &Utf8Path {
+    match path.extension() {
+        Some(ext) if ENCRYPTED_EXTENSIONS.contains(&ext) => {
+            let s = path.as_str();
+            Utf8Path::new(&s[..s.len() - ext.len() - 1])
+        }
+        _ => path,
+    }
+}
+
+pub(crate) fn read(path: &Utf8Path) -> std::io::Result<String> {
+    if !is_encrypted(path) {
+        return std::fs::read_to_string(path);
+    }
+    match DECRYPTOR.get() {
+        Some(decrypt) => decrypt(path),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("Can't decrypt {path}, no decryptor set"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain() {
+        assert_eq!(plain_path("a/Stew.cook.age".into()), "a/Stew.cook");
+        assert_eq!(plain_path("a/Stew.cook".into()), "a/Stew.cook");
+        assert!(is_encrypted("Stew.cook.gpg".into()));
+        assert!(!is_encrypted("Stew.cook".into()));
+    }
+}
@@ -7,6 +7,8 @@
 //! from a path. The index can be lazy or eager. Both created with
 //! [`new_index`].
 
+mod encryption;
+mod rules;
 mod walker;
 
 use std::{cell::RefCell, collections::HashMap, sync::OnceLock};
@@ -15,6 +17,8 @@ use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 use cooklang::quantity::QuantityValue;
 use serde::Serialize;
 
+pub use encryption::{is_encrypted, plain_path, set_decryptor, ENCRYPTED_EXTENSIONS};
+pub use rules::{DiscoveryRules, RECIPE_EXTENSION};
 pub use walker::DirEntry;
 use walker::Walker;
 
@@ -28,6 +32,7 @@ pub fn new_index(
 pub struct FsIndexBuilder {
     base_path: Utf8PathBuf,
     walker: Walker,
+    rules: DiscoveryRules,
 }
 
 impl FsIndexBuilder {
@@ -41,9 +46,17 @@ impl FsIndexBuilder {
         Ok(Self {
             base_path: base_path.to_path_buf(),
             walker,
+            rules: DiscoveryRules::default(),
         })
     }
 
+    /// Sets the rules to find recipes
+    pub fn rules(mut self, rules: DiscoveryRules) -> Self {
+        self.walker.set_rules(rules.clone(), &self.base_path);
+        self.rules = rules;
+        self
+    }
+
     /// Sets a config dir to the walker
     ///
     /// If this dir is found not in the top level, a warning will be printed.
@@ -67,6 +80,7 @@ impl FsIndexBuilder {
     pub fn lazy(self) -> LazyFsIndex {
         LazyFsIndex {
             base_path: self.base_path,
+            rules: self.rules,
             walker: RefCell::new(self.walker),
             cache: RefCell::new(Cache::default()),
         }
@@ -78,6 +92,7 @@ impl FsIndexBuilder {
         index_all(&mut cache, &mut self.walker)?;
         Ok(FsIndex {
             base_path: self.base_path,
+            rules: self.rules,
             cache,
         })
     }
@@ -108,6 +123,7 @@ fn index_all(cache: &mut Cache, walker: &mut Walker) -> Result<(), Error> {
 #[derive(Debug)]
 pub struct LazyFsIndex {
     base_path: Utf8PathBuf,
+    rules: DiscoveryRules,
     cache: RefCell<Cache>,
     walker: RefCell<Walker>,
 }
@@ -118,6 +134,7 @@ pub struct LazyFsIndex {
 #[derive(Debug)]
 pub struct FsIndex {
     base_path: Utf8PathBuf,
+    rules: DiscoveryRules,
     cache: Cache,
 }
 
@@ -149,6 +166,11 @@ impl FsIndex {
         &self.base_path
     }
 
+    /// Rules used to find the recipes
+    pub fn rules(&self) -> &DiscoveryRules {
+        &self.rules
+    }
+
     pub fn contains(&self, recipe: &str) -> bool {
         let Ok((name, path)) = into_name_path(recipe) else {
             return false;
@@ -165,7 +187,7 @@ impl FsIndex {
         recipe: &str,
         relative_to: Option<&Utf8Path>,
     ) -> Result<RecipeEntry, Error> {
-        try_path(recipe, relative_to, &self.base_path).or_else(|_| self.get(recipe))
+        try_path(recipe, relative_to, &self.base_path, &self.rules).or_else(|_| self.get(recipe))
     }
 
     pub fn get(&self, recipe: &str) -> Result<RecipeEntry, Error> {
@@ -253,6 +275,7 @@ impl LazyFsIndex {
         index_all(&mut cache, &mut walker)?;
         Ok(FsIndex {
             base_path: self.base_path,
+            rules: self.rules,
             cache,
         })
     }
@@ -266,7 +289,7 @@ impl LazyFsIndex {
         recipe: &str,
         relative_to: Option<&Utf8Path>,
     ) -> Result<RecipeEntry, Error> {
-        try_path(recipe, relative_to, &self.base_path).or_else(|_| self.get(recipe))
+        try_path(recipe, relative_to, &self.base_path, &self.rules).or_else(|_| self.get(recipe))
     }
 
     /// Get a recipe from the index
@@ -346,7 +369,7 @@ impl Cache {
 
 fn into_name_path(recipe: &str) -> Result<(String, Utf8PathBuf), Error> {
     let path = Utf8PathBuf::from(recipe);
-    let name = path
+    let name = plain_path(&path)
         .file_stem()
         .ok_or_else(|| Error::InvalidName(recipe.into()))?
         .to_string();
@@ -354,7 +377,7 @@ fn into_name_path(recipe: &str) -> Result<(String, Utf8PathBuf), Error> {
 }
 
 fn compare_path_key(p: &Utf8Path) -> Utf8PathBuf {
-    Utf8PathBuf::from(p.as_str().to_lowercase()).with_extension("")
+    Utf8PathBuf::from(plain_path(p).as_str().to_lowercase()).with_extension("")
 }
 
 fn compare_path(full: &Utf8Path, suffix: &Utf8Path) -> bool {
@@ -366,12 +389,23 @@ fn compare_path(full: &Utf8Path, suffix: &Utf8Path) -> bool {
 pub fn all_recipes(
     base_path: impl AsRef<std::path::Path>,
     max_depth: usize,
+) -> Result<impl Iterator<Item = RecipeEntry>, std::io::Error> {
+    all_recipes_with_rules(base_path, max_depth, &DiscoveryRules::default())
+}
+
+/// Same as [`all_recipes`] with some [`DiscoveryRules`]
+pub fn all_recipes_with_rules(
+    base_path: impl AsRef<std::path::Path>,
+    max_depth: usize,
+    rules: &DiscoveryRules,
 ) -> Result<impl Iterator<Item = RecipeEntry>, std::io::Error> {
     let base_path: &Utf8Path = base_path
         .as_ref()
         .try_into()
         .map_err(|e: camino::FromPathError| e.into_io_error())?;
-    let walker = Walker::new(base_path, max_depth).flatten();
+    let mut walker = Walker::new(base_path, max_depth);
+    walker.set_rules(rules.clone(), base_path);
+    let walker = walker.flatten();
     let grouped = group_images(walker);
     Ok(grouped.filter_map(|e| match e {
         Entry::Dir(_) => None,
@@ -387,13 +421,26 @@ pub fn walk_dir(
         .as_ref()
         .try_into()
         .map_err(|e: camino::FromPathError| e.into_io_error())?;
+    walk_dir_with_rules(path, path, &DiscoveryRules::default())
+}
+
+/// Same as [`walk_dir`] with some [`DiscoveryRules`]
+///
+/// `base_path` is the base of the globs, an ancestor of `path`.
+pub fn walk_dir_with_rules(
+    path: &Utf8Path,
+    base_path: &Utf8Path,
+    rules: &DiscoveryRules,
+) -> Result<impl Iterator<Item = Entry>, std::io::Error> {
     if !path.is_dir() {
         return Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
             "dir not found",
         ));
     }
-    Ok(group_images(Walker::new(path, 0).flatten()))
+    let mut walker = Walker::new(path, 0);
+    walker.set_rules(rules.clone(), base_path);
+    Ok(group_images(walker.flatten()))
 }
 
 fn group_images(walker: impl Iterator<Item = DirEntry>) -> impl Iterator<Item = Entry> {
@@ -450,8 +497,9 @@ fn try_path(
     recipe: &str,
     relative_to: Option<&Utf8Path>,
     base_path: &Utf8Path,
+    rules: &DiscoveryRules,
 ) -> Result<RecipeEntry, Error> {
-    let mut path = Utf8PathBuf::from(recipe).with_extension("cook");
+    let mut path = Utf8PathBuf::from(recipe);
 
     if path
         .components()
@@ -472,9 +520,33 @@ fn try_path(
         return Err(Error::OutsideBase(recipe.to_string()));
     }
 
-    DirEntry::new(&path)
-        .map_err(Error::from)
-        .and_then(|e| RecipeEntry::try_from(e).map_err(Error::from))
+    let candidates = if rules.is_recipe_extension(&path) {
+        vec![path]
+    } else {
+        rules
+            .extensions
+            .iter()
+            .flat_map(|ext| {
+                let plain = path.with_extension(ext);
+                let encrypted = ENCRYPTED_EXTENSIONS
+                    .iter()
+                    .map(|enc| Utf8PathBuf::from(format!("{plain}.{enc}")))
+                    .collect::<Vec<_>>();
+                std::iter::once(plain).chain(encrypted)
+            })
+            .collect()
+    };
+    let mut last_err = Error::NotFound(recipe.to_string());
+    for candidate in candidates {
+        match DirEntry::with_rules(&candidate, rules)
+            .map_err(Error::from)
+            .and_then(|e| RecipeEntry::try_from(e).map_err(Error::from))
+        {
+            Ok(entry) => return Ok(entry),
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
 }
 
 fn norm_path(path: &Utf8Path) -> Utf8PathBuf {
@@ -543,16 +615,29 @@ impl RecipeEntry {
     }
 
     pub fn name(&self) -> &str {
-        self.path.file_stem().unwrap()
+        plain_path(&self.path).file_stem().unwrap()
     }
 
     pub fn relative_name(&self) -> &str {
-        self.path.as_str().trim_end_matches(".cook")
+        let plain = plain_path(&self.path);
+        let path = plain.as_str();
+        match plain.extension() {
+            Some(ext) => &path[..path.len() - ext.len() - 1],
+            None => path,
+        }
+    }
+
+    /// Checks if the recipe file is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        is_encrypted(&self.path)
     }
 
     /// Reads the content of the entry
+    ///
+    /// Encrypted recipes are decrypted with the decryptor set with
+    /// [`set_decryptor`].
     pub fn read(&self) -> std::io::Result<RecipeContent> {
-        let content = std::fs::read_to_string(&self.path)?;
+        let content = encryption::read(&self.path)?;
         Ok(RecipeContent::new(content))
     }
 
@@ -685,7 +770,7 @@ pub fn recipe_images(path: &Utf8Path) -> Vec<Image> {
         return vec![];
     };
 
-    let Some(recipe_name) = path.file_stem() else {
+    let Some(recipe_name) = plain_path(path).file_stem() else {
         return vec![];
     };
 
@@ -0,0 +1,123 @@
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+
+/// Rules to find the recipe files of a collection
+///
+/// Globs are matched against the path relative to the base path, with `/`
+/// separators. A glob without a `/` matches the file or dir name at any
+/// depth. `*` matches anything but `/`, `**` anything and `?` a single char.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscoveryRules {
+    /// Only recipe files matching one of these are found, all if empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Files and dirs matching one of these are skipped
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Follow symbolic links to files and dirs
+    pub follow_symlinks: bool,
+    /// Extensions of the recipe files, without the dot
+    pub extensions: Vec<String>,
+}
+
+impl Default for DiscoveryRules {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            follow_symlinks: false,
+            extensions: vec![RECIPE_EXTENSION.to_string()],
+        }
+    }
+}
+
+/// Default recipe file extension
+pub const RECIPE_EXTENSION: &str = "cook";
+
+impl DiscoveryRules {
+    /// Checks if a file has a recipe extension
+    ///
+    /// Encrypted recipes, like `Stew.cook.age`, have it too.
+    pub fn is_recipe_extension(&self, path: &Utf8Path) -> bool {
+        crate::plain_path(path)
+            .extension()
+            .is_some_and(|ext| self.extensions.iter().any(|e| e == ext))
+    }
+
+    /// Checks if a dir should be walked into
+    ///
+    /// `relative` is the path relative to the base path.
+    pub fn allows_dir(&self, relative: &Utf8Path) -> bool {
+        !self.exclude.iter().any(|g| glob_matches(g, relative))
+    }
+
+    /// Checks if a recipe file is found
+    ///
+    /// `relative` is the path relative to the base path.
+    pub fn allows_file(&self, relative: &Utf8Path) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|g| glob_matches(g, relative)))
+            && !self.exclude.iter().any(|g| glob_matches(g, relative))
+    }
+}
+
+fn glob_matches(glob: &str, relative: &Utf8Path) -> bool {
+    let path = relative.as_str().replace('\\', "/");
+    if glob.contains('/') {
+        glob_match(glob.trim_start_matches('/').as_bytes(), path.as_bytes())
+    } else {
+        let name = path.rsplit('/').next().unwrap_or(&path);
+        glob_match(glob.as_bytes(), name.as_bytes())
+    }
+}
+
+fn glob_match(glob: &[u8], text: &[u8]) -> bool {
+    match glob {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            // zero or more whole dirs
+            glob_match(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(i, &c)| c == b'/' && glob_match(rest, &text[i + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        [b'*', rest @ ..] => {
+            let segment = text.iter().position(|&c| c == b'/').unwrap_or(text.len());
+            (0..=segment).any(|i| glob_match(rest, &text[i..]))
+        }
+        [b'?', rest @ ..] => {
+            matches!(text.first(), Some(&c) if c != b'/') && glob_match(rest, &text[1..])
+        }
+        [c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        let m = |g, p| glob_matches(g, Utf8Path::new(p));
+        assert!(m("*.cook", "a/b/Bread.cook"));
+        assert!(m("drafts", "a/drafts"));
+        assert!(!m("drafts", "a/drafts/x.cook"));
+        assert!(m("drafts/**", "drafts/x/y.cook"));
+        assert!(m("**/old/*.cook", "old/x.cook"));
+        assert!(m("**/old/*.cook", "a/b/old/x.cook"));
+        assert!(!m("Mains/*.cook", "Mains/x/y.cook"));
+        assert!(m("Mains/?.cook", "Mains/a.cook"));
+
+        let rules = DiscoveryRules {
+            include: vec!["Mains/**".into()],
+            exclude: vec!["*.draft.cook".into()],
+            ..Default::default()
+        };
+        assert!(rules.allows_file(Utf8Path::new("Mains/Stew.cook")));
+        assert!(!rules.allows_file(Utf8Path::new("Mains/Stew.draft.cook")));
+        assert!(!rules.allows_file(Utf8Path::new("Sides/Rice.cook")));
+        assert!(rules.allows_dir(Utf8Path::new("Sides")));
+    }
+}
@@ -2,14 +2,15 @@ use std::{collections::VecDeque, fs::FileType};
 
 use camino::{Utf8Path, Utf8PathBuf};
 
-use crate::IMAGE_EXTENSIONS;
+use crate::{DiscoveryRules, IMAGE_EXTENSIONS};
 
-/// Breadth-first, sorted by file name, .cook filtered, dir walker.
+/// Breadth-first, sorted by file name, recipe filtered, dir walker.
 ///
 /// Paths are relative to the base path, with the base path included. So when
 /// walking over `dir`, entries will be `dir/whatever.cook`.
 ///
-/// Files/dirs starting with '.' are ignored.
+/// Files/dirs starting with '.' are ignored, and the [`DiscoveryRules`] are
+/// applied.
 ///
 /// Currently, it returns dirs, cooklang files and images.
 #[derive(Debug)]
@@ -20,6 +21,9 @@ pub struct Walker {
     current: std::vec::IntoIter<DirEntry>,
     config_dir: Option<String>,
     ignore: Vec<String>,
+    rules: DiscoveryRules,
+    /// Base of the paths the rules globs are relative to
+    rules_base: Utf8PathBuf,
 }
 
 impl Walker {
@@ -34,9 +38,20 @@ impl Walker {
             current: Vec::new().into_iter(),
             config_dir: None,
             ignore: Vec::new(),
+            rules: DiscoveryRules::default(),
+            rules_base: dir.to_path_buf(),
         }
     }
 
+    /// Sets the rules to find recipes
+    ///
+    /// The globs are relative to `base_path`, that may be an ancestor of the
+    /// walked dir.
+    pub fn set_rules(&mut self, rules: DiscoveryRules, base_path: &Utf8Path) {
+        self.rules = rules;
+        self.rules_base = base_path.to_path_buf();
+    }
+
     /// Sets a config dir to the walker
     ///
     /// If this dir is found not in the top level, a warning will be printed.
@@ -62,7 +77,16 @@ impl Walker {
         let mut new_entries = Vec::new();
         for e in dir.read_dir_utf8()? {
             let e = e?;
-            let ft = e.file_type()?;
+            let mut ft = e.file_type()?;
+            if ft.is_symlink() && self.rules.follow_symlinks {
+                match e.path().metadata() {
+                    Ok(metadata) => ft = metadata.file_type(),
+                    Err(err) => {
+                        tracing::warn!("Skipping broken link '{}': {err}", e.path());
+                        continue;
+                    }
+                }
+            }
 
             // print warning for unexpected config dir
             if let Some(config_dir) = &self.config_dir {
@@ -79,12 +103,23 @@ impl Walker {
                 continue;
             }
 
+            let relative = e
+                .path()
+                .strip_prefix(&self.rules_base)
+                .unwrap_or(e.path())
+                .to_path_buf();
             let entry = DirEntry {
+                recipe: ft.is_file()
+                    && self.rules.is_recipe_extension(e.path())
+                    && self.rules.allows_file(&relative),
                 path: e.into_path(),
                 file_type: ft,
             };
 
             if entry.file_type.is_dir() {
+                if !self.rules.allows_dir(&relative) {
+                    continue;
+                }
                 let depth = entry_depth(entry.path(), &self.base_path);
                 if depth <= self.max_depth {
                     new_dirs.push(entry.path().to_path_buf());
@@ -133,14 +168,25 @@ impl Iterator for Walker {
 pub struct DirEntry {
     path: Utf8PathBuf,
     file_type: FileType,
+    recipe: bool,
 }
 
 impl DirEntry {
+    /// Creates an entry, a recipe if it's a `.cook` file
     pub fn new(path: &Utf8Path) -> Result<Self, std::io::Error> {
+        Self::with_rules(path, &DiscoveryRules::default())
+    }
+
+    /// Same as [`Self::new`] with the extensions of some rules
+    ///
+    /// The globs are not checked, the path is assumed to be explicitly
+    /// wanted.
+    pub fn with_rules(path: &Utf8Path, rules: &DiscoveryRules) -> Result<Self, std::io::Error> {
         let metadata = path.metadata()?;
         Ok(Self {
             path: path.to_path_buf(),
             file_type: metadata.file_type(),
+            recipe: metadata.is_file() && rules.is_recipe_extension(path),
         })
     }
 
@@ -148,7 +194,9 @@ impl DirEntry {
         self.path.file_name().unwrap_or(self.path.as_str())
     }
     pub fn file_stem(&self) -> &str {
-        self.path.file_stem().unwrap_or(self.path.as_str())
+        crate::plain_path(&self.path)
+            .file_stem()
+            .unwrap_or(self.path.as_str())
     }
     pub fn path(&self) -> &Utf8Path {
         &self.path
@@ -161,7 +209,7 @@ impl DirEntry {
     }
 
     pub fn is_cooklang_file(&self) -> bool {
-        self.file_type.is_file() && self.path.extension().is_some_and(|e| e == "cook")
+        self.recipe
     }
 
     pub fn is_image(&self) -> bool {
@@ -0,0 +1,179 @@
+//! Python bindings
+//!
+//! Recipes cross the boundary as JSON and are loaded with Python's `json`
+//! module, so they are plain dicts and lists, the same as
+//! `chef recipe --format json`.
+
+use std::sync::LazyLock;
+
+use cooklang::{
+    convert::System, ingredient_list::IngredientList, CooklangParser, ScalableRecipe,
+    ScaledQuantity, ScaledRecipe,
+};
+use cooklang_render::{RecipeRenderer, RenderInput};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::Serialize;
+
+static PARSER: LazyLock<CooklangParser> = LazyLock::new(CooklangParser::default);
+
+/// Parses a recipe, raises `ValueError` if it has errors
+#[pyfunction]
+fn parse(py: Python<'_>, text: &str) -> PyResult<PyObject> {
+    to_py(py, &parse_recipe(text)?)
+}
+
+/// Parses and scales a recipe to a number of servings
+#[pyfunction]
+fn scale(py: Python<'_>, text: &str, servings: u32) -> PyResult<PyObject> {
+    to_py(py, &scaled(text, Some(servings), None)?)
+}
+
+/// Parses a recipe and converts it to `"metric"` or `"imperial"` units
+#[pyfunction]
+#[pyo3(signature = (text, system, servings=None))]
+fn convert(py: Python<'_>, text: &str, system: &str, servings: Option<u32>) -> PyResult<PyObject> {
+    to_py(py, &scaled(text, servings, Some(system))?)
+}
+
+/// Renders a recipe as text
+///
+/// `format` is one of `human`, `markdown`, `html`, `latex`, `cooklang` or
+/// `json`.
+#[pyfunction]
+#[pyo3(signature = (text, name, format="human", servings=None))]
+fn render(text: &str, name: &str, format: &str, servings: Option<u32>) -> PyResult<String> {
+    let recipe = scaled(text, servings, None)?;
+    let md_options = Default::default();
+    let renderer: Box<dyn RecipeRenderer + '_> = match format {
+        "human" => Box::new(cooklang_render::Human::default()),
+        "markdown" | "md" => Box::new(cooklang_render::Markdown {
+            options: &md_options,
+        }),
+        "html" => Box::new(cooklang_render::Html),
+        "latex" | "tex" => Box::new(cooklang_render::Latex),
+        "cooklang" | "cook" => Box::new(cooklang_render::Cooklang),
+        "json" => Box::new(cooklang_render::Json { pretty: true }),
+        other => return Err(PyValueError::new_err(format!("Unknown format: '{other}'"))),
+    };
+    let mut buf = Vec::new();
+    renderer
+        .render(
+            &mut buf,
+            &RenderInput::new(&recipe, name, PARSER.converter()),
+        )
+        .map_err(value_error)?;
+    String::from_utf8(buf).map_err(value_error)
+}
+
+/// Merges the ingredients of several recipes
+///
+/// `servings`, if given, has the servings for each recipe, `None` to keep a
+/// recipe as written.
+#[pyfunction]
+#[pyo3(signature = (recipes, servings=None))]
+fn shopping_list(
+    py: Python<'_>,
+    recipes: Vec<String>,
+    servings: Option<Vec<Option<u32>>>,
+) -> PyResult<PyObject> {
+    #[derive(Serialize)]
+    struct Item {
+        name: String,
+        quantity: Vec<ScaledQuantity>,
+    }
+
+    if let Some(s) = &servings {
+        if s.len() != recipes.len() {
+            return Err(PyValueError::new_err(
+                "servings must have the same length as recipes",
+            ));
+        }
+    }
+    let mut list = IngredientList::new();
+    for (i, text) in recipes.iter().enumerate() {
+        let servings = servings.as_ref().and_then(|s| s[i]);
+        let recipe = scaled(text, servings, None)?;
+        list.add_recipe(&recipe, PARSER.converter());
+    }
+    let items = list
+        .into_iter()
+        .map(|(name, quantity)| Item {
+            name,
+            quantity: quantity.into_vec(),
+        })
+        .collect::<Vec<_>>();
+    to_py(py, &items)
+}
+
+/// Lists the recipes in a collection
+///
+/// Returns dicts with the `name` and `path` of every recipe.
+#[pyfunction]
+#[pyo3(signature = (path, max_depth=10))]
+fn collection(py: Python<'_>, path: &str, max_depth: usize) -> PyResult<PyObject> {
+    #[derive(Serialize)]
+    struct Entry {
+        name: String,
+        path: String,
+    }
+
+    let entries = cooklang_fs::all_recipes(path, max_depth)
+        .map_err(value_error)?
+        .map(|e| Entry {
+            name: e.name().to_string(),
+            path: e.path().to_string(),
+        })
+        .collect::<Vec<_>>();
+    to_py(py, &entries)
+}
+
+fn parse_recipe(text: &str) -> PyResult<ScalableRecipe> {
+    PARSER
+        .parse(text)
+        .into_result()
+        .map(|(recipe, _warnings)| recipe)
+        .map_err(|report| {
+            let messages = report
+                .iter()
+                .map(|d| d.message.to_string())
+                .collect::<Vec<_>>();
+            PyValueError::new_err(messages.join("\n"))
+        })
+}
+
+fn scaled(text: &str, servings: Option<u32>, system: Option<&str>) -> PyResult<ScaledRecipe> {
+    let recipe = parse_recipe(text)?;
+    let mut recipe = match servings {
+        Some(servings) => recipe.scale(servings, PARSER.converter()),
+        None => recipe.default_scale(),
+    };
+    if let Some(system) = system {
+        let system: System = system
+            .parse()
+            .map_err(|_| PyValueError::new_err(format!("Unknown unit system: '{system}'")))?;
+        // quantities that can't be converted are left as they are
+        let _ = recipe.convert(system, PARSER.converter());
+    }
+    Ok(recipe)
+}
+
+fn to_py(py: Python<'_>, value: &impl Serialize) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(value_error)?;
+    let obj = py.import_bound("json")?.call_method1("loads", (json,))?;
+    Ok(obj.unbind())
+}
+
+fn value_error(e: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+#[pymodule]
+fn cooklang_chef(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(scale, m)?)?;
+    m.add_function(wrap_pyfunction!(convert, m)?)?;
+    m.add_function(wrap_pyfunction!(render, m)?)?;
+    m.add_function(wrap_pyfunction!(shopping_list, m)?)?;
+    m.add_function(wrap_pyfunction!(collection, m)?)?;
+    Ok(())
+}
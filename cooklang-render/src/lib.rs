@@ -0,0 +1,728 @@
+//! Output formats for a single recipe
+//!
+//! Every format implements [`RecipeRenderer`]. A renderer either writes the
+//! parts of the recipe one by one (header, metadata, ingredients and steps) or
+//! overrides [`RecipeRenderer::render`] when the format is written as a whole,
+//! like JSON.
+//!
+//! Implement the trait to add custom output formats.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{Arc, LazyLock},
+};
+
+use cooklang::{
+    metadata::RecipeTime, model::Item, quantity::Quantity, Content, Converter, ScaledRecipe, Step,
+};
+pub use cooklang_to_human::{
+    DefaultQuantityFormatter, Hyperlinks, IngredientColumn, IngredientInfo, MalformedRecipe,
+    NumberFormat, QuantityFormatter, TagColor,
+};
+use cooklang_to_human::{HumanFormatter, ScaleMark};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Error serializing JSON")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Markdown(#[from] cooklang_to_md::Error),
+    #[error(transparent)]
+    Malformed(#[from] MalformedRecipe),
+}
+
+pub type Result<T = (), E = Error> = std::result::Result<T, E>;
+
+/// Everything a renderer needs to write a recipe
+///
+/// Built with [`RenderInput::new`] and the methods to set the rest, so new
+/// fields don't break the users.
+#[non_exhaustive]
+pub struct RenderInput<'a> {
+    pub recipe: &'a ScaledRecipe,
+    pub name: &'a str,
+    pub converter: &'a Converter,
+    pub marks: &'a HashMap<usize, ScaleMark>,
+    /// Separators for the numbers in quantities
+    pub number_format: NumberFormat,
+    /// Custom display for the quantities, instead of the numbers with
+    /// `number_format`
+    pub quantity_formatter: Option<Arc<dyn QuantityFormatter>>,
+    /// Problems found in the recipe, like parser warnings, for the formats
+    /// with a warnings section
+    pub warnings: &'a [String],
+}
+
+impl<'a> RenderInput<'a> {
+    /// An input with no marks, warnings or custom number format
+    pub fn new(recipe: &'a ScaledRecipe, name: &'a str, converter: &'a Converter) -> Self {
+        static NO_MARKS: LazyLock<HashMap<usize, ScaleMark>> = LazyLock::new(HashMap::new);
+        Self {
+            recipe,
+            name,
+            converter,
+            marks: &NO_MARKS,
+            number_format: NumberFormat::default(),
+            quantity_formatter: None,
+            warnings: &[],
+        }
+    }
+
+    /// Scaling marks of the ingredients, by index
+    pub fn marks(mut self, marks: &'a HashMap<usize, ScaleMark>) -> Self {
+        self.marks = marks;
+        self
+    }
+
+    /// Separators for the numbers in quantities
+    pub fn number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Custom display for the quantities
+    pub fn quantity_formatter(mut self, formatter: Arc<dyn QuantityFormatter>) -> Self {
+        self.quantity_formatter = Some(formatter);
+        self
+    }
+
+    /// Problems found in the recipe
+    pub fn warnings(mut self, warnings: &'a [String]) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// A quantity as every renderer writes it
+    pub fn format_quantity(&self, quantity: &Quantity) -> String {
+        match &self.quantity_formatter {
+            Some(f) => f.format(quantity),
+            None => DefaultQuantityFormatter::new(self.number_format).format(quantity),
+        }
+    }
+}
+
+pub trait RecipeRenderer {
+    /// Name and tags
+    fn header(&self, _w: &mut dyn Write, _input: &RenderInput) -> Result {
+        Ok(())
+    }
+
+    /// Description, servings, time...
+    fn metadata(&self, _w: &mut dyn Write, _input: &RenderInput) -> Result {
+        Ok(())
+    }
+
+    /// Ingredient and cookware lists
+    fn ingredients(&self, _w: &mut dyn Write, _input: &RenderInput) -> Result {
+        Ok(())
+    }
+
+    /// Sections with their steps and text
+    fn steps(&self, _w: &mut dyn Write, _input: &RenderInput) -> Result {
+        Ok(())
+    }
+
+    /// Problems found in the recipe, if any
+    fn warnings(&self, _w: &mut dyn Write, _input: &RenderInput) -> Result {
+        Ok(())
+    }
+
+    /// Writes the whole recipe
+    fn render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        self.header(w, input)?;
+        self.metadata(w, input)?;
+        self.ingredients(w, input)?;
+        self.steps(w, input)?;
+        self.warnings(w, input)?;
+        Ok(())
+    }
+
+    /// Same as [`RecipeRenderer::render`] but checks the recipe first
+    ///
+    /// The renderers can panic with a recipe built or changed by hand that
+    /// has items pointing nowhere, this returns [`Error::Malformed`] instead.
+    /// Use it with recipes that don't come straight from the parser.
+    fn try_render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        cooklang_to_human::validate(input.recipe)?;
+        self.render(w, input)
+    }
+}
+
+#[derive(Default)]
+pub struct Human {
+    /// Add a bakers' percentage column to the ingredients
+    pub bakers_percentages: bool,
+    /// Plain text with a fixed width, for snapshot tests, see
+    /// [`HumanFormatterBuilder::deterministic`](cooklang_to_human::HumanFormatterBuilder::deterministic)
+    pub deterministic: bool,
+    /// Colours for some tags, see
+    /// [`HumanFormatterBuilder::tag_colors`](cooklang_to_human::HumanFormatterBuilder::tag_colors)
+    pub tag_colors: HashMap<String, TagColor>,
+    /// Columns of the ingredients table, the default ones when empty
+    pub ingredient_columns: Vec<IngredientColumn>,
+    /// Data for the category, cost and calories columns
+    pub ingredient_info: Option<Arc<dyn IngredientInfo>>,
+    /// Never lay the ingredients in two columns, see
+    /// [`HumanFormatterBuilder::two_columns`](cooklang_to_human::HumanFormatterBuilder::two_columns)
+    pub one_column: bool,
+    /// Clickable links, for terminals that support them
+    pub hyperlinks: Option<Hyperlinks>,
+}
+
+impl Human {
+    fn formatter(&self, input: &RenderInput) -> cooklang_to_human::HumanFormatterBuilder {
+        let mut builder = HumanFormatter::builder()
+            .bakers_percentages(self.bakers_percentages)
+            .deterministic(self.deterministic)
+            .tag_colors(self.tag_colors.clone())
+            .ingredient_columns(self.ingredient_columns.iter().copied())
+            .two_columns(!self.one_column)
+            .number_format(input.number_format)
+            .quantity_formatter(quantities(input));
+        if let Some(info) = &self.ingredient_info {
+            builder = builder.ingredient_info(Arc::clone(info));
+        }
+        if let Some(links) = &self.hyperlinks {
+            builder = builder.hyperlinks(links.clone());
+        }
+        builder
+    }
+
+    fn part(&self, w: &mut dyn Write, input: &RenderInput, part: HumanPart) -> Result {
+        let formatter = self
+            .formatter(input)
+            .show_header(matches!(part, HumanPart::Header))
+            .show_metadata(matches!(part, HumanPart::Metadata))
+            .show_ingredients(matches!(part, HumanPart::Ingredients))
+            .show_cookware(matches!(part, HumanPart::Ingredients))
+            .show_steps(matches!(part, HumanPart::Steps))
+            .build();
+        formatter.format_with_marks(
+            input.recipe,
+            input.name,
+            input.converter,
+            input.marks,
+            &mut *w,
+        )?;
+        Ok(())
+    }
+}
+
+fn quantities(input: &RenderInput) -> Arc<dyn QuantityFormatter> {
+    match &input.quantity_formatter {
+        Some(f) => Arc::clone(f),
+        None => Arc::new(DefaultQuantityFormatter::new(input.number_format)),
+    }
+}
+
+enum HumanPart {
+    Header,
+    Metadata,
+    Ingredients,
+    Steps,
+}
+
+impl RecipeRenderer for Human {
+    fn header(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        self.part(w, input, HumanPart::Header)
+    }
+
+    fn metadata(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        self.part(w, input, HumanPart::Metadata)
+    }
+
+    fn ingredients(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        self.part(w, input, HumanPart::Ingredients)
+    }
+
+    fn steps(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        self.part(w, input, HumanPart::Steps)
+    }
+
+    fn warnings(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        use yansi::Paint;
+
+        if input.warnings.is_empty() {
+            return Ok(());
+        }
+        let condition = if self.deterministic {
+            yansi::Condition::NEVER
+        } else {
+            yansi::Condition::DEFAULT
+        };
+        let title = "Warnings".bold().underline().whenever(condition);
+        writeln!(w, "\n{title}:")?;
+        for warning in input.warnings {
+            writeln!(w, "  {} {warning}", "!".yellow().bold().whenever(condition))?;
+        }
+        Ok(())
+    }
+
+    fn render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        self.formatter(input).build().format_with_marks(
+            input.recipe,
+            input.name,
+            input.converter,
+            input.marks,
+            &mut *w,
+        )?;
+        self.warnings(w, input)?;
+        Ok(())
+    }
+}
+
+pub struct Json {
+    pub pretty: bool,
+}
+
+impl RecipeRenderer for Json {
+    fn render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        #[derive(serde::Serialize)]
+        struct JsonRecipe<'a> {
+            name: &'a str,
+            #[serde(flatten)]
+            recipe: &'a cooklang::ScaledRecipe,
+        }
+
+        let recipe = JsonRecipe {
+            recipe: input.recipe,
+            name: input.name,
+        };
+
+        if self.pretty {
+            serde_json::to_writer_pretty(w, &recipe)?;
+        } else {
+            serde_json::to_writer(w, &recipe)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Cooklang;
+
+impl RecipeRenderer for Cooklang {
+    fn render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        cooklang_to_cooklang::print_cooklang(input.recipe, w)?;
+        Ok(())
+    }
+}
+
+pub struct Markdown<'a> {
+    pub options: &'a cooklang_to_md::Options,
+}
+
+impl RecipeRenderer for Markdown<'_> {
+    fn render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        // the separators in the options unless the input has some
+        let numbers = if input.number_format != NumberFormat::default() {
+            input.number_format
+        } else {
+            NumberFormat {
+                decimal: self.options.decimal_separator,
+                thousands: self.options.thousands_separator,
+            }
+        };
+        let formatter = match &input.quantity_formatter {
+            Some(f) => Arc::clone(f),
+            None => Arc::new(DefaultQuantityFormatter::new(numbers)),
+        };
+        cooklang_to_md::print_md_with_formatter(
+            input.recipe,
+            input.name,
+            self.options,
+            input.converter,
+            &|q| formatter.format(q),
+            w,
+        )?;
+        Ok(())
+    }
+}
+
+/// Rust debug representation of the recipe
+pub struct DebugDump;
+
+impl RecipeRenderer for DebugDump {
+    fn render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        write!(w, "{:?}", input.recipe)?;
+        Ok(())
+    }
+}
+
+/// Standalone HTML document, without styles
+pub struct Html;
+
+impl RecipeRenderer for Html {
+    fn header(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        let name = html_escape(input.name);
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html>")?;
+        writeln!(
+            w,
+            "<head><meta charset=\"utf-8\"><title>{name}</title></head>"
+        )?;
+        writeln!(w, "<body>")?;
+        writeln!(w, "<h1>{name}</h1>")?;
+        if let Some(tags) = input.recipe.metadata.tags() {
+            let tags = tags
+                .iter()
+                .map(|t| format!("<li>#{}</li>", html_escape(t)))
+                .collect::<String>();
+            writeln!(w, "<ul class=\"tags\">{tags}</ul>")?;
+        }
+        Ok(())
+    }
+
+    fn metadata(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        let meta = &input.recipe.metadata;
+        if let Some(desc) = meta.description() {
+            writeln!(w, "<p>{}</p>", html_escape(desc))?;
+        }
+        let facts = facts(input);
+        if !facts.is_empty() {
+            writeln!(w, "<dl>")?;
+            for (key, value) in facts {
+                writeln!(w, "<dt>{key}</dt><dd>{}</dd>", html_escape(&value))?;
+            }
+            writeln!(w, "</dl>")?;
+        }
+        Ok(())
+    }
+
+    fn ingredients(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        let ingredients = ingredient_list(input);
+        if !ingredients.is_empty() {
+            writeln!(w, "<h2>Ingredients</h2>")?;
+            writeln!(w, "<ul>")?;
+            for (name, quantity) in ingredients {
+                match quantity {
+                    Some(q) => writeln!(w, "<li>{}: {}</li>", html_escape(&name), html_escape(&q))?,
+                    None => writeln!(w, "<li>{}</li>", html_escape(&name))?,
+                }
+            }
+            writeln!(w, "</ul>")?;
+        }
+        let cookware = cookware_list(input);
+        if !cookware.is_empty() {
+            writeln!(w, "<h2>Cookware</h2>")?;
+            writeln!(w, "<ul>")?;
+            for name in cookware {
+                writeln!(w, "<li>{}</li>", html_escape(&name))?;
+            }
+            writeln!(w, "</ul>")?;
+        }
+        Ok(())
+    }
+
+    fn steps(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        writeln!(w, "<h2>Steps</h2>")?;
+        for section in &input.recipe.sections {
+            if let Some(name) = &section.name {
+                writeln!(w, "<h3>{}</h3>", html_escape(name))?;
+            }
+            let mut in_list = false;
+            for content in &section.content {
+                match content {
+                    Content::Step(step) => {
+                        if !in_list {
+                            writeln!(w, "<ol>")?;
+                            in_list = true;
+                        }
+                        let text = step_text(input, step);
+                        writeln!(
+                            w,
+                            "<li value=\"{}\">{}</li>",
+                            step.number,
+                            html_escape(&text)
+                        )?;
+                    }
+                    Content::Text(text) => {
+                        if in_list {
+                            writeln!(w, "</ol>")?;
+                            in_list = false;
+                        }
+                        writeln!(w, "<p>{}</p>", html_escape(text.trim()))?;
+                    }
+                }
+            }
+            if in_list {
+                writeln!(w, "</ol>")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn warnings(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        if input.warnings.is_empty() {
+            return Ok(());
+        }
+        writeln!(w, "<section class=\"warnings\">")?;
+        writeln!(w, "<h2>Warnings</h2>")?;
+        writeln!(w, "<ul>")?;
+        for warning in input.warnings {
+            writeln!(w, "<li>{}</li>", html_escape(warning))?;
+        }
+        writeln!(w, "</ul>")?;
+        writeln!(w, "</section>")?;
+        Ok(())
+    }
+
+    fn render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        self.header(w, input)?;
+        self.metadata(w, input)?;
+        self.ingredients(w, input)?;
+        self.steps(w, input)?;
+        self.warnings(w, input)?;
+        writeln!(w, "</body>")?;
+        writeln!(w, "</html>")?;
+        Ok(())
+    }
+}
+
+/// LaTeX document using only the base `article` class
+pub struct Latex;
+
+impl RecipeRenderer for Latex {
+    fn header(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        writeln!(w, "\\documentclass{{article}}")?;
+        writeln!(w, "\\usepackage[utf8]{{inputenc}}")?;
+        writeln!(w, "\\begin{{document}}")?;
+        writeln!(w, "\\section*{{{}}}", latex_escape(input.name))?;
+        if let Some(tags) = input.recipe.metadata.tags() {
+            let tags = tags
+                .iter()
+                .map(|t| format!("\\#{}", latex_escape(t)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(w, "\\textit{{{tags}}}\n")?;
+        }
+        Ok(())
+    }
+
+    fn metadata(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        if let Some(desc) = input.recipe.metadata.description() {
+            writeln!(w, "{}\n", latex_escape(desc))?;
+        }
+        let facts = facts(input);
+        if !facts.is_empty() {
+            writeln!(w, "\\begin{{description}}")?;
+            for (key, value) in facts {
+                writeln!(w, "  \\item[{key}] {}", latex_escape(&value))?;
+            }
+            writeln!(w, "\\end{{description}}")?;
+        }
+        Ok(())
+    }
+
+    fn ingredients(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        let ingredients = ingredient_list(input);
+        if !ingredients.is_empty() {
+            writeln!(w, "\\subsection*{{Ingredients}}")?;
+            writeln!(w, "\\begin{{itemize}}")?;
+            for (name, quantity) in ingredients {
+                match quantity {
+                    Some(q) => {
+                        writeln!(w, "  \\item {}: {}", latex_escape(&name), latex_escape(&q))?
+                    }
+                    None => writeln!(w, "  \\item {}", latex_escape(&name))?,
+                }
+            }
+            writeln!(w, "\\end{{itemize}}")?;
+        }
+        let cookware = cookware_list(input);
+        if !cookware.is_empty() {
+            writeln!(w, "\\subsection*{{Cookware}}")?;
+            writeln!(w, "\\begin{{itemize}}")?;
+            for name in cookware {
+                writeln!(w, "  \\item {}", latex_escape(&name))?;
+            }
+            writeln!(w, "\\end{{itemize}}")?;
+        }
+        Ok(())
+    }
+
+    fn steps(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        writeln!(w, "\\subsection*{{Steps}}")?;
+        for section in &input.recipe.sections {
+            if let Some(name) = &section.name {
+                writeln!(w, "\\subsubsection*{{{}}}", latex_escape(name))?;
+            }
+            let mut in_list = false;
+            for content in &section.content {
+                match content {
+                    Content::Step(step) => {
+                        if !in_list {
+                            writeln!(w, "\\begin{{enumerate}}")?;
+                            writeln!(w, "  \\setcounter{{enumi}}{{{}}}", step.number - 1)?;
+                            in_list = true;
+                        }
+                        let text = step_text(input, step);
+                        writeln!(w, "  \\item {}", latex_escape(&text))?;
+                    }
+                    Content::Text(text) => {
+                        if in_list {
+                            writeln!(w, "\\end{{enumerate}}")?;
+                            in_list = false;
+                        }
+                        writeln!(w, "{}\n", latex_escape(text.trim()))?;
+                    }
+                }
+            }
+            if in_list {
+                writeln!(w, "\\end{{enumerate}}")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&self, w: &mut dyn Write, input: &RenderInput) -> Result {
+        self.header(w, input)?;
+        self.metadata(w, input)?;
+        self.ingredients(w, input)?;
+        self.steps(w, input)?;
+        writeln!(w, "\\end{{document}}")?;
+        Ok(())
+    }
+}
+
+/// Servings and time as key value pairs
+fn facts(input: &RenderInput) -> Vec<(&'static str, String)> {
+    let meta = &input.recipe.metadata;
+    let mut facts = Vec::new();
+    if let Some(servings) = meta.servings() {
+        let servings = match input.recipe.scaled_data() {
+            Some(data) => data.target.target_servings().to_string(),
+            None => servings
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join("|"),
+        };
+        facts.push(("Servings", servings));
+    }
+    match meta.time(input.converter) {
+        Some(RecipeTime::Total(t)) => facts.push(("Time", format!("{t} min"))),
+        Some(RecipeTime::Composed {
+            prep_time,
+            cook_time,
+        }) => {
+            if let Some(p) = prep_time {
+                facts.push(("Prep time", format!("{p} min")));
+            }
+            if let Some(c) = cook_time {
+                facts.push(("Cook time", format!("{c} min")));
+            }
+        }
+        None => {}
+    }
+    facts
+}
+
+/// Listed ingredients with their grouped quantities
+fn ingredient_list(input: &RenderInput) -> Vec<(String, Option<String>)> {
+    input
+        .recipe
+        .group_ingredients(input.converter)
+        .into_iter()
+        .filter(|entry| entry.ingredient.modifiers().should_be_listed())
+        .map(|entry| {
+            let quantities = entry
+                .quantity
+                .iter()
+                .map(|q| input.format_quantity(q))
+                .collect::<Vec<_>>();
+            let quantity = (!quantities.is_empty()).then(|| quantities.join(", "));
+            (entry.ingredient.display_name().to_string(), quantity)
+        })
+        .collect()
+}
+
+fn cookware_list(input: &RenderInput) -> Vec<String> {
+    input
+        .recipe
+        .cookware
+        .iter()
+        .filter(|cw| cw.modifiers().should_be_listed())
+        .map(|cw| cw.display_name().to_string())
+        .collect()
+}
+
+/// Text of a step without any markup
+fn step_text(input: &RenderInput, step: &Step) -> String {
+    let recipe = input.recipe;
+    let mut text = String::new();
+    for item in &step.items {
+        match item {
+            Item::Text { value } => text += value,
+            &Item::Ingredient { index } => text += &recipe.ingredients[index].display_name(),
+            &Item::Cookware { index } => text += &recipe.cookware[index].name,
+            &Item::Timer { index } => {
+                let timer = &recipe.timers[index];
+                match (&timer.quantity, &timer.name) {
+                    (Some(q), Some(name)) => {
+                        text += &format!("{} ({name})", input.format_quantity(q))
+                    }
+                    (Some(q), None) => text += &input.format_quantity(q),
+                    (None, Some(name)) => text += name,
+                    (None, None) => {}
+                }
+            }
+            &Item::InlineQuantity { index } => {
+                text += &input.format_quantity(&recipe.inline_quantities[index])
+            }
+        }
+    }
+    text.trim().to_string()
+}
+
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn latex_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            html_escape("a <b> & \"c\""),
+            "a &lt;b&gt; &amp; &quot;c&quot;"
+        );
+        assert_eq!(latex_escape("50% & 1_000 #1"), "50\\% \\& 1\\_000 \\#1");
+        assert_eq!(
+            latex_escape("a\\b~"),
+            "a\\textbackslash{}b\\textasciitilde{}"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Checks of the parser and the collection against the cooklang spec
+//!
+//! The canonical tests are a subset of the ones of the spec, bundled in
+//! `data/canonical.yaml`. They define how recipes without extensions are
+//! read, so a test failing with the configured extensions means recipes
+//! relying on that may read differently in other cooklang tools.
+
+use std::collections::BTreeMap;
+
+use cooklang::{
+    metadata::CooklangValueExt,
+    model::Item as ModelItem,
+    quantity::{Quantity, Value},
+    Content, Converter, CooklangParser, Extensions, ScaledRecipe,
+};
+use serde::{Deserialize, Serialize};
+
+const CANONICAL: &str = include_str!("data/canonical.yaml");
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Bad bundled canonical tests")]
+    Bundled(#[source] serde_yaml::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Deserialize)]
+struct CanonicalFile {
+    tests: BTreeMap<String, Case>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Case {
+    source: String,
+    result: Expected,
+}
+
+/// A recipe as the canonical tests write it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Expected {
+    pub steps: Vec<Vec<CanonicalItem>>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CanonicalItem {
+    Text {
+        value: String,
+    },
+    Ingredient {
+        name: String,
+        quantity: CanonicalQuantity,
+        units: String,
+    },
+    Cookware {
+        name: String,
+        quantity: CanonicalQuantity,
+    },
+    Timer {
+        name: String,
+        quantity: CanonicalQuantity,
+        units: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CanonicalQuantity {
+    Number(f64),
+    Text(String),
+}
+
+impl PartialEq for CanonicalQuantity {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => (a - b).abs() < 1e-9,
+            (Self::Text(a), Self::Text(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Result of a canonical test
+#[derive(Debug, Serialize)]
+pub struct CaseResult {
+    pub name: String,
+    pub passed: bool,
+    /// What the parser read, `None` if the recipe had errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub got: Option<Expected>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Expected>,
+}
+
+/// Runs the bundled canonical tests with a parser
+pub fn canonical_tests(parser: &CooklangParser) -> Result<Vec<CaseResult>> {
+    let file: CanonicalFile = serde_yaml::from_str(CANONICAL).map_err(Error::Bundled)?;
+    let results = file
+        .tests
+        .into_iter()
+        .map(|(name, case)| {
+            let got = parser
+                .parse(&case.source)
+                .into_output()
+                .map(|r| canonical(&r.default_scale()));
+            let passed = got.as_ref() == Some(&case.result);
+            CaseResult {
+                name,
+                passed,
+                expected: (!passed).then_some(case.result),
+                got: got.filter(|_| !passed),
+            }
+        })
+        .collect();
+    Ok(results)
+}
+
+/// A parser without extensions and units, like the spec
+pub fn canonical_parser() -> CooklangParser {
+    CooklangParser::new(Extensions::empty(), Converter::empty())
+}
+
+/// The recipe in the format of the canonical tests
+pub fn canonical(recipe: &ScaledRecipe) -> Expected {
+    let mut steps = Vec::new();
+    for section in &recipe.sections {
+        for step in section.content.iter().filter_map(|c| match c {
+            Content::Step(step) => Some(step),
+            Content::Text(_) => None,
+        }) {
+            let mut items = Vec::<CanonicalItem>::new();
+            for item in &step.items {
+                let item = match item {
+                    ModelItem::Text { value } => {
+                        if let Some(CanonicalItem::Text { value: last }) = items.last_mut() {
+                            last.push_str(value);
+                            continue;
+                        }
+                        CanonicalItem::Text {
+                            value: value.clone(),
+                        }
+                    }
+                    &ModelItem::Ingredient { index } => {
+                        let igr = &recipe.ingredients[index];
+                        CanonicalItem::Ingredient {
+                            name: igr.name.clone(),
+                            quantity: quantity(igr.quantity.as_ref(), "some"),
+                            units: units(igr.quantity.as_ref()),
+                        }
+                    }
+                    &ModelItem::Cookware { index } => {
+                        let cw = &recipe.cookware[index];
+                        CanonicalItem::Cookware {
+                            name: cw.name.clone(),
+                            quantity: match &cw.quantity {
+                                Some(q) => text_quantity(q.to_string()),
+                                None => CanonicalQuantity::Number(1.0),
+                            },
+                        }
+                    }
+                    &ModelItem::Timer { index } => {
+                        let timer = &recipe.timers[index];
+                        CanonicalItem::Timer {
+                            name: timer.name.clone().unwrap_or_default(),
+                            quantity: quantity(timer.quantity.as_ref(), ""),
+                            units: units(timer.quantity.as_ref()),
+                        }
+                    }
+                    &ModelItem::InlineQuantity { index } => CanonicalItem::Text {
+                        value: recipe.inline_quantities[index].to_string(),
+                    },
+                };
+                items.push(item);
+            }
+            steps.push(items);
+        }
+    }
+    let metadata = recipe
+        .metadata
+        .map
+        .iter()
+        .filter_map(|(key, value)| {
+            Some((
+                key.as_str_like()?.to_string(),
+                value.as_str_like()?.to_string(),
+            ))
+        })
+        .collect();
+    Expected { steps, metadata }
+}
+
+fn quantity(q: Option<&Quantity>, default: &str) -> CanonicalQuantity {
+    match q.map(|q| q.value()) {
+        Some(Value::Number(n)) => CanonicalQuantity::Number(n.value()),
+        Some(value) => text_quantity(value.to_string()),
+        None => CanonicalQuantity::Text(default.to_string()),
+    }
+}
+
+fn units(q: Option<&Quantity>) -> String {
+    q.and_then(|q| q.unit()).unwrap_or_default().to_string()
+}
+
+fn text_quantity(text: String) -> CanonicalQuantity {
+    match text.parse() {
+        Ok(n) => CanonicalQuantity::Number(n),
+        Err(_) => CanonicalQuantity::Text(text),
+    }
+}
+
+/// Recipes that read differently without an extension
+#[derive(Debug, Serialize)]
+pub struct ExtensionUsage {
+    pub extension: String,
+    pub enabled: bool,
+    /// Names of the recipes, only for enabled extensions
+    pub recipes: Vec<String>,
+}
+
+/// For each extension, the recipes that need it
+///
+/// A recipe needs an extension when parsing it without it gives a different
+/// recipe or errors. `recipes` are the names and texts.
+pub fn extension_usage(
+    parser: &CooklangParser,
+    recipes: &[(String, String)],
+) -> Vec<ExtensionUsage> {
+    let enabled = parser.extensions();
+    let baseline = recipes
+        .iter()
+        .map(|(_, text)| reading(parser, text))
+        .collect::<Vec<_>>();
+    Extensions::all()
+        .iter_names()
+        .map(|(name, flag)| {
+            let mut usage = ExtensionUsage {
+                extension: name.to_lowercase(),
+                enabled: enabled.contains(flag),
+                recipes: Vec::new(),
+            };
+            if usage.enabled {
+                let without = CooklangParser::new(enabled - flag, parser.converter().clone());
+                usage.recipes = recipes
+                    .iter()
+                    .zip(&baseline)
+                    .filter(|((_, text), base)| base.is_some() && reading(&without, text) != **base)
+                    .map(|((name, _), _)| name.clone())
+                    .collect();
+            }
+            usage
+        })
+        .collect()
+}
+
+/// The recipe as data, to compare how different parsers read it. `None` if it
+/// has errors.
+pub fn reading(parser: &CooklangParser, text: &str) -> Option<serde_json::Value> {
+    parser
+        .parse(text)
+        .into_output()
+        .and_then(|r| serde_json::to_value(r.default_scale()).ok())
+}
+
+/// Extensions the parser has disabled that change how the recipe reads
+///
+/// The names are in lowercase, like in the config.
+pub fn disabled_extensions(parser: &CooklangParser, text: &str) -> Vec<String> {
+    let enabled = parser.extensions();
+    if enabled.is_all() {
+        return Vec::new();
+    }
+    let current = reading(parser, text);
+    (Extensions::all() - enabled)
+        .iter_names()
+        .filter(|&(_, flag)| {
+            let with = CooklangParser::new(enabled | flag, parser.converter().clone());
+            reading(&with, text) != current
+        })
+        .map(|(name, _)| name.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_canonical_tests() {
+        let results = canonical_tests(&canonical_parser()).unwrap();
+        assert!(results.len() > 10);
+        let failed = results
+            .iter()
+            .filter(|r| !r.passed)
+            .map(|r| &r.name)
+            .collect::<Vec<_>>();
+        assert!(failed.is_empty(), "failed: {failed:?}");
+    }
+
+    #[test]
+    fn disabled_extension_used() {
+        let parser = CooklangParser::new(
+            Extensions::all() - Extensions::RANGE_VALUES,
+            Converter::empty(),
+        );
+        assert_eq!(
+            disabled_extensions(&parser, "Add @flour{1-2%cups}.\n"),
+            ["range_values"]
+        );
+        assert!(disabled_extensions(&parser, "Add @flour{1%cups}.\n").is_empty());
+    }
+}
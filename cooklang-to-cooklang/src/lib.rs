@@ -213,14 +213,14 @@ impl<'a, V: QuantityValue> ComponentFormatter<'a, V> {
                 Modifiers::NEW => '+',
                 _ => panic!("Unknown modifier: {:?}", m),
             });
-            if m == Modifiers::REF && self.intermediate_data.is_some() {
+            if let Some(IntermediateData {
+                ref_mode,
+                target_kind,
+                val,
+            }) = self.intermediate_data.filter(|_| m == Modifiers::REF)
+            {
                 use cooklang::parser::IntermediateRefMode::*;
                 use cooklang::parser::IntermediateTargetKind::*;
-                let IntermediateData {
-                    ref_mode,
-                    target_kind,
-                    val,
-                } = self.intermediate_data.unwrap();
                 let repr = match (target_kind, ref_mode) {
                     (Step, Number) => format!("{val}"),
                     (Step, Relative) => format!("~{val}"),
@@ -0,0 +1,60 @@
+use cooklang::CooklangParser;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+const RECIPE: &str = r#"---
+title: Pasta with tomato sauce
+servings: 2|4|8
+tags: [pasta, italian, quick]
+time: 30 min
+---
+
+= Sauce
+
+Heat @olive oil{2%tbsp} in a #pan{}. Add @garlic{2%cloves}(minced) and cook
+for ~{1%minute} until fragrant.
+
+Add @crushed tomatoes{400%g}, @salt{} and @pepper{} and simmer for ~{15%minutes}.
+Add @basil{1/2%bunch}(torn) at the end.
+
+= Pasta
+
+Boil @water{3%l} with @salt{1%tbsp} in a #big pot{}. Cook @pasta{250%g} for
+~{10%minutes}.
+
+Drain and mix with the @&(=1)sauce{}. Serve with @parmesan{30%g}(grated).
+"#;
+
+fn render(c: &mut Criterion) {
+    let parser = CooklangParser::default();
+    let recipe = parser
+        .parse(RECIPE)
+        .into_output()
+        .expect("bench recipe has errors")
+        .default_scale();
+
+    let mut group = c.benchmark_group("print_human");
+    group.bench_function("one", |b| {
+        b.iter(|| {
+            cooklang_to_human::print_human(&recipe, "Pasta", parser.converter(), std::io::sink())
+                .unwrap()
+        })
+    });
+    // like exporting a whole collection
+    group.throughput(Throughput::Elements(100));
+    group.bench_function("hundred", |b| {
+        b.iter_batched_ref(
+            || Vec::with_capacity(1 << 20),
+            |buf| {
+                for _ in 0..100 {
+                    cooklang_to_human::print_human(&recipe, "Pasta", parser.converter(), &mut *buf)
+                        .unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,96 @@
+use cooklang::ingredient_list::GroupedIngredient;
+
+/// A column of the ingredients table
+///
+/// The default table is [`IngredientColumn::DEFAULT`]. [`Category`](Self::Category),
+/// [`Cost`](Self::Cost) and [`Calories`](Self::Calories) come from an
+/// [`IngredientInfo`] and are left out when no ingredient has them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IngredientColumn {
+    Name,
+    Quantity,
+    /// The "(optional)" marker
+    Optional,
+    Note,
+    Category,
+    Cost,
+    Calories,
+}
+
+impl IngredientColumn {
+    pub const ALL: [Self; 7] = [
+        Self::Name,
+        Self::Quantity,
+        Self::Optional,
+        Self::Note,
+        Self::Category,
+        Self::Cost,
+        Self::Calories,
+    ];
+
+    pub const DEFAULT: &'static [Self] = &[Self::Name, Self::Optional, Self::Quantity, Self::Note];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Quantity => "quantity",
+            Self::Optional => "optional",
+            Self::Note => "note",
+            Self::Category => "category",
+            Self::Cost => "cost",
+            Self::Calories => "calories",
+        }
+    }
+
+    /// Numbers are aligned to the right
+    pub(crate) fn right_aligned(self) -> bool {
+        matches!(self, Self::Cost | Self::Calories)
+    }
+}
+
+impl std::fmt::Display for IngredientColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for IngredientColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|c| c.name() == s)
+            .ok_or_else(|| {
+                let names = Self::ALL.map(Self::name).join(", ");
+                format!("Unknown ingredient column '{s}', expected one of {names}")
+            })
+    }
+}
+
+/// Data of an ingredient that is not in the recipe
+///
+/// Fills the [`Category`](IngredientColumn::Category),
+/// [`Cost`](IngredientColumn::Cost) and [`Calories`](IngredientColumn::Calories)
+/// columns. The text is written as is, so it should have the currency or the
+/// unit. `None` leaves the cell empty.
+pub trait IngredientInfo: std::fmt::Debug + Send + Sync {
+    fn category(&self, _entry: &GroupedIngredient) -> Option<String> {
+        None
+    }
+
+    fn cost(&self, _entry: &GroupedIngredient) -> Option<String> {
+        None
+    }
+
+    fn calories(&self, _entry: &GroupedIngredient) -> Option<String> {
+        None
+    }
+}
+
+/// Knows nothing, only the recipe columns are shown
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoIngredientInfo;
+
+impl IngredientInfo for NoIngredientInfo {}
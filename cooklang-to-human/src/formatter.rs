@@ -0,0 +1,742 @@
+use std::{collections::HashMap, io, sync::Arc};
+
+use cooklang::{convert::Converter, ScaledRecipe};
+
+use crate::{
+    style::{styles, OwoStyles},
+    CookStyles, DefaultQuantityFormatter, Hyperlinks, IngredientColumn, IngredientInfo,
+    NoIngredientInfo, QuantityFormatter, Result, ScaleMark, TagColor,
+};
+
+/// Configurable human formatter
+///
+/// All the options live in the formatter, so different formatters can be used
+/// at the same time with different styles, widths or labels.
+///
+/// ```no_run
+/// # use cooklang_to_human::HumanFormatter;
+/// # let recipe: cooklang::ScaledRecipe = todo!();
+/// # let converter = cooklang::Converter::empty();
+/// let formatter = HumanFormatter::builder()
+///     .width(60)
+///     .color(false)
+///     .show_cookware(false)
+///     .build();
+/// formatter.format(&recipe, "Pancakes", &converter, std::io::stdout())?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct HumanFormatter {
+    pub(crate) width: usize,
+    /// Width for the layout of the ingredients, not capped like `width`
+    pub(crate) layout_width: usize,
+    pub(crate) two_columns: bool,
+    pub(crate) color: bool,
+    pub(crate) sections: Sections,
+    pub(crate) quantities: Arc<dyn QuantityFormatter>,
+    pub(crate) number_format: NumberFormat,
+    pub(crate) labels: Labels,
+    pub(crate) styles: OwoStyles,
+    pub(crate) bakers_percentages: bool,
+    pub(crate) tag_colors: HashMap<String, TagColor>,
+    pub(crate) ingredient_columns: Vec<IngredientColumn>,
+    pub(crate) ingredient_info: Arc<dyn IngredientInfo>,
+    pub(crate) hyperlinks: Option<Hyperlinks>,
+}
+
+/// Parts of the recipe shown by a [`HumanFormatter`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Sections {
+    pub header: bool,
+    pub metadata: bool,
+    pub ingredients: bool,
+    pub cookware: bool,
+    pub steps: bool,
+}
+
+/// How the numbers in quantities are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QuantityFormat {
+    /// As the parser gives them, fractions included
+    #[default]
+    Default,
+    /// Always as decimals, with up to 3 digits after the point
+    Decimal,
+}
+
+/// Separators to write numbers with, like in a locale
+///
+/// The default is a decimal point and no thousands separator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimal: char,
+    /// Only used for numbers of 5 digits or more, 1500 g is easier to read
+    /// without it
+    pub thousands: Option<char>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal: '.',
+            thousands: None,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Separators of a locale like `de`, `es-ES` or `en_US`, by its language
+    ///
+    /// Unknown languages get the default.
+    pub fn from_locale(locale: &str) -> Self {
+        let lang = locale
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let (decimal, thousands) = match lang.as_str() {
+            "en" | "ja" | "zh" | "ko" | "he" | "hi" | "th" => ('.', ','),
+            "de" | "es" | "it" | "nl" | "pt" | "ca" | "da" | "el" | "id" | "tr" => (',', '.'),
+            "fr" | "cs" | "fi" | "hu" | "nb" | "no" | "pl" | "ru" | "sk" | "sv" | "uk" => {
+                (',', '\u{a0}')
+            }
+            _ => return Self::default(),
+        };
+        Self {
+            decimal,
+            thousands: Some(thousands),
+        }
+    }
+
+    /// Rewrites the decimal numbers in a text, like a formatted quantity
+    ///
+    /// Fractions and ranges are kept, `1.5-2` is `1,5-2` with a decimal comma.
+    pub fn localize(&self, text: &str) -> String {
+        if *self == Self::default() {
+            return text.to_string();
+        }
+        let digits_len = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let int_len = digits_len(rest);
+            self.push_integer(&mut out, &rest[..int_len]);
+            rest = &rest[int_len..];
+            if let Some(frac) = rest.strip_prefix('.') {
+                let frac_len = digits_len(frac);
+                if frac_len > 0 {
+                    out.push(self.decimal);
+                    out.push_str(&frac[..frac_len]);
+                    rest = &frac[frac_len..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn push_integer(&self, out: &mut String, digits: &str) {
+        let Some(sep) = self.thousands.filter(|_| digits.len() >= 5) else {
+            out.push_str(digits);
+            return;
+        };
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(sep);
+            }
+            out.push(c);
+        }
+    }
+}
+
+/// Text written by the formatter that is not from the recipe
+///
+/// Change them to translate the output.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Labels {
+    pub ingredients: String,
+    pub cookware: String,
+    pub steps: String,
+    pub optional: String,
+    pub optional_short: String,
+    pub author: String,
+    pub source: String,
+    pub time: String,
+    pub prep_time: String,
+    pub cook_time: String,
+    pub total_time: String,
+    pub servings: String,
+    pub from_section: String,
+    pub from_step: String,
+    pub fixed_value: String,
+    pub scaled_sqrt: String,
+    pub rounded: String,
+    pub scaling_target: String,
+    pub adjusted: String,
+    pub scaling_error: String,
+    pub bakers_percentage: String,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self {
+            ingredients: "Ingredients".into(),
+            cookware: "Cookware".into(),
+            steps: "Steps".into(),
+            optional: "optional".into(),
+            optional_short: "opt".into(),
+            author: "author".into(),
+            source: "source".into(),
+            time: "time".into(),
+            prep_time: "prep time".into(),
+            cook_time: "cook time".into(),
+            total_time: "total time".into(),
+            servings: "servings".into(),
+            from_section: "from section".into(),
+            from_step: "from step".into(),
+            fixed_value: "fixed value".into(),
+            scaled_sqrt: "scaled by square root".into(),
+            rounded: "rounded".into(),
+            scaling_target: "scaling target".into(),
+            adjusted: "adjusted".into(),
+            scaling_error: "error scaling".into(),
+            bakers_percentage: "% of the total flour".into(),
+        }
+    }
+}
+
+impl Default for HumanFormatter {
+    fn default() -> Self {
+        HumanFormatterBuilder::new().build()
+    }
+}
+
+impl HumanFormatter {
+    pub fn builder() -> HumanFormatterBuilder {
+        HumanFormatterBuilder::new()
+    }
+
+    /// Writes the recipe
+    pub fn format(
+        &self,
+        recipe: &ScaledRecipe,
+        name: &str,
+        converter: &Converter,
+        writer: impl io::Write,
+    ) -> Result {
+        self.format_with_marks(recipe, name, converter, &HashMap::new(), writer)
+    }
+
+    /// Same as [`HumanFormatter::format`] but with [`ScaleMark`]s for the
+    /// ingredients
+    ///
+    /// The marks are indexed by the ingredient index in the recipe.
+    /// The links to write, never without colours
+    pub(crate) fn links(&self) -> Option<&Hyperlinks> {
+        self.hyperlinks.as_ref().filter(|_| self.color)
+    }
+
+    pub fn format_with_marks(
+        &self,
+        recipe: &ScaledRecipe,
+        name: &str,
+        converter: &Converter,
+        marks: &HashMap<usize, ScaleMark>,
+        mut writer: impl io::Write,
+    ) -> Result {
+        if self.color {
+            crate::write_recipe(self, recipe, name, converter, marks, &mut writer)
+        } else {
+            let mut w = StripAnsi::new(writer);
+            crate::write_recipe(self, recipe, name, converter, marks, &mut w)
+        }
+    }
+
+    /// Same as [`HumanFormatter::format`] but checks the recipe first
+    ///
+    /// The parser output is always fine, but a recipe built or changed by
+    /// hand can make the other ways to write it panic. This returns a
+    /// [`MalformedRecipe`](crate::MalformedRecipe) error instead.
+    pub fn try_format(
+        &self,
+        recipe: &ScaledRecipe,
+        name: &str,
+        converter: &Converter,
+        writer: impl io::Write,
+    ) -> std::result::Result<(), crate::Error> {
+        self.try_format_with_marks(recipe, name, converter, &HashMap::new(), writer)
+    }
+
+    /// Same as [`HumanFormatter::format_with_marks`] but checks the recipe
+    /// first, see [`HumanFormatter::try_format`]
+    pub fn try_format_with_marks(
+        &self,
+        recipe: &ScaledRecipe,
+        name: &str,
+        converter: &Converter,
+        marks: &HashMap<usize, ScaleMark>,
+        writer: impl io::Write,
+    ) -> std::result::Result<(), crate::Error> {
+        crate::validate(recipe)?;
+        self.format_with_marks(recipe, name, converter, marks, writer)?;
+        Ok(())
+    }
+}
+
+/// Builder for [`HumanFormatter`]
+#[derive(Debug, Clone)]
+pub struct HumanFormatterBuilder {
+    width: Option<usize>,
+    two_columns: bool,
+    color: bool,
+    sections: Sections,
+    quantity_format: QuantityFormat,
+    number_format: NumberFormat,
+    quantity_formatter: Option<Arc<dyn QuantityFormatter>>,
+    labels: Labels,
+    styles: Option<CookStyles>,
+    bakers_percentages: bool,
+    tag_colors: HashMap<String, TagColor>,
+    ingredient_columns: Vec<IngredientColumn>,
+    ingredient_info: Option<Arc<dyn IngredientInfo>>,
+    hyperlinks: Option<Hyperlinks>,
+}
+
+impl HumanFormatterBuilder {
+    fn new() -> Self {
+        Self {
+            width: None,
+            two_columns: true,
+            color: true,
+            sections: Sections {
+                header: true,
+                metadata: true,
+                ingredients: true,
+                cookware: true,
+                steps: true,
+            },
+            quantity_format: QuantityFormat::default(),
+            number_format: NumberFormat::default(),
+            quantity_formatter: None,
+            labels: Labels::default(),
+            styles: None,
+            bakers_percentages: false,
+            tag_colors: HashMap::new(),
+            ingredient_columns: IngredientColumn::DEFAULT.to_vec(),
+            ingredient_info: None,
+            hyperlinks: None,
+        }
+    }
+
+    /// Max width of the text, by default the terminal width up to 80
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Write ansi colours, true by default
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Custom styles, by default the ones from [`set_styles`](crate::set_styles)
+    pub fn styles(mut self, styles: CookStyles) -> Self {
+        self.styles = Some(styles);
+        self
+    }
+
+    /// Show the name and tags
+    pub fn show_header(mut self, show: bool) -> Self {
+        self.sections.header = show;
+        self
+    }
+
+    /// Show the description, time, servings and other metadata
+    pub fn show_metadata(mut self, show: bool) -> Self {
+        self.sections.metadata = show;
+        self
+    }
+
+    /// Show the ingredient list
+    pub fn show_ingredients(mut self, show: bool) -> Self {
+        self.sections.ingredients = show;
+        self
+    }
+
+    /// Show the cookware list
+    pub fn show_cookware(mut self, show: bool) -> Self {
+        self.sections.cookware = show;
+        self
+    }
+
+    /// Show the steps
+    pub fn show_steps(mut self, show: bool) -> Self {
+        self.sections.steps = show;
+        self
+    }
+
+    pub fn quantity_format(mut self, format: QuantityFormat) -> Self {
+        self.quantity_format = format;
+        self
+    }
+
+    /// Separators for the numbers, see [`NumberFormat::from_locale`]
+    pub fn number_format(mut self, format: NumberFormat) -> Self {
+        self.number_format = format;
+        self
+    }
+
+    /// Custom display for the quantities
+    ///
+    /// It takes the place of [`quantity_format`](Self::quantity_format) and
+    /// [`number_format`](Self::number_format) for them.
+    pub fn quantity_formatter(mut self, formatter: Arc<dyn QuantityFormatter>) -> Self {
+        self.quantity_formatter = Some(formatter);
+        self
+    }
+
+    /// Text for headings, legends and metadata keys
+    pub fn labels(mut self, labels: Labels) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Output that only depends on the recipe and the options, for snapshot
+    /// tests
+    ///
+    /// The width is 80 unless set, instead of the terminal width, there are
+    /// no colours and the global styles from [`set_styles`](crate::set_styles)
+    /// are ignored.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        if deterministic {
+            self.width.get_or_insert(80);
+            self.color = false;
+            self.styles
+                .get_or_insert_with(crate::CookStyles::default_styles);
+        }
+        self
+    }
+
+    /// Add a column with each ingredient as a percentage of the total flour
+    ///
+    /// The quantities are converted to grams, the column is not shown if the
+    /// recipe has no flour in grams.
+    pub fn bakers_percentages(mut self, show: bool) -> Self {
+        self.bakers_percentages = show;
+        self
+    }
+
+    /// Lay a long ingredients table in two columns when there is room
+    ///
+    /// On by default. With a wide terminal, or a width set to at least 100,
+    /// tables of 8 or more ingredients are split in two halves side by side.
+    pub fn two_columns(mut self, two_columns: bool) -> Self {
+        self.two_columns = two_columns;
+        self
+    }
+
+    /// Colours for some tags, the others get [`TagColor::from_tag`]
+    ///
+    /// The tags are matched ignoring case.
+    pub fn tag_colors(mut self, colors: HashMap<String, TagColor>) -> Self {
+        self.tag_colors = colors
+            .into_iter()
+            .map(|(tag, color)| (tag.to_lowercase(), color))
+            .collect();
+        self
+    }
+
+    /// Columns of the ingredients table, in order
+    ///
+    /// By default [`IngredientColumn::DEFAULT`], also used with no columns. A
+    /// column given more than once is only shown the first time.
+    pub fn ingredient_columns(
+        mut self,
+        columns: impl IntoIterator<Item = IngredientColumn>,
+    ) -> Self {
+        self.ingredient_columns.clear();
+        for column in columns {
+            if !self.ingredient_columns.contains(&column) {
+                self.ingredient_columns.push(column);
+            }
+        }
+        if self.ingredient_columns.is_empty() {
+            self.ingredient_columns = IngredientColumn::DEFAULT.to_vec();
+        }
+        self
+    }
+
+    /// Data for the category, cost and calories columns
+    pub fn ingredient_info(mut self, info: Arc<dyn IngredientInfo>) -> Self {
+        self.ingredient_info = Some(info);
+        self
+    }
+
+    /// Make the URLs, images and referenced recipes clickable in terminals
+    /// with OSC 8 support
+    ///
+    /// Check that the terminal supports them, others may show garbage.
+    pub fn hyperlinks(mut self, links: Hyperlinks) -> Self {
+        self.hyperlinks = Some(links);
+        self
+    }
+
+    pub fn build(self) -> HumanFormatter {
+        HumanFormatter {
+            width: self.width.unwrap_or_else(|| textwrap::termwidth().min(80)),
+            layout_width: self.width.unwrap_or_else(textwrap::termwidth),
+            two_columns: self.two_columns,
+            color: self.color,
+            sections: self.sections,
+            quantities: self.quantity_formatter.unwrap_or_else(|| {
+                Arc::new(DefaultQuantityFormatter {
+                    format: self.quantity_format,
+                    numbers: self.number_format,
+                })
+            }),
+            number_format: self.number_format,
+            labels: self.labels,
+            styles: match self.styles {
+                Some(s) => s.into(),
+                None => styles().clone(),
+            },
+            bakers_percentages: self.bakers_percentages,
+            tag_colors: self.tag_colors,
+            ingredient_columns: self.ingredient_columns,
+            ingredient_info: self
+                .ingredient_info
+                .unwrap_or_else(|| Arc::new(NoIngredientInfo)),
+            hyperlinks: self.hyperlinks,
+        }
+    }
+}
+
+/// Removes ansi escape sequences from the output
+struct StripAnsi<W> {
+    inner: W,
+    state: EscapeState,
+}
+
+#[derive(Clone, Copy)]
+enum EscapeState {
+    Text,
+    Escape,
+    Csi,
+}
+
+impl<W: io::Write> StripAnsi<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            state: EscapeState::Text,
+        }
+    }
+}
+
+impl<W: io::Write> io::Write for StripAnsi<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut start = 0;
+        for (i, &b) in buf.iter().enumerate() {
+            self.state = match (self.state, b) {
+                (EscapeState::Text, 0x1b) => {
+                    self.inner.write_all(&buf[start..i])?;
+                    EscapeState::Escape
+                }
+                (EscapeState::Text, _) => continue,
+                (EscapeState::Escape, b'[') => EscapeState::Csi,
+                (EscapeState::Escape, _) => EscapeState::Text,
+                // final byte of a control sequence
+                (EscapeState::Csi, 0x40..=0x7e) => EscapeState::Text,
+                (EscapeState::Csi, _) => EscapeState::Csi,
+            };
+            start = i + 1;
+        }
+        if matches!(self.state, EscapeState::Text) {
+            self.inner.write_all(&buf[start..])?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn strip_ansi() {
+        let mut w = StripAnsi::new(Vec::new());
+        w.write_all(b"a\x1b[1;3").unwrap();
+        w.write_all(b"2mbold\x1b[0m c").unwrap();
+        assert_eq!(w.inner, b"abold c");
+    }
+
+    #[test]
+    fn deterministic_output() {
+        let recipe = cooklang::CooklangParser::default()
+            .parse("---\ntags: [quick]\n---\nMix @flour{200%g} and @water{150%ml} in a #bowl{} for a very long while so that the line has to be wrapped.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        let render = || {
+            let mut buf = Vec::new();
+            HumanFormatter::builder()
+                .deterministic(true)
+                .build()
+                .format(&recipe, "Dough", &Converter::default(), &mut buf)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let out = render();
+        assert_eq!(out, render());
+        assert!(!out.contains('\x1b'));
+        assert!(out.lines().all(|l| l.chars().count() <= 80));
+    }
+
+    #[test]
+    fn deterministic_snapshot() {
+        let recipe = cooklang::CooklangParser::default()
+            .parse("---\ntags: [quick]\n---\nMix @flour{200%g} and @water{150%ml} in a #bowl{}. Rest ~{20%min}.\n\nBake in the #oven{}.\n")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        let mut buf = Vec::new();
+        HumanFormatter::builder()
+            .deterministic(true)
+            .build()
+            .format(&recipe, "Dough", &Converter::default(), &mut buf)
+            .unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/snapshots/deterministic.txt"
+        );
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(path, &out).unwrap();
+        }
+        let expected = std::fs::read_to_string(path).unwrap();
+        assert_eq!(
+            out, expected,
+            "run with UPDATE_SNAPSHOTS=1 to update {path}"
+        );
+    }
+
+    #[test]
+    fn ingredient_columns() {
+        use crate::{IngredientColumn as C, IngredientInfo};
+        use cooklang::ingredient_list::GroupedIngredient;
+
+        #[derive(Debug)]
+        struct Prices;
+        impl IngredientInfo for Prices {
+            fn cost(&self, entry: &GroupedIngredient) -> Option<String> {
+                (entry.ingredient.name == "flour").then(|| "0.30 EUR".to_string())
+            }
+        }
+
+        let recipe = cooklang::CooklangParser::default()
+            .parse("Mix @flour{200%g} and @water{150%ml}.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        let render = |info: Option<Prices>| {
+            let mut builder = HumanFormatter::builder()
+                .deterministic(true)
+                .show_header(false)
+                .show_steps(false)
+                .ingredient_columns([C::Quantity, C::Name, C::Cost]);
+            if let Some(info) = info {
+                builder = builder.ingredient_info(Arc::new(info));
+            }
+            let mut buf = Vec::new();
+            builder
+                .build()
+                .format(&recipe, "Dough", &Converter::default(), &mut buf)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let out = render(Some(Prices));
+        let flour = out.lines().find(|l| l.contains("flour")).unwrap();
+        assert!(flour.trim_start().starts_with("200 g"));
+        assert!(flour.trim_end().ends_with("0.30 EUR"));
+        // no ingredient has a cost
+        let out = render(None);
+        let flour = out.lines().find(|l| l.contains("flour")).unwrap();
+        assert!(flour.trim_end().ends_with("flour"));
+    }
+
+    #[test]
+    fn two_columns() {
+        let text = (0..10)
+            .map(|i| format!("Add @ingredient{i}{{{}%g}}.", i + 1))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let recipe = cooklang::CooklangParser::default()
+            .parse(&text)
+            .into_output()
+            .unwrap()
+            .default_scale();
+        let ingredient_lines = |width| {
+            let mut buf = Vec::new();
+            HumanFormatter::builder()
+                .deterministic(true)
+                .width(width)
+                .show_steps(false)
+                .build()
+                .format(&recipe, "Many", &Converter::default(), &mut buf)
+                .unwrap();
+            String::from_utf8(buf)
+                .unwrap()
+                .lines()
+                .filter(|l| l.contains("ingredient"))
+                .count()
+        };
+        assert_eq!(ingredient_lines(80), 10);
+        assert_eq!(ingredient_lines(120), 5);
+    }
+
+    #[test]
+    fn hyperlinks() {
+        let recipe = cooklang::CooklangParser::default()
+            .parse("---\nsource: https://example.com/bread\n---\nBake @flour{500%g}.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        let render = |color| {
+            let mut buf = Vec::new();
+            HumanFormatter::builder()
+                .width(80)
+                .color(color)
+                .hyperlinks(Hyperlinks::default())
+                .build()
+                .format(&recipe, "Bread", &Converter::default(), &mut buf)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        assert!(render(true).contains("\x1b]8;;https://example.com/bread\x1b\\"));
+        assert!(!render(false).contains("\x1b]8"));
+    }
+
+    #[test]
+    fn localize_numbers() {
+        let de = NumberFormat::from_locale("de-DE");
+        assert_eq!(de.localize("1.5-2 kg"), "1,5-2 kg");
+        assert_eq!(de.localize("1/2 cup"), "1/2 cup");
+        assert_eq!(de.localize("1500 g"), "1500 g");
+        assert_eq!(de.localize("12500.25 g"), "12.500,25 g");
+        assert_eq!(de.localize("end."), "end.");
+        let en = NumberFormat::from_locale("en_US");
+        assert_eq!(en.localize("1234567.5"), "1,234,567.5");
+        assert_eq!(NumberFormat::from_locale("xx").localize("1.5"), "1.5");
+    }
+}
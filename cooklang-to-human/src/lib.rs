@@ -1,7 +1,7 @@
 //! Format a recipe for humans to read
 //!
-//! This will always write ansi colours. Use something like
-//! [`anstream`](https://docs.rs/anstream) to remove them if needed.
+//! [`print_human`] always writes ansi colours. Use a [`HumanFormatter`] to
+//! configure the colours, width, shown parts or labels.
 
 use std::{collections::HashMap, io, time::Duration};
 
@@ -10,7 +10,7 @@ use cooklang::{
     ingredient_list::GroupedIngredient,
     metadata::CooklangValueExt,
     model::{Ingredient, IngredientReferenceTarget, Item},
-    quantity::Quantity,
+    quantity::{Quantity, Value},
     scale::ScaleOutcome,
     ScaledRecipe, Section, Step,
 };
@@ -18,30 +18,109 @@ use std::fmt::Write;
 use tabular::{Row, Table};
 use yansi::Paint;
 
+mod columns;
+mod formatter;
+mod link;
+mod quantity;
 mod style;
-use style::styles;
+mod tag;
+mod validate;
+pub use columns::{IngredientColumn, IngredientInfo, NoIngredientInfo};
+pub use formatter::{HumanFormatter, HumanFormatterBuilder, Labels, NumberFormat, QuantityFormat};
+pub use link::Hyperlinks;
+pub use quantity::{approx_value, DefaultQuantityFormatter, QuantityFormatter};
 pub use style::{set_styles, CookStyles};
+pub use tag::TagColor;
+pub use validate::{validate, MalformedRecipe};
 
 pub type Result<T = ()> = std::result::Result<T, io::Error>;
 
+/// Error of the checked ways to write a recipe, like
+/// [`HumanFormatter::try_format`]
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Malformed recipe: {0}")]
+    Malformed(#[from] MalformedRecipe),
+}
+
+/// Writes the recipe with the default [`HumanFormatter`]
 pub fn print_human(
     recipe: &ScaledRecipe,
     name: &str,
     converter: &Converter,
-    mut writer: impl std::io::Write,
+    writer: impl std::io::Write,
 ) -> Result {
-    let w = &mut writer;
+    print_human_with_marks(recipe, name, converter, &HashMap::new(), writer)
+}
+
+/// Extra information about how an ingredient was scaled
+///
+/// The parser only reports a [`ScaleOutcome`]. When the quantities are
+/// modified after scaling, these marks replace the outcome in the ingredient
+/// list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMark {
+    /// The value does not scale
+    Fixed,
+    /// Scaled with the square root of the factor
+    Sqrt,
+    /// Scaled and rounded to a whole number
+    Stepwise,
+    /// The ingredient the recipe was scaled to
+    Anchor,
+    /// Changed by some adjustment other than scaling
+    Adjusted,
+    /// An approximate value scaled linearly, the value already shows it
+    Approximate,
+}
 
-    header(w, recipe, name)?;
-    metadata(w, recipe, converter)?;
-    ingredients(w, recipe, converter)?;
-    cookware(w, recipe)?;
-    steps(w, recipe)?;
+/// Same as [`print_human`] but with [`ScaleMark`]s for the ingredients
+///
+/// The marks are indexed by the ingredient index in the recipe.
+pub fn print_human_with_marks(
+    recipe: &ScaledRecipe,
+    name: &str,
+    converter: &Converter,
+    marks: &HashMap<usize, ScaleMark>,
+    writer: impl std::io::Write,
+) -> Result {
+    HumanFormatter::default().format_with_marks(recipe, name, converter, marks, writer)
+}
 
+fn write_recipe(
+    hf: &HumanFormatter,
+    recipe: &ScaledRecipe,
+    name: &str,
+    converter: &Converter,
+    marks: &HashMap<usize, ScaleMark>,
+    w: &mut impl io::Write,
+) -> Result {
+    if hf.sections.header {
+        header(w, hf, recipe, name)?;
+    }
+    if hf.sections.metadata {
+        metadata(w, hf, recipe, converter)?;
+    }
+    if hf.sections.ingredients {
+        ingredients(w, hf, recipe, converter, marks)?;
+    }
+    if hf.sections.cookware {
+        cookware(w, hf, recipe)?;
+    }
+    if hf.sections.steps {
+        steps(w, hf, recipe)?;
+    }
     Ok(())
 }
 
-fn header(w: &mut impl io::Write, recipe: &ScaledRecipe, name: &str) -> Result {
+fn header(
+    w: &mut impl io::Write,
+    hf: &HumanFormatter,
+    recipe: &ScaledRecipe,
+    name: &str,
+) -> Result {
     let title_text = format!(
         " {}{} ",
         recipe
@@ -52,55 +131,49 @@ fn header(w: &mut impl io::Write, recipe: &ScaledRecipe, name: &str) -> Result {
             .unwrap_or_default(),
         name
     );
-    writeln!(w, "{}", title_text.paint(styles().title))?;
+    writeln!(w, "{}", title_text.paint(hf.styles.title))?;
     if let Some(tags) = recipe.metadata.tags() {
         let mut tags_str = String::new();
         for tag in tags {
-            let color = tag_color(&tag);
+            let color = hf
+                .tag_colors
+                .get(&tag.to_lowercase())
+                .copied()
+                .unwrap_or_else(|| TagColor::from_tag(&tag))
+                .terminal();
             write!(&mut tags_str, "{} ", format!("#{tag}").paint(color)).unwrap();
         }
-        print_wrapped(w, &tags_str)?;
+        print_wrapped(w, hf.width, &tags_str)?;
     }
     writeln!(w)
 }
 
-fn tag_color(tag: &str) -> yansi::Color {
-    let hash = tag
-        .chars()
-        .enumerate()
-        .map(|(i, c)| c as usize * i)
-        .reduce(usize::wrapping_add)
-        .map(|h| (h % 7))
-        .unwrap_or_default();
-    match hash {
-        0 => yansi::Color::Red,
-        1 => yansi::Color::Blue,
-        2 => yansi::Color::Cyan,
-        3 => yansi::Color::Yellow,
-        4 => yansi::Color::Green,
-        5 => yansi::Color::Magenta,
-        6 => yansi::Color::White,
-        _ => unreachable!(),
-    }
-}
-
-fn metadata(w: &mut impl io::Write, recipe: &ScaledRecipe, converter: &Converter) -> Result {
+fn metadata(
+    w: &mut impl io::Write,
+    hf: &HumanFormatter,
+    recipe: &ScaledRecipe,
+    converter: &Converter,
+) -> Result {
     if let Some(desc) = recipe.metadata.description() {
-        print_wrapped_with_options(w, desc, |o| {
+        print_wrapped_with_options(w, hf.width, desc, |o| {
             o.initial_indent("\u{2502} ").subsequent_indent("\u{2502}")
         })?;
         writeln!(w)?;
     }
 
     let mut meta_fmt =
-        |name: &str, value: &str| writeln!(w, "{}: {}", name.paint(styles().meta_key), value);
+        |name: &str, value: &str| writeln!(w, "{}: {}", name.paint(hf.styles.meta_key), value);
+    let linked = |text: &str, url: Option<&str>| match (hf.links(), url) {
+        (Some(_), Some(url)) => link::hyperlink(text, url),
+        _ => text.to_string(),
+    };
     if let Some(author) = recipe.metadata.author() {
         let text = author.name().or(author.url()).unwrap_or("-");
-        meta_fmt("author", text)?;
+        meta_fmt(&hf.labels.author, &linked(text, author.url()))?;
     }
     if let Some(source) = recipe.metadata.source() {
         let text = source.name().or(source.url()).unwrap_or("-");
-        meta_fmt("source", text)?;
+        meta_fmt(&hf.labels.source, &linked(text, source.url()))?;
     }
     if let Some(time) = recipe.metadata.time(converter) {
         let time_fmt = |t: u32| {
@@ -110,18 +183,18 @@ fn metadata(w: &mut impl io::Write, recipe: &ScaledRecipe, converter: &Converter
             )
         };
         match time {
-            cooklang::metadata::RecipeTime::Total(t) => meta_fmt("time", &time_fmt(t))?,
+            cooklang::metadata::RecipeTime::Total(t) => meta_fmt(&hf.labels.time, &time_fmt(t))?,
             cooklang::metadata::RecipeTime::Composed {
                 prep_time,
                 cook_time,
             } => {
                 if let Some(p) = prep_time {
-                    meta_fmt("prep time", &time_fmt(p))?
+                    meta_fmt(&hf.labels.prep_time, &time_fmt(p))?
                 }
                 if let Some(c) = cook_time {
-                    meta_fmt("cook time", &time_fmt(c))?;
+                    meta_fmt(&hf.labels.cook_time, &time_fmt(c))?;
                 }
-                meta_fmt("total time", &time_fmt(time.total()))?;
+                meta_fmt(&hf.labels.total_time, &time_fmt(time.total()))?;
             }
         }
     }
@@ -136,7 +209,7 @@ fn metadata(w: &mut impl io::Write, recipe: &ScaledRecipe, converter: &Converter
             .map(|(i, s)| {
                 if Some(i) == index {
                     format!("[{s}]")
-                        .paint(styles().selected_servings)
+                        .paint(hf.styles.selected_servings)
                         .to_string()
                 } else {
                     s.to_string()
@@ -154,7 +227,7 @@ fn metadata(w: &mut impl io::Write, recipe: &ScaledRecipe, converter: &Converter
                 );
             }
         }
-        meta_fmt("servings", &text)?;
+        meta_fmt(&hf.labels.servings, &text)?;
     }
     for (key, value) in recipe.metadata.map.iter().filter_map(|(key, value)| {
         let key = key.as_str_like()?;
@@ -166,7 +239,11 @@ fn metadata(w: &mut impl io::Write, recipe: &ScaledRecipe, converter: &Converter
         let value = value.as_str_like()?;
         Some((key, value))
     }) {
-        meta_fmt(&key, &value)?;
+        let url = match key.as_ref() {
+            "image" | "images" => hf.links().and_then(|l| l.image(&value)),
+            _ => None,
+        };
+        meta_fmt(&key, &linked(&value, url.as_deref()))?;
     }
     if !recipe.metadata.map.is_empty() {
         writeln!(w)?;
@@ -174,85 +251,349 @@ fn metadata(w: &mut impl io::Write, recipe: &ScaledRecipe, converter: &Converter
     Ok(())
 }
 
-fn ingredients(w: &mut impl io::Write, recipe: &ScaledRecipe, converter: &Converter) -> Result {
+fn ingredients(
+    w: &mut impl io::Write,
+    hf: &HumanFormatter,
+    recipe: &ScaledRecipe,
+    converter: &Converter,
+    marks: &HashMap<usize, ScaleMark>,
+) -> Result {
     if recipe.ingredients.is_empty() {
         return Ok(());
     }
-    writeln!(w, "Ingredients:")?;
-    let mut table = Table::new("  {:<} {:<}    {:<} {:<}");
+    writeln!(w, "{}:", hf.labels.ingredients)?;
+    let grouped = recipe.group_ingredients(converter);
+    let total_flour = hf
+        .bakers_percentages
+        .then(|| {
+            grouped
+                .iter()
+                .filter(|e| e.ingredient.modifiers().should_be_listed())
+                .filter(|e| is_flour(&e.ingredient.name))
+                .filter_map(|e| grams(e.quantity.iter(), converter))
+                .sum::<f64>()
+        })
+        .filter(|&total| total > 0.0);
+    let mut shown = Vec::new();
+    for &column in &hf.ingredient_columns {
+        shown.push(Shown::Column(column));
+        if column == IngredientColumn::Quantity && total_flour.is_some() {
+            shown.push(Shown::Bakers);
+        }
+    }
+    let wants = |column| hf.ingredient_columns.contains(&column);
+    let mut rows = Vec::new();
     let mut there_is_fixed = false;
     let mut there_is_err = false;
+    let mut there_is_sqrt = false;
+    let mut there_is_stepwise = false;
+    let mut there_is_anchor = false;
+    let mut there_is_adjusted = false;
     let trinagle = " \u{26a0}";
     let octagon = " \u{2BC3}";
-    for entry in recipe.group_ingredients(converter) {
+    let root = " \u{221a}";
+    let approx = " \u{2248}";
+    let diamond = " \u{25c6}";
+    let pencil = " \u{270e}";
+    for entry in grouped {
+        if !entry.ingredient.modifiers().should_be_listed() {
+            continue;
+        }
+        let info = &hf.ingredient_info;
+        let category = wants(IngredientColumn::Category)
+            .then(|| info.category(&entry))
+            .flatten();
+        let cost = wants(IngredientColumn::Cost)
+            .then(|| info.cost(&entry))
+            .flatten();
+        let calories = wants(IngredientColumn::Calories)
+            .then(|| info.calories(&entry))
+            .flatten();
         let GroupedIngredient {
+            index,
             ingredient: igr,
             quantity,
             outcome,
             ..
         } = entry;
-        if !igr.modifiers().should_be_listed() {
-            continue;
-        }
-        let mut is_fixed = false;
-        let mut is_err = false;
-        let (outcome_style, outcome_char) = outcome
-            .map(|outcome| match outcome {
-                ScaleOutcome::Fixed => {
-                    there_is_fixed = true;
-                    is_fixed = true;
-                    (yansi::Style::new().yellow(), trinagle)
+        let (outcome_style, outcome_char) = match marks.get(&index) {
+            Some(ScaleMark::Fixed) => {
+                there_is_fixed = true;
+                (yansi::Style::new().yellow(), trinagle)
+            }
+            Some(ScaleMark::Sqrt) => {
+                there_is_sqrt = true;
+                (yansi::Style::new().cyan(), root)
+            }
+            Some(ScaleMark::Stepwise) => {
+                there_is_stepwise = true;
+                (yansi::Style::new().cyan(), approx)
+            }
+            Some(ScaleMark::Anchor) => {
+                there_is_anchor = true;
+                (yansi::Style::new().green(), diamond)
+            }
+            Some(ScaleMark::Adjusted) => {
+                there_is_adjusted = true;
+                (yansi::Style::new().magenta(), pencil)
+            }
+            Some(ScaleMark::Approximate) => (yansi::Style::new(), ""),
+            None => outcome
+                .map(|outcome| match outcome {
+                    ScaleOutcome::Fixed => {
+                        there_is_fixed = true;
+                        (yansi::Style::new().yellow(), trinagle)
+                    }
+                    ScaleOutcome::Error(_) => {
+                        there_is_err = true;
+                        (yansi::Style::new().red(), octagon)
+                    }
+                    ScaleOutcome::Scaled | ScaleOutcome::NoQuantity => (yansi::Style::new(), ""),
+                })
+                .unwrap_or_default(),
+        };
+        let mut cells = Vec::with_capacity(shown.len());
+        for &column in &shown {
+            let cell = match column {
+                Shown::Column(IngredientColumn::Name) => {
+                    let name = igr.display_name();
+                    match hf.links().and_then(|l| l.recipes.get(name.as_ref())) {
+                        Some(url) => Cell::Ansi(link::hyperlink(&name, url)),
+                        None => Cell::Plain(name.to_string()),
+                    }
                 }
-                ScaleOutcome::Error(_) => {
-                    there_is_err = true;
-                    is_err = true;
-                    (yansi::Style::new().red(), octagon)
+                Shown::Column(IngredientColumn::Optional) => {
+                    if igr.modifiers().is_optional() {
+                        Cell::Ansi(
+                            format!("({})", hf.labels.optional)
+                                .paint(hf.styles.opt_marker)
+                                .to_string(),
+                        )
+                    } else {
+                        Cell::Plain(String::new())
+                    }
                 }
-                ScaleOutcome::Scaled | ScaleOutcome::NoQuantity => (yansi::Style::new(), ""),
-            })
-            .unwrap_or_default();
-        let mut row = Row::new().with_cell(igr.display_name());
-        if igr.modifiers().is_optional() {
-            row.add_ansi_cell("(optional)".paint(styles().opt_marker));
-        } else {
-            row.add_cell("");
+                Shown::Column(IngredientColumn::Quantity) => {
+                    let mut content = String::new();
+                    for (i, q) in quantity.iter().enumerate() {
+                        if i > 0 {
+                            content.push_str(", ");
+                        }
+                        write!(&mut content, "{}", QuantityFmt(q, hf).paint(outcome_style))
+                            .unwrap();
+                    }
+                    write!(&mut content, "{}", outcome_char.paint(outcome_style)).unwrap();
+                    Cell::Ansi(content)
+                }
+                Shown::Bakers => match (total_flour, grams(quantity.iter(), converter)) {
+                    (Some(total), Some(g)) => Cell::Ansi(
+                        hf.number_format
+                            .localize(&format!("{:.1}%", g / total * 100.0))
+                            .dim()
+                            .to_string(),
+                    ),
+                    _ => Cell::Plain(String::new()),
+                },
+                Shown::Column(IngredientColumn::Note) => Cell::Plain(
+                    igr.note
+                        .as_ref()
+                        .map(|n| format!("({n})"))
+                        .unwrap_or_default(),
+                ),
+                Shown::Column(IngredientColumn::Category) => {
+                    Cell::Plain(category.clone().unwrap_or_default())
+                }
+                Shown::Column(IngredientColumn::Cost) => {
+                    Cell::Plain(cost.clone().unwrap_or_default())
+                }
+                Shown::Column(IngredientColumn::Calories) => {
+                    Cell::Plain(calories.clone().unwrap_or_default())
+                }
+            };
+            cells.push(cell);
         }
-        let content = quantity
-            .iter()
-            .map(|q| quantity_fmt(q).paint(outcome_style).to_string())
-            .reduce(|s, q| format!("{s}, {q}"))
-            .unwrap_or_default();
-        row.add_ansi_cell(format!("{content}{}", outcome_char.paint(outcome_style)));
+        rows.push(cells);
+    }
 
-        if let Some(note) = &igr.note {
-            row.add_cell(format!("({note})"));
-        } else {
-            row.add_cell("");
+    // the columns from the ingredient info are left out when they are empty
+    let keep = shown
+        .iter()
+        .enumerate()
+        .map(|(i, column)| match column {
+            Shown::Column(
+                IngredientColumn::Category | IngredientColumn::Cost | IngredientColumn::Calories,
+            ) => rows.iter().any(|cells| !cells[i].is_empty()),
+            _ => true,
+        })
+        .collect::<Vec<_>>();
+    let mut spec = String::from("  ");
+    let mut prev = None;
+    for (&column, _) in shown.iter().zip(&keep).filter(|(_, &k)| k) {
+        if let Some(prev) = prev {
+            spec += match (prev, column) {
+                (_, Shown::Column(IngredientColumn::Quantity)) => "    ",
+                (Shown::Bakers, _) => "  ",
+                _ => " ",
+            };
+        }
+        spec += match column {
+            Shown::Bakers => "{:>}",
+            Shown::Column(c) if c.right_aligned() => "{:>}",
+            Shown::Column(_) => "{:<}",
+        };
+        prev = Some(column);
+    }
+    let mut table = Table::new(&spec);
+    for cells in rows {
+        let mut row = Row::new();
+        for (cell, _) in cells.into_iter().zip(&keep).filter(|(_, &k)| k) {
+            match cell {
+                Cell::Plain(text) => row.add_cell(text),
+                Cell::Ansi(text) => row.add_ansi_cell(text),
+            };
         }
         table.add_row(row);
     }
-    write!(w, "{table}")?;
-    if there_is_fixed || there_is_err {
+    write_table(w, hf, &table.to_string())?;
+    let mut legend = Vec::new();
+    if there_is_fixed {
+        legend.push(format!(
+            "{} {}",
+            trinagle.trim().yellow(),
+            hf.labels.fixed_value.yellow()
+        ));
+    }
+    if there_is_sqrt {
+        legend.push(format!(
+            "{} {}",
+            root.trim().cyan(),
+            hf.labels.scaled_sqrt.cyan()
+        ));
+    }
+    if there_is_stepwise {
+        legend.push(format!(
+            "{} {}",
+            approx.trim().cyan(),
+            hf.labels.rounded.cyan()
+        ));
+    }
+    if there_is_anchor {
+        legend.push(format!(
+            "{} {}",
+            diamond.trim().green(),
+            hf.labels.scaling_target.green()
+        ));
+    }
+    if there_is_adjusted {
+        legend.push(format!(
+            "{} {}",
+            pencil.trim().magenta(),
+            hf.labels.adjusted.magenta()
+        ));
+    }
+    if there_is_err {
+        legend.push(format!(
+            "{} {}",
+            octagon.trim().red(),
+            hf.labels.scaling_error.red()
+        ));
+    }
+    if total_flour.is_some() {
+        legend.push(format!("{}", hf.labels.bakers_percentage.dim()));
+    }
+    if !legend.is_empty() && wants(IngredientColumn::Quantity) {
         writeln!(w)?;
-        if there_is_fixed {
-            write!(w, "{} {}", trinagle.trim().yellow(), "fixed value".yellow())?;
-        }
-        if there_is_err {
-            if there_is_fixed {
-                write!(w, " | ")?;
+        writeln!(w, "{}", legend.join(" | "))?;
+    }
+    writeln!(w)
+}
+
+/// Min width of the output to lay a table in two columns
+const TWO_COLUMNS_MIN_WIDTH: usize = 100;
+/// Min rows of a table to lay it in two columns
+const TWO_COLUMNS_MIN_ROWS: usize = 8;
+const TWO_COLUMNS_GAP: usize = 4;
+
+/// Writes a table, in two columns if it's long and fits
+///
+/// The rows are split in two halves, the first one on the left. Both come
+/// from the same table, so the cells are aligned the same.
+fn write_table(w: &mut impl io::Write, hf: &HumanFormatter, table: &str) -> Result {
+    let rows = table.lines().collect::<Vec<_>>();
+    let row_width = rows
+        .iter()
+        .map(|r| textwrap::core::display_width(r.trim_end()))
+        .max()
+        .unwrap_or(0);
+    let fits = hf.layout_width >= TWO_COLUMNS_MIN_WIDTH
+        && row_width * 2 + TWO_COLUMNS_GAP <= hf.layout_width;
+    if !hf.two_columns || rows.len() < TWO_COLUMNS_MIN_ROWS || !fits {
+        return write!(w, "{table}");
+    }
+    let (left, right) = rows.split_at(rows.len().div_ceil(2));
+    for (i, l) in left.iter().enumerate() {
+        let l = l.trim_end();
+        match right.get(i) {
+            Some(r) => {
+                let pad = row_width - textwrap::core::display_width(l) + TWO_COLUMNS_GAP;
+                writeln!(w, "{l}{:pad$}{}", "", r.trim_end())?;
             }
-            write!(w, "{} {}", octagon.trim().red(), "error scaling".red())?;
+            None => writeln!(w, "{l}")?,
         }
-        writeln!(w)?;
     }
-    writeln!(w)
+    Ok(())
+}
+
+/// A column of the ingredients table, the bakers' percentage goes after the
+/// quantity
+#[derive(Clone, Copy)]
+enum Shown {
+    Column(IngredientColumn),
+    Bakers,
+}
+
+enum Cell {
+    Plain(String),
+    /// Text with colours, that tabular has to measure without them
+    Ansi(String),
 }
 
-fn cookware(w: &mut impl io::Write, recipe: &ScaledRecipe) -> Result {
+impl Cell {
+    fn is_empty(&self) -> bool {
+        match self {
+            Cell::Plain(text) | Cell::Ansi(text) => text.is_empty(),
+        }
+    }
+}
+
+/// Whether an ingredient counts as flour for the bakers' percentages
+pub fn is_flour(name: &str) -> bool {
+    name.to_lowercase().contains("flour")
+}
+
+/// Sum of the quantities in grams, `None` if there are none or any of them
+/// can't be converted
+fn grams<'a>(
+    quantities: impl Iterator<Item = &'a Quantity<Value>>,
+    converter: &Converter,
+) -> Option<f64> {
+    let mut total = None;
+    for q in quantities {
+        let mut q = q.clone();
+        q.convert("g", converter).ok()?;
+        let Value::Number(n) = q.value() else {
+            return None;
+        };
+        *total.get_or_insert(0.0) += n.value();
+    }
+    total
+}
+
+fn cookware(w: &mut impl io::Write, hf: &HumanFormatter, recipe: &ScaledRecipe) -> Result {
     if recipe.cookware.is_empty() {
         return Ok(());
     }
-    writeln!(w, "Cookware:")?;
+    writeln!(w, "{}:", hf.labels.cookware)?;
     let mut table = Table::new("  {:<} {:<}    {:<} {:<}");
     for item in recipe
         .cookware
@@ -261,9 +602,9 @@ fn cookware(w: &mut impl io::Write, recipe: &ScaledRecipe) -> Result {
     {
         let mut row = Row::new().with_cell(item.display_name()).with_cell(
             if item.modifiers().is_optional() {
-                "(optional)"
+                format!("({})", hf.labels.optional)
             } else {
-                ""
+                String::new()
             },
         );
 
@@ -291,29 +632,36 @@ fn cookware(w: &mut impl io::Write, recipe: &ScaledRecipe) -> Result {
     Ok(())
 }
 
-fn steps(w: &mut impl io::Write, recipe: &ScaledRecipe) -> Result {
-    writeln!(w, "Steps:")?;
+fn steps(w: &mut impl io::Write, hf: &HumanFormatter, recipe: &ScaledRecipe) -> Result {
+    writeln!(w, "{}:", hf.labels.steps)?;
+    // reused for every step, they have to be complete before wrapping
+    let mut step_text = String::new();
+    let mut step_igrs = String::new();
     for (section_index, section) in recipe.sections.iter().enumerate() {
         if recipe.sections.len() > 1 {
             writeln!(
                 w,
                 "{: ^width$}",
                 format!("─── § {} ───", section_index + 1),
-                width = TERM_WIDTH
+                width = hf.width
             )?;
         }
 
         if let Some(name) = &section.name {
-            writeln!(w, "{}:", name.paint(styles().section_name))?;
+            writeln!(w, "{}:", name.paint(hf.styles.section_name))?;
         }
 
         for content in &section.content {
             match content {
                 cooklang::Content::Step(step) => {
-                    let (step_text, step_ingredients) = step_text(recipe, section, step);
-                    let step_text = format!("{:>2}. {}", step.number, step_text.trim());
-                    print_wrapped_with_options(w, &step_text, |o| o.subsequent_indent("    "))?;
-                    print_wrapped_with_options(w, &step_ingredients, |o| {
+                    // not `.clear()`, it resolves to `Paint::clear` before `String::clear`
+                    String::clear(&mut step_text);
+                    String::clear(&mut step_igrs);
+                    write_step_text(&mut step_text, &mut step_igrs, hf, recipe, section, step);
+                    print_wrapped_with_options(w, hf.width, &step_text, |o| {
+                        o.subsequent_indent("    ")
+                    })?;
+                    print_wrapped_with_options(w, hf.width, &step_igrs, |o| {
                         let indent = "     "; // 5
                         o.initial_indent(indent)
                             .subsequent_indent(indent)
@@ -327,7 +675,7 @@ fn steps(w: &mut impl io::Write, recipe: &ScaledRecipe) -> Result {
                 }
                 cooklang::Content::Text(t) => {
                     writeln!(w)?;
-                    print_wrapped_with_options(w, t.trim(), |o| o.initial_indent("  "))?;
+                    print_wrapped_with_options(w, hf.width, t.trim(), |o| o.initial_indent("  "))?;
                     writeln!(w)?;
                 }
             }
@@ -336,8 +684,20 @@ fn steps(w: &mut impl io::Write, recipe: &ScaledRecipe) -> Result {
     Ok(())
 }
 
-fn step_text(recipe: &ScaledRecipe, section: &Section, step: &Step) -> (String, String) {
-    let mut step_text = String::new();
+/// Writes the numbered step text and the line with its ingredients
+///
+/// Both buffers are expected to be empty.
+fn write_step_text(
+    step_text: &mut String,
+    igrs_text: &mut String,
+    hf: &HumanFormatter,
+    recipe: &ScaledRecipe,
+    section: &Section,
+    step: &Step,
+) {
+    // writing to a String never fails, the unwraps are safe
+    write!(step_text, "{:>2}. ", step.number).unwrap();
+    let prefix_len = step_text.len();
 
     let step_igrs_dedup = build_step_igrs_dedup(step, recipe);
 
@@ -347,110 +707,116 @@ fn step_text(recipe: &ScaledRecipe, section: &Section, step: &Step) -> (String,
 
     for item in &step.items {
         match item {
-            Item::Text { value } => step_text += value,
+            Item::Text { value } => step_text.push_str(value),
             &Item::Ingredient { index } => {
                 let igr = &recipe.ingredients[index];
                 write!(
-                    &mut step_text,
+                    step_text,
                     "{}",
-                    igr.display_name().paint(styles().ingredient)
+                    igr.display_name().paint(hf.styles.ingredient)
                 )
                 .unwrap();
-                let pos = write_igr_count(&mut step_text, &step_igrs_dedup, index, &igr.name);
+                let pos = write_igr_count(step_text, &step_igrs_dedup, index, &igr.name);
                 if step_igrs_dedup[igr.name.as_str()].contains(&index) {
                     step_igrs_line.push((igr, pos));
                 }
             }
             &Item::Cookware { index } => {
                 let cookware = &recipe.cookware[index];
-                write!(&mut step_text, "{}", cookware.name.paint(styles().cookware)).unwrap();
+                write!(step_text, "{}", cookware.name.paint(hf.styles.cookware)).unwrap();
             }
             &Item::Timer { index } => {
                 let timer = &recipe.timers[index];
 
                 match (&timer.quantity, &timer.name) {
                     (Some(quantity), Some(name)) => {
-                        let s = format!(
+                        write!(
+                            step_text,
                             "{} ({})",
-                            quantity_fmt(quantity).paint(styles().timer),
-                            name.paint(styles().timer),
-                        );
-                        write!(&mut step_text, "{}", s).unwrap();
+                            QuantityFmt(quantity, hf).paint(hf.styles.timer),
+                            name.paint(hf.styles.timer),
+                        )
+                        .unwrap();
                     }
                     (Some(quantity), None) => {
                         write!(
-                            &mut step_text,
+                            step_text,
                             "{}",
-                            quantity_fmt(quantity).paint(styles().timer)
+                            QuantityFmt(quantity, hf).paint(hf.styles.timer)
                         )
                         .unwrap();
                     }
                     (None, Some(name)) => {
-                        write!(&mut step_text, "{}", name.paint(styles().timer)).unwrap();
+                        write!(step_text, "{}", name.paint(hf.styles.timer)).unwrap();
                     }
-                    (None, None) => unreachable!(), // guaranteed in parsing
+                    // the parser never outputs it, but nothing to write
+                    (None, None) => {}
                 }
             }
             &Item::InlineQuantity { index } => {
                 let q = &recipe.inline_quantities[index];
                 write!(
-                    &mut step_text,
+                    step_text,
                     "{}",
-                    quantity_fmt(q).paint(styles().inline_quantity)
+                    QuantityFmt(q, hf).paint(hf.styles.inline_quantity)
                 )
                 .unwrap()
             }
         }
     }
 
+    // trim the step text in place, keeping the number
+    let leading = step_text[prefix_len..].len() - step_text[prefix_len..].trim_start().len();
+    step_text.drain(prefix_len..prefix_len + leading);
+    step_text.truncate(step_text.trim_end().len());
+
     // This is only for the line where ingredients are placed
 
     if step_igrs_line.is_empty() {
-        return (step_text, "[-]".into());
+        igrs_text.push_str("[-]");
+        return;
     }
-    let mut igrs_text = String::from("[");
+    igrs_text.push('[');
     for (i, (igr, pos)) in step_igrs_line.iter().enumerate() {
-        write!(&mut igrs_text, "{}", igr.display_name()).unwrap();
+        igrs_text.push_str(&igr.display_name());
         if let Some(pos) = pos {
-            write_subscript(&mut igrs_text, &pos.to_string());
+            write_subscript(igrs_text, *pos);
         }
         if igr.modifiers().is_optional() {
-            write!(&mut igrs_text, "{}", " (opt)".paint(styles().opt_marker)).unwrap();
-        }
-        if let Some(source) = inter_ref_text(igr, section) {
-            write!(
-                &mut igrs_text,
-                "{}",
-                format!(" from {source}").paint(styles().intermediate_ref)
-            )
-            .unwrap();
+            let text = format!(" ({})", hf.labels.optional_short);
+            write!(igrs_text, "{}", text.paint(hf.styles.opt_marker)).unwrap();
         }
+        write_inter_ref(igrs_text, hf, igr, section);
         if let Some(q) = &igr.quantity {
             write!(
-                &mut igrs_text,
+                igrs_text,
                 ": {}",
-                quantity_fmt(q).paint(styles().step_igr_quantity)
+                QuantityFmt(q, hf).paint(hf.styles.step_igr_quantity)
             )
             .unwrap();
         }
         if i != step_igrs_line.len() - 1 {
-            igrs_text += ", ";
+            igrs_text.push_str(", ");
         }
     }
-    igrs_text += "]";
-    (step_text, igrs_text)
+    igrs_text.push(']');
 }
 
-fn inter_ref_text(igr: &Ingredient, section: &Section) -> Option<String> {
+fn write_inter_ref(buffer: &mut String, hf: &HumanFormatter, igr: &Ingredient, section: &Section) {
+    let style = hf.styles.intermediate_ref;
     match igr.relation.references_to() {
         Some((target_sect, IngredientReferenceTarget::Section)) => {
-            Some(format!("section {}", target_sect + 1))
+            let text = format!(" {} {}", hf.labels.from_section, target_sect + 1);
+            write!(buffer, "{}", text.paint(style)).unwrap();
         }
         Some((target_step, IngredientReferenceTarget::Step)) => {
-            let step = &section.content[target_step].unwrap_step();
-            Some(format!("step {}", step.number))
+            // only a hand made recipe can point to something else
+            if let Some(cooklang::Content::Step(step)) = section.content.get(target_step) {
+                let text = format!(" {} {}", hf.labels.from_step, step.number);
+                write!(buffer, "{}", text.paint(style)).unwrap();
+            }
         }
-        _ => None,
+        _ => {}
     }
 }
 
@@ -497,52 +863,49 @@ fn write_igr_count(
     }
     if let Some(mut pos) = entries.iter().position(|&i| i == index) {
         pos += 1;
-        write_subscript(buffer, &pos.to_string());
+        write_subscript(buffer, pos);
         Some(pos)
     } else {
         None
     }
 }
 
-fn quantity_fmt(qty: &Quantity) -> String {
-    if let Some(unit) = qty.unit() {
-        format!("{} {}", qty.value(), unit.italic())
-    } else {
-        format!("{}", qty.value())
+/// Displays a quantity with the unit in italics
+struct QuantityFmt<'a>(&'a Quantity, &'a HumanFormatter);
+
+impl std::fmt::Display for QuantityFmt<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let QuantityFmt(qty, hf) = self;
+        f.write_str(&hf.quantities.value(qty))?;
+        if let Some(unit) = hf.quantities.unit(qty) {
+            write!(f, " {}", unit.italic())?;
+        }
+        Ok(())
     }
 }
 
-fn write_subscript(buffer: &mut String, s: &str) {
-    buffer.reserve(s.len());
-    s.chars()
-        .map(|c| match c {
-            '0' => '₀',
-            '1' => '₁',
-            '2' => '₂',
-            '3' => '₃',
-            '4' => '₄',
-            '5' => '₅',
-            '6' => '₆',
-            '7' => '₇',
-            '8' => '₈',
-            '9' => '₉',
-            _ => c,
-        })
-        .for_each(|c| buffer.push(c))
+fn write_subscript(buffer: &mut String, n: usize) {
+    const DIGITS: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+    let start = buffer.len();
+    let mut n = n;
+    loop {
+        buffer.insert(start, DIGITS[n % 10]);
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
 }
 
-fn print_wrapped(w: &mut impl io::Write, text: &str) -> Result {
-    print_wrapped_with_options(w, text, |o| o)
+fn print_wrapped(w: &mut impl io::Write, width: usize, text: &str) -> Result {
+    print_wrapped_with_options(w, width, text, |o| o)
 }
 
-static TERM_WIDTH: std::sync::LazyLock<usize> =
-    std::sync::LazyLock::new(|| textwrap::termwidth().min(80));
-
-fn print_wrapped_with_options<F>(w: &mut impl io::Write, text: &str, f: F) -> Result
+fn print_wrapped_with_options<F>(w: &mut impl io::Write, width: usize, text: &str, f: F) -> Result
 where
     F: FnOnce(textwrap::Options) -> textwrap::Options,
 {
-    let options = f(textwrap::Options::new(*TERM_WIDTH));
+    let options = f(textwrap::Options::new(width));
     let lines = textwrap::wrap(text, options);
     for line in lines {
         writeln!(w, "{}", line)?;
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+/// Clickable links in the output, with OSC 8 escape sequences
+///
+/// The URLs of the author and source are always linked. Terminals without
+/// support show only the text. Links are never written without colours.
+#[derive(Debug, Clone, Default)]
+pub struct Hyperlinks {
+    /// URL of the directory of the recipe, ending in `/`, to link the
+    /// relative image paths in the metadata
+    pub base_url: Option<String>,
+    /// URL of each referenced recipe, by the ingredient display name
+    pub recipes: HashMap<String, String>,
+}
+
+impl Hyperlinks {
+    /// URL of an `image` metadata value, a URL or a path relative to the
+    /// recipe
+    pub(crate) fn image(&self, value: &str) -> Option<String> {
+        if value.contains("://") {
+            return Some(value.to_string());
+        }
+        let base = self.base_url.as_deref()?;
+        Some(format!("{base}{value}"))
+    }
+}
+
+/// The text linked to the URL
+pub(crate) fn hyperlink(text: &str, url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{text}\x1b]8;;\x1b\\", escape_url(url))
+}
+
+/// The URL with only printable ASCII, so it can't end the escape sequence
+///
+/// Control characters are dropped and the rest is percent-encoded.
+fn escape_url(url: &str) -> String {
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars().filter(|c| !c.is_control()) {
+        if c.is_ascii_graphic() {
+            escaped.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                escaped.push_str(&format!("%{b:02X}"));
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_urls() {
+        assert_eq!(escape_url("https://a.com/b?c=d"), "https://a.com/b?c=d");
+        assert_eq!(escape_url("img/my bread.jpg"), "img/my%20bread.jpg");
+        assert_eq!(escape_url("pan/ñ.jpg"), "pan/%C3%B1.jpg");
+        assert_eq!(
+            escape_url("https://a.com\x1b\\\x1b]2;pwned\x07"),
+            "https://a.com\\]2;pwned"
+        );
+    }
+}
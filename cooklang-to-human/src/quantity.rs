@@ -0,0 +1,129 @@
+use cooklang::quantity::{Quantity, Value};
+
+use crate::{NumberFormat, QuantityFormat};
+
+/// How the quantities are written
+///
+/// Implement it to change how quantities look without changing the rest of
+/// the output, like ranges as `2–3`, approximations as `~200 g` or small
+/// amounts as `a pinch`.
+///
+/// ```
+/// # use cooklang::quantity::{Quantity, Value};
+/// # use cooklang_to_human::{DefaultQuantityFormatter, QuantityFormatter};
+/// #[derive(Debug)]
+/// struct Pinch;
+///
+/// impl QuantityFormatter for Pinch {
+///     fn value(&self, quantity: &Quantity) -> String {
+///         match (quantity.value(), quantity.unit()) {
+///             (Value::Number(n), Some("g")) if n.value() < 1.0 => "a pinch".into(),
+///             _ => DefaultQuantityFormatter::default().value(quantity),
+///         }
+///     }
+///
+///     fn unit(&self, quantity: &Quantity) -> Option<String> {
+///         match (quantity.value(), quantity.unit()) {
+///             (Value::Number(n), Some("g")) if n.value() < 1.0 => None,
+///             _ => quantity.unit().map(str::to_string),
+///         }
+///     }
+/// }
+/// ```
+pub trait QuantityFormatter: std::fmt::Debug + Send + Sync {
+    /// Text of the value, without the unit
+    fn value(&self, quantity: &Quantity) -> String;
+
+    /// Text of the unit, `None` to write no unit
+    fn unit(&self, quantity: &Quantity) -> Option<String> {
+        quantity.unit().map(str::to_string)
+    }
+
+    /// The value and the unit separated by a space
+    fn format(&self, quantity: &Quantity) -> String {
+        let value = self.value(quantity);
+        match self.unit(quantity) {
+            Some(unit) => format!("{value} {unit}"),
+            None => value,
+        }
+    }
+}
+
+/// The quantities as the parser gives them, with a [`QuantityFormat`] and the
+/// separators of a [`NumberFormat`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultQuantityFormatter {
+    pub format: QuantityFormat,
+    pub numbers: NumberFormat,
+}
+
+impl DefaultQuantityFormatter {
+    pub fn new(numbers: NumberFormat) -> Self {
+        Self {
+            format: QuantityFormat::default(),
+            numbers,
+        }
+    }
+}
+
+impl QuantityFormatter for DefaultQuantityFormatter {
+    /// Ranges are written with an en dash, `2–3`, and approximate values
+    /// with `≈`, `≈150`
+    fn value(&self, quantity: &Quantity) -> String {
+        let number = |n: f64| match self.format {
+            QuantityFormat::Default => n.to_string(),
+            QuantityFormat::Decimal => decimal(n).to_string(),
+        };
+        let value = match (self.format, quantity.value()) {
+            (QuantityFormat::Default, Value::Number(n)) => n.to_string(),
+            (QuantityFormat::Default, Value::Range { start, end }) => format!("{start}–{end}"),
+            (QuantityFormat::Decimal, Value::Number(n)) => number(n.value()),
+            (QuantityFormat::Decimal, Value::Range { start, end }) => {
+                format!("{}–{}", number(start.value()), number(end.value()))
+            }
+            (_, Value::Text(t)) => match approx_value(t) {
+                Some(n) => format!("≈{}", number(n)),
+                None => t.clone(),
+            },
+        };
+        self.numbers.localize(&value)
+    }
+}
+
+/// Prefixes of an approximate value
+const APPROX_PREFIXES: &[&str] = &["~", "≈", "about", "approx.", "approx", "ca.", "circa"];
+
+/// Number of an approximate value, the parser keeps them as text
+///
+/// `~150`, `≈150`, `about 150` or `ca. 1.5` are approximate.
+pub fn approx_value(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let lower = text.to_lowercase();
+    let prefix = APPROX_PREFIXES.iter().find(|p| lower.starts_with(*p))?;
+    let n = text[prefix.len()..]
+        .trim()
+        .replace(',', ".")
+        .parse::<f64>()
+        .ok()?;
+    n.is_finite().then_some(n)
+}
+
+/// Rounded to 3 decimals
+fn decimal(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approx() {
+        assert_eq!(approx_value("~150"), Some(150.0));
+        assert_eq!(approx_value("≈ 1,5"), Some(1.5));
+        assert_eq!(approx_value("About 2"), Some(2.0));
+        assert_eq!(approx_value("ca. 0.5"), Some(0.5));
+        assert_eq!(approx_value("a pinch"), None);
+        assert_eq!(approx_value("150"), None);
+    }
+}
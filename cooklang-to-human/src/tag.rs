@@ -0,0 +1,102 @@
+/// Colour of a tag, the same in every output
+///
+/// A tag has a default colour from its name that never changes between runs
+/// or programs, see [`TagColor::from_tag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Teal,
+    Blue,
+    Indigo,
+    Gray,
+}
+
+impl TagColor {
+    pub const ALL: [Self; 8] = [
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Green,
+        Self::Teal,
+        Self::Blue,
+        Self::Indigo,
+        Self::Gray,
+    ];
+
+    /// Default colour of a tag, from a FNV-1a hash of its lowercase name
+    pub fn from_tag(tag: &str) -> Self {
+        let mut hash: u32 = 0x811c_9dc5;
+        for b in tag.to_lowercase().bytes() {
+            hash ^= u32::from(b);
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+        Self::ALL[hash as usize % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Red => "red",
+            Self::Orange => "orange",
+            Self::Yellow => "yellow",
+            Self::Green => "green",
+            Self::Teal => "teal",
+            Self::Blue => "blue",
+            Self::Indigo => "indigo",
+            Self::Gray => "gray",
+        }
+    }
+
+    pub(crate) fn terminal(self) -> yansi::Color {
+        use yansi::Color;
+        match self {
+            Self::Red => Color::Red,
+            Self::Orange => Color::Fixed(208),
+            Self::Yellow => Color::Yellow,
+            Self::Green => Color::Green,
+            Self::Teal => Color::Cyan,
+            Self::Blue => Color::Blue,
+            Self::Indigo => Color::Magenta,
+            Self::Gray => Color::White,
+        }
+    }
+}
+
+impl std::fmt::Display for TagColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for TagColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|c| c.name() == s || (s == "grey" && *c == Self::Gray))
+            .ok_or_else(|| {
+                let names = Self::ALL.map(Self::name).join(", ");
+                format!("Unknown tag colour '{s}', expected one of {names}")
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_colors() {
+        assert_eq!(TagColor::from_tag("Vegan"), TagColor::from_tag("vegan"));
+        // tags that only differ in the first letter got the same colour with
+        // the old hash
+        let colors = ["pasta", "basta", "casta", "dasta"].map(TagColor::from_tag);
+        assert!(colors.iter().any(|&c| c != colors[0]));
+        assert_eq!("Grey".parse(), Ok(TagColor::Gray));
+        assert!("purple".parse::<TagColor>().is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use cooklang::{
+    model::{IngredientReferenceTarget, Item},
+    Content, ScaledRecipe,
+};
+use thiserror::Error;
+
+/// A recipe that can't be written
+///
+/// The parser never outputs these, but a recipe built or changed by hand can
+/// have items that point nowhere. The indexes start at 0, like in the model.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MalformedRecipe {
+    #[error("section {section}, step {step}: there is no {kind} {index}")]
+    MissingItem {
+        section: usize,
+        step: usize,
+        kind: &'static str,
+        index: usize,
+    },
+    #[error("timer {index} has neither a quantity nor a name")]
+    EmptyTimer { index: usize },
+    #[error("ingredient {index} references {kind} {target}, which is not there")]
+    BadReference {
+        index: usize,
+        kind: &'static str,
+        target: usize,
+    },
+}
+
+/// Checks that everything in the recipe points to something that exists
+///
+/// With `Ok`, writing the recipe won't panic.
+pub fn validate(recipe: &ScaledRecipe) -> Result<(), MalformedRecipe> {
+    for (index, timer) in recipe.timers.iter().enumerate() {
+        if timer.quantity.is_none() && timer.name.is_none() {
+            return Err(MalformedRecipe::EmptyTimer { index });
+        }
+    }
+    for (index, igr) in recipe.ingredients.iter().enumerate() {
+        let bad = |kind, target| MalformedRecipe::BadReference {
+            index,
+            kind,
+            target,
+        };
+        if let Some(&target) = igr
+            .relation
+            .referenced_from()
+            .iter()
+            .find(|&&i| i >= recipe.ingredients.len())
+        {
+            return Err(bad("ingredient", target));
+        }
+        match igr.relation.references_to() {
+            Some((target, IngredientReferenceTarget::Ingredient))
+                if target >= recipe.ingredients.len() =>
+            {
+                return Err(bad("ingredient", target))
+            }
+            Some((target, IngredientReferenceTarget::Section))
+                if target >= recipe.sections.len() =>
+            {
+                return Err(bad("section", target))
+            }
+            _ => {}
+        }
+    }
+
+    for (s_index, section) in recipe.sections.iter().enumerate() {
+        for step in section.content.iter().filter_map(|c| match c {
+            Content::Step(step) => Some(step),
+            Content::Text(_) => None,
+        }) {
+            for item in &step.items {
+                let (kind, index, len) = match *item {
+                    Item::Text { .. } => continue,
+                    Item::Ingredient { index } => ("ingredient", index, recipe.ingredients.len()),
+                    Item::Cookware { index } => ("cookware", index, recipe.cookware.len()),
+                    Item::Timer { index } => ("timer", index, recipe.timers.len()),
+                    Item::InlineQuantity { index } => {
+                        ("inline quantity", index, recipe.inline_quantities.len())
+                    }
+                };
+                if index >= len {
+                    return Err(MalformedRecipe::MissingItem {
+                        section: s_index,
+                        step: step.number as usize,
+                        kind,
+                        index,
+                    });
+                }
+                // step references are to a step of the same section
+                if let Item::Ingredient { index } = *item {
+                    if let Some((target, IngredientReferenceTarget::Step)) =
+                        recipe.ingredients[index].relation.references_to()
+                    {
+                        if !matches!(section.content.get(target), Some(Content::Step(_))) {
+                            return Err(MalformedRecipe::BadReference {
+                                index,
+                                kind: "step",
+                                target,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cooklang::CooklangParser;
+
+    #[test]
+    fn catches_missing_items() {
+        let parser = CooklangParser::default();
+        let mut recipe = parser
+            .parse("Boil @water{1%l} for ~{5%min}.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        assert_eq!(validate(&recipe), Ok(()));
+
+        recipe.timers[0].quantity = None;
+        assert_eq!(
+            validate(&recipe),
+            Err(MalformedRecipe::EmptyTimer { index: 0 })
+        );
+
+        recipe.timers.clear();
+        assert!(matches!(
+            validate(&recipe),
+            Err(MalformedRecipe::MissingItem { kind: "timer", .. })
+        ));
+    }
+}
@@ -6,8 +6,10 @@ use cooklang::{
     convert::Converter,
     metadata::Metadata,
     model::{Item, Section, Step},
+    quantity::Quantity,
     ScaledRecipe,
 };
+use cooklang_to_human::NumberFormat;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, thiserror::Error)]
@@ -71,6 +73,11 @@ pub struct Options {
     pub heading: Headings,
     /// Text to write when an ingredient or cookware item is optional
     pub optional_marker: String,
+    /// Character between the whole and decimal parts of the numbers in
+    /// quantities
+    pub decimal_separator: char,
+    /// Character to group the thousands of numbers with 5 digits or more
+    pub thousands_separator: Option<char>,
 }
 
 impl Default for Options {
@@ -83,6 +90,8 @@ impl Default for Options {
             front_matter_name: FrontMatterName::default(),
             heading: Headings::default(),
             optional_marker: "(optional)".to_string(),
+            decimal_separator: '.',
+            thousands_separator: None,
         }
     }
 }
@@ -204,6 +213,33 @@ pub fn print_md_with_options(
     name: &str,
     opts: &Options,
     converter: &Converter,
+    writer: impl io::Write,
+) -> Result {
+    print_md_with_formatter(
+        recipe,
+        name,
+        opts,
+        converter,
+        &|q| {
+            NumberFormat {
+                decimal: opts.decimal_separator,
+                thousands: opts.thousands_separator,
+            }
+            .localize(&q.to_string())
+        },
+        writer,
+    )
+}
+
+/// Same as [`print_md_with_options`] but `quantity` writes the quantities
+///
+/// The separators in the [`Options`] are not used, `quantity` does it all.
+pub fn print_md_with_formatter(
+    recipe: &ScaledRecipe,
+    name: &str,
+    opts: &Options,
+    converter: &Converter,
+    quantity: &dyn Fn(&Quantity) -> String,
     mut writer: impl io::Write,
 ) -> Result {
     frontmatter(&mut writer, &recipe.metadata, name, opts)?;
@@ -239,9 +275,9 @@ pub fn print_md_with_options(
         }
     }
 
-    ingredients(&mut writer, recipe, converter, opts)?;
+    ingredients(&mut writer, recipe, converter, opts, quantity)?;
     cookware(&mut writer, recipe, opts)?;
-    sections(&mut writer, recipe, opts)?;
+    sections(&mut writer, recipe, opts, quantity)?;
 
     Ok(())
 }
@@ -275,6 +311,7 @@ fn ingredients(
     recipe: &ScaledRecipe,
     converter: &Converter,
     opts: &Options,
+    fmt: &dyn Fn(&Quantity) -> String,
 ) -> Result {
     if recipe.ingredients.is_empty() {
         return Ok(());
@@ -291,10 +328,16 @@ fn ingredients(
 
         write!(w, "- ")?;
         if !entry.quantity.is_empty() {
+            let quantity = entry
+                .quantity
+                .iter()
+                .map(fmt)
+                .collect::<Vec<_>>()
+                .join(", ");
             if opts.italic_amounts {
-                write!(w, "*{}* ", entry.quantity)?;
+                write!(w, "*{quantity}* ")?;
             } else {
-                write!(w, "{} ", entry.quantity)?;
+                write!(w, "{quantity} ")?;
             }
         }
 
@@ -346,10 +389,15 @@ fn cookware(w: &mut impl io::Write, recipe: &ScaledRecipe, opts: &Options) -> Re
     Ok(())
 }
 
-fn sections(w: &mut impl io::Write, recipe: &ScaledRecipe, opts: &Options) -> Result<()> {
+fn sections(
+    w: &mut impl io::Write,
+    recipe: &ScaledRecipe,
+    opts: &Options,
+    fmt: &dyn Fn(&Quantity) -> String,
+) -> Result<()> {
     writeln!(w, "## {}\n", opts.heading.steps)?;
     for (idx, section) in recipe.sections.iter().enumerate() {
-        w_section(w, section, recipe, idx + 1, opts)?;
+        w_section(w, section, recipe, idx + 1, opts, fmt)?;
     }
     Ok(())
 }
@@ -360,6 +408,7 @@ fn w_section(
     recipe: &ScaledRecipe,
     num: usize,
     opts: &Options,
+    fmt: &dyn Fn(&Quantity) -> String,
 ) -> Result {
     if section.name.is_some() || recipe.sections.len() > 1 {
         if let Some(name) = &section.name {
@@ -371,7 +420,7 @@ fn w_section(
     }
     for content in &section.content {
         match content {
-            cooklang::Content::Step(step) => w_step(w, step, recipe, opts)?,
+            cooklang::Content::Step(step) => w_step(w, step, recipe, opts, fmt)?,
             cooklang::Content::Text(text) => print_wrapped(w, text)?,
         };
         writeln!(w)?;
@@ -379,7 +428,13 @@ fn w_section(
     Ok(())
 }
 
-fn w_step(w: &mut impl io::Write, step: &Step, recipe: &ScaledRecipe, opts: &Options) -> Result {
+fn w_step(
+    w: &mut impl io::Write,
+    step: &Step,
+    recipe: &ScaledRecipe,
+    opts: &Options,
+    fmt: &dyn Fn(&Quantity) -> String,
+) -> Result {
     let mut step_str = step.number.to_string();
     if opts.escape_step_numbers {
         step_str.push_str("\\. ")
@@ -404,11 +459,11 @@ fn w_step(w: &mut impl io::Write, step: &Step, recipe: &ScaledRecipe, opts: &Opt
                     write!(&mut step_str, "({name})").unwrap();
                 }
                 if let Some(quantity) = &t.quantity {
-                    write!(&mut step_str, "{}", quantity).unwrap();
+                    step_str.push_str(&fmt(quantity));
                 }
             }
             &Item::InlineQuantity { index } => {
-                let q = &recipe.inline_quantities[index];
+                let q = fmt(&recipe.inline_quantities[index]);
                 if opts.italic_amounts {
                     write!(&mut step_str, "*{q}*").unwrap();
                 } else {
@@ -0,0 +1,319 @@
+//! Adjustments for high altitude and other pan sizes
+//!
+//! The altitude corrections are the usual ones for baking above 900 m
+//! (3000 ft): less leavening and sugar, more liquid and flour, a hotter oven
+//! and shorter baking times. A different pan scales the recipe by the ratio of
+//! the pan areas.
+
+use std::collections::HashMap;
+
+use cooklang::{
+    convert::PhysicalQuantity, model::Item, quantity::Number, Content, Converter, Quantity,
+    ScaledRecipe, Value,
+};
+use cooklang_to_human::ScaleMark;
+use serde::Serialize;
+
+use crate::scaling::{scale_quantity, ScalingStrategy};
+
+/// A value changed by an adjustment
+#[derive(Debug, Serialize)]
+pub struct Change {
+    pub what: String,
+    pub from: String,
+    pub to: String,
+    pub reason: &'static str,
+}
+
+/// An altitude in meters, written like `2000m`, `6500ft` or just meters
+#[derive(Debug, Clone, Copy)]
+pub struct Altitude(pub f64);
+
+impl std::str::FromStr for Altitude {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, factor) = if let Some(n) = s.strip_suffix("ft") {
+            (n, 0.3048)
+        } else {
+            (s.strip_suffix('m').unwrap_or(s), 1.0)
+        };
+        let value = number
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid altitude '{s}', expected like 2000m or 6500ft"))?;
+        if !value.is_finite() || value < 0.0 {
+            return Err("the altitude can't be negative".into());
+        }
+        Ok(Self(value * factor))
+    }
+}
+
+/// The area of a pan, in cm²
+///
+/// A single length is a round pan diameter, `23cm`, and two are a
+/// rectangular pan, `20x30cm`. Lengths are in `cm` or `in`.
+#[derive(Debug, Clone, Copy)]
+pub struct Pan(pub f64);
+
+impl std::str::FromStr for Pan {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid pan '{s}', expected like 23cm, 9in or 20x30cm");
+        let s = s.trim();
+        let (dims, factor) = if let Some(d) = s.strip_suffix("cm") {
+            (d, 1.0)
+        } else if let Some(d) = s.strip_suffix("in").or_else(|| s.strip_suffix('"')) {
+            (d, 2.54)
+        } else {
+            return Err(err());
+        };
+        let lengths = dims
+            .split(['x', '×'])
+            .map(|d| d.trim().parse::<f64>().map(|d| d * factor))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| err())?;
+        let area = match lengths.as_slice() {
+            [d] => std::f64::consts::PI * (d / 2.0).powi(2),
+            [w, h] => w * h,
+            _ => return Err(err()),
+        };
+        if !(area.is_finite() && area > 0.0) {
+            return Err(err());
+        }
+        Ok(Self(area))
+    }
+}
+
+/// A change of pan, `FROM->TO`
+#[derive(Debug, Clone, Copy)]
+pub struct PanChange {
+    pub from: Pan,
+    pub to: Pan,
+}
+
+impl PanChange {
+    pub fn factor(&self) -> f64 {
+        self.to.0 / self.from.0
+    }
+}
+
+impl std::str::FromStr for PanChange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once("->")
+            .ok_or("expected FROM->TO, like 23cm->30cm")?;
+        Ok(Self {
+            from: from.parse()?,
+            to: to.parse()?,
+        })
+    }
+}
+
+/// Corrections for an altitude
+struct Corrections {
+    leavening: f64,
+    sugar: f64,
+    liquid: f64,
+    flour: f64,
+    time: f64,
+}
+
+impl Corrections {
+    fn for_altitude(meters: f64) -> Option<Self> {
+        let feet = meters / 0.3048;
+        if feet < 3000.0 {
+            return None;
+        }
+        // in tablespoons per cup, 16 in a cup
+        let (leavening, sugar, liquid) = if feet < 5000.0 {
+            (0.875, 1.0, 1.5)
+        } else if feet < 7000.0 {
+            (0.8, 1.5, 3.0)
+        } else {
+            (0.75, 2.0, 3.5)
+        };
+        let flour = if feet >= 3500.0 {
+            1.0 + ((feet - 3500.0) / 1500.0).floor()
+        } else {
+            0.0
+        };
+        Some(Self {
+            leavening,
+            sugar: 1.0 - sugar / 16.0,
+            liquid: 1.0 + liquid / 16.0,
+            flour: 1.0 + flour / 16.0,
+            time: 0.8,
+        })
+    }
+}
+
+const LEAVENING: &[&str] = &["baking powder", "baking soda", "bicarbonate", "yeast"];
+const SUGAR: &[&str] = &["sugar", "honey"];
+const LIQUID: &[&str] = &[
+    "water",
+    "milk",
+    "buttermilk",
+    "juice",
+    "coffee",
+    "wine",
+    "beer",
+    "stock",
+    "broth",
+];
+const FLOUR: &[&str] = &["flour"];
+
+/// Applies the altitude corrections to a recipe
+///
+/// Returns what changed, nothing if the altitude is too low to need it.
+/// Changed ingredients get [`ScaleMark::Adjusted`].
+pub fn altitude(
+    recipe: &mut ScaledRecipe,
+    altitude: Altitude,
+    converter: &Converter,
+    marks: &mut HashMap<usize, ScaleMark>,
+) -> Vec<Change> {
+    let Some(c) = Corrections::for_altitude(altitude.0) else {
+        return Vec::new();
+    };
+    let mut changes = Vec::new();
+
+    for (index, igr) in recipe.ingredients.iter_mut().enumerate() {
+        if igr.relation.references_to().is_some() {
+            continue;
+        }
+        let name = igr.name.to_lowercase();
+        let is = |list: &[&str]| list.iter().any(|k| name.contains(k));
+        let (factor, reason) = if is(LEAVENING) {
+            (c.leavening, "less leavening")
+        } else if is(SUGAR) {
+            (c.sugar, "less sugar")
+        } else if is(FLOUR) {
+            (c.flour, "more flour")
+        } else if is(LIQUID) {
+            (c.liquid, "more liquid")
+        } else {
+            continue;
+        };
+        if factor == 1.0 {
+            continue;
+        }
+        let Some(q) = &igr.quantity else { continue };
+        let Some(new) = scale_quantity(q, ScalingStrategy::Linear, factor) else {
+            continue;
+        };
+        changes.push(Change {
+            what: igr.display_name().to_string(),
+            from: q.to_string(),
+            to: new.to_string(),
+            reason,
+        });
+        igr.quantity = Some(new);
+        marks.insert(index, ScaleMark::Adjusted);
+    }
+
+    // hotter oven
+    for q in &mut recipe.inline_quantities {
+        let is_temp = q
+            .unit_info(converter)
+            .is_some_and(|u| u.physical_quantity == PhysicalQuantity::Temperature);
+        let Value::Number(n) = q.value() else {
+            continue;
+        };
+        if !is_temp {
+            continue;
+        }
+        let unit = q.unit().unwrap_or_default().to_string();
+        let increase = if unit.to_lowercase().contains('f') {
+            20.0
+        } else {
+            10.0
+        };
+        let new = Quantity::new(
+            Value::Number(Number::Regular(n.value() + increase)),
+            Some(unit.clone()),
+        );
+        changes.push(Change {
+            what: "temperature".into(),
+            from: q.to_string(),
+            to: new.to_string(),
+            reason: "hotter oven",
+        });
+        *q = new;
+    }
+
+    // shorter baking, the timers in steps that bake
+    for index in baking_timers(recipe) {
+        let timer = &mut recipe.timers[index];
+        let Some(q) = &timer.quantity else { continue };
+        let Some(new) = scale_quantity(q, ScalingStrategy::Linear, c.time) else {
+            continue;
+        };
+        changes.push(Change {
+            what: timer.name.clone().unwrap_or_else(|| "timer".into()),
+            from: q.to_string(),
+            to: new.to_string(),
+            reason: "shorter baking",
+        });
+        timer.quantity = Some(new);
+    }
+
+    changes
+}
+
+fn baking_timers(recipe: &ScaledRecipe) -> Vec<usize> {
+    let mut timers = Vec::new();
+    for section in &recipe.sections {
+        for content in &section.content {
+            let Content::Step(step) = content else {
+                continue;
+            };
+            let bakes = step.items.iter().any(|item| match item {
+                Item::Text { value } => {
+                    let v = value.to_lowercase();
+                    v.contains("bake") || v.contains("oven")
+                }
+                &Item::Cookware { index } => {
+                    recipe.cookware[index].name.eq_ignore_ascii_case("oven")
+                }
+                _ => false,
+            });
+            if !bakes {
+                continue;
+            }
+            timers.extend(step.items.iter().filter_map(|item| match item {
+                &Item::Timer { index } => Some(index),
+                _ => None,
+            }));
+        }
+    }
+    timers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pans() {
+        let change: PanChange = "20cm->40cm".parse().unwrap();
+        assert!((change.factor() - 4.0).abs() < 1e-9);
+        let change: PanChange = "10x10cm->20x10cm".parse().unwrap();
+        assert!((change.factor() - 2.0).abs() < 1e-9);
+        assert!("9in".parse::<Pan>().is_ok());
+        assert!("23".parse::<Pan>().is_err());
+        assert!("23cm".parse::<PanChange>().is_err());
+    }
+
+    #[test]
+    fn altitude_levels() {
+        assert!(Corrections::for_altitude(500.0).is_none());
+        let c = Corrections::for_altitude("7500ft".parse::<Altitude>().unwrap().0).unwrap();
+        assert_eq!(c.leavening, 0.75);
+        assert_eq!(c.flour, 1.0 + 3.0 / 16.0);
+    }
+}
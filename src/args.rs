@@ -1,12 +1,17 @@
 use camino::Utf8PathBuf;
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use cooklang::Extensions;
 
 use crate::cmd::{
-    collection, config, convert, edit, generate_completions, list, new, recipe, shopping_list,
-    units,
+    adjust, backup, bench, bulk, cache, check, collection, config, convert, cook, cost, dedupe,
+    doctor, edit, encrypt, fork, generate_completions, graph, import, init, list, log, menu,
+    migrate, new, nutrition, pantry, random, receive, recipe, refactor, restore, search, seasonal,
+    seasonal_ingredients, self_test, share, shopping_list, stats, substitutions, sync, tokens,
+    units, zip,
 };
 
+#[cfg(feature = "browse")]
+use crate::cmd::browse;
 #[cfg(feature = "serve")]
 use crate::cmd::serve;
 
@@ -29,7 +34,7 @@ pub struct CliArgs {
 pub enum Command {
     /// Read a recipe
     #[command(alias = "read", visible_alias = "r")]
-    Recipe(recipe::ReadArgs),
+    Recipe(Box<recipe::ReadArgs>),
     /// List all the recipes
     #[command(visible_alias = "ls")]
     List(list::ListArgs),
@@ -46,14 +51,104 @@ pub enum Command {
     Convert(convert::ConvertArgs),
     /// See loaded configuration
     Config(config::ConfigArgs),
+    /// Create a collection with a config, an example recipe and an aisle file
+    ///
+    /// Asks where to create it, the locale, whether to track it with git and
+    /// whether to make it the default collection.
+    Init(init::InitArgs),
     /// Manage the recipe collection
     Collection(collection::CollectionArgs),
     /// Generate shell completions
     GenerateCompletions(generate_completions::GenerateCompletionsArgs),
     /// Create a new recipe
     New(new::NewArgs),
+    /// Add a recipe from a file, a web page or a photo
+    ///
+    /// The recipe is converted to cooklang and opened in the editor to review
+    /// it. Photos and plain text are converted with guesses.
+    Import(import::ImportArgs),
     /// Edit an existing recipe
     Edit(edit::EditArgs),
+    /// Run an operation over many recipes of the collection
+    Bulk(bulk::BulkArgs),
+    /// Refactor the cooklang source of the collection
+    Refactor(refactor::RefactorArgs),
+    /// Find recipes that are possibly duplicated
+    Dedupe(dedupe::DedupeArgs),
+    /// Show statistics of the collection
+    Stats(stats::StatsArgs),
+    /// Check recipes and report the diagnostics
+    #[command(alias = "lint")]
+    Check(check::CheckArgs),
+    /// Look for problems in the setup and tell how to fix them
+    ///
+    /// Checks the config, the permissions of the collection, the remote
+    /// index, the names of the images, the references to other recipes, the
+    /// port of `chef serve` and the programs chef runs.
+    Doctor(doctor::DoctorArgs),
+    /// Export the highlighting tokens of a recipe as JSON
+    Tokens(tokens::TokensArgs),
+    /// Cook several recipes at the same time
+    Cook(cook::CookArgs),
+    /// See what you have cooked, from the cook log
+    Log(log::LogArgs),
+    #[cfg(feature = "browse")]
+    /// Browse the collection in the terminal
+    Browse(browse::BrowseArgs),
+    /// Manage the cache of rendered recipes
+    Cache(cache::CacheArgs),
+    /// Show the nutrition of a recipe using the configured plugins
+    Nutrition(nutrition::NutritionArgs),
+    /// Compute the cost of a recipe from the ingredient prices
+    Cost(cost::CostArgs),
+    /// Manage the ingredients at home
+    Pantry(pantry::PantryArgs),
+    /// Create a new recipe from another one with some changes
+    Fork(fork::ForkArgs),
+    /// Show the known substitutes of an ingredient
+    #[command(alias = "subs")]
+    Substitutions(substitutions::SubstitutionsArgs),
+    /// Search recipes by name, tags, ingredients, diet or allergens
+    Search(search::SearchArgs),
+    /// Pick a random recipe, for when you don't know what to cook
+    Random(random::RandomArgs),
+    /// Recipes in season this month and cooked around this day in past years
+    Seasonal(seasonal::SeasonalArgs),
+    /// Produce in season this month, or the season of some ingredients
+    SeasonalIngredients(seasonal_ingredients::SeasonalIngredientsArgs),
+    /// Adjust a recipe for high altitude or another pan size
+    Adjust(adjust::AdjustArgs),
+    /// Compose recipes into a meal with a menu file
+    Menu(menu::MenuArgs),
+    /// Draw the dependency graph of a recipe
+    ///
+    /// Steps, grouped by section, linked by intermediate references and to
+    /// the recipes they use. Render the DOT output with Graphviz or paste the
+    /// Mermaid one in a Markdown document.
+    Graph(graph::GraphArgs),
+    /// Convert recipes between cooklang syntax versions
+    Migrate(migrate::MigrateArgs),
+    /// Fetch remote collections from git or HTTP
+    Sync(sync::SyncArgs),
+    /// Encrypt private recipes, or decrypt them back
+    Encrypt(encrypt::EncryptArgs),
+    /// Make a link to send a recipe to someone
+    Share(share::ShareArgs),
+    /// Save a recipe from a `share` link
+    Receive(receive::ReceiveArgs),
+    /// Save the collection and config to a single file
+    Backup(backup::BackupArgs),
+    /// Restore a collection from `backup`
+    Restore(restore::RestoreArgs),
+    /// Save some recipes with their images in a ZIP file
+    Zip(zip::ZipArgs),
+    /// Run the cooklang spec tests and check the extensions the recipes use
+    ///
+    /// Useful before sharing a collection with other cooklang tools.
+    SelfTest(self_test::SelfTestArgs),
+    /// Time parsing, rendering, scaling and shopping lists over the collection
+    #[command(hide = true)]
+    Bench(bench::BenchArgs),
 }
 
 #[derive(Debug, Args)]
@@ -126,6 +221,20 @@ pub struct GlobalArgs {
     #[command(flatten)]
     pub color: colorchoice_clap::Color,
 
+    /// When to make links clickable in the terminal
+    ///
+    /// `auto` uses them in terminals known to support OSC 8 hyperlinks.
+    /// `FORCE_HYPERLINK=1` or `FORCE_HYPERLINK=0` overrides the detection.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHEN",
+        hide_short_help = true,
+        global = true,
+        default_value_t = HyperlinksChoice::Auto
+    )]
+    pub hyperlinks: HyperlinksChoice,
+
     /// Change the base path
     ///
     /// This path is used to load configuration files, search for images and
@@ -133,6 +242,10 @@ pub struct GlobalArgs {
     #[arg(long, value_name = "PATH", value_hint = clap::ValueHint::DirPath, global = true)]
     pub path: Option<Utf8PathBuf>,
 
+    /// Use a remote collection, synced with `chef sync`
+    #[arg(long, value_name = "NAME", global = true, conflicts_with = "path")]
+    pub remote: Option<String>,
+
     /// Skip checking if referenced recipes exist
     #[arg(long, hide_short_help = true, global = true)]
     pub no_recipe_ref_check: bool,
@@ -140,13 +253,73 @@ pub struct GlobalArgs {
     /// Override recipe indexing depth
     ///
     /// This is used to search for referenced recipes.
-    #[arg(long, hide_short_help = true, global = true, default_value_t = 10)]
-    pub max_depth: usize,
-
     #[arg(long, hide_short_help = true, global = true)]
+    pub max_depth: Option<usize>,
+
+    /// Log everything, with the spans and how long they take
+    #[arg(long, alias = "trace", hide_short_help = true, global = true)]
     pub debug_trace: bool,
 
+    /// Level of the logs written to stderr
+    ///
+    /// From `debug` the spans, like the requests of the web server or the
+    /// commands, are logged when they end with how long they took.
+    #[arg(
+        long,
+        value_enum,
+        hide_short_help = true,
+        global = true,
+        default_value_t = LogLevel::Info
+    )]
+    pub log_level: LogLevel,
+
+    /// Format of the logs
+    #[arg(
+        long,
+        value_enum,
+        hide_short_help = true,
+        global = true,
+        default_value_t = LogFormat::Human
+    )]
+    pub log_format: LogFormat,
+
     /// Use a specific configuration fileignoring the expected path
     #[arg(long = "config", global = true, hide_short_help = true)]
     pub config_file: Option<Utf8PathBuf>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HyperlinksChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for tracing::Level {
+    fn from(value: LogLevel) -> Self {
+        match value {
+            LogLevel::Error => Self::ERROR,
+            LogLevel::Warn => Self::WARN,
+            LogLevel::Info => Self::INFO,
+            LogLevel::Debug => Self::DEBUG,
+            LogLevel::Trace => Self::TRACE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LogFormat {
+    /// Compact lines for people
+    Human,
+    /// A JSON object per line, with the spans
+    Json,
+}
@@ -0,0 +1,299 @@
+//! Backups of a collection
+//!
+//! A backup is a zstd compressed tar with a `manifest.json` first and then the
+//! files. Collection files are under `collection/` and the global config files
+//! under `global/`. The manifest has the size and SHA-256 of every file, so a
+//! backup is checked completely before restoring anything.
+
+use std::{
+    fs::File,
+    io::{Read, Write},
+};
+
+use anyhow::{bail, Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{global_file_path, CHEF_CONFIG_FILE, DEFAULT_CONFIG_FILE},
+    pantry::rfc3339,
+    remote::is_safe_path,
+    util::hex,
+    COOK_DIR,
+};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// Version of the backup format, increased when old chef can't restore it
+pub const FORMAT_VERSION: u32 = 1;
+pub const COLLECTION_PREFIX: &str = "collection/";
+pub const GLOBAL_PREFIX: &str = "global/";
+/// Global config files that go in a backup
+pub const GLOBAL_FILES: &[&str] = &[CHEF_CONFIG_FILE, DEFAULT_CONFIG_FILE];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// chef version that made the backup
+    pub chef_version: String,
+    pub created: String,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path in the archive
+    pub path: String,
+    pub size: u64,
+    /// Hex SHA-256 of the content
+    pub sha256: String,
+}
+
+/// A file to back up: path in the archive and where it is now
+pub struct Source {
+    pub path: String,
+    pub local: Utf8PathBuf,
+}
+
+/// Files of a collection, and the global config if `global`
+///
+/// Everything in the collection dir goes in, except hidden files and dirs
+/// other than `.cooklang`, so `.git` is left out.
+pub fn sources(base_path: &Utf8Path, global: bool) -> Result<Vec<Source>> {
+    let mut sources = Vec::new();
+    walk(base_path, base_path, &mut sources)?;
+    if global {
+        for name in GLOBAL_FILES {
+            let local = global_file_path(name)?;
+            if local.is_file() {
+                sources.push(Source {
+                    path: format!("{GLOBAL_PREFIX}{name}"),
+                    local,
+                });
+            }
+        }
+    }
+    sources.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(sources)
+}
+
+fn walk(base_path: &Utf8Path, dir: &Utf8Path, sources: &mut Vec<Source>) -> Result<()> {
+    for entry in dir
+        .read_dir_utf8()
+        .with_context(|| format!("Failed to read {dir}"))?
+    {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name.starts_with('.') && name != COOK_DIR {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(base_path, entry.path(), sources)?;
+        } else if file_type.is_file() {
+            let relative = entry.path().strip_prefix(base_path).unwrap();
+            sources.push(Source {
+                path: format!(
+                    "{COLLECTION_PREFIX}{}",
+                    relative.as_str().replace('\\', "/")
+                ),
+                local: entry.path().to_path_buf(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Writes a backup, returns its manifest
+pub fn create(sources: &[Source], out: &Utf8Path) -> Result<Manifest> {
+    let mut files = Vec::with_capacity(sources.len());
+    for source in sources {
+        let content = std::fs::read(&source.local)
+            .with_context(|| format!("Failed to read {}", source.local))?;
+        files.push(ManifestFile {
+            path: source.path.clone(),
+            size: content.len() as u64,
+            sha256: hex(digest(&SHA256, &content).as_ref()),
+        });
+    }
+    let manifest = Manifest {
+        version: FORMAT_VERSION,
+        chef_version: env!("CARGO_PKG_VERSION").to_string(),
+        created: rfc3339(std::time::SystemTime::now()),
+        files,
+    };
+
+    let file = File::create(out).with_context(|| format!("Failed to create {out}"))?;
+    let encoder = zstd::Encoder::new(file, 0)?;
+    let mut builder = tar::Builder::new(encoder);
+    append(
+        &mut builder,
+        MANIFEST_FILE,
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    for (source, entry) in sources.iter().zip(&manifest.files) {
+        let content = std::fs::read(&source.local)?;
+        if hex(digest(&SHA256, &content).as_ref()) != entry.sha256 {
+            bail!("{} changed while making the backup", source.local);
+        }
+        append(&mut builder, &source.path, &content)?;
+    }
+    builder.into_inner()?.finish()?.flush()?;
+    Ok(manifest)
+}
+
+fn append(builder: &mut tar::Builder<impl Write>, path: &str, content: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    );
+    builder.append_data(&mut header, path, content)?;
+    Ok(())
+}
+
+/// Reads a backup, calling `f` with every file once it's checked
+///
+/// Files are checked one by one as they are read, so `f` should not write
+/// anything for real: [`verify`] the whole backup first.
+fn read_files(
+    reader: impl Read,
+    mut f: impl FnMut(&ManifestFile, &[u8]) -> Result<()>,
+) -> Result<Manifest> {
+    let mut archive = tar::Archive::new(zstd::Decoder::new(reader)?);
+    let mut entries = archive.entries()?;
+
+    let mut first = entries.next().context("Empty backup")??;
+    if first.path()?.to_str() != Some(MANIFEST_FILE) {
+        bail!("Not a chef backup, it has no manifest");
+    }
+    let mut buf = Vec::new();
+    first.read_to_end(&mut buf)?;
+    let manifest: Manifest = serde_json::from_slice(&buf).context("Invalid backup manifest")?;
+    if manifest.version > FORMAT_VERSION {
+        bail!(
+            "The backup was made by chef {} with a newer format, update chef to restore it",
+            manifest.chef_version
+        );
+    }
+
+    let mut expected = manifest.files.iter();
+    for entry in entries {
+        let mut entry = entry?;
+        let path = entry
+            .path()?
+            .to_str()
+            .context("Non UTF-8 path in backup")?
+            .to_string();
+        let Some(file) = expected.next().filter(|f| f.path == path) else {
+            bail!("Unexpected file in backup: '{path}'");
+        };
+        let relative = path
+            .strip_prefix(COLLECTION_PREFIX)
+            .or_else(|| path.strip_prefix(GLOBAL_PREFIX))
+            .filter(|p| is_safe_path(p));
+        if relative.is_none() {
+            bail!("Invalid path in backup: '{path}'");
+        }
+        buf.clear();
+        entry.read_to_end(&mut buf)?;
+        if buf.len() as u64 != file.size || hex(digest(&SHA256, &buf).as_ref()) != file.sha256 {
+            bail!("'{path}' is corrupted");
+        }
+        f(file, &buf)?;
+    }
+    if let Some(missing) = expected.next() {
+        bail!("'{}' is missing from the backup", missing.path);
+    }
+    Ok(manifest)
+}
+
+/// Checks the integrity of a backup
+pub fn verify(reader: impl Read) -> Result<Manifest> {
+    read_files(reader, |_, _| Ok(()))
+}
+
+/// Writes the files of a checked backup
+///
+/// Collection files go to `dest` and global files to the global config dir
+/// if `global`. Returns the written paths.
+pub fn extract(reader: impl Read, dest: &Utf8Path, global: bool) -> Result<Vec<Utf8PathBuf>> {
+    let mut written = Vec::new();
+    read_files(reader, |file, content| {
+        let Some(path) = local_path(&file.path, dest, global)? else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content).with_context(|| format!("Failed to write {path}"))?;
+        written.push(path);
+        Ok(())
+    })?;
+    Ok(written)
+}
+
+/// Where a file of a backup is restored, `None` if it's skipped
+pub fn local_path(path: &str, dest: &Utf8Path, global: bool) -> Result<Option<Utf8PathBuf>> {
+    if let Some(relative) = path.strip_prefix(COLLECTION_PREFIX) {
+        Ok(Some(dest.join(relative)))
+    } else if let Some(name) = path.strip_prefix(GLOBAL_PREFIX) {
+        if global {
+            global_file_path(name).map(Some)
+        } else {
+            Ok(None)
+        }
+    } else {
+        bail!("Invalid path in backup: '{path}'")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(manifest: &Manifest, files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(zstd::Encoder::new(Vec::new(), 0).unwrap());
+        append(
+            &mut builder,
+            MANIFEST_FILE,
+            &serde_json::to_vec(manifest).unwrap(),
+        )
+        .unwrap();
+        for (path, content) in files {
+            append(&mut builder, path, content).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn integrity() {
+        let content = b"Mix @flour{200%g}.";
+        let manifest = Manifest {
+            version: FORMAT_VERSION,
+            chef_version: "0.0.0".into(),
+            created: "2025-01-01T00:00:00Z".into(),
+            files: vec![ManifestFile {
+                path: "collection/Bread.cook".into(),
+                size: content.len() as u64,
+                sha256: hex(digest(&SHA256, content).as_ref()),
+            }],
+        };
+        let ok = archive(&manifest, &[("collection/Bread.cook", content)]);
+        assert!(verify(ok.as_slice()).is_ok());
+
+        let corrupted = archive(&manifest, &[("collection/Bread.cook", b"Mix @salt.")]);
+        assert!(verify(corrupted.as_slice()).is_err());
+        let missing = archive(&manifest, &[]);
+        assert!(verify(missing.as_slice()).is_err());
+        let extra = archive(
+            &manifest,
+            &[("collection/Bread.cook", content), ("collection/x", b"")],
+        );
+        assert!(verify(extra.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Cookware capacity
+//!
+//! Cookware can declare how much it holds in its note, like
+//! `#pot(capacity: 5 l)`, or in the `capacity` metadata:
+//!
+//! ```yaml
+//! capacity:
+//!   pot: 5 l
+//! ```
+//!
+//! The ingredients that go in the cookware are the ones in the steps from the
+//! first to the last that use it. Mass is counted as the same volume of
+//! water, so it is only an estimate.
+
+use std::{collections::HashSet, ops::RangeInclusive};
+
+use cooklang::{
+    model::{IngredientReferenceTarget, Item},
+    Content, Converter, ScaledRecipe, Value,
+};
+
+use crate::{
+    scaling::parse_amount,
+    util::{convert_value, fmt_number},
+};
+
+pub const CAPACITY_KEY: &str = "capacity";
+
+/// Cookware with more ingredients than it holds
+#[derive(Debug)]
+pub struct Overflow {
+    pub cookware: String,
+    /// Value and unit
+    pub capacity: (f64, String),
+    /// Estimated content in the unit of the capacity
+    pub content: f64,
+}
+
+impl Overflow {
+    /// Batches needed for everything to fit
+    pub fn batches(&self) -> u32 {
+        (self.content / self.capacity.0).ceil() as u32
+    }
+
+    pub fn hint(&self) -> String {
+        format!(
+            "split it in {} batches or use a bigger {}",
+            self.batches(),
+            self.cookware
+        )
+    }
+}
+
+impl std::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (value, unit) = &self.capacity;
+        write!(
+            f,
+            "'{}' holds {} {unit} but about {} {unit} go in it",
+            self.cookware,
+            fmt_number(*value),
+            fmt_number(self.content)
+        )
+    }
+}
+
+/// Capacity of a cookware from its note, like `capacity: 5 l`
+fn from_note(note: &str) -> Option<(f64, String)> {
+    note.split([',', ';']).find_map(|part| {
+        let (key, value) = part.split_once([':', '='])?;
+        if key.trim() != CAPACITY_KEY {
+            return None;
+        }
+        let (value, unit) = parse_amount(value).ok()?;
+        Some((value, unit?))
+    })
+}
+
+fn declared_capacity(recipe: &ScaledRecipe, name: &str) -> Option<(f64, String)> {
+    let from_notes = recipe
+        .cookware
+        .iter()
+        .filter(|c| c.name.eq_ignore_ascii_case(name))
+        .find_map(|c| c.note.as_deref().and_then(from_note));
+    from_notes.or_else(|| {
+        let map = recipe.metadata.map.get(CAPACITY_KEY)?.as_mapping()?;
+        let value = map.iter().find_map(|(k, v)| {
+            k.as_str()
+                .filter(|k| k.eq_ignore_ascii_case(name))
+                .and(v.as_str())
+        })?;
+        let (value, unit) = parse_amount(value).ok()?;
+        Some((value, unit?))
+    })
+}
+
+/// Finds the cookware that gets more than its capacity
+pub fn check(recipe: &ScaledRecipe, converter: &Converter) -> Vec<Overflow> {
+    let mut names = recipe
+        .cookware
+        .iter()
+        .map(|c| c.name.to_lowercase())
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+
+    let mut overflows = Vec::new();
+    for name in names {
+        let Some((value, unit)) = declared_capacity(recipe, &name) else {
+            continue;
+        };
+        let Some(capacity_ml) = to_ml(value, &unit, converter) else {
+            tracing::warn!("Unknown capacity unit for '{name}': {unit}");
+            continue;
+        };
+        let content_ml = recipe
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(index, _)| section_content(recipe, index, &name, converter))
+            .fold(0.0, f64::max);
+        if content_ml > capacity_ml * 1.005 {
+            overflows.push(Overflow {
+                cookware: name,
+                content: content_ml / capacity_ml * value,
+                capacity: (value, unit),
+            });
+        }
+    }
+    overflows
+}
+
+/// Content indexes of a section from the first to the last step that use a
+/// cookware
+fn cookware_span(
+    recipe: &ScaledRecipe,
+    section_index: usize,
+    cookware: &str,
+) -> Option<RangeInclusive<usize>> {
+    let section = &recipe.sections[section_index];
+    let uses_cookware = |content: &Content| match content {
+        Content::Step(step) => step.items.iter().any(|item| {
+            matches!(item, &Item::Cookware { index }
+                if recipe.cookware[index].name.eq_ignore_ascii_case(cookware))
+        }),
+        Content::Text(_) => false,
+    };
+    let first = section.content.iter().position(uses_cookware)?;
+    let last = section.content.iter().rposition(uses_cookware)?;
+    Some(first..=last)
+}
+
+/// Marks the steps to repeat for each batch
+///
+/// These are the steps that use a cookware with a capacity, or all of them if
+/// there is none.
+pub fn mark_batch_steps(recipe: &mut ScaledRecipe, batches: u32) {
+    let limited = recipe
+        .cookware
+        .iter()
+        .map(|c| c.name.to_lowercase())
+        .filter(|name| declared_capacity(recipe, name).is_some())
+        .collect::<HashSet<_>>();
+    let spans = (0..recipe.sections.len())
+        .map(|index| {
+            limited
+                .iter()
+                .filter_map(|name| cookware_span(recipe, index, name))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mark = format!("[×{batches}] ");
+    for (section, spans) in recipe.sections.iter_mut().zip(spans) {
+        for (index, content) in section.content.iter_mut().enumerate() {
+            let Content::Step(step) = content else {
+                continue;
+            };
+            if limited.is_empty() || spans.iter().any(|s| s.contains(&index)) {
+                step.items.insert(
+                    0,
+                    Item::Text {
+                        value: mark.clone(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Millilitres that go in the cookware in a section
+fn section_content(
+    recipe: &ScaledRecipe,
+    section_index: usize,
+    cookware: &str,
+    converter: &Converter,
+) -> f64 {
+    let section = &recipe.sections[section_index];
+    let Some(span) = cookware_span(recipe, section_index, cookware) else {
+        return 0.0;
+    };
+
+    let mut seen = HashSet::new();
+    let mut total = 0.0;
+    for content in &section.content[span.clone()] {
+        let Content::Step(step) = content else {
+            continue;
+        };
+        for item in &step.items {
+            let &Item::Ingredient { index } = item else {
+                continue;
+            };
+            if !seen.insert(index) {
+                continue;
+            }
+            let igr = &recipe.ingredients[index];
+            // already counted in the steps it comes from
+            match igr.relation.references_to() {
+                Some((i, IngredientReferenceTarget::Step)) if span.contains(&i) => continue,
+                Some((i, IngredientReferenceTarget::Section)) if i == section_index => continue,
+                _ => {}
+            }
+            let Some(q) = &igr.quantity else { continue };
+            let (Value::Number(n), Some(unit)) = (q.value(), q.unit()) else {
+                continue;
+            };
+            total += to_ml(n.value(), unit, converter).unwrap_or(0.0);
+        }
+    }
+    total
+}
+
+/// Volume in ml, mass is taken as water
+fn to_ml(value: f64, unit: &str, converter: &Converter) -> Option<f64> {
+    convert_value(value, unit, "ml", converter)
+        .or_else(|| convert_value(value, unit, "g", converter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_note() {
+        assert_eq!(from_note("capacity: 5 l"), Some((5.0, "l".to_string())));
+        assert_eq!(
+            from_note("large, capacity=2.5l"),
+            Some((2.5, "l".to_string()))
+        );
+        assert_eq!(from_note("capacity: 5"), None);
+        assert_eq!(from_note("cast iron"), None);
+    }
+}
@@ -0,0 +1,144 @@
+//! Ingredient checklist for the mise en place
+//!
+//! `chef recipe --checklist` shows the ingredients of a recipe as a list to
+//! check with space. In a collection the checked ones are saved to
+//! `.cooklang/checklist.toml`, by the path of the recipe without extension,
+//! so running it again goes on where it was until all of them are checked:
+//!
+//! ```toml
+//! "Mains/Paella" = ["rice", "saffron"]
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use cooklang::{Converter, ScaledRecipe};
+use serde::{Deserialize, Serialize};
+
+use crate::COOK_DIR;
+
+pub const CHECKLIST_FILE: &str = "checklist.toml";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Checklists {
+    /// Checked ingredients of each recipe, by name
+    pub recipes: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Checklists {
+    pub fn path(base_path: &Utf8Path) -> Utf8PathBuf {
+        base_path.join(COOK_DIR).join(CHECKLIST_FILE)
+    }
+
+    /// Loads the checklists, empty if there are none
+    pub fn load(base_path: &Utf8Path) -> Result<Self> {
+        let path = Self::path(base_path);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read checklists: {path}"))?;
+        toml::from_str(&text).with_context(|| format!("Bad checklists: {path}"))
+    }
+
+    pub fn save(&self, base_path: &Utf8Path) -> Result<()> {
+        let path = Self::path(base_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string_pretty(self)?;
+        std::fs::write(&path, text).with_context(|| format!("Cannot write checklists: {path}"))
+    }
+
+    /// Sets the checked ingredients of a recipe out of `total`
+    ///
+    /// When all of them are checked the checklist is done and forgotten.
+    /// Returns if it's done.
+    pub fn update(&mut self, recipe: String, checked: BTreeSet<String>, total: usize) -> bool {
+        let done = checked.len() >= total;
+        if done || checked.is_empty() {
+            self.recipes.remove(&recipe);
+        } else {
+            self.recipes.insert(recipe, checked);
+        }
+        done
+    }
+}
+
+/// An ingredient in the checklist
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub name: String,
+    pub quantity: Option<String>,
+}
+
+impl std::fmt::Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.quantity {
+            Some(q) => write!(f, "{}: {q}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// The listed ingredients of a recipe, like in the ingredient table
+pub fn items(recipe: &ScaledRecipe, converter: &Converter) -> Vec<Item> {
+    recipe
+        .group_ingredients(converter)
+        .into_iter()
+        .filter(|e| e.ingredient.modifiers().should_be_listed())
+        .map(|e| {
+            let quantity = e
+                .quantity
+                .iter()
+                .map(|q| q.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Item {
+                name: e.ingredient.display_name().to_string(),
+                quantity: (!quantity.is_empty()).then_some(quantity),
+            }
+        })
+        .collect()
+}
+
+/// Asks to check the ingredients, `None` if the user cancels
+pub fn prompt(items: Vec<Item>, checked: &BTreeSet<String>) -> Result<Option<BTreeSet<String>>> {
+    let defaults = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| checked.contains(&item.name).then_some(i))
+        .collect::<Vec<_>>();
+    let res = inquire::MultiSelect::new("Ingredients ready", items)
+        .with_default(&defaults)
+        .with_page_size(20)
+        .with_help_message("space to check, enter to save, esc to leave unchanged")
+        .prompt();
+    match res {
+        Ok(selected) => Ok(Some(selected.into_iter().map(|i| i.name).collect())),
+        Err(
+            inquire::InquireError::OperationCanceled | inquire::InquireError::OperationInterrupted,
+        ) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update() {
+        let set = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<BTreeSet<_>>();
+        let mut lists = Checklists::default();
+        assert!(!lists.update("Paella".into(), set(&["rice"]), 3));
+        assert_eq!(lists.recipes["Paella"], set(&["rice"]));
+        assert!(!lists.update("Paella".into(), set(&[]), 3));
+        assert!(!lists.recipes.contains_key("Paella"));
+        lists.update("Paella".into(), set(&["rice"]), 3);
+        assert!(lists.update("Paella".into(), set(&["rice", "saffron", "water"]), 3));
+        assert!(lists.recipes.is_empty());
+    }
+}
@@ -1,12 +1,48 @@
+pub mod adjust;
+pub mod backup;
+pub mod bench;
+#[cfg(feature = "browse")]
+pub mod browse;
+pub mod bulk;
+pub mod cache;
+pub mod check;
 pub mod collection;
 pub mod config;
 pub mod convert;
+pub mod cook;
+pub mod cost;
+pub mod dedupe;
+pub mod doctor;
 pub mod edit;
+pub mod encrypt;
+pub mod fork;
 pub mod generate_completions;
+pub mod graph;
+pub mod import;
+pub mod init;
 pub mod list;
+pub mod log;
+pub mod menu;
+pub mod migrate;
 pub mod new;
+pub mod nutrition;
+pub mod pantry;
+pub mod random;
+pub mod receive;
 pub mod recipe;
+pub mod refactor;
+pub mod restore;
+pub mod search;
+pub mod seasonal;
+pub mod seasonal_ingredients;
+pub mod self_test;
 #[cfg(feature = "serve")]
 pub mod serve;
+pub mod share;
 pub mod shopping_list;
+pub mod stats;
+pub mod substitutions;
+pub mod sync;
+pub mod tokens;
 pub mod units;
+pub mod zip;
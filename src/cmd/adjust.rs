@@ -0,0 +1,157 @@
+use std::io::Write;
+
+use anstream::{print, println};
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use cooklang::{Quantity, ScaledRecipe};
+use cooklang_render::{RecipeRenderer, RenderInput};
+use cooklang_to_human::ScaleMark;
+use serde::Serialize;
+use yansi::Paint;
+
+use crate::{
+    adjust::{self, Altitude, Change, PanChange},
+    locale::number_format,
+    scaling::{base_quantities, scale_by, scale_recipe},
+    util::{fmt_number, meta_name, resolve_recipe, Input},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct AdjustArgs {
+    /// Recipe to adjust
+    recipe: String,
+
+    /// Altitude to bake at, like `2000m` or `6500ft`
+    #[arg(short, long)]
+    altitude: Option<Altitude>,
+
+    /// Change of pan, like `23cm->30cm`, `9in->10in` or `20x20cm->20x30cm`
+    ///
+    /// The quantities scale with the ratio of the pan areas.
+    #[arg(short, long, value_name = "FROM->TO")]
+    pan: Option<PanChange>,
+
+    /// Scale to a number of servings
+    #[arg(short, long, conflicts_with = "pan")]
+    servings: Option<u32>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+    Markdown,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    recipe: &'a ScaledRecipe,
+    changes: &'a [Change],
+}
+
+pub fn run(ctx: &Context, args: AdjustArgs) -> Result<()> {
+    if args.altitude.is_none() && args.pan.is_none() {
+        bail!("Nothing to adjust, use `--altitude` or `--pan`");
+    }
+
+    let converter = ctx.parser()?.converter();
+    let input = Input::File {
+        entry: resolve_recipe(ctx, &args.recipe)?,
+        override_name: None,
+    };
+    let name = input.name()?;
+    let recipe = input.parse(ctx)?;
+
+    let mut changes = Vec::new();
+    let (mut scaled, mut marks) = match args.pan {
+        Some(pan) => {
+            let base = base_quantities(&recipe);
+            let factor = pan.factor();
+            let (scaled, mut marks) = scale_by(recipe, factor, &ctx.config.scaling);
+            let shown = |q: &Option<Quantity>| q.as_ref().map(ToString::to_string);
+            for (index, (new, old)) in scaled.ingredients.iter().zip(&base).enumerate() {
+                if shown(&new.quantity) != shown(old) {
+                    marks.entry(index).or_insert(ScaleMark::Adjusted);
+                }
+            }
+            changes.push(Change {
+                what: "pan area".into(),
+                from: format!("{} cm²", fmt_number(pan.from.0.round())),
+                to: format!("{} cm²", fmt_number(pan.to.0.round())),
+                reason: if factor >= 1.0 {
+                    "bigger pan"
+                } else {
+                    "smaller pan"
+                },
+            });
+            (scaled, marks)
+        }
+        None => scale_recipe(recipe, args.servings, converter, &ctx.config.scaling),
+    };
+
+    if let Some(altitude) = args.altitude {
+        let altitude_changes = adjust::altitude(&mut scaled, altitude, converter, &mut marks);
+        if altitude_changes.is_empty() {
+            tracing::info!("No altitude adjustment is needed below 900 m (3000 ft)");
+        }
+        changes.extend(altitude_changes);
+    }
+
+    let name = meta_name(&scaled.metadata).unwrap_or(name);
+
+    if args.format == OutputFormat::Json {
+        let report = Report {
+            recipe: &scaled,
+            changes: &changes,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let renderer: Box<dyn RecipeRenderer + '_> = match args.format {
+        OutputFormat::Markdown => Box::new(cooklang_render::Markdown {
+            options: &ctx.config.export.markdown,
+        }),
+        _ => Box::new(cooklang_render::Human::default()),
+    };
+    let mut buf = Vec::new();
+    renderer.render(
+        &mut buf,
+        &RenderInput::new(&scaled, name, converter)
+            .marks(&marks)
+            .number_format(number_format(&scaled.metadata, &ctx.config)),
+    )?;
+    write_changes(&mut buf, &changes, args.format == OutputFormat::Markdown)?;
+    print!("{}", String::from_utf8_lossy(&buf));
+    Ok(())
+}
+
+fn write_changes(mut w: impl Write, changes: &[Change], markdown: bool) -> std::io::Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    if markdown {
+        writeln!(w, "\n## Adjustments\n")?;
+        for c in changes {
+            writeln!(w, "- {}: {} → {} ({})", c.what, c.from, c.to, c.reason)?;
+        }
+    } else {
+        writeln!(w, "\n{}:", "Adjustments".bold().underline())?;
+        for c in changes {
+            writeln!(
+                w,
+                "  {}: {} → {} {}",
+                c.what.bold(),
+                c.from.dim(),
+                c.to.magenta(),
+                format!("({})", c.reason).dim()
+            )?;
+        }
+    }
+    Ok(())
+}
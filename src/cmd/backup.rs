@@ -0,0 +1,42 @@
+use anyhow::{bail, Result};
+use camino::Utf8PathBuf;
+use clap::Args;
+
+use crate::{backup, Context};
+
+#[derive(Debug, Args)]
+pub struct BackupArgs {
+    /// Backup file to write
+    #[arg(short, long, default_value = "chef-backup.tar.zst")]
+    out: Utf8PathBuf,
+    /// Leave out the global config files
+    #[arg(long)]
+    no_global: bool,
+    /// Overwrite the backup file if it exists
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(ctx: &Context, args: BackupArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`backup` needs to run inside a collection");
+    }
+    if args.out.exists() && !args.force {
+        bail!("{} already exists, use `--force` to overwrite it", args.out);
+    }
+
+    let out = std::path::absolute(&args.out)?;
+    let mut sources = backup::sources(&ctx.base_path, !args.no_global)?;
+    // an old backup inside the collection
+    sources.retain(|s| std::path::absolute(&s.local).map_or(true, |p| p != out));
+
+    let manifest = backup::create(&sources, &args.out)?;
+    let size: u64 = manifest.files.iter().map(|f| f.size).sum();
+    eprintln!(
+        "Saved {} files ({:.1} MB) to {}",
+        manifest.files.len(),
+        size as f64 / 1_000_000.0,
+        args.out
+    );
+    Ok(())
+}
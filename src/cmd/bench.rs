@@ -0,0 +1,196 @@
+use std::time::{Duration, Instant};
+
+use anstream::println;
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use cooklang::ingredient_list::IngredientList;
+use cooklang_render::{RecipeRenderer, RenderInput};
+use serde::Serialize;
+
+use crate::{
+    prep::Preparations,
+    scaling::{apply_strategies, base_quantities},
+    util::{all_recipes, parse_all},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Times each workload runs
+    #[arg(short = 'n', long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    runs: u32,
+
+    /// Servings the recipes are scaled to
+    #[arg(long, default_value_t = 4)]
+    servings: u32,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct Timing {
+    workload: &'static str,
+    recipes: usize,
+    /// Fastest run, in milliseconds
+    best_ms: f64,
+    /// Average of the runs, in milliseconds
+    mean_ms: f64,
+}
+
+/// Times the main workloads of chef over the collection, to compare
+/// performance changes with real recipes. `cargo bench` has the same with a
+/// synthetic collection.
+pub fn run(ctx: &Context, args: BenchArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`bench` needs to run inside a collection");
+    }
+
+    let parser = ctx.parser()?;
+    let converter = parser.converter();
+    let entries = all_recipes(ctx)?.collect::<Vec<_>>();
+    let texts = entries
+        .iter()
+        .map(|e| Ok(e.read()?.into_text()))
+        .collect::<Result<Vec<_>>>()?;
+    // scaling consumes the recipes, so the workloads that scale get a new
+    // batch each run, parsed outside of the timing
+    let valid_recipes = || {
+        texts
+            .iter()
+            .filter_map(|t| parser.parse(t).into_output())
+            .collect::<Vec<_>>()
+    };
+    let default_scaled = valid_recipes()
+        .into_iter()
+        .map(|r| r.default_scale())
+        .collect::<Vec<_>>();
+    if default_scaled.is_empty() {
+        bail!("No valid recipes to run the benchmarks with");
+    }
+    let n_recipes = default_scaled.len();
+
+    let mut timings = Vec::new();
+    let mut time = |workload, recipes, runs: Vec<Duration>| {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        timings.push(Timing {
+            workload,
+            recipes,
+            best_ms: ms(*runs.iter().min().unwrap()),
+            mean_ms: ms(runs.iter().sum::<Duration>()) / runs.len() as f64,
+        });
+    };
+
+    time(
+        "parse",
+        texts.len(),
+        measure(
+            args.runs,
+            || (),
+            |()| {
+                for text in &texts {
+                    let _ = parser.parse(text);
+                }
+            },
+        ),
+    );
+    time(
+        "parse_all",
+        entries.len(),
+        measure(
+            args.runs,
+            || entries.clone(),
+            |entries| {
+                // the path of the commands over the whole collection
+                let _ = parse_all(ctx, entries);
+            },
+        ),
+    );
+    time(
+        "render_human",
+        n_recipes,
+        measure(
+            args.runs,
+            || (),
+            |()| {
+                let renderer = cooklang_render::Human::default();
+                for scaled in &default_scaled {
+                    let input = RenderInput::new(scaled, "bench", converter);
+                    let _ = renderer.render(&mut std::io::sink(), &input);
+                }
+            },
+        ),
+    );
+    time(
+        "scale",
+        n_recipes,
+        measure(args.runs, valid_recipes, |recipes| {
+            for recipe in recipes {
+                let base = base_quantities(&recipe);
+                let mut scaled = recipe.scale(args.servings, converter);
+                apply_strategies(&mut scaled, &base, &ctx.config.scaling);
+            }
+        }),
+    );
+    time(
+        "shopping_list",
+        n_recipes,
+        measure(args.runs, valid_recipes, |recipes| {
+            let preps = Preparations::new(&ctx.config.prep);
+            let mut list = IngredientList::new();
+            for recipe in recipes {
+                let scaled = recipe.scale(args.servings, converter);
+                preps.add_recipe(&mut list, &scaled, converter);
+            }
+        }),
+    );
+
+    match args.format {
+        OutputFormat::Human => print_human(&timings),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(anstream::stdout().lock(), &timings)?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs `f` `runs` times with the input from `setup`, timing only `f`
+fn measure<T>(runs: u32, mut setup: impl FnMut() -> T, mut f: impl FnMut(T)) -> Vec<Duration> {
+    (0..runs)
+        .map(|_| {
+            let input = setup();
+            let start = Instant::now();
+            f(input);
+            start.elapsed()
+        })
+        .collect()
+}
+
+fn print_human(timings: &[Timing]) {
+    use tabular::{row, Table};
+    use yansi::Paint;
+
+    let mut table = Table::new("{:<}  {:>}  {:>}  {:>}");
+    table.add_row(row!(
+        "workload".bold(),
+        "recipes".bold(),
+        "best".bold(),
+        "mean".bold()
+    ));
+    for t in timings {
+        table.add_row(row!(
+            t.workload,
+            t.recipes,
+            format!("{:.1} ms", t.best_ms),
+            format!("{:.1} ms", t.mean_ms).dim()
+        ));
+    }
+    println!("{table}");
+}
@@ -0,0 +1,407 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    cmd::edit::edit_recipe,
+    scaling::scale_recipe,
+    util::{all_recipes, fuzzy_score, CachedRecipeEntry, Input},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct BrowseArgs {
+    /// Only show recipes with this tag at start
+    #[arg(short, long)]
+    tag: Option<String>,
+}
+
+struct Item {
+    entry: CachedRecipeEntry,
+    /// Relative path without extension
+    name: String,
+    tags: Vec<String>,
+}
+
+struct App<'a> {
+    ctx: &'a Context,
+    items: Vec<Item>,
+    all_tags: Vec<String>,
+    query: String,
+    searching: bool,
+    tag: Option<String>,
+    /// Indexes into `items` that pass the filters
+    visible: Vec<usize>,
+    list: ListState,
+    servings: HashMap<usize, u32>,
+    preview_scroll: u16,
+    previews: HashMap<(usize, Option<u32>), String>,
+    shopping_list: Vec<String>,
+    status: String,
+}
+
+enum Action {
+    Quit,
+    Open(usize),
+    Edit(usize),
+}
+
+pub fn run(ctx: &Context, args: BrowseArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`browse` needs to run inside a collection");
+    }
+
+    let mut items = Vec::new();
+    for entry in all_recipes(ctx)? {
+        let entry = CachedRecipeEntry::new(entry);
+        let name = entry
+            .relative_name()
+            .strip_prefix(ctx.base_path.as_str())
+            .unwrap_or(entry.relative_name())
+            .trim_start_matches(['/', '\\'])
+            .to_string();
+        let tags = entry
+            .metadata(ctx, false)
+            .ok()
+            .and_then(|m| m.tags())
+            .map(|tags| tags.iter().map(|t| t.to_string()).collect())
+            .unwrap_or_default();
+        items.push(Item { entry, name, tags });
+    }
+    items.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    let all_tags = items
+        .iter()
+        .flat_map(|i| i.tags.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut app = App {
+        ctx,
+        items,
+        all_tags,
+        query: String::new(),
+        searching: false,
+        tag: args.tag,
+        visible: Vec::new(),
+        list: ListState::default(),
+        servings: HashMap::new(),
+        preview_scroll: 0,
+        previews: HashMap::new(),
+        shopping_list: Vec::new(),
+        status: String::new(),
+    };
+    app.update_visible();
+
+    let mut terminal = ratatui::init();
+    let res = loop {
+        match app.event_loop(&mut terminal) {
+            Ok(Action::Edit(index)) => {
+                ratatui::restore();
+                let res = edit_recipe(ctx, &app.items[index].entry);
+                terminal = ratatui::init();
+                // the recipe may have changed
+                app.previews.retain(|(i, _), _| *i != index);
+                app.items[index].entry = CachedRecipeEntry::new((*app.items[index].entry).clone());
+                if let Err(e) = res {
+                    app.status = format!("{e:#}");
+                }
+            }
+            other => break other,
+        }
+    };
+    ratatui::restore();
+
+    match res? {
+        Action::Open(index) => {
+            let item = &app.items[index];
+            let recipe = Input::File {
+                entry: (*item.entry).clone(),
+                override_name: None,
+            }
+            .parse(ctx)?;
+            let converter = ctx.parser()?.converter();
+            let servings = app.servings.get(&index).copied();
+            let (scaled, marks) = scale_recipe(recipe, servings, converter, &ctx.config.scaling);
+            cooklang_to_human::print_human_with_marks(
+                &scaled,
+                item.entry.name(),
+                converter,
+                &marks,
+                anstream::stdout().lock(),
+            )?;
+        }
+        Action::Quit | Action::Edit(_) => {}
+    }
+
+    if !app.shopping_list.is_empty() {
+        let args = app
+            .shopping_list
+            .iter()
+            .map(|r| shell_words::quote(r))
+            .collect::<Vec<_>>()
+            .join(" ");
+        eprintln!("Shopping list: chef shopping-list {args}");
+    }
+    Ok(())
+}
+
+impl App<'_> {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<Action> {
+        loop {
+            terminal.draw(|f| self.draw(f))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return Ok(Action::Quit);
+            }
+
+            if self.searching {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Enter => self.searching = false,
+                    KeyCode::Backspace => {
+                        self.query.pop();
+                        self.update_visible();
+                    }
+                    KeyCode::Char(c) => {
+                        self.query.push(c);
+                        self.update_visible();
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') => return Ok(Action::Quit),
+                KeyCode::Esc => {
+                    self.query.clear();
+                    self.update_visible();
+                }
+                KeyCode::Char('/') => self.searching = true,
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::PageDown => self.preview_scroll = self.preview_scroll.saturating_add(10),
+                KeyCode::PageUp => self.preview_scroll = self.preview_scroll.saturating_sub(10),
+                KeyCode::Tab => self.cycle_tag(),
+                KeyCode::Char('+') => self.change_servings(1),
+                KeyCode::Char('-') => self.change_servings(-1),
+                KeyCode::Char('a') => self.add_to_shopping_list(),
+                KeyCode::Enter => {
+                    if let Some(index) = self.selected() {
+                        return Ok(Action::Open(index));
+                    }
+                }
+                KeyCode::Char('e') => {
+                    if let Some(index) = self.selected() {
+                        return Ok(Action::Edit(index));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.list
+            .selected()
+            .and_then(|i| self.visible.get(i).copied())
+    }
+
+    fn update_visible(&mut self) {
+        let mut scored = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| match &self.tag {
+                Some(tag) => item.tags.contains(tag),
+                None => true,
+            })
+            .filter_map(|(i, item)| {
+                if self.query.is_empty() {
+                    Some((0, i))
+                } else {
+                    fuzzy_score(&self.query, &item.name).map(|s| (s, i))
+                }
+            })
+            .collect::<Vec<_>>();
+        // stable, so equal scores keep the alphabetical order
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.visible = scored.into_iter().map(|(_, i)| i).collect();
+        self.list.select((!self.visible.is_empty()).then_some(0));
+        self.preview_scroll = 0;
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, self.visible.len() as isize - 1);
+        self.list.select(Some(next as usize));
+        self.preview_scroll = 0;
+    }
+
+    fn cycle_tag(&mut self) {
+        self.tag = match &self.tag {
+            None => self.all_tags.first().cloned(),
+            Some(current) => self
+                .all_tags
+                .iter()
+                .skip_while(|t| *t != current)
+                .nth(1)
+                .cloned(),
+        };
+        self.update_visible();
+    }
+
+    fn base_servings(&self, index: usize) -> Option<u32> {
+        let metadata = self.items[index].entry.metadata(self.ctx, false).ok()?;
+        metadata.servings()?.first().copied()
+    }
+
+    fn change_servings(&mut self, delta: i32) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        let Some(current) = self
+            .servings
+            .get(&index)
+            .copied()
+            .or_else(|| self.base_servings(index))
+        else {
+            self.status = "This recipe has no servings to scale".into();
+            return;
+        };
+        let new = current.saturating_add_signed(delta).max(1);
+        self.servings.insert(index, new);
+    }
+
+    fn add_to_shopping_list(&mut self) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        let name = &self.items[index].name;
+        let entry = match self.servings.get(&index) {
+            Some(servings) => format!("{name}*{servings}"),
+            None => name.clone(),
+        };
+        self.status = format!("Added {entry} to the shopping list");
+        self.shopping_list.push(entry);
+    }
+
+    fn preview(&mut self, index: usize) -> &str {
+        let servings = self.servings.get(&index).copied();
+        let ctx = self.ctx;
+        let item = &self.items[index];
+        self.previews
+            .entry((index, servings))
+            .or_insert_with(|| render_preview(ctx, &item.entry, servings))
+            .as_str()
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(f.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(main);
+        let [search, list] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(left);
+
+        let search_style = if self.searching {
+            Style::new().yellow()
+        } else {
+            Style::new()
+        };
+        f.render_widget(
+            Paragraph::new(self.query.as_str()).block(
+                Block::bordered()
+                    .title(" Search ")
+                    .border_style(search_style),
+            ),
+            search,
+        );
+
+        let title = match &self.tag {
+            Some(tag) => format!(" Recipes [{tag}] "),
+            None => " Recipes ".to_string(),
+        };
+        let items = self
+            .visible
+            .iter()
+            .map(|&i| {
+                let item = &self.items[i];
+                let mut spans = vec![Span::raw(item.name.clone())];
+                if let Some(s) = self.servings.get(&i) {
+                    spans.push(Span::raw(format!(" ({s})")).dim());
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect::<Vec<_>>();
+        f.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(title))
+                .highlight_style(Style::new().reversed()),
+            list,
+            &mut self.list,
+        );
+
+        let preview = match self.selected() {
+            Some(index) => self.preview(index).to_string(),
+            None => String::new(),
+        };
+        f.render_widget(
+            Paragraph::new(preview)
+                .block(Block::bordered().title(" Preview "))
+                .wrap(Wrap { trim: false })
+                .scroll((self.preview_scroll, 0)),
+            right,
+        );
+
+        let help = "/ search  tab tag  +/- servings  a add to list  e edit  enter open  q quit";
+        let status_line = if self.status.is_empty() {
+            Line::from(help).dim()
+        } else {
+            Line::from(self.status.as_str())
+        };
+        f.render_widget(Paragraph::new(status_line), status);
+    }
+}
+
+fn render_preview(ctx: &Context, entry: &CachedRecipeEntry, servings: Option<u32>) -> String {
+    let render = || -> Result<String> {
+        // scaling takes the recipe, so it's not the cached one
+        let res = entry.parse(ctx)?;
+        if !res.is_valid() {
+            return Ok("This recipe has errors, check it with `chef check`".into());
+        }
+        let recipe = res.unwrap_output();
+        let converter = ctx.parser()?.converter();
+        let (scaled, marks) = scale_recipe(recipe, servings, converter, &ctx.config.scaling);
+        let mut buf = anstream::StripStream::new(Vec::new());
+        cooklang_to_human::print_human_with_marks(
+            &scaled,
+            entry.name(),
+            converter,
+            &marks,
+            &mut buf,
+        )?;
+        Ok(String::from_utf8_lossy(&buf.into_inner()).into_owned())
+    };
+    render().unwrap_or_else(|e| format!("Could not render the recipe: {e:#}"))
+}
@@ -0,0 +1,184 @@
+use anstream::println;
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use yansi::Paint;
+
+use crate::{
+    encryption, hooks,
+    source_edit::{remove_metadata, rename_ingredient, set_metadata, split_metadata},
+    util::{all_recipes, write_diff, CachedRecipeEntry},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct BulkArgs {
+    #[command(subcommand)]
+    operation: Operation,
+
+    /// Only recipes with all these tags
+    #[arg(short, long, global = true)]
+    tag: Vec<String>,
+
+    /// Only recipes whose path contains this text
+    #[arg(long, global = true)]
+    path_contains: Option<String>,
+
+    /// Show a diff of the changes without writing them
+    #[arg(short = 'n', long, global = true)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Operation {
+    /// Re-format the recipe steps
+    ///
+    /// The metadata is kept as is.
+    Format,
+    /// Add a tag
+    AddTag { tag: String },
+    /// Remove a tag
+    RemoveTag { tag: String },
+    /// Rename an ingredient in every step
+    RenameIngredient { from: String, to: String },
+    /// Set a metadata key
+    ///
+    /// The value is YAML when the recipe has a front-matter.
+    SetMeta { key: String, value: String },
+    /// Remove a metadata key
+    RemoveMeta { key: String },
+}
+
+pub fn run(ctx: &Context, args: BulkArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`bulk` needs to run inside a collection");
+    }
+    if let Operation::AddTag { tag } = &args.operation {
+        if !crate::util::is_valid_tag(tag) {
+            bail!("Invalid tag: '{tag}'");
+        }
+    }
+
+    let mut changed = 0;
+    let mut failed = 0;
+    let mut total = 0;
+    for entry in all_recipes(ctx)? {
+        let entry = CachedRecipeEntry::new(entry);
+        if !matches_filter(ctx, &args, &entry) {
+            continue;
+        }
+        total += 1;
+
+        let old = entry.read()?.into_text();
+        let new = match apply(ctx, &args.operation, &old) {
+            Ok(new) => new,
+            Err(e) => {
+                tracing::warn!("Skipping '{}': {e:#}", entry.path());
+                failed += 1;
+                continue;
+            }
+        };
+        if new == old {
+            continue;
+        }
+        changed += 1;
+
+        if args.dry_run {
+            println!("{}", format!("--- {}", entry.path()).bold());
+            write_diff(anstream::stdout().lock(), &old, &new)?;
+            println!();
+        } else {
+            encryption::write_recipe(ctx, entry.path(), &new)?;
+            eprintln!("{} {}", "Updated".green().bold(), entry.path());
+            hooks::fire(
+                ctx,
+                hooks::Event::RecipeSaved {
+                    path: entry.path().as_str(),
+                },
+            );
+        }
+    }
+
+    let verb = if args.dry_run {
+        "would change"
+    } else {
+        "changed"
+    };
+    eprintln!("{total} recipe(s) matched, {changed} {verb}, {failed} skipped");
+    Ok(())
+}
+
+fn matches_filter(ctx: &Context, args: &BulkArgs, entry: &CachedRecipeEntry) -> bool {
+    if let Some(part) = &args.path_contains {
+        if !entry.path().as_str().contains(part.as_str()) {
+            return false;
+        }
+    }
+    if args.tag.is_empty() {
+        return true;
+    }
+    let Ok(metadata) = entry.metadata(ctx, false) else {
+        tracing::warn!("Skipping '{}': could not parse metadata", entry.path());
+        return false;
+    };
+    args.tag.iter().all(|t| {
+        metadata
+            .tags()
+            .is_some_and(|tags| tags.iter().any(|tt| tt == t))
+    })
+}
+
+fn apply(ctx: &Context, op: &Operation, text: &str) -> Result<String> {
+    let has_front_matter = text.trim_start().starts_with("---");
+    let new = match op {
+        Operation::Format => {
+            // only the body is parsed and printed, the metadata is kept as is
+            let (meta, body) = split_metadata(text);
+            let res = ctx.parser()?.parse(body);
+            let Some(recipe) = res.valid_output() else {
+                bail!("the recipe has errors");
+            };
+            let mut body = Vec::new();
+            cooklang_to_cooklang::print_cooklang(recipe, &mut body)?;
+            let body = String::from_utf8(body)?;
+            let meta = meta.trim_end();
+            if meta.is_empty() {
+                format!("{}\n", body.trim())
+            } else {
+                format!("{meta}\n\n{}\n", body.trim())
+            }
+        }
+        Operation::AddTag { tag } | Operation::RemoveTag { tag } => {
+            // not the cached metadata, inherited keys should not be written
+            let Some(metadata) = ctx.parser()?.parse_metadata(text).into_output() else {
+                bail!("could not parse metadata");
+            };
+            let mut tags = metadata
+                .tags()
+                .unwrap_or_default()
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>();
+            let before = tags.len();
+            if matches!(op, Operation::AddTag { .. }) {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            } else {
+                tags.retain(|t| t != tag);
+            }
+            if tags.len() == before {
+                text.to_string()
+            } else if tags.is_empty() {
+                remove_metadata(text, "tags")
+            } else if has_front_matter {
+                set_metadata(text, "tags", &format!("[{}]", tags.join(", ")))
+            } else {
+                set_metadata(text, "tags", &tags.join(", "))
+            }
+        }
+        Operation::RenameIngredient { from, to } => rename_ingredient(text, from, to).0,
+        Operation::SetMeta { key, value } => set_metadata(text, key, value),
+        Operation::RemoveMeta { key } => remove_metadata(text, key),
+    };
+    Ok(new)
+}
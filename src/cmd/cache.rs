@@ -0,0 +1,32 @@
+use anstream::println;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+use crate::{render_cache, thumbnails};
+
+#[derive(Debug, Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Remove all the cached rendered recipes and image thumbnails
+    Clear,
+    /// Print the path of the cache
+    Path,
+}
+
+pub fn run(args: CacheArgs) -> Result<()> {
+    match args.command {
+        Command::Clear => {
+            let count = render_cache::clear()?;
+            println!("Removed {count} cached recipes");
+            let count = thumbnails::clear()?;
+            println!("Removed {count} thumbnails");
+        }
+        Command::Path => println!("{}", render_cache::path()?),
+    }
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, ValueEnum};
+use cooklang_fs::RecipeEntry;
+use yansi::Paint;
+
+use crate::{
+    diagnostics::{self, Diagnostic},
+    util::{all_recipes, parse_all},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// Recipe files to check, all the recipes of the collection by default
+    files: Vec<Utf8PathBuf>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// Same reports as the other commands
+    Human,
+    /// A JSON array of diagnostics
+    Json,
+    /// SARIF 2.1.0 log
+    Sarif,
+}
+
+pub fn run(ctx: &Context, args: CheckArgs) -> Result<()> {
+    let entries = if args.files.is_empty() {
+        all_recipes(ctx)?.collect()
+    } else {
+        args.files.iter().map(RecipeEntry::new).collect()
+    };
+
+    let mut all = Vec::<Diagnostic>::new();
+    for entry in parse_all(ctx, entries)? {
+        let text = entry.read()?.into_text();
+        let parsed = entry.parsed(ctx)?;
+        let mut report = parsed.report().clone();
+        if ctx.global_args.ignore_warnings {
+            report.remove_warnings();
+        }
+        let path = entry
+            .path()
+            .strip_prefix(&ctx.base_path)
+            .unwrap_or(entry.path())
+            .to_owned();
+        if !report.is_empty() {
+            if matches!(args.format, OutputFormat::Human) {
+                report.eprint(entry.file_name(), &text, ctx.color.color_stderr)?;
+            }
+            all.extend(diagnostics::from_report(&report, &path, &text));
+        }
+        if !ctx.global_args.ignore_warnings {
+            for d in diagnostics::extensions(ctx.parser()?, &path, &text) {
+                if matches!(args.format, OutputFormat::Human) {
+                    print_warning(&path, &d);
+                }
+                all.push(d);
+            }
+        }
+
+        if !parsed.is_valid() || ctx.global_args.ignore_warnings {
+            continue;
+        }
+        let file = entry.path().to_owned();
+        let recipe = entry.into_parsed(ctx)?.unwrap_output().default_scale();
+        let lints = diagnostics::lint(
+            &recipe,
+            &file,
+            &ctx.base_path,
+            &text,
+            ctx.parser()?.converter(),
+        );
+        for d in lints {
+            if matches!(args.format, OutputFormat::Human) {
+                print_warning(&path, &d);
+            }
+            all.push(d);
+        }
+    }
+
+    match args.format {
+        OutputFormat::Human => {}
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(anstream::stdout().lock(), &all)?;
+        }
+        OutputFormat::Sarif => {
+            serde_json::to_writer_pretty(anstream::stdout().lock(), &diagnostics::to_sarif(&all))?;
+        }
+    }
+
+    let errors = all.iter().filter(|d| d.severity == "error").count();
+    let warnings = all.len() - errors;
+    if errors > 0 || warnings > 0 && ctx.global_args.warnings_as_errors {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn print_warning(path: &Utf8Path, d: &Diagnostic) {
+    eprintln!("{}: {path}: {}", "Warning".yellow().bold(), d.message);
+    for hint in &d.hints {
+        eprintln!("  {} {hint}", "help:".bold());
+    }
+}
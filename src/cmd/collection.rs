@@ -9,7 +9,7 @@ use crate::{
         config_file_path, global_file_path, global_store, store_at_path, ChefConfig, Config,
         CHEF_CONFIG_FILE, DEFAULT_CONFIG_FILE,
     },
-    Context, COOK_DIR,
+    remote, Context, COOK_DIR,
 };
 
 #[derive(Debug, Args)]
@@ -42,6 +42,16 @@ enum Command {
     Get,
     /// Removes the default collection
     Unset,
+    /// Write the index to serve the collection over HTTP as a remote
+    ///
+    /// The index lists the recipes, their images and `collection.toml`
+    /// files. The `.cooklang` dir is never synced. Serve the collection dir with any static
+    /// file server and add it with `chef sync <NAME> --url <URL>`.
+    Index {
+        /// Output file, `index.json` in the collection if not given
+        #[arg(short, long)]
+        output: Option<Utf8PathBuf>,
+    },
 }
 
 pub fn run(ctx: &Context, args: CollectionArgs) -> Result<()> {
@@ -89,6 +99,15 @@ pub fn run(ctx: &Context, args: CollectionArgs) -> Result<()> {
                 eprintln!("No default collection is set");
             }
         }
+        Command::Index { output } => {
+            let index =
+                remote::build_index(&ctx.base_path, ctx.config.max_depth, &ctx.config.discovery)?;
+            let output = output.unwrap_or_else(|| ctx.base_path.join(remote::INDEX_FILE));
+            let file = fs::File::create(&output)
+                .with_context(|| format!("Failed to create index file: {output}"))?;
+            serde_json::to_writer_pretty(file, &index)?;
+            eprintln!("Index with {} files written to {output}", index.files.len());
+        }
     }
     Ok(())
 }
@@ -3,19 +3,25 @@ use std::{fs, path::Path};
 use anstream::{print, println};
 use anyhow::{bail, Context as _, Result};
 use camino::Utf8PathBuf;
-use clap::Args;
+use clap::{Args, Subcommand};
 use cooklang::Extensions;
+use toml::Value;
+use toml_edit::{DocumentMut, TableLike};
 
 use crate::{
     config::{
         config_file_path, global_file_path, global_store, store_at_path, ChefConfig, Config,
         CHEF_CONFIG_FILE, DEFAULT_CONFIG_FILE,
     },
+    config_layers::{self, check_key, validate, Layers},
+    util::run_editor,
     Context, COOK_DIR, UTF8_PATH_PANIC,
 };
 
 #[derive(Debug, Args)]
 pub struct ConfigArgs {
+    #[command(subcommand)]
+    command: Option<ConfigCommand>,
     /// Run the basic interactive config setup
     #[arg(long, exclusive = true)]
     setup: bool,
@@ -24,6 +30,32 @@ pub struct ConfigArgs {
     chef: bool,
 }
 
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Print the value of a key, like `season.region`
+    Get { key: String },
+    /// Set a key in the collection config file
+    ///
+    /// The value is like in a TOML file, `true`, `3` or `["a", "b"]`, or
+    /// else a string. The file keeps its comments and the new config is
+    /// checked before saving it.
+    Set {
+        key: String,
+        value: String,
+        /// Set it in the global default config instead
+        #[arg(long)]
+        global: bool,
+    },
+    /// List every key with its value and the layer it comes from
+    List,
+    /// Open the config file in the editor and check it after
+    Edit {
+        /// Edit the global default config instead
+        #[arg(long)]
+        global: bool,
+    },
+}
+
 pub fn run_setup(config: &Config, chef_config: &ChefConfig) -> Result<()> {
     use inquire::{Confirm, Text};
     use yansi::Paint;
@@ -171,6 +203,14 @@ pub fn run(ctx: &Context, args: ConfigArgs) -> Result<()> {
         return Ok(());
     }
 
+    match args.command {
+        Some(ConfigCommand::Get { key }) => return get(ctx, &key),
+        Some(ConfigCommand::Set { key, value, global }) => return set(ctx, &key, &value, global),
+        Some(ConfigCommand::List) => return list(ctx),
+        Some(ConfigCommand::Edit { global }) => return edit(ctx, global),
+        None => {}
+    }
+
     if args.chef {
         display_chef_config(ctx)
     } else {
@@ -178,18 +218,126 @@ pub fn run(ctx: &Context, args: ConfigArgs) -> Result<()> {
     }
 }
 
+/// The collection config file, or the global default one
+fn config_path(ctx: &Context, global: bool) -> Result<Utf8PathBuf> {
+    if global {
+        global_file_path(DEFAULT_CONFIG_FILE)
+    } else if let Some(file) = &ctx.global_args.config_file {
+        Ok(file.clone())
+    } else {
+        Ok(config_file_path(&ctx.base_path))
+    }
+}
+
+fn get(ctx: &Context, key: &str) -> Result<()> {
+    let layers = Layers::load(Some(config_path(ctx, false)?.as_path()))?;
+    match config_layers::get(&layers.merged(), key) {
+        Some(Value::String(s)) => println!("{s}"),
+        Some(Value::Table(table)) => print!("{}", toml::to_string_pretty(table)?),
+        Some(value) => println!("{value}"),
+        None => {
+            check_key(key)?;
+            bail!("'{key}' is not set");
+        }
+    }
+    Ok(())
+}
+
+fn set(ctx: &Context, key: &str, value: &str, global: bool) -> Result<()> {
+    check_key(key)?;
+    let path = config_path(ctx, global)?;
+    let content = if path.is_file() {
+        fs::read_to_string(&path).with_context(|| format!("Failed to read '{path}'"))?
+    } else {
+        String::new()
+    };
+    let mut doc = content
+        .parse::<DocumentMut>()
+        .with_context(|| format!("Bad TOML in '{path}'"))?;
+
+    // the same parsing as the environment variables
+    let value = config_layers::parse_value(value)
+        .to_string()
+        .parse::<toml_edit::Value>()
+        .context("Can't write the value")?;
+    let parts = key.split('.').collect::<Vec<_>>();
+    let (last, tables) = parts.split_last().expect("split is never empty");
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for (i, part) in tables.iter().enumerate() {
+        table = table
+            .entry(part)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .with_context(|| format!("'{}' is not a table", parts[..=i].join(".")))?;
+    }
+    table.insert(last, toml_edit::Item::Value(value));
+
+    let new = doc.to_string();
+    validate(&new).with_context(|| format!("Can't set '{key}'"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, new).with_context(|| format!("Failed to write '{path}'"))?;
+    println!("Set {key} in {path}");
+    Ok(())
+}
+
+fn list(ctx: &Context) -> Result<()> {
+    use yansi::Paint;
+
+    let layers = Layers::load(Some(config_path(ctx, false)?.as_path()))?;
+    let merged = layers.merged();
+    let mut values = Vec::new();
+    config_layers::flatten(&merged, "", &mut values);
+    for (key, value) in values {
+        let source = layers.origin(&key).map(|l| l.source.to_string());
+        println!(
+            "{key} = {value} {}",
+            format!("# {}", source.unwrap_or_default()).dim()
+        );
+    }
+    Ok(())
+}
+
+fn edit(ctx: &Context, global: bool) -> Result<()> {
+    let path = config_path(ctx, global)?;
+    if !path.is_file() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, "")?;
+    }
+    loop {
+        run_editor(ctx, &path)?;
+        let content = fs::read_to_string(&path)?;
+        let Err(err) = validate(&content) else {
+            return Ok(());
+        };
+        println!("{err:?}");
+        let again = inquire::Confirm::new("The config has errors, edit it again?")
+            .with_default(true)
+            .prompt()?;
+        if !again {
+            return Err(err.context(format!("Invalid config file '{path}'")));
+        }
+    }
+}
+
 fn display_regular(ctx: &Context) -> Result<()> {
     use yansi::Paint;
 
     println!("Recipes path: {}", ctx.base_path.yellow());
 
-    let mut config_path = config_file_path(&ctx.base_path);
-    if !config_path.is_file() {
+    let collection_path = config_path(ctx, false)?;
+    if !collection_path.is_file() {
         print!("{}", "No config at: ".dim());
-        println!("{}", config_path.dim().bright_red());
-        config_path = global_file_path(DEFAULT_CONFIG_FILE)?;
+        println!("{}", collection_path.dim().bright_red());
+    }
+    for layer in Layers::load(Some(collection_path.as_path()))?.iter() {
+        if let Some(path) = &layer.path {
+            println!("Config ({}): {}", layer.source, path.yellow());
+        }
     }
-    println!("Config: {}", config_path.yellow());
 
     let fence = "+++".dim();
     println!("{fence}");
@@ -202,6 +350,10 @@ fn display_regular(ctx: &Context) -> Result<()> {
         .units(&ctx.base_path)
         .iter()
         .chain(ctx.config.aisle(&ctx.base_path).iter())
+        .chain(ctx.config.prices(&ctx.base_path).iter())
+        .chain(ctx.config.substitutions(&ctx.base_path).iter())
+        .chain(ctx.config.diet(&ctx.base_path).iter())
+        .chain(ctx.config.produce(&ctx.base_path).iter())
     {
         print!("{file} {} ", "--".dim());
         if file.is_file() {
@@ -0,0 +1,591 @@
+use std::{
+    io::Write,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use anstream::{print, println};
+use anyhow::{bail, Context as _, Result};
+use camino::Utf8PathBuf;
+use clap::{Args, ValueEnum};
+use cooklang::Converter;
+use serde::Serialize;
+use yansi::Paint;
+
+use crate::{
+    cook_log::{recipe_key, CookLog},
+    hooks,
+    pantry::Date,
+    probe::{self, Probe, ProbeConfig, Target},
+    recipe_notes::{self, parse_rating, Note, MAX_RATING},
+    scaling::{scale_factor, scale_recipe},
+    schedule::{format_duration, schedule, total, ClockTime, ScheduledStep},
+    session::{SavedSession, Session},
+    timers::adjust_timers,
+    util::{encode_path, fmt_number, resolve_recipe, Input},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct CookArgs {
+    /// Recipes to cook together, with optional servings number
+    ///
+    /// The steps of all the recipes are merged in a single timeline so all of
+    /// them are done at the same time.
+    #[arg(
+        long,
+        required_unless_present = "resume",
+        num_args = 1..,
+        value_name = "RECIPE[*<SERVINGS>]"
+    )]
+    menu: Vec<String>,
+
+    /// Time to have everything ready, like `19:00`
+    #[arg(long, value_name = "HH:MM")]
+    finish_at: Option<ClockTime>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+
+    /// Go through the steps one at a time, press enter for the next one
+    ///
+    /// The timers of a step start with it and ring when done. Steps with a
+    /// target in the `probe` metadata show the live temperature of the probe
+    /// in the `[probe]` config and alert when it's reached. With a
+    /// `[session]` config, the state is published to MQTT.
+    #[arg(long)]
+    follow: bool,
+
+    /// How to move to the next step when following
+    ///
+    /// With `auto`, the next step comes after the time to read the step
+    /// aloud and its timers, for hands-free cooking. Enter still skips ahead.
+    #[arg(long, value_enum, default_value_t = Advance::Prompt)]
+    advance: Advance,
+
+    /// Go on with the last `--follow` that was interrupted
+    ///
+    /// It starts again in the step it was in, with the same menu and the
+    /// timers that were running.
+    #[arg(long, conflicts_with_all = ["menu", "finish_at", "format"])]
+    resume: bool,
+
+    /// Share the session with the web UI of `chef serve` when following
+    ///
+    /// The recipe pages open in a browser show the current step and timer
+    /// of the terminal.
+    #[arg(long, value_name = "URL")]
+    sync: Option<String>,
+
+    /// Add the recipes to the cook log as cooked today
+    ///
+    /// Done always after following the steps with `--follow`.
+    #[arg(long)]
+    log: bool,
+
+    /// Ask for a rating and a note of each recipe after following the steps
+    ///
+    /// They are saved in the notes file of the recipe. Done always with
+    /// `rate_after_cooking = true` in the config.
+    #[arg(long)]
+    rate: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Advance {
+    /// Wait for enter
+    Prompt,
+    /// Wait for the pace of the step
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct MenuStep<'a> {
+    recipe: &'a str,
+    /// Path of the recipe in the collection without extension
+    #[serde(skip)]
+    key: &'a str,
+    /// Seconds from the start of the menu
+    menu_start: u64,
+    #[serde(flatten)]
+    step: &'a ScheduledStep,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    probe: Option<&'a Target>,
+}
+
+pub fn run(ctx: &Context, mut args: CookArgs) -> Result<()> {
+    let converter = ctx.parser()?.converter();
+
+    let saved = if args.resume {
+        if !ctx.is_collection {
+            bail!("`--resume` needs to run inside a collection");
+        }
+        let Some(saved) = SavedSession::load(&ctx.base_path)? else {
+            bail!("No cooking session to resume");
+        };
+        args.menu.clone_from(&saved.menu);
+        args.follow = true;
+        Some(saved)
+    } else {
+        None
+    };
+
+    let mut recipes = Vec::new();
+    let mut keys = Vec::new();
+    let mut paths = Vec::new();
+    for entry in &args.menu {
+        let (name, servings) = match entry.rsplit_once('*') {
+            Some((name, servings)) => {
+                let servings = servings
+                    .trim()
+                    .parse::<u32>()
+                    .with_context(|| format!("Invalid servings for '{name}'"))?;
+                (name.trim(), Some(servings))
+            }
+            None => (entry.trim(), None),
+        };
+        let entry = resolve_recipe(ctx, name)?;
+        keys.push(recipe_key(entry.path(), &ctx.base_path));
+        paths.push(entry.path().to_owned());
+        let input = Input::File {
+            entry,
+            override_name: None,
+        };
+        let recipe = input.parse(ctx)?;
+        let (mut scaled, _) = scale_recipe(recipe, servings, converter, &ctx.config.scaling);
+        let factor = scale_factor(&scaled).unwrap_or(1.0);
+        adjust_timers(&mut scaled, factor, converter);
+        let (targets, warnings) = probe::targets(&scaled);
+        for warning in warnings {
+            tracing::warn!("{warning}");
+        }
+        recipes.push((
+            input.name()?.to_string(),
+            schedule(&scaled, converter),
+            targets,
+        ));
+    }
+
+    // every recipe ends at the end of the longest one
+    let menu_total = recipes.iter().map(|(_, s, _)| total(s)).max().unwrap_or(0);
+    let mut merged = Vec::new();
+    for ((name, steps, targets), key) in recipes.iter().zip(&keys) {
+        let offset = menu_total - total(steps);
+        for step in steps {
+            merged.push(MenuStep {
+                recipe: name,
+                key,
+                menu_start: offset + step.start,
+                step,
+                probe: targets.get(&(step.section_index, step.number)),
+            });
+        }
+    }
+    // stable, steps of a recipe starting at the same time keep their order
+    merged.sort_by_key(|s| s.menu_start);
+
+    let lines = args.follow.then(stdin_lines);
+    if let Some(lines) = &lines {
+        let sync = args.sync.as_deref().map(|url| SessionSync {
+            url: url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(SYNC_TIMEOUT)
+                .timeout(SYNC_TIMEOUT)
+                .build(),
+            failed: false,
+        });
+        follow(ctx, &args, &merged, menu_total, lines, saved, sync)?;
+    } else {
+        match args.format {
+            OutputFormat::Human => print_menu(&merged, menu_total, args.finish_at),
+            OutputFormat::Json => serde_json::to_writer_pretty(anstream::stdout().lock(), &merged)?,
+        }
+    }
+
+    if args.follow || args.log {
+        if ctx.is_collection {
+            let mut log = CookLog::load(&ctx.base_path)?;
+            let today = Date::today();
+            for key in keys {
+                log.record(key, today);
+            }
+            log.save(&ctx.base_path)?;
+        } else {
+            tracing::warn!("Not in a collection, the cook log is not saved");
+        }
+    }
+
+    if let Some(lines) = &lines {
+        if args.rate || ctx.config.rate_after_cooking {
+            let names = recipes.iter().map(|(name, _, _)| name.as_str());
+            ask_ratings(names.zip(&paths), lines)?;
+        }
+    }
+
+    hooks::fire(
+        ctx,
+        hooks::Event::RecipeCooked {
+            recipes: &args.menu,
+        },
+    );
+    Ok(())
+}
+
+fn follow(
+    ctx: &Context,
+    args: &CookArgs,
+    steps: &[MenuStep],
+    total: u64,
+    enter: &Receiver<String>,
+    saved: Option<SavedSession>,
+    mut sync: Option<SessionSync>,
+) -> Result<()> {
+    let converter = ctx.parser()?.converter();
+    let mut session = Session::new(ctx.config.session.as_ref(), steps.len());
+    let mut first = 0;
+    let mut resuming = saved.is_some();
+    if let Some(saved) = saved {
+        if saved.position > steps.len() {
+            bail!("The saved cooking session doesn't match the recipes, they changed");
+        }
+        first = saved.position.saturating_sub(1);
+        session.resume(saved.position, saved.timers);
+        println!("{} {}", "Resuming at step".bold(), saved.position);
+        ring(&mut session);
+    } else {
+        println!("{} {}", "Total time:".bold(), format_duration(total));
+    }
+    for (i, s) in steps.iter().enumerate().skip(first) {
+        // the timers of the current step are already running
+        if std::mem::take(&mut resuming) {
+            session.resume_step(s.recipe, s.step);
+        } else {
+            session.start_step(s.recipe, s.step);
+        }
+        save_session(ctx, &args.menu, &session);
+        if let Some(sync) = &mut sync {
+            sync.step(s, &session);
+        }
+        println!();
+        println!(
+            "{} {} {}",
+            format!("[{}/{}]", i + 1, steps.len()).dim(),
+            s.recipe.bold(),
+            format!("step {}", s.step.number).dim()
+        );
+        println!("{}", s.step.text);
+        if s.step.duration > 0 {
+            println!("{} {}", "Timers:".dim(), format_duration(s.step.duration));
+        }
+        match (s.probe, &ctx.chef_config.probe) {
+            (Some(target), Some(config)) => {
+                wait_for_probe(config, target, converter, enter, &mut session)?
+            }
+            (Some(target), None) => {
+                println!("{} {target}", "Probe target:".dim());
+                tracing::warn!("No probe configured in `[probe]` of the global config");
+            }
+            (None, _) => {}
+        }
+        if i + 1 < steps.len() {
+            let deadline = match args.advance {
+                Advance::Prompt => {
+                    print!("{}", "Press enter for the next step".dim());
+                    None
+                }
+                Advance::Auto => {
+                    print!(
+                        "{}",
+                        format!("Next step in {}", format_duration(s.step.pace)).dim()
+                    );
+                    Some(Instant::now() + Duration::from_secs(s.step.pace))
+                }
+            };
+            std::io::stdout().flush()?;
+            wait_for_enter(enter, &mut session, deadline);
+            if args.advance == Advance::Auto {
+                println!();
+            }
+        }
+    }
+    println!();
+    if session.has_timers() {
+        println!("{}", "Waiting for the timers, press enter to stop".dim());
+        while session.has_timers() {
+            match enter.recv_timeout(Duration::from_millis(500)) {
+                Ok(_) | Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => ring(&mut session),
+            }
+        }
+    }
+    session.finish();
+    if ctx.is_collection {
+        SavedSession::remove(&ctx.base_path)?;
+    }
+    Ok(())
+}
+
+/// Longest wait for the server in a step, cooking goes on without it
+const SYNC_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Shares the current step and timer with the recipe pages of `chef serve`
+struct SessionSync {
+    url: String,
+    agent: ureq::Agent,
+    /// Stops after the first error, the session goes on without it
+    failed: bool,
+}
+
+impl SessionSync {
+    fn step(&mut self, s: &MenuStep, session: &Session) {
+        if self.failed {
+            return;
+        }
+        if let Err(e) = self.send(s, session) {
+            tracing::warn!(
+                "Sharing the session with {} failed, stopped: {e:#}",
+                self.url
+            );
+            self.failed = true;
+        }
+    }
+
+    fn send(&self, s: &MenuStep, session: &Session) -> Result<()> {
+        let url = format!("{}/cook/state/{}", self.url, encode_path(s.key));
+        // keep what the other devices checked
+        let mut state: serde_json::Value = self.agent.get(&url).call()?.into_json()?;
+        let timer = session
+            .timers()
+            .iter()
+            .filter(|t| t.recipe == s.recipe)
+            .min_by_key(|t| t.ends_at)
+            .map(|t| {
+                serde_json::json!({
+                    "name": t.name,
+                    "state": "running",
+                    "end": t.ends_at * 1000,
+                    "seconds": t.duration,
+                })
+            });
+        state["from"] = "terminal".into();
+        state["step"] = format!("{}-{}", s.step.section_index, s.step.number).into();
+        state["timer"] = timer.into();
+        self.agent.post(&url).send_json(state)?;
+        Ok(())
+    }
+}
+
+/// Saves the state to resume if cooking is interrupted
+fn save_session(ctx: &Context, menu: &[String], session: &Session) {
+    if !ctx.is_collection {
+        return;
+    }
+    let saved = SavedSession {
+        menu: menu.to_vec(),
+        position: session.position(),
+        timers: session.timers().to_vec(),
+    };
+    if let Err(e) = saved.save(&ctx.base_path) {
+        tracing::warn!("{e:#}");
+    }
+}
+
+/// Waits for enter, or until the deadline
+fn wait_for_enter(enter: &Receiver<String>, session: &mut Session, deadline: Option<Instant>) {
+    loop {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            return;
+        }
+        match enter.recv_timeout(Duration::from_millis(500)) {
+            Ok(_) | Err(RecvTimeoutError::Disconnected) => return,
+            Err(RecvTimeoutError::Timeout) => ring(session),
+        }
+    }
+}
+
+/// Alerts of the timers that have finished
+fn ring(session: &mut Session) {
+    for timer in session.tick() {
+        let name = timer.name.as_deref().unwrap_or("timer");
+        println!(
+            "\n\x07{} {} {}",
+            "Timer done:".yellow().bold(),
+            name.bold(),
+            format!("({})", timer.recipe).dim()
+        );
+    }
+}
+
+/// The lines read from stdin, so enter can be waited for with a timeout
+fn stdin_lines() -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Asks for a rating and a note of each recipe and saves them
+fn ask_ratings<'a>(
+    recipes: impl Iterator<Item = (&'a str, &'a Utf8PathBuf)>,
+    lines: &Receiver<String>,
+) -> Result<()> {
+    let today = Date::today();
+    println!();
+    for (name, path) in recipes {
+        let rating = loop {
+            print!(
+                "{} {} {} ",
+                "Rate".bold(),
+                name.bold(),
+                format!("from 1 to {MAX_RATING}, enter to skip:").dim()
+            );
+            std::io::stdout().flush()?;
+            let Ok(line) = lines.recv() else {
+                return Ok(());
+            };
+            if line.trim().is_empty() {
+                break None;
+            }
+            match parse_rating(&line) {
+                Ok(rating) => break Some(rating),
+                Err(e) => println!("{}", e.red()),
+            }
+        };
+        print!("{} ", "Note, enter to skip:".dim());
+        std::io::stdout().flush()?;
+        let text = lines
+            .recv()
+            .ok()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        if rating.is_none() && text.is_none() {
+            continue;
+        }
+        let note = Note {
+            date: today,
+            rating,
+            text,
+        };
+        recipe_notes::append(path, note)?;
+    }
+    Ok(())
+}
+
+fn wait_for_probe(
+    config: &ProbeConfig,
+    target: &Target,
+    converter: &Converter,
+    enter: &Receiver<String>,
+    session: &mut Session,
+) -> Result<()> {
+    let Some(wanted) = target.value_in(&config.unit, converter) else {
+        tracing::warn!("Can't convert the probe target {target} to {}", config.unit);
+        return Ok(());
+    };
+    let probe = Probe::start(&config.backend)?;
+    println!(
+        "{} {target}, press enter to skip",
+        "Waiting for the probe to reach".dim()
+    );
+    // cooling down if the first reading is above the target
+    let mut cooling = None;
+    loop {
+        if enter.try_recv().is_ok() {
+            return Ok(());
+        }
+        ring(session);
+        match probe.readings.recv_timeout(Duration::from_millis(200)) {
+            Ok(value) => {
+                let cooling = *cooling.get_or_insert(value > wanted);
+                print!(
+                    "\r  {} {} {}   ",
+                    "probe".dim(),
+                    fmt_number(value).bold(),
+                    config.unit
+                );
+                std::io::stdout().flush()?;
+                if (cooling && value <= wanted) || (!cooling && value >= wanted) {
+                    println!("\x07{}", "target reached".green().bold());
+                    return Ok(());
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                println!();
+                tracing::warn!("The probe stopped sending readings");
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn print_menu(steps: &[MenuStep], total: u64, finish_at: Option<ClockTime>) {
+    use yansi::Color;
+
+    const COLORS: &[Color] = &[
+        Color::Green,
+        Color::Magenta,
+        Color::Cyan,
+        Color::Yellow,
+        Color::Blue,
+        Color::Red,
+    ];
+
+    println!("{} {}", "Total time:".bold(), format_duration(total));
+    if let Some(finish) = finish_at {
+        println!("{} {}", "Start at:".bold(), finish.minus(total));
+    }
+    println!();
+
+    let mut names = Vec::new();
+    let mut table = tabular::Table::new("{:>}  {:<}  {:>}  {:<}  {:<}");
+    for s in steps {
+        let color_index = match names.iter().position(|n| *n == s.recipe) {
+            Some(i) => i,
+            None => {
+                names.push(s.recipe);
+                names.len() - 1
+            }
+        };
+        let color = COLORS[color_index % COLORS.len()];
+
+        let remaining = total - s.menu_start;
+        let when = match finish_at {
+            Some(finish) => finish.minus(remaining).to_string(),
+            None => format!("T-{}", format_duration(remaining)),
+        };
+        let duration = if s.step.duration > 0 {
+            format_duration(s.step.duration)
+        } else {
+            "-".into()
+        };
+        let mut text = s.step.text.clone();
+        if text.chars().count() > 50 {
+            text = text.chars().take(49).collect::<String>() + "…";
+        }
+        table.add_row(
+            tabular::Row::new()
+                .with_ansi_cell(when.bold())
+                .with_ansi_cell(s.recipe.paint(color))
+                .with_cell(format!("{}.", s.step.number))
+                .with_ansi_cell(duration.dim())
+                .with_cell(text),
+        );
+    }
+    print!("{table}");
+}
@@ -0,0 +1,104 @@
+use anstream::{print, println};
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+use crate::{
+    plugins,
+    prices::{self, format_cost, Costs},
+    scaling::scale_recipe,
+    util::{resolve_recipe, Input},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct CostArgs {
+    /// Recipe to price
+    recipe: String,
+
+    /// Scale to a number of servings
+    servings: Option<u32>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    servings: Option<u32>,
+    total: f64,
+    per_serving: Option<f64>,
+    #[serde(flatten)]
+    costs: &'a Costs,
+}
+
+pub fn run(ctx: &Context, args: CostArgs) -> Result<()> {
+    let converter = ctx.parser()?.converter();
+    let input = Input::File {
+        entry: resolve_recipe(ctx, &args.recipe)?,
+        override_name: None,
+    };
+    let recipe = input.parse(ctx)?;
+    let (scaled, _) = scale_recipe(recipe, args.servings, converter, &ctx.config.scaling);
+    let servings = args
+        .servings
+        .or_else(|| scaled.metadata.servings().and_then(|s| s.first().copied()));
+
+    let amounts = plugins::recipe_amounts(&scaled, converter);
+    let costs = prices::costs(ctx, &amounts)?;
+    let total = costs.total();
+    let report = Report {
+        servings,
+        total,
+        per_serving: servings.filter(|&s| s > 0).map(|s| total / s as f64),
+        costs: &costs,
+    };
+
+    match args.format {
+        OutputFormat::Human => print_human(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+fn print_human(report: &Report) {
+    use tabular::{row, Table};
+    use yansi::Paint;
+
+    let currency = report.costs.currency.as_deref();
+    let mut table = Table::new("{:<}  {:>}");
+    for (name, cost) in &report.costs.ingredients {
+        match cost {
+            Some(cost) => table.add_row(row!(name, format_cost(*cost, currency))),
+            None => table.add_row(row!(name, "-".dim())),
+        };
+    }
+    print!("{table}");
+
+    println!();
+    let mut table = Table::new("{:<}  {:>}");
+    table.add_row(row!("Total".bold(), format_cost(report.total, currency)));
+    if let (Some(servings), Some(per_serving)) = (report.servings, report.per_serving) {
+        table.add_row(row!(
+            format!("Per serving ({servings})").bold(),
+            format_cost(per_serving, currency)
+        ));
+    }
+    print!("{table}");
+
+    let missing = report.costs.missing().collect::<Vec<_>>();
+    if !missing.is_empty() {
+        println!(
+            "\n{} {}",
+            "Without price:".yellow().bold(),
+            missing.join(", ")
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+
+use anstream::println;
+use anyhow::{bail, Result};
+use camino::Utf8PathBuf;
+use clap::{Args, ValueEnum};
+use cooklang::{model::Item, ScalableRecipe};
+use serde::Serialize;
+
+use crate::{
+    util::{all_recipes, parse_all},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct DedupeArgs {
+    /// Minimum similarity to report a pair, from 0 to 1
+    #[arg(short, long, default_value_t = 0.75)]
+    threshold: f64,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+/// What is compared of each recipe
+struct Fingerprint {
+    path: Utf8PathBuf,
+    ingredients: HashMap<String, u32>,
+    words: HashSet<String>,
+}
+
+#[derive(Serialize)]
+struct Candidate<'a> {
+    a: &'a Utf8PathBuf,
+    b: &'a Utf8PathBuf,
+    score: f64,
+    ingredients: f64,
+    steps: f64,
+}
+
+/// Weight of the ingredients in the final score, the rest is the steps
+const INGREDIENTS_WEIGHT: f64 = 0.6;
+
+pub fn run(ctx: &Context, args: DedupeArgs) -> Result<()> {
+    if !(0.0..=1.0).contains(&args.threshold) {
+        bail!("The threshold must be between 0 and 1");
+    }
+
+    let mut fingerprints = Vec::new();
+    let entries = parse_all(ctx, all_recipes(ctx)?.collect())?;
+    for entry in entries {
+        match entry.parsed(ctx)?.valid_output() {
+            Some(recipe) => fingerprints.push(fingerprint(entry.path().to_owned(), recipe)),
+            None => tracing::warn!("Skipping '{}': the recipe has errors", entry.path()),
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (i, a) in fingerprints.iter().enumerate() {
+        for b in &fingerprints[i + 1..] {
+            let ingredients = multiset_similarity(&a.ingredients, &b.ingredients);
+            // can't reach the threshold even with identical steps
+            if ingredients * INGREDIENTS_WEIGHT + (1.0 - INGREDIENTS_WEIGHT) < args.threshold {
+                continue;
+            }
+            let steps = jaccard(&a.words, &b.words);
+            let score = ingredients * INGREDIENTS_WEIGHT + steps * (1.0 - INGREDIENTS_WEIGHT);
+            if score >= args.threshold {
+                candidates.push(Candidate {
+                    a: &a.path,
+                    b: &b.path,
+                    score,
+                    ingredients,
+                    steps,
+                });
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    match args.format {
+        OutputFormat::Human => {
+            use yansi::Paint;
+
+            if candidates.is_empty() {
+                eprintln!("No duplicates found");
+                return Ok(());
+            }
+            for c in &candidates {
+                println!(
+                    "{} {} {}",
+                    format!("{:>3.0}%", c.score * 100.0).yellow().bold(),
+                    c.a,
+                    c.b
+                );
+                println!(
+                    "     {}",
+                    format!(
+                        "ingredients {:.0}% | steps {:.0}%",
+                        c.ingredients * 100.0,
+                        c.steps * 100.0
+                    )
+                    .dim()
+                );
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(anstream::stdout().lock(), &candidates)?;
+        }
+    }
+    Ok(())
+}
+
+fn fingerprint(path: Utf8PathBuf, recipe: &ScalableRecipe) -> Fingerprint {
+    let mut ingredients = HashMap::new();
+    for igr in &recipe.ingredients {
+        if igr.relation.references_to().is_some() {
+            continue;
+        }
+        *ingredients.entry(igr.name.to_lowercase()).or_default() += 1;
+    }
+
+    let mut words = HashSet::new();
+    for section in &recipe.sections {
+        for content in &section.content {
+            let cooklang::Content::Step(step) = content else {
+                continue;
+            };
+            for item in &step.items {
+                if let Item::Text { value } = item {
+                    words.extend(normalized_words(value));
+                }
+            }
+        }
+    }
+
+    Fingerprint {
+        path,
+        ingredients,
+        words,
+    }
+}
+
+fn normalized_words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() > 2)
+        .map(str::to_lowercase)
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Weighted Jaccard similarity of two multisets
+fn multiset_similarity(a: &HashMap<String, u32>, b: &HashMap<String, u32>) -> f64 {
+    let mut min_sum = 0;
+    let mut max_sum = 0;
+    for key in a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))) {
+        let x = a.get(key).copied().unwrap_or(0);
+        let y = b.get(key).copied().unwrap_or(0);
+        min_sum += x.min(y);
+        max_sum += x.max(y);
+    }
+    if max_sum == 0 {
+        return 0.0;
+    }
+    min_sum as f64 / max_sum as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similarity() {
+        let a = HashMap::from([("flour".to_string(), 2), ("water".to_string(), 1)]);
+        let b = HashMap::from([("flour".to_string(), 1), ("salt".to_string(), 1)]);
+        assert_eq!(multiset_similarity(&a, &a), 1.0);
+        assert_eq!(multiset_similarity(&a, &b), 0.25);
+        assert_eq!(multiset_similarity(&a, &HashMap::new()), 0.0);
+
+        let words = |s| normalized_words(s).collect::<HashSet<_>>();
+        assert_eq!(
+            jaccard(&words("Mix the flour"), &words("mix THE flour!")),
+            1.0
+        );
+        assert_eq!(jaccard(&words("mix flour"), &words("bake bread")), 0.0);
+    }
+}
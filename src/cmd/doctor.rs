@@ -0,0 +1,480 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    net::TcpListener,
+    path::{Path, PathBuf},
+};
+
+use anstream::println;
+use anyhow::{bail, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use cooklang::Modifiers;
+use cooklang_fs::{check_recipe_images, plain_path, recipe_images, IMAGE_EXTENSIONS};
+use yansi::Paint;
+
+use crate::{
+    args::GlobalArgs,
+    config::config_file_path,
+    config_layers::Layers,
+    encryption::Backend,
+    ocr::OcrConfig,
+    probe::BackendConfig,
+    remote::{self, RemoteKind},
+    util::{all_recipes, parse_all},
+    ColorContext, Context, COOK_DIR,
+};
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Port to check for `chef serve`
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+}
+
+/// Result of a check
+struct Check {
+    name: &'static str,
+    /// What was found, when there are no problems
+    summary: String,
+    problems: Vec<Problem>,
+}
+
+struct Problem {
+    error: bool,
+    message: String,
+    fix: String,
+}
+
+impl Check {
+    fn new(name: &'static str, summary: impl Into<String>) -> Self {
+        Self {
+            name,
+            summary: summary.into(),
+            problems: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, message: impl Into<String>, fix: impl Into<String>) {
+        self.problems.push(Problem {
+            error: true,
+            message: message.into(),
+            fix: fix.into(),
+        });
+    }
+
+    fn warning(&mut self, message: impl Into<String>, fix: impl Into<String>) {
+        self.problems.push(Problem {
+            error: false,
+            message: message.into(),
+            fix: fix.into(),
+        });
+    }
+}
+
+pub fn run(args: DoctorArgs, global_args: GlobalArgs, color: ColorContext) -> Result<()> {
+    let config_file = global_args.config_file.clone();
+    let mut checks = Vec::new();
+
+    // the config is needed for the collection checks, so it can't come from
+    // the usual context
+    let mut config = Check::new("Config", "");
+    match crate::configure_context(global_args, color) {
+        Ok(ctx) => {
+            let file = config_file.unwrap_or_else(|| config_file_path(&ctx.base_path));
+            check_config(&mut config, &file)?;
+            checks.push(config);
+            checks.push(check_collection(&ctx));
+            checks.push(check_index(&ctx)?);
+            let (images, references) = check_recipes(&ctx)?;
+            checks.push(images);
+            checks.push(references);
+            checks.push(check_port(args.port));
+            checks.push(check_tools(&ctx));
+        }
+        Err(e) => {
+            config.error(
+                format!("{e:#}"),
+                "Fix the file with `chef config edit`, or `chef config edit --global` for the global one",
+            );
+            checks.push(config);
+            checks.push(check_port(args.port));
+        }
+    }
+
+    let mut errors = 0;
+    for check in &checks {
+        print_check(check);
+        errors += check.problems.iter().filter(|p| p.error).count();
+    }
+    if errors > 0 {
+        bail!("{errors} problems found");
+    }
+    Ok(())
+}
+
+fn print_check(check: &Check) {
+    if check.problems.is_empty() {
+        println!(
+            "{} {} {}",
+            "✔".green().bold(),
+            check.name.bold(),
+            check.summary.dim()
+        );
+        return;
+    }
+    if check.problems.iter().any(|p| p.error) {
+        println!("{} {}", "✘".red().bold(), check.name.bold());
+    } else {
+        println!("{} {}", "!".yellow().bold(), check.name.bold());
+    }
+    for problem in &check.problems {
+        if problem.error {
+            println!("    {}", problem.message.red());
+        } else {
+            println!("    {}", problem.message.yellow());
+        }
+        println!("      {} {}", "fix:".bold(), problem.fix);
+    }
+}
+
+fn check_config(check: &mut Check, collection_file: &Utf8Path) -> Result<()> {
+    let layers = Layers::load(Some(collection_file))?;
+    let files = layers
+        .iter()
+        .filter_map(|l| l.path.as_ref())
+        .map(|p| p.as_str())
+        .collect::<Vec<_>>();
+    check.summary = if files.is_empty() {
+        "No config files, using the defaults".to_string()
+    } else {
+        format!("Loaded from {}", files.join(", "))
+    };
+    Ok(())
+}
+
+fn check_collection(ctx: &Context) -> Check {
+    let base = &ctx.base_path;
+    let mut check = Check::new("Collection", format!("{base} is readable and writable"));
+    if !ctx.is_collection {
+        check.warning(
+            format!("{base} has no '{COOK_DIR}' dir, so it's not a collection"),
+            format!(
+                "Run `chef init {base}` to set it up, or use `--path` to point to a collection"
+            ),
+        );
+    }
+    if let Err(e) = base.read_dir_utf8() {
+        check.error(
+            format!("Can't read {base}: {e}"),
+            format!("Give your user read access, like `chmod u+rx {base}`"),
+        );
+    }
+    for dir in [base.to_owned(), base.join(COOK_DIR)] {
+        if !dir.is_dir() {
+            continue;
+        }
+        // asking for the permissions is not reliable across platforms, so
+        // try to write a file
+        let probe = dir.join(".chef-doctor");
+        match std::fs::write(&probe, b"") {
+            Ok(()) => {
+                let _ = std::fs::remove_file(&probe);
+            }
+            Err(e) => check.error(
+                format!("Can't write in {dir}: {e}"),
+                format!("Give your user write access, like `chmod u+w {dir}`"),
+            ),
+        }
+    }
+    check
+}
+
+fn check_index(ctx: &Context) -> Result<Check> {
+    let path = ctx.base_path.join(remote::INDEX_FILE);
+    let mut check = Check::new(
+        "Remote index",
+        format!(
+            "No {}, only needed to serve the collection as a remote",
+            remote::INDEX_FILE
+        ),
+    );
+    if !path.is_file() {
+        return Ok(check);
+    }
+    let fix = "Write it again with `chef collection index`";
+    let index: remote::Index = match std::fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|s| Ok(serde_json::from_str(&s)?))
+    {
+        Ok(index) => index,
+        Err(e) => {
+            check.error(format!("Can't read {path}: {e:#}"), fix);
+            return Ok(check);
+        }
+    };
+    let current = remote::build_index(&ctx.base_path, ctx.config.max_depth, &ctx.config.discovery)?;
+    let indexed = index.files.iter().collect::<BTreeSet<_>>();
+    let files = current.files.iter().collect::<BTreeSet<_>>();
+    let missing = files.difference(&indexed).count();
+    let gone = indexed.difference(&files).count();
+    if missing > 0 || gone > 0 {
+        check.warning(
+            format!(
+                "{path} is out of date: {missing} files missing and {gone} that no longer exist"
+            ),
+            fix,
+        );
+    }
+    check.summary = format!("{path} is up to date");
+    Ok(check)
+}
+
+/// A problem with the name of an image next to the recipes
+#[derive(Debug, PartialEq)]
+enum ImageIssue {
+    /// Of a recipe, but only with another case in the name or extension
+    Rename(String),
+    /// No recipe has that name
+    Orphan,
+}
+
+/// Checks the name of a file next to the recipes named `recipes`
+///
+/// Files that are not images have no issues.
+fn image_issue(file_name: &str, recipes: &[&str]) -> Option<ImageIssue> {
+    let (rest, ext) = file_name.rsplit_once('.')?;
+    let lower_ext = ext.to_lowercase();
+    if !IMAGE_EXTENSIONS.contains(&lower_ext.as_str()) {
+        return None;
+    }
+    // the step and section numbers, like `Bread.2.jpg` or `Bread.1.2.jpg`
+    let mut name = rest;
+    for _ in 0..2 {
+        match name.rsplit_once('.') {
+            Some((n, i)) if i.parse::<u16>().is_ok() => name = n,
+            _ => break,
+        }
+    }
+    let recipe = recipes.iter().find(|r| **r == name).or_else(|| {
+        recipes
+            .iter()
+            .find(|r| r.to_lowercase() == name.to_lowercase())
+    });
+    let Some(recipe) = recipe else {
+        return Some(ImageIssue::Orphan);
+    };
+    if *recipe == name && ext == lower_ext {
+        return None;
+    }
+    let indexes = &rest[name.len()..];
+    Some(ImageIssue::Rename(format!("{recipe}{indexes}.{lower_ext}")))
+}
+
+/// Checks the images and the references to other recipes
+fn check_recipes(ctx: &Context) -> Result<(Check, Check)> {
+    let entries = all_recipes(ctx)?.collect::<Vec<_>>();
+    let mut images = Check::new("Images", "");
+    let mut references = Check::new("Recipe references", "");
+
+    let mut dirs = BTreeMap::<Utf8PathBuf, Vec<&str>>::new();
+    for entry in &entries {
+        let path = plain_path(entry.path());
+        if let (Some(dir), Some(name)) = (path.parent(), path.file_stem()) {
+            dirs.entry(dir.to_owned()).or_default().push(name);
+        }
+    }
+    let mut n_images = 0;
+    for (dir, recipes) in &dirs {
+        for file in dir.read_dir_utf8()?.filter_map(|e| e.ok()) {
+            if !file.file_type().is_ok_and(|t| t.is_file()) {
+                continue;
+            }
+            let relative = file
+                .path()
+                .strip_prefix(&ctx.base_path)
+                .unwrap_or(file.path());
+            match image_issue(file.file_name(), recipes) {
+                Some(ImageIssue::Rename(name)) => images.warning(
+                    format!("{relative} is not found as an image of its recipe"),
+                    format!("Rename it to {name}, names and extensions are case sensitive"),
+                ),
+                Some(ImageIssue::Orphan) => images.warning(
+                    format!("{relative} is not named like any recipe"),
+                    "Rename it like its recipe, `Name.jpg`, `Name.<step>.jpg` or `Name.<section>.<step>.jpg`, or move it out",
+                ),
+                None => {}
+            }
+        }
+    }
+
+    let names = entries
+        .iter()
+        .map(|e| e.name().to_lowercase())
+        .collect::<Vec<_>>();
+    let mut n_references = 0;
+    for entry in parse_all(ctx, entries.clone())? {
+        let relative = entry
+            .path()
+            .strip_prefix(&ctx.base_path)
+            .unwrap_or(entry.path())
+            .to_owned();
+        let Some(recipe) = entry.parsed(ctx)?.output() else {
+            continue;
+        };
+
+        let found = recipe_images(entry.path());
+        n_images += found.len();
+        if let Err(errors) = check_recipe_images(&found, recipe) {
+            for e in errors {
+                images.warning(
+                    e.to_string(),
+                    "Rename the image to an existing step, numbers start at 1",
+                );
+            }
+        }
+
+        for igr in &recipe.ingredients {
+            if !igr.modifiers().contains(Modifiers::RECIPE) {
+                continue;
+            }
+            n_references += 1;
+            if ctx
+                .recipe_index
+                .resolve(&igr.name, entry.path().parent())
+                .is_ok()
+            {
+                continue;
+            }
+            let wanted = igr
+                .name
+                .rsplit(['/', '\\'])
+                .next()
+                .unwrap_or(&igr.name)
+                .to_lowercase();
+            let fix = match names.iter().position(|n| *n == wanted) {
+                Some(i) => {
+                    let path = entries[i].path();
+                    let path = path.strip_prefix(&ctx.base_path).unwrap_or(path);
+                    format!("Did you mean '{}'?", path.with_extension(""))
+                }
+                None => {
+                    "Create that recipe or fix the name, `chef list` shows the recipes".to_string()
+                }
+            };
+            references.error(format!("{relative}: recipe '{}' not found", igr.name), fix);
+        }
+    }
+    images.summary = format!("{n_images} images in {} dirs", dirs.len());
+    references.summary = format!("{n_references} references, all found");
+    Ok((images, references))
+}
+
+fn check_port(port: u16) -> Check {
+    let mut check = Check::new(
+        "Serve port",
+        format!("Port {port} is free for `chef serve`"),
+    );
+    if TcpListener::bind(("127.0.0.1", port)).is_err() {
+        let free = (port.saturating_add(1)..=port.saturating_add(100))
+            .find(|p| TcpListener::bind(("127.0.0.1", *p)).is_ok());
+        let fix = match free {
+            Some(free) => format!("Use another one, like `chef serve --port {free}`"),
+            None => "Stop what is using it or use another one with `chef serve --port`".to_string(),
+        };
+        check.warning(format!("Port {port} is in use"), fix);
+    }
+    check
+}
+
+fn check_tools(ctx: &Context) -> Check {
+    let mut check = Check::new("Tools", "");
+    let mut found = Vec::new();
+    let mut tool = |program: &str, needed: bool, used_for: &str| {
+        if find_program(program).is_some() {
+            found.push(program.to_string());
+            return;
+        }
+        let message = format!("`{program}` was not found, it's used {used_for}");
+        let fix = format!("Install `{program}` or add it to the PATH");
+        if needed {
+            check.error(message, fix);
+        } else {
+            check.warning(message, fix);
+        }
+    };
+
+    match ctx.chef_config.editor() {
+        Ok(editor) => tool(&editor[0], true, "to edit recipes and the config"),
+        Err(e) => tool("editor", true, &format!("to edit recipes, but {e}")),
+    }
+    let git_remotes = ctx
+        .chef_config
+        .remotes
+        .values()
+        .any(|r| r.kind() == RemoteKind::Git);
+    tool("git", git_remotes, "to sync git remotes and by `chef init`");
+    if let Some(encryption) = &ctx.config.encryption {
+        let program = match encryption.backend {
+            Backend::Age => "age",
+            Backend::Gpg => "gpg",
+        };
+        tool(program, true, "to encrypt and decrypt private recipes");
+    }
+    if let OcrConfig::Tesseract { command, .. } = ctx.chef_config.ocr.clone().unwrap_or_default() {
+        let needed = ctx.chef_config.ocr.is_some();
+        tool(&command, needed, "to import photos of recipes");
+    }
+    if ctx.config.session.is_some() {
+        tool("mosquitto_pub", true, "to share cook mode sessions");
+    }
+    if let Some(probe) = &ctx.chef_config.probe {
+        if matches!(probe.backend, BackendConfig::Mqtt { .. }) {
+            tool("mosquitto_sub", true, "to read the temperature probe");
+        }
+    }
+    check.summary = format!("Found {}", found.join(", "));
+    check
+}
+
+/// Path of a program, like the shell finds it
+fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_owned());
+    }
+    let windows_exe = cfg!(windows) && path.extension().is_none();
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| {
+            let file = dir.join(program);
+            if windows_exe {
+                ["exe", "cmd", "bat"]
+                    .map(|ext| file.with_extension(ext))
+                    .to_vec()
+            } else {
+                vec![file]
+            }
+        })
+        .find(|p| p.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_names() {
+        let recipes = ["Bread", "Pasta"];
+        assert_eq!(image_issue("Bread.jpg", &recipes), None);
+        assert_eq!(image_issue("Bread.1.2.png", &recipes), None);
+        assert_eq!(image_issue("Bread.cook", &recipes), None);
+        assert_eq!(
+            image_issue("Bread.3.JPG", &recipes),
+            Some(ImageIssue::Rename("Bread.3.jpg".into()))
+        );
+        assert_eq!(
+            image_issue("pasta.webp", &recipes),
+            Some(ImageIssue::Rename("Pasta.webp".into()))
+        );
+        assert_eq!(image_issue("Soup.jpg", &recipes), Some(ImageIssue::Orphan));
+    }
+}
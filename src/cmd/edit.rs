@@ -1,33 +1,76 @@
+use std::io::Write;
+
 use anyhow::{Context as _, Result};
+use camino::Utf8Path;
 use clap::Args;
+use cooklang_fs::RecipeEntry;
 
-use crate::Context;
+use crate::{
+    encryption, hooks,
+    util::{resolve_recipe, run_editor},
+    Context,
+};
 
 #[derive(Debug, Args)]
 pub struct EditArgs {
-    /// Recipe name
+    /// Recipe name, can be fuzzy
     name: String,
 }
 
 pub fn run(args: EditArgs, ctx: &Context) -> Result<()> {
-    let entry = ctx.recipe_index.resolve(&args.name, None)?;
-    let path = entry.path();
-
-    let editor = ctx
-        .chef_config
-        .editor()
-        .context("Could not determine editor")?;
-    let (cmd, args) = editor.split_first().expect("empty editor cmd");
-
-    let ok = std::process::Command::new(cmd)
-        .args(args)
-        .arg(path)
-        .status()?
-        .success();
-
-    if !ok {
-        tracing::warn!("Editor didn't exit successfully")
+    let entry = resolve_recipe(ctx, &args.name)?;
+    edit_recipe(ctx, &entry)
+}
+
+/// Opens a recipe with the configured editor, decrypting it if needed
+pub fn edit_recipe(ctx: &Context, entry: &RecipeEntry) -> Result<()> {
+    if entry.is_encrypted() {
+        edit_encrypted(ctx, entry.path(), &entry.read()?.into_text())
+    } else {
+        open_editor(ctx, entry.path())
+    }
+}
+
+/// Opens a file with the configured editor and waits for it to exit
+pub fn open_editor(ctx: &Context, path: &Utf8Path) -> Result<()> {
+    if run_editor(ctx, path)? {
+        hooks::fire(
+            ctx,
+            hooks::Event::RecipeSaved {
+                path: path.as_str(),
+            },
+        );
     }
+    Ok(())
+}
 
+/// Edits a decrypted copy and encrypts it back if it changed
+///
+/// The copy lives in the temp dir only while the editor is open, with a
+/// unique name and only readable by the user. It's removed when dropped,
+/// even on errors.
+fn edit_encrypted(ctx: &Context, path: &Utf8Path, text: &str) -> Result<()> {
+    let name = cooklang_fs::plain_path(path)
+        .file_name()
+        .unwrap_or("recipe.cook");
+    let mut tmp = tempfile::Builder::new()
+        .prefix("chef-")
+        .suffix(&format!("-{name}"))
+        .tempfile()
+        .context("Failed to create a temp file")?;
+    tmp.write_all(text.as_bytes())?;
+    tmp.flush()?;
+    let tmp_path = Utf8Path::from_path(tmp.path()).context("Temp file path is not UTF-8")?;
+
+    if run_editor(ctx, tmp_path)? {
+        let new = std::fs::read_to_string(tmp_path)?;
+        encryption::write_recipe(ctx, path, &new)?;
+        hooks::fire(
+            ctx,
+            hooks::Event::RecipeSaved {
+                path: path.as_str(),
+            },
+        );
+    }
     Ok(())
 }
@@ -0,0 +1,62 @@
+use anyhow::{bail, Result};
+use camino::Utf8PathBuf;
+use clap::Args;
+use cooklang_fs::RecipeEntry;
+use yansi::Paint;
+
+use crate::{encryption, util::all_recipes, Context};
+
+#[derive(Debug, Args)]
+pub struct EncryptArgs {
+    /// Recipe files to encrypt
+    ///
+    /// If none are given, the recipes of the collection marked as private
+    /// are encrypted, or all of them with `all` in the config.
+    files: Vec<Utf8PathBuf>,
+    /// Decrypt the files back to plain text
+    #[arg(short, long, requires = "files")]
+    decrypt: bool,
+}
+
+pub fn run(ctx: &Context, args: EncryptArgs) -> Result<()> {
+    if args.decrypt {
+        for file in &args.files {
+            let plain = encryption::decrypt_to_plain(file)?;
+            eprintln!("{} {plain}", "Decrypted".green().bold());
+        }
+        return Ok(());
+    }
+
+    if ctx.config.encryption.is_none() {
+        bail!("Encryption is not configured, add an `[encryption]` table to the config");
+    }
+
+    let entries: Vec<RecipeEntry> = if args.files.is_empty() {
+        if !ctx.is_collection {
+            bail!("`encrypt` without files needs to run inside a collection");
+        }
+        all_recipes(ctx)?.filter(|e| !e.is_encrypted()).collect()
+    } else {
+        args.files.iter().map(RecipeEntry::new).collect()
+    };
+    let explicit = !args.files.is_empty();
+
+    let mut count = 0;
+    for entry in entries {
+        if entry.is_encrypted() {
+            tracing::warn!("Already encrypted: {}", entry.path());
+            continue;
+        }
+        let text = entry.read()?.into_text();
+        if !explicit && !encryption::is_private(ctx, &text)? {
+            continue;
+        }
+        let path = encryption::encrypt_recipe(ctx, entry.path(), &text)?;
+        eprintln!("{} {path}", "Encrypted".green().bold());
+        count += 1;
+    }
+    if count == 0 && !explicit {
+        eprintln!("No private recipes to encrypt");
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use anstream::println;
+use anyhow::{bail, Result};
+use camino::Utf8PathBuf;
+use clap::Args;
+use yansi::Paint;
+
+use crate::{
+    encryption, hooks,
+    source_edit::{remove_ingredient, rename_ingredient, scale_quantities, set_metadata},
+    util::{resolve_recipe, write_diff, Input},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct ForkArgs {
+    /// Recipe to fork
+    recipe: String,
+
+    /// Name of the new recipe, defaults to "<recipe> (fork)"
+    ///
+    /// Split directories with "/". It's relative to the collection.
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// Multiply the ingredient quantities and servings
+    #[arg(short, long)]
+    scale: Option<f64>,
+
+    /// Replace an ingredient with another one
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_swap)]
+    swap: Vec<(String, String)>,
+
+    /// Remove an ingredient from the recipe
+    #[arg(long, value_name = "INGREDIENT")]
+    remove: Vec<String>,
+
+    /// Show the diff with the original recipe instead of writing it
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+}
+
+fn parse_swap(s: &str) -> Result<(String, String), String> {
+    let (from, to) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected FROM=TO, found '{s}'"))?;
+    let (from, to) = (from.trim(), to.trim());
+    if from.is_empty() || to.is_empty() || to.contains(['@', '#', '~', '{', '}', '|', '\n']) {
+        return Err(format!("invalid swap '{s}'"));
+    }
+    Ok((from.to_string(), to.to_string()))
+}
+
+pub fn run(ctx: &Context, args: ForkArgs) -> Result<()> {
+    let input = Input::File {
+        entry: resolve_recipe(ctx, &args.recipe)?,
+        override_name: None,
+    };
+    let source_path = input.path().expect("file input").to_owned();
+    let original = input.text()?.into_owned();
+    let mut text = original.clone();
+    let mut changes = Vec::new();
+
+    if let Some(factor) = args.scale {
+        if !(factor.is_finite() && factor > 0.0) {
+            bail!("The scale must be a positive number");
+        }
+        let recipe = input.parse(ctx)?;
+        let (scaled, count) = scale_quantities(&text, factor);
+        text = scaled;
+        if let Some(servings) = recipe.metadata.servings().filter(|s| s.len() == 1) {
+            let servings = (servings[0] as f64 * factor).round().max(1.0);
+            text = set_metadata(&text, "servings", &servings.to_string());
+        }
+        tracing::debug!("Scaled {count} quantities");
+        changes.push(format!("scale {factor}"));
+    }
+    for (from, to) in &args.swap {
+        let (new, count) = rename_ingredient(&text, from, to);
+        if count == 0 {
+            bail!("No ingredient named '{from}' found");
+        }
+        text = new;
+        changes.push(format!("swap {from}={to}"));
+    }
+    for name in &args.remove {
+        let (new, count) = remove_ingredient(&text, name);
+        if count == 0 {
+            bail!("No ingredient named '{name}' found");
+        }
+        text = new;
+        changes.push(format!("remove {name}"));
+    }
+
+    let source = source_path
+        .strip_prefix(&ctx.base_path)
+        .unwrap_or(&source_path);
+    text = set_metadata(&text, "forked from", &yaml_string(source.as_str()));
+    if !changes.is_empty() {
+        text = set_metadata(&text, "changes", &yaml_string(&changes.join(", ")));
+    }
+
+    if args.dry_run {
+        write_diff(anstream::stdout().lock(), &original, &text)?;
+        return Ok(());
+    }
+
+    let path = match &args.name {
+        Some(name) => {
+            let name = name.strip_suffix(".cook").unwrap_or(name);
+            let file = Utf8PathBuf::from(format!("{name}.cook"));
+            let valid = !file.is_absolute()
+                && file
+                    .components()
+                    .all(|c| matches!(c, camino::Utf8Component::Normal(_)));
+            if !valid {
+                bail!("Invalid name: {name}");
+            }
+            ctx.base_path.join(file)
+        }
+        None => {
+            let stem = cooklang_fs::plain_path(&source_path)
+                .file_stem()
+                .unwrap_or("recipe");
+            source_path.with_file_name(format!("{stem} (fork).cook"))
+        }
+    };
+    if path.exists() {
+        bail!("File already exists: {path}");
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let path = encryption::write_recipe(ctx, &path, &text)?;
+    println!("{} {path}", "Created".green());
+    if ctx.parser()?.parse(&text).into_report().has_errors() {
+        tracing::warn!("The forked recipe has errors, check it with `chef check`");
+    }
+    hooks::fire(
+        ctx,
+        hooks::Event::RecipeSaved {
+            path: path.as_str(),
+        },
+    );
+    Ok(())
+}
+
+/// Quotes a string if it's not plain YAML
+fn yaml_string(s: &str) -> String {
+    serde_yaml::to_string(s)
+        .map(|y| y.trim_end().to_string())
+        .unwrap_or_else(|_| s.to_string())
+}
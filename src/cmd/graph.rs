@@ -0,0 +1,65 @@
+use std::io::Write;
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+use clap::{Args, ValueEnum};
+
+use crate::{
+    graph,
+    util::{meta_name, resolve_recipe, write_to_output, Input},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct GraphArgs {
+    /// Recipe to graph
+    recipe: String,
+
+    /// Output file, none for stdout
+    #[arg(short, long)]
+    output: Option<Utf8PathBuf>,
+
+    /// Output format
+    ///
+    /// Tries to infer it from output file extension. Defaults to "dot".
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Dot,
+    #[value(alias("mmd"))]
+    Mermaid,
+    Json,
+}
+
+pub fn run(ctx: &Context, args: GraphArgs) -> Result<()> {
+    let input = Input::File {
+        entry: resolve_recipe(ctx, &args.recipe)?,
+        override_name: None,
+    };
+    let recipe = input.parse(ctx)?.default_scale();
+    let name = meta_name(&recipe.metadata)
+        .unwrap_or(input.name()?)
+        .to_string();
+    let graph = graph::build(&recipe, ctx.parser()?.converter());
+
+    let format = args.format.unwrap_or_else(|| match args.output.as_ref() {
+        Some(p) => match p.extension() {
+            Some("json") => OutputFormat::Json,
+            Some("mmd") => OutputFormat::Mermaid,
+            _ => OutputFormat::Dot,
+        },
+        None => OutputFormat::Dot,
+    });
+
+    write_to_output(args.output.as_deref(), |mut w| {
+        match format {
+            OutputFormat::Dot => write!(w, "{}", graph.to_dot(&name))?,
+            OutputFormat::Mermaid => write!(w, "{}", graph.to_mermaid())?,
+            OutputFormat::Json => serde_json::to_writer_pretty(&mut w, &graph)?,
+        }
+        Ok(())
+    })
+}
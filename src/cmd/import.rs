@@ -0,0 +1,91 @@
+use anyhow::{bail, Context as _, Result};
+use camino::Utf8PathBuf;
+use clap::Args;
+
+use crate::{
+    cmd::{edit::open_editor, new::new_recipe_path},
+    hooks,
+    import::{self, Imported},
+    ocr::Ocr,
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// URL or file of the recipe
+    ///
+    /// A `.cook` file, a `chef share` link, a web page with a schema.org
+    /// recipe or a `.txt` file with the recipe in plain text.
+    #[arg(required_unless_present = "image")]
+    source: Option<String>,
+
+    /// Photo or scan of a recipe, its text is read with OCR
+    #[arg(long, conflicts_with = "source")]
+    image: Option<Utf8PathBuf>,
+
+    /// Name of the new recipe, taken from the recipe if not given
+    ///
+    /// Split directories with "/"
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// Skip opening the editor to review it
+    #[arg(long, short = 'E')]
+    no_edit: bool,
+}
+
+pub fn run(ctx: &Context, args: ImportArgs) -> Result<()> {
+    let (imported, stem) = if let Some(image) = &args.image {
+        let ocr = ctx.chef_config.ocr.clone().unwrap_or_default();
+        let text = ocr.recognize(image)?;
+        if text.trim().is_empty() {
+            bail!("No text found in {image}");
+        }
+        (
+            import::from_plain_text(&text),
+            image.file_stem().map(String::from),
+        )
+    } else {
+        let source = args.source.as_deref().expect("required by clap");
+        let path = Utf8PathBuf::from(source);
+        if path.is_file() {
+            let text =
+                std::fs::read_to_string(&path).with_context(|| format!("Failed to read {path}"))?;
+            let imported = if path.extension() == Some("txt") {
+                import::from_plain_text(&text)
+            } else {
+                import::from_text(&text)?
+            };
+            (imported, path.file_stem().map(String::from))
+        } else {
+            (import::from_url(source)?, None)
+        }
+    };
+    let Imported { name, text } = imported;
+
+    // names in the recipe are not paths
+    let name = name.map(|n| n.replace(['/', '\\', ':'], "-"));
+    let Some(name) = args.name.or(name).or(stem) else {
+        bail!("The recipe has no name, give it one with `--name`");
+    };
+    let path = new_recipe_path(ctx, &name)?;
+    std::fs::write(&path, &text).with_context(|| format!("Failed to write {path}"))?;
+
+    let report = ctx.parser()?.parse(&text).into_report();
+    if !report.is_empty() {
+        report.eprint(path.as_str(), &text, ctx.color.color_stderr)?;
+    }
+    eprintln!("Imported to {path}");
+
+    if args.no_edit {
+        hooks::fire(
+            ctx,
+            hooks::Event::RecipeSaved {
+                path: path.as_str(),
+            },
+        );
+    } else {
+        open_editor(ctx, &path)?;
+    }
+    Ok(())
+}
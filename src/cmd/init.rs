@@ -0,0 +1,207 @@
+use std::{fs, io::IsTerminal, process::Command};
+
+use anyhow::{bail, Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use cooklang::convert::System;
+use inquire::{Confirm, Text};
+use yansi::Paint;
+
+use crate::{
+    config::{
+        config_file_path, global_load, global_store, store_at_path, ChefConfig, Config, AUTO_AISLE,
+        CHEF_CONFIG_FILE,
+    },
+    locale::unit_system,
+    COOK_DIR, UTF8_PATH_PANIC,
+};
+
+#[derive(Debug, Args)]
+pub struct InitArgs {
+    /// Directory of the new collection, asked if not given
+    path: Option<Utf8PathBuf>,
+
+    /// Locale of the numbers and units, like `en-US`, asked if not given
+    #[arg(long)]
+    locale: Option<String>,
+
+    /// Accept the defaults instead of asking
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Make it the default collection without asking
+    ///
+    /// With `--yes` or without a terminal the global config is only changed
+    /// with this.
+    #[arg(long)]
+    default: bool,
+
+    /// Create the collection even if the directory is not empty
+    #[arg(long)]
+    force: bool,
+}
+
+const EXAMPLE_RECIPE: &str = "Tomato pasta.cook";
+
+pub fn run(args: InitArgs) -> Result<()> {
+    // without a terminal there is no one to ask
+    let ask = !args.yes && std::io::stdin().is_terminal();
+    let chef = "chef".green().italic().to_string();
+
+    let path = match args.path {
+        Some(path) => path,
+        None if ask => Text::new("Where do you want your recipes?")
+            .with_initial_value(default_path().as_str())
+            .prompt()
+            .map(Utf8PathBuf::from)?,
+        None => default_path(),
+    };
+    create_dir(&path, args.force)?;
+
+    let locale = match args.locale {
+        Some(locale) => Some(locale),
+        None if ask => Text::new("Locale for the numbers and units:")
+            .with_initial_value(&env_locale().unwrap_or_default())
+            .with_help_message("Like `en-US`, its unit system is used. Leave empty for none")
+            .prompt_skippable()?
+            .filter(|s| !s.trim().is_empty()),
+        None => env_locale(),
+    };
+    let system = locale.as_deref().map_or(System::Metric, unit_system);
+    if let Some(locale) = &locale {
+        let name = match system {
+            System::Metric => "metric",
+            System::Imperial => "imperial",
+        };
+        println!("Using {locale} with the {name} unit system");
+    }
+
+    let config = Config {
+        locale,
+        ..Default::default()
+    };
+    store_at_path(config_file_path(&path), &config)?;
+
+    let aisle = path.join(COOK_DIR).join(AUTO_AISLE);
+    if !aisle.exists() {
+        fs::write(&aisle, AISLE).with_context(|| format!("Failed to write {aisle}"))?;
+    }
+    let recipe = path.join(EXAMPLE_RECIPE);
+    if !recipe.exists() {
+        fs::write(&recipe, example_recipe(system))
+            .with_context(|| format!("Failed to write {recipe}"))?;
+    }
+
+    let git = !ask
+        || Confirm::new("Track the recipes with git?")
+            .with_default(true)
+            .prompt()?;
+    if git {
+        init_git(&path);
+    }
+
+    let set_default = args.default
+        || ask
+            && Confirm::new("Make it the default collection, used from any directory?")
+                .with_default(true)
+                .prompt()?;
+    if set_default {
+        let mut chef_config: ChefConfig = global_load(CHEF_CONFIG_FILE)?;
+        chef_config.default_collection = Some(path.canonicalize_utf8()?);
+        global_store(CHEF_CONFIG_FILE, &chef_config)?;
+    }
+
+    println!();
+    println!("{chef} is ready in {}", path.yellow());
+    println!("Try it with:");
+    let example = EXAMPLE_RECIPE.trim_end_matches(".cook");
+    println!("  chef --path {path} recipe \"{example}\"");
+    println!("  chef --path {path} serve --open");
+    Ok(())
+}
+
+fn default_path() -> Utf8PathBuf {
+    let dirs = directories::UserDirs::new();
+    let parent = match &dirs {
+        Some(d) => d.document_dir().unwrap_or(d.home_dir()),
+        None => std::path::Path::new("."),
+    };
+    Utf8PathBuf::from_path_buf(parent.join("Recipes")).expect(UTF8_PATH_PANIC)
+}
+
+/// Locale of the environment, like `en-US` from `LANG=en_US.UTF-8`
+fn env_locale() -> Option<String> {
+    let lang = ["LC_ALL", "LANG"]
+        .iter()
+        .filter_map(|v| std::env::var(v).ok())
+        .find(|v| !v.is_empty())?;
+    let locale = lang.split('.').next()?.replace('_', "-");
+    (locale != "C" && locale != "POSIX").then_some(locale)
+}
+
+fn create_dir(path: &Utf8Path, force: bool) -> Result<()> {
+    if path.exists() {
+        if !path.is_dir() {
+            bail!("The path is not a dir: {path}");
+        }
+        if path.join(COOK_DIR).is_dir() {
+            bail!("There is already a collection in {path}");
+        }
+        if !force && path.read_dir()?.any(|_| true) {
+            bail!("The dir is not empty, use `--force` to use it anyway: {path}");
+        }
+    }
+    fs::create_dir_all(path.join(COOK_DIR))
+        .with_context(|| format!("Failed to create the collection in {path}"))
+}
+
+fn init_git(path: &Utf8Path) {
+    if path.join(".git").exists() {
+        return;
+    }
+    match Command::new("git").arg("init").arg("-q").arg(path).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => tracing::warn!("`git init` failed: {status}"),
+        Err(e) => tracing::warn!("Could not run git, is it installed? {e}"),
+    }
+}
+
+fn example_recipe(system: System) -> String {
+    let (pasta, tomatoes, cheese) = match system {
+        System::Imperial => ("7%oz", "14%oz", "1%oz"),
+        System::Metric => ("200%g", "400%g", "30%g"),
+    };
+    format!(
+        "\
+>> servings: 2
+>> time: 25 min
+>> tags: example, pasta
+>> course: main
+
+Bring a #pot{{}} of salted water to a boil and cook the @pasta{{{pasta}}} for ~{{10%minutes}}.
+
+Meanwhile, gently fry the @garlic{{2%cloves}}(sliced) in the @olive oil{{2%tbsp}}. \
+Add the @canned tomatoes{{{tomatoes}}} and a pinch of @salt and simmer for ~{{10%minutes}}.
+
+Drain the pasta, mix it with the sauce and serve with @parmesan{{{cheese}}}(grated).
+"
+    )
+}
+
+/// Example aisle file, with the ingredients of the example recipe
+const AISLE: &str = "\
+[produce]
+garlic
+onion
+tomato
+
+[dairy]
+butter
+parmesan
+
+[pantry]
+canned tomatoes
+olive oil
+pasta
+salt
+";
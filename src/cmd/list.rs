@@ -1,9 +1,19 @@
-use anstream::print;
+use std::cmp::Reverse;
+
+use anstream::{print, println};
 use anyhow::{bail, Result};
-use clap::{builder::ArgPredicate, Args};
-use cooklang_fs::all_recipes;
+use clap::{builder::ArgPredicate, Args, ValueEnum};
+use serde_json::{json, Value};
 
-use crate::{util::CachedRecipeEntry, Context};
+use crate::{
+    cmd::search::{matches, parse_term},
+    cook_log::{recipe_key, sort_by_dates, CookLog, RecipeDates, RecipeOrder},
+    course::{course_key, recipe_course},
+    diet::DietData,
+    pantry::Date,
+    util::{all_recipes, meta_name, parse_all, CachedRecipeEntry},
+    Context,
+};
 
 #[derive(Debug, Args)]
 pub struct ListArgs {
@@ -48,6 +58,109 @@ pub struct ListArgs {
     /// Force to list recipes even outside a collection
     #[arg(short, long)]
     force: bool,
+
+    /// Order of the recipes
+    ///
+    /// Times, servings and ingredients go from less to more, the dates from
+    /// the last one and the courses like in a menu, from the starters.
+    /// Recipes without the value go at the end.
+    #[arg(short, long, value_enum, default_value_t = ListSort::Name)]
+    sort: ListSort,
+
+    /// Reverse the order
+    #[arg(short, long)]
+    reverse: bool,
+
+    /// Only recipes matching a term, like `chef search`
+    ///
+    /// Can be given more than once, all of them have to match.
+    #[arg(long, value_name = "TERM")]
+    filter: Vec<String>,
+
+    /// Columns to show, separated by commas
+    ///
+    /// Defaults to all but the dates with `--format json` or `csv`.
+    #[arg(
+        short = 'C',
+        long,
+        value_enum,
+        value_delimiter = ',',
+        conflicts_with = "count"
+    )]
+    columns: Vec<Column>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, conflicts_with = "count")]
+    format: OutputFormat,
+
+    /// Only recipes that are not in the cook log
+    #[arg(long)]
+    never_cooked: bool,
+
+    /// Show when the recipes were added and last cooked
+    #[arg(short, long)]
+    dates: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ListSort {
+    Name,
+    Newest,
+    LastCooked,
+    Time,
+    Servings,
+    Ingredients,
+    Modified,
+    Course,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Column {
+    Name,
+    Path,
+    Tags,
+    /// Total time in minutes
+    Time,
+    Servings,
+    /// Number of listed ingredients
+    Ingredients,
+    /// Day of the last change of the file
+    Modified,
+    Added,
+    LastCooked,
+}
+
+const DEFAULT_COLUMNS: &[Column] = &[
+    Column::Name,
+    Column::Path,
+    Column::Tags,
+    Column::Time,
+    Column::Servings,
+    Column::Ingredients,
+    Column::Modified,
+];
+
+impl Column {
+    fn name(self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Path => "path",
+            Column::Tags => "tags",
+            Column::Time => "time",
+            Column::Servings => "servings",
+            Column::Ingredients => "ingredients",
+            Column::Modified => "modified",
+            Column::Added => "added",
+            Column::LastCooked => "last_cooked",
+        }
+    }
 }
 
 pub fn run(ctx: &Context, args: ListArgs) -> Result<()> {
@@ -55,8 +168,54 @@ pub fn run(ctx: &Context, args: ListArgs) -> Result<()> {
         bail!("`list` needs to run inside a collection or pass `--force`");
     }
 
-    let iter = all_recipes(&ctx.base_path, ctx.config.max_depth)?.filter_map(|entry| {
-        let entry = CachedRecipeEntry::new(entry);
+    let needs_dates = matches!(args.sort, ListSort::Newest | ListSort::LastCooked)
+        || args
+            .columns
+            .iter()
+            .any(|c| matches!(c, Column::Added | Column::LastCooked));
+    let log = if needs_dates || args.never_cooked || args.dates {
+        CookLog::load(&ctx.base_path)?
+    } else {
+        CookLog::default()
+    };
+    let entries = all_recipes(ctx)?
+        .filter(|e| {
+            !args.never_cooked
+                || log
+                    .last_cooked(&recipe_key(e.path(), &ctx.base_path))
+                    .is_none()
+        })
+        .collect::<Vec<_>>();
+    let diet;
+    let filter = if args.filter.is_empty() {
+        None
+    } else {
+        diet = DietData::load(&ctx.config, &ctx.base_path)?;
+        let terms = args
+            .filter
+            .iter()
+            .map(|t| parse_term(t, &diet))
+            .collect::<Result<Vec<_>>>()?;
+        Some((terms, &diet))
+    };
+    let full_parse = args.check
+        || filter.is_some()
+        || args.sort == ListSort::Ingredients
+        || args.columns.contains(&Column::Ingredients)
+        || (args.columns.is_empty() && args.format != OutputFormat::Table);
+    let entries = if full_parse {
+        parse_all(ctx, entries)?
+    } else {
+        entries.into_iter().map(CachedRecipeEntry::new).collect()
+    };
+    let iter = entries.into_iter().filter_map(|entry| {
+        if let Some((terms, diet)) = &filter {
+            let recipe = entry.parsed(ctx).ok()?.valid_output()?;
+            let name = meta_name(&recipe.metadata).unwrap_or(entry.name());
+            if !matches(terms, diet, name, recipe) {
+                return None;
+            }
+        }
         if args.tag.is_empty() {
             return Some(entry);
         }
@@ -114,11 +273,32 @@ pub fn run(ctx: &Context, args: ListArgs) -> Result<()> {
         }
         print!("{table}");
     } else {
-        let mut table = tabular::Table::new("{:<}{:<}{:<}{:<}");
         let mut all = iter.collect::<Vec<_>>();
         all.sort_unstable_by(|a, b| a.path().cmp(b.path()));
+        sort_entries(ctx, &mut all, args.sort, &log)?;
+        if args.reverse {
+            all.reverse();
+        }
+
+        if !args.columns.is_empty() || args.format != OutputFormat::Table {
+            let columns = if args.columns.is_empty() {
+                DEFAULT_COLUMNS
+            } else {
+                &args.columns
+            };
+            return print_columns(ctx, &all, columns, args.format, &log);
+        }
+
+        let mut table = tabular::Table::new("{:<}{:<}{:<}{:<}{:<}");
+        let today = Date::today();
         for entry in &all {
-            let row = list_row(ctx, &args, entry)?;
+            let mut row = list_row(ctx, &args, entry)?;
+            if args.dates {
+                let dates = RecipeDates::new(&log, entry.path(), &ctx.base_path);
+                row.add_ansi_cell(dates_str(dates, today));
+            } else {
+                row.add_cell("");
+            }
             table.add_row(row);
         }
         print!("{table}");
@@ -127,6 +307,168 @@ pub fn run(ctx: &Context, args: ListArgs) -> Result<()> {
     Ok(())
 }
 
+fn sort_entries(
+    ctx: &Context,
+    entries: &mut [CachedRecipeEntry],
+    sort: ListSort,
+    log: &CookLog,
+) -> Result<()> {
+    let converter = ctx.parser()?.converter();
+    let dates = |e: &CachedRecipeEntry| RecipeDates::new(log, e.path(), &ctx.base_path);
+    // `None` sorts first, so the key is if the value is missing first
+    match sort {
+        ListSort::Name => {}
+        ListSort::Newest => sort_by_dates(entries, RecipeOrder::Newest, dates),
+        ListSort::LastCooked => sort_by_dates(entries, RecipeOrder::LastCooked, dates),
+        ListSort::Time => entries.sort_by_cached_key(|e| {
+            let time = e
+                .metadata(ctx, false)
+                .ok()
+                .and_then(|m| m.time(converter))
+                .map(|t| t.total());
+            (time.is_none(), time)
+        }),
+        ListSort::Servings => entries.sort_by_cached_key(|e| {
+            let servings = e
+                .metadata(ctx, false)
+                .ok()
+                .and_then(|m| m.servings())
+                .and_then(|s| s.first().copied());
+            (servings.is_none(), servings)
+        }),
+        ListSort::Ingredients => entries.sort_by_cached_key(|e| {
+            let count = ingredient_count(ctx, e);
+            (count.is_none(), count)
+        }),
+        ListSort::Course => entries.sort_by_cached_key(|e| {
+            let course = e.metadata(ctx, false).ok().and_then(recipe_course);
+            course_key(course.as_deref())
+        }),
+        ListSort::Modified => entries.sort_by_cached_key(|e| {
+            let modified = modified(e);
+            (modified.is_none(), Reverse(modified))
+        }),
+    }
+    Ok(())
+}
+
+fn ingredient_count(ctx: &Context, entry: &CachedRecipeEntry) -> Option<usize> {
+    let recipe = entry.parsed(ctx).ok()?.valid_output()?;
+    let count = recipe
+        .ingredients
+        .iter()
+        .filter(|i| i.modifiers().should_be_listed())
+        .count();
+    Some(count)
+}
+
+fn modified(entry: &CachedRecipeEntry) -> Option<Date> {
+    let time = entry.path().metadata().ok()?.modified().ok()?;
+    Some(Date::from_time(time))
+}
+
+fn column_value(ctx: &Context, entry: &CachedRecipeEntry, column: Column, log: &CookLog) -> Value {
+    let metadata = entry.metadata(ctx, false).ok();
+    match column {
+        Column::Name => json!(metadata.and_then(meta_name).unwrap_or(entry.name())),
+        Column::Path => {
+            let path = entry.path();
+            json!(path
+                .strip_prefix(&ctx.base_path)
+                .unwrap_or(path)
+                .as_str()
+                .replace('\\', "/"))
+        }
+        Column::Tags => json!(metadata.and_then(|m| m.tags()).unwrap_or_default()),
+        Column::Time => {
+            let converter = ctx.parser().ok().map(|p| p.converter());
+            json!(metadata
+                .zip(converter)
+                .and_then(|(m, c)| m.time(c))
+                .map(|t| t.total()))
+        }
+        Column::Servings => json!(metadata.and_then(|m| m.servings())),
+        Column::Ingredients => json!(ingredient_count(ctx, entry)),
+        Column::Modified => json!(modified(entry).map(|d| d.to_string())),
+        Column::Added => json!(crate::cook_log::added(entry.path()).map(|d| d.to_string())),
+        Column::LastCooked => json!(log
+            .last_cooked(&recipe_key(entry.path(), &ctx.base_path))
+            .map(|d| d.to_string())),
+    }
+}
+
+/// Text of a value for the table and CSV
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(value_text).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+fn print_columns(
+    ctx: &Context,
+    entries: &[CachedRecipeEntry],
+    columns: &[Column],
+    format: OutputFormat,
+    log: &CookLog,
+) -> Result<()> {
+    let rows = entries
+        .iter()
+        .map(|e| {
+            columns
+                .iter()
+                .map(|&c| column_value(ctx, e, c, log))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        OutputFormat::Table => {
+            use yansi::Paint;
+
+            let spec = vec!["{:<}"; columns.len()].join("  ");
+            let mut table = tabular::Table::new(&spec);
+            let mut header = tabular::Row::new();
+            for c in columns {
+                header.add_ansi_cell(c.name().bold());
+            }
+            table.add_row(header);
+            for row in &rows {
+                let mut r = tabular::Row::new();
+                for value in row {
+                    r.add_cell(value_text(value));
+                }
+                table.add_row(r);
+            }
+            print!("{table}");
+        }
+        OutputFormat::Json => {
+            let objects = rows
+                .into_iter()
+                .map(|row| {
+                    columns
+                        .iter()
+                        .map(|c| c.name().to_string())
+                        .zip(row)
+                        .collect::<serde_json::Map<_, _>>()
+                })
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string_pretty(&objects)?);
+        }
+        OutputFormat::Csv => {
+            let mut csv = csv::Writer::from_writer(std::io::stdout().lock());
+            csv.write_record(columns.iter().map(|c| c.name()))?;
+            for row in &rows {
+                csv.write_record(row.iter().map(value_text))?;
+            }
+            csv.flush()?;
+        }
+    }
+    Ok(())
+}
+
 fn list_row(ctx: &Context, args: &ListArgs, entry: &CachedRecipeEntry) -> Result<tabular::Row> {
     use yansi::Paint;
 
@@ -188,6 +530,19 @@ fn list_row(ctx: &Context, args: &ListArgs, entry: &CachedRecipeEntry) -> Result
     Ok(row)
 }
 
+fn dates_str(dates: RecipeDates, today: Date) -> String {
+    use yansi::Paint;
+
+    let added = dates
+        .added
+        .map_or_else(|| "-".to_string(), |d| d.ago(today));
+    let cooked = match dates.last_cooked {
+        Some(d) => format!("cooked {}", d.ago(today)),
+        None => "never cooked".dim().to_string(),
+    };
+    format!(" [added {added}, {cooked}]")
+}
+
 fn check_str(ctx: &Context, entry: &CachedRecipeEntry) -> String {
     use yansi::Paint;
 
@@ -0,0 +1,151 @@
+use anstream::println;
+use anyhow::Result;
+use clap::{Args, Subcommand, ValueEnum};
+use yansi::Paint;
+
+use crate::{
+    cook_log::CookLog,
+    log_stats::{compute, recipe_facts, LogStats, StatsOptions},
+    pantry::Date,
+    prices::format_cost,
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct LogArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// What you cook: how often, streaks, cuisines, cost and calories
+    Stats(StatsArgs),
+}
+
+#[derive(Debug, Args)]
+struct StatsArgs {
+    /// Weeks of the cost and calories averages
+    #[arg(short, long, default_value_t = StatsOptions::default().weeks, value_parser = clap::value_parser!(u32).range(1..))]
+    weeks: u32,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+pub fn run(ctx: &Context, args: LogArgs) -> Result<()> {
+    match args.command {
+        Command::Stats(args) => stats(ctx, args),
+    }
+}
+
+fn stats(ctx: &Context, args: StatsArgs) -> Result<()> {
+    let log = CookLog::load(&ctx.base_path)?;
+    let (facts, currency) = recipe_facts(
+        &ctx.config,
+        &ctx.chef_config.plugins,
+        &ctx.base_path,
+        ctx.parser()?,
+        &log,
+    )?;
+    let options = StatsOptions {
+        weeks: args.weeks,
+        ..Default::default()
+    };
+    let stats = compute(&log, &facts, currency, Date::today(), &options);
+
+    match args.format {
+        OutputFormat::Human => print_human(&stats),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+    }
+    Ok(())
+}
+
+/// Width of the longest bar
+const BAR_WIDTH: usize = 30;
+
+fn bar(count: usize, max: usize) -> String {
+    let len = (count * BAR_WIDTH).div_ceil(max.max(1));
+    "█".repeat(len)
+}
+
+fn print_human(stats: &LogStats) {
+    if stats.cooked == 0 {
+        println!("Nothing cooked yet, `chef cook` records what you cook");
+        return;
+    }
+    println!(
+        "Cooked {} times, {} different recipes",
+        stats.cooked.bold(),
+        stats.recipes.bold()
+    );
+    println!(
+        "Streak: {} weeks, longest {} weeks",
+        stats.current_streak.bold(),
+        stats.longest_streak
+    );
+
+    println!("\n{}", "Last months".bold().underline());
+    let max = stats.months.iter().map(|(_, n)| *n).max().unwrap_or(0);
+    for (month, count) in &stats.months {
+        println!("  {month} {:>3} {}", count, bar(*count, max).green());
+    }
+
+    if !stats.cuisines.is_empty() {
+        println!("\n{}", "Cuisines".bold().underline());
+        let max = stats.cuisines.first().map_or(0, |(_, n)| *n);
+        let width = stats
+            .cuisines
+            .iter()
+            .map(|(c, _)| c.len())
+            .max()
+            .unwrap_or(0);
+        for (cuisine, count) in &stats.cuisines {
+            println!(
+                "  {:<width$} {:>3} {}",
+                cuisine,
+                count,
+                bar(*count, max).cyan()
+            );
+        }
+    }
+
+    if stats.cost_per_week.is_some() || stats.kcal_per_week.is_some() {
+        println!(
+            "\n{}",
+            format!("Per week, last {} weeks", stats.weeks)
+                .bold()
+                .underline()
+        );
+        if let Some(cost) = stats.cost_per_week {
+            println!("  Cost: {}", format_cost(cost, stats.currency.as_deref()));
+        }
+        if let Some(kcal) = stats.kcal_per_week {
+            println!("  Calories: {kcal:.0} kcal");
+        }
+    }
+
+    if !stats.nudges.is_empty() {
+        println!("\n{}", "Not made in a while".bold().underline());
+        let today = Date::today();
+        for nudge in &stats.nudges {
+            println!(
+                "  {} {}",
+                nudge.name.yellow(),
+                format!(
+                    "last {}, {} times",
+                    nudge.last_cooked.ago(today),
+                    nudge.times
+                )
+                .dim()
+            );
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use std::io::Write;
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+use clap::{Args, Subcommand, ValueEnum};
+use cooklang::{quantity::GroupedQuantity, ScaledQuantity, ScaledRecipe};
+use cooklang_render::{RecipeRenderer, RenderInput};
+use serde::Serialize;
+use yansi::Paint;
+
+use crate::{
+    course::course_label,
+    locale::number_format,
+    menu::{self, Menu, MenuItem},
+    schedule::schedule,
+    seasonal::warn_out_of_season,
+    util::write_to_output,
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct MenuArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Render a menu as a single document
+    ///
+    /// The ingredients and cookware of all the recipes are merged in a
+    /// single list, then the steps of each recipe follow.
+    Read(ReadArgs),
+}
+
+#[derive(Debug, Args)]
+struct ReadArgs {
+    /// Menu file, like `menu.toml`
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    menu: Utf8PathBuf,
+
+    /// Output file, none for stdout
+    #[arg(short, long)]
+    output: Option<Utf8PathBuf>,
+
+    /// Output format
+    ///
+    /// Tries to infer it from output file extension. Defaults to "human".
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+    #[value(alias("md"))]
+    Markdown,
+}
+
+pub fn run(ctx: &Context, args: MenuArgs) -> Result<()> {
+    match args.command {
+        Command::Read(args) => read(ctx, args),
+    }
+}
+
+fn read(ctx: &Context, args: ReadArgs) -> Result<()> {
+    let path = if args.menu.is_file() {
+        args.menu.clone()
+    } else {
+        ctx.base_path.join(&args.menu)
+    };
+    let menu = Menu::read(&path)?;
+    let items = menu::load(ctx, &menu)?;
+    let name = menu
+        .name
+        .clone()
+        .or_else(|| path.file_stem().map(str::to_string))
+        .unwrap_or_else(|| "Menu".into());
+
+    let format = args.format.unwrap_or_else(|| match args.output.as_ref() {
+        Some(p) => match p.extension() {
+            Some("json") => OutputFormat::Json,
+            Some("md") => OutputFormat::Markdown,
+            _ => OutputFormat::Human,
+        },
+        None => OutputFormat::Human,
+    });
+
+    let ingredients = menu::ingredients(ctx, &items)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    let cookware = menu::cookware(&items);
+
+    write_to_output(args.output.as_deref(), |mut w| {
+        match format {
+            OutputFormat::Human => {
+                writeln!(w, "{}", name.bold().underline())?;
+                if let Some(description) = &menu.description {
+                    writeln!(w, "{description}")?;
+                }
+                writeln!(w)?;
+                writeln!(w, "{}:", "Ingredients".bold())?;
+                let mut table = tabular::Table::new("  {:<} {:<}");
+                for (name, quantity) in &ingredients {
+                    table.add_row(
+                        tabular::Row::new()
+                            .with_cell(name)
+                            .with_cell(quantity_text(quantity)),
+                    );
+                }
+                writeln!(w, "{table}")?;
+                if !cookware.is_empty() {
+                    writeln!(w, "{}:", "Cookware".bold())?;
+                    for (name, used_by) in &cookware {
+                        writeln!(w, "  {name} {}", format!("({})", used_by.join(", ")).dim())?;
+                    }
+                    writeln!(w)?;
+                }
+                let renderer = cooklang_render::Human::default();
+                for (i, item) in items.iter().enumerate() {
+                    if let Some(course) = course_heading(&items, i) {
+                        writeln!(w, "{}\n", course.bold().underline())?;
+                    }
+                    writeln!(w, "{}", format!(" {} ", recipe_title(item)).invert().bold())?;
+                    renderer.steps(
+                        &mut w,
+                        &RenderInput::new(&item.recipe, &item.name, ctx.parser()?.converter())
+                            .marks(&item.marks)
+                            .number_format(number_format(&item.recipe.metadata, &ctx.config)),
+                    )?;
+                }
+                warn_out_of_season(
+                    &mut w,
+                    &ctx.config,
+                    &ctx.base_path,
+                    ingredients.iter().map(|(name, _)| name.as_str()),
+                )?;
+            }
+            OutputFormat::Markdown => {
+                writeln!(w, "# {name}\n")?;
+                if let Some(description) = &menu.description {
+                    writeln!(w, "{description}\n")?;
+                }
+                writeln!(w, "## Ingredients\n")?;
+                for (name, quantity) in &ingredients {
+                    match quantity_text(quantity) {
+                        q if q.is_empty() => writeln!(w, "- {name}")?,
+                        q => writeln!(w, "- *{q}* {name}")?,
+                    }
+                }
+                if !cookware.is_empty() {
+                    writeln!(w, "\n## Cookware\n")?;
+                    for name in cookware.keys() {
+                        writeln!(w, "- {name}")?;
+                    }
+                }
+                for item in &items {
+                    match &item.course {
+                        Some(course) => {
+                            writeln!(w, "\n## {}: {}", course_label(course), recipe_title(item))?
+                        }
+                        None => writeln!(w, "\n## {}", recipe_title(item))?,
+                    }
+                    write_md_steps(&mut w, &item.recipe, ctx)?;
+                }
+            }
+            OutputFormat::Json => {
+                #[derive(Serialize)]
+                struct Ingredient<'a> {
+                    name: &'a str,
+                    quantity: Vec<ScaledQuantity>,
+                }
+                #[derive(Serialize)]
+                struct Recipe<'a> {
+                    name: &'a str,
+                    servings: Option<u32>,
+                    course: Option<&'a str>,
+                    recipe: &'a ScaledRecipe,
+                }
+                #[derive(Serialize)]
+                struct Document<'a> {
+                    name: &'a str,
+                    description: Option<&'a str>,
+                    ingredients: Vec<Ingredient<'a>>,
+                    cookware: Vec<&'a str>,
+                    recipes: Vec<Recipe<'a>>,
+                }
+                let document = Document {
+                    name: &name,
+                    description: menu.description.as_deref(),
+                    ingredients: ingredients
+                        .iter()
+                        .map(|(name, quantity)| Ingredient {
+                            name,
+                            quantity: quantity.iter().cloned().collect(),
+                        })
+                        .collect(),
+                    cookware: cookware.keys().map(String::as_str).collect(),
+                    recipes: items
+                        .iter()
+                        .map(|item| Recipe {
+                            name: &item.name,
+                            servings: item.servings,
+                            course: item.course.as_deref(),
+                            recipe: &item.recipe,
+                        })
+                        .collect(),
+                };
+                serde_json::to_writer_pretty(&mut w, &document)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Course to show before the recipe `i`, the first of each course
+fn course_heading(items: &[MenuItem], i: usize) -> Option<String> {
+    let course = items[i].course.as_deref();
+    let previous = i.checked_sub(1).and_then(|p| items[p].course.as_deref());
+    if course == previous || items.iter().all(|item| item.course.is_none()) {
+        return None;
+    }
+    Some(course.map_or_else(|| "Other".to_string(), course_label))
+}
+
+fn recipe_title(item: &MenuItem) -> String {
+    match item.servings {
+        Some(servings) => format!("{} ({servings} servings)", item.name),
+        None => item.name.clone(),
+    }
+}
+
+fn quantity_text(quantity: &GroupedQuantity) -> String {
+    quantity
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn write_md_steps(w: &mut dyn Write, recipe: &ScaledRecipe, ctx: &Context) -> Result<()> {
+    let mut last_section = None;
+    for (i, step) in schedule(recipe, ctx.parser()?.converter())
+        .into_iter()
+        .enumerate()
+    {
+        if i == 0 || step.section != last_section {
+            if let Some(section) = &step.section {
+                writeln!(w, "\n### {section}")?;
+            }
+            writeln!(w)?;
+        }
+        writeln!(w, "{}. {}", step.number, step.text)?;
+        last_section = step.section;
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use anstream::println;
+use anyhow::{bail, Result};
+use camino::Utf8PathBuf;
+use clap::{Args, Subcommand, ValueEnum};
+use cooklang_fs::RecipeEntry;
+use yansi::Paint;
+
+use crate::{
+    encryption, hooks,
+    source_edit::{to_front_matter, to_old_style_metadata},
+    util::{all_recipes, write_diff},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct MigrateArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Convert the metadata of the recipes between `>>` lines and YAML
+    /// front-matter
+    ///
+    /// Key order and comments are kept. Converting to `>>` lines fails for
+    /// values that don't fit in a single line, like nested maps.
+    Metadata(MetadataArgs),
+}
+
+#[derive(Debug, Args)]
+struct MetadataArgs {
+    /// Recipe files to convert, all the recipes of the collection by default
+    files: Vec<Utf8PathBuf>,
+
+    /// Metadata style to convert to
+    #[arg(long, value_enum, default_value_t = Style::FrontMatter)]
+    to: Style,
+
+    /// Show a diff of the changes without writing them
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Style {
+    /// YAML front-matter
+    FrontMatter,
+    /// `>> key: value` lines
+    OldStyle,
+}
+
+pub fn run(ctx: &Context, args: MigrateArgs) -> Result<()> {
+    match args.command {
+        Command::Metadata(args) => metadata(ctx, args),
+    }
+}
+
+fn metadata(ctx: &Context, args: MetadataArgs) -> Result<()> {
+    let entries: Vec<RecipeEntry> = if args.files.is_empty() {
+        if !ctx.is_collection {
+            bail!("Give the files to convert or run inside a collection");
+        }
+        all_recipes(ctx)?.collect()
+    } else {
+        args.files.iter().map(RecipeEntry::new).collect()
+    };
+
+    let mut converted = 0;
+    let mut failed = 0;
+    for entry in entries {
+        let old = entry.read()?.into_text();
+        let new = match args.to {
+            Style::FrontMatter => to_front_matter(&old),
+            Style::OldStyle => match to_old_style_metadata(&old) {
+                Ok(new) => new,
+                Err(e) => {
+                    tracing::warn!("Skipping '{}': {e:#}", entry.path());
+                    failed += 1;
+                    continue;
+                }
+            },
+        };
+        let Some(new) = new else {
+            continue;
+        };
+        converted += 1;
+
+        if args.dry_run {
+            println!("{}", format!("--- {}", entry.path()).bold());
+            write_diff(anstream::stdout().lock(), &old, &new)?;
+            println!();
+        } else {
+            encryption::write_recipe(ctx, entry.path(), &new)?;
+            println!("{}", entry.path());
+            hooks::fire(
+                ctx,
+                hooks::Event::RecipeSaved {
+                    path: entry.path().as_str(),
+                },
+            );
+        }
+    }
+
+    let verb = if args.dry_run {
+        "Would convert"
+    } else {
+        "Converted"
+    };
+    eprintln!("{verb} {converted} file(s)");
+    if failed > 0 {
+        bail!("{failed} file(s) could not be converted");
+    }
+    Ok(())
+}
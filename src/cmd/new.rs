@@ -1,10 +1,10 @@
 use std::fs;
 
-use anyhow::{bail, Context as _, Result};
-use camino::Utf8Path;
+use anyhow::{bail, Result};
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::Args;
 
-use crate::Context;
+use crate::{cmd::edit::open_editor, hooks, util::get_clipboard, Context};
 
 #[derive(Debug, Args)]
 pub struct NewArgs {
@@ -16,16 +16,54 @@ pub struct NewArgs {
     /// Skip opening the editor
     #[arg(long, short = 'E')]
     no_edit: bool,
+
+    /// Use the text in the clipboard as the recipe
+    #[arg(long)]
+    from_clipboard: bool,
 }
 
 pub fn run(args: NewArgs, ctx: &Context) -> Result<()> {
-    let file = Utf8Path::new(&args.name).with_extension("cook");
+    let path = new_recipe_path(ctx, &args.name)?;
+    let content = if args.from_clipboard {
+        let text = get_clipboard()?;
+        if text.trim().is_empty() {
+            bail!("The clipboard is empty");
+        }
+        let report = ctx.parser()?.parse(&text).into_report();
+        if report.has_errors() {
+            tracing::warn!("The recipe from the clipboard has errors");
+        }
+        text
+    } else {
+        String::new()
+    };
+    fs::write(&path, content)?;
+
+    if args.no_edit {
+        hooks::fire(
+            ctx,
+            hooks::Event::RecipeSaved {
+                path: path.as_str(),
+            },
+        );
+    } else {
+        open_editor(ctx, &path)?;
+    }
+
+    Ok(())
+}
+
+/// Path of a new recipe in the collection, with its dirs created
+///
+/// Fails if the name is not valid or the recipe already exists.
+pub fn new_recipe_path(ctx: &Context, name: &str) -> Result<Utf8PathBuf> {
+    let file = Utf8Path::new(name).with_extension("cook");
     let valid = !file.is_absolute()
         && file
             .components()
             .all(|c| matches!(c, camino::Utf8Component::Normal(_)));
     if !valid {
-        bail!("Invalid name: {}", args.name);
+        bail!("Invalid name: {name}");
     }
 
     let path = ctx.base_path.join(file);
@@ -37,25 +75,5 @@ pub fn run(args: NewArgs, ctx: &Context) -> Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::File::create(&path)?;
-
-    if !args.no_edit {
-        let editor = ctx
-            .chef_config
-            .editor()
-            .context("Could not determine editor")?;
-        let (cmd, args) = editor.split_first().expect("empty editor cmd");
-
-        let ok = std::process::Command::new(cmd)
-            .args(args)
-            .arg(path)
-            .status()?
-            .success();
-
-        if !ok {
-            tracing::warn!("Editor didn't exit successfully")
-        }
-    }
-
-    Ok(())
+    Ok(path)
 }
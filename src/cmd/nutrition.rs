@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use anstream::{print, println};
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+use crate::{
+    plugins::{self, IngredientNutrition},
+    scaling::scale_recipe,
+    util::{resolve_recipe, Input},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct NutritionArgs {
+    /// Recipe to analyse
+    recipe: String,
+
+    /// Scale to a number of servings
+    #[arg(short, long)]
+    servings: Option<u32>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    servings: Option<u32>,
+    ingredients: Vec<IngredientNutrition>,
+    total: BTreeMap<String, f64>,
+    per_serving: Option<BTreeMap<String, f64>>,
+    /// Ingredients no plugin knows
+    missing: Vec<String>,
+}
+
+pub fn run(ctx: &Context, args: NutritionArgs) -> Result<()> {
+    if !ctx
+        .chef_config
+        .plugins
+        .iter()
+        .any(|p| p.provides.contains(&plugins::Capability::Nutrition))
+    {
+        bail!("No nutrition plugin configured");
+    }
+
+    let converter = ctx.parser()?.converter();
+    let input = Input::File {
+        entry: resolve_recipe(ctx, &args.recipe)?,
+        override_name: None,
+    };
+    let recipe = input.parse(ctx)?;
+    let (scaled, _) = scale_recipe(recipe, args.servings, converter, &ctx.config.scaling);
+    let servings = args
+        .servings
+        .or_else(|| scaled.metadata.servings().and_then(|s| s.first().copied()));
+
+    let amounts = plugins::recipe_amounts(&scaled, converter);
+    let ingredients = plugins::nutrition(&ctx.chef_config.plugins, &amounts);
+
+    let mut total = BTreeMap::<String, f64>::new();
+    for igr in &ingredients {
+        for (nutrient, value) in &igr.nutrients {
+            *total.entry(nutrient.clone()).or_default() += value;
+        }
+    }
+    let per_serving = servings.filter(|&s| s > 0).map(|s| {
+        total
+            .iter()
+            .map(|(k, v)| (k.clone(), v / s as f64))
+            .collect()
+    });
+    let mut missing = amounts
+        .iter()
+        .filter(|a| !ingredients.iter().any(|i| i.name == a.name))
+        .map(|a| a.name.clone())
+        .collect::<Vec<_>>();
+    missing.dedup();
+
+    let report = Report {
+        servings,
+        ingredients,
+        total,
+        per_serving,
+        missing,
+    };
+    match args.format {
+        OutputFormat::Human => print_human(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+fn print_human(report: &Report) {
+    use tabular::{Row, Table};
+    use yansi::Paint;
+
+    if report.total.is_empty() {
+        println!("{}", "No nutrition data".dim());
+    } else {
+        let mut header = Row::new().with_cell("").with_cell("total".bold());
+        let mut table = if let (Some(servings), Some(_)) = (report.servings, &report.per_serving) {
+            header.add_cell(format!("per serving ({servings})").bold());
+            Table::new("{:<}  {:>}  {:>}")
+        } else {
+            Table::new("{:<}  {:>}")
+        };
+        table.add_row(header);
+        for (nutrient, value) in &report.total {
+            let mut row = Row::new()
+                .with_cell(nutrient)
+                .with_cell(format!("{value:.1}"));
+            if let Some(per_serving) = &report.per_serving {
+                row.add_cell(format!("{:.1}", per_serving[nutrient]));
+            }
+            table.add_row(row);
+        }
+        print!("{table}");
+    }
+
+    if !report.missing.is_empty() {
+        println!(
+            "\n{} {}",
+            "Missing:".yellow().bold(),
+            report.missing.join(", ")
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use anstream::{print, println};
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+
+use crate::{
+    pantry::{lookup_barcode, Date, Pantry, PantryItem, EXPIRING_SOON_DAYS},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct PantryArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Add an item or update it if it's already in the pantry
+    Add {
+        /// Item name, optional with `--barcode`
+        #[arg(required_unless_present = "barcode")]
+        name: Option<String>,
+        /// Quantity, like `500 g`
+        #[arg(short, long)]
+        quantity: Option<String>,
+        /// Category of the item
+        #[arg(short, long)]
+        category: Option<String>,
+        /// Look up the product in Open Food Facts by its EAN/UPC barcode
+        ///
+        /// The name, quantity and category of the product are used unless
+        /// they are given.
+        #[arg(short, long, value_name = "EAN")]
+        barcode: Option<String>,
+        /// Expiry date, `YYYY-MM-DD` or `+N` for N days from today
+        #[arg(short, long, value_name = "DATE")]
+        expires: Option<Date>,
+    },
+    /// Remove an item
+    #[command(visible_alias = "rm")]
+    Remove { name: String },
+    /// List the items
+    #[command(visible_alias = "ls")]
+    List,
+    /// List the items that expire soon or already expired
+    Expiring {
+        /// Days from today
+        #[arg(short, long, default_value_t = EXPIRING_SOON_DAYS)]
+        days: i64,
+    },
+    /// Print the path of the pantry file
+    Path,
+}
+
+pub fn run(ctx: &Context, args: PantryArgs) -> Result<()> {
+    match args.command {
+        Command::Add {
+            name,
+            quantity,
+            category,
+            barcode,
+            expires,
+        } => {
+            let product = barcode.as_deref().map(lookup_barcode).transpose()?;
+            let name = name
+                .or_else(|| product.as_ref().map(|p| p.name.clone()))
+                .expect("name or barcode required");
+            let item = PantryItem {
+                quantity: quantity.or_else(|| product.as_ref().and_then(|p| p.quantity.clone())),
+                category: category.or_else(|| product.as_ref().and_then(|p| p.category.clone())),
+                barcode,
+                expires,
+            };
+
+            let mut pantry = Pantry::load(ctx)?;
+            let updated = pantry.items.insert(name.clone(), item).is_some();
+            pantry.save(ctx)?;
+            println!("{} {name}", if updated { "Updated" } else { "Added" });
+        }
+        Command::Remove { name } => {
+            let mut pantry = Pantry::load(ctx)?;
+            if pantry.items.remove(&name).is_none() {
+                bail!("'{name}' is not in the pantry");
+            }
+            pantry.save(ctx)?;
+            println!("Removed {name}");
+        }
+        Command::List => {
+            use tabular::{row, Table};
+            use yansi::Paint;
+
+            let pantry = Pantry::load(ctx)?;
+            if pantry.items.is_empty() {
+                println!("{}", "The pantry is empty".dim());
+                return Ok(());
+            }
+            let mut table = Table::new("{:<}  {:<}  {:<}  {:<}");
+            for (name, item) in &pantry.items {
+                table.add_row(row!(
+                    name,
+                    item.quantity.as_deref().unwrap_or("-"),
+                    item.expires.map(|d| d.to_string()).unwrap_or_default(),
+                    item.category.as_deref().unwrap_or("").dim()
+                ));
+            }
+            print!("{table}");
+        }
+        Command::Expiring { days } => {
+            use tabular::{Row, Table};
+            use yansi::Paint;
+
+            let pantry = Pantry::load(ctx)?;
+            let expiring = pantry.expiring(days);
+            if expiring.is_empty() {
+                println!("{}", format!("Nothing expires in {days} days").dim());
+                return Ok(());
+            }
+            let mut table = Table::new("{:<}  {:<}  {:<}");
+            for (name, item, left) in expiring {
+                let when = match left {
+                    ..=-1 => format!("expired {} days ago", -left).red().to_string(),
+                    0 => "today".red().to_string(),
+                    1 => "tomorrow".yellow().to_string(),
+                    _ => format!("in {left} days"),
+                };
+                table.add_row(
+                    Row::new()
+                        .with_cell(name)
+                        .with_cell(item.expires.expect("expiring item has date"))
+                        .with_ansi_cell(when),
+                );
+            }
+            print!("{table}");
+        }
+        Command::Path => println!("{}", Pantry::path(ctx)),
+    }
+    Ok(())
+}
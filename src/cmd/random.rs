@@ -0,0 +1,90 @@
+use anstream::println;
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::{
+    cook_log::{recipe_key, CookLog},
+    pantry::Date,
+    pick::{choose, parse_minutes, weight, Constraints},
+    recipe_notes::{rated_weight, RecipeNotes},
+    util::{all_recipes, meta_name, parse_all},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct RandomArgs {
+    /// Only recipes with this tag, can be given more than once
+    #[arg(short, long)]
+    tag: Vec<String>,
+
+    /// Only recipes that take at most this time, like `45m` or `1h30m`
+    #[arg(short, long, value_parser = parse_minutes)]
+    max_time: Option<u32>,
+
+    /// Skip recipes with this ingredient, can be given more than once
+    #[arg(short = 'x', long, value_name = "INGREDIENT")]
+    exclude_ingredient: Vec<String>,
+
+    /// Favor the recipes not cooked for longer in the cook log
+    ///
+    /// And the ones with better ratings in their notes file.
+    #[arg(short, long)]
+    weighted: bool,
+
+    /// Display the relative path of the recipe
+    #[arg(short, long)]
+    paths: bool,
+}
+
+pub fn run(ctx: &Context, args: RandomArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`random` needs to run inside a collection");
+    }
+
+    let constraints = Constraints {
+        tags: args.tag,
+        max_time: args.max_time,
+        exclude_ingredients: args.exclude_ingredient,
+    };
+    let converter = ctx.parser()?.converter();
+
+    let entries = parse_all(ctx, all_recipes(ctx)?.collect())?;
+    let mut candidates = Vec::new();
+    for entry in &entries {
+        let Some(recipe) = entry.parsed(ctx)?.valid_output() else {
+            continue;
+        };
+        let ingredients = recipe.ingredients.iter().map(|i| i.name.as_str());
+        if constraints.matches(&recipe.metadata, ingredients, converter) {
+            let name = meta_name(&recipe.metadata).unwrap_or(entry.name());
+            candidates.push((entry, name));
+        }
+    }
+
+    let weights = if args.weighted {
+        let log = CookLog::load(&ctx.base_path)?;
+        let today = Date::today();
+        candidates
+            .iter()
+            .map(|(e, _)| {
+                let last = log.last_cooked(&recipe_key(e.path(), &ctx.base_path));
+                let rating = RecipeNotes::load(e.path())?.rating();
+                Ok(rated_weight(weight(last, today), rating))
+            })
+            .collect::<Result<_>>()?
+    } else {
+        vec![1; candidates.len()]
+    };
+    let Some(i) = choose(&weights) else {
+        bail!("No recipe matches");
+    };
+
+    let (entry, name) = candidates[i];
+    if args.paths {
+        let path = entry.path();
+        println!("{}", path.strip_prefix(&ctx.base_path).unwrap_or(path));
+    } else {
+        println!("{name}");
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use anyhow::{bail, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use yansi::Paint;
+
+use crate::{encryption, hooks, share, Context};
+
+#[derive(Debug, Args)]
+pub struct ReceiveArgs {
+    /// Link from `chef share`, or the URL of a pasted recipe
+    link: String,
+    /// Recipe name, instead of the one in the link
+    ///
+    /// Split directories with "/"
+    #[arg(short, long)]
+    name: Option<String>,
+    /// Overwrite the recipe if it exists
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(ctx: &Context, args: ReceiveArgs) -> Result<()> {
+    let (link_name, text) = match share::decode(&args.link)? {
+        Some((name, text)) => (Some(name), text),
+        None if args.link.starts_with("http://") || args.link.starts_with("https://") => {
+            (None, share::fetch(&args.link)?)
+        }
+        None => bail!("Not a recipe link: {}", args.link),
+    };
+
+    let name = match args.name {
+        Some(name) => name,
+        None => {
+            // only the file name, the link can't choose where to write
+            let from_link = link_name.or_else(|| metadata_name(ctx, &text)).or_else(|| {
+                args.link
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .map(String::from)
+            });
+            from_link
+                .as_deref()
+                .and_then(|n| Utf8Path::new(n).file_name())
+                .map(String::from)
+                .unwrap_or_else(|| "Shared recipe".to_string())
+        }
+    };
+    let name = name.strip_suffix(".cook").unwrap_or(&name);
+    let file = Utf8PathBuf::from(format!("{name}.cook"));
+    let valid = !file.is_absolute()
+        && file
+            .components()
+            .all(|c| matches!(c, camino::Utf8Component::Normal(_)));
+    if !valid {
+        bail!("Invalid name: {name}");
+    }
+    let path = ctx.base_path.join(file);
+    if path.exists() && !args.force {
+        bail!("File already exists: {path}, use `--force` to overwrite it");
+    }
+
+    if ctx.parser()?.parse(&text).into_report().has_errors() {
+        tracing::warn!("The received recipe has errors, check it with `chef check`");
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let path = encryption::write_recipe(ctx, &path, &text)?;
+    println!("{} {path}", "Received".green());
+    hooks::fire(
+        ctx,
+        hooks::Event::RecipeSaved {
+            path: path.as_str(),
+        },
+    );
+    Ok(())
+}
+
+fn metadata_name(ctx: &Context, text: &str) -> Option<String> {
+    let metadata = ctx.parser().ok()?.parse_metadata(text).into_output()?;
+    metadata
+        .map
+        .get("title")
+        .or_else(|| metadata.map.get("name"))
+        .and_then(serde_yaml::Value::as_str)
+        .map(String::from)
+}
@@ -1,22 +1,53 @@
-use std::io::Read;
+use std::{io::Read, sync::Arc};
 
 use anyhow::{bail, Context as _, Result};
-use camino::Utf8PathBuf;
-use clap::{Args, ValueEnum};
-use cooklang_fs::{check_recipe_images, recipe_images, LazyFsIndex, RecipeEntry};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, Subcommand, ValueEnum};
+use cooklang_fs::{check_recipe_images, recipe_images, RecipeEntry, IMAGE_EXTENSIONS};
+use cooklang_render::{IngredientColumn, RecipeRenderer, RenderInput};
 use yansi::Paint;
 
 use crate::{
-    util::{meta_name, unwrap_recipe, write_to_output, Input},
+    capacity,
+    checklist::{self, Checklists},
+    collection_file::{check_schema, inherited_schema},
+    cook_log::recipe_key,
+    diagnostics,
+    diet::{write_warnings, DietData},
+    flatten, graph,
+    hyperlinks::{self, recipe_links},
+    ingredient_info::IngredientData,
+    locale::{number_format, recipe_locale, unit_system},
+    plugins,
+    prep::{self, Preparations},
+    render_cache::{self, CacheKey},
+    scaling::{
+        is_baking, scale_factor, scale_recipe, scale_to_flour, scale_to_target, scale_to_yield,
+        scale_yield, split_batches, ScaleTarget,
+    },
+    schedule::{print_schedule, schedule, ClockTime},
+    seasonal::warn_out_of_season,
+    substitutions::{write_annotations, Substitutions},
+    timers::{adjust_timers, write_timer_notes},
+    util::{
+        meta_name, resolve_recipe, unwrap_recipe, write_to_output, write_to_output_or_clipboard,
+        Input,
+    },
+    variables::parse_assignment,
+    yields::{write_yields, yields},
     Context,
 };
 
 #[derive(Debug, Args)]
-#[command(args_conflicts_with_subcommands = true)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct ReadArgs {
+    #[command(subcommand)]
+    command: Option<RecipeCommand>,
+
     /// Input recipe, none for stdin
     ///
-    /// This can be a full path, a partial path, or just the name.
+    /// This can be a full path, a partial path, or just the name. If nothing
+    /// matches exactly, it is searched by fuzzy name.
     #[arg(value_hint = clap::ValueHint::FilePath)]
     recipe: Option<Utf8PathBuf>,
 
@@ -26,10 +57,30 @@ pub struct ReadArgs {
     #[arg(long, required_unless_present = "recipe")]
     name: Option<String>,
 
+    /// Give a value to a recipe variable, like `fruit=blueberry`
+    ///
+    /// Recipes can have `{{name}}` placeholders, replaced before parsing.
+    #[arg(long, value_name = "NAME=VALUE", value_parser = parse_assignment)]
+    set: Vec<(String, String)>,
+
+    /// Choose a variant of the recipe
+    ///
+    /// A variant of the recipe variants file, `<recipe>.variants.toml` next
+    /// to the recipe, gives values to the variables, and `--set` values take
+    /// precedence. A variant of the tagged sections and steps, like
+    /// `air-fryer`, keeps only its method. Can be repeated, one for each
+    /// group of variants.
+    #[arg(long, value_name = "NAME")]
+    variant: Vec<String>,
+
     /// Output file, none for stdout.
     #[arg(short, long)]
     output: Option<Utf8PathBuf>,
 
+    /// Copy the output to the clipboard instead
+    #[arg(long, conflicts_with = "output")]
+    to_clipboard: bool,
+
     /// Output format
     ///
     /// Tries to infer it from output file extension. Defaults to "human".
@@ -49,6 +100,90 @@ pub struct ReadArgs {
     /// Check the recipe for errors, warnings and images
     #[arg(long, conflicts_with_all = ["ScalingArgs", "DebugArgs"])]
     check: bool,
+
+    /// Show the steps and timers on a timeline instead of the recipe
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check"])]
+    schedule: bool,
+
+    /// Time to finish the recipe, like `19:00`, to get when each step starts
+    #[arg(long, value_name = "HH:MM", requires = "schedule")]
+    finish_at: Option<ClockTime>,
+
+    /// List known substitutes for the ingredients after the recipe
+    ///
+    /// Only for the "human" and "markdown" formats.
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check", "schedule"])]
+    with_substitutes: bool,
+
+    /// Suggest steps to start while others wait on their timers
+    ///
+    /// Only for the "human" and "markdown" formats and the schedule.
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check"])]
+    hints: bool,
+
+    /// Replace the referenced recipes with their ingredients
+    ///
+    /// The ingredients are scaled to the amount of the recipe used. Only for
+    /// the "human" format.
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check", "schedule"])]
+    flatten: bool,
+
+    /// Show each ingredient as a percentage of the total flour
+    ///
+    /// Only for the "human" format. Recipes tagged `baking`, `bread` or
+    /// `sourdough` always show it.
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check", "schedule"])]
+    bakers: bool,
+
+    /// Columns of the ingredients table, in order, like `quantity,name,cost`
+    ///
+    /// Any of name, quantity, optional, note, category, cost and calories.
+    /// Overrides `ingredient_columns` in the config. Only for the "human"
+    /// format.
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["DebugArgs", "check", "schedule"])]
+    columns: Vec<IngredientColumn>,
+
+    /// Keep the ingredients in one column, even in a wide terminal
+    ///
+    /// Only for the "human" format.
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check", "schedule"])]
+    one_column: bool,
+
+    /// List the preparation of the ingredients before the steps
+    ///
+    /// Notes like `(finely diced)`, or names like `onion, finely diced`, are
+    /// grouped by technique. Only for the "human" format.
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check", "schedule", "flatten"])]
+    prep: bool,
+
+    /// Always parse and render the recipe, ignoring the render cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Check the ingredients in the terminal, for the mise en place
+    ///
+    /// Space checks an ingredient and enter saves the list. In a collection
+    /// the checked ones are kept until all of them are.
+    #[arg(long, conflicts_with_all = [
+        "DebugArgs", "check", "schedule", "output", "to_clipboard", "format",
+        "with_substitutes", "hints", "flatten", "bakers", "prep",
+    ])]
+    checklist: bool,
+
+    /// Render what is valid of a recipe with errors, marking where they are
+    ///
+    /// The blocks of the text with errors are replaced by a step with the
+    /// message. Useful to preview a recipe while writing it. Only for the
+    /// "human" and "markdown" formats.
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check", "checklist"])]
+    partial: bool,
+
+    /// Add a section with the parser warnings and other problems found in
+    /// the recipe, like unknown units
+    ///
+    /// Only for the "human" and "html" formats.
+    #[arg(long, conflicts_with_all = ["DebugArgs", "check", "schedule", "checklist"])]
+    warnings: bool,
 }
 
 #[derive(Debug, Args)]
@@ -58,9 +193,36 @@ struct ScalingArgs {
     #[arg(short, long, alias = "servings", value_name = "SERVINGS")]
     scale: Option<u32>,
 
+    /// Scale so an ingredient reaches an amount, like `flour=1kg`
+    #[arg(long, value_name = "INGREDIENT=AMOUNT", conflicts_with = "scale")]
+    scale_to: Option<ScaleTarget>,
+
+    /// Scale so the total flour weighs an amount, like `1kg`
+    #[arg(long, value_name = "AMOUNT", conflicts_with_all = ["scale", "scale_to"])]
+    flour: Option<String>,
+
+    /// Scale so the recipe makes an amount, like `36` or `2kg`
+    ///
+    /// It's compared with the `yield` metadata of the recipe, like
+    /// `yield: 24 cookies`. Without a unit, it's the one of the yield.
+    #[arg(
+        long = "yield",
+        id = "yield",
+        value_name = "AMOUNT",
+        conflicts_with_all = ["scale", "scale_to", "flour"]
+    )]
+    yield_amount: Option<String>,
+
     /// Convert to a unit system
     #[arg(short, long, alias = "system", value_name = "SYSTEM")]
     convert: Option<System>,
+
+    /// Split the recipe in batches, the quantities are for one of them
+    ///
+    /// The steps to repeat for each batch are marked. These are the ones
+    /// that use a cookware with a capacity, or all of them.
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u32).range(2..))]
+    batches: Option<u32>,
 }
 
 #[derive(Debug, Args)]
@@ -82,6 +244,10 @@ enum OutputFormat {
     Cooklang,
     #[value(alias("md"))]
     Markdown,
+    /// Standalone HTML page
+    Html,
+    #[value(alias("tex"))]
+    Latex,
     #[value(hide = true)]
     Debug,
 }
@@ -93,7 +259,34 @@ enum System {
     Imperial,
 }
 
-pub fn run(ctx: &Context, args: ReadArgs) -> Result<()> {
+#[derive(Debug, Subcommand)]
+enum RecipeCommand {
+    /// Open the source URL of a recipe in the web browser
+    OpenSource {
+        /// Recipe name or path
+        recipe: String,
+    },
+    /// Open the images of a recipe in the default viewer
+    ///
+    /// The images are the files next to the recipe named like it, like
+    /// `Pasta.jpg` or `Pasta.3.jpg` for a step, and the `image` metadata.
+    Images {
+        /// Recipe name or path
+        recipe: String,
+
+        /// Print the paths instead of opening them
+        #[arg(short, long)]
+        list: bool,
+    },
+}
+
+pub fn run(ctx: &Context, mut args: ReadArgs) -> Result<()> {
+    if let Some(command) = args.command.take() {
+        return match command {
+            RecipeCommand::OpenSource { recipe } => open_source(ctx, &recipe),
+            RecipeCommand::Images { recipe, list } => open_images(ctx, &recipe, list),
+        };
+    }
     if args.debug.events || args.debug.ast {
         return just_events(ctx, args);
     }
@@ -101,92 +294,418 @@ pub fn run(ctx: &Context, args: ReadArgs) -> Result<()> {
         return just_check(ctx, args);
     }
 
-    let input = args.read(&ctx.recipe_index)?;
-
-    let recipe = input.parse(ctx)?;
-
-    let mut scaled_recipe = if let Some(scale) = args.values.scale {
-        recipe.scale(scale, ctx.parser()?.converter())
-    } else {
-        recipe.default_scale()
-    };
-
-    if let Some(system) = args.values.convert {
-        let to = match system {
-            System::Metric => cooklang::convert::System::Metric,
-            System::Imperial => cooklang::convert::System::Imperial,
-        };
-        let _ = scaled_recipe.convert(to, ctx.parser()?.converter());
-    }
+    let input = args.read(ctx)?;
 
     let format = args.format.unwrap_or_else(|| match &args.output {
         Some(p) => match p.extension() {
             Some("json") => OutputFormat::Json,
             Some("cook") => OutputFormat::Cooklang,
             Some("md") => OutputFormat::Markdown,
+            Some("html") => OutputFormat::Html,
+            Some("tex") => OutputFormat::Latex,
             _ => OutputFormat::Human,
         },
         None => OutputFormat::Human,
     });
 
-    let name = match meta_name(&scaled_recipe.metadata) {
-        Some(n) => n,
-        None => input.name()?,
-    };
+    if args.with_substitutes && !matches!(format, OutputFormat::Human | OutputFormat::Markdown) {
+        bail!("Substitutes are only available for the human and markdown formats");
+    }
+    if args.hints && !matches!(format, OutputFormat::Human | OutputFormat::Markdown) {
+        bail!("Hints are only available for the human and markdown formats");
+    }
+    if args.bakers && format != OutputFormat::Human {
+        bail!("Bakers' percentages are only available for the human format");
+    }
+    if (!args.columns.is_empty() || args.one_column) && format != OutputFormat::Human {
+        bail!("Ingredient columns are only available for the human format");
+    }
+    if args.prep && format != OutputFormat::Human {
+        bail!("The prep list is only available for the human format");
+    }
+    if args.flatten && format != OutputFormat::Human {
+        bail!("Flattening is only available for the human format");
+    }
+    if args.partial && !matches!(format, OutputFormat::Human | OutputFormat::Markdown) {
+        bail!("Partial rendering is only available for the human and markdown formats");
+    }
+    if args.warnings && !matches!(format, OutputFormat::Human | OutputFormat::Html) {
+        bail!("The warnings section is only available for the human and html formats");
+    }
 
-    write_to_output(args.output.as_deref(), |mut writer| {
-        match format {
-            OutputFormat::Human => cooklang_to_human::print_human(
-                &scaled_recipe,
-                name,
-                ctx.parser()?.converter(),
-                writer,
-            )?,
-            OutputFormat::Json => {
-                #[derive(serde::Serialize)]
-                struct JsonRecipe<'a> {
-                    name: &'a str,
-                    #[serde(flatten)]
-                    recipe: &'a cooklang::ScaledRecipe,
+    // the warnings depend on files of the collection too
+    let links = format == OutputFormat::Human && hyperlinks::enabled(ctx.global_args.hyperlinks);
+    let columns = if args.columns.is_empty() {
+        ctx.config.ingredient_columns()
+    } else {
+        args.columns.clone()
+    };
+    // the category, cost and calories come from outside the recipe
+    let external_columns = format == OutputFormat::Human
+        && columns.iter().any(|c| {
+            matches!(
+                c,
+                IngredientColumn::Category | IngredientColumn::Cost | IngredientColumn::Calories
+            )
+        });
+    let cache_key =
+        if args.no_cache || args.schedule || args.checklist || args.warnings || external_columns {
+            None
+        } else {
+            let width = match format {
+                OutputFormat::Human => textwrap::termwidth(),
+                _ => 0,
+            };
+            let mut options = format!(
+                "{format:?} {:?} {:?} {} {} {} {} {} {} {width}",
+                args.values,
+                input.name().ok(),
+                args.pretty,
+                args.with_substitutes,
+                args.bakers,
+                args.flatten,
+                args.hints,
+                args.prep
+            );
+            if format == OutputFormat::Human {
+                options += &format!(" {columns:?} {} {links}", args.one_column);
+            }
+            // these files can change with the recipe untouched
+            let mut extra_files = Vec::new();
+            if args.with_substitutes {
+                extra_files.extend(ctx.config.substitutions(&ctx.base_path));
+            }
+            if format == OutputFormat::Human {
+                extra_files.extend(ctx.config.diet(&ctx.base_path));
+                let mut colors = ctx
+                    .config
+                    .ui
+                    .tag_colors()
+                    .into_iter()
+                    .map(|(tag, color)| format!("{tag}={color}"))
+                    .collect::<Vec<_>>();
+                colors.sort();
+                options += &colors.join(",");
+                // the season warnings change with the month
+                if ctx.config.season.warnings {
+                    extra_files.extend(ctx.config.produce(&ctx.base_path));
+                    let month = crate::pantry::Date::today().ymd().1;
+                    options += &format!(" {} {month}", ctx.config.season.region);
                 }
+            }
+            for path in extra_files {
+                options += &std::fs::read_to_string(path).unwrap_or_default();
+            }
+            Some(CacheKey::new(ctx, &input.text()?, input.path(), &options)?)
+        };
+    if let Some(content) = cache_key.as_ref().and_then(render_cache::get) {
+        return write_to_output_or_clipboard(args.output.as_deref(), args.to_clipboard, |mut w| {
+            w.write_all(&content)?;
+            Ok(())
+        });
+    }
 
-                let recipe = JsonRecipe {
-                    recipe: &scaled_recipe,
-                    name,
-                };
+    let parsed = input.parse_result(ctx)?;
+    // only cache recipes that would render with no report at all. Recipe
+    // references are checked against other files, they can change without
+    // this recipe changing.
+    let cacheable = parsed.report().is_empty()
+        && parsed.output().is_some_and(|r| {
+            !r.ingredients
+                .iter()
+                .any(|i| i.modifiers().contains(cooklang::Modifiers::RECIPE))
+        });
+    let mut problems = Vec::new();
+    if args.warnings {
+        let path = input.path().unwrap_or(Utf8Path::new(input.file_name()));
+        let text = input.text()?;
+        problems.extend(
+            diagnostics::from_report(parsed.report(), path, &text)
+                .into_iter()
+                .filter(|d| d.severity == "warning"),
+        );
+    }
+    // the lints need the recipe as it's shown, they are added after scaling
+    let lint = args.warnings && parsed.is_valid();
+    let recipe = if args.partial && !parsed.is_valid() {
+        parsed
+            .into_report()
+            .eprint(input.file_name(), &input.text()?, ctx.color.color_stderr)?;
+        match input.parse_partial(ctx)? {
+            Some(recipe) => recipe,
+            None => bail!("Error parsing recipe, nothing could be rendered"),
+        }
+    } else {
+        unwrap_recipe(parsed, input.file_name(), &input.text()?, ctx)?
+    };
 
-                if args.pretty {
-                    serde_json::to_writer_pretty(writer, &recipe)?;
-                } else {
-                    serde_json::to_writer(writer, &recipe)?;
+    let (mut scaled_recipe, marks, factor) = if let Some(target) = &args.values.scale_to {
+        let (scaled, marks, factor) = scale_to_target(
+            recipe,
+            target,
+            ctx.parser()?.converter(),
+            &ctx.config.scaling,
+        )?;
+        eprintln!("Scaled by {factor:.3}");
+        (scaled, marks, Some(factor))
+    } else if let Some(amount) = &args.values.yield_amount {
+        let (scaled, marks, factor) = scale_to_yield(
+            recipe,
+            amount,
+            ctx.parser()?.converter(),
+            &ctx.config.scaling,
+        )?;
+        eprintln!("Scaled by {factor:.3}");
+        (scaled, marks, Some(factor))
+    } else if let Some(amount) = &args.values.flour {
+        let (scaled, marks, factor) = scale_to_flour(
+            recipe,
+            amount,
+            ctx.parser()?.converter(),
+            &ctx.config.scaling,
+        )?;
+        eprintln!("Scaled by {factor:.3}");
+        (scaled, marks, Some(factor))
+    } else {
+        let (scaled, marks) = scale_recipe(
+            recipe,
+            args.values.scale,
+            ctx.parser()?.converter(),
+            &ctx.config.scaling,
+        );
+        let factor = scale_factor(&scaled);
+        (scaled, marks, factor)
+    };
+    if let Some(factor) = factor {
+        scale_yield(&mut scaled_recipe, factor);
+    }
+    let timer_notes = adjust_timers(
+        &mut scaled_recipe,
+        factor.unwrap_or(1.0),
+        ctx.parser()?.converter(),
+    );
+
+    let convert_to = match args.values.convert {
+        Some(System::Metric) => Some(cooklang::convert::System::Metric),
+        Some(System::Imperial) => Some(cooklang::convert::System::Imperial),
+        None => recipe_locale(&scaled_recipe.metadata, &ctx.config).map(unit_system),
+    };
+    if let Some(to) = convert_to {
+        let _ = scaled_recipe.convert(to, ctx.parser()?.converter());
+    }
+
+    if let Some(batches) = args.values.batches {
+        split_batches(&mut scaled_recipe, batches, &ctx.config.scaling);
+        scale_yield(&mut scaled_recipe, 1.0 / batches as f64);
+        capacity::mark_batch_steps(&mut scaled_recipe, batches);
+        eprintln!("Split in {batches} batches, the quantities are for one");
+    }
+
+    for overflow in capacity::check(&scaled_recipe, ctx.parser()?.converter()) {
+        tracing::warn!("{overflow}, {}", overflow.hint());
+    }
+
+    if lint {
+        let path = input.path().unwrap_or(Utf8Path::new(input.file_name()));
+        problems.extend(diagnostics::lint(
+            &scaled_recipe,
+            path,
+            &ctx.base_path,
+            &input.text()?,
+            ctx.parser()?.converter(),
+        ));
+    }
+    let problems = problems.iter().map(|d| d.summary()).collect::<Vec<_>>();
+
+    if args.checklist {
+        return run_checklist(ctx, &input, &scaled_recipe);
+    }
+
+    if args.schedule {
+        let converter = ctx.parser()?.converter();
+        let mut steps = schedule(&scaled_recipe, converter);
+        let graph = graph::build(&scaled_recipe, converter);
+        graph::critical_path(&graph, &mut steps);
+        return write_to_output_or_clipboard(args.output.as_deref(), args.to_clipboard, |mut w| {
+            match args.format {
+                Some(OutputFormat::Json) => serde_json::to_writer_pretty(w, &steps)?,
+                None | Some(OutputFormat::Human) => {
+                    print_schedule(&mut w, &steps, args.finish_at)?;
+                    if args.hints {
+                        graph::write_hints(&mut w, &steps, &graph::hints(&graph, &steps), false)?;
+                    }
                 }
+                Some(_) => bail!("Format not supported"),
             }
-            OutputFormat::Cooklang => cooklang_to_cooklang::print_cooklang(&scaled_recipe, writer)?,
-            OutputFormat::Markdown => cooklang_to_md::print_md_with_options(
-                &scaled_recipe,
-                name,
-                &ctx.config.export.markdown,
-                ctx.parser()?.converter(),
-                writer,
-            )?,
-            OutputFormat::Debug => write!(writer, "{scaled_recipe:?}")?,
+            Ok(())
+        });
+    }
+
+    let name = match meta_name(&scaled_recipe.metadata) {
+        Some(n) => n,
+        None => input.name()?,
+    };
+
+    let renderer: Box<dyn RecipeRenderer + '_> = match format {
+        OutputFormat::Human => Box::new(cooklang_render::Human {
+            bakers_percentages: args.bakers || is_baking(&scaled_recipe),
+            tag_colors: ctx.config.ui.tag_colors(),
+            ingredient_info: if external_columns {
+                Some(Arc::new(IngredientData::load(
+                    ctx,
+                    &scaled_recipe,
+                    &columns,
+                )?))
+            } else {
+                None
+            },
+            ingredient_columns: columns,
+            one_column: args.one_column,
+            hyperlinks: links.then(|| recipe_links(ctx, &scaled_recipe, input.path())),
+            ..Default::default()
+        }),
+        OutputFormat::Json => Box::new(cooklang_render::Json {
+            pretty: args.pretty,
+        }),
+        OutputFormat::Cooklang => Box::new(cooklang_render::Cooklang),
+        OutputFormat::Markdown => Box::new(cooklang_render::Markdown {
+            options: &ctx.config.export.markdown,
+        }),
+        OutputFormat::Html => Box::new(cooklang_render::Html),
+        OutputFormat::Latex => Box::new(cooklang_render::Latex),
+        OutputFormat::Debug => Box::new(cooklang_render::DebugDump),
+    };
+    let mut buf = Vec::new();
+    let render_input = RenderInput::new(&scaled_recipe, name, ctx.parser()?.converter())
+        .marks(&marks)
+        .number_format(number_format(&scaled_recipe.metadata, &ctx.config))
+        .warnings(&problems);
+    if args.flatten {
+        flatten::write_human(&mut buf, ctx, &scaled_recipe, name, input.path())?;
+    } else if args.prep {
+        renderer.header(&mut buf, &render_input)?;
+        renderer.metadata(&mut buf, &render_input)?;
+        renderer.ingredients(&mut buf, &render_input)?;
+        let preps = Preparations::new(&ctx.config.prep);
+        prep::write_prep(
+            &mut buf,
+            &preps.prep_list(&scaled_recipe, ctx.parser()?.converter()),
+        )?;
+        renderer.steps(&mut buf, &render_input)?;
+        renderer.warnings(&mut buf, &render_input)?;
+    } else {
+        renderer.render(&mut buf, &render_input)?;
+    }
+    if format == OutputFormat::Human {
+        let data = DietData::load(&ctx.config, &ctx.base_path)?;
+        let classification = data.classify(
+            scaled_recipe
+                .ingredients
+                .iter()
+                .filter(|i| i.modifiers().should_be_listed())
+                .map(|i| i.name.as_str()),
+        );
+        write_warnings(&mut buf, &data, &classification)?;
+        warn_out_of_season(
+            &mut buf,
+            &ctx.config,
+            &ctx.base_path,
+            scaled_recipe
+                .ingredients
+                .iter()
+                .filter(|i| {
+                    i.modifiers().should_be_listed()
+                        && !i.modifiers().contains(cooklang::Modifiers::OPT)
+                })
+                .map(|i| i.name.as_str()),
+        )?;
+
+        let (yields, warnings) = yields(&scaled_recipe, ctx.parser()?.converter());
+        for warning in warnings {
+            tracing::warn!("{warning}");
         }
+        write_yields(&mut buf, &yields)?;
+        write_timer_notes(&mut buf, &timer_notes)?;
+    }
+    if args.hints {
+        let converter = ctx.parser()?.converter();
+        let steps = schedule(&scaled_recipe, converter);
+        let hints = graph::hints(&graph::build(&scaled_recipe, converter), &steps);
+        graph::write_hints(&mut buf, &steps, &hints, format == OutputFormat::Markdown)?;
+    }
+    if args.with_substitutes {
+        let subs = Substitutions::load(ctx)?;
+        let amounts = plugins::recipe_amounts(&scaled_recipe, ctx.parser()?.converter());
+        write_annotations(&mut buf, &subs, &amounts, format == OutputFormat::Markdown)?;
+    }
+
+    if let Some(key) = cache_key.filter(|_| cacheable) {
+        render_cache::put(&key, &buf);
+    }
 
+    write_to_output_or_clipboard(args.output.as_deref(), args.to_clipboard, |mut w| {
+        w.write_all(&buf)?;
         Ok(())
-    })?;
+    })
+}
+
+fn run_checklist(ctx: &Context, input: &Input, recipe: &cooklang::ScaledRecipe) -> Result<()> {
+    use std::io::IsTerminal;
 
+    if !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+        bail!("The checklist needs an interactive terminal");
+    }
+    let items = checklist::items(recipe, ctx.parser()?.converter());
+    if items.is_empty() {
+        eprintln!("No ingredients to check");
+        return Ok(());
+    }
+    let total = items.len();
+
+    let key = input
+        .path()
+        .filter(|_| ctx.is_collection)
+        .map(|path| recipe_key(path, &ctx.base_path));
+    let mut lists = match key {
+        Some(_) => Checklists::load(&ctx.base_path)?,
+        None => Checklists::default(),
+    };
+    let checked = key
+        .as_ref()
+        .and_then(|k| lists.recipes.get(k))
+        .cloned()
+        .unwrap_or_default();
+
+    let Some(checked) = checklist::prompt(items, &checked)? else {
+        return Ok(());
+    };
+    let ready = checked.len();
+    let done = match key {
+        Some(key) => {
+            let done = lists.update(key, checked, total);
+            lists.save(&ctx.base_path)?;
+            done
+        }
+        None => ready >= total,
+    };
+    if done {
+        eprintln!("{}", "Everything is ready".green().bold());
+    } else {
+        eprintln!("{ready}/{total} ingredients ready");
+    }
     Ok(())
 }
 
 impl ReadArgs {
-    fn read(&self, index: &LazyFsIndex) -> Result<Input> {
+    fn read(&self, ctx: &Context) -> Result<Input> {
         let input = if let Some(query) = &self.recipe {
-            let entry = if query.extension().is_some_and(|e| e == "cook") && query.is_file() {
+            let entry = if ctx.config.discovery.is_recipe_extension(query) && query.is_file() {
+                ctx.usage.recipe(&recipe_key(query, &ctx.base_path));
                 RecipeEntry::new(query)
             } else {
                 // RecipeInputArgs::recipe is a pathbuf even if inmediatly converted
                 // to a string to enforce validation.
-                index.resolve(query.as_str(), None)?
+                resolve_recipe(ctx, query.as_str())?
             };
 
             Input::File {
@@ -203,12 +722,12 @@ impl ReadArgs {
                 name: self.name.clone(),
             }
         };
-        Ok(input)
+        input.with_variables(&self.variant, &self.set)
     }
 }
 
 fn just_events(ctx: &Context, args: ReadArgs) -> Result<()> {
-    let input = args.read(&ctx.recipe_index)?;
+    let input = args.read(ctx)?;
     let text = input.text()?;
     let file_name = input.file_name();
 
@@ -285,7 +804,7 @@ fn just_events(ctx: &Context, args: ReadArgs) -> Result<()> {
 }
 
 fn just_check(ctx: &Context, args: ReadArgs) -> Result<()> {
-    let input = args.read(&ctx.recipe_index)?;
+    let input = args.read(ctx)?;
     let res = input.parse_result(ctx)?;
     let mut n_warns = 0;
     let mut n_errs = 0;
@@ -299,11 +818,33 @@ fn just_check(ctx: &Context, args: ReadArgs) -> Result<()> {
     let file_name = input.file_name();
     let recipe = unwrap_recipe(res, file_name, input.text()?.as_ref(), ctx).ok();
 
-    if let Some(recipe) = &recipe {
+    if let Some(recipe) = recipe.map(|r| r.default_scale()) {
+        let converter = ctx.parser()?.converter();
+        let (_, warnings) = yields(&recipe, converter);
+        let overflows = capacity::check(&recipe, converter);
+        let violations = match input.path() {
+            Some(path) => check_schema(
+                &inherited_schema(path, &ctx.base_path),
+                &recipe.metadata.map,
+            ),
+            None => Vec::new(),
+        };
+        n_warns += warnings.len() + overflows.len() + violations.len();
+        for warning in warnings {
+            eprintln!("{}: {warning}", "Warning".yellow().bold());
+        }
+        for overflow in overflows {
+            eprintln!("{}: {overflow}", "Warning".yellow().bold());
+            eprintln!("  {} {}", "help:".bold(), overflow.hint());
+        }
+        for violation in violations {
+            eprintln!("{}: {violation}", "Warning".yellow().bold());
+        }
+
         if let Some(path) = &input.path() {
             let images = recipe_images(path);
 
-            if let Err(errors) = check_recipe_images(&images, recipe) {
+            if let Err(errors) = check_recipe_images(&images, &recipe) {
                 n_image_errs = errors.len();
                 for e in errors {
                     eprintln!("{e}");
@@ -337,3 +878,81 @@ fn just_check(ctx: &Context, args: ReadArgs) -> Result<()> {
 
     Ok(())
 }
+
+fn open_source(ctx: &Context, query: &str) -> Result<()> {
+    let input = Input::File {
+        entry: resolve_recipe(ctx, query)?,
+        override_name: None,
+    };
+    let recipe = input.parse(ctx)?;
+    let source = recipe.metadata.source();
+    let Some(url) = source.as_ref().and_then(|s| s.url()) else {
+        bail!("The recipe has no source URL");
+    };
+    if !is_web_url(url) {
+        bail!("The source is not a web page: {url}");
+    }
+    eprintln!("Opening {url}");
+    open::that(url).context("Could not open the web browser")?;
+    Ok(())
+}
+
+fn is_web_url(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// Path of an image in the metadata, `None` if it's not an image file inside
+/// the collection
+fn collection_image(base_path: &Utf8Path, dir: &Utf8Path, value: &str) -> Option<Utf8PathBuf> {
+    let path = dir.join(value).canonicalize_utf8().ok()?;
+    let base = base_path.canonicalize_utf8().ok()?;
+    let ext = path.extension()?.to_ascii_lowercase();
+    (path.starts_with(base) && IMAGE_EXTENSIONS.contains(&ext.as_str())).then_some(path)
+}
+
+fn open_images(ctx: &Context, query: &str, list: bool) -> Result<()> {
+    let entry = resolve_recipe(ctx, query)?;
+    let path = entry.path().to_path_buf();
+    let input = Input::File {
+        entry,
+        override_name: None,
+    };
+    let recipe = input.parse(ctx)?;
+
+    let mut targets = recipe_images(&path)
+        .into_iter()
+        .map(|image| image.path.into_string())
+        .collect::<Vec<_>>();
+    for key in ["image", "images"] {
+        let Some(value) = recipe.metadata.get(key).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        // the metadata could point to anything, like a program
+        if value.contains("://") {
+            if is_web_url(value) {
+                targets.push(value.to_string());
+            } else {
+                tracing::warn!("Skipping image '{value}', it's not a web URL");
+            }
+        } else if let Some(image) = path
+            .parent()
+            .and_then(|dir| collection_image(&ctx.base_path, dir, value))
+        {
+            targets.push(image.into_string());
+        } else {
+            tracing::warn!("Skipping image '{value}', it's not an image in the collection");
+        }
+    }
+    if targets.is_empty() {
+        bail!("The recipe has no images");
+    }
+    for target in targets {
+        if list {
+            println!("{target}");
+        } else if let Err(e) = open::that(&target) {
+            tracing::error!("Could not open {target}: {e}");
+        }
+    }
+    Ok(())
+}
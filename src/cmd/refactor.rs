@@ -0,0 +1,88 @@
+use anstream::println;
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+use yansi::Paint;
+
+use crate::{
+    encryption, hooks,
+    source_edit::rename_ingredient,
+    util::{all_recipes, write_diff},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct RefactorArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Rename an ingredient in all the recipes of the collection
+    ///
+    /// Quantities, notes and modifiers are kept. Aliases matching the name
+    /// are renamed too.
+    RenameIngredient {
+        /// Current name, case insensitive
+        from: String,
+        /// New name
+        to: String,
+        /// Show a diff of the changes without writing them
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+}
+
+pub fn run(ctx: &Context, args: RefactorArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`refactor` needs to run inside a collection");
+    }
+
+    match args.command {
+        Command::RenameIngredient { from, to, dry_run } => {
+            rename_ingredient_cmd(ctx, &from, &to, dry_run)
+        }
+    }
+}
+
+fn rename_ingredient_cmd(ctx: &Context, from: &str, to: &str, dry_run: bool) -> Result<()> {
+    let to = to.trim();
+    if to.is_empty() || to.contains(['@', '#', '~', '{', '}', '|', '\n']) {
+        bail!("Invalid ingredient name: '{to}'");
+    }
+
+    let mut files = 0;
+    let mut total = 0;
+    for entry in all_recipes(ctx)? {
+        let old = entry.read()?.into_text();
+        let (new, count) = rename_ingredient(&old, from, to);
+        if count == 0 {
+            continue;
+        }
+        files += 1;
+        total += count;
+
+        if dry_run {
+            println!("{}", format!("--- {}", entry.path()).bold());
+            write_diff(anstream::stdout().lock(), &old, &new)?;
+            println!();
+        } else {
+            encryption::write_recipe(ctx, entry.path(), &new)?;
+            println!("{} ({count})", entry.path());
+            hooks::fire(
+                ctx,
+                hooks::Event::RecipeSaved {
+                    path: entry.path().as_str(),
+                },
+            );
+        }
+    }
+
+    if files == 0 {
+        tracing::warn!("No ingredient named '{from}' found");
+    } else {
+        let verb = if dry_run { "Would rename" } else { "Renamed" };
+        eprintln!("{verb} {total} ingredient(s) in {files} file(s)");
+    }
+    Ok(())
+}
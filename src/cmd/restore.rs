@@ -0,0 +1,75 @@
+use std::fs::File;
+
+use anyhow::{bail, Context as _, Result};
+use camino::Utf8PathBuf;
+use clap::Args;
+
+use crate::{backup, COOK_DIR};
+
+#[derive(Debug, Args)]
+pub struct RestoreArgs {
+    /// Backup file made with `chef backup`
+    backup: Utf8PathBuf,
+    /// Directory to restore the collection into
+    ///
+    /// By default, the current directory.
+    #[arg(long)]
+    into: Option<Utf8PathBuf>,
+    /// Only check the integrity of the backup
+    #[arg(long)]
+    check: bool,
+    /// Don't restore the global config files
+    #[arg(long)]
+    no_global: bool,
+    /// Overwrite existing files
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(args: RestoreArgs) -> Result<()> {
+    let open =
+        || File::open(&args.backup).with_context(|| format!("Failed to open {}", args.backup));
+
+    let manifest = backup::verify(open()?)
+        .with_context(|| format!("{} is not a valid backup", args.backup))?;
+    eprintln!(
+        "Backup from {} by chef {} with {} files is valid",
+        manifest.created,
+        manifest.chef_version,
+        manifest.files.len()
+    );
+    if args.check {
+        return Ok(());
+    }
+
+    let dest = match args.into {
+        Some(dir) => dir,
+        None => Utf8PathBuf::from_path_buf(std::env::current_dir()?)
+            .map_err(|p| anyhow::anyhow!("Non UTF-8 current dir: {}", p.display()))?,
+    };
+    let global = !args.no_global;
+    if !args.force {
+        if dest.join(COOK_DIR).is_dir() {
+            bail!("{dest} is already a collection, use `--force` to restore over it");
+        }
+        for file in &manifest.files {
+            if let Some(path) = backup::local_path(&file.path, &dest, global)? {
+                if path.exists() {
+                    bail!("{path} already exists, use `--force` to overwrite it");
+                }
+            }
+        }
+    }
+
+    let written = backup::extract(open()?, &dest, global)?;
+    eprintln!("Restored {} files into {dest}", written.len());
+    if global
+        && manifest
+            .files
+            .iter()
+            .any(|f| f.path.starts_with(backup::GLOBAL_PREFIX))
+    {
+        eprintln!("The global config was restored, check its paths with `chef config --setup`");
+    }
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use anstream::println;
+use anyhow::{bail, Result};
+use clap::Args;
+use cooklang::ScalableRecipe;
+use yansi::Paint;
+
+use crate::{
+    diet::DietData,
+    util::{all_recipes, meta_name, parse_all},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    /// Search terms, all of them have to match
+    ///
+    /// A term can be part of the name, `tag:TAG`, `ingredient:NAME`,
+    /// `cookware:NAME`, `diet:DIET` or `allergen:NAME`. Start a term with `!`
+    /// to negate it; `allergen:-nuts` is the same as `!allergen:nuts`.
+    #[arg(required = true, value_name = "TERM")]
+    terms: Vec<String>,
+
+    /// Display the relative path of the recipes
+    #[arg(short, long)]
+    paths: bool,
+}
+
+#[derive(Debug)]
+pub enum Term {
+    Name(String),
+    Tag(String),
+    Ingredient(String),
+    Cookware(String),
+    Diet(String),
+    Allergen(String),
+}
+
+/// Parses a search term, returns if it's negated and the term
+pub fn parse_term(s: &str, diet: &DietData) -> Result<(bool, Term)> {
+    let (mut negated, s) = match s.strip_prefix('!') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let s = s.to_lowercase();
+    let term = if let Some(tag) = s.strip_prefix("tag:") {
+        Term::Tag(tag.to_string())
+    } else if let Some(ingredient) = s.strip_prefix("ingredient:") {
+        Term::Ingredient(ingredient.to_string())
+    } else if let Some(cookware) = s.strip_prefix("cookware:") {
+        Term::Cookware(cookware.to_string())
+    } else if let Some(name) = s.strip_prefix("diet:") {
+        let (excluded, name) = without(name);
+        negated ^= excluded;
+        if !diet.is_diet(name) {
+            bail!("Unknown diet '{name}'");
+        }
+        Term::Diet(name.to_string())
+    } else if let Some(name) = s.strip_prefix("allergen:") {
+        let (excluded, name) = without(name);
+        negated ^= excluded;
+        if !diet.is_category(name) {
+            bail!("Unknown allergen '{name}'");
+        }
+        Term::Allergen(name.to_string())
+    } else {
+        Term::Name(s)
+    };
+    Ok((negated, term))
+}
+
+/// `-name` is the recipes without `name`
+fn without(name: &str) -> (bool, &str) {
+    match name.strip_prefix('-') {
+        Some(name) => (true, name),
+        None => (false, name),
+    }
+}
+
+pub fn run(ctx: &Context, args: SearchArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`search` needs to run inside a collection");
+    }
+
+    let diet = DietData::load(&ctx.config, &ctx.base_path)?;
+    let terms = args
+        .terms
+        .iter()
+        .map(|t| parse_term(t, &diet))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut entries = parse_all(ctx, all_recipes(ctx)?.collect())?;
+    entries.sort_unstable_by(|a, b| a.path().cmp(b.path()));
+
+    for entry in entries {
+        let Some(recipe) = entry.parsed(ctx)?.valid_output() else {
+            continue;
+        };
+        let name = meta_name(&recipe.metadata).unwrap_or(entry.name());
+        if !matches(&terms, &diet, name, recipe) {
+            continue;
+        }
+
+        if args.paths {
+            let path = entry.path();
+            println!("{}", path.strip_prefix(&ctx.base_path).unwrap_or(path));
+        } else {
+            let classification =
+                diet.classify(listed_ingredients(recipe).iter().map(String::as_str));
+            let allergens = diet
+                .allergens(&classification)
+                .into_iter()
+                .map(|(a, _)| a)
+                .collect::<Vec<_>>();
+            if allergens.is_empty() {
+                println!("{name}");
+            } else {
+                println!("{name} {}", format!("[{}]", allergens.join(", ")).dim());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn listed_ingredients(recipe: &ScalableRecipe) -> Vec<String> {
+    recipe
+        .ingredients
+        .iter()
+        .filter(|i| i.modifiers().should_be_listed())
+        .map(|i| i.name.to_lowercase())
+        .collect()
+}
+
+/// Checks if a recipe matches all the parsed terms
+pub fn matches(
+    terms: &[(bool, Term)],
+    diet: &DietData,
+    name: &str,
+    recipe: &ScalableRecipe,
+) -> bool {
+    let ingredients = listed_ingredients(recipe);
+    let classification = diet.classify(ingredients.iter().map(String::as_str));
+    terms.iter().all(|(negated, term)| {
+        let m = match term {
+            Term::Name(part) => name.to_lowercase().contains(part.as_str()),
+            Term::Tag(tag) => recipe
+                .metadata
+                .tags()
+                .is_some_and(|tags| tags.iter().any(|t| t.to_lowercase() == *tag)),
+            Term::Ingredient(part) => ingredients.iter().any(|i| i.contains(part.as_str())),
+            Term::Cookware(part) => recipe
+                .cookware
+                .iter()
+                .any(|c| c.name.to_lowercase().contains(part.as_str())),
+            Term::Diet(d) => diet
+                .conflicts(&classification, d)
+                .is_ok_and(|c| c.is_empty()),
+            Term::Allergen(a) => classification.has(a),
+        };
+        m != *negated
+    })
+}
@@ -0,0 +1,87 @@
+use anstream::println;
+use anyhow::{bail, Result};
+use clap::Args;
+use yansi::Paint;
+
+use crate::{
+    cook_log::{recipe_key, CookLog},
+    pantry::Date,
+    seasonal::{anniversary, recipe_months},
+    util::{all_recipes, meta_name, CachedRecipeEntry},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct SeasonalArgs {
+    /// Month from 1 to 12, the current one by default
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..=12))]
+    month: Option<u32>,
+
+    /// Display the relative path of the recipes
+    #[arg(short, long)]
+    paths: bool,
+}
+
+pub fn run(ctx: &Context, args: SeasonalArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`seasonal` needs to run inside a collection");
+    }
+
+    let today = Date::today();
+    let month = args.month.unwrap_or_else(|| today.ymd().1);
+    let log = CookLog::load(&ctx.base_path)?;
+
+    let mut entries = all_recipes(ctx)?
+        .map(CachedRecipeEntry::new)
+        .collect::<Vec<_>>();
+    entries.sort_unstable_by(|a, b| a.path().cmp(b.path()));
+
+    let mut in_season = Vec::new();
+    let mut on_this_day = Vec::new();
+    for entry in &entries {
+        let Ok(metadata) = entry.metadata(ctx, false) else {
+            tracing::warn!("Skipping '{}': could not parse metadata", entry.path());
+            continue;
+        };
+        let name = if args.paths {
+            let path = entry.path();
+            path.strip_prefix(&ctx.base_path)
+                .unwrap_or(path)
+                .to_string()
+        } else {
+            meta_name(metadata).unwrap_or(entry.name()).to_string()
+        };
+        if recipe_months(metadata, &ctx.config.ui.tags).is_some_and(|m| m.contains(month)) {
+            in_season.push(name.clone());
+        }
+        // anniversaries only make sense for today
+        if args.month.is_none() {
+            let key = recipe_key(entry.path(), &ctx.base_path);
+            let cooked = log.recipes.get(&key).map_or(&[][..], Vec::as_slice);
+            if let Some(date) = anniversary(cooked, today) {
+                on_this_day.push((name, date));
+            }
+        }
+    }
+
+    if in_season.is_empty() && on_this_day.is_empty() {
+        println!("{}", "No seasonal recipes".dim());
+        return Ok(());
+    }
+    if !in_season.is_empty() {
+        println!("{}", "In season".bold().underline());
+        for name in &in_season {
+            println!("  {name}");
+        }
+    }
+    if !on_this_day.is_empty() {
+        if !in_season.is_empty() {
+            println!();
+        }
+        println!("{}", "On this day".bold().underline());
+        for (name, date) in &on_this_day {
+            println!("  {name} {}", format!("(cooked {})", date.ago(today)).dim());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use anstream::println;
+use anyhow::Result;
+use clap::Args;
+use yansi::Paint;
+
+use crate::{
+    pantry::Date,
+    seasonal::{month_name, ProduceData},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct SeasonalIngredientsArgs {
+    /// Ingredients to look up, none to list the produce in season
+    ingredients: Vec<String>,
+
+    /// Month from 1 to 12, the current one by default
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..=12))]
+    month: Option<u32>,
+
+    /// Region of the seasons, the one in the config by default
+    #[arg(short, long)]
+    region: Option<String>,
+}
+
+pub fn run(ctx: &Context, args: SeasonalIngredientsArgs) -> Result<()> {
+    let month = args.month.unwrap_or_else(|| Date::today().ymd().1);
+    let region_name = args.region.as_deref().unwrap_or(&ctx.config.season.region);
+    let data = ProduceData::load(&ctx.config, &ctx.base_path)?;
+    let region = data.region(region_name)?;
+
+    if args.ingredients.is_empty() {
+        println!(
+            "{}",
+            format!("In season in {} ({region_name})", month_name(month))
+                .bold()
+                .underline()
+        );
+        let mut produce = region.in_season(month).collect::<Vec<_>>();
+        produce.sort_unstable_by_key(|(name, _)| *name);
+        for (name, months) in produce {
+            println!("  {name} {}", months.dim());
+        }
+        return Ok(());
+    }
+
+    for ingredient in &args.ingredients {
+        match region.months_of(ingredient) {
+            Some((_, months)) if months.contains(month) => {
+                println!("{} {}", ingredient.green().bold(), months.dim())
+            }
+            Some((_, months)) => println!(
+                "{} {} {}",
+                ingredient.yellow().bold(),
+                months.dim(),
+                "out of season".yellow()
+            ),
+            None => println!("{} {}", ingredient, "no season data".dim()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use anstream::println;
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use cooklang_self_test::{
+    canonical_parser, canonical_tests, extension_usage, CaseResult, ExtensionUsage,
+};
+use serde::Serialize;
+use yansi::Paint;
+
+use crate::{util::all_recipes, Context};
+
+#[derive(Debug, Args)]
+pub struct SelfTestArgs {
+    /// Skip the checks of the collection recipes
+    #[arg(long)]
+    no_collection: bool,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    /// The canonical tests without extensions
+    canonical: Vec<CaseResult>,
+    /// The canonical tests with the configured extensions
+    configured: Vec<CaseResult>,
+    extensions: Vec<ExtensionUsage>,
+    /// Recipes checked for the extensions
+    recipes: usize,
+}
+
+pub fn run(ctx: &Context, args: SelfTestArgs) -> Result<()> {
+    let parser = ctx.parser()?;
+    let canonical = canonical_tests(&canonical_parser())?;
+    let configured = canonical_tests(parser)?;
+
+    let recipes = if args.no_collection || !ctx.is_collection {
+        Vec::new()
+    } else {
+        all_recipes(ctx)?
+            .filter_map(|entry| {
+                let text = entry.read().ok()?.into_text();
+                Some((entry.name().to_string(), text))
+            })
+            .collect()
+    };
+    let report = Report {
+        extensions: extension_usage(parser, &recipes),
+        recipes: recipes.len(),
+        canonical,
+        configured,
+    };
+
+    match args.format {
+        OutputFormat::Human => print_human(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    let failed = report.canonical.iter().filter(|r| !r.passed).count();
+    if failed > 0 {
+        bail!("{failed} canonical test(s) failed without extensions");
+    }
+    Ok(())
+}
+
+fn print_human(report: &Report) {
+    let summary = |title: &str, results: &[CaseResult]| {
+        let passed = results.iter().filter(|r| r.passed).count();
+        let count = format!("{passed}/{}", results.len());
+        if passed == results.len() {
+            println!("{title}: {}", count.green().bold());
+        } else {
+            println!("{title}: {}", count.red().bold());
+        }
+        for r in results.iter().filter(|r| !r.passed) {
+            println!("  {} {}", "\u{2717}".red(), r.name);
+            let json = |e| serde_json::to_string(e).unwrap_or_default();
+            match &r.got {
+                Some(got) => println!("    {} {}", "got".dim(), json(got)),
+                None => println!("    {}", "got errors".dim()),
+            }
+            if let Some(expected) = &r.expected {
+                println!("    {} {}", "expected".dim(), json(expected));
+            }
+        }
+    };
+    summary("Canonical tests without extensions", &report.canonical);
+    summary("Canonical tests with the extensions", &report.configured);
+
+    println!("\n{}", "Extensions".bold());
+    for usage in &report.extensions {
+        if !usage.enabled {
+            println!("  {} {}", "-".dim(), usage.extension.dim());
+            continue;
+        }
+        if report.recipes == 0 {
+            println!("  {} {}", "\u{2713}".green(), usage.extension);
+        } else {
+            println!(
+                "  {} {} {}",
+                "\u{2713}".green(),
+                usage.extension,
+                format!(
+                    "used by {} of {} recipes",
+                    usage.recipes.len(),
+                    report.recipes
+                )
+                .dim()
+            );
+        }
+    }
+    let portable = report.extensions.iter().all(|u| u.recipes.is_empty());
+    if report.recipes > 0 && portable {
+        println!("\nThe recipes read the same without extensions");
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
@@ -8,11 +8,16 @@ use std::{
 use anyhow::Result;
 use camino::{Utf8Path, Utf8PathBuf};
 use cooklang::{CooklangParser, Metadata};
-use cooklang_fs::{FsIndex, RecipeEntry};
+use cooklang_fs::{DiscoveryRules, FsIndex, RecipeContent, RecipeEntry};
 use notify::{RecommendedWatcher, Watcher};
 use serde::Serialize;
 use tokio::sync::{broadcast, mpsc, RwLock};
 
+use crate::{
+    collection_file::{add_inherited, dir_metadata, inherited_metadata},
+    util::par_map,
+};
+
 pub struct AsyncFsIndex {
     indexes: Arc<RwLock<Indexes>>,
 }
@@ -23,6 +28,35 @@ pub struct RecipeData {
     pub cookware: Vec<String>,
 }
 
+impl RecipeData {
+    fn new(
+        content: &RecipeContent,
+        parser: &CooklangParser,
+        inherited: &serde_yaml::Mapping,
+    ) -> Self {
+        let recipe = content.parse(parser);
+        let mut ingredients = Vec::new();
+        let mut cookware = Vec::new();
+        let mut metadata = None;
+        if let Some(r) = recipe.valid_output() {
+            let mut m = r.metadata.to_owned();
+            add_inherited(&mut m, inherited);
+            metadata = Some(m);
+            for ingredient in &r.ingredients {
+                ingredients.push(ingredient.name.to_owned());
+            }
+            for tool in &r.cookware {
+                cookware.push(tool.name.to_string());
+            }
+        }
+        RecipeData {
+            metadata,
+            ingredients,
+            cookware,
+        }
+    }
+}
+
 struct Indexes {
     parser: Arc<CooklangParser>,
     fs: FsIndex,
@@ -31,33 +65,33 @@ struct Indexes {
 
 impl Indexes {
     fn new(fs: FsIndex, parser: Arc<CooklangParser>) -> Self {
-        let mut srch = BTreeMap::new();
-        let insert_search_entry = |index: &mut BTreeMap<_, _>, entry: RecipeEntry| {
-            let recipe = entry.read().expect("can't read recipe").parse(&parser);
-            let mut ingredients = Vec::new();
-            let mut cookware = Vec::new();
-            let mut metadata = None;
-            if let Some(r) = recipe.valid_output() {
-                metadata = Some(r.metadata.to_owned());
-                for ingredient in &r.ingredients {
-                    ingredients.push(ingredient.name.to_owned());
-                }
-                for tool in &r.cookware {
-                    cookware.push(tool.name.to_string());
-                }
-            }
-            index.insert(
-                entry.path().to_owned(),
-                RecipeData {
-                    metadata,
-                    ingredients,
-                    cookware,
-                },
-            );
-        };
-        for entry in fs.get_all() {
-            insert_search_entry(&mut srch, entry);
-        }
+        let entries = fs.get_all().collect::<Vec<_>>();
+        // the `collection.toml` files are read once per dir
+        let mut dirs = entries
+            .iter()
+            .filter_map(|e| e.path().parent())
+            .collect::<Vec<_>>();
+        dirs.sort_unstable();
+        dirs.dedup();
+        let inherited = dirs
+            .into_iter()
+            .map(|dir| (dir, dir_metadata(dir, fs.base_path())))
+            .collect::<HashMap<_, _>>();
+        let none = serde_yaml::Mapping::new();
+        let data = par_map(&entries, |entry| {
+            let content = entry.read().expect("can't read recipe");
+            let inherited = entry
+                .path()
+                .parent()
+                .and_then(|dir| inherited.get(dir))
+                .unwrap_or(&none);
+            RecipeData::new(&content, &parser, inherited)
+        });
+        let srch = entries
+            .iter()
+            .map(|e| e.path().to_owned())
+            .zip(data)
+            .collect();
 
         Self { fs, srch, parser }
     }
@@ -73,27 +107,10 @@ impl Indexes {
     }
 
     fn insert_srch(&mut self, path: &Utf8Path) -> Result<(), cooklang_fs::Error> {
-        let recipe = RecipeEntry::new(path).read()?.parse(&self.parser);
-        let mut ingredients = Vec::new();
-        let mut cookware = Vec::new();
-        let mut metadata = None;
-        if let Some(r) = recipe.valid_output() {
-            metadata = Some(r.metadata.to_owned());
-            for ingredient in &r.ingredients {
-                ingredients.push(ingredient.name.to_owned());
-            }
-            for tool in &r.cookware {
-                cookware.push(tool.name.to_string());
-            }
-        }
-        self.srch.insert(
-            path.to_owned(),
-            RecipeData {
-                metadata,
-                ingredients,
-                cookware,
-            },
-        );
+        let content = RecipeEntry::new(path).read()?;
+        let inherited = inherited_metadata(path, self.fs.base_path());
+        let data = RecipeData::new(&content, &self.parser, &inherited);
+        self.srch.insert(path.to_owned(), data);
         Ok(())
     }
 
@@ -118,7 +135,7 @@ impl AsyncFsIndex {
     pub fn new(index: FsIndex, parser: Arc<CooklangParser>) -> (Self, broadcast::Receiver<Update>) {
         let (in_updt_tx, mut in_updt_rx) = mpsc::channel::<Update>(1);
         let (out_updates_tx, out_updates_rx) = broadcast::channel::<Update>(1);
-        watch_changes_task(in_updt_tx, index.base_path());
+        watch_changes_task(in_updt_tx, index.base_path(), index.rules().clone());
 
         let indexes = Arc::new(RwLock::new(Indexes::new(index, parser)));
 
@@ -198,9 +215,27 @@ impl AsyncFsIndex {
             .map(|(entry, meta)| map(entry, meta))
             .collect()
     }
+
+    /// Like [`Self::search`] but over some entries, in their order
+    pub async fn filter_map<T>(
+        &self,
+        entries: impl IntoIterator<Item = RecipeEntry>,
+        mut f: impl FnMut(RecipeEntry, Option<&RecipeData>) -> Option<T>,
+        take: usize,
+    ) -> Vec<T> {
+        let indexes = self.indexes.read().await;
+        entries
+            .into_iter()
+            .filter_map(|entry| {
+                let data = indexes.srch.get(entry.path());
+                f(entry, data)
+            })
+            .take(take)
+            .collect()
+    }
 }
 
-fn watch_changes_task(tx: mpsc::Sender<Update>, base_path: &Utf8Path) {
+fn watch_changes_task(tx: mpsc::Sender<Update>, base_path: &Utf8Path, rules: DiscoveryRules) {
     let watched_path = base_path.canonicalize().expect("Bad base path");
     let base_path = base_path.to_owned();
 
@@ -238,7 +273,7 @@ fn watch_changes_task(tx: mpsc::Sender<Update>, base_path: &Utf8Path) {
                     continue;
                 }
             };
-            let paths = iter_paths(&watched_path, &ev.paths);
+            let paths = iter_paths(&watched_path, &ev.paths, &rules);
             match ev.kind {
                 notify::EventKind::Create(_) => {
                     for path in paths {
@@ -249,7 +284,7 @@ fn watch_changes_task(tx: mpsc::Sender<Update>, base_path: &Utf8Path) {
                 }
                 notify::EventKind::Modify(notify::event::ModifyKind::Name(rename)) => {
                     if let Some((from, to)) =
-                        handle_rename(&ev.paths, rename, &mut w_rx, &watched_path).await
+                        handle_rename(&ev.paths, rename, &mut w_rx, &watched_path, &rules).await
                     {
                         send(Update::Renamed {
                             from: restore_path(from),
@@ -289,8 +324,9 @@ async fn handle_rename(
     rename: notify::event::RenameMode,
     w_rx: &mut mpsc::Receiver<Result<notify::Event, notify::Error>>,
     watched_path: &Path,
+    rules: &DiscoveryRules,
 ) -> Option<(Utf8PathBuf, Utf8PathBuf)> {
-    let mut paths = iter_paths(watched_path, paths);
+    let mut paths = iter_paths(watched_path, paths, rules);
 
     match rename {
         notify::event::RenameMode::From => {
@@ -305,7 +341,8 @@ async fn handle_rename(
             };
 
             if let Some(Ok(next_ev)) = next_res {
-                let mut next_paths = iter_paths(watched_path, &next_ev.paths).collect::<Vec<_>>();
+                let mut next_paths =
+                    iter_paths(watched_path, &next_ev.paths, rules).collect::<Vec<_>>();
                 if next_paths.len() != 1 {
                     return None;
                 }
@@ -335,6 +372,7 @@ async fn handle_rename(
 fn iter_paths<'a>(
     base_path: &'a Path,
     paths: &'a [PathBuf],
+    rules: &'a DiscoveryRules,
 ) -> impl Iterator<Item = Utf8PathBuf> + 'a {
     paths
         .iter()
@@ -343,7 +381,7 @@ fn iter_paths<'a>(
                 .ok()
                 .and_then(|p| Utf8Path::from_path(p).map(Utf8Path::to_path_buf))
         })
-        .filter(|p| p.extension() == Some("cook"))
+        .filter(|p| rules.is_recipe_extension(p) && rules.allows_file(p))
 }
 
 fn async_watcher() -> notify::Result<(
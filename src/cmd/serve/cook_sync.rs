@@ -0,0 +1,87 @@
+//! Cooking sessions shared between devices
+//!
+//! Every recipe has a room. The state of the session in it, the checked
+//! ingredients and cookware, the current step and the timer, is the last one
+//! a device sent, and it goes to all the devices in the room. The web UI
+//! sends and receives it over a WebSocket, `chef cook --follow --sync` posts
+//! it.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Messages waiting for a slow device before it misses some
+const ROOM_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CookState {
+    /// Device that sent it, so it can skip its own changes
+    #[serde(default)]
+    pub from: String,
+    /// Ids of the checked ingredients and cookware
+    #[serde(default)]
+    pub checked: Vec<String>,
+    /// Current step as `<section index>-<step number>`
+    #[serde(default)]
+    pub step: Option<String>,
+    #[serde(default)]
+    pub timer: Option<TimerState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerState {
+    pub name: Option<String>,
+    /// `running` or `paused`
+    pub state: String,
+    /// Unix time in milliseconds when a running timer ends
+    pub end: Option<u64>,
+    /// Seconds left of a paused timer
+    #[serde(default)]
+    pub seconds: u64,
+}
+
+struct Room {
+    state: Option<Arc<CookState>>,
+    tx: broadcast::Sender<Arc<CookState>>,
+}
+
+#[derive(Default)]
+pub struct CookRooms {
+    rooms: Mutex<HashMap<String, Room>>,
+}
+
+impl CookRooms {
+    /// The current state of a room and the next ones
+    pub fn join(
+        &self,
+        room: &str,
+    ) -> (Option<Arc<CookState>>, broadcast::Receiver<Arc<CookState>>) {
+        let mut rooms = self.rooms.lock().unwrap();
+        let room = rooms.entry(room.to_string()).or_insert_with(|| Room {
+            state: None,
+            tx: broadcast::channel(ROOM_CAPACITY).0,
+        });
+        (room.state.clone(), room.tx.subscribe())
+    }
+
+    pub fn state(&self, room: &str) -> Option<Arc<CookState>> {
+        let rooms = self.rooms.lock().unwrap();
+        rooms.get(room).and_then(|r| r.state.clone())
+    }
+
+    pub fn publish(&self, room: &str, state: CookState) {
+        let state = Arc::new(state);
+        let mut rooms = self.rooms.lock().unwrap();
+        let room = rooms.entry(room.to_string()).or_insert_with(|| Room {
+            state: None,
+            tx: broadcast::channel(ROOM_CAPACITY).0,
+        });
+        room.state = Some(Arc::clone(&state));
+        // no one listening is fine, the state is kept for the next one
+        let _ = room.tx.send(state);
+    }
+}
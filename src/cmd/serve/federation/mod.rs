@@ -0,0 +1,757 @@
+//! ActivityPub publishing
+//!
+//! With a `[federation]` config, the server is an ActivityPub actor that can
+//! be followed from Mastodon-like clients as `@username@host`. Recipes are
+//! published as `Article` objects with the cooklang text as their `source`,
+//! and new or changed recipes are sent to the followers while the server
+//! runs. Private and encrypted recipes are never published.
+//!
+//! Posts sent to the actor, like mentions, are kept and shown in
+//! `/federation`.
+//!
+//! State lives in `.cooklang/federation`: the followers and the received
+//! posts.
+
+mod signature;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context as _, Result};
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use cooklang::Metadata;
+use cooklang_fs::RecipeEntry;
+use minijinja::context;
+use ring::signature::RsaKeyPair;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+
+use self::signature::Signature;
+use super::{
+    async_index::Update,
+    handlers::{check_path, clean_path, mj_ok, ok_status},
+    locale::UserLocale,
+    S,
+};
+use crate::{
+    config::{resolve_path, FederationConfig},
+    encryption::is_marked_private,
+    pantry::rfc3339,
+    util::{encode_path, escape_xml, meta_name},
+    COOK_DIR,
+};
+
+const FEDERATION_DIR: &str = "federation";
+const FOLLOWERS_FILE: &str = "followers.json";
+const RECEIVED_FILE: &str = "received.json";
+
+const AS_CONTEXT: &str = "https://www.w3.org/ns/activitystreams";
+const SECURITY_CONTEXT: &str = "https://w3id.org/security/v1";
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+const ACTIVITY_JSON: &str = "application/activity+json";
+const COOKLANG_MEDIA_TYPE: &str = "text/x-cooklang";
+
+const OUTBOX_SIZE: usize = 20;
+const MAX_RECEIVED: usize = 100;
+
+pub struct Federation {
+    config: FederationConfig,
+    url: String,
+    key: RsaKeyPair,
+    public_key_pem: String,
+    dir: Utf8PathBuf,
+    /// Actor id to inbox
+    followers: Mutex<BTreeMap<String, String>>,
+    received: Mutex<Vec<Received>>,
+}
+
+/// A post sent to the actor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Received {
+    id: String,
+    actor: String,
+    #[serde(default)]
+    name: Option<String>,
+    /// Content without HTML
+    text: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    published: Option<String>,
+    /// Cooklang text, if it's a recipe
+    #[serde(default)]
+    source: Option<String>,
+}
+
+impl Federation {
+    pub fn load(config: &FederationConfig, base_path: &Utf8Path) -> Result<Self> {
+        let private_key = resolve_path(base_path, &config.private_key);
+        let pem = std::fs::read_to_string(&private_key)
+            .with_context(|| format!("Failed to read private key {private_key}"))?;
+        let key = RsaKeyPair::from_pkcs8(&signature::pem_to_der(&pem)?)
+            .map_err(|e| anyhow::anyhow!("Invalid private key {private_key}: {e}"))?;
+        let public_key = resolve_path(base_path, &config.public_key);
+        let public_key_pem = std::fs::read_to_string(&public_key)
+            .with_context(|| format!("Failed to read public key {public_key}"))?;
+
+        let dir = base_path.join(COOK_DIR).join(FEDERATION_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let followers = load_json(&dir.join(FOLLOWERS_FILE))?;
+        let received = load_json(&dir.join(RECEIVED_FILE))?;
+
+        Ok(Self {
+            url: config.url.trim_end_matches('/').to_string(),
+            config: config.clone(),
+            key,
+            public_key_pem,
+            dir,
+            followers: Mutex::new(followers),
+            received: Mutex::new(received),
+        })
+    }
+
+    fn actor_id(&self) -> String {
+        format!("{}/ap/actor", self.url)
+    }
+
+    fn followers_id(&self) -> String {
+        format!("{}/ap/followers", self.url)
+    }
+
+    fn key_id(&self) -> String {
+        format!("{}#main-key", self.actor_id())
+    }
+
+    fn save_followers(&self, followers: &BTreeMap<String, String>) -> Result<()> {
+        save_json(&self.dir.join(FOLLOWERS_FILE), followers)
+    }
+
+    fn save_received(&self, received: &[Received]) -> Result<()> {
+        save_json(&self.dir.join(RECEIVED_FILE), received)
+    }
+
+    /// Fetches an ActivityPub object, signed for servers that need it
+    fn fetch(&self, url: &str) -> Result<Value> {
+        let mut req = ureq::get(url).set("Accept", ACTIVITY_JSON);
+        for (name, value) in signature::sign(&self.key, &self.key_id(), "GET", url, None)? {
+            if name != "Host" {
+                req = req.set(name, &value);
+            }
+        }
+        req.call()
+            .with_context(|| format!("Failed to fetch {url}"))?
+            .into_json()
+            .with_context(|| format!("Invalid object in {url}"))
+    }
+
+    /// Sends an activity to an inbox
+    fn deliver(&self, inbox: &str, activity: &Value) -> Result<()> {
+        // the name may point somewhere else since it was followed
+        if let Some((host, _)) = signature::split_url(inbox) {
+            check_public_host(host)?;
+        }
+        let body = serde_json::to_vec(activity)?;
+        let mut req = ureq::post(inbox).set(CONTENT_TYPE.as_str(), ACTIVITY_JSON);
+        for (name, value) in signature::sign(&self.key, &self.key_id(), "POST", inbox, Some(&body))?
+        {
+            if name != "Host" {
+                req = req.set(name, &value);
+            }
+        }
+        req.send_bytes(&body)
+            .with_context(|| format!("Failed to deliver to {inbox}"))?;
+        Ok(())
+    }
+
+    fn deliver_to_followers(&self, activity: &Value) {
+        let inboxes: Vec<String> = {
+            let followers = self.followers.lock().unwrap();
+            let mut inboxes: Vec<_> = followers.values().cloned().collect();
+            inboxes.sort();
+            inboxes.dedup();
+            inboxes
+        };
+        for inbox in inboxes {
+            if let Err(e) = self.deliver(&inbox, activity) {
+                tracing::warn!("{e:#}");
+            }
+        }
+    }
+
+    fn actor(&self) -> Value {
+        let actor = self.actor_id();
+        json!({
+            "@context": [AS_CONTEXT, SECURITY_CONTEXT],
+            "id": actor,
+            "type": "Service",
+            "preferredUsername": self.config.username,
+            "name": self.config.display_name.as_deref().unwrap_or(&self.config.username),
+            "summary": self.config.summary,
+            "url": self.url,
+            "inbox": format!("{}/ap/inbox", self.url),
+            "outbox": format!("{}/ap/outbox", self.url),
+            "followers": self.followers_id(),
+            "publicKey": {
+                "id": self.key_id(),
+                "owner": actor,
+                "publicKeyPem": self.public_key_pem,
+            }
+        })
+    }
+
+    /// A recipe as an ActivityPub object
+    ///
+    /// `None` if it can't be published.
+    fn article(
+        &self,
+        entry: &RecipeEntry,
+        metadata: &Metadata,
+        base_path: &Utf8Path,
+    ) -> Option<Value> {
+        if entry.is_encrypted() || is_marked_private(metadata) {
+            return None;
+        }
+        let text = entry.read().ok()?.into_text();
+        let path = encode_path(
+            clean_path(entry.path(), base_path)
+                .with_extension("")
+                .as_str(),
+        );
+        let link = format!("{}/r/{path}", self.url);
+        let name = meta_name(metadata).unwrap_or(entry.name());
+        let description = metadata.description();
+
+        let fs_metadata = entry.path().metadata().ok()?;
+        let updated = fs_metadata.modified().ok()?;
+        let published = fs_metadata.created().unwrap_or(updated);
+
+        let tags = metadata.tags().unwrap_or(vec![]);
+        let mut content = String::new();
+        if let Some(desc) = description {
+            content += &format!("<p>{}</p>", escape_xml(desc));
+        }
+        content += &format!(r#"<p><a href="{link}">{}</a></p>"#, escape_xml(name));
+        if !tags.is_empty() {
+            let tags: Vec<_> = tags
+                .iter()
+                .map(|t| format!("#{}", escape_xml(&hashtag(t))))
+                .collect();
+            content += &format!("<p>{}</p>", tags.join(" "));
+        }
+
+        Some(json!({
+            "id": format!("{}/ap/r/{path}", self.url),
+            "type": "Article",
+            "url": link,
+            "attributedTo": self.actor_id(),
+            "name": name,
+            "summary": description,
+            "content": content,
+            "published": rfc3339(published),
+            "updated": rfc3339(updated),
+            "to": [PUBLIC],
+            "cc": [self.followers_id()],
+            "tag": tags.iter().map(|t| json!({
+                "type": "Hashtag",
+                "name": format!("#{}", hashtag(t)),
+            })).collect::<Vec<_>>(),
+            "source": {
+                "content": text,
+                "mediaType": COOKLANG_MEDIA_TYPE,
+            }
+        }))
+    }
+
+    fn activity(&self, kind: &str, object: Value) -> Value {
+        let time = object["updated"].as_str().unwrap_or_default().to_string();
+        json!({
+            "@context": AS_CONTEXT,
+            "id": format!("{}#{}-{time}", object["id"].as_str().unwrap_or_default(), kind.to_lowercase()),
+            "type": kind,
+            "actor": self.actor_id(),
+            "published": time,
+            "to": [PUBLIC],
+            "cc": [self.followers_id()],
+            "object": object,
+        })
+    }
+
+    /// Checks the signature of an inbox request and returns the sender
+    fn verify(&self, headers: &HeaderMap, body: &[u8], activity: &Value) -> Result<Value> {
+        let header = headers
+            .get("signature")
+            .and_then(|v| v.to_str().ok())
+            .context("Request not signed")?;
+        let signature = Signature::parse(header)?;
+        let actor_url = signature
+            .key_id
+            .split_once('#')
+            .map_or(signature.key_id.as_str(), |(url, _)| url);
+        if signature::split_url(actor_url).is_none() {
+            bail!("Invalid key id {}", signature.key_id);
+        }
+        let actor = self.fetch(actor_url)?;
+        // anyone can point to the key of someone else
+        if actor["id"].as_str() != Some(actor_url)
+            || actor["publicKey"]["id"].as_str() != Some(signature.key_id.as_str())
+            || actor["publicKey"]["owner"].as_str() != Some(actor_url)
+        {
+            bail!("The key {} is not owned by {actor_url}", signature.key_id);
+        }
+        let pem = actor["publicKey"]["publicKeyPem"]
+            .as_str()
+            .context("Actor without public key")?;
+        signature.verify("POST", "/ap/inbox", headers, body, pem)?;
+        if activity["actor"].as_str() != actor["id"].as_str() {
+            bail!("The activity is not from the signer");
+        }
+        Ok(actor)
+    }
+
+    fn handle(&self, activity: Value, actor: Value) -> Result<()> {
+        let actor_id = actor["id"].as_str().unwrap_or_default().to_string();
+        match activity["type"].as_str().unwrap_or_default() {
+            "Follow" => {
+                if activity["object"].as_str() != Some(self.actor_id().as_str()) {
+                    bail!("Follow of another actor");
+                }
+                let inbox = actor["inbox"].as_str().context("Actor without inbox")?;
+                check_inbox(&actor_id, inbox)?;
+                {
+                    let mut followers = self.followers.lock().unwrap();
+                    followers.insert(actor_id.clone(), inbox.to_string());
+                    self.save_followers(&followers)?;
+                }
+                tracing::info!("New follower: {actor_id}");
+                let now = SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                let accept = json!({
+                    "@context": AS_CONTEXT,
+                    "id": format!("{}#accept-{now}", self.actor_id()),
+                    "type": "Accept",
+                    "actor": self.actor_id(),
+                    "object": activity,
+                });
+                self.deliver(inbox, &accept)?;
+            }
+            "Undo" if activity["object"]["type"] == "Follow" => {
+                let mut followers = self.followers.lock().unwrap();
+                if followers.remove(&actor_id).is_some() {
+                    tracing::info!("Unfollowed by {actor_id}");
+                    self.save_followers(&followers)?;
+                }
+            }
+            "Create" | "Update" => {
+                let object = &activity["object"];
+                let Some(id) = object["id"].as_str() else {
+                    bail!("Object without id");
+                };
+                let source = (object["source"]["mediaType"] == COOKLANG_MEDIA_TYPE)
+                    .then(|| object["source"]["content"].as_str().map(String::from))
+                    .flatten();
+                let post = Received {
+                    id: id.to_string(),
+                    actor: actor_id,
+                    name: object["name"].as_str().map(String::from),
+                    text: strip_html(object["content"].as_str().unwrap_or_default()),
+                    // shown as a link, no `javascript:` URLs
+                    url: object["url"]
+                        .as_str()
+                        .filter(|u| is_http_url(u))
+                        .map(String::from),
+                    published: object["published"].as_str().map(String::from),
+                    source,
+                };
+                let mut received = self.received.lock().unwrap();
+                received.retain(|r| r.id != post.id);
+                received.insert(0, post);
+                received.truncate(MAX_RECEIVED);
+                self.save_received(&received)?;
+            }
+            "Delete" => {
+                let id = activity["object"]["id"]
+                    .as_str()
+                    .or(activity["object"].as_str())
+                    .unwrap_or_default();
+                // only the author deletes a post, and an actor only itself
+                let deletes_actor = id == actor_id;
+                let mut received = self.received.lock().unwrap();
+                let before = received.len();
+                received.retain(|r| r.actor != actor_id || (r.id != id && !deletes_actor));
+                if received.len() != before {
+                    self.save_received(&received)?;
+                }
+                drop(received);
+                if deletes_actor {
+                    let mut followers = self.followers.lock().unwrap();
+                    if followers.remove(id).is_some() {
+                        self.save_followers(&followers)?;
+                    }
+                }
+            }
+            other => tracing::debug!("Ignoring {other} activity"),
+        }
+        Ok(())
+    }
+}
+
+pub fn routes() -> Router<S> {
+    Router::new()
+        .route("/.well-known/webfinger", get(webfinger))
+        .route("/ap/actor", get(actor))
+        .route("/ap/outbox", get(outbox))
+        .route("/ap/followers", get(followers))
+        .route("/ap/inbox", post(inbox))
+        .route("/ap/r/{*path}", get(object))
+        .route("/federation", get(received))
+}
+
+/// Sends new and changed recipes to the followers
+pub fn publish_updates(state: S) {
+    let mut updates = state.updates_stream.resubscribe();
+    tokio::spawn(async move {
+        loop {
+            let (path, kind) = match updates.recv().await {
+                Ok(Update::Added { path }) => (path, "Create"),
+                Ok(Update::Renamed { to, .. }) => (to, "Create"),
+                Ok(Update::Modified { path }) => (path, "Update"),
+                // deleting would tell the name of private recipes
+                Ok(Update::Deleted { .. }) => continue,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let state = Arc::clone(&state);
+            tokio::task::spawn_blocking(move || {
+                let fed = federation(&state);
+                let entry = RecipeEntry::new(&path);
+                let Some(metadata) = entry
+                    .read()
+                    .ok()
+                    .and_then(|c| c.metadata(&state.parser).into_output())
+                else {
+                    return;
+                };
+                if let Some(article) = fed.article(&entry, &metadata, &state.base_path) {
+                    tracing::info!("Publishing '{path}'");
+                    fed.deliver_to_followers(&fed.activity(kind, article));
+                }
+            });
+        }
+    });
+}
+
+fn federation(state: &S) -> &Federation {
+    state
+        .federation
+        .as_ref()
+        .expect("federation routes without config")
+}
+
+fn activity_json(value: Value) -> Response {
+    ([(CONTENT_TYPE, ACTIVITY_JSON)], Json(value)).into_response()
+}
+
+async fn webfinger(
+    State(state): State<S>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let fed = federation(&state);
+    let host = signature::split_url(&fed.url).map_or("", |(host, _)| host);
+    let expected = format!("acct:{}@{host}", fed.config.username);
+    match query.get("resource") {
+        Some(resource) if resource.eq_ignore_ascii_case(&expected) => (
+            [(CONTENT_TYPE, "application/jrd+json")],
+            Json(json!({
+                "subject": expected,
+                "links": [{
+                    "rel": "self",
+                    "type": ACTIVITY_JSON,
+                    "href": fed.actor_id(),
+                }]
+            })),
+        )
+            .into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn actor(State(state): State<S>) -> Response {
+    activity_json(federation(&state).actor())
+}
+
+async fn followers(State(state): State<S>) -> Response {
+    let fed = federation(&state);
+    let total = fed.followers.lock().unwrap().len();
+    activity_json(json!({
+        "@context": AS_CONTEXT,
+        "id": fed.followers_id(),
+        "type": "OrderedCollection",
+        "totalItems": total,
+    }))
+}
+
+async fn outbox(State(state): State<S>) -> Response {
+    let recipes = state
+        .recipe_index
+        .search(
+            |entry, _| !entry.is_encrypted(),
+            |entry, data| (entry, data.and_then(|d| d.metadata.clone())),
+            0,
+            usize::MAX,
+        )
+        .await;
+    let fed = federation(&state);
+    let mut articles: Vec<Value> = tokio::task::block_in_place(|| {
+        recipes
+            .iter()
+            .filter_map(|(entry, metadata)| {
+                fed.article(entry, metadata.as_ref()?, &state.base_path)
+            })
+            .collect()
+    });
+    // RFC 3339 in UTC sorts like the time
+    articles.sort_by(|a, b| b["updated"].as_str().cmp(&a["updated"].as_str()));
+    let total = articles.len();
+    let items: Vec<Value> = articles
+        .into_iter()
+        .take(OUTBOX_SIZE)
+        .map(|a| fed.activity("Create", a))
+        .collect();
+    activity_json(json!({
+        "@context": AS_CONTEXT,
+        "id": format!("{}/ap/outbox", fed.url),
+        "type": "OrderedCollection",
+        "totalItems": total,
+        "orderedItems": items,
+    }))
+}
+
+async fn object(State(state): State<S>, Path(path): Path<String>) -> Response {
+    if let Err(e) = check_path(&path) {
+        return e.into_response();
+    }
+    let entry = ok_status!(state.recipe_index.get(&path).await, NOT_FOUND);
+    let fed = federation(&state);
+    let article = tokio::task::block_in_place(|| {
+        let metadata = entry.read().ok()?.metadata(&state.parser).into_output()?;
+        let mut article = fed.article(&entry, &metadata, &state.base_path)?;
+        article["@context"] = json!(AS_CONTEXT);
+        Some(article)
+    });
+    match article {
+        Some(article) => activity_json(article),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn inbox(State(state): State<S>, headers: HeaderMap, body: Bytes) -> Response {
+    let activity: Value = ok_status!(serde_json::from_slice(&body), BAD_REQUEST);
+    let res = tokio::task::spawn_blocking(move || {
+        let fed = federation(&state);
+        let actor = match fed.verify(&headers, &body, &activity) {
+            Ok(actor) => actor,
+            Err(e) => {
+                tracing::warn!("Rejected inbox request: {e:#}");
+                return StatusCode::UNAUTHORIZED;
+            }
+        };
+        if let Err(e) = fed.handle(activity, actor) {
+            tracing::warn!("Failed to handle activity: {e:#}");
+        }
+        StatusCode::ACCEPTED
+    })
+    .await;
+    ok_status!(res).into_response()
+}
+
+async fn received(UserLocale(t): UserLocale, State(state): State<S>) -> Response {
+    let tmpl = mj_ok!(state.templates.get_template("federation.html"));
+    let fed = federation(&state);
+    let mut posts = fed.received.lock().unwrap().clone();
+    // they are links, autoescaping doesn't stop `javascript:` URLs
+    posts.retain(|p| is_http_url(&p.actor));
+    for post in &mut posts {
+        post.url = post.url.take().filter(|u| is_http_url(u));
+    }
+    let host = signature::split_url(&fed.url).map_or("", |(host, _)| host);
+    let res = tmpl.render(context! {
+        t,
+        account => format!("@{}@{host}", fed.config.username),
+        posts,
+    });
+    Html(mj_ok!(res)).into_response()
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// Checks an inbox before sending requests to it
+///
+/// It must be on the host of its actor and not in a local network, or
+/// anyone could make the server send requests to other servers.
+fn check_inbox(actor_id: &str, inbox: &str) -> Result<()> {
+    let (Some((actor_host, _)), Some((host, _))) =
+        (signature::split_url(actor_id), signature::split_url(inbox))
+    else {
+        bail!("Invalid inbox {inbox}");
+    };
+    if !host.eq_ignore_ascii_case(actor_host) {
+        bail!("The inbox {inbox} is not on the host of {actor_id}");
+    }
+    check_public_host(host)
+}
+
+/// Fails if a host, with an optional port, resolves to a local address
+fn check_public_host(host: &str) -> Result<()> {
+    use std::net::ToSocketAddrs;
+
+    let has_port = !host.ends_with(']')
+        && host
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    let addr = if has_port {
+        host.to_string()
+    } else {
+        format!("{host}:443")
+    };
+    let ips = addr
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve {host}"))?;
+    for ip in ips.map(|a| a.ip()) {
+        if is_local(ip) {
+            bail!("{host} is a local address ({ip})");
+        }
+    }
+    Ok(())
+}
+
+fn is_local(ip: std::net::IpAddr) -> bool {
+    use std::net::IpAddr;
+
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // shared address space, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_local(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                // unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // link local, fe80::/10
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+fn load_json<T: serde::de::DeserializeOwned + Default>(path: &Utf8Path) -> Result<T> {
+    if !path.is_file() {
+        return Ok(T::default());
+    }
+    let content = std::fs::read_to_string(path)?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {path}"))
+}
+
+fn save_json<T: Serialize + ?Sized>(path: &Utf8Path, value: &T) -> Result<()> {
+    let content = serde_json::to_string_pretty(value)?;
+    std::fs::write(path, content).with_context(|| format!("Failed to write {path}"))
+}
+
+/// A tag as a hashtag, without spaces or dashes
+fn hashtag(tag: &str) -> String {
+    tag.split([' ', '-', '_'])
+        .filter(|w| !w.is_empty())
+        .enumerate()
+        .map(|(i, w)| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(c) if i > 0 => c.to_uppercase().chain(chars).collect(),
+                _ => w.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Text of the HTML content of a post
+fn strip_html(html: &str) -> String {
+    let html = html
+        .replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("</p>", "\n\n");
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_helpers() {
+        assert_eq!(hashtag("quick meals"), "quickMeals");
+        assert_eq!(hashtag("vegan"), "vegan");
+        assert_eq!(
+            strip_html(r#"<p><span class="h-card"><a href="x">@me</a></span> try &amp; see</p>"#),
+            "@me try & see"
+        );
+    }
+
+    #[test]
+    fn inboxes() {
+        assert!(is_local("127.0.0.1".parse().unwrap()));
+        assert!(is_local("10.1.2.3".parse().unwrap()));
+        assert!(is_local("100.100.0.1".parse().unwrap()));
+        assert!(is_local("::1".parse().unwrap()));
+        assert!(is_local("fd00::1".parse().unwrap()));
+        assert!(is_local("::ffff:192.168.1.1".parse().unwrap()));
+        assert!(!is_local("93.184.215.14".parse().unwrap()));
+        assert!(!is_local("2606:4700::1".parse().unwrap()));
+
+        let actor = "https://93.184.215.14/users/me";
+        assert!(check_inbox(actor, "https://93.184.215.14/inbox").is_ok());
+        assert!(check_inbox(actor, "https://93.184.215.15/inbox").is_err());
+        assert!(check_inbox("https://127.0.0.1/me", "https://127.0.0.1/inbox").is_err());
+        assert!(check_inbox("https://[::1]:8080/me", "https://[::1]:8080/inbox").is_err());
+        assert!(check_inbox(actor, "file:///etc/passwd").is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! HTTP signatures, as used by Mastodon
+//!
+//! Only `rsa-sha256` over the `(request-target)`, `host`, `date` and, with a
+//! body, `digest` headers, which is what the fediverse uses.
+
+use anyhow::{bail, Context as _, Result};
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ring::{
+    digest::{digest, SHA256},
+    rand::SystemRandom,
+    signature::{RsaKeyPair, UnparsedPublicKey, RSA_PKCS1_2048_8192_SHA256, RSA_PKCS1_SHA256},
+};
+
+/// Max difference with the `Date` of a signed request
+const MAX_CLOCK_SKEW: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Decodes the base64 content of a PEM file
+pub fn pem_to_der(pem: &str) -> Result<Vec<u8>> {
+    let data: String = pem
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.starts_with("-----"))
+        .collect();
+    STANDARD.decode(data).context("Invalid PEM")
+}
+
+pub fn digest_header(body: &[u8]) -> String {
+    format!("SHA-256={}", STANDARD.encode(digest(&SHA256, body)))
+}
+
+/// Splits an URL into host and path with query
+pub fn split_url(url: &str) -> Option<(&str, &str)> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    match rest.find('/') {
+        Some(i) => Some((&rest[..i], &rest[i..])),
+        None => Some((rest, "/")),
+    }
+}
+
+/// Headers to add to a request signed with `key`
+pub fn sign(
+    key: &RsaKeyPair,
+    key_id: &str,
+    method: &str,
+    url: &str,
+    body: Option<&[u8]>,
+) -> Result<Vec<(&'static str, String)>> {
+    let Some((host, path)) = split_url(url) else {
+        bail!("Invalid URL: {url}");
+    };
+    let mut headers = vec![
+        ("Host", host.to_string()),
+        (
+            "Date",
+            httpdate::fmt_http_date(std::time::SystemTime::now()),
+        ),
+    ];
+    if let Some(body) = body {
+        headers.push(("Digest", digest_header(body)));
+    }
+
+    let mut names = vec!["(request-target)".to_string()];
+    let mut lines = vec![format!(
+        "(request-target): {} {path}",
+        method.to_lowercase()
+    )];
+    for (name, value) in &headers {
+        names.push(name.to_lowercase());
+        lines.push(format!("{}: {value}", name.to_lowercase()));
+    }
+
+    let mut signature = vec![0; key.public().modulus_len()];
+    key.sign(
+        &RSA_PKCS1_SHA256,
+        &SystemRandom::new(),
+        lines.join("\n").as_bytes(),
+        &mut signature,
+    )
+    .map_err(|_| anyhow::anyhow!("Failed to sign request"))?;
+
+    headers.push((
+        "Signature",
+        format!(
+            r#"keyId="{key_id}",algorithm="rsa-sha256",headers="{}",signature="{}""#,
+            names.join(" "),
+            STANDARD.encode(signature)
+        ),
+    ));
+    Ok(headers)
+}
+
+/// Parts of a `Signature` header
+#[derive(Debug, PartialEq)]
+pub struct Signature {
+    pub key_id: String,
+    pub headers: Vec<String>,
+    pub signature: Vec<u8>,
+}
+
+impl Signature {
+    pub fn parse(header: &str) -> Result<Self> {
+        let mut key_id = None;
+        let mut headers = None;
+        let mut signature = None;
+        for part in header.split(',') {
+            let Some((key, value)) = part.trim().split_once('=') else {
+                continue;
+            };
+            let value = value.trim_matches('"');
+            match key {
+                "keyId" => key_id = Some(value.to_string()),
+                "headers" => headers = Some(value.split(' ').map(String::from).collect()),
+                "signature" => {
+                    signature = Some(STANDARD.decode(value).context("Invalid signature")?)
+                }
+                _ => {}
+            }
+        }
+        Ok(Self {
+            key_id: key_id.context("Signature without keyId")?,
+            // the default in the spec
+            headers: headers.unwrap_or_else(|| vec!["date".to_string()]),
+            signature: signature.context("Signature without signature")?,
+        })
+    }
+
+    /// The text that was signed
+    pub fn signing_string(&self, method: &str, path: &str, headers: &HeaderMap) -> Result<String> {
+        let mut lines = Vec::with_capacity(self.headers.len());
+        for name in &self.headers {
+            if name == "(request-target)" {
+                lines.push(format!(
+                    "(request-target): {} {path}",
+                    method.to_lowercase()
+                ));
+            } else {
+                let value = headers
+                    .get(name.as_str())
+                    .and_then(|v| v.to_str().ok())
+                    .with_context(|| format!("Signed header '{name}' missing"))?;
+                lines.push(format!("{name}: {value}"));
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Checks the signature of a request with the PEM public key of its
+    /// sender
+    pub fn verify(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+        public_key_pem: &str,
+    ) -> Result<()> {
+        if !self.headers.iter().any(|h| h == "date") {
+            bail!("The date is not signed");
+        }
+        let date = headers
+            .get("date")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|d| httpdate::parse_http_date(d).ok())
+            .context("Invalid date")?;
+        let now = std::time::SystemTime::now();
+        let skew = now
+            .duration_since(date)
+            .or_else(|_| date.duration_since(now))
+            .unwrap_or_default();
+        if skew > MAX_CLOCK_SKEW {
+            bail!("Request too old or from the future");
+        }
+        if !body.is_empty() {
+            if !self.headers.iter().any(|h| h == "digest") {
+                bail!("The body is not signed");
+            }
+            let digest = headers.get("digest").and_then(|v| v.to_str().ok());
+            if digest != Some(digest_header(body).as_str()) {
+                bail!("Digest does not match the body");
+            }
+        }
+
+        let spki = pem_to_der(public_key_pem)?;
+        let key = spki_to_pkcs1(&spki).context("Unsupported public key")?;
+        let message = self.signing_string(method, path, headers)?;
+        UnparsedPublicKey::new(&RSA_PKCS1_2048_8192_SHA256, key)
+            .verify(message.as_bytes(), &self.signature)
+            .map_err(|_| anyhow::anyhow!("Invalid signature"))
+    }
+}
+
+/// Gets the RSA key from a `SubjectPublicKeyInfo`, what `BEGIN PUBLIC KEY`
+/// has
+fn spki_to_pkcs1(spki: &[u8]) -> Option<&[u8]> {
+    let (0x30, info, _) = der_element(spki)? else {
+        return None;
+    };
+    let (0x30, _algorithm, rest) = der_element(info)? else {
+        return None;
+    };
+    let (0x03, bits, _) = der_element(rest)? else {
+        return None;
+    };
+    // first byte is the number of unused bits
+    bits.strip_prefix(&[0])
+}
+
+/// Tag, content and the rest of a DER element
+fn der_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first = *data.get(1)?;
+    let (len, header) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 {
+            return None;
+        }
+        let bytes = data.get(2..2 + n)?;
+        (bytes.iter().fold(0, |acc, &b| acc << 8 | b as usize), 2 + n)
+    };
+    let content = data.get(header..header + len)?;
+    Some((tag, content, &data[header + len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_signature() {
+        let sig = Signature::parse(
+            r#"keyId="https://a.example/users/x#main-key",algorithm="rsa-sha256",headers="(request-target) host date",signature="AQID""#,
+        )
+        .unwrap();
+        assert_eq!(sig.key_id, "https://a.example/users/x#main-key");
+        assert_eq!(sig.headers, ["(request-target)", "host", "date"]);
+        assert_eq!(sig.signature, [1, 2, 3]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("host", "b.example".parse().unwrap());
+        headers.insert("date", "Tue, 07 Jun 2022 20:51:35 GMT".parse().unwrap());
+        assert_eq!(
+            sig.signing_string("POST", "/ap/inbox", &headers).unwrap(),
+            "(request-target): post /ap/inbox\nhost: b.example\ndate: Tue, 07 Jun 2022 20:51:35 GMT"
+        );
+    }
+
+    #[test]
+    fn der() {
+        // SEQUENCE { SEQUENCE { NULL }, BIT STRING { 0, 0xAB } }
+        let spki = [0x30, 0x08, 0x30, 0x02, 0x05, 0x00, 0x03, 0x02, 0x00, 0xab];
+        assert_eq!(spki_to_pkcs1(&spki), Some(&[0xab][..]));
+        assert_eq!(spki_to_pkcs1(&spki[..5]), None);
+        assert_eq!(split_url("https://a.example"), Some(("a.example", "/")));
+        assert_eq!(
+            split_url("https://a.example/users/x/inbox"),
+            Some(("a.example", "/users/x/inbox"))
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! GraphQL API of the server, needs the `graphql` feature
+//!
+//! Listing and filtering only use the search index of the server. Fields that
+//! need the whole recipe, like the quantities, parse it when asked for.
+
+use std::collections::BTreeMap;
+
+use async_graphql::{
+    http::GraphiQLSource, EmptyMutation, EmptySubscription, InputObject, Object, Result, Schema,
+    SimpleObject,
+};
+use async_graphql_axum::GraphQL;
+use axum::{
+    response::{Html, IntoResponse},
+    routing::get,
+    Router,
+};
+use cooklang::{ingredient_list::IngredientList, Modifiers, ScaledRecipe};
+use cooklang_fs::RecipeEntry;
+use tokio::task::block_in_place;
+
+use super::{async_index::RecipeData, handlers::clean_path, S};
+use crate::{
+    collection_file::inherit,
+    prep::Preparations,
+    util::{map_recipe, meta_name},
+};
+
+pub const GRAPHQL_PATH: &str = "/api/graphql";
+
+/// Max number of recipes in a page
+const MAX_PAGE: usize = 500;
+/// Max nesting of a query, enough for the introspection of the playground
+const MAX_DEPTH: usize = 16;
+/// Max number of fields in a query
+const MAX_COMPLEXITY: usize = 500;
+
+pub fn routes(state: S) -> Router<S> {
+    let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish();
+    Router::new().route(
+        GRAPHQL_PATH,
+        get(graphiql).post_service(GraphQL::new(schema)),
+    )
+}
+
+/// Playground to try queries from the browser
+async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint(GRAPHQL_PATH).finish())
+}
+
+pub struct QueryRoot;
+
+#[derive(InputObject, Default)]
+pub struct RecipeFilter {
+    /// Part of the name, case insensitive
+    name: Option<String>,
+    /// Recipes with all these tags
+    #[graphql(default)]
+    tags: Vec<String>,
+    /// Recipes with all these ingredients, part of the name is enough
+    #[graphql(default)]
+    ingredients: Vec<String>,
+    /// Recipes with all this cookware
+    #[graphql(default)]
+    cookware: Vec<String>,
+    /// Max total time in minutes
+    max_time: Option<u32>,
+}
+
+impl RecipeFilter {
+    fn matches(&self, entry: &RecipeEntry, data: &RecipeData, state: &S) -> bool {
+        let metadata = data.metadata.as_ref();
+        let contains = |list: &[String], part: &str| {
+            let part = part.to_lowercase();
+            list.iter().any(|i| i.to_lowercase().contains(&part))
+        };
+        if let Some(name) = &self.name {
+            let recipe_name = metadata.and_then(meta_name).unwrap_or(entry.name());
+            if !recipe_name.to_lowercase().contains(&name.to_lowercase()) {
+                return false;
+            }
+        }
+        let tags = metadata.and_then(|m| m.tags()).unwrap_or_default();
+        self.tags.iter().all(|t| tags.iter().any(|tag| tag == t))
+            && self
+                .ingredients
+                .iter()
+                .all(|i| contains(&data.ingredients, i))
+            && self.cookware.iter().all(|c| contains(&data.cookware, c))
+            && self.max_time.is_none_or(|max| {
+                metadata
+                    .and_then(|m| m.time(state.parser.converter()))
+                    .is_some_and(|t| t.total() <= max)
+            })
+    }
+}
+
+#[derive(InputObject)]
+pub struct ShoppingListRecipe {
+    /// Path of the recipe in the collection, without extension
+    recipe: String,
+    /// Servings to scale the recipe to
+    servings: Option<u32>,
+}
+
+#[derive(SimpleObject)]
+pub struct Count {
+    name: String,
+    recipes: usize,
+}
+
+#[derive(SimpleObject)]
+pub struct ListItem {
+    name: String,
+    quantities: Vec<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct Ingredient {
+    name: String,
+    quantity: Option<String>,
+    note: Option<String>,
+    optional: bool,
+}
+
+#[Object]
+impl QueryRoot {
+    /// Recipes, optionally filtered, sorted by path
+    async fn recipes(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        #[graphql(default)] filter: RecipeFilter,
+        #[graphql(default)] offset: usize,
+        #[graphql(default = 100)] limit: usize,
+    ) -> Vec<Recipe> {
+        let state = ctx.data_unchecked::<S>();
+        state
+            .recipe_index
+            .search(
+                |entry, data| data.is_some_and(|d| filter.matches(entry, d, state)),
+                |entry, data| Recipe::new(state, entry, data),
+                offset,
+                limit.min(MAX_PAGE),
+            )
+            .await
+    }
+
+    /// A recipe by its path, without extension
+    async fn recipe(&self, ctx: &async_graphql::Context<'_>, path: String) -> Option<Recipe> {
+        let state = ctx.data_unchecked::<S>();
+        let entry = state.recipe_index.get(&path).await.ok()?;
+        let mut found = state
+            .recipe_index
+            .search(
+                |e, _| e.path() == entry.path(),
+                |entry, data| Recipe::new(state, entry, data),
+                0,
+                1,
+            )
+            .await;
+        found.pop()
+    }
+
+    /// All the tags and how many recipes have them
+    async fn tags(&self, ctx: &async_graphql::Context<'_>) -> Vec<Count> {
+        let lists = all_data(ctx, |d| {
+            d.metadata
+                .as_ref()
+                .and_then(|m| m.tags())
+                .unwrap_or_default()
+                .iter()
+                .map(|t| t.to_string())
+                .collect()
+        })
+        .await;
+        count(lists)
+    }
+
+    /// All the ingredients and how many recipes use them
+    async fn ingredients(&self, ctx: &async_graphql::Context<'_>) -> Vec<Count> {
+        count(all_data(ctx, |d| d.ingredients.clone()).await)
+    }
+
+    /// Merged ingredients of some recipes
+    async fn shopping_list(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        recipes: Vec<ShoppingListRecipe>,
+    ) -> Result<Vec<ListItem>> {
+        let state = ctx.data_unchecked::<S>();
+        let converter = state.parser.converter();
+        let preps = Preparations::new(&state.config.prep);
+        let mut list = IngredientList::new();
+        for item in recipes {
+            let entry = state
+                .recipe_index
+                .get(&item.recipe)
+                .await
+                .map_err(|e| format!("{}: {e}", item.recipe))?;
+            let recipe = parse(state, &entry, item.servings)?;
+            preps.add_recipe(&mut list, &recipe, converter);
+        }
+        Ok(list
+            .iter()
+            .map(|(name, quantity)| ListItem {
+                name: name.to_string(),
+                quantities: quantity.iter().map(ToString::to_string).collect(),
+            })
+            .collect())
+    }
+}
+
+async fn all_data(
+    ctx: &async_graphql::Context<'_>,
+    f: impl Fn(&RecipeData) -> Vec<String>,
+) -> Vec<Vec<String>> {
+    let state = ctx.data_unchecked::<S>();
+    state
+        .recipe_index
+        .search(
+            |_, data| data.is_some(),
+            |_, data| data.map(&f).unwrap_or_default(),
+            0,
+            usize::MAX,
+        )
+        .await
+}
+
+fn count(lists: Vec<Vec<String>>) -> Vec<Count> {
+    let mut counts = BTreeMap::<String, usize>::new();
+    for mut list in lists {
+        list.sort();
+        list.dedup();
+        for name in list {
+            *counts.entry(name).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .map(|(name, recipes)| Count { name, recipes })
+        .collect()
+}
+
+fn parse(state: &S, entry: &RecipeEntry, servings: Option<u32>) -> Result<ScaledRecipe> {
+    block_in_place(|| {
+        let content = entry.read()?.into_text();
+        let (recipe, _) = state
+            .parser
+            .parse_with_options(&content, state.parse_options(Some(entry.path())))
+            .map(|r| inherit(r, entry.path(), &state.base_path))
+            .map(map_recipe)
+            .into_result()
+            .map_err(|_| format!("{} has errors", entry.name()))?;
+        Ok(match servings {
+            Some(servings) => recipe.scale(servings, state.parser.converter()),
+            None => recipe.default_scale(),
+        })
+    })
+}
+
+pub struct Recipe {
+    entry: RecipeEntry,
+    path: String,
+    name: String,
+    description: Option<String>,
+    tags: Vec<String>,
+    servings: Vec<u32>,
+    total_time: Option<u32>,
+    ingredient_names: Vec<String>,
+    cookware: Vec<String>,
+}
+
+impl Recipe {
+    fn new(state: &S, entry: RecipeEntry, data: Option<&RecipeData>) -> Self {
+        let metadata = data.and_then(|d| d.metadata.as_ref());
+        Self {
+            path: clean_path(entry.path(), &state.base_path)
+                .with_extension("")
+                .to_string(),
+            name: metadata
+                .and_then(meta_name)
+                .unwrap_or(entry.name())
+                .to_string(),
+            description: metadata.and_then(|m| m.description()).map(String::from),
+            tags: metadata
+                .and_then(|m| m.tags())
+                .unwrap_or_default()
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            servings: metadata.and_then(|m| m.servings()).unwrap_or_default(),
+            total_time: metadata
+                .and_then(|m| m.time(state.parser.converter()))
+                .map(|t| t.total()),
+            ingredient_names: data.map(|d| d.ingredients.clone()).unwrap_or_default(),
+            cookware: data.map(|d| d.cookware.clone()).unwrap_or_default(),
+            entry,
+        }
+    }
+}
+
+#[Object]
+impl Recipe {
+    /// Path in the collection without extension, the web UI page is `/r/<path>`
+    async fn path(&self) -> &str {
+        &self.path
+    }
+
+    async fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    async fn servings(&self) -> &[u32] {
+        &self.servings
+    }
+
+    /// Total time in minutes
+    async fn total_time(&self) -> Option<u32> {
+        self.total_time
+    }
+
+    /// Names of the ingredients, without parsing the recipe again
+    async fn ingredient_names(&self) -> &[String] {
+        &self.ingredient_names
+    }
+
+    async fn cookware(&self) -> &[String] {
+        &self.cookware
+    }
+
+    /// Ingredients with their quantities, scaled to `servings` if given
+    async fn ingredients(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        servings: Option<u32>,
+    ) -> Result<Vec<Ingredient>> {
+        let state = ctx.data_unchecked::<S>();
+        let recipe = parse(state, &self.entry, servings)?;
+        Ok(recipe
+            .group_ingredients(state.parser.converter())
+            .into_iter()
+            .filter(|e| e.ingredient.modifiers().should_be_listed())
+            .map(|e| {
+                let quantities = e
+                    .quantity
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                Ingredient {
+                    name: e.ingredient.display_name().to_string(),
+                    quantity: (!quantities.is_empty()).then(|| quantities.join(", ")),
+                    note: e.ingredient.note.clone(),
+                    optional: e.ingredient.modifiers().contains(Modifiers::OPT),
+                }
+            })
+            .collect())
+    }
+
+    /// Cooklang source of the recipe
+    async fn source(&self) -> Result<String> {
+        Ok(block_in_place(|| self.entry.read())?.into_text())
+    }
+}
@@ -24,22 +24,20 @@ pub async fn convert_popover(
         None => return StatusCode::BAD_REQUEST.into_response(),
     };
 
-    if quantity.unit().is_none() {
-        return StatusCode::BAD_REQUEST.into_response();
-    }
-    let unit = match quantity.unit_info(converter) {
-        Some(unit) => unit,
-        None => return StatusCode::BAD_REQUEST.into_response(),
+    // without a known unit there is nothing to convert to, but the popover
+    // still lets the user override the quantity
+    let conversions: Vec<_> = match quantity.unit_info(converter) {
+        Some(unit) => converter
+            .best_units(unit.physical_quantity, None)
+            .into_iter()
+            .filter_map(|target| {
+                let mut q = quantity.clone();
+                q.convert(&target, converter).ok()?;
+                Some(q)
+            })
+            .collect(),
+        None => Vec::new(),
     };
-    let conversions: Vec<_> = converter
-        .best_units(unit.physical_quantity, None)
-        .into_iter()
-        .filter_map(|target| {
-            let mut q = quantity.clone();
-            q.convert(&target, converter).ok()?;
-            Some(q)
-        })
-        .collect();
 
     let tmpl = mj_ok!(state
         .templates
@@ -47,3 +45,31 @@ pub async fn convert_popover(
     let html = mj_ok!(tmpl.render(context! { t, conversions, triggered_by }));
     Html(html).into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    /// The anchor form of the popover has the inputs `recipe.js` reads
+    #[test]
+    fn anchor_form_inputs() {
+        let template = include_str!("../../../../ui/templates/components/convert_popover.html");
+        let script = include_str!("../../../../ui/assets/js/recipe.js");
+        let mut read = script
+            .split("form.elements.")
+            .skip(1)
+            .map(|s| {
+                s.split(|c: char| !c.is_ascii_alphanumeric())
+                    .next()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        read.sort_unstable();
+        read.dedup();
+        assert_eq!(read, ["have", "rest"]);
+        for name in read {
+            assert!(
+                template.contains(&format!(r#"name="{name}""#)),
+                "no input named '{name}' in the popover"
+            );
+        }
+    }
+}
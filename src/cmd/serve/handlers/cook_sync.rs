@@ -0,0 +1,107 @@
+//! Shared cooking sessions, see [`crate::cmd::serve::cook_sync`]
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::cmd::serve::{cook_sync::CookState, S};
+
+use super::check_path;
+
+/// Room of a recipe, its path without extension if the recipe exists
+fn room(state: &S, path: &str) -> Result<String, StatusCode> {
+    check_path(path)?;
+    let path = path.trim_matches('/');
+    let file = state.base_path.join(format!("{path}.cook"));
+    if !file.is_file() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(path.to_string())
+}
+
+pub async fn cook_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<S>,
+    Path(path): Path<String>,
+) -> Response {
+    let room = match room(&state, &path) {
+        Ok(room) => room,
+        Err(status) => return status.into_response(),
+    };
+    ws.on_upgrade(move |socket| session(socket, state, room))
+}
+
+async fn session(mut socket: WebSocket, state: S, room: String) {
+    let (current, mut updates) = state.cook_rooms.join(&room);
+    if let Some(current) = current {
+        if send(&mut socket, &current).await.is_err() {
+            return;
+        }
+    }
+    loop {
+        tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<CookState>(text.as_str()) {
+                    Ok(s) => state.cook_rooms.publish(&room, s),
+                    Err(e) => tracing::debug!("Bad cooking session message: {e}"),
+                },
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => break,
+            },
+            update = updates.recv() => match update {
+                Ok(s) => {
+                    if send(&mut socket, &s).await.is_err() {
+                        break;
+                    }
+                }
+                // only the last state matters
+                Err(RecvError::Lagged(_)) => {
+                    if let Some(s) = state.cook_rooms.state(&room) {
+                        if send(&mut socket, &s).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, state: &CookState) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(state).expect("cook state serialization");
+    socket.send(Message::Text(text.into())).await
+}
+
+/// The current state of the cooking session of a recipe
+pub async fn cook_state(State(state): State<S>, Path(path): Path<String>) -> Response {
+    let room = match room(&state, &path) {
+        Ok(room) => room,
+        Err(status) => return status.into_response(),
+    };
+    match state.cook_rooms.state(&room) {
+        Some(s) => Json(s.as_ref().clone()).into_response(),
+        None => Json(CookState::default()).into_response(),
+    }
+}
+
+/// Changes the cooking session of a recipe, for the devices without a socket
+pub async fn set_cook_state(
+    State(state): State<S>,
+    Path(path): Path<String>,
+    Json(new): Json<CookState>,
+) -> Response {
+    let room = match room(&state, &path) {
+        Ok(room) => room,
+        Err(status) => return status.into_response(),
+    };
+    state.cook_rooms.publish(&room, new);
+    StatusCode::NO_CONTENT.into_response()
+}
@@ -0,0 +1,128 @@
+use std::io::Cursor;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tokio::task::block_in_place;
+
+use crate::{
+    cmd::serve::{
+        openapi::{ApiParams, Param},
+        S,
+    },
+    collection_file::inherit,
+    locale::number_format,
+    recipe_zip::{self, ZipRecipe},
+    util::{map_recipe, meta_name},
+};
+
+use super::{ok_status, search::SearchQuery, Searcher};
+
+/// The archive is built in memory, so the files of the recipes can't be
+/// bigger than this
+const MAX_ZIP_SIZE: u64 = 256 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct DownloadQuery {
+    q: Option<String>,
+    /// Add an HTML page of every recipe
+    #[serde(default)]
+    html: bool,
+}
+
+impl ApiParams for DownloadQuery {
+    const PARAMS: &'static [Param] = &[
+        Param {
+            name: "q",
+            ty: "string",
+            description: "Search terms like in `/search`, none for all the recipes",
+        },
+        Param {
+            name: "html",
+            ty: "boolean",
+            description: "Add an HTML page of every recipe",
+        },
+    ];
+}
+
+/// The recipes of a search as a ZIP file, with their images
+///
+/// Encrypted and private recipes are left out.
+pub async fn download_zip(State(state): State<S>, Query(query): Query<DownloadQuery>) -> Response {
+    let srch = Searcher::from(SearchQuery {
+        q: query.q.filter(|q| !q.trim().is_empty()),
+    });
+    let entries = state
+        .recipe_index
+        .search(
+            |entry, data| {
+                data.is_some_and(|d| {
+                    let Some(metadata) = &d.metadata else {
+                        return false;
+                    };
+                    let name = meta_name(metadata).unwrap_or(entry.name());
+                    recipe_zip::can_archive(entry, metadata)
+                        && srch.matches_recipe(name, d, &state.diet)
+                })
+            },
+            |entry, _| entry,
+            0,
+            usize::MAX,
+        )
+        .await;
+    if entries.is_empty() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    if block_in_place(|| recipe_zip::files_size(&entries)) > MAX_ZIP_SIZE {
+        tracing::warn!("Denied ZIP of {} recipes: too big", entries.len());
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+
+    let res = block_in_place(|| -> anyhow::Result<Vec<u8>> {
+        let mut recipes = Vec::with_capacity(entries.len());
+        for entry in &entries {
+            let html = if query.html {
+                let content = entry.read()?.into_text();
+                let recipe = state
+                    .parser
+                    .parse_with_options(&content, state.parse_options(Some(entry.path())))
+                    .map(|r| inherit(r, entry.path(), &state.base_path))
+                    .map(map_recipe)
+                    .into_output();
+                // recipes with errors still go in, only without the page
+                recipe
+                    .map(|r| {
+                        let name = meta_name(&r.metadata).unwrap_or(entry.name()).to_string();
+                        let number_format = number_format(&r.metadata, &state.config);
+                        recipe_zip::render_html(
+                            &r.default_scale(),
+                            &name,
+                            state.parser.converter(),
+                            number_format,
+                        )
+                    })
+                    .transpose()?
+            } else {
+                None
+            };
+            recipes.push(ZipRecipe { entry, html });
+        }
+        let (buf, _) = recipe_zip::write(Cursor::new(Vec::new()), &state.base_path, &recipes)?;
+        Ok(buf.into_inner())
+    });
+    let content = ok_status!(res);
+    (
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"recipes.zip\"",
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
@@ -0,0 +1,142 @@
+use std::{fmt::Write, time::SystemTime};
+
+use axum::{
+    extract::State,
+    http::{header::CONTENT_TYPE, HeaderMap},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    cmd::serve::S,
+    encryption::is_marked_private,
+    pantry::rfc3339,
+    util::{encode_path, escape_xml, meta_name},
+};
+
+use super::clean_path;
+
+/// Max number of recipes in the feed
+const FEED_SIZE: usize = 50;
+
+struct FeedEntry {
+    name: String,
+    path: String,
+    summary: Option<String>,
+    tags: Vec<String>,
+    published: SystemTime,
+    updated: SystemTime,
+}
+
+/// Atom feed of the recently added or modified recipes
+///
+/// Private and encrypted recipes are left out, feed readers may keep them.
+pub async fn feed(State(state): State<S>, headers: HeaderMap) -> Response {
+    let recipes = state
+        .recipe_index
+        .search(
+            |entry, data| {
+                !entry.is_encrypted()
+                    && !data
+                        .and_then(|d| d.metadata.as_ref())
+                        .is_some_and(is_marked_private)
+            },
+            |entry, data| {
+                let metadata = data.and_then(|d| d.metadata.as_ref());
+                let name = metadata
+                    .and_then(meta_name)
+                    .unwrap_or(entry.name())
+                    .to_string();
+                let summary = metadata.and_then(|m| m.description()).map(String::from);
+                let tags = metadata
+                    .and_then(|m| m.tags())
+                    .unwrap_or(vec![])
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect();
+                (entry, name, summary, tags)
+            },
+            0,
+            usize::MAX,
+        )
+        .await;
+
+    let mut entries: Vec<FeedEntry> = tokio::task::block_in_place(|| {
+        recipes
+            .into_iter()
+            .filter_map(|(entry, name, summary, tags)| {
+                let metadata = entry.path().metadata().ok()?;
+                let updated = metadata.modified().ok()?;
+                Some(FeedEntry {
+                    name,
+                    path: encode_path(
+                        clean_path(entry.path(), &state.base_path)
+                            .with_extension("")
+                            .as_str(),
+                    ),
+                    summary,
+                    tags,
+                    published: metadata.created().unwrap_or(updated),
+                    updated,
+                })
+            })
+            .collect()
+    });
+    entries.sort_by_key(|e| std::cmp::Reverse(e.updated));
+    entries.truncate(FEED_SIZE);
+
+    let host = headers
+        .get("host")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("localhost");
+    let scheme = headers
+        .get("x-forwarded-proto")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("http");
+    let base_url = format!("{scheme}://{host}");
+    let title = state.base_path.file_name().unwrap_or("chef");
+
+    let body = atom(&base_url, title, &entries);
+    (
+        [(CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        body,
+    )
+        .into_response()
+}
+
+fn atom(base_url: &str, title: &str, entries: &[FeedEntry]) -> String {
+    let updated = entries
+        .first()
+        .map_or(SystemTime::UNIX_EPOCH, |e| e.updated);
+
+    let mut xml = String::new();
+    xml += r#"<?xml version="1.0" encoding="utf-8"?>"#;
+    xml += "\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n";
+    let _ = writeln!(xml, "  <title>{}</title>", escape_xml(title));
+    let _ = writeln!(xml, "  <id>{base_url}/</id>");
+    let _ = writeln!(xml, r#"  <link href="{base_url}/"/>"#);
+    let _ = writeln!(xml, r#"  <link rel="self" href="{base_url}/feed.xml"/>"#);
+    let _ = writeln!(xml, "  <updated>{}</updated>", rfc3339(updated));
+    let _ = writeln!(xml, "  <author><name>chef</name></author>");
+    for entry in entries {
+        let link = escape_xml(&format!("{base_url}/r/{}", entry.path));
+        xml += "  <entry>\n";
+        let _ = writeln!(xml, "    <title>{}</title>", escape_xml(&entry.name));
+        let _ = writeln!(xml, "    <id>{link}</id>");
+        let _ = writeln!(xml, r#"    <link href="{link}"/>"#);
+        let _ = writeln!(
+            xml,
+            "    <published>{}</published>",
+            rfc3339(entry.published)
+        );
+        let _ = writeln!(xml, "    <updated>{}</updated>", rfc3339(entry.updated));
+        if let Some(summary) = &entry.summary {
+            let _ = writeln!(xml, "    <summary>{}</summary>", escape_xml(summary));
+        }
+        for tag in &entry.tags {
+            let _ = writeln!(xml, r#"    <category term="{}"/>"#, escape_xml(tag));
+        }
+        xml += "  </entry>\n";
+    }
+    xml += "</feed>\n";
+    xml
+}
@@ -0,0 +1,41 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tokio::task::block_in_place;
+
+use crate::{
+    cmd::serve::{handlers::ok_status, S},
+    collection_file::inherit,
+    graph,
+    util::map_recipe,
+};
+
+use super::check_path;
+
+/// Dependency graph of a recipe as JSON, for the UI to draw
+pub async fn graph(State(state): State<S>, Path(path): Path<String>) -> Response {
+    if let Err(e) = check_path(&path) {
+        return e.into_response();
+    }
+
+    let entry = ok_status!(state.recipe_index.get(&path).await, NOT_FOUND);
+    let content = ok_status!(block_in_place(|| entry.read()), NOT_FOUND).into_text();
+
+    let recipe = ok_status!(
+        block_in_place(|| {
+            state
+                .parser
+                .parse_with_options(&content, state.parse_options(Some(entry.path())))
+                .map(|r| inherit(r, entry.path(), &state.base_path))
+                .map(map_recipe)
+                .into_result()
+        }),
+        UNPROCESSABLE_ENTITY
+    )
+    .0
+    .default_scale();
+
+    Json(graph::build(&recipe, state.parser.converter())).into_response()
+}
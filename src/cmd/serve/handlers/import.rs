@@ -0,0 +1,277 @@
+//! Import page, to add recipes to the collection from the browser
+//!
+//! It writes files, so like opening the editor it only answers requests from
+//! this computer, and only the ones sent by the page itself.
+
+use std::{collections::BTreeSet, net::SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    Form,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use cooklang_to_human::HumanFormatter;
+use minijinja::{context, Value};
+use serde::Deserialize;
+use tokio::task::block_in_place;
+
+use crate::{
+    cmd::serve::{locale::UserLocale, S},
+    import,
+    remote::is_safe_path,
+    util::{encode_path, map_recipe, meta_name},
+};
+
+use super::{
+    check_same_origin, mj_ok, ok_status, recipe::report_to_html, recipe_folders, text::TEXT_WIDTH,
+};
+
+/// File name used in the parse reports
+const REPORT_FILE_NAME: &str = "import.cook";
+
+#[derive(Deserialize)]
+pub struct ImportForm {
+    /// Cooklang, JSON-LD or a share link
+    #[serde(default)]
+    text: String,
+    /// Where to download the recipe from, instead of `text`
+    #[serde(default)]
+    url: String,
+    /// File name without extension
+    #[serde(default)]
+    name: String,
+    /// Folder in the collection, empty for the top one
+    #[serde(default)]
+    folder: String,
+}
+
+fn check_allowed(state: &S, who: SocketAddr) -> Result<(), StatusCode> {
+    if state.disable_import {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if !who.ip().is_loopback() {
+        tracing::warn!("Denied import request from '{who}': Not loopback ip");
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
+
+fn render(state: &S, status: StatusCode, ctx: Value) -> Response {
+    let tmpl = mj_ok!(state.templates.get_template("import.html"));
+    let content = mj_ok!(tmpl.render(ctx));
+    (status, Html(content)).into_response()
+}
+
+pub async fn import_page(
+    UserLocale(t): UserLocale,
+    State(state): State<S>,
+    ConnectInfo(who): ConnectInfo<SocketAddr>,
+) -> Response {
+    if let Err(status) = check_allowed(&state, who) {
+        let error_key = (status == StatusCode::FORBIDDEN).then_some("import.notAllowed");
+        return render(&state, status, context! { t, error_key });
+    }
+    render(&state, StatusCode::OK, context! { t })
+}
+
+/// Converts the recipe and shows it with the parse warnings
+pub async fn import_preview(
+    UserLocale(t): UserLocale,
+    State(state): State<S>,
+    ConnectInfo(who): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<ImportForm>,
+) -> Response {
+    if let Err(status) = check_allowed(&state, who).and(check_same_origin(&headers)) {
+        return status.into_response();
+    }
+    let url = form.url.trim();
+    let res = block_in_place(|| {
+        if url.is_empty() {
+            import::from_text(&form.text)
+        } else {
+            import::from_url(url)
+        }
+    });
+    match res {
+        Ok(imported) => {
+            let name = imported.name.unwrap_or_default();
+            preview(&state, t, imported.text, name, form.folder, None).await
+        }
+        Err(e) => render(
+            &state,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            context! { t, error => format!("{e:#}"), text => form.text, url => form.url },
+        ),
+    }
+}
+
+async fn preview(
+    state: &S,
+    t: Value,
+    text: String,
+    name: String,
+    folder: String,
+    error_key: Option<&str>,
+) -> Response {
+    // where the recipe would be, to check the references to other recipes
+    let path = state.base_path.join(
+        recipe_path(folder.trim().trim_matches('/'), "import")
+            .unwrap_or_else(|| REPORT_FILE_NAME.into()),
+    );
+    let res = block_in_place(|| {
+        state
+            .parser
+            .parse_with_options(&text, state.parse_options(Some(&path)))
+            .map(map_recipe)
+            .into_result()
+    });
+    let (rendered, report_html, severity, name) = match res {
+        Ok((recipe, warnings)) => {
+            let recipe = recipe.default_scale();
+            let name = if name.is_empty() {
+                meta_name(&recipe.metadata).unwrap_or_default().to_string()
+            } else {
+                name
+            };
+            let mut buf = Vec::new();
+            ok_status!(HumanFormatter::builder()
+                .color(false)
+                .width(TEXT_WIDTH)
+                .build()
+                .format(&recipe, &name, state.parser.converter(), &mut buf));
+            let report_html = if warnings.is_empty() {
+                None
+            } else {
+                Some(ok_status!(report_to_html(
+                    &warnings,
+                    REPORT_FILE_NAME,
+                    &text
+                )))
+            };
+            (
+                Some(String::from_utf8_lossy(&buf).into_owned()),
+                report_html,
+                "warning",
+                name,
+            )
+        }
+        Err(report) => (
+            None,
+            Some(ok_status!(report_to_html(&report, REPORT_FILE_NAME, &text))),
+            "error",
+            name,
+        ),
+    };
+
+    let folders = recipe_folders(state)
+        .await
+        .into_iter()
+        .filter(|f| !f.is_empty())
+        .collect::<BTreeSet<_>>();
+
+    let status = if error_key.is_some() {
+        StatusCode::CONFLICT
+    } else {
+        StatusCode::OK
+    };
+    render(
+        state,
+        status,
+        context! {
+            t,
+            preview => true,
+            text,
+            name,
+            folder,
+            folders,
+            rendered,
+            report_html,
+            severity,
+            error_key,
+        },
+    )
+}
+
+/// Saves the previewed recipe and opens it
+pub async fn import_save(
+    UserLocale(t): UserLocale,
+    State(state): State<S>,
+    ConnectInfo(who): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<ImportForm>,
+) -> Response {
+    if let Err(status) = check_allowed(&state, who).and(check_same_origin(&headers)) {
+        return status.into_response();
+    }
+    let name = form.name.trim();
+    let folder = form.folder.trim().trim_matches('/');
+    let Some(relative) = recipe_path(folder, name) else {
+        return preview(
+            &state,
+            t,
+            form.text,
+            form.name,
+            form.folder,
+            Some("import.invalidName"),
+        )
+        .await;
+    };
+    let path = state.base_path.join(&relative);
+    if path.exists() {
+        return preview(
+            &state,
+            t,
+            form.text,
+            form.name,
+            form.folder,
+            Some("import.exists"),
+        )
+        .await;
+    }
+
+    ok_status!(block_in_place(|| {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, &form.text)
+    }));
+    tracing::info!("Imported '{path}'");
+    Redirect::to(&format!(
+        "/r/{}",
+        encode_path(relative.with_extension("").as_str())
+    ))
+    .into_response()
+}
+
+/// Path of the new recipe in the collection
+fn recipe_path(folder: &str, name: &str) -> Option<Utf8PathBuf> {
+    let valid_name = !name.is_empty() && !name.contains(['/', '\\']) && !name.starts_with('.');
+    let valid_folder = folder.is_empty()
+        || (is_safe_path(folder) && !folder.split('/').any(|c| c.starts_with('.')));
+    if !valid_name || !valid_folder {
+        return None;
+    }
+    Some(Utf8Path::new(folder).join(format!("{name}.cook")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        assert_eq!(recipe_path("", "Flan").unwrap(), "Flan.cook");
+        assert_eq!(
+            recipe_path("Desserts/Eggs", "Flan").unwrap(),
+            "Desserts/Eggs/Flan.cook"
+        );
+        assert!(recipe_path("", "").is_none());
+        assert!(recipe_path("", "../Flan").is_none());
+        assert!(recipe_path("../Desserts", "Flan").is_none());
+        assert!(recipe_path("", ".hidden").is_none());
+        assert!(recipe_path(".cooklang", "Flan").is_none());
+    }
+}
@@ -1,22 +1,121 @@
-use std::io;
+use std::{collections::BTreeMap, io};
 
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::{Html, IntoResponse, Response},
 };
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use minijinja::{context, Value};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use tokio::task::block_in_place;
 
-use crate::cmd::serve::{locale::UserLocale, S};
+use crate::{
+    cmd::serve::{
+        locale::UserLocale,
+        openapi::{ApiParams, Param},
+        S,
+    },
+    collection_file::CollectionFile,
+    cook_log::{recipe_key, sort_by_dates, CookLog, RecipeDates, RecipeOrder},
+    course::{course_key, course_label, recipe_course},
+    pantry::Date,
+    seasonal::{anniversary, recipe_months},
+    util::meta_name,
+};
 
 use super::super::async_index::RecipeData;
-use super::{check_path, clean_path, mj_ok, recipe_entry_context};
+use super::{check_path, clean_path, mj_ok, recipe_entry_context, recipe_folders};
+
+/// Recipes in a page of the index, the next ones load when scrolling
+const PAGE_SIZE: usize = 48;
+
+/// Seasonal suggestions at the top of the index
+const SEASONAL_SIZE: usize = 8;
 
 #[derive(Deserialize)]
 pub struct IndexQuery {
     deleted: Option<String>,
+    /// Path of the last recipe of the previous page
+    after: Option<String>,
+    tag: Option<String>,
+    max_time: Option<u32>,
+    #[serde(default)]
+    sort: RecipeOrder,
+    #[serde(default)]
+    never_cooked: bool,
+}
+
+impl ApiParams for IndexQuery {
+    const PARAMS: &'static [Param] = &[
+        Param {
+            name: "deleted",
+            ty: "string",
+            description: "Name of a recipe that was just deleted, to show a notice",
+        },
+        Param {
+            name: "after",
+            ty: "string",
+            description: "Path of the last recipe of the previous page",
+        },
+        Param {
+            name: "tag",
+            ty: "string",
+            description: "Only recipes with this tag",
+        },
+        Param {
+            name: "max_time",
+            ty: "integer",
+            description: "Only recipes that take at most these minutes",
+        },
+        Param {
+            name: "sort",
+            ty: "string",
+            description: "`name` (default), `newest`, `last_cooked` or `course`",
+        },
+        Param {
+            name: "never_cooked",
+            ty: "boolean",
+            description: "Only recipes that are not in the cook log",
+        },
+    ];
+}
+
+impl IndexQuery {
+    fn matches(&self, data: Option<&RecipeData>, state: &S) -> bool {
+        let metadata = data.and_then(|d| d.metadata.as_ref());
+        if let Some(tag) = &self.tag {
+            let tags = metadata.and_then(|m| m.tags()).unwrap_or_default();
+            if !tags.iter().any(|t| t == tag) {
+                return false;
+            }
+        }
+        if let Some(max) = self.max_time {
+            let time = metadata.and_then(|m| m.time(state.parser.converter()));
+            if time.is_none_or(|t| t.total() > max) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Query with the same filters and another order
+    fn with_order(&self, sort: RecipeOrder, never_cooked: bool) -> BTreeMap<&'static str, String> {
+        let mut query = BTreeMap::new();
+        if let Some(tag) = &self.tag {
+            query.insert("tag", tag.clone());
+        }
+        if let Some(max) = self.max_time {
+            query.insert("max_time", max.to_string());
+        }
+        if sort != RecipeOrder::Name {
+            query.insert("sort", sort.as_str().to_string());
+        }
+        if never_cooked {
+            query.insert("never_cooked", "true".to_string());
+        }
+        query
+    }
 }
 
 pub async fn index(
@@ -35,7 +134,8 @@ pub async fn index(
         }
     }
 
-    let entries = match cooklang_fs::walk_dir(&path) {
+    let rules = &state.config.discovery;
+    let entries = match cooklang_fs::walk_dir_with_rules(&path, &state.base_path, rules) {
         Ok(entries) => entries,
         Err(err) => {
             let status = if err.kind() == io::ErrorKind::NotFound {
@@ -48,53 +148,381 @@ pub async fn index(
     };
 
     let mut folders = Vec::new();
-    let mut recipes = Vec::new();
-    for e in entries {
-        match e {
-            cooklang_fs::Entry::Dir(dir) => folders.push(context! {
-                name => dir.file_name(),
-                path => clean_path(dir.path(), &state.base_path)
-            }),
-            cooklang_fs::Entry::Recipe(r) => {
-                let tokens = r.read().ok().map(|c| {
-                    let recipe = c.parse(&state.parser);
-                    let mut ingredients = Vec::new();
-                    let mut cookware = Vec::new();
-                    let mut metadata = None;
-                    if let Some(r) = recipe.valid_output() {
-                        metadata = Some(r.metadata.to_owned());
-                        for ingredient in &r.ingredients {
-                            ingredients.push(ingredient.name.to_owned());
-                        }
-                        for tool in &r.cookware {
-                            cookware.push(tool.name.to_string());
-                        }
-                    }
-                    RecipeData {
-                        metadata,
-                        ingredients,
-                        cookware,
-                    }
+    let mut entries = entries
+        .into_iter()
+        .filter_map(|e| match e {
+            cooklang_fs::Entry::Dir(dir) => {
+                folders.push(context! {
+                    name => dir.file_name(),
+                    path => clean_path(dir.path(), &state.base_path)
                 });
-                recipes.push(recipe_entry_context(r, &state, tokens.as_ref()).unwrap());
+                None
             }
+            cooklang_fs::Entry::Recipe(r) => {
+                let key = clean_path(r.path(), &state.base_path)
+                    .with_extension("")
+                    .into_string();
+                Some((key, r))
+            }
+        })
+        .collect::<Vec<_>>();
+    // sorted so the cursor is stable
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let log = match block_in_place(|| CookLog::load(&state.base_path)) {
+        Ok(log) => log,
+        Err(e) => {
+            tracing::error!("{e:#}");
+            CookLog::default()
         }
+    };
+    if q.never_cooked {
+        entries.retain(|(key, _)| log.last_cooked(key).is_none());
     }
+    block_in_place(|| {
+        sort_by_dates(&mut entries, q.sort, |(_, r)| {
+            RecipeDates::new(&log, r.path(), &state.base_path)
+        })
+    });
+    // the course of the last recipe shown, to know when a new one starts
+    let mut previous_course = None;
+    if q.sort == RecipeOrder::Course {
+        let courses = state
+            .recipe_index
+            .filter_map(
+                entries.iter().map(|(_, r)| r.clone()),
+                |_, data| {
+                    Some(
+                        data.and_then(|d| d.metadata.as_ref())
+                            .and_then(recipe_course),
+                    )
+                },
+                entries.len(),
+            )
+            .await;
+        let mut sorted = entries.into_iter().zip(courses).collect::<Vec<_>>();
+        sorted.sort_by_cached_key(|(_, course)| course_key(course.as_deref()));
+        if let Some(after) = &q.after {
+            previous_course = sorted
+                .iter()
+                .find(|((key, _), _)| key == after)
+                .map(|(_, course)| course.clone());
+        }
+        entries = sorted.into_iter().map(|(entry, _)| entry).collect();
+    }
+    if let Some(after) = &q.after {
+        if q.sort == RecipeOrder::Name {
+            entries.retain(|(key, _)| key > after);
+        } else if let Some(pos) = entries.iter().position(|(key, _)| key == after) {
+            entries.drain(..=pos);
+        }
+        // only the first page has the folders
+        folders.clear();
+    }
+
+    // one more to know if there is a next page
+    let mut recipes = state
+        .recipe_index
+        .filter_map(
+            entries.into_iter().map(|(_, r)| r),
+            |r, data| {
+                if !q.matches(data, &state) {
+                    return None;
+                }
+                let key = clean_path(r.path(), &state.base_path)
+                    .with_extension("")
+                    .into_string();
+                let dates = RecipeDates::new(&log, r.path(), &state.base_path);
+                let course = data
+                    .and_then(|d| d.metadata.as_ref())
+                    .and_then(recipe_course);
+                let c = recipe_entry_context(r, &state, data)?;
+                let course_heading =
+                    q.sort == RecipeOrder::Course && previous_course.as_ref() != Some(&course);
+                previous_course = Some(course.clone());
+                let c = context! {
+                    added => dates.added.map(|d| d.to_string()),
+                    last_cooked => dates.last_cooked.map(|d| d.to_string()),
+                    course => course.as_deref().map(course_label),
+                    course_heading,
+                    ..c
+                };
+                Some((key, c))
+            },
+            PAGE_SIZE + 1,
+        )
+        .await;
+    let next_page = if recipes.len() > PAGE_SIZE {
+        recipes.truncate(PAGE_SIZE);
+        recipes.last().map(|(key, _)| {
+            let mut query = q.with_order(q.sort, q.never_cooked);
+            query.insert("after", key.clone());
+            query
+        })
+    } else {
+        None
+    };
+    let recipes = recipes.into_iter().map(|(_, c)| c).collect::<Vec<_>>();
+
+    let relative = path.strip_prefix(&state.base_path).unwrap();
+    // the folder info is in the first page only
+    let (tree, readme, folder_metadata) = if q.after.is_none() {
+        (
+            folder_tree(&recipe_folders(&state).await),
+            read_readme(&path),
+            folder_metadata(&path),
+        )
+    } else {
+        Default::default()
+    };
+
+    // only in the first page of the top folder without filters
+    let seasonal = if q.after.is_none()
+        && relative.as_str().is_empty()
+        && q.tag.is_none()
+        && q.max_time.is_none()
+        && !q.never_cooked
+    {
+        seasonal(&state, &log).await
+    } else {
+        Vec::new()
+    };
+
+    // the same filters, favoring what was not cooked for long
+    let mut random_query = q.with_order(RecipeOrder::Name, false);
+    random_query.insert("weighted", "true".to_string());
+
+    let sort_links = [
+        (RecipeOrder::Name, "index.sortName"),
+        (RecipeOrder::Newest, "index.sortNewest"),
+        (RecipeOrder::LastCooked, "index.sortLastCooked"),
+        (RecipeOrder::Course, "index.sortCourse"),
+    ]
+    .map(|(order, key)| {
+        context! {
+            key,
+            query => q.with_order(order, q.never_cooked),
+            active => q.sort == order,
+        }
+    });
 
     let tmpl = mj_ok!(state.templates.get_template("index.html"));
-    let path_parts = path
-        .strip_prefix(&state.base_path)
-        .unwrap()
-        .components()
-        .map(|c| c.as_str());
+    let path_parts = relative.components().map(|c| c.as_str());
 
     let res = tmpl.render(context! {
         t,
         recipes,
         folders,
+        next_page,
+        filter_tag => q.tag,
+        filter_time => q.max_time,
+        sort_links,
+        never_cooked => q.never_cooked,
+        never_cooked_query => q.with_order(q.sort, !q.never_cooked),
+        random_query,
         path => Value::from_iter(path_parts),
+        current_folder => relative.as_str().replace('\\', "/"),
+        tree,
+        readme,
+        seasonal,
+        folder_metadata,
         deleted => q.deleted,
     });
     let content = mj_ok!(res);
     Html(content).into_response()
 }
+
+/// Recipes in season this month and cooked around this day in past years
+async fn seasonal(state: &S, log: &CookLog) -> Vec<Value> {
+    let today = Date::today();
+    let (_, month, _) = today.ymd();
+    let cooked_around = |path: &Utf8Path| {
+        let cooked = log.recipes.get(&recipe_key(path, &state.base_path));
+        anniversary(cooked.map_or(&[][..], Vec::as_slice), today)
+    };
+    state
+        .recipe_index
+        .search(
+            |entry, data| {
+                let in_season = data
+                    .and_then(|d| d.metadata.as_ref())
+                    .and_then(|m| recipe_months(m, &state.config.ui.tags))
+                    .is_some_and(|m| m.contains(month));
+                in_season || cooked_around(entry.path()).is_some()
+            },
+            |entry, data| {
+                let name = data
+                    .and_then(|d| d.metadata.as_ref())
+                    .and_then(meta_name)
+                    .unwrap_or(entry.name());
+                let path = clean_path(entry.path(), &state.base_path).with_extension("");
+                context! {
+                    name,
+                    href => format!("/r/{path}"),
+                    cooked => cooked_around(entry.path()).map(|d| d.to_string()),
+                }
+            },
+            0,
+            SEASONAL_SIZE,
+        )
+        .await
+}
+
+/// A folder with recipes, for the folder tree
+#[derive(Serialize)]
+struct FolderNode {
+    name: String,
+    path: String,
+    /// Recipes in the folder and its subfolders
+    recipes: usize,
+    children: Vec<FolderNode>,
+}
+
+/// Builds the tree of folders from the folder of every recipe
+fn folder_tree(folders: &[String]) -> Vec<FolderNode> {
+    #[derive(Default)]
+    struct Node<'a> {
+        recipes: usize,
+        children: BTreeMap<&'a str, Node<'a>>,
+    }
+
+    fn finish(prefix: &str, children: BTreeMap<&str, Node>) -> Vec<FolderNode> {
+        children
+            .into_iter()
+            .map(|(name, node)| {
+                let path = if prefix.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{prefix}/{name}")
+                };
+                FolderNode {
+                    name: name.to_string(),
+                    recipes: node.recipes,
+                    children: finish(&path, node.children),
+                    path,
+                }
+            })
+            .collect()
+    }
+
+    let mut root = Node::default();
+    for folder in folders.iter().filter(|f| !f.is_empty()) {
+        let mut node = &mut root;
+        for part in folder.split('/') {
+            node = node.children.entry(part).or_default();
+            node.recipes += 1;
+        }
+    }
+    finish("", root.children)
+}
+
+/// A heading or paragraph of a folder README
+#[derive(Serialize)]
+struct ReadmeBlock {
+    heading: bool,
+    text: String,
+}
+
+/// Reads the `README.md` of a folder
+///
+/// Only headings and paragraphs are kept, the rest of the markdown is shown
+/// as text.
+fn read_readme(dir: &Utf8Path) -> Vec<ReadmeBlock> {
+    match std::fs::read_to_string(dir.join("README.md")) {
+        Ok(content) => readme_blocks(&content),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn readme_blocks(content: &str) -> Vec<ReadmeBlock> {
+    fn flush(paragraph: &mut Vec<&str>, blocks: &mut Vec<ReadmeBlock>) {
+        if !paragraph.is_empty() {
+            blocks.push(ReadmeBlock {
+                heading: false,
+                text: paragraph.join(" "),
+            });
+            paragraph.clear();
+        }
+    }
+
+    let mut blocks = Vec::new();
+    let mut paragraph = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else if line.starts_with('#') {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(ReadmeBlock {
+                heading: true,
+                text: line.trim_start_matches('#').trim().to_string(),
+            });
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+/// Metadata in the `collection.toml` of a folder, as text
+fn folder_metadata(dir: &Utf8Path) -> Vec<(String, String)> {
+    let file = match CollectionFile::read(dir) {
+        Ok(Some(file)) => file,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            tracing::warn!("{e:#}");
+            return Vec::new();
+        }
+    };
+    file.metadata
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Array(items) => items
+                    .iter()
+                    .map(|i| match i {
+                        toml::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            (key, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree() {
+        let folders = ["", "Mains/Fish", "Mains", "Mains/Fish", "Desserts"].map(String::from);
+        let tree = folder_tree(&folders);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].name, "Desserts");
+        assert_eq!(tree[1].recipes, 3);
+        assert_eq!(tree[1].children[0].path, "Mains/Fish");
+        assert_eq!(tree[1].children[0].recipes, 2);
+    }
+
+    #[test]
+    fn readme() {
+        let blocks = readme_blocks("# Mains\n\nFrom the\nfamily.\n\n## Fish\nFresh only.");
+        let text = blocks
+            .iter()
+            .map(|b| (b.heading, b.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            text,
+            [
+                (true, "Mains"),
+                (false, "From the family."),
+                (true, "Fish"),
+                (false, "Fresh only.")
+            ]
+        );
+    }
+}
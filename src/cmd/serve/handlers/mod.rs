@@ -2,29 +2,52 @@ use camino::{Utf8Path, Utf8PathBuf};
 use cooklang_fs::RecipeEntry;
 use minijinja::{context, Value};
 
+use crate::diet::DietData;
 use crate::util::get_emoji;
 use crate::{config::UiConfig, util::meta_name};
+use cooklang_to_human::TagColor;
 
 use super::async_index::RecipeData;
-use super::AppState;
+use super::{AppState, S};
 
 pub mod about;
 pub mod convert_popover;
+pub mod cook_sync;
+pub mod download;
+pub mod feed;
+pub mod graph;
+pub mod import;
 pub mod index;
 pub mod open_editor;
+pub mod openapi;
+pub mod random;
+pub mod rate;
 pub mod recipe;
 pub mod search;
 pub mod sse_updates;
 pub mod static_file;
+pub mod stats;
+pub mod text;
+pub mod thumbnail;
 
 pub use about::about;
 pub use convert_popover::convert_popover;
+pub use cook_sync::{cook_state, cook_ws, set_cook_state};
+pub use download::download_zip;
+pub use feed::feed;
+pub use graph::graph;
+pub use import::{import_page, import_preview, import_save};
 pub use index::index;
 pub use open_editor::open_editor;
+pub use openapi::openapi;
+pub use random::random;
+pub use rate::rate;
 pub use recipe::recipe;
 pub use search::search;
 pub use sse_updates::sse_updates;
 pub use static_file::static_file;
+pub use stats::stats;
+pub use thumbnail::thumbnail;
 
 macro_rules! ok_status {
     ($res:expr) => {
@@ -81,7 +104,29 @@ pub fn check_path(p: &str) -> Result<(), axum::http::StatusCode> {
     Ok(())
 }
 
-fn clean_path(p: &Utf8Path, base_path: &Utf8Path) -> Utf8PathBuf {
+/// Rejects requests sent by other sites, for the routes that change things
+///
+/// Browsers tell where a request comes from in `Sec-Fetch-Site`, or only in
+/// `Origin` the older ones. A request without both is not from a browser, so
+/// it can't be forged by a web page.
+pub fn check_same_origin(headers: &axum::http::HeaderMap) -> Result<(), axum::http::StatusCode> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let same_origin = if let Some(site) = header("sec-fetch-site") {
+        matches!(site, "same-origin" | "none")
+    } else if let Some(origin) = header("origin") {
+        let origin_host = origin.split_once("://").map(|(_, host)| host);
+        origin_host.is_some() && origin_host == header("host")
+    } else {
+        true
+    };
+    if !same_origin {
+        tracing::warn!("Denied request from another site");
+        return Err(axum::http::StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
+
+pub(super) fn clean_path(p: &Utf8Path, base_path: &Utf8Path) -> Utf8PathBuf {
     let p = p
         .strip_prefix(base_path)
         .expect("dir entry path not relative to base path");
@@ -92,6 +137,24 @@ fn clean_path(p: &Utf8Path, base_path: &Utf8Path) -> Utf8PathBuf {
     p
 }
 
+/// Folder of every recipe, relative to the collection, one per recipe
+async fn recipe_folders(state: &S) -> Vec<String> {
+    state
+        .recipe_index
+        .search(
+            |_, _| true,
+            |entry, _| {
+                clean_path(entry.path(), &state.base_path)
+                    .parent()
+                    .map(|p| p.to_string())
+                    .unwrap_or_default()
+            },
+            0,
+            usize::MAX,
+        )
+        .await
+}
+
 fn recipe_entry_context(
     r: RecipeEntry,
     state: &AppState,
@@ -123,12 +186,13 @@ fn recipe_entry_context(
         error = true;
     }
 
+    let mut srcset = None;
     if image.is_none() {
-        image = r
-            .images()
-            .iter()
-            .find(|i| i.indexes.is_none())
-            .map(|i| image_url(&i.path, &state.base_path));
+        let images = r.images();
+        if let Some(i) = images.iter().find(|i| i.indexes.is_none()) {
+            image = Some(image_url(&i.path, &state.base_path));
+            srcset = Some(image_srcset(&i.path, &state.base_path));
+        }
     }
 
     let path = clean_path(r.path(), &state.base_path).with_extension("");
@@ -138,6 +202,7 @@ fn recipe_entry_context(
         href => format!("/r/{path}"),
         error,
         image,
+        srcset,
         ..metadata,
     })
 }
@@ -146,6 +211,16 @@ fn image_url(path: &Utf8Path, base_path: &Utf8Path) -> String {
     format!("/src/{}", clean_path(path, base_path))
 }
 
+/// `srcset` with the thumbnails of a collection image
+fn image_srcset(path: &Utf8Path, base_path: &Utf8Path) -> String {
+    let path = clean_path(path, base_path);
+    crate::thumbnails::WIDTHS
+        .iter()
+        .map(|w| format!("/thumb/{w}/{path} {w}w"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn tag_context(name: &str, ui_config: &UiConfig) -> Value {
     let emoji = ui_config
         .tags
@@ -159,7 +234,22 @@ fn tag_context(name: &str, ui_config: &UiConfig) -> Value {
             }
         })
         .map(|e| e.as_str());
-    context! { emoji, name }
+    let color = radix_palette(ui_config.tag_color(name));
+    context! { emoji, name, color }
+}
+
+/// Radix colour of the styles for a tag colour
+fn radix_palette(color: TagColor) -> &'static str {
+    match color {
+        TagColor::Red => "tomato",
+        TagColor::Orange => "orange",
+        TagColor::Yellow => "yellow",
+        TagColor::Green => "grass",
+        TagColor::Teal => "jade",
+        TagColor::Blue => "blue",
+        TagColor::Indigo => "indigo",
+        TagColor::Gray => "sand",
+    }
 }
 
 #[derive(Debug)]
@@ -171,14 +261,16 @@ enum Searcher {
     Tag(String),
     Ingredient(String),
     Cookware(String),
+    Diet(String),
+    Allergen(String),
 }
 
 impl Searcher {
-    fn matches_recipe(&self, name: &str, tokens: &RecipeData) -> bool {
+    fn matches_recipe(&self, name: &str, tokens: &RecipeData, diet: &DietData) -> bool {
         match self {
-            Self::All(v) => v.is_empty() | v.iter().all(|s| s.matches_recipe(name, tokens)),
-            Self::Any(v) => v.is_empty() | v.iter().any(|s| s.matches_recipe(name, tokens)),
-            Self::Not(searcher) => !searcher.matches_recipe(name, tokens),
+            Self::All(v) => v.is_empty() | v.iter().all(|s| s.matches_recipe(name, tokens, diet)),
+            Self::Any(v) => v.is_empty() | v.iter().any(|s| s.matches_recipe(name, tokens, diet)),
+            Self::Not(searcher) => !searcher.matches_recipe(name, tokens, diet),
             Self::NamePart(part) => name.to_lowercase().contains(part),
             Self::Tag(tag) => match tokens.metadata.as_ref() {
                 Some(meta) => meta
@@ -196,6 +288,46 @@ impl Searcher {
                 .cookware
                 .iter()
                 .any(|str| str.to_lowercase().contains(cookware)),
+            Self::Diet(name) => {
+                let c = diet.classify(tokens.ingredients.iter().map(String::as_str));
+                diet.conflicts(&c, name).is_ok_and(|c| c.is_empty())
+            }
+            Self::Allergen(category) => tokens
+                .ingredients
+                .iter()
+                .any(|i| diet.categories_of(i).contains(&category.as_str())),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+
+    use super::*;
+
+    #[test]
+    fn same_origin() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in pairs {
+                map.insert(*name, value.parse().unwrap());
+            }
+            map
+        };
+        assert!(check_same_origin(&headers(&[])).is_ok());
+        assert!(check_same_origin(&headers(&[("sec-fetch-site", "same-origin")])).is_ok());
+        assert!(check_same_origin(&headers(&[("sec-fetch-site", "cross-site")])).is_err());
+        assert!(check_same_origin(&headers(&[
+            ("origin", "http://localhost:8080"),
+            ("host", "localhost:8080")
+        ]))
+        .is_ok());
+        assert!(check_same_origin(&headers(&[
+            ("origin", "https://evil.example"),
+            ("host", "localhost:8080")
+        ]))
+        .is_err());
+        assert!(check_same_origin(&headers(&[("origin", "null"), ("host", "localhost")])).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+use axum::{extract::State, Json};
+use serde_json::Value;
+
+use crate::cmd::serve::{openapi::document, S};
+
+pub async fn openapi(State(state): State<S>) -> Json<Value> {
+    #[cfg(feature = "federation")]
+    let federation = state.federation.is_some();
+    #[cfg(not(feature = "federation"))]
+    let federation = false;
+    Json(document(state.text_only, federation))
+}
@@ -0,0 +1,123 @@
+//! Random recipe, the "what's for dinner" button
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Redirect, Response},
+};
+use serde::Deserialize;
+use tokio::task::block_in_place;
+
+use crate::{
+    cmd::serve::{
+        openapi::{ApiParams, Param},
+        S,
+    },
+    cook_log::{recipe_key, CookLog},
+    pantry::Date,
+    pick::{choose, weight, Constraints},
+    recipe_notes::{rated_weight, RecipeNotes},
+    util::encode_path,
+};
+
+use super::clean_path;
+
+#[derive(Deserialize)]
+pub struct RandomQuery {
+    tag: Option<String>,
+    max_time: Option<u32>,
+    /// Ingredients separated by commas
+    exclude: Option<String>,
+    #[serde(default)]
+    weighted: bool,
+}
+
+impl ApiParams for RandomQuery {
+    const PARAMS: &'static [Param] = &[
+        Param {
+            name: "tag",
+            ty: "string",
+            description: "Only recipes with this tag",
+        },
+        Param {
+            name: "max_time",
+            ty: "integer",
+            description: "Only recipes that take at most these minutes",
+        },
+        Param {
+            name: "exclude",
+            ty: "string",
+            description: "Skip recipes with these ingredients, separated by commas",
+        },
+        Param {
+            name: "weighted",
+            ty: "boolean",
+            description: "Favor the recipes not cooked for longer and the best rated",
+        },
+    ];
+}
+
+/// Redirects to a random recipe matching the query
+pub async fn random(State(state): State<S>, Query(q): Query<RandomQuery>) -> Response {
+    let constraints = Constraints {
+        tags: q.tag.into_iter().collect(),
+        max_time: q.max_time,
+        exclude_ingredients: q
+            .exclude
+            .iter()
+            .flat_map(|e| e.split(','))
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .map(String::from)
+            .collect(),
+    };
+    let converter = state.parser.converter();
+    let paths = state
+        .recipe_index
+        .search(
+            |_, data| {
+                data.and_then(|d| d.metadata.as_ref().map(|m| (m, d)))
+                    .is_some_and(|(m, d)| {
+                        let ingredients = d.ingredients.iter().map(String::as_str);
+                        constraints.matches(m, ingredients, converter)
+                    })
+            },
+            |entry, _| entry.path().to_owned(),
+            0,
+            usize::MAX,
+        )
+        .await;
+
+    let weights = if q.weighted {
+        let log = match block_in_place(|| CookLog::load(&state.base_path)) {
+            Ok(log) => log,
+            Err(e) => {
+                tracing::error!("{e:#}");
+                CookLog::default()
+            }
+        };
+        let today = Date::today();
+        paths
+            .iter()
+            .map(|p| {
+                let last = log.last_cooked(&recipe_key(p, &state.base_path));
+                let rating = match block_in_place(|| RecipeNotes::load(p)) {
+                    Ok(notes) => notes.rating(),
+                    Err(e) => {
+                        tracing::error!("{e:#}");
+                        None
+                    }
+                };
+                rated_weight(weight(last, today), rating)
+            })
+            .collect()
+    } else {
+        vec![1; paths.len()]
+    };
+    let Some(i) = choose(&weights) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let path = clean_path(&paths[i], &state.base_path).with_extension("");
+    Redirect::to(&format!("/r/{}", encode_path(path.as_str()))).into_response()
+}
@@ -0,0 +1,58 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use serde::Deserialize;
+use tokio::task::block_in_place;
+
+use crate::{
+    cmd::serve::S,
+    pantry::Date,
+    recipe_notes::{self, parse_rating, Note},
+    util::encode_path,
+};
+
+use super::{check_path, check_same_origin, ok_status};
+
+#[derive(Deserialize)]
+pub struct RateForm {
+    /// From 1 to 5, empty to only leave a note
+    #[serde(default)]
+    rating: String,
+    #[serde(default)]
+    text: String,
+}
+
+/// Appends a rating and a note to the notes file of a recipe
+///
+/// It writes to the collection, so only the recipe page can send it.
+pub async fn rate(
+    State(state): State<S>,
+    Path(path): Path<String>,
+    headers: HeaderMap,
+    Form(form): Form<RateForm>,
+) -> Response {
+    if let Err(e) = check_path(&path).and(check_same_origin(&headers)) {
+        return e.into_response();
+    }
+    let entry = ok_status!(state.recipe_index.get(&path).await, NOT_FOUND);
+
+    let rating = match form.rating.trim() {
+        "" => None,
+        rating => Some(ok_status!(parse_rating(rating), BAD_REQUEST)),
+    };
+    let text = Some(form.text.trim().to_string()).filter(|t| !t.is_empty());
+    if rating.is_none() && text.is_none() {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let note = Note {
+        date: Date::today(),
+        rating,
+        text,
+    };
+    ok_status!(block_in_place(|| recipe_notes::append(entry.path(), note)));
+
+    Redirect::to(&format!("/r/{}#notes", encode_path(&path))).into_response()
+}
@@ -1,11 +1,16 @@
-use std::{collections::HashMap, net::SocketAddr, time::SystemTime};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::Mutex,
+    time::SystemTime,
+};
 
 use axum::{
     extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode, Uri},
     response::{Html, IntoResponse, Response},
 };
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
 use cooklang::{
     convert::PhysicalQuantity, error::SourceReport, metadata::CooklangValueExt, Converter,
     Modifiers, ParseOptions, ScaledRecipe,
@@ -19,14 +24,30 @@ use crate::{
         get_cookie,
         handlers::{clean_path, ok_status, tag_context},
         locale::UserLocale,
+        openapi::{ApiParams, Param},
         AppState, S,
     },
+    collection_file::{inherit, COLLECTION_FILE},
     config::Config,
+    diagnostics,
+    locale::{recipe_locale, unit_system},
+    pantry::{Date, Pantry, EXPIRING_SOON_DAYS},
+    partial::parse_partial,
+    prep::Preparations,
+    recipe_notes::{notes_path, stars, RecipeNotes},
+    scaling::{apply_strategies, base_quantities, is_approx, scale_factor, scale_yield},
+    timers::{adjust_timers, TIMER_SCALING_KEY},
     util::{map_recipe, meta_name, metadata_validator},
     RECIPE_REF_ERROR,
 };
 
-use super::{check_path, image_url, mj_ok};
+use super::{check_path, image_srcset, image_url, mj_ok};
+
+/// Latest notes shown in a recipe page
+const NOTES_SHOWN: usize = 5;
+
+/// Rendered recipe pages kept in memory
+const MAX_PAGES: usize = 128;
 
 #[derive(Deserialize, Serialize)]
 pub struct RecipeQuery {
@@ -34,6 +55,21 @@ pub struct RecipeQuery {
     units: Option<String>,
 }
 
+impl ApiParams for RecipeQuery {
+    const PARAMS: &'static [Param] = &[
+        Param {
+            name: "scale",
+            ty: "integer",
+            description: "Servings to scale the recipe to",
+        },
+        Param {
+            name: "units",
+            ty: "string",
+            description: "`metric`, `imperial` or `default` units",
+        },
+    ];
+}
+
 pub async fn recipe(
     headers: HeaderMap,
     State(state): State<S>,
@@ -57,15 +93,40 @@ pub async fn recipe(
     }
 
     let entry = ok_status!(state.recipe_index.get(&path).await, NOT_FOUND);
-    let content = ok_status!(tokio::fs::read_to_string(&entry.path()).await, NOT_FOUND);
 
-    let res = block_in_place(|| {
+    let page_key = block_in_place(|| PageKey {
+        path: uri.path().to_string(),
+        scale: query.scale,
+        units: query.units.clone(),
+        locale: state.locales.get_from_headers(&headers).code.clone(),
+        igr_layout: get_cookie(&headers, "igr_layout").map(str::to_string),
+        loopback: addr.ip().is_loopback(),
+        today: Date::today(),
+        files: page_files(&state, entry.path(), entry.images()),
+    });
+    if let Some(page) = state.recipe_pages.get(&page_key) {
+        return Html(page).into_response();
+    }
+
+    let content = ok_status!(block_in_place(|| entry.read()), NOT_FOUND).into_text();
+
+    let parse = |text: &str| {
         state
             .parser
-            .parse_with_options(&content, state.parse_options(Some(entry.path())))
+            .parse_with_options(text, state.parse_options(Some(entry.path())))
+            .map(|r| inherit(r, entry.path(), &state.base_path))
             .map(map_recipe)
-            .into_result()
-    });
+    };
+    let res = block_in_place(|| parse(&content).into_result());
+    // with errors, what is valid of the recipe is still shown, with the
+    // errors marked, for the live preview while writing it
+    let (res, partial) = match res {
+        Err(report) => match block_in_place(|| parse_partial(&content, parse)) {
+            Some(recipe) => (Ok((recipe, report)), true),
+            None => (Err(report), false),
+        },
+        res => (res, false),
+    };
 
     let tmpl = mj_ok!(state.templates.get_template("recipe.html"));
 
@@ -81,16 +142,40 @@ pub async fn recipe(
         Ok((scalable, warnings)) => {
             let scaled = {
                 let mut r = if let Some(servings) = query.scale {
-                    scalable.scale(servings, state.parser.converter())
+                    let base = base_quantities(&scalable);
+                    let mut scaled = scalable.scale(servings, state.parser.converter());
+                    apply_strategies(&mut scaled, &base, &state.config.scaling);
+                    if let Some(factor) = scale_factor(&scaled) {
+                        scale_yield(&mut scaled, factor);
+                    }
+                    scaled
                 } else {
                     scalable.default_scale()
                 };
-                if let Some(target) = units {
+                // without a choice, the units of the locale if there is one
+                let target = units.or_else(|| {
+                    query
+                        .units
+                        .is_none()
+                        .then(|| recipe_locale(&r.metadata, &state.config).map(unit_system))?
+                });
+                if let Some(target) = target {
                     let _ = r.convert(target, state.parser.converter());
                 }
                 r
             };
 
+            let lints = diagnostics::lint(
+                &scaled,
+                entry.path(),
+                &state.base_path,
+                &content,
+                state.parser.converter(),
+            )
+            .iter()
+            .map(|d| d.summary())
+            .collect::<Vec<_>>();
+
             let report_html = if warnings.is_empty() {
                 None
             } else {
@@ -137,19 +222,51 @@ pub async fn recipe(
                     href => image_url(&img.path, &state.base_path)
                 }
             }));
-            let main_image = scaled
+            let external_image = scaled
                 .metadata
                 .map
                 .get("image")
                 .and_then(|v| v.as_str())
-                .map(|v| v.to_string())
-                .or_else(|| {
-                    entry
-                        .images()
-                        .iter()
-                        .find(|img| img.indexes.is_none())
-                        .map(|img| image_url(&img.path, &state.base_path))
-                });
+                .map(|v| v.to_string());
+            let entry_images = entry.images();
+            let local_image = entry_images.iter().find(|img| img.indexes.is_none());
+            let main_image_srcset = local_image
+                .filter(|_| external_image.is_none())
+                .map(|img| image_srcset(&img.path, &state.base_path));
+            let main_image = external_image
+                .or_else(|| local_image.map(|img| image_url(&img.path, &state.base_path)));
+
+            let expiring = block_in_place(|| expiring_items(&state));
+            let notes = match block_in_place(|| RecipeNotes::load(entry.path())) {
+                Ok(notes) => notes,
+                Err(e) => {
+                    tracing::error!("{e:#}");
+                    RecipeNotes::default()
+                }
+            };
+            let rating = notes
+                .rating()
+                .map(|r| context! { stars => stars(r), value => format!("{r:.1}") });
+            // newest first
+            let notes = notes
+                .notes
+                .iter()
+                .rev()
+                .take(NOTES_SHOWN)
+                .collect::<Vec<_>>();
+            let classification = state.diet.classify(
+                scaled
+                    .ingredients
+                    .iter()
+                    .filter(|i| i.modifiers().should_be_listed())
+                    .map(|i| i.name.as_str()),
+            );
+            let allergens = state
+                .diet
+                .allergens(&classification)
+                .into_iter()
+                .map(|(name, ingredients)| context! { name, ingredients })
+                .collect::<Vec<_>>();
 
             let r = make_recipe_context(scaled, state.parser.converter(), &state.config);
 
@@ -159,19 +276,27 @@ pub async fn recipe(
                 query,
                 path => uri.path(),
                 recipe_refs,
+                expiring,
+                allergens,
+                rating,
+                notes,
+                rate_prompt => state.config.rate_after_cooking,
 
                 times,
                 images,
                 main_image,
+                main_image_srcset,
 
                 show_open_editor_btn => addr.ip().is_loopback() && !state.disable_open_editor,
                 igr_layout => get_cookie(&headers, "igr_layout").unwrap_or("line"),
 
                 report_html,
-                severity => "warning",
+                lints,
+                severity => if partial { "error" } else { "warning" },
                 ..ctx
             };
             let content = mj_ok!(tmpl.render(ctx));
+            state.recipe_pages.put(page_key, &content);
             Html(content).into_response()
         }
         Err(report) => {
@@ -183,19 +308,113 @@ pub async fn recipe(
                 severity => "error",
                 ..ctx
             }));
+            state.recipe_pages.put(page_key, &content);
             Html(content).into_response()
         }
     }
 }
 
-fn make_recipe_context(r: ScaledRecipe, converter: &Converter, config: &Config) -> Value {
+/// Everything a recipe page depends on
+///
+/// The request and the files read to render it, with their modification
+/// time, so a changed file renders the page again.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PageKey {
+    path: String,
+    scale: Option<u32>,
+    units: Option<String>,
+    locale: String,
+    igr_layout: Option<String>,
+    loopback: bool,
+    /// The expiring pantry items change with the day
+    today: Date,
+    files: Vec<(Utf8PathBuf, Option<SystemTime>)>,
+}
+
+/// The files read to render a recipe page
+fn page_files(
+    state: &AppState,
+    recipe: &Utf8Path,
+    images: &[cooklang_fs::Image],
+) -> Vec<(Utf8PathBuf, Option<SystemTime>)> {
+    let mut files = vec![
+        recipe.to_path_buf(),
+        notes_path(recipe),
+        state.config.pantry(&state.base_path),
+    ];
+    if let Some(dir) = recipe.parent() {
+        files.extend(
+            dir.ancestors()
+                .take_while(|d| d.starts_with(&state.base_path))
+                .map(|d| d.join(COLLECTION_FILE)),
+        );
+    }
+    files.extend(images.iter().map(|img| img.path.clone()));
+    files
+        .into_iter()
+        .map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// The last recipe pages rendered, parsing and rendering a recipe is slow
+///
+/// The links to referenced recipes are not in the key, so all the pages are
+/// dropped when the recipes in the collection change.
+#[derive(Default)]
+pub struct PageCache(Mutex<Pages>);
+
+#[derive(Default)]
+struct Pages {
+    pages: HashMap<PageKey, String>,
+    /// Oldest first, to remove them over [`MAX_PAGES`]
+    order: VecDeque<PageKey>,
+}
+
+impl PageCache {
+    fn get(&self, key: &PageKey) -> Option<String> {
+        self.0.lock().unwrap().pages.get(key).cloned()
+    }
+
+    fn put(&self, key: PageKey, page: &str) {
+        let mut cache = self.0.lock().unwrap();
+        let Pages { pages, order } = &mut *cache;
+        if pages.len() >= MAX_PAGES {
+            if let Some(oldest) = order.pop_front() {
+                pages.remove(&oldest);
+            }
+        }
+        if pages.insert(key.clone(), page.to_string()).is_none() {
+            order.push_back(key);
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut cache = self.0.lock().unwrap();
+        cache.pages.clear();
+        cache.order.clear();
+    }
+}
+
+fn make_recipe_context(mut r: ScaledRecipe, converter: &Converter, config: &Config) -> Value {
+    // by timer index, none for the ones unchanged
+    let mut timer_notes = vec![Value::from(()); r.timers.len()];
+    let factor = scale_factor(&r).unwrap_or(1.0);
+    for note in adjust_timers(&mut r, factor, converter) {
+        timer_notes[note.index] = context! { exact => note.exact_duration(), check => note.check };
+    }
+
     let grouped_ingredients = r
         .group_ingredients(converter)
         .into_iter()
         .map(|entry| {
+            // approximate values are scaled after the parser, not an error
+            let approx = entry.quantity.iter().any(is_approx);
             context! {
                 index => entry.index,
-                outcome => entry.outcome,
+                outcome => if approx { None } else { entry.outcome },
                 quantities => entry.quantity.iter().map(|q| context! {
                     value => q.value(),
                     unit => q.unit()
@@ -215,6 +434,8 @@ fn make_recipe_context(r: ScaledRecipe, converter: &Converter, config: &Config)
         })
         .collect::<Value>();
 
+    let prep = Preparations::new(&config.prep).prep_list(&r, converter);
+
     let timers_seconds = r
         .timers
         .iter()
@@ -248,7 +469,7 @@ fn make_recipe_context(r: ScaledRecipe, converter: &Converter, config: &Config)
             other => Value::from_iter(r.metadata.map.iter().filter_map(|(key, value)| {
                 let key = key.as_str_like()?;
                 match key.as_ref() {
-                    "name" | "title" | "description" | "tags" | "emoji" |  "author" | "source" | "time" | "prep time" | "cook time" | "servings" => return None,
+                    "name" | "title" | "description" | "tags" | "emoji" |  "author" | "source" | "time" | "prep time" | "cook time" | "servings" | TIMER_SCALING_KEY => return None,
                     _ => {}
                 }
                 if value.get("unsafe") == Some(&serde_yaml::Value::Bool(true)) {
@@ -263,6 +484,7 @@ fn make_recipe_context(r: ScaledRecipe, converter: &Converter, config: &Config)
         },
         grouped_ingredients,
         grouped_cookware,
+        prep,
 
         sections => r.sections,
 
@@ -270,6 +492,7 @@ fn make_recipe_context(r: ScaledRecipe, converter: &Converter, config: &Config)
         cookware => r.cookware.into_iter().map(TemplateCookware).map(Value::from_object).collect::<Value>(),
         timers => r.timers,
         timers_seconds,
+        timer_notes,
         inline_quantities => r.inline_quantities,
         inline_is_temp => r.inline_quantities.iter().map(|q| q.unit_info(converter).map(|u| u.physical_quantity == PhysicalQuantity::Temperature)).collect::<Value>(),
     }
@@ -325,6 +548,22 @@ impl minijinja::value::Object for TemplateCookware {
     }
 }
 
+/// Days left of the pantry items expiring soon, by lowercase name
+fn expiring_items(state: &AppState) -> HashMap<String, i64> {
+    let pantry = match Pantry::read(&state.config.pantry(&state.base_path)) {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::warn!("{e:#}");
+            return HashMap::new();
+        }
+    };
+    pantry
+        .expiring(EXPIRING_SOON_DAYS)
+        .into_iter()
+        .map(|(name, _, left)| (name.to_lowercase(), left))
+        .collect()
+}
+
 async fn get_times(path: &Utf8Path) -> anyhow::Result<Value> {
     fn f(st: std::io::Result<SystemTime>) -> Option<u64> {
         st.ok()
@@ -341,7 +580,7 @@ impl AppState {
     fn checker(
         &self,
         relative_to: Option<&Utf8Path>,
-    ) -> Option<cooklang::analysis::RecipeRefCheck> {
+    ) -> Option<cooklang::analysis::RecipeRefCheck<'_>> {
         if self.config.recipe_ref_check {
             let relative_to =
                 relative_to.map(|r| r.parent().expect("no parent for recipe entry").to_owned());
@@ -361,7 +600,10 @@ impl AppState {
         }
     }
 
-    fn parse_options(&self, relative_to: Option<&Utf8Path>) -> ParseOptions {
+    pub(in crate::cmd::serve) fn parse_options(
+        &self,
+        relative_to: Option<&Utf8Path>,
+    ) -> ParseOptions<'_> {
         ParseOptions {
             recipe_ref_check: self.checker(relative_to),
             metadata_validator: Some(Box::new(metadata_validator)),
@@ -369,7 +611,11 @@ impl AppState {
     }
 }
 
-fn report_to_html(report: &SourceReport, file_name: &str, content: &str) -> anyhow::Result<String> {
+pub(super) fn report_to_html(
+    report: &SourceReport,
+    file_name: &str,
+    content: &str,
+) -> anyhow::Result<String> {
     let mut buf = Vec::new();
     report.write(file_name, content, true, &mut buf)?;
     let ansi = String::from_utf8(buf)?;
@@ -455,3 +701,46 @@ pub fn step_ingredients(
     }
     Ok(Value::from(step_ingredients))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(path: &str, locale: &str) -> PageKey {
+        PageKey {
+            path: path.to_string(),
+            scale: None,
+            units: None,
+            locale: locale.to_string(),
+            igr_layout: None,
+            loopback: true,
+            today: Date::today(),
+            files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn pages_by_request() {
+        let cache = PageCache::default();
+        cache.put(key("/r/a", "en"), "a en");
+        cache.put(key("/r/a", "es"), "a es");
+        assert_eq!(cache.get(&key("/r/a", "en")).as_deref(), Some("a en"));
+        assert_eq!(cache.get(&key("/r/a", "es")).as_deref(), Some("a es"));
+        assert_eq!(cache.get(&key("/r/b", "en")), None);
+
+        for i in 0..MAX_PAGES {
+            cache.put(key(&format!("/r/{i}"), "en"), "page");
+        }
+        // the oldest are gone
+        assert_eq!(cache.get(&key("/r/a", "en")), None);
+        assert!(cache
+            .get(&key(&format!("/r/{}", MAX_PAGES - 1), "en"))
+            .is_some());
+
+        cache.clear();
+        assert_eq!(
+            cache.get(&key(&format!("/r/{}", MAX_PAGES - 1), "en")),
+            None
+        );
+    }
+}
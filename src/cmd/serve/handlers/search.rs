@@ -9,7 +9,11 @@ use minijinja::context;
 use serde::Deserialize;
 
 use crate::{
-    cmd::serve::{locale::UserLocale, S},
+    cmd::serve::{
+        locale::UserLocale,
+        openapi::{ApiParams, Param},
+        S,
+    },
     util::{is_valid_tag, meta_name},
 };
 
@@ -18,7 +22,15 @@ use super::{mj_ok, recipe_entry_context, Searcher};
 #[derive(Deserialize)]
 pub struct SearchQuery {
     #[serde(default, deserialize_with = "empty_string_as_none")]
-    q: Option<String>,
+    pub(super) q: Option<String>,
+}
+
+impl ApiParams for SearchQuery {
+    const PARAMS: &'static [Param] = &[Param {
+        name: "q",
+        ty: "string",
+        description: "Search terms, like `tag:dessert ingredient:flour`",
+    }];
 }
 
 fn empty_string_as_none<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
@@ -54,7 +66,7 @@ pub async fn search(
                     } else {
                         entry.name()
                     };
-                    srch.matches_recipe(name, t)
+                    srch.matches_recipe(name, t, &state.diet)
                 }
                 None => false,
             },
@@ -106,21 +118,6 @@ fn error_correct_query(query: &str) -> String {
     working_string
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_error_correct_query() {
-        assert_eq!(error_correct_query("a b c"), "a b c");
-        assert_eq!(error_correct_query("a | c"), "a | c");
-        assert_eq!(error_correct_query("(b c"), "(b c)");
-        assert_eq!(error_correct_query("(a b)"), "(a b)");
-        assert_eq!(error_correct_query("a | (b | c)"), "a | (b | c)");
-        assert_eq!(error_correct_query("b) c"), "(b) c");
-    }
-}
-
 fn parse_disjunct_chunks(query: &str) -> Vec<&str> {
     let mut depth = 0;
     let mut from = 0;
@@ -210,6 +207,16 @@ impl From<SearchQuery> for Searcher {
                     Some(Searcher::Ingredient(ingredient.to_owned()))
                 } else if let Some(cookware) = part.strip_prefix("cookware:") {
                     Some(Searcher::Cookware(cookware.to_owned()))
+                } else if let Some(diet) = part.strip_prefix("diet:") {
+                    Some(Searcher::Diet(diet.to_lowercase()))
+                } else if let Some(allergen) = part.strip_prefix("allergen:") {
+                    // `allergen:-nuts` is a recipe without nuts
+                    match allergen.strip_prefix('-') {
+                        Some(a) => Some(Searcher::Not(Box::new(Searcher::Allergen(
+                            a.to_lowercase(),
+                        )))),
+                        None => Some(Searcher::Allergen(allergen.to_lowercase())),
+                    }
                 } else {
                     Some(Searcher::NamePart(part.to_owned()))
                 }
@@ -274,6 +281,23 @@ impl Searcher {
                 format!("ingredient:{ingredient}").replace(' ', "+")
             }
             Searcher::Cookware(cookware) => format!("cookware:{cookware}").replace(' ', "+"),
+            Searcher::Diet(diet) => format!("diet:{diet}").replace(' ', "+"),
+            Searcher::Allergen(allergen) => format!("allergen:{allergen}").replace(' ', "+"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_correct_query() {
+        assert_eq!(error_correct_query("a b c"), "a b c");
+        assert_eq!(error_correct_query("a | c"), "a | c");
+        assert_eq!(error_correct_query("(b c"), "(b c)");
+        assert_eq!(error_correct_query("(a b)"), "(a b)");
+        assert_eq!(error_correct_query("a | (b | c)"), "a | (b | c)");
+        assert_eq!(error_correct_query("b) c"), "(b) c");
+    }
+}
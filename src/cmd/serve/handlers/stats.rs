@@ -0,0 +1,123 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Response},
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use cooklang::CooklangParser;
+use minijinja::context;
+use tokio::task::block_in_place;
+
+use crate::{
+    cmd::serve::{locale::UserLocale, S},
+    collection_file::COLLECTION_FILE,
+    config::Config,
+    cook_log::CookLog,
+    log_stats::{compute, recipe_facts, Facts, StatsOptions},
+    pantry::Date,
+    plugins::PluginConfig,
+    prices::format_cost,
+};
+
+use super::{mj_ok, ok_status};
+
+pub async fn stats(UserLocale(t): UserLocale, State(state): State<S>) -> Response {
+    let res = block_in_place(|| -> anyhow::Result<_> {
+        let log = CookLog::load(&state.base_path)?;
+        let facts = state.stats_facts.get(
+            &state.config,
+            &state.plugins,
+            &state.base_path,
+            &state.parser,
+            &log,
+        )?;
+        let (facts, currency) = facts.as_ref();
+        Ok(compute(
+            &log,
+            facts,
+            currency.clone(),
+            Date::today(),
+            &StatsOptions::default(),
+        ))
+    });
+    let stats = ok_status!(res);
+
+    let max_month = stats.months.iter().map(|(_, n)| *n).max().unwrap_or(0);
+    let max_cuisine = stats.cuisines.first().map_or(0, |(_, n)| *n);
+    let cost_per_week = stats
+        .cost_per_week
+        .map(|c| format_cost(c, stats.currency.as_deref()));
+    let kcal_per_week = stats.kcal_per_week.map(|k| k.round() as i64);
+
+    let tmpl = mj_ok!(state.templates.get_template("stats.html"));
+    let res = tmpl.render(context! {
+        t,
+        stats,
+        max_month,
+        max_cuisine,
+        cost_per_week,
+        kcal_per_week,
+    });
+    let content = mj_ok!(res);
+    Html(content).into_response()
+}
+
+type FactsKey = Vec<(Utf8PathBuf, Option<SystemTime>)>;
+
+/// The last facts computed, asking the plugins every time is slow
+#[derive(Default)]
+pub struct FactsCache(Mutex<Option<(FactsKey, Arc<Facts>)>>);
+
+impl FactsCache {
+    /// Same as [`recipe_facts`], computed again only if a file changed
+    pub fn get(
+        &self,
+        config: &Config,
+        plugins: &[PluginConfig],
+        base_path: &Utf8Path,
+        parser: &CooklangParser,
+        log: &CookLog,
+    ) -> Result<Arc<Facts>> {
+        let key = facts_key(config, base_path, log);
+        let mut cache = self.0.lock().unwrap();
+        if let Some((cached, facts)) = cache.as_ref() {
+            if *cached == key {
+                return Ok(Arc::clone(facts));
+            }
+        }
+        let facts = Arc::new(recipe_facts(config, plugins, base_path, parser, log)?);
+        *cache = Some((key, Arc::clone(&facts)));
+        Ok(facts)
+    }
+}
+
+/// The files [`recipe_facts`] reads, with their modification time
+fn facts_key(config: &Config, base_path: &Utf8Path, log: &CookLog) -> FactsKey {
+    let mut files = vec![CookLog::path(base_path)];
+    files.extend(config.prices(base_path));
+    for key in log.recipes.keys() {
+        let path = base_path.join(format!("{key}.cook"));
+        if let Some(dir) = path.parent() {
+            files.extend(
+                dir.ancestors()
+                    .take_while(|d| d.starts_with(base_path))
+                    .map(|d| d.join(COLLECTION_FILE)),
+            );
+        }
+        files.push(path);
+    }
+    files.sort();
+    files.dedup();
+    files
+        .into_iter()
+        .map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
@@ -0,0 +1,159 @@
+//! Text only pages, for slow connections and text browsers
+//!
+//! Recipes are rendered like `chef recipe` does in the terminal, without
+//! images, scripts or external styles.
+
+use std::io;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use camino::Utf8PathBuf;
+use cooklang_to_human::HumanFormatter;
+use minijinja::{context, Value};
+use serde::Deserialize;
+use tokio::task::block_in_place;
+
+use crate::{
+    cmd::serve::{
+        locale::UserLocale,
+        openapi::{ApiParams, Param},
+        S,
+    },
+    collection_file::inherit,
+    util::{map_recipe, meta_name},
+};
+
+use super::{check_path, clean_path, mj_ok, ok_status};
+
+/// Width of the rendered recipes, fits a phone in a text browser
+pub(super) const TEXT_WIDTH: usize = 72;
+
+pub async fn index(
+    UserLocale(t): UserLocale,
+    State(state): State<S>,
+    requested_path: Option<Path<String>>,
+) -> Response {
+    let mut path = state.base_path.clone();
+    if let Some(Path(p)) = &requested_path {
+        if let Err(e) = check_path(p) {
+            return e.into_response();
+        }
+        path = path.join(p);
+    }
+
+    let entries =
+        match cooklang_fs::walk_dir_with_rules(&path, &state.base_path, &state.config.discovery) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return StatusCode::NOT_FOUND.into_response()
+            }
+            Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+        };
+
+    let mut folders = Vec::new();
+    let mut recipes = Vec::new();
+    for e in entries {
+        match e {
+            cooklang_fs::Entry::Dir(dir) => folders.push(context! {
+                name => dir.file_name(),
+                path => clean_path(dir.path(), &state.base_path),
+            }),
+            cooklang_fs::Entry::Recipe(r) => recipes.push(context! {
+                name => r.name(),
+                href => format!("/r/{}", clean_path(r.path(), &state.base_path).with_extension("")),
+            }),
+        }
+    }
+
+    let relative = clean_path(&path, &state.base_path);
+    let tmpl = mj_ok!(state.templates.get_template("text.html"));
+    let res = tmpl.render(context! {
+        t,
+        folders,
+        recipes,
+        path => Value::from_iter(relative.components().map(|c| c.as_str())),
+    });
+    Html(mj_ok!(res)).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct TextRecipeQuery {
+    scale: Option<u32>,
+}
+
+impl ApiParams for TextRecipeQuery {
+    const PARAMS: &'static [Param] = &[Param {
+        name: "scale",
+        ty: "integer",
+        description: "Servings to scale the recipe to",
+    }];
+}
+
+pub async fn recipe(
+    UserLocale(t): UserLocale,
+    State(state): State<S>,
+    Path(path): Path<String>,
+    Query(query): Query<TextRecipeQuery>,
+) -> Response {
+    if let Err(e) = check_path(&path) {
+        return e.into_response();
+    }
+    let entry = ok_status!(state.recipe_index.get(&path).await, NOT_FOUND);
+    let content = ok_status!(block_in_place(|| entry.read()), NOT_FOUND).into_text();
+
+    let res = block_in_place(|| {
+        state
+            .parser
+            .parse_with_options(&content, state.parse_options(Some(entry.path())))
+            .map(|r| inherit(r, entry.path(), &state.base_path))
+            .map(map_recipe)
+            .into_result()
+    });
+
+    let (name, text, servings) = match res {
+        Ok((scalable, _)) => {
+            let recipe = match query.scale {
+                Some(servings) => scalable.scale(servings, state.parser.converter()),
+                None => scalable.default_scale(),
+            };
+            let name = meta_name(&recipe.metadata)
+                .unwrap_or(entry.name())
+                .to_string();
+            let mut buf = Vec::new();
+            ok_status!(HumanFormatter::builder()
+                .color(false)
+                .width(TEXT_WIDTH)
+                .build()
+                .format(&recipe, &name, state.parser.converter(), &mut buf));
+            let servings = query
+                .scale
+                .or_else(|| recipe.metadata.servings().and_then(|s| s.first().copied()));
+            (name, String::from_utf8_lossy(&buf).into_owned(), servings)
+        }
+        Err(report) => {
+            let mut buf = Vec::new();
+            ok_status!(report.write(entry.file_name(), &content, false, &mut buf));
+            (
+                entry.name().to_string(),
+                String::from_utf8_lossy(&buf).into_owned(),
+                None,
+            )
+        }
+    };
+
+    let src_path = clean_path(entry.path(), &state.base_path);
+    let dir = src_path.parent().map(Utf8PathBuf::from).unwrap_or_default();
+    let tmpl = mj_ok!(state.templates.get_template("text.html"));
+    let res = tmpl.render(context! {
+        t,
+        name,
+        text,
+        servings,
+        src_path,
+        path => Value::from_iter(dir.components().map(|c| c.as_str())),
+    });
+    Html(mj_ok!(res)).into_response()
+}
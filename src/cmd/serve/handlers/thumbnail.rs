@@ -0,0 +1,54 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+};
+
+use crate::{cmd::serve::S, thumbnails};
+
+use super::{check_path, ok_status};
+
+/// An image of the collection resized to one of the thumbnail widths
+///
+/// Images that can't be resized redirect to the original.
+pub async fn thumbnail(
+    State(state): State<S>,
+    Path((width, path)): Path<(u32, String)>,
+) -> Response {
+    if let Err(e) = check_path(&path) {
+        return e.into_response();
+    }
+    if !thumbnails::WIDTHS.contains(&width) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let image = state.base_path.join(&path);
+    let supported = image
+        .extension()
+        .is_some_and(|e| thumbnails::SUPPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if !supported {
+        return if cooklang_fs::IMAGE_EXTENSIONS.contains(&image.extension().unwrap_or_default()) {
+            Redirect::to(&format!("/src/{path}")).into_response()
+        } else {
+            StatusCode::NOT_FOUND.into_response()
+        };
+    }
+    if !image.is_file() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let thumbnail = ok_status!(tokio::task::spawn_blocking(move || thumbnails::get_or_make(
+        &image, width
+    ))
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|r| r));
+    let content = ok_status!(tokio::fs::read(&thumbnail).await);
+    (
+        [
+            (header::CONTENT_TYPE, "image/webp"),
+            (header::CACHE_CONTROL, "public, max-age=86400"),
+        ],
+        content,
+    )
+        .into_response()
+}
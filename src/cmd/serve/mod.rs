@@ -1,6 +1,12 @@
 mod async_index;
+mod cook_sync;
+#[cfg(feature = "federation")]
+mod federation;
+#[cfg(feature = "graphql")]
+mod graphql;
 mod handlers;
 mod locale;
+mod openapi;
 
 use self::{
     async_index::{AsyncFsIndex, Update},
@@ -9,7 +15,7 @@ use self::{
 use crate::Context;
 use anyhow::{bail, Context as _, Result};
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::{header::CONTENT_TYPE, HeaderMap, HeaderValue, StatusCode},
     middleware::{self, Next},
     response::Response,
@@ -28,7 +34,11 @@ use std::{
 };
 use tokio::sync::broadcast;
 use tower::ServiceBuilder;
-use tracing::info;
+use tower_http::{
+    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
+    LatencyUnit,
+};
+use tracing::{info, Level};
 
 #[derive(Debug, Args)]
 pub struct ServeArgs {
@@ -40,6 +50,10 @@ pub struct ServeArgs {
     #[arg(long)]
     disable_open_editor: bool,
 
+    /// Disable the import page, to add recipes from the browser
+    #[arg(long)]
+    disable_import: bool,
+
     /// Set http server port
     #[arg(long, default_value_t = 8080)]
     port: u16,
@@ -47,15 +61,41 @@ pub struct ServeArgs {
     /// Open browser on start
     #[arg(long, conflicts_with = "host", default_value_t = false)]
     open: bool,
+
+    /// Serve a text only UI, without images, for slow connections
+    ///
+    /// Can also be set with `ui.text_only` in the config.
+    #[arg(long)]
+    text_only: bool,
+
+    /// Print the OpenAPI document of the server and exit
+    #[arg(long)]
+    print_openapi: bool,
 }
 
 #[tokio::main]
 pub async fn run(ctx: Context, args: ServeArgs) -> Result<()> {
+    if args.print_openapi {
+        let doc = openapi::document(
+            args.text_only || ctx.config.ui.text_only,
+            cfg!(feature = "federation") && ctx.config.federation.is_some(),
+        );
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+        return Ok(());
+    }
     if !ctx.is_collection {
         bail!("`serve` needs to run inside a collection");
     }
 
     let state = build_state(ctx, &args).context("failed to build web server")?;
+    #[cfg(feature = "federation")]
+    if state.federation.is_some() {
+        federation::publish_updates(Arc::clone(&state));
+    }
+    if !state.hooks.is_empty() {
+        fire_hooks(Arc::clone(&state));
+    }
+    clear_pages_on_update(Arc::clone(&state));
     let app = make_router(state);
 
     let addr = if args.host {
@@ -88,23 +128,71 @@ pub async fn run(ctx: Context, args: ServeArgs) -> Result<()> {
 
 #[tracing::instrument(level = "debug", skip_all)]
 fn make_router(state: Arc<AppState>) -> Router {
-    Router::new()
-        .route("/", get(handlers::index))
-        .route("/d/{*path}", get(handlers::index))
-        .route("/search", get(handlers::search))
-        .route("/about", get(handlers::about))
-        .route("/r/{*path}", get(handlers::recipe))
-        .route("/updates", get(handlers::sse_updates))
-        .route("/open_editor/{*path}", get(handlers::open_editor))
-        .route("/convert_modal", post(handlers::convert_popover))
+    let router = if state.text_only {
+        Router::new()
+            .route("/", get(handlers::text::index))
+            .route("/d/{*path}", get(handlers::text::index))
+            .route("/r/{*path}", get(handlers::text::recipe))
+    } else {
+        Router::new()
+            .route("/", get(handlers::index))
+            .route("/d/{*path}", get(handlers::index))
+            .route("/search", get(handlers::search))
+            .route("/zip", get(handlers::download_zip))
+            .route("/random", get(handlers::random))
+            .route("/about", get(handlers::about))
+            .route("/stats", get(handlers::stats))
+            .route("/r/{*path}", get(handlers::recipe))
+            .route("/rate/{*path}", post(handlers::rate))
+            .route("/graph/{*path}", get(handlers::graph))
+            .route("/updates", get(handlers::sse_updates))
+            .route("/open_editor/{*path}", get(handlers::open_editor))
+            .route("/convert_modal", post(handlers::convert_popover))
+            .route("/import", get(handlers::import_page))
+            .route("/import/preview", post(handlers::import_preview))
+            .route("/import/save", post(handlers::import_save))
+            .route("/cook/ws/{*path}", get(handlers::cook_ws))
+            .route(
+                "/cook/state/{*path}",
+                get(handlers::cook_state).post(handlers::set_cook_state),
+            )
+    };
+    let router = router
+        .route("/feed.xml", get(handlers::feed))
+        .route("/thumb/{width}/{*path}", get(handlers::thumbnail))
+        .route(openapi::OPENAPI_PATH, get(handlers::openapi))
         .nest_service(
             "/src",
             ServiceBuilder::new()
-                .layer(middleware::from_fn(filter_files))
-                .layer(middleware::from_fn(cook_mime_type))
+                .layer(middleware::from_fn_with_state(
+                    Arc::clone(&state),
+                    filter_files,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    Arc::clone(&state),
+                    cook_mime_type,
+                ))
                 .service(tower_http::services::ServeDir::new(&state.base_path)),
-        )
+        );
+    #[cfg(feature = "federation")]
+    let router = if state.federation.is_some() {
+        router.merge(federation::routes())
+    } else {
+        router
+    };
+    #[cfg(feature = "graphql")]
+    let router = router.merge(graphql::routes(Arc::clone(&state)));
+    // a span for each request, logged with its time from the debug level
+    let trace = TraceLayer::new_for_http()
+        .make_span_with(DefaultMakeSpan::new().level(Level::DEBUG))
+        .on_response(
+            DefaultOnResponse::new()
+                .level(Level::DEBUG)
+                .latency_unit(LatencyUnit::Millis),
+        );
+    router
         .fallback(handlers::static_file)
+        .layer(trace)
         .with_state(state)
 }
 
@@ -115,14 +203,85 @@ pub struct AppState {
     base_path: Utf8PathBuf,
     recipe_index: AsyncFsIndex,
     updates_stream: broadcast::Receiver<Update>,
+    cook_rooms: cook_sync::CookRooms,
     config: crate::config::Config,
+    hooks: crate::hooks::Hooks,
+    plugins: Vec<crate::plugins::PluginConfig>,
+    diet: crate::diet::DietData,
     disable_open_editor: bool,
+    disable_import: bool,
+    text_only: bool,
     editor_command: Option<Vec<String>>,
     editor_count: AtomicI32,
+    stats_facts: handlers::stats::FactsCache,
+    recipe_pages: handlers::recipe::PageCache,
+    #[cfg(feature = "federation")]
+    federation: Option<federation::Federation>,
 }
 
 type S = Arc<AppState>;
 
+/// Drops the rendered recipe pages when a recipe changes, the links between
+/// recipes may be different
+fn clear_pages_on_update(state: S) {
+    use broadcast::error::RecvError;
+
+    let mut updates = state.updates_stream.resubscribe();
+    tokio::spawn(async move {
+        while let Ok(_) | Err(RecvError::Lagged(_)) = updates.recv().await {
+            state.recipe_pages.clear();
+        }
+    });
+}
+
+/// Runs the hooks for the changes in the collection made outside chef too
+fn fire_hooks(state: S) {
+    use crate::hooks::{fire_in, Event};
+    use broadcast::error::RecvError;
+
+    let mut updates = state.updates_stream.resubscribe();
+    tokio::spawn(async move {
+        loop {
+            let update = match updates.recv().await {
+                Ok(update) => update,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let state = Arc::clone(&state);
+            tokio::task::spawn_blocking(move || {
+                let hooks = &state.hooks;
+                let base_path = &state.base_path;
+                match &update {
+                    Update::Added { path } | Update::Modified { path } => fire_in(
+                        hooks,
+                        base_path,
+                        Event::RecipeSaved {
+                            path: path.as_str(),
+                        },
+                    ),
+                    Update::Deleted { path } => fire_in(
+                        hooks,
+                        base_path,
+                        Event::RecipeDeleted {
+                            path: path.as_str(),
+                        },
+                    ),
+                    Update::Renamed { from, to } => {
+                        fire_in(
+                            hooks,
+                            base_path,
+                            Event::RecipeDeleted {
+                                path: from.as_str(),
+                            },
+                        );
+                        fire_in(hooks, base_path, Event::RecipeSaved { path: to.as_str() });
+                    }
+                }
+            });
+        }
+    });
+}
+
 #[tracing::instrument(level = "debug", skip_all)]
 fn build_state(ctx: Context, args: &ServeArgs) -> Result<S> {
     ctx.parser()?;
@@ -140,8 +299,25 @@ fn build_state(ctx: Context, args: &ServeArgs) -> Result<S> {
         .context("failed to index the recipes")?;
     let (recipe_index, updates) = AsyncFsIndex::new(complete_index, Arc::clone(&parser));
 
+    let diet = crate::diet::DietData::load(&config, &base_path)?;
+
+    #[cfg(feature = "federation")]
+    let federation = config
+        .federation
+        .as_ref()
+        .map(|c| federation::Federation::load(c, &base_path))
+        .transpose()
+        .context("failed to set up federation")?;
+    #[cfg(not(feature = "federation"))]
+    if config.federation.is_some() {
+        tracing::warn!(
+            "`federation` is configured but chef was built without the `federation` feature"
+        );
+    }
+
     let locales = make_locale_store();
     let templates = make_template_env(&locales);
+    let text_only = args.text_only || config.ui.text_only;
 
     Ok(Arc::new(AppState {
         templates,
@@ -150,10 +326,20 @@ fn build_state(ctx: Context, args: &ServeArgs) -> Result<S> {
         base_path,
         recipe_index,
         updates_stream: updates,
+        cook_rooms: Default::default(),
         config,
+        hooks: chef_config.hooks.clone(),
+        plugins: chef_config.plugins.clone(),
+        diet,
         editor_command: chef_config.editor().ok(),
         editor_count: 0.into(),
+        stats_facts: Default::default(),
+        recipe_pages: Default::default(),
         disable_open_editor: args.disable_open_editor,
+        disable_import: args.disable_import,
+        text_only,
+        #[cfg(feature = "federation")]
+        federation,
     }))
 }
 
@@ -179,6 +365,9 @@ fn make_template_env(locales: &LocaleStore) -> Environment<'static> {
     );
 
     env.add_test("empty", |v: Value| v.len().is_some_and(|l| l == 0));
+    env.add_test("error_marker", |v: &str| {
+        v.starts_with(crate::partial::ERROR_MARKER)
+    });
 
     env.add_function("youtube_videoid", |v: &str| {
         let re =
@@ -266,6 +455,13 @@ fn make_template_env(locales: &LocaleStore) -> Environment<'static> {
         },
     );
 
+    env.add_filter("approx", |v: &str| {
+        match cooklang_to_human::approx_value(v) {
+            Some(n) => Value::from(format!("≈{}", crate::util::fmt_number(n))),
+            None => Value::from(v),
+        }
+    });
+
     env.add_filter("zeroless_float", |v: f64| {
         if v.fract() == 0.0 {
             Value::from(v as i128)
@@ -277,20 +473,29 @@ fn make_template_env(locales: &LocaleStore) -> Environment<'static> {
     env
 }
 
-/// filters static files to only expose images and cook files and html files
-async fn filter_files(req: Request, next: Next) -> impl axum::response::IntoResponse {
+/// filters static files to only expose images, recipe files and html files
+async fn filter_files(
+    State(state): State<S>,
+    req: Request,
+    next: Next,
+) -> impl axum::response::IntoResponse {
     let path = req.uri().path();
     let (_, ext) = path.rsplit_once('.').ok_or(StatusCode::NOT_FOUND)?;
-    if ext == "html" || ext == "cook" || cooklang_fs::IMAGE_EXTENSIONS.contains(&ext) {
+    let is_recipe = state.config.discovery.extensions.iter().any(|e| e == ext);
+    if ext == "html" || is_recipe || cooklang_fs::IMAGE_EXTENSIONS.contains(&ext) {
         Ok(next.run(req).await)
     } else {
         Err(StatusCode::NOT_FOUND)
     }
 }
 
-/// sets the mime type for .cook files based on extension
-async fn cook_mime_type(req: Request, next: Next) -> Response {
-    let is_dot_cook = req.uri().path().ends_with(".cook");
+/// sets the mime type for recipe files based on extension
+async fn cook_mime_type(State(state): State<S>, req: Request, next: Next) -> Response {
+    let is_dot_cook = req
+        .uri()
+        .path()
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| state.config.discovery.extensions.iter().any(|e| e == ext));
     let mut res = next.run(req).await;
     if is_dot_cook {
         res.headers_mut().insert(
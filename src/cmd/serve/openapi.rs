@@ -0,0 +1,488 @@
+//! OpenAPI document of the server
+//!
+//! Most endpoints answer HTML for the web UI, but they are described anyway
+//! so clients know what is there. The query parameters come from the
+//! [`ApiParams`] of the query types the handlers extract.
+
+use serde_json::{json, Map, Value};
+
+use super::handlers::{
+    download::DownloadQuery, index::IndexQuery, random::RandomQuery, recipe::RecipeQuery,
+    search::SearchQuery, text::TextRecipeQuery,
+};
+
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// A query parameter
+pub struct Param {
+    pub name: &'static str,
+    /// JSON schema type
+    pub ty: &'static str,
+    pub description: &'static str,
+}
+
+/// Query parameters of a handler
+pub trait ApiParams {
+    const PARAMS: &'static [Param];
+}
+
+enum Body {
+    Html,
+    Json(fn() -> Value),
+    Atom,
+    EventStream,
+    File,
+    Zip,
+    Webp,
+    Empty,
+}
+
+struct Endpoint {
+    method: &'static str,
+    /// Axum route, `{*path}` and `{width}` are documented as path parameters
+    route: &'static str,
+    summary: &'static str,
+    params: &'static [Param],
+    request: Option<fn() -> Value>,
+    /// The request is a form instead of JSON
+    form: bool,
+    response: Body,
+}
+
+impl Endpoint {
+    fn get(route: &'static str, summary: &'static str, response: Body) -> Self {
+        Self {
+            method: "get",
+            route,
+            summary,
+            params: &[],
+            request: None,
+            form: false,
+            response,
+        }
+    }
+
+    fn post_form(route: &'static str, summary: &'static str, request: fn() -> Value) -> Self {
+        Self {
+            method: "post",
+            route,
+            summary,
+            params: &[],
+            request: Some(request),
+            form: true,
+            response: Body::Html,
+        }
+    }
+
+    fn params<P: ApiParams>(mut self) -> Self {
+        self.params = P::PARAMS;
+        self
+    }
+}
+
+struct WebfingerQuery;
+
+impl ApiParams for WebfingerQuery {
+    const PARAMS: &'static [Param] = &[Param {
+        name: "resource",
+        ty: "string",
+        description: "Account to look up, like `acct:recipes@example.com`",
+    }];
+}
+
+fn endpoints(text_only: bool, federation: bool) -> Vec<Endpoint> {
+    let mut endpoints = if text_only {
+        vec![
+            Endpoint::get("/", "Text only list of the top folder", Body::Html),
+            Endpoint::get("/d/{*path}", "Text only list of a folder", Body::Html),
+            Endpoint::get("/r/{*path}", "Text only recipe", Body::Html).params::<TextRecipeQuery>(),
+        ]
+    } else {
+        vec![
+            Endpoint::get("/", "Recipes and folders of the top folder", Body::Html)
+                .params::<IndexQuery>(),
+            Endpoint::get("/d/{*path}", "Recipes and folders of a folder", Body::Html)
+                .params::<IndexQuery>(),
+            Endpoint::get("/search", "Search recipes", Body::Html).params::<SearchQuery>(),
+            Endpoint::get(
+                "/zip",
+                "Recipes of a search with their images as a ZIP file",
+                Body::Zip,
+            )
+            .params::<DownloadQuery>(),
+            Endpoint::get(
+                "/random",
+                "Redirects to a random recipe matching the query",
+                Body::Html,
+            )
+            .params::<RandomQuery>(),
+            Endpoint::get("/about", "About and settings page", Body::Html),
+            Endpoint::get("/stats", "Statistics of the cook log", Body::Html),
+            Endpoint::get("/r/{*path}", "Recipe page", Body::Html).params::<RecipeQuery>(),
+            Endpoint::get(
+                "/graph/{*path}",
+                "Dependency graph of a recipe",
+                Body::Json(graph_schema),
+            ),
+            Endpoint::get(
+                "/updates",
+                "Changes in the collection as server sent events",
+                Body::EventStream,
+            ),
+            Endpoint::get(
+                "/open_editor/{*path}",
+                "Opens a recipe in the editor of the server",
+                Body::Html,
+            ),
+            Endpoint {
+                method: "post",
+                route: "/convert_modal",
+                summary: "Conversions of a quantity to other units",
+                params: &[],
+                request: Some(quantity_schema),
+                form: false,
+                response: Body::Html,
+            },
+            Endpoint::post_form(
+                "/rate/{*path}",
+                "Adds a rating and a note of a recipe and redirects to it",
+                rate_schema,
+            ),
+            Endpoint::get("/import", "Page to import a recipe", Body::Html),
+            Endpoint::post_form(
+                "/import/preview",
+                "Converts a recipe and shows it with its warnings",
+                import_schema,
+            ),
+            Endpoint::post_form(
+                "/import/save",
+                "Saves an imported recipe and redirects to it",
+                import_schema,
+            ),
+            Endpoint::get(
+                "/cook/ws/{*path}",
+                "WebSocket with the shared cooking session of a recipe",
+                Body::Json(cook_state_schema),
+            ),
+            Endpoint::get(
+                "/cook/state/{*path}",
+                "Shared cooking session of a recipe",
+                Body::Json(cook_state_schema),
+            ),
+            Endpoint {
+                method: "post",
+                route: "/cook/state/{*path}",
+                summary: "Changes the shared cooking session of a recipe",
+                params: &[],
+                request: Some(cook_state_schema),
+                form: false,
+                response: Body::Empty,
+            },
+        ]
+    };
+    endpoints.extend([
+        Endpoint::get("/feed.xml", "Atom feed of the recent recipes", Body::Atom),
+        Endpoint::get(
+            "/thumb/{width}/{*path}",
+            "An image resized to a thumbnail width, or a redirect to the original",
+            Body::Webp,
+        ),
+        Endpoint::get(
+            "/src/{*path}",
+            "Source file of a recipe or image",
+            Body::File,
+        ),
+        Endpoint::get(
+            OPENAPI_PATH,
+            "This document",
+            Body::Json(|| json!({ "type": "object" })),
+        ),
+    ]);
+    #[cfg(feature = "graphql")]
+    endpoints.extend([
+        Endpoint::get(
+            super::graphql::GRAPHQL_PATH,
+            "GraphQL playground",
+            Body::Html,
+        ),
+        Endpoint {
+            method: "post",
+            route: super::graphql::GRAPHQL_PATH,
+            summary: "GraphQL queries",
+            params: &[],
+            request: Some(|| json!({ "type": "object", "required": ["query"] })),
+            form: false,
+            response: Body::Json(|| json!({ "type": "object" })),
+        },
+    ]);
+    if federation {
+        let object = || json!({ "type": "object" });
+        endpoints.extend([
+            Endpoint::get(
+                "/.well-known/webfinger",
+                "WebFinger lookup of the account",
+                Body::Json(object),
+            )
+            .params::<WebfingerQuery>(),
+            Endpoint::get("/ap/actor", "ActivityPub actor", Body::Json(object)),
+            Endpoint::get("/ap/outbox", "Published recipes", Body::Json(object)),
+            Endpoint::get("/ap/followers", "Followers collection", Body::Json(object)),
+            Endpoint {
+                method: "post",
+                route: "/ap/inbox",
+                summary: "Signed ActivityPub activities",
+                params: &[],
+                request: Some(object),
+                form: false,
+                response: Body::Json(object),
+            },
+            Endpoint::get("/ap/r/{*path}", "Recipe as an article", Body::Json(object)),
+            Endpoint::get(
+                "/federation",
+                "Posts received from followed accounts",
+                Body::Html,
+            ),
+        ]);
+    }
+    endpoints
+}
+
+fn graph_schema() -> Value {
+    let id = json!({ "type": "string" });
+    json!({
+        "type": "object",
+        "required": ["nodes", "edges"],
+        "properties": {
+            "nodes": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["id", "kind", "label"],
+                    "properties": {
+                        "id": id,
+                        "kind": { "type": "string", "enum": ["section", "step", "recipe"] },
+                        "label": { "type": "string" },
+                        "section": id,
+                        "duration": { "type": "integer", "description": "Seconds of the timers of a step" },
+                    }
+                }
+            },
+            "edges": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["from", "to", "kind"],
+                    "properties": {
+                        "from": id,
+                        "to": id,
+                        "kind": { "type": "string", "enum": ["next", "uses", "recipe"] },
+                        "label": { "type": "string" },
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn cook_state_schema() -> Value {
+    let string = json!({ "type": "string" });
+    json!({
+        "type": "object",
+        "properties": {
+            "from": { "type": "string", "description": "Device that sent the state" },
+            "checked": {
+                "type": "array",
+                "items": string,
+                "description": "Ids of the checked ingredients and cookware",
+            },
+            "step": { "type": "string", "description": "`<section index>-<step number>`" },
+            "timer": {
+                "type": "object",
+                "properties": {
+                    "name": string,
+                    "state": { "type": "string", "enum": ["running", "paused"] },
+                    "end": { "type": "integer", "description": "Unix milliseconds" },
+                    "seconds": { "type": "integer", "description": "Left when paused" },
+                }
+            },
+        }
+    })
+}
+
+fn import_schema() -> Value {
+    let text = |description: &str| json!({ "type": "string", "description": description });
+    json!({
+        "type": "object",
+        "properties": {
+            "text": text("Cooklang, JSON-LD, the HTML of a page or a `chef share` link"),
+            "url": text("Where to download the recipe from, instead of `text`"),
+            "name": text("File name without extension, to save"),
+            "folder": text("Folder in the collection, to save"),
+        }
+    })
+}
+
+fn rate_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "rating": { "type": "string", "description": "From 1 to 5, empty for none" },
+            "text": { "type": "string", "description": "Note" },
+        }
+    })
+}
+
+fn quantity_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "A cooklang quantity, as serialized by the parser",
+        "required": ["value"],
+        "properties": {
+            "value": { "type": "object" },
+            "unit": { "type": "string", "nullable": true },
+        }
+    })
+}
+
+/// The OpenAPI 3 document for the routes the server has
+pub fn document(text_only: bool, federation: bool) -> Value {
+    let mut paths = Map::new();
+    for endpoint in endpoints(text_only, federation) {
+        let path = endpoint.route.replace("{*path}", "{path}");
+        let mut parameters: Vec<Value> = endpoint
+            .params
+            .iter()
+            .map(|p| {
+                json!({
+                    "name": p.name,
+                    "in": "query",
+                    "required": false,
+                    "description": p.description,
+                    "schema": { "type": p.ty },
+                })
+            })
+            .collect();
+        if path.contains("{path}") {
+            parameters.insert(
+                0,
+                json!({
+                    "name": "path",
+                    "in": "path",
+                    "required": true,
+                    "description": "Path relative to the collection, can have `/`",
+                    "schema": { "type": "string" },
+                }),
+            );
+        }
+        if path.contains("{width}") {
+            parameters.insert(
+                0,
+                json!({
+                    "name": "width",
+                    "in": "path",
+                    "required": true,
+                    "description": "Width of the thumbnail",
+                    "schema": { "type": "integer", "enum": crate::thumbnails::WIDTHS },
+                }),
+            );
+        }
+        let content = match endpoint.response {
+            Body::Html => json!({ "text/html": {} }),
+            Body::Json(schema) => json!({ "application/json": { "schema": schema() } }),
+            Body::Atom => json!({ "application/atom+xml": {} }),
+            Body::EventStream => json!({ "text/event-stream": {} }),
+            Body::File => json!({ "application/octet-stream": {} }),
+            Body::Zip => json!({ "application/zip": {} }),
+            Body::Webp => json!({ "image/webp": {} }),
+            Body::Empty => Value::Null,
+        };
+        let mut responses = json!({ "404": { "description": "Not found" } });
+        if content.is_null() {
+            responses["204"] = json!({ "description": "No content" });
+        } else {
+            responses["200"] = json!({ "description": "OK", "content": content });
+        }
+        let mut operation = json!({
+            "summary": endpoint.summary,
+            "responses": responses,
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if let Some(schema) = endpoint.request {
+            let content_type = if endpoint.form {
+                "application/x-www-form-urlencoded"
+            } else {
+                "application/json"
+            };
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { content_type: { "schema": schema() } },
+            });
+        }
+        paths
+            .entry(path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .unwrap()
+            .insert(endpoint.method.to_string(), operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "chef",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Web server of the cooklang chef CLI",
+        },
+        "paths": paths,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        let doc = document(false, true);
+        let paths = doc["paths"].as_object().unwrap();
+        assert!(paths.contains_key("/r/{path}"));
+        assert!(paths["/convert_modal"]["post"]["requestBody"].is_object());
+        assert_eq!(
+            paths["/r/{path}"]["get"]["parameters"][0]["in"],
+            json!("path")
+        );
+        assert!(paths.contains_key("/ap/inbox"));
+
+        let text = document(true, false);
+        assert!(!text["paths"].as_object().unwrap().contains_key("/search"));
+    }
+
+    /// Routes registered with `.route("...")` in a source file
+    fn registered_routes(source: &str) -> Vec<&str> {
+        source
+            .split(".route(")
+            .skip(1)
+            .filter_map(|rest| rest.trim_start().strip_prefix('"')?.split('"').next())
+            .collect()
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let documented = endpoints(false, true)
+            .into_iter()
+            .chain(endpoints(true, false))
+            .map(|e| e.route)
+            .collect::<Vec<_>>();
+        let sources = [include_str!("mod.rs"), include_str!("federation/mod.rs")];
+        let registered = sources
+            .into_iter()
+            .flat_map(registered_routes)
+            .collect::<Vec<_>>();
+        assert!(registered.contains(&"/thumb/{width}/{*path}"));
+        for route in registered {
+            assert!(documented.contains(&route), "{route} is not documented");
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::{
+    encryption,
+    share::{self, DEFAULT_LINK_PREFIX},
+    util::{resolve_recipe, set_clipboard},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct ShareArgs {
+    /// Recipe to share, can be fuzzy
+    recipe: String,
+    /// Upload the recipe to the paste service in the config
+    ///
+    /// The link is shorter, but the recipe is public to anyone with it.
+    #[arg(short, long)]
+    paste: bool,
+    /// Copy the link to the clipboard
+    #[arg(short, long)]
+    copy: bool,
+    /// Share the recipe even if it's private
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(ctx: &Context, args: ShareArgs) -> Result<()> {
+    let entry = resolve_recipe(ctx, &args.recipe)?;
+    let text = entry.read()?.into_text();
+    if !args.force && (entry.is_encrypted() || encryption::is_private(ctx, &text)?) {
+        bail!(
+            "'{}' is private, use `--force` to share it anyway",
+            entry.name()
+        );
+    }
+
+    let config = &ctx.chef_config.share;
+    let link = if args.paste {
+        let Some(url) = &config.paste_url else {
+            bail!("No paste service configured, set `share.paste_url` in the global config");
+        };
+        share::paste(url, &text)?
+    } else {
+        let prefix = config.link_prefix.as_deref().unwrap_or(DEFAULT_LINK_PREFIX);
+        share::encode(prefix, entry.name(), &text)?
+    };
+
+    if args.copy {
+        set_clipboard(link)?;
+        eprintln!("Link copied to the clipboard");
+    } else {
+        println!("{link}");
+    }
+    Ok(())
+}
@@ -4,23 +4,44 @@ use clap::{Args, CommandFactory, ValueEnum};
 use cooklang::{
     aisle::AisleConf,
     ingredient_list::IngredientList,
-    quantity::{GroupedQuantity, Quantity, Value},
+    quantity::{GroupedQuantity, Quantity},
     ScaledQuantity,
 };
 use serde::Serialize;
 
-use crate::{util::write_to_output, util::Input, Context};
+use crate::{
+    flatten, grocery, hooks,
+    pantry::Pantry,
+    plugins,
+    prep::Preparations,
+    prices::{self, format_cost, Costs},
+    scaling::{apply_strategies, base_quantities},
+    util::{resolve_recipe, write_to_output_or_clipboard, Input},
+    variables::parse_assignment,
+    Context,
+};
 
 #[derive(Debug, Args)]
 pub struct ShoppingListArgs {
-    /// Recipes to add with optional servings number
-    #[arg(value_name = "RECIPE[*<SERVINGS>]")]
+    /// Recipes to add with optional variants and servings number
+    ///
+    /// Like `Muffins@blueberry*12`, or `Wings@air-fryer@spicy` for more than
+    /// one variant.
+    #[arg(value_name = "RECIPE[@<VARIANT>...][*<SERVINGS>]")]
     recipes: Vec<String>,
 
+    /// Give a value to a variable of the recipes, like `fruit=blueberry`
+    #[arg(long, value_name = "NAME=VALUE", value_parser = parse_assignment)]
+    set: Vec<(String, String)>,
+
     /// Output file, none for stdout.
     #[arg(short, long)]
     output: Option<Utf8PathBuf>,
 
+    /// Copy the output to the clipboard instead
+    #[arg(long, conflicts_with = "output")]
+    to_clipboard: bool,
+
     /// Do not display categories
     #[arg(short, long)]
     plain: bool,
@@ -38,12 +59,34 @@ pub struct ShoppingListArgs {
     /// Load aisle conf file
     #[arg(short, long)]
     aisle: Option<Utf8PathBuf>,
+
+    /// Add the cost of each ingredient from the prices file and plugins
+    #[arg(long)]
+    cost: bool,
+
+    /// Export for a grocery store
+    ///
+    /// `csv`, `instacart` or a store in the `[grocery.stores]` config.
+    /// Quantities are rounded up to whole packages when the ingredient has
+    /// a package size.
+    #[arg(long, conflicts_with_all = ["format", "cost"])]
+    store: Option<String>,
+
+    /// Replace the referenced recipes with their ingredients
+    #[arg(long)]
+    flatten: bool,
+
+    /// Do not subtract what is in the pantry
+    #[arg(long)]
+    no_pantry: bool,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum OutputFormat {
     Human,
     Json,
+    /// Same as `--store csv`
+    Csv,
 }
 
 pub fn run(ctx: &Context, mut args: ShoppingListArgs) -> Result<()> {
@@ -81,6 +124,7 @@ pub fn run(ctx: &Context, mut args: ShoppingListArgs) -> Result<()> {
     let format = args.format.unwrap_or_else(|| match &args.output {
         Some(p) => match p.extension() {
             Some("json") => OutputFormat::Json,
+            Some("csv") => OutputFormat::Csv,
             _ => OutputFormat::Human,
         },
         None => OutputFormat::Human,
@@ -88,30 +132,77 @@ pub fn run(ctx: &Context, mut args: ShoppingListArgs) -> Result<()> {
 
     // retrieve, scale and merge ingredients
     let mut list = IngredientList::new();
-    for entry in args.recipes {
-        extract_ingredients(&entry, &mut list, ctx)?;
+    for entry in &args.recipes {
+        extract_ingredients(entry, &mut list, ctx, &args.set, args.flatten)?;
+    }
+    if !args.no_pantry {
+        list = Pantry::load(ctx)?.subtract(list, ctx.parser()?.converter());
     }
 
-    write_to_output(args.output.as_deref(), |mut w| {
-        match format {
-            OutputFormat::Human => {
-                let table = build_human_table(list, &aisle, args.plain);
-                write!(w, "{table}")?;
-            }
-            OutputFormat::Json => {
-                let value = build_json_value(list, &aisle, args.plain);
-                if args.pretty {
-                    serde_json::to_writer_pretty(w, &value)?;
-                } else {
-                    serde_json::to_writer(w, &value)?;
+    let store = match (&args.store, format) {
+        (Some(store), _) => Some(store.as_str()),
+        (None, OutputFormat::Csv) => Some("csv"),
+        _ => None,
+    };
+    if let Some(store) = store {
+        let adapter = grocery::adapter(store, &ctx.config.grocery)?;
+        let items = grocery::items(
+            list,
+            &aisle,
+            args.plain,
+            &ctx.config.grocery,
+            ctx.parser()?.converter(),
+        );
+        write_to_output_or_clipboard(args.output.as_deref(), args.to_clipboard, |mut w| {
+            adapter.write(&mut w, &items)
+        })?;
+    } else {
+        let costs = if args.cost {
+            let amounts = list
+                .iter()
+                .flat_map(|(name, q)| plugins::grouped_amounts(name, q))
+                .collect::<Vec<_>>();
+            Some(prices::costs(ctx, &amounts)?)
+        } else {
+            None
+        };
+
+        write_to_output_or_clipboard(args.output.as_deref(), args.to_clipboard, |mut w| {
+            match format {
+                OutputFormat::Human => {
+                    let table = build_human_table(list, &aisle, args.plain, costs.as_ref());
+                    write!(w, "{table}")?;
+                }
+                OutputFormat::Json => {
+                    let value = build_json_value(list, &aisle, args.plain, costs.as_ref());
+                    if args.pretty {
+                        serde_json::to_writer_pretty(w, &value)?;
+                    } else {
+                        serde_json::to_writer(w, &value)?;
+                    }
                 }
+                OutputFormat::Csv => unreachable!("exported as a store"),
             }
-        }
-        Ok(())
-    })
+            Ok(())
+        })?;
+    }
+
+    hooks::fire(
+        ctx,
+        hooks::Event::ShoppingList {
+            recipes: &args.recipes,
+        },
+    );
+    Ok(())
 }
 
-fn extract_ingredients(entry: &str, list: &mut IngredientList, ctx: &Context) -> Result<()> {
+fn extract_ingredients(
+    entry: &str,
+    list: &mut IngredientList,
+    ctx: &Context,
+    set: &[(String, String)],
+    flatten: bool,
+) -> Result<()> {
     let converter = ctx.parser()?.converter();
 
     // split into name and servings
@@ -131,25 +222,41 @@ fn extract_ingredients(entry: &str, list: &mut IngredientList, ctx: &Context) ->
         })
         .unwrap_or((entry, None));
 
+    // split the variants, if they don't look like part of a path
+    let (name, variants) = match name.split_once('@') {
+        Some((name, variants)) if !variants.is_empty() && !variants.contains(['/', '\\']) => {
+            (name, variants.split('@').map(str::to_string).collect())
+        }
+        _ => (name, Vec::new()),
+    };
+
     // Resolve and parse the recipe
     let input = {
-        let entry = ctx.recipe_index.resolve(name, None)?;
+        let entry = resolve_recipe(ctx, name)?;
         Input::File {
             entry,
             override_name: None,
         }
+        .with_variables(&variants, set)?
     };
     let recipe = input.parse(ctx)?;
 
     // Scale
     let recipe = if let Some(servings) = servings {
-        recipe.scale(servings, converter)
+        let base = base_quantities(&recipe);
+        let mut scaled = recipe.scale(servings, converter);
+        apply_strategies(&mut scaled, &base, &ctx.config.scaling);
+        scaled
     } else {
         recipe.default_scale()
     };
 
     // Add ingredients to the list
-    list.add_recipe(&recipe, converter);
+    if flatten {
+        flatten::add_flattened(ctx, list, &recipe, input.path())?;
+    } else {
+        Preparations::new(&ctx.config.prep).add_recipe(list, &recipe, converter);
+    }
 
     Ok(())
 }
@@ -164,36 +271,69 @@ fn grouped_qty_fmt(qty: &GroupedQuantity, row: &mut tabular::Row) {
 }
 
 fn quantity_fmt(qty: &Quantity) -> String {
+    use cooklang_to_human::{DefaultQuantityFormatter, QuantityFormatter};
     use yansi::Paint;
 
-    if let Some(unit) = qty.unit() {
-        format!("{} {}", qty.value(), unit.italic())
+    let formatter = DefaultQuantityFormatter::default();
+    let value = formatter.value(qty);
+    if let Some(unit) = formatter.unit(qty) {
+        format!("{value} {}", unit.italic())
     } else {
-        format!("{}", qty.value())
+        value
     }
 }
 
-fn build_human_table(list: IngredientList, aisle: &AisleConf, plain: bool) -> tabular::Table {
+fn build_human_table(
+    list: IngredientList,
+    aisle: &AisleConf,
+    plain: bool,
+    costs: Option<&Costs>,
+) -> tabular::Table {
     use yansi::Paint;
 
-    let mut table = tabular::Table::new("{:<} {:<}");
+    let row = |igr: String, q: GroupedQuantity| {
+        let mut row = tabular::Row::new();
+        if let Some(costs) = costs {
+            let cost = costs.ingredients.get(&igr).copied().flatten();
+            row.add_cell(&igr);
+            grouped_qty_fmt(&q, &mut row);
+            match cost {
+                Some(cost) => row.add_cell(format_cost(cost, costs.currency.as_deref())),
+                None => row.add_ansi_cell("-".dim()),
+            };
+        } else {
+            row.add_cell(igr);
+            grouped_qty_fmt(&q, &mut row);
+        }
+        row
+    };
+
+    let mut table = if costs.is_some() {
+        tabular::Table::new("{:<} {:<} {:>}")
+    } else {
+        tabular::Table::new("{:<} {:<}")
+    };
     if plain {
         for (igr, q) in list {
-            let mut row = tabular::Row::new().with_cell(igr);
-            grouped_qty_fmt(&q, &mut row);
-            table.add_row(row);
+            table.add_row(row(igr, q));
         }
     } else {
         let categories = list.categorize(aisle);
         for (cat, items) in categories {
             table.add_heading(format!("[{}]", cat.green()));
             for (igr, q) in items {
-                let mut row = tabular::Row::new().with_cell(igr);
-                grouped_qty_fmt(&q, &mut row);
-                table.add_row(row);
+                table.add_row(row(igr, q));
             }
         }
     }
+    if let Some(costs) = costs {
+        table.add_row(
+            tabular::Row::new()
+                .with_ansi_cell("Total".bold())
+                .with_cell("")
+                .with_cell(format_cost(costs.total(), costs.currency.as_deref())),
+        );
+    }
     table
 }
 
@@ -201,32 +341,20 @@ fn build_json_value<'a>(
     list: IngredientList,
     aisle: &'a AisleConf<'a>,
     plain: bool,
+    costs: Option<&Costs>,
 ) -> serde_json::Value {
-    #[derive(Serialize)]
-    struct Quantity {
-        value: Value,
-        unit: Option<String>,
-    }
-    impl From<cooklang::quantity::Quantity> for Quantity {
-        fn from(qty: cooklang::quantity::Quantity) -> Self {
-            let unit = qty.unit().map(|s| s.to_owned());
-            let value = qty.value().clone();
-            Self { value, unit }
-        }
-    }
     #[derive(Serialize)]
     struct Ingredient {
         name: String,
         quantity: Vec<ScaledQuantity>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cost: Option<f64>,
     }
-    impl From<(String, GroupedQuantity)> for Ingredient {
-        fn from((name, qty): (String, GroupedQuantity)) -> Self {
-            Ingredient {
-                name,
-                quantity: qty.into_vec(),
-            }
-        }
-    }
+    let ingredient = |(name, qty): (String, GroupedQuantity)| Ingredient {
+        cost: costs.and_then(|c| c.ingredients.get(&name).copied().flatten()),
+        name,
+        quantity: qty.into_vec(),
+    };
     #[derive(Serialize)]
     struct Category {
         category: String,
@@ -234,17 +362,55 @@ fn build_json_value<'a>(
     }
 
     if plain {
-        serde_json::to_value(list.into_iter().map(Ingredient::from).collect::<Vec<_>>()).unwrap()
+        serde_json::to_value(list.into_iter().map(ingredient).collect::<Vec<_>>()).unwrap()
     } else {
         serde_json::to_value(
             list.categorize(aisle)
                 .into_iter()
                 .map(|(category, items)| Category {
                     category,
-                    items: items.into_iter().map(Ingredient::from).collect(),
+                    items: items.into_iter().map(ingredient).collect(),
                 })
                 .collect::<Vec<_>>(),
         )
         .unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cooklang_to_human::{DefaultQuantityFormatter, QuantityFormatter};
+
+    use super::*;
+
+    #[test]
+    fn ranges_add_up() {
+        let parser = cooklang::CooklangParser::default();
+        let recipe = parser
+            .parse("Mix @flour{1-2%cups} and @flour{1%cups}. Add @salt{~1%tsp} and @salt{1%tsp}.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        let mut list = IngredientList::new();
+        list.add_recipe(&recipe, parser.converter());
+        let formatter = DefaultQuantityFormatter::default();
+        let quantities = |name: &str| {
+            let (_, grouped) = list.iter().find(|(n, _)| *n == name).unwrap();
+            grouped
+                .iter()
+                .map(|q| {
+                    format!(
+                        "{} {}",
+                        formatter.value(q),
+                        formatter.unit(q).unwrap_or_default()
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(quantities("flour"), ["2–3 c"]);
+        // approximate quantities are apart
+        let salt = quantities("salt");
+        assert_eq!(salt.len(), 2);
+        assert!(salt.contains(&"≈1 tsp".to_string()));
+    }
+}
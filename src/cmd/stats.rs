@@ -0,0 +1,292 @@
+use std::collections::{HashMap, HashSet};
+
+use anstream::{print, println};
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+use crate::{
+    cook_log::{recipe_key, CookLog},
+    pantry::Date,
+    usage,
+    util::{all_recipes, parse_all},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    /// Number of entries shown in each ranking
+    #[arg(short = 'n', long, default_value_t = 10)]
+    top: usize,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+
+    /// Your own use of chef instead of the collection
+    ///
+    /// The commands and recipes used, recorded in a local file with
+    /// `usage_stats = true` in the global config.
+    #[arg(long = "self")]
+    own_usage: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Stats {
+    recipes: usize,
+    with_errors: usize,
+    with_warnings: usize,
+    total_warnings: usize,
+    /// Average total time in minutes of the recipes with a time
+    average_time: Option<f64>,
+    ingredients: Vec<(String, usize)>,
+    tags: Vec<(String, usize)>,
+    units: Vec<(String, usize)>,
+    /// Recipes not in the cook log, by their path without extension
+    never_cooked: Vec<String>,
+}
+
+pub fn run(ctx: &Context, args: StatsArgs) -> Result<()> {
+    if args.own_usage {
+        return run_usage(ctx, args);
+    }
+    if !ctx.is_collection {
+        bail!("`stats` needs to run inside a collection");
+    }
+
+    let converter = ctx.parser()?.converter();
+    let mut stats = Stats::default();
+    let mut ingredients = HashMap::<String, usize>::new();
+    let mut tags = HashMap::<String, usize>::new();
+    let mut units = HashMap::<String, usize>::new();
+    let mut times = Vec::new();
+    let log = CookLog::load(&ctx.base_path)?;
+
+    let entries = parse_all(ctx, all_recipes(ctx)?.collect())?;
+    for entry in entries {
+        stats.recipes += 1;
+        let key = recipe_key(entry.path(), &ctx.base_path);
+        if log.last_cooked(&key).is_none() {
+            stats.never_cooked.push(key);
+        }
+
+        let parsed = entry.parsed(ctx)?;
+        let report = parsed.report();
+        if report.has_errors() {
+            stats.with_errors += 1;
+        }
+        if report.has_warnings() {
+            stats.with_warnings += 1;
+            stats.total_warnings += report.warnings().count();
+        }
+        let Some(recipe) = parsed.valid_output() else {
+            continue;
+        };
+
+        // count each ingredient once per recipe, ignoring references
+        let mut seen = HashSet::new();
+        for igr in &recipe.ingredients {
+            if igr.relation.references_to().is_some() {
+                continue;
+            }
+            let name = igr.name.to_lowercase();
+            if seen.insert(name.clone()) {
+                *ingredients.entry(name).or_default() += 1;
+            }
+            if let Some(unit) = igr.quantity.as_ref().and_then(|q| q.unit()) {
+                *units.entry(unit.to_string()).or_default() += 1;
+            }
+        }
+        for tag in recipe.metadata.tags().unwrap_or_default() {
+            *tags.entry(tag.to_string()).or_default() += 1;
+        }
+        if let Some(time) = recipe.metadata.time(converter) {
+            times.push(time.total());
+        }
+    }
+
+    if !times.is_empty() {
+        stats.average_time =
+            Some(times.iter().map(|&t| t as f64).sum::<f64>() / times.len() as f64);
+    }
+    stats.ingredients = ranking(ingredients, args.top);
+    stats.tags = ranking(tags, args.top);
+    stats.units = ranking(units, args.top);
+    stats.never_cooked.sort_unstable();
+
+    match args.format {
+        OutputFormat::Human => print_human(&stats, args.top),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(anstream::stdout().lock(), &stats)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Serialize)]
+struct UsageStats {
+    runs: usize,
+    failed: usize,
+    since: Option<Date>,
+    commands: Vec<CommandStats>,
+    recipes: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Serialize)]
+struct CommandStats {
+    command: String,
+    runs: usize,
+    average_ms: u64,
+}
+
+fn run_usage(ctx: &Context, args: StatsArgs) -> Result<()> {
+    let records = usage::load()?;
+    if records.is_empty() && !ctx.chef_config.usage_stats {
+        bail!(
+            "No usage recorded. Set `usage_stats = true` in the global config to record it in {}",
+            usage::usage_file()?
+        );
+    }
+
+    let mut stats = UsageStats {
+        runs: records.len(),
+        failed: records.iter().filter(|r| !r.ok).count(),
+        since: records.iter().map(|r| r.date).min(),
+        ..Default::default()
+    };
+    let mut commands = HashMap::<String, (usize, u64)>::new();
+    let mut recipes = HashMap::<String, usize>::new();
+    for record in records {
+        let (runs, ms) = commands.entry(record.command).or_default();
+        *runs += 1;
+        *ms += record.ms;
+        for recipe in record.recipes {
+            *recipes.entry(recipe).or_default() += 1;
+        }
+    }
+    let runs = commands
+        .iter()
+        .map(|(name, (runs, _))| (name.clone(), *runs))
+        .collect();
+    stats.commands = ranking(runs, args.top)
+        .into_iter()
+        .map(|(command, runs)| {
+            let average_ms = commands[&command].1 / runs as u64;
+            CommandStats {
+                command,
+                runs,
+                average_ms,
+            }
+        })
+        .collect();
+    stats.recipes = ranking(recipes, args.top);
+
+    match args.format {
+        OutputFormat::Human => print_usage(&stats),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(anstream::stdout().lock(), &stats)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_usage(stats: &UsageStats) {
+    use tabular::{row, table};
+    use yansi::Paint;
+
+    let mut table = table!("{:>}  {:<}", row!("Runs", stats.runs));
+    table.add_row(row!("Failed", stats.failed));
+    let since = stats
+        .since
+        .map(|d| d.to_string())
+        .unwrap_or_else(|| "-".into());
+    table.add_row(row!("Since", since));
+    print!("{table}");
+
+    println!("\n{}", "Commands".bold().underline());
+    if stats.commands.is_empty() {
+        println!("{}", "none".dim());
+    } else {
+        let mut table = tabular::Table::new("{:>}  {:<}  {:>}");
+        for c in &stats.commands {
+            table.add_row(row!(
+                c.runs,
+                &c.command,
+                format!("{} ms", c.average_ms).dim()
+            ));
+        }
+        print!("{table}");
+    }
+
+    println!("\n{}", "Most used recipes".bold().underline());
+    if stats.recipes.is_empty() {
+        println!("{}", "none".dim());
+    } else {
+        let mut table = tabular::Table::new("{:>}  {:<}");
+        for (name, count) in &stats.recipes {
+            table.add_row(row!(count, name));
+        }
+        print!("{table}");
+    }
+}
+
+/// Most common first, then alphabetically
+fn ranking(counts: HashMap<String, usize>, top: usize) -> Vec<(String, usize)> {
+    let mut v = counts.into_iter().collect::<Vec<_>>();
+    v.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    v.truncate(top);
+    v
+}
+
+fn print_human(stats: &Stats, top: usize) {
+    use tabular::{row, table};
+    use yansi::Paint;
+
+    let mut table = table!("{:>}  {:<}", row!("Recipes", stats.recipes));
+    table.add_row(row!("With errors", stats.with_errors));
+    table.add_row(row!("With warnings", stats.with_warnings));
+    table.add_row(row!("Total warnings", stats.total_warnings));
+    let time = stats
+        .average_time
+        .map(|t| format!("{t:.0} min"))
+        .unwrap_or_else(|| "-".into());
+    table.add_row(row!("Average time", time));
+    table.add_row(row!("Never cooked", stats.never_cooked.len()));
+    print!("{table}");
+
+    for (title, list) in [
+        ("Most used ingredients", &stats.ingredients),
+        ("Tags", &stats.tags),
+        ("Units", &stats.units),
+    ] {
+        println!("\n{}", title.bold().underline());
+        if list.is_empty() {
+            println!("{}", "none".dim());
+            continue;
+        }
+        let mut table = tabular::Table::new("{:>}  {:<}");
+        for (name, count) in list {
+            table.add_row(row!(count, name));
+        }
+        print!("{table}");
+    }
+
+    println!("\n{}", "Never cooked".bold().underline());
+    if stats.never_cooked.is_empty() {
+        println!("{}", "none".dim());
+        return;
+    }
+    for name in stats.never_cooked.iter().take(top) {
+        println!("{name}");
+    }
+    if stats.never_cooked.len() > top {
+        let more = format!("and {} more", stats.never_cooked.len() - top);
+        println!("{}", more.dim());
+    }
+}
@@ -0,0 +1,110 @@
+use anstream::{print, println};
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use yansi::Paint;
+
+use crate::{scaling::parse_amount, substitutions::Substitutions, util::fmt_number, Context};
+
+#[derive(Debug, Args)]
+pub struct SubstitutionsArgs {
+    /// Ingredient to substitute, none to list the known ingredients
+    ingredient: Option<String>,
+
+    /// Amount of the ingredient, like `250ml` or `2`, to get the amounts of
+    /// the substitutes
+    #[arg(value_parser = parse_amount)]
+    amount: Option<(f64, Option<String>)>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+pub fn run(ctx: &Context, args: SubstitutionsArgs) -> Result<()> {
+    let subs = Substitutions::load(ctx)?;
+
+    let Some(ingredient) = &args.ingredient else {
+        let mut names = subs.names().collect::<Vec<_>>();
+        names.sort_unstable();
+        match args.format {
+            OutputFormat::Human => names.iter().for_each(|n| println!("{n}")),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&names)?),
+        }
+        return Ok(());
+    };
+
+    let found = subs.get(ingredient);
+    if found.is_empty() {
+        bail!("No known substitutes for '{ingredient}'");
+    }
+
+    let (value, unit) = match &args.amount {
+        Some((value, unit)) => (Some(*value), unit.as_deref()),
+        None => (None, None),
+    };
+
+    match args.format {
+        OutputFormat::Human => {
+            use tabular::{Row, Table};
+
+            let original = match (value, unit) {
+                (Some(v), Some(u)) => format!("{} {u} {ingredient}", fmt_number(v)),
+                (Some(v), None) => format!("{} {ingredient}", fmt_number(v)),
+                (None, _) => ingredient.clone(),
+            };
+            println!("{}", original.bold());
+            let mut table = Table::new("  {:<}  {:<}");
+            for sub in found {
+                table.add_row(
+                    Row::new()
+                        .with_cell(sub.describe(value, unit))
+                        .with_ansi_cell(sub.note.as_deref().unwrap_or("").dim()),
+                );
+            }
+            print!("{table}");
+        }
+        OutputFormat::Json => {
+            #[derive(serde::Serialize)]
+            struct Entry<'a> {
+                #[serde(flatten)]
+                sub: &'a crate::substitutions::Substitute,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                amounts: Option<Vec<Amount<'a>>>,
+            }
+            #[derive(serde::Serialize)]
+            struct Amount<'a> {
+                name: &'a str,
+                quantity: f64,
+                unit: Option<String>,
+            }
+
+            let entries = found
+                .iter()
+                .map(|sub| Entry {
+                    sub,
+                    amounts: value.map(|v| {
+                        sub.with
+                            .iter()
+                            .map(|(name, part)| {
+                                let (quantity, unit) = part.amount(v, unit);
+                                Amount {
+                                    name,
+                                    quantity,
+                                    unit,
+                                }
+                            })
+                            .collect()
+                    }),
+                })
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::{
+    config::{global_store, CHEF_CONFIG_FILE},
+    remote::{self, Remote},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct SyncArgs {
+    /// Remotes to fetch, all if empty
+    #[arg(value_name = "NAME")]
+    names: Vec<String>,
+    /// Add the remote, or change its URL, before fetching it
+    ///
+    /// It's saved in the global config. Git repositories are detected by
+    /// the URL, anything else is read as an HTTP index.
+    #[arg(long, value_name = "URL")]
+    url: Option<String>,
+}
+
+pub fn run(ctx: &Context, args: SyncArgs) -> Result<()> {
+    let mut remotes = ctx.chef_config.remotes.clone();
+
+    if let Some(url) = args.url {
+        let [name] = args.names.as_slice() else {
+            bail!("`--url` needs exactly one remote name");
+        };
+        remote::dir(name)?; // validate the name before saving it
+        remotes.insert(name.clone(), Remote { url, kind: None });
+        let mut global = ctx.chef_config.clone();
+        global.remotes = remotes.clone();
+        global_store(CHEF_CONFIG_FILE, &global)?;
+    }
+
+    let names = if args.names.is_empty() {
+        remotes.keys().cloned().collect()
+    } else {
+        args.names
+    };
+    if names.is_empty() {
+        tracing::warn!("No remotes configured, add one with `chef sync <NAME> --url <URL>`");
+        return Ok(());
+    }
+
+    for name in &names {
+        let Some(remote) = remotes.get(name) else {
+            bail!("Unknown remote '{name}', add it with `chef sync {name} --url <URL>`");
+        };
+        eprintln!("Syncing '{name}' from {}", remote.url);
+        let dir = remote::sync(name, remote)?;
+        println!("{name}: {dir}");
+    }
+    Ok(())
+}
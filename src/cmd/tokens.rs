@@ -0,0 +1,146 @@
+use std::io::Read;
+
+use anyhow::{Context as _, Result};
+use camino::Utf8PathBuf;
+use clap::Args;
+use cooklang::parser::{Event, PullParser};
+use serde::Serialize;
+
+use crate::{diagnostics::Span, source_edit::split_metadata, util::write_to_output, Context};
+
+#[derive(Debug, Args)]
+pub struct TokensArgs {
+    /// Input recipe file, none for stdin
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    recipe: Option<Utf8PathBuf>,
+
+    /// Output file, none for stdout
+    #[arg(short, long)]
+    output: Option<Utf8PathBuf>,
+
+    /// Pretty print the JSON
+    #[arg(long)]
+    pretty: bool,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum TokenKind {
+    FrontMatter,
+    MetadataKey,
+    MetadataValue,
+    Section,
+    Ingredient,
+    Cookware,
+    Timer,
+    Comment,
+}
+
+#[derive(Debug, Serialize)]
+struct Token {
+    kind: TokenKind,
+    #[serde(flatten)]
+    span: Span,
+}
+
+pub fn run(ctx: &Context, args: TokensArgs) -> Result<()> {
+    let text = match &args.recipe {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read recipe: {path}"))?,
+        None => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .context("Failed to read stdin")?;
+            buf
+        }
+    };
+
+    let tokens = tokens(&text, ctx.parser()?.extensions());
+
+    write_to_output(args.output.as_deref(), |w| {
+        if args.pretty {
+            serde_json::to_writer_pretty(w, &tokens)?;
+        } else {
+            serde_json::to_writer(w, &tokens)?;
+        }
+        Ok(())
+    })
+}
+
+/// Highlighting tokens of a recipe, sorted by position
+///
+/// Plain text is not a token. Tokens never overlap.
+fn tokens(text: &str, extensions: cooklang::Extensions) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut push = |kind, range: std::ops::Range<usize>| {
+        if !range.is_empty() {
+            tokens.push(Token {
+                kind,
+                span: Span::new(text, range.start, range.end),
+            })
+        }
+    };
+
+    for ev in PullParser::new(text, extensions) {
+        match ev {
+            Event::YAMLFrontMatter(t) => push(TokenKind::FrontMatter, t.span().range()),
+            Event::Metadata { key, value } => {
+                push(TokenKind::MetadataKey, key.span().range());
+                push(TokenKind::MetadataValue, value.span().range());
+            }
+            Event::Section { name: Some(name) } => push(TokenKind::Section, name.span().range()),
+            Event::Ingredient(c) => push(TokenKind::Ingredient, c.span().range()),
+            Event::Cookware(c) => push(TokenKind::Cookware, c.span().range()),
+            Event::Timer(c) => push(TokenKind::Timer, c.span().range()),
+            _ => {}
+        }
+    }
+    for range in comments(text) {
+        push(TokenKind::Comment, range);
+    }
+
+    tokens.sort_by_key(|t| t.span.start);
+    tokens
+}
+
+/// Finds the line (`--`) and block (`[- -]`) comments
+///
+/// The parser does not emit events for them.
+fn comments(text: &str) -> Vec<std::ops::Range<usize>> {
+    let mut found = Vec::new();
+    // skip the front-matter, old style metadata can't have comments either
+    let mut i = split_metadata(text).0.len();
+    while i < text.len() {
+        let rest = &text[i..];
+        if let Some(escaped) = rest.strip_prefix('\\') {
+            i += 1 + escaped.chars().next().map(char::len_utf8).unwrap_or(0);
+        } else if rest.starts_with("--") {
+            let end = i + rest.find('\n').unwrap_or(rest.len());
+            found.push(i..end);
+            i = end;
+        } else if rest.starts_with("[-") {
+            let end = i + rest.find("-]").map(|p| p + 2).unwrap_or(rest.len());
+            found.push(i..end);
+            i = end;
+        } else {
+            i += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_comments() {
+        let text = "---\na: b\n---\nMix -- line\n[- block -] \\-- no";
+        let found = comments(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect::<Vec<_>>();
+        assert_eq!(found, ["-- line", "[- block -]"]);
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{bail, Context as _, Result};
+use camino::Utf8PathBuf;
+use clap::Args;
+
+use crate::{
+    cmd::search::{matches, parse_term},
+    diet::DietData,
+    locale::number_format,
+    recipe_zip::{self, ZipRecipe},
+    util::{all_recipes, meta_name, parse_all},
+    Context,
+};
+
+#[derive(Debug, Args)]
+pub struct ZipArgs {
+    /// Search terms to choose the recipes, none for all of them
+    ///
+    /// Same terms as `chef search`, like `tag:dinner-party`.
+    #[arg(value_name = "TERM")]
+    terms: Vec<String>,
+
+    /// ZIP file to write
+    #[arg(short, long, default_value = "recipes.zip")]
+    out: Utf8PathBuf,
+
+    /// Add an HTML page of every recipe
+    #[arg(long)]
+    html: bool,
+
+    /// Overwrite the ZIP file if it exists
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(ctx: &Context, args: ZipArgs) -> Result<()> {
+    if !ctx.is_collection {
+        bail!("`zip` needs to run inside a collection");
+    }
+    if args.out.exists() && !args.force {
+        bail!("{} already exists, use `--force` to overwrite it", args.out);
+    }
+
+    let diet = DietData::load(&ctx.config, &ctx.base_path)?;
+    let terms = args
+        .terms
+        .iter()
+        .map(|t| parse_term(t, &diet))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut entries = parse_all(ctx, all_recipes(ctx)?.collect())?;
+    entries.sort_unstable_by(|a, b| a.path().cmp(b.path()));
+
+    let mut recipes = Vec::new();
+    for entry in &entries {
+        let Some(recipe) = entry.parsed(ctx)?.valid_output() else {
+            tracing::warn!("Skipping '{}': the recipe has errors", entry.path());
+            continue;
+        };
+        let name = meta_name(&recipe.metadata).unwrap_or(entry.name());
+        if !matches(&terms, &diet, name, recipe) {
+            continue;
+        }
+        if !recipe_zip::can_archive(entry, &recipe.metadata) {
+            tracing::warn!("Skipping '{}': the recipe is private", entry.path());
+            continue;
+        }
+        let html = if args.html {
+            let converter = ctx.parser()?.converter();
+            // the cached recipe is borrowed, scaling needs its own
+            let scaled = entry.parse(ctx)?.unwrap_output().default_scale();
+            let number_format = number_format(&scaled.metadata, &ctx.config);
+            Some(recipe_zip::render_html(
+                &scaled,
+                name,
+                converter,
+                number_format,
+            )?)
+        } else {
+            None
+        };
+        recipes.push(ZipRecipe { entry, html });
+    }
+    if recipes.is_empty() {
+        bail!("No recipe matches");
+    }
+
+    let file = std::fs::File::create(&args.out)
+        .with_context(|| format!("Failed to create {}", args.out))?;
+    let (_, files) = recipe_zip::write(file, &ctx.base_path, &recipes)?;
+    eprintln!(
+        "Saved {} recipes ({files} files) to {}",
+        recipes.len(),
+        args.out
+    );
+    Ok(())
+}
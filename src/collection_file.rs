@@ -0,0 +1,292 @@
+//! `collection.toml` files
+//!
+//! Any directory of a collection can have a `collection.toml` file. The
+//! metadata in it is inherited by all the recipes in that directory and its
+//! subdirectories that don't set the key themselves. Files in inner
+//! directories take precedence over outer ones.
+//!
+//! ```toml
+//! [metadata]
+//! author = "Grandma"
+//! servings = 4
+//! tags = ["family"]
+//! ```
+//!
+//! They can also have a schema for the metadata of the recipes, checked by
+//! `chef check`. It's inherited and merged the same way, by key:
+//!
+//! ```toml
+//! [schema.difficulty]
+//! required = true
+//! values = ["easy", "medium", "hard"]
+//!
+//! [schema.servings]
+//! type = "number"
+//! ```
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context as _, Result};
+use camino::Utf8Path;
+use cooklang::{Metadata, ScalableRecipe};
+use serde::{Deserialize, Serialize};
+
+pub const COLLECTION_FILE: &str = "collection.toml";
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CollectionFile {
+    /// Metadata inherited by the recipes
+    pub metadata: toml::Table,
+    /// Rules for the metadata of the recipes
+    pub schema: BTreeMap<String, KeySchema>,
+}
+
+/// Rules for a metadata key
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeySchema {
+    /// Every recipe has to set it, or inherit it
+    pub required: bool,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<ValueType>,
+    /// Allowed values, for lists each item has to be one of them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    String,
+    Number,
+    Bool,
+    /// A YAML list or comma separated values
+    List,
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ValueType::String => "a string",
+            ValueType::Number => "a number",
+            ValueType::Bool => "true or false",
+            ValueType::List => "a list",
+        };
+        f.write_str(s)
+    }
+}
+
+impl CollectionFile {
+    /// Reads the `collection.toml` of a dir, if any
+    pub fn read(dir: &Utf8Path) -> Result<Option<Self>> {
+        let path = dir.join(COLLECTION_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        tracing::trace!("loading collection file {path}");
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read collection file: {path}"))?;
+        let file =
+            toml::from_str(&content).with_context(|| format!("Bad collection file: {path}"))?;
+        Ok(Some(file))
+    }
+}
+
+/// The `collection.toml` files that apply to a dir, outer ones first
+fn collection_files(dir: &Utf8Path, base_path: &Utf8Path) -> Vec<CollectionFile> {
+    let mut dirs = dir
+        .ancestors()
+        .take_while(|dir| dir.starts_with(base_path))
+        .collect::<Vec<_>>();
+    dirs.reverse();
+    dirs.into_iter()
+        .filter_map(|dir| match CollectionFile::read(dir) {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::warn!("{e:#}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Metadata a recipe inherits from the `collection.toml` files
+///
+/// Outer directories are merged first, so inner ones override their keys.
+pub fn inherited_metadata(recipe_path: &Utf8Path, base_path: &Utf8Path) -> serde_yaml::Mapping {
+    match recipe_path.parent() {
+        Some(dir) => dir_metadata(dir, base_path),
+        None => serde_yaml::Mapping::new(),
+    }
+}
+
+/// Metadata the recipes in a dir inherit, see [`inherited_metadata`]
+pub fn dir_metadata(dir: &Utf8Path, base_path: &Utf8Path) -> serde_yaml::Mapping {
+    let mut merged = serde_yaml::Mapping::new();
+    for file in collection_files(dir, base_path) {
+        for (key, value) in file.metadata {
+            match serde_yaml::to_value(&value) {
+                Ok(value) => {
+                    merged.insert(key.into(), value);
+                }
+                Err(e) => tracing::warn!("Skipping inherited metadata key '{key}': {e}"),
+            }
+        }
+    }
+    merged
+}
+
+/// Add the inherited metadata keys the recipe does not set
+pub fn inherit_metadata(metadata: &mut Metadata, recipe_path: &Utf8Path, base_path: &Utf8Path) {
+    add_inherited(metadata, &inherited_metadata(recipe_path, base_path));
+}
+
+/// Add the keys of some inherited metadata the recipe does not set
+pub fn add_inherited(metadata: &mut Metadata, inherited: &serde_yaml::Mapping) {
+    for (key, value) in inherited {
+        if !metadata.map.contains_key(key) {
+            metadata.map.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Same as [`inherit_metadata`] but for a full recipe
+pub fn inherit(
+    mut recipe: ScalableRecipe,
+    recipe_path: &Utf8Path,
+    base_path: &Utf8Path,
+) -> ScalableRecipe {
+    inherit_metadata(&mut recipe.metadata, recipe_path, base_path);
+    recipe
+}
+
+/// Metadata schema that applies to a recipe, merged like the metadata
+pub fn inherited_schema(
+    recipe_path: &Utf8Path,
+    base_path: &Utf8Path,
+) -> BTreeMap<String, KeySchema> {
+    let mut merged = BTreeMap::new();
+    let Some(dir) = recipe_path.parent() else {
+        return merged;
+    };
+    for file in collection_files(dir, base_path) {
+        merged.extend(file.schema);
+    }
+    merged
+}
+
+/// A metadata value that doesn't follow the schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    pub key: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "metadata '{}': {}", self.key, self.message)
+    }
+}
+
+/// Checks the metadata of a recipe, `metadata.map`, against a schema
+pub fn check_schema(
+    schema: &BTreeMap<String, KeySchema>,
+    metadata: &serde_yaml::Mapping,
+) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    for (key, rules) in schema {
+        let violation = |message: String| SchemaViolation {
+            key: key.clone(),
+            message,
+        };
+        let Some(value) = metadata.get(key.as_str()) else {
+            if rules.required {
+                violations.push(violation("required but missing".into()));
+            }
+            continue;
+        };
+        let type_ok = match rules.value_type {
+            None => true,
+            Some(ValueType::String) => value.is_string(),
+            Some(ValueType::Number) => {
+                value.is_number()
+                    || value
+                        .as_str()
+                        .is_some_and(|s| s.trim().parse::<f64>().is_ok())
+            }
+            Some(ValueType::Bool) => value.is_bool(),
+            Some(ValueType::List) => value.is_sequence() || value.is_string(),
+        };
+        if !type_ok {
+            let expected = rules.value_type.unwrap();
+            violations.push(violation(format!("expected {expected}")));
+            continue;
+        }
+        if rules.values.is_empty() {
+            continue;
+        }
+        let items = match value {
+            serde_yaml::Value::Sequence(seq) => seq.iter().map(scalar_text).collect(),
+            serde_yaml::Value::String(s) if rules.value_type == Some(ValueType::List) => {
+                s.split(',').map(|s| Some(s.trim().to_string())).collect()
+            }
+            other => vec![scalar_text(other)],
+        };
+        for item in items {
+            match item {
+                Some(item) if rules.values.contains(&item) => {}
+                Some(item) => violations.push(violation(format!(
+                    "'{item}' is not allowed, expected one of: {}",
+                    rules.values.join(", ")
+                ))),
+                None => violations.push(violation("expected a plain value".into())),
+            }
+        }
+    }
+    violations
+}
+
+fn scalar_text(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.trim().to_string()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_violations() {
+        let file: CollectionFile = toml::from_str(
+            r#"
+            [schema.difficulty]
+            required = true
+            values = ["easy", "hard"]
+
+            [schema.cuisine]
+            required = true
+
+            [schema.servings]
+            type = "number"
+
+            [schema.tags]
+            type = "list"
+            values = ["vegan", "quick"]
+            "#,
+        )
+        .unwrap();
+        let metadata: serde_yaml::Mapping =
+            serde_yaml::from_str("difficulty: medium\nservings: four\ntags: quick, spicy\n")
+                .unwrap();
+        let keys = check_schema(&file.schema, &metadata)
+            .into_iter()
+            .map(|v| v.key)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["cuisine", "difficulty", "servings", "tags"]);
+    }
+}
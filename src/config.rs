@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env,
     fs::{self, File},
     io::{self, Read},
@@ -9,13 +9,25 @@ use std::{
 use anyhow::{bail, Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 use cooklang::Extensions;
+use cooklang_fs::DiscoveryRules;
+use cooklang_to_human::{IngredientColumn, TagColor};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::{APP_NAME, COOK_DIR, UTF8_PATH_PANIC};
+use crate::{
+    config_layers::Layers, encryption::EncryptionConfig, grocery::GroceryConfig, hooks::Hooks,
+    ocr::OcrConfig, plugins::PluginConfig, prep::PrepConfig, probe::ProbeConfig, remote::Remote,
+    scaling::ScalingStrategy, seasonal::SeasonConfig, session::SessionConfig, share::ShareConfig,
+    APP_NAME, COOK_DIR, UTF8_PATH_PANIC,
+};
 
 pub const CONFIG_FILE: &str = "config.toml";
 pub const AUTO_AISLE: &str = "aisle.conf";
 pub const AUTO_UNITS: &str = "units.toml";
+pub const AUTO_PRICES: &str = "prices.toml";
+pub const AUTO_PANTRY: &str = "pantry.toml";
+pub const AUTO_SUBSTITUTIONS: &str = "substitutions.toml";
+pub const AUTO_DIET: &str = "diet.toml";
+pub const AUTO_PRODUCE: &str = "produce.toml";
 pub const DEFAULT_CONFIG_FILE: &str = "default-config.toml";
 pub const CHEF_CONFIG_FILE: &str = "chef-config.toml";
 
@@ -23,6 +35,26 @@ pub const CHEF_CONFIG_FILE: &str = "chef-config.toml";
 pub struct ChefConfig {
     pub default_collection: Option<Utf8PathBuf>,
     pub editor_command: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remotes: BTreeMap<String, Remote>,
+    #[serde(default, skip_serializing_if = "ShareConfig::is_empty")]
+    pub share: ShareConfig,
+    /// Record the commands run in a local file, see `chef stats --self`
+    #[serde(default)]
+    pub usage_stats: bool,
+    /// Commands run on events, only here so a collection can't run commands
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+    /// Programs that give extra data, see [`crate::plugins`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<PluginConfig>,
+    /// Temperature probe of cook mode, the command backend runs a command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe: Option<ProbeConfig>,
+    /// OCR backend of `chef import --image`, it runs a command or sends
+    /// headers with tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr: Option<OcrConfig>,
 }
 
 impl ChefConfig {
@@ -59,14 +91,17 @@ impl Default for ChefConfig {
         Self {
             default_collection: None,
             editor_command: None,
+            remotes: BTreeMap::new(),
+            share: ShareConfig::default(),
+            usage_stats: false,
+            hooks: Hooks::default(),
+            plugins: Vec::new(),
+            probe: None,
+            ocr: None,
         }
     }
 }
 
-pub fn default_config() -> Result<Config> {
-    global_load(DEFAULT_CONFIG_FILE)
-}
-
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct Config {
@@ -74,6 +109,15 @@ pub struct Config {
     pub warnings_as_errors: bool,
     pub recipe_ref_check: bool,
     pub max_depth: usize,
+    /// Locale for the numbers and units of the recipes, like `de-DE`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Columns of the ingredients table in the human format, like
+    /// `["name", "quantity", "cost"]`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ingredient_columns: Vec<String>,
+    /// Ask for a rating and a note after cooking, like `cook --rate`
+    pub rate_after_cooking: bool,
     #[serde(with = "extensions_serde")]
     pub extensions: Extensions,
     #[serde(skip_serializing_if = "Load::is_empty")]
@@ -82,6 +126,22 @@ pub struct Config {
     pub ui: UiConfig,
     #[serde(skip_serializing_if = "is_default")]
     pub export: ExportConfig,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub scaling: HashMap<String, ScalingStrategy>,
+    #[serde(skip_serializing_if = "GroceryConfig::is_empty")]
+    pub grocery: GroceryConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub prep: PrepConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub season: SeasonConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionConfig>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub discovery: DiscoveryRules,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub federation: Option<FederationConfig>,
 }
 
 impl Default for Config {
@@ -92,9 +152,20 @@ impl Default for Config {
             warnings_as_errors: false,
             recipe_ref_check: true,
             max_depth: 10,
+            locale: None,
+            ingredient_columns: Vec::new(),
+            rate_after_cooking: false,
             load: Default::default(),
             ui: Default::default(),
             export: Default::default(),
+            scaling: Default::default(),
+            grocery: Default::default(),
+            prep: Default::default(),
+            season: Default::default(),
+            session: None,
+            discovery: Default::default(),
+            encryption: None,
+            federation: None,
         }
     }
 }
@@ -106,29 +177,100 @@ pub struct Load {
     pub units: Vec<PathBuf>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aisle: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prices: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pantry: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub substitutions: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diet: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub produce: Option<PathBuf>,
 }
 
 impl Load {
     fn is_empty(&self) -> bool {
-        self.units.is_empty() && self.aisle.is_none()
+        self.units.is_empty()
+            && self.aisle.is_none()
+            && self.prices.is_none()
+            && self.pantry.is_none()
+            && self.substitutions.is_none()
+            && self.diet.is_none()
+            && self.produce.is_none()
     }
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct UiConfig {
     pub tags: HashMap<String, TagProps>,
+    /// Serve the text only UI, like `serve --text-only`
+    #[serde(default)]
+    pub text_only: bool,
 }
 
 impl UiConfig {
     fn is_empty(&self) -> bool {
-        self.tags.is_empty()
+        self.tags.is_empty() && !self.text_only
+    }
+
+    /// Colours set in the tags config, skipping the unknown ones
+    pub fn tag_colors(&self) -> HashMap<String, TagColor> {
+        self.tags
+            .iter()
+            .filter_map(|(tag, props)| match props.color.as_deref()?.parse() {
+                Ok(color) => Some((tag.clone(), color)),
+                Err(e) => {
+                    tracing::warn!("Tag '{tag}': {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Colour of a tag, the one in the config or else the default
+    pub fn tag_color(&self, tag: &str) -> TagColor {
+        self.tags
+            .iter()
+            .find(|(t, _)| t.to_lowercase() == tag.to_lowercase())
+            .and_then(|(_, props)| props.color.as_deref()?.parse().ok())
+            .unwrap_or_else(|| TagColor::from_tag(tag))
     }
 }
 
+/// ActivityPub publishing of `serve`, needs the `federation` feature
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FederationConfig {
+    /// Public URL of the server, like `https://recipes.example.com`
+    pub url: String,
+    /// Account name, followed as `@username@host`
+    #[serde(default = "default_federation_username")]
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// PKCS#8 PEM RSA key to sign requests
+    pub private_key: PathBuf,
+    /// PEM public key of `private_key`
+    pub public_key: PathBuf,
+}
+
+fn default_federation_username() -> String {
+    "recipes".into()
+}
+
 #[derive(Serialize, Deserialize, Default, Clone)]
 #[serde(default)]
 pub struct TagProps {
     pub emoji: Option<String>,
+    /// Months of the recipes with the tag, like `jun-aug`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season: Option<String>,
+    /// Colour of the tag in the terminal and the web UI, like `blue`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
@@ -146,16 +288,13 @@ where
 }
 
 impl Config {
-    pub fn read(path: &Utf8Path) -> Result<Self> {
-        let local = path;
-        if !local.is_file() {
-            tracing::debug!("Local config not found, loading global default");
-            return default_config().context("Error loading default global config file");
-        }
-        tracing::debug!("Loading local config from {local}");
-        let content = std::fs::read_to_string(local)?;
-        let config = toml::from_str(&content)?;
-        Ok(config)
+    /// Loads the config layers, with `path` as the collection config file
+    ///
+    /// See [`crate::config_layers`].
+    pub fn read(path: Option<&Utf8Path>) -> Result<Self> {
+        let layers = Layers::load(path)?;
+        layers.warn_ignored_keys();
+        layers.config()
     }
 
     pub fn override_with_args(&mut self, args: &crate::GlobalArgs) {
@@ -183,7 +322,9 @@ impl Config {
         if args.warnings_as_errors {
             self.warnings_as_errors = true;
         }
-        self.max_depth = args.max_depth;
+        if let Some(max_depth) = args.max_depth {
+            self.max_depth = max_depth;
+        }
         if !args.units.is_empty() {
             let new_units = args.units.iter().flat_map(|p| p.canonicalize().ok());
             if args.override_units {
@@ -194,6 +335,20 @@ impl Config {
         }
     }
 
+    /// Columns of the ingredients table, skipping the unknown ones
+    pub fn ingredient_columns(&self) -> Vec<IngredientColumn> {
+        self.ingredient_columns
+            .iter()
+            .filter_map(|c| match c.parse() {
+                Ok(column) => Some(column),
+                Err(e) => {
+                    tracing::warn!("{e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
     pub fn aisle(&self, base_path: &Utf8Path) -> Option<Utf8PathBuf> {
         self.load
             .aisle
@@ -211,8 +366,85 @@ impl Config {
             })
     }
 
+    pub fn prices(&self, base_path: &Utf8Path) -> Option<Utf8PathBuf> {
+        self.load
+            .prices
+            .as_ref()
+            .map(|p| resolve_path(base_path, p))
+            .or_else(|| {
+                let auto = base_path.join(COOK_DIR).join(AUTO_PRICES);
+                tracing::trace!("checking auto prices file: {auto}");
+                auto.is_file().then_some(auto)
+            })
+            .or_else(|| {
+                let global = global_file_path(AUTO_PRICES).ok()?;
+                tracing::trace!("checking global auto prices file: {global}");
+                global.is_file().then_some(global)
+            })
+    }
+
+    pub fn substitutions(&self, base_path: &Utf8Path) -> Option<Utf8PathBuf> {
+        self.load
+            .substitutions
+            .as_ref()
+            .map(|p| resolve_path(base_path, p))
+            .or_else(|| {
+                let auto = base_path.join(COOK_DIR).join(AUTO_SUBSTITUTIONS);
+                tracing::trace!("checking auto substitutions file: {auto}");
+                auto.is_file().then_some(auto)
+            })
+            .or_else(|| {
+                let global = global_file_path(AUTO_SUBSTITUTIONS).ok()?;
+                tracing::trace!("checking global auto substitutions file: {global}");
+                global.is_file().then_some(global)
+            })
+    }
+
+    pub fn diet(&self, base_path: &Utf8Path) -> Option<Utf8PathBuf> {
+        self.load
+            .diet
+            .as_ref()
+            .map(|p| resolve_path(base_path, p))
+            .or_else(|| {
+                let auto = base_path.join(COOK_DIR).join(AUTO_DIET);
+                tracing::trace!("checking auto diet file: {auto}");
+                auto.is_file().then_some(auto)
+            })
+            .or_else(|| {
+                let global = global_file_path(AUTO_DIET).ok()?;
+                tracing::trace!("checking global auto diet file: {global}");
+                global.is_file().then_some(global)
+            })
+    }
+
+    pub fn produce(&self, base_path: &Utf8Path) -> Option<Utf8PathBuf> {
+        self.load
+            .produce
+            .as_ref()
+            .map(|p| resolve_path(base_path, p))
+            .or_else(|| {
+                let auto = base_path.join(COOK_DIR).join(AUTO_PRODUCE);
+                tracing::trace!("checking auto produce file: {auto}");
+                auto.is_file().then_some(auto)
+            })
+            .or_else(|| {
+                let global = global_file_path(AUTO_PRODUCE).ok()?;
+                tracing::trace!("checking global auto produce file: {global}");
+                global.is_file().then_some(global)
+            })
+    }
+
+    /// Pantry file, it may not exist yet
+    pub fn pantry(&self, base_path: &Utf8Path) -> Utf8PathBuf {
+        self.load
+            .pantry
+            .as_ref()
+            .map(|p| resolve_path(base_path, p))
+            .unwrap_or_else(|| base_path.join(COOK_DIR).join(AUTO_PANTRY))
+    }
+
     pub fn units(&self, base_path: &Utf8Path) -> Vec<Utf8PathBuf> {
-        (!self.load.is_empty())
+        (!self.load.units.is_empty())
             .then(|| {
                 self.load
                     .units
@@ -255,6 +487,22 @@ pub fn global_file_path(name: &str) -> Result<Utf8PathBuf> {
     Ok(path)
 }
 
+/// Directory for data kept between runs that is not config
+pub fn global_data_dir() -> Result<Utf8PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "", APP_NAME)
+        .context("Could not determine home directory path")?;
+    let data = Utf8Path::from_path(dirs.data_dir()).expect(UTF8_PATH_PANIC);
+    Ok(data.to_path_buf())
+}
+
+/// Directory for cached data, safe to remove
+pub fn global_cache_dir() -> Result<Utf8PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "", APP_NAME)
+        .context("Could not determine home directory path")?;
+    let cache = Utf8Path::from_path(dirs.cache_dir()).expect(UTF8_PATH_PANIC);
+    Ok(cache.to_path_buf())
+}
+
 pub fn global_load<T: DeserializeOwned + Serialize + Default>(name: &str) -> Result<T> {
     let path = global_file_path(name)?;
     match File::open(&path) {
@@ -351,7 +599,9 @@ mod extensions_serde {
                 // https://github.com/serde-rs/serde/issues/2467
                 while let Some((name, enabled)) = map.next_entry::<String, bool>()? {
                     let e = Extensions::from_name(&name.replace(' ', "_").to_uppercase())
-                        .ok_or_else(|| A::Error::custom(format!("Unknown extension name: {}", name)))?;
+                        .ok_or_else(|| {
+                            A::Error::custom(format!("Unknown extension name: {}", name))
+                        })?;
                     if enabled {
                         extensions |= e;
                     }
@@ -0,0 +1,373 @@
+//! Layers of the collection config
+//!
+//! From lowest to highest priority: the defaults, the global
+//! `default-config.toml`, the collection `.cooklang/config.toml`, the
+//! `CHEF_CONFIG_*` environment variables and the command line flags. Each
+//! layer only sets some keys, tables are merged key by key and anything else
+//! is replaced.
+//!
+//! Keys are paths separated by dots, like `season.region`. In the
+//! environment, the dots are double underscores, like
+//! `CHEF_CONFIG_SEASON__REGION=australia`.
+
+use std::{cell::Cell, fmt};
+
+use anyhow::{bail, Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use once_cell::sync::Lazy;
+use serde::de::{DeserializeOwned, Visitor};
+use toml::{Table, Value};
+
+use cooklang_fs::DiscoveryRules;
+
+use crate::{
+    config::{
+        global_file_path, Config, ExportConfig, FederationConfig, Load, UiConfig, CHEF_CONFIG_FILE,
+        DEFAULT_CONFIG_FILE,
+    },
+    encryption::EncryptionConfig,
+    grocery::GroceryConfig,
+    prep::PrepConfig,
+    seasonal::SeasonConfig,
+    session::SessionConfig,
+};
+
+/// Prefix of the environment variables with config keys
+pub const ENV_PREFIX: &str = "CHEF_CONFIG_";
+
+/// Keys that run commands, only read from the global `chef-config.toml`
+const GLOBAL_ONLY_KEYS: &[&str] = &["hooks", "plugins", "probe", "ocr"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    Global,
+    Collection,
+    Env,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Default => "default",
+            Source::Global => "global",
+            Source::Collection => "collection",
+            Source::Env => "env",
+        })
+    }
+}
+
+pub struct Layer {
+    pub source: Source,
+    /// File of the layer, if it comes from one
+    pub path: Option<Utf8PathBuf>,
+    pub table: Table,
+}
+
+pub struct Layers(Vec<Layer>);
+
+impl Layers {
+    /// Loads the layers, with `collection_file` as the collection one
+    ///
+    /// Missing files are skipped, and without a collection file there is no
+    /// collection layer.
+    pub fn load(collection_file: Option<&Utf8Path>) -> Result<Self> {
+        let mut layers = vec![Layer {
+            source: Source::Default,
+            path: None,
+            table: Table::try_from(Config::default())?,
+        }];
+        let global = global_file_path(DEFAULT_CONFIG_FILE)?;
+        if global.is_file() {
+            layers.push(read_layer(Source::Global, global)?);
+        }
+        if let Some(file) = collection_file.filter(|f| f.is_file()) {
+            layers.push(read_layer(Source::Collection, file.to_owned())?);
+        }
+        let env = env_table(std::env::vars());
+        if !env.is_empty() {
+            validate(&toml::to_string(&env)?).context("Invalid config in the environment")?;
+            layers.push(Layer {
+                source: Source::Env,
+                path: None,
+                table: env,
+            });
+        }
+        Ok(Self(layers))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Layer> {
+        self.0.iter()
+    }
+
+    pub fn merged(&self) -> Table {
+        let mut merged = Table::new();
+        for layer in &self.0 {
+            merge(&mut merged, &layer.table);
+        }
+        merged
+    }
+
+    pub fn config(&self) -> Result<Config> {
+        self.merged().try_into().context("Invalid config")
+    }
+
+    /// Warns about the keys of the files that are ignored or unknown
+    ///
+    /// Only for the config of the context, so they are not repeated when the
+    /// layers are loaded again.
+    pub fn warn_ignored_keys(&self) {
+        for layer in &self.0 {
+            let Some(path) = &layer.path else { continue };
+            for key in layer.table.keys() {
+                if GLOBAL_ONLY_KEYS.contains(&key.as_str()) {
+                    tracing::warn!(
+                        "Ignoring '{key}' in '{path}', it can run commands so it's only read from the global {CHEF_CONFIG_FILE}"
+                    );
+                } else if !known_keys().contains(&key.as_str()) {
+                    tracing::warn!("Unknown config key '{key}' in '{path}'");
+                }
+            }
+        }
+    }
+
+    /// Highest layer that sets a key
+    pub fn origin(&self, key: &str) -> Option<&Layer> {
+        self.0.iter().rev().find(|l| get(&l.table, key).is_some())
+    }
+}
+
+fn read_layer(source: Source, path: Utf8PathBuf) -> Result<Layer> {
+    tracing::debug!("Loading {source} config from {path}");
+    let content =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read '{path}'"))?;
+    validate(&content).with_context(|| format!("Invalid config file '{path}'"))?;
+    let table = toml::from_str::<Table>(&content)?;
+    Ok(Layer {
+        source,
+        path: Some(path),
+        table,
+    })
+}
+
+/// Checks that a config file has the right types, the errors point to the key
+pub fn validate(content: &str) -> Result<Config> {
+    Ok(toml::from_str(content)?)
+}
+
+/// Merges `over` into `base`, key by key in tables
+pub fn merge(base: &mut Table, over: &Table) {
+    for (key, value) in over {
+        match (base.get_mut(key), value) {
+            (Some(Value::Table(base)), Value::Table(over)) => merge(base, over),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Value of a key, like `season.region`
+pub fn get<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Every value that is not a table, with its key
+pub fn flatten<'a>(table: &'a Table, prefix: &str, out: &mut Vec<(String, &'a Value)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Value::Table(table) => flatten(table, &key, out),
+            value => out.push((key, value)),
+        }
+    }
+}
+
+/// Parses a value like in a TOML file, anything else is a string
+pub fn parse_value(s: &str) -> Value {
+    toml::from_str::<Table>(&format!("v = {s}"))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| Value::String(s.to_string()))
+}
+
+/// The config keys set in the environment variables
+fn env_table(vars: impl Iterator<Item = (String, String)>) -> Table {
+    let mut table = Table::new();
+    for (var, value) in vars {
+        let Some(key) = var.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let parts = key
+            .split("__")
+            .map(|p| p.to_lowercase())
+            .collect::<Vec<_>>();
+        if !known_keys().contains(&parts[0].as_str()) {
+            tracing::warn!("Unknown config key in the environment: {var}");
+            continue;
+        }
+        let (last, tables) = parts.split_last().expect("split is never empty");
+        let mut current = &mut table;
+        for part in tables {
+            let entry = current
+                .entry(part.clone())
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            current = entry.as_table_mut().expect("just made a table");
+        }
+        current.insert(last.clone(), parse_value(&value));
+    }
+    table
+}
+
+/// Keys of the top level of the config, the fields of [`Config`]
+pub fn known_keys() -> &'static [&'static str] {
+    static KEYS: Lazy<&'static [&'static str]> = Lazy::new(struct_fields::<Config>);
+    *KEYS
+}
+
+/// Keys of a table in the config that is a struct, like `season`
+///
+/// `None` for the tables that are maps, like `scaling`, they take any key.
+fn table_keys(table: &str) -> Option<&'static [&'static str]> {
+    Some(match table {
+        "load" => struct_fields::<Load>(),
+        "ui" => struct_fields::<UiConfig>(),
+        "export" => struct_fields::<ExportConfig>(),
+        "export.markdown" => struct_fields::<cooklang_to_md::Options>(),
+        "grocery" => struct_fields::<GroceryConfig>(),
+        "prep" => struct_fields::<PrepConfig>(),
+        "season" => struct_fields::<SeasonConfig>(),
+        "session" => struct_fields::<SessionConfig>(),
+        "discovery" => struct_fields::<DiscoveryRules>(),
+        "encryption" => struct_fields::<EncryptionConfig>(),
+        "federation" => struct_fields::<FederationConfig>(),
+        _ => return None,
+    })
+}
+
+/// Checks a key before setting it
+pub fn check_key(key: &str) -> Result<()> {
+    let parts = key.split('.').collect::<Vec<_>>();
+    if parts.iter().any(|p| p.is_empty()) {
+        bail!("Invalid config key '{key}'");
+    }
+    if !known_keys().contains(&parts[0]) {
+        bail!(
+            "Unknown config key '{}', the known ones are: {}",
+            parts[0],
+            known_keys().join(", ")
+        );
+    }
+    for (i, part) in parts.iter().enumerate().skip(1) {
+        let table = parts[..i].join(".");
+        if let Some(keys) = table_keys(&table) {
+            if !keys.contains(part) {
+                bail!(
+                    "Unknown config key '{}', the known ones in '{table}' are: {}",
+                    parts[..=i].join("."),
+                    keys.join(", ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Names of the fields of a struct, asking its `Deserialize` impl
+fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    struct Fields<'a>(&'a Cell<&'static [&'static str]>);
+
+    impl<'de> serde::Deserializer<'de> for Fields<'_> {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(serde::de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.0.set(fields);
+            Err(serde::de::Error::custom("only the fields"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let fields = Cell::new(&[][..]);
+    let _ = T::deserialize(Fields(&fields));
+    fields.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let mut base: Table =
+            toml::from_str("max_depth = 10\n[season]\nregion = 'europe'").unwrap();
+        let over: Table = toml::from_str("[season]\nwarnings = true").unwrap();
+        merge(&mut base, &over);
+        assert_eq!(
+            get(&base, "season.region").unwrap().as_str(),
+            Some("europe")
+        );
+        assert_eq!(get(&base, "season.warnings"), Some(&Value::Boolean(true)));
+        assert_eq!(get(&base, "max_depth"), Some(&Value::Integer(10)));
+
+        let env = env_table(
+            [
+                ("CHEF_CONFIG_SEASON__REGION", "australia"),
+                ("CHEF_CONFIG_MAX_DEPTH", "3"),
+                ("CHEF_CONFIG_NOT_A_KEY", "1"),
+                ("CHEF_EVENT", "recipe_saved"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string())),
+        );
+        merge(&mut base, &env);
+        assert_eq!(
+            get(&base, "season.region").unwrap().as_str(),
+            Some("australia")
+        );
+        assert_eq!(get(&base, "max_depth"), Some(&Value::Integer(3)));
+        assert_eq!(env.len(), 2);
+
+        assert!(known_keys().contains(&"season"));
+        assert!(check_key("seasons.region").is_err());
+        assert!(check_key("season.regoin").is_err());
+        assert!(check_key("season.region").is_ok());
+        assert!(check_key("scaling.flour").is_ok());
+        assert!(check_key("export.markdown.tags").is_ok());
+        assert!(check_key("export.markdown.tagz").is_err());
+        assert_eq!(
+            parse_value("[1, 2]"),
+            Value::Array(vec![Value::Integer(1), Value::Integer(2)])
+        );
+        assert_eq!(
+            parse_value("hello world"),
+            Value::String("hello world".into())
+        );
+        assert!(validate("max_depth = 'deep'").is_err());
+    }
+}
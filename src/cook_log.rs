@@ -0,0 +1,166 @@
+//! When recipes were cooked and added
+//!
+//! `chef cook` writes the days a recipe is cooked to `.cooklang/cook_log.toml`,
+//! by the path of the recipe without extension:
+//!
+//! ```toml
+//! "Mains/Paella" = ["2025-05-01", "2025-06-12"]
+//! ```
+//!
+//! The day a recipe was added is the creation time of its file, or the
+//! modification time where the file system doesn't keep it.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{pantry::Date, COOK_DIR};
+
+pub const COOK_LOG_FILE: &str = "cook_log.toml";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CookLog {
+    /// Days each recipe was cooked, oldest first
+    pub recipes: BTreeMap<String, Vec<Date>>,
+}
+
+impl CookLog {
+    pub fn path(base_path: &Utf8Path) -> Utf8PathBuf {
+        base_path.join(COOK_DIR).join(COOK_LOG_FILE)
+    }
+
+    /// Loads the log, empty if nothing was cooked yet
+    pub fn load(base_path: &Utf8Path) -> Result<Self> {
+        let path = Self::path(base_path);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read cook log: {path}"))?;
+        toml::from_str(&text).with_context(|| format!("Bad cook log: {path}"))
+    }
+
+    pub fn save(&self, base_path: &Utf8Path) -> Result<()> {
+        let path = Self::path(base_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string_pretty(self)?;
+        std::fs::write(&path, text).with_context(|| format!("Cannot write cook log: {path}"))
+    }
+
+    /// Adds a day a recipe was cooked, once per day
+    pub fn record(&mut self, recipe: String, date: Date) {
+        let dates = self.recipes.entry(recipe).or_default();
+        if let Err(i) = dates.binary_search(&date) {
+            dates.insert(i, date);
+        }
+    }
+
+    pub fn last_cooked(&self, recipe: &str) -> Option<Date> {
+        self.recipes.get(recipe).and_then(|d| d.last().copied())
+    }
+}
+
+/// Key of a recipe in the log, its path in the collection without extension
+pub fn recipe_key(path: &Utf8Path, base_path: &Utf8Path) -> String {
+    let relative = path.strip_prefix(base_path).unwrap_or(path);
+    relative.with_extension("").as_str().replace('\\', "/")
+}
+
+/// Day the file of a recipe was added
+pub fn added(path: &Utf8Path) -> Option<Date> {
+    let metadata = path.metadata().ok()?;
+    let time = metadata.created().or_else(|_| metadata.modified()).ok()?;
+    Some(Date::from_time(time))
+}
+
+/// Orders to list recipes in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeOrder {
+    /// By path
+    #[default]
+    Name,
+    /// Added last first
+    Newest,
+    /// Cooked last first, never cooked at the end
+    LastCooked,
+    /// By the `course` metadata, see [`crate::course`]
+    Course,
+}
+
+impl RecipeOrder {
+    /// Name in the web UI queries
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecipeOrder::Name => "name",
+            RecipeOrder::Newest => "newest",
+            RecipeOrder::LastCooked => "last_cooked",
+            RecipeOrder::Course => "course",
+        }
+    }
+}
+
+/// Sorts items already sorted by name, stable for the same dates
+pub fn sort_by_dates<T>(items: &mut [T], order: RecipeOrder, dates: impl Fn(&T) -> RecipeDates) {
+    use std::cmp::Reverse;
+    match order {
+        // the course needs the metadata, the caller sorts by it
+        RecipeOrder::Name | RecipeOrder::Course => {}
+        RecipeOrder::Newest => items.sort_by_cached_key(|i| Reverse(dates(i).added)),
+        RecipeOrder::LastCooked => items.sort_by_cached_key(|i| Reverse(dates(i).last_cooked)),
+    }
+}
+
+/// When a recipe was added and last cooked
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecipeDates {
+    pub added: Option<Date>,
+    pub last_cooked: Option<Date>,
+}
+
+impl RecipeDates {
+    pub fn new(log: &CookLog, path: &Utf8Path, base_path: &Utf8Path) -> Self {
+        Self {
+            added: added(path),
+            last_cooked: log.last_cooked(&recipe_key(path, base_path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log() {
+        let day = |s: &str| s.parse::<Date>().unwrap();
+        let mut log = CookLog::default();
+        log.record("Paella".into(), day("2025-06-12"));
+        log.record("Paella".into(), day("2025-05-01"));
+        log.record("Paella".into(), day("2025-06-12"));
+        assert_eq!(log.recipes["Paella"].len(), 2);
+        assert_eq!(log.last_cooked("Paella"), Some(day("2025-06-12")));
+        assert_eq!(log.last_cooked("Flan"), None);
+        assert_eq!(
+            recipe_key(Utf8Path::new("/r/Mains/Paella.cook"), Utf8Path::new("/r")),
+            "Mains/Paella"
+        );
+
+        let mut items = [
+            (None, "a"),
+            (Some(day("2025-01-01")), "b"),
+            (Some(day("2025-02-01")), "c"),
+        ];
+        sort_by_dates(&mut items, RecipeOrder::LastCooked, |i| RecipeDates {
+            added: None,
+            last_cooked: i.0,
+        });
+        assert_eq!(items.map(|i| i.1), ["c", "b", "a"]);
+    }
+}
@@ -0,0 +1,92 @@
+//! Courses of a meal, from the `course` metadata
+//!
+//! Recipes are ordered by course, like in a menu: starters first and
+//! desserts at the end. Courses that are not known go after the known ones,
+//! by name, and recipes without a course last.
+
+use cooklang::Metadata;
+
+/// Known courses in order, each with its other names
+const COURSES: &[&[&str]] = &[
+    &["breakfast", "brunch"],
+    &[
+        "starter",
+        "appetizer",
+        "antipasto",
+        "entrée",
+        "hors d'oeuvre",
+        "tapas",
+    ],
+    &["soup"],
+    &["salad"],
+    &["main", "main course", "mains"],
+    &["side", "side dish", "sides"],
+    &["dessert", "desserts", "pudding"],
+    &["drink", "drinks", "beverage", "cocktail"],
+];
+
+/// Course of a recipe, in lowercase
+pub fn recipe_course(metadata: &Metadata) -> Option<String> {
+    let course = metadata.map.get("course")?.as_str()?.trim().to_lowercase();
+    (!course.is_empty()).then_some(course)
+}
+
+/// Position of a known course
+fn position(course: &str) -> Option<usize> {
+    COURSES.iter().position(|names| names.contains(&course))
+}
+
+/// Key to sort by course
+///
+/// A course with other names sorts like its first one, so all of them are
+/// together.
+pub fn course_key(course: Option<&str>) -> (usize, String) {
+    match course {
+        Some(course) => match position(course) {
+            Some(pos) => (pos, String::new()),
+            None => (COURSES.len(), course.to_string()),
+        },
+        None => (COURSES.len() + 1, String::new()),
+    }
+}
+
+/// Name of a course to show, the first name of the known ones, capitalized
+pub fn course_label(course: &str) -> String {
+    let name = position(course).map_or(course, |pos| COURSES[pos][0]);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn course_order() {
+        let mut courses = [
+            None,
+            Some("dessert"),
+            Some("barbecue"),
+            Some("appetizer"),
+            Some("main"),
+            Some("starter"),
+        ];
+        courses.sort_by_key(|c| course_key(*c));
+        assert_eq!(
+            courses,
+            [
+                Some("appetizer"),
+                Some("starter"),
+                Some("main"),
+                Some("dessert"),
+                Some("barbecue"),
+                None
+            ]
+        );
+        assert_eq!(course_label("appetizer"), "Starter");
+        assert_eq!(course_label("barbecue"), "Barbecue");
+    }
+}
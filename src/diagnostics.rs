@@ -0,0 +1,299 @@
+//! Machine readable diagnostics
+//!
+//! The parser reports are meant for humans. This converts them into plain
+//! data that can be written as JSON or [SARIF] for editors and CI.
+//!
+//! [SARIF]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use camino::Utf8Path;
+use cooklang::{
+    error::{Severity, SourceDiag, SourceReport, Stage},
+    Converter, CooklangParser, ScaledRecipe,
+};
+use cooklang_self_test::disabled_extensions;
+use serde::Serialize;
+
+use crate::{
+    capacity,
+    collection_file::{check_schema, inherited_schema},
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub severity: &'static str,
+    pub code: &'static str,
+    pub message: String,
+    /// Primary span, if the diagnostic has one
+    pub span: Option<Span>,
+    pub hints: Vec<String>,
+}
+
+impl Diagnostic {
+    /// The message with the line, like `line 3: Unknown unit`
+    pub fn summary(&self) -> String {
+        match self.span {
+            Some(span) => format!("line {}: {}", span.start_line, self.message),
+            None => self.message.clone(),
+        }
+    }
+}
+
+/// Location in the source. Lines and columns start at 1 and columns are
+/// counted in chars.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    pub fn new(text: &str, start: usize, end: usize) -> Self {
+        let (start_line, start_column) = line_col(text, start);
+        let (end_line, end_column) = line_col(text, end);
+        Self {
+            start,
+            end,
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        }
+    }
+}
+
+fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|p| p + 1).unwrap_or(0);
+    let col = text[line_start..offset].chars().count() + 1;
+    (line, col)
+}
+
+/// Span of the line that sets a metadata key, in the front matter or with
+/// `>> key:`
+pub fn metadata_span(text: &str, key: &str) -> Option<Span> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start().trim_start_matches(">>").trim_start();
+        if trimmed
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        {
+            let content = line.trim_end();
+            return Some(Span::new(text, offset, offset + content.len()));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Collects the diagnostics of a report
+pub fn from_report(report: &SourceReport, file: &Utf8Path, text: &str) -> Vec<Diagnostic> {
+    report.iter().map(|d| from_diag(d, file, text)).collect()
+}
+
+fn from_diag(diag: &SourceDiag, file: &Utf8Path, text: &str) -> Diagnostic {
+    let severity = match diag.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let code = match diag.stage {
+        Stage::Parse => "cooklang::parse",
+        Stage::Analysis => "cooklang::analysis",
+    };
+    let span = diag
+        .labels
+        .first()
+        .map(|(span, _)| Span::new(text, span.start(), span.end()));
+    Diagnostic {
+        file: file.to_string(),
+        severity,
+        code,
+        message: diag.message.to_string(),
+        span,
+        hints: diag.hints.iter().map(|h| h.to_string()).collect(),
+    }
+}
+
+/// Extensions disabled in the collection that the recipe uses
+///
+/// The recipe reads differently, or has errors, without them. Other cooklang
+/// tools with the extensions would read it another way.
+pub fn extensions(parser: &CooklangParser, path: &Utf8Path, text: &str) -> Vec<Diagnostic> {
+    disabled_extensions(parser, text)
+        .into_iter()
+        .map(|name| Diagnostic {
+            file: path.to_string(),
+            severity: "warning",
+            code: "chef::disabled-extension",
+            message: format!("Uses the `{name}` extension, disabled in this collection"),
+            span: None,
+            hints: vec![format!(
+                "enable it with `{name} = true` in `[extensions]` of the config, or change the recipe"
+            )],
+        })
+        .collect()
+}
+
+/// Problems of a valid recipe that the parser doesn't report
+///
+/// Quantities over the capacity of the cookware, metadata that breaks the
+/// schema of the collection and units the converter doesn't know. The recipe
+/// is checked as scaled, so the capacity is the one of the amounts shown.
+/// `path` is the recipe file, the diagnostics have it relative to `base_path`.
+pub fn lint(
+    recipe: &ScaledRecipe,
+    path: &Utf8Path,
+    base_path: &Utf8Path,
+    text: &str,
+    converter: &Converter,
+) -> Vec<Diagnostic> {
+    let file = path.strip_prefix(base_path).unwrap_or(path).to_string();
+    let mut all = Vec::new();
+
+    for overflow in capacity::check(recipe, converter) {
+        all.push(Diagnostic {
+            file: file.clone(),
+            severity: "warning",
+            code: "chef::capacity",
+            message: overflow.to_string(),
+            span: None,
+            hints: vec![overflow.hint()],
+        });
+    }
+
+    let schema = inherited_schema(path, base_path);
+    for violation in check_schema(&schema, &recipe.metadata.map) {
+        all.push(Diagnostic {
+            file: file.clone(),
+            severity: "warning",
+            code: "chef::metadata-schema",
+            message: violation.to_string(),
+            span: metadata_span(text, &violation.key),
+            hints: Vec::new(),
+        });
+    }
+
+    // without units loaded every unit is unknown
+    if converter.unit_count() > 0 {
+        let mut seen = Vec::new();
+        for q in recipe
+            .ingredients
+            .iter()
+            .filter_map(|i| i.quantity.as_ref())
+        {
+            let Some(unit) = q.unit() else { continue };
+            if q.unit_info(converter).is_some() || seen.contains(&unit) {
+                continue;
+            }
+            seen.push(unit);
+            let span = text
+                .find(&format!("%{unit}}}"))
+                .map(|start| Span::new(text, start + 1, start + 1 + unit.len()));
+            all.push(Diagnostic {
+                file: file.clone(),
+                severity: "warning",
+                code: "chef::unknown-unit",
+                message: format!("Unknown unit '{unit}', it can't be converted"),
+                span,
+                hints: vec!["Check the spelling or add it to the units file".to_string()],
+            });
+        }
+    }
+
+    all
+}
+
+/// Builds a SARIF 2.1.0 log with a single run
+pub fn to_sarif(diagnostics: &[Diagnostic]) -> serde_json::Value {
+    use serde_json::json;
+
+    let results = diagnostics
+        .iter()
+        .map(|d| {
+            let mut location = json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": d.file.replace('\\', "/") },
+                }
+            });
+            if let Some(span) = d.span {
+                location["physicalLocation"]["region"] = json!({
+                    "startLine": span.start_line,
+                    "startColumn": span.start_column,
+                    "endLine": span.end_line,
+                    "endColumn": span.end_column,
+                    "charOffset": span.start,
+                    "charLength": span.end - span.start,
+                });
+            }
+            let mut message = d.message.clone();
+            for hint in &d.hints {
+                message.push_str("\nhelp: ");
+                message.push_str(hint);
+            }
+            json!({
+                "ruleId": d.code,
+                "level": d.severity,
+                "message": { "text": message },
+                "locations": [location],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "chef",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/Zheoni/cooklang-chef",
+                    "rules": [
+                        { "id": "cooklang::parse" },
+                        { "id": "cooklang::analysis" },
+                        { "id": "chef::capacity" },
+                        { "id": "chef::metadata-schema" },
+                        { "id": "chef::unknown-unit" },
+                        { "id": "chef::disabled-extension" },
+                    ],
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_and_column() {
+        let text = "a\nbñc\n";
+        assert_eq!(line_col(text, 0), (1, 1));
+        assert_eq!(line_col(text, 2), (2, 1));
+        assert_eq!(line_col(text, 5), (2, 3));
+        assert_eq!(line_col(text, 100), (3, 1));
+    }
+
+    #[test]
+    fn disabled_extension_lint() {
+        let parser = CooklangParser::new(
+            cooklang::Extensions::all() - cooklang::Extensions::RANGE_VALUES,
+            Converter::empty(),
+        );
+        let path = Utf8Path::new("Bread.cook");
+        let diags = extensions(&parser, path, "Add @flour{1-2%cups}.\n");
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "chef::disabled-extension");
+        assert!(diags[0].message.contains("range_values"));
+        assert!(extensions(&parser, path, "Add @flour{1%cups}.\n").is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! Allergens and diets of the ingredients
+//!
+//! The bundled data maps ingredient names to categories, like `gluten` or
+//! `meat`. Some categories are allergens, and each diet excludes some of
+//! them. More can be added in `load.diet` or a `diet.toml` file, with the
+//! same format as the bundled one:
+//!
+//! ```toml
+//! allergens = ["mustard"]
+//!
+//! [diets]
+//! keto = ["sugar", "gluten"]
+//!
+//! [categories]
+//! mustard = ["mustard", "dijon"]
+//! sugar = ["sugar", "honey"]
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{bail, Context as _, Result};
+use camino::Utf8Path;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+const BUNDLED: &str = include_str!("data/diet.toml");
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DietData {
+    allergens: Vec<String>,
+    /// Categories each diet excludes
+    diets: BTreeMap<String, Vec<String>>,
+    /// Ingredient names of each category
+    categories: BTreeMap<String, Vec<String>>,
+    /// Names that are not in the category even if they match it
+    exceptions: BTreeMap<String, Vec<String>>,
+}
+
+/// The categories of the ingredients of a recipe
+#[derive(Debug, Default)]
+pub struct Classification {
+    /// Category to the ingredients in it
+    pub categories: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DietData {
+    pub fn load(config: &Config, base_path: &Utf8Path) -> Result<Self> {
+        let mut data: Self = toml::from_str(BUNDLED).expect("bundled diet data");
+        if let Some(path) = config.diet(base_path) {
+            tracing::debug!("Loading diet data {path}");
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read diet file: {path}"))?;
+            let user: Self =
+                toml::from_str(&text).with_context(|| format!("Bad diet file: {path}"))?;
+            data.extend(user);
+        }
+        Ok(data)
+    }
+
+    fn extend(&mut self, other: Self) {
+        for a in other.allergens {
+            if !self.allergens.contains(&a) {
+                self.allergens.push(a);
+            }
+        }
+        // a diet given again replaces the bundled one
+        self.diets.extend(other.diets);
+        for (name, list) in other.categories {
+            self.categories.entry(name).or_default().extend(list);
+        }
+        for (name, list) in other.exceptions {
+            self.exceptions.entry(name).or_default().extend(list);
+        }
+    }
+
+    pub fn is_category(&self, name: &str) -> bool {
+        self.categories.contains_key(name)
+    }
+
+    pub fn is_diet(&self, name: &str) -> bool {
+        self.diets.contains_key(name)
+    }
+
+    /// Categories of an ingredient
+    pub fn categories_of(&self, ingredient: &str) -> Vec<&str> {
+        let lower = ingredient.to_lowercase();
+        let name = words(&lower);
+        self.categories
+            .iter()
+            .filter(|(category, keywords)| {
+                let excluded = self
+                    .exceptions
+                    .get(*category)
+                    .is_some_and(|e| e.iter().any(|k| contains_words(&name, &words(k))));
+                !excluded && keywords.iter().any(|k| contains_words(&name, &words(k)))
+            })
+            .map(|(category, _)| category.as_str())
+            .collect()
+    }
+
+    pub fn classify<'a>(&self, ingredients: impl IntoIterator<Item = &'a str>) -> Classification {
+        let mut c = Classification::default();
+        for ingredient in ingredients {
+            for category in self.categories_of(ingredient) {
+                c.categories
+                    .entry(category.to_string())
+                    .or_default()
+                    .insert(ingredient.to_lowercase());
+            }
+        }
+        c
+    }
+
+    /// Allergens found, with the ingredients that have them
+    pub fn allergens<'c>(&self, c: &'c Classification) -> Vec<(&'c str, &'c BTreeSet<String>)> {
+        c.categories
+            .iter()
+            .filter(|(category, _)| self.allergens.contains(*category))
+            .map(|(category, ingredients)| (category.as_str(), ingredients))
+            .collect()
+    }
+
+    /// Ingredients that don't fit a diet
+    pub fn conflicts<'c>(&self, c: &'c Classification, diet: &str) -> Result<BTreeSet<&'c str>> {
+        let Some(excluded) = self.diets.get(diet) else {
+            bail!(
+                "Unknown diet '{diet}', known diets are: {}",
+                self.diets.keys().cloned().collect::<Vec<_>>().join(", ")
+            );
+        };
+        Ok(excluded
+            .iter()
+            .filter_map(|category| c.categories.get(category))
+            .flatten()
+            .map(String::as_str)
+            .collect())
+    }
+}
+
+impl Classification {
+    pub fn has(&self, category: &str) -> bool {
+        self.categories.contains_key(category)
+    }
+}
+
+/// Writes the allergens of a recipe, after a rendered recipe
+pub fn write_warnings(
+    mut w: impl std::io::Write,
+    data: &DietData,
+    c: &Classification,
+) -> std::io::Result<()> {
+    use yansi::Paint;
+
+    let allergens = data.allergens(c);
+    if allergens.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "\n{}:", "Allergens".bold().underline())?;
+    for (allergen, ingredients) in allergens {
+        let list = ingredients.iter().cloned().collect::<Vec<_>>().join(", ");
+        writeln!(w, "  {} {}", allergen.yellow().bold(), list.dim())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn words(s: &str) -> Vec<&str> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// If `needle` words appear in order in `name`, the last one may be plural,
+/// also like "berries" for "berry"
+pub(crate) fn contains_words(name: &[&str], needle: &[&str]) -> bool {
+    if needle.is_empty() || needle.len() > name.len() {
+        return false;
+    }
+    name.windows(needle.len()).any(|window| {
+        let (last, rest) = needle.split_last().unwrap();
+        let w_last = window[window.len() - 1];
+        window[..rest.len()] == *rest
+            && (w_last == *last
+                || w_last
+                    .strip_prefix(*last)
+                    .is_some_and(|s| s == "s" || s == "es")
+                || last
+                    .strip_suffix('y')
+                    .is_some_and(|stem| w_last.strip_prefix(stem) == Some("ies")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify() {
+        let data: DietData = toml::from_str(BUNDLED).unwrap();
+        let c = data.classify(["Whole wheat flour", "eggs", "almond milk", "nutmeg"]);
+        assert!(c.has("gluten"));
+        assert!(c.has("eggs"));
+        assert!(c.has("nuts"));
+        assert!(!c.has("dairy"));
+        assert_eq!(c.categories["nuts"].len(), 1);
+        let vegan = data.conflicts(&c, "vegan").unwrap();
+        assert_eq!(vegan.into_iter().collect::<Vec<_>>(), ["eggs"]);
+        assert!(data.conflicts(&c, "carnivore").is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Encrypted recipes
+//!
+//! Private recipes can be kept encrypted with [age](https://age-encryption.org)
+//! or GPG, so a collection can live in a synced folder. An encrypted recipe
+//! has an extra extension, like `Stew.cook.age`, and is decrypted when read
+//! and encrypted again when chef writes it. Encryption is done by the `age`
+//! and `gpg` programs.
+//!
+//! ```toml
+//! [encryption]
+//! backend = "age"
+//! recipients = ["age1..."]
+//! identity = "/home/me/.config/age/key.txt"
+//! ```
+//!
+//! Recipes with `private: true` in the metadata are encrypted, or all of
+//! them with `all = true`.
+
+use std::{
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::resolve_path, util::communicate, Context};
+
+pub const PRIVATE_KEY: &str = "private";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncryptionConfig {
+    pub backend: Backend,
+    /// age recipients or GPG key ids to encrypt to
+    pub recipients: Vec<String>,
+    /// age identity file to decrypt, GPG uses its keyring
+    ///
+    /// Relative paths are relative to the `.cooklang` dir, but keys are
+    /// better kept out of the collection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<PathBuf>,
+    /// Encrypt every recipe, not only private ones
+    pub all: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Age,
+    Gpg,
+}
+
+impl Backend {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Backend::Age => "age",
+            Backend::Gpg => "gpg",
+        }
+    }
+
+    fn from_path(path: &Utf8Path) -> Option<Self> {
+        match path.extension()? {
+            "age" => Some(Backend::Age),
+            "gpg" => Some(Backend::Gpg),
+            _ => None,
+        }
+    }
+}
+
+/// Makes [`cooklang_fs::RecipeEntry::read`] decrypt encrypted recipes
+pub fn install(config: Option<EncryptionConfig>, base_path: &Utf8Path) {
+    let identity = config
+        .and_then(|c| c.identity)
+        .map(|p| resolve_path(base_path, &p));
+    cooklang_fs::set_decryptor(move |path| {
+        decrypt(path, identity.as_deref()).map_err(|e| std::io::Error::other(format!("{e:#}")))
+    });
+}
+
+fn decrypt(path: &Utf8Path, identity: Option<&Utf8Path>) -> Result<String> {
+    let mut cmd = match Backend::from_path(path) {
+        Some(Backend::Age) => {
+            let Some(identity) = identity else {
+                bail!("Can't decrypt {path}, set `identity` in the `[encryption]` config");
+            };
+            let mut cmd = Command::new("age");
+            cmd.args(["--decrypt", "--identity", identity.as_str()]);
+            cmd
+        }
+        Some(Backend::Gpg) => {
+            let mut cmd = Command::new("gpg");
+            cmd.args(["--batch", "--quiet", "--decrypt"]);
+            cmd
+        }
+        None => bail!("Not an encrypted file: {path}"),
+    };
+    let output = cmd
+        .arg(path.as_str())
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run {:?}, is it installed?", cmd.get_program()))?;
+    if !output.status.success() {
+        bail!("Failed to decrypt {path}: {}", output.status);
+    }
+    String::from_utf8(output.stdout).with_context(|| format!("{path} is not UTF-8"))
+}
+
+fn encrypt(backend: Backend, recipients: &[String], text: &str) -> Result<Vec<u8>> {
+    if recipients.is_empty() {
+        bail!("No recipients to encrypt to, set `recipients` in the `[encryption]` config");
+    }
+    let mut cmd = match backend {
+        Backend::Age => {
+            let mut cmd = Command::new("age");
+            cmd.arg("--encrypt");
+            for r in recipients {
+                cmd.args(["--recipient", r]);
+            }
+            cmd
+        }
+        Backend::Gpg => {
+            let mut cmd = Command::new("gpg");
+            cmd.args(["--batch", "--yes", "--quiet", "--encrypt"]);
+            for r in recipients {
+                cmd.args(["--recipient", r]);
+            }
+            cmd
+        }
+    };
+    let child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("Failed to run {:?}, is it installed?", cmd.get_program()))?;
+    let output = communicate(child, text.as_bytes().to_vec(), None)?;
+    if !output.status.success() {
+        bail!("Failed to encrypt: {}", output.status);
+    }
+    Ok(output.stdout)
+}
+
+/// Checks if a recipe should be encrypted
+pub fn is_private(ctx: &Context, text: &str) -> Result<bool> {
+    let Some(config) = &ctx.config.encryption else {
+        return Ok(false);
+    };
+    if config.all {
+        return Ok(true);
+    }
+    let metadata = ctx.parser()?.parse_metadata(text).into_output();
+    Ok(metadata.is_some_and(|m| is_marked_private(&m)))
+}
+
+/// Checks the `private` metadata key
+pub fn is_marked_private(metadata: &cooklang::Metadata) -> bool {
+    metadata
+        .map
+        .get(PRIVATE_KEY)
+        .and_then(serde_yaml::Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Writes a recipe, encrypted if needed
+///
+/// Already encrypted files are encrypted again with the same backend. A
+/// plain file that should be encrypted is replaced by an encrypted one with
+/// the extra extension. Returns the written path.
+pub fn write_recipe(ctx: &Context, path: &Utf8Path, text: &str) -> Result<Utf8PathBuf> {
+    let config = ctx.config.encryption.clone().unwrap_or_default();
+    if let Some(backend) = Backend::from_path(path) {
+        let data = encrypt(backend, &config.recipients, text)?;
+        std::fs::write(path, data).with_context(|| format!("Failed to write {path}"))?;
+        return Ok(path.to_path_buf());
+    }
+    if !is_private(ctx, text)? {
+        std::fs::write(path, text).with_context(|| format!("Failed to write {path}"))?;
+        return Ok(path.to_path_buf());
+    }
+    encrypt_recipe(ctx, path, text)
+}
+
+/// Replaces a plain recipe with an encrypted one
+///
+/// The new file has the extension of the configured backend after the
+/// current one. Returns the written path.
+pub fn encrypt_recipe(ctx: &Context, path: &Utf8Path, text: &str) -> Result<Utf8PathBuf> {
+    let Some(config) = &ctx.config.encryption else {
+        bail!("Encryption is not configured, add an `[encryption]` table to the config");
+    };
+    let encrypted = Utf8PathBuf::from(format!("{path}.{}", config.backend.extension()));
+    let data = encrypt(config.backend, &config.recipients, text)?;
+    std::fs::write(&encrypted, data).with_context(|| format!("Failed to write {encrypted}"))?;
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(encrypted)
+}
+
+/// Replaces an encrypted recipe with the plain text one
+pub fn decrypt_to_plain(path: &Utf8Path) -> Result<Utf8PathBuf> {
+    let plain = cooklang_fs::plain_path(path).to_path_buf();
+    if plain == path {
+        bail!("Not an encrypted file: {path}");
+    }
+    if plain.exists() {
+        bail!("File already exists: {plain}");
+    }
+    let text = cooklang_fs::RecipeEntry::new(path).read()?.into_text();
+    std::fs::write(&plain, text).with_context(|| format!("Failed to write {plain}"))?;
+    std::fs::remove_file(path)?;
+    Ok(plain)
+}
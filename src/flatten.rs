@@ -0,0 +1,270 @@
+//! Inline the ingredients of referenced recipes
+//!
+//! A recipe can use another one as an ingredient, `@@sauces/marinara{200%g}`.
+//! Flattening replaces that ingredient with the ingredients of the referenced
+//! recipe, scaled to the amount used:
+//!
+//! - With a unit, the amount is compared with the `yield` metadata of the
+//!   referenced recipe, like `yield: 800 g`, or else with the total of its
+//!   ingredients that can be converted to that unit.
+//! - Without a unit, it's the servings, or a factor if the recipe has none.
+//! - Without a quantity, the whole recipe is used.
+//!
+//! References are flattened recursively.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use cooklang::{
+    ingredient_list::IngredientList, Converter, Modifiers, ScalableRecipe, ScaledRecipe, Value,
+};
+
+use crate::{
+    prep::Preparations,
+    scaling::{base_quantities, parse_amount, scale_by, scale_recipe, ScalingStrategy, YIELD_KEY},
+    util::{convert_value, Input},
+    Context,
+};
+
+/// Deepest chain of references, it's a cycle after that
+const MAX_DEPTH: usize = 8;
+
+/// Adds the ingredients of a recipe to a list, with the referenced recipes
+/// replaced by their ingredients
+///
+/// `path` is the path of the recipe, to find the references relative to it.
+pub fn add_flattened(
+    ctx: &Context,
+    list: &mut IngredientList,
+    recipe: &ScaledRecipe,
+    path: Option<&Utf8Path>,
+) -> Result<()> {
+    let load = |name: &str, dir: Option<&Utf8Path>| -> Result<(ScalableRecipe, Utf8PathBuf)> {
+        let entry = ctx
+            .recipe_index
+            .resolve(name, dir)
+            .with_context(|| format!("Referenced recipe not found: '{name}'"))?;
+        let path = entry.path().to_owned();
+        let input = Input::File {
+            entry,
+            override_name: None,
+        };
+        Ok((input.parse(ctx)?, path))
+    };
+    let flatten = Flatten {
+        converter: ctx.parser()?.converter(),
+        scaling: &ctx.config.scaling,
+        preps: Preparations::new(&ctx.config.prep),
+        load: &load,
+    };
+    flatten.add(list, recipe, path, 0)
+}
+
+/// Finds a referenced recipe by its name and the dir of the recipe using it,
+/// and returns it parsed with its path
+type Load<'a> = dyn Fn(&str, Option<&Utf8Path>) -> Result<(ScalableRecipe, Utf8PathBuf)> + 'a;
+
+struct Flatten<'a> {
+    converter: &'a Converter,
+    scaling: &'a HashMap<String, ScalingStrategy>,
+    preps: Preparations,
+    load: &'a Load<'a>,
+}
+
+impl Flatten<'_> {
+    fn add(
+        &self,
+        list: &mut IngredientList,
+        recipe: &ScaledRecipe,
+        path: Option<&Utf8Path>,
+        depth: usize,
+    ) -> Result<()> {
+        if depth > MAX_DEPTH {
+            bail!("Recipe references nested too deep, there may be a cycle");
+        }
+        let converter = self.converter;
+        for entry in recipe.group_ingredients(converter) {
+            let igr = entry.ingredient;
+            if !igr.modifiers().should_be_listed() {
+                continue;
+            }
+            if !igr.modifiers().contains(Modifiers::RECIPE) {
+                let name = igr.display_name();
+                let name = self.preps.ingredient_name(&name).to_string();
+                list.add_ingredient(name, &entry.quantity, converter);
+                continue;
+            }
+
+            let (sub, sub_path) = (self.load)(&igr.name, path.and_then(Utf8Path::parent))?;
+            let quantity = entry.quantity.iter().next();
+            let scaled = match quantity.map(|q| (q.value(), q.unit())) {
+                None => sub.default_scale(),
+                Some((Value::Number(n), None)) if sub.metadata.servings().is_some() => {
+                    let servings = n.value().round().max(1.0) as u32;
+                    scale_recipe(sub, Some(servings), converter, self.scaling).0
+                }
+                Some((Value::Number(n), None)) => scale_by(sub, n.value(), self.scaling).0,
+                Some((Value::Number(n), Some(unit))) => {
+                    let made = made_amount(&sub, unit, converter).with_context(|| {
+                        format!(
+                            "Can't tell how much of '{unit}' '{}' makes, add a `{YIELD_KEY}` to it",
+                            igr.name
+                        )
+                    })?;
+                    scale_by(sub, n.value() / made, self.scaling).0
+                }
+                Some(_) => bail!("The quantity of '{}' is not a number", igr.name),
+            };
+            self.add(list, &scaled, Some(&sub_path), depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// How much a recipe makes, in `unit`
+fn made_amount(recipe: &ScalableRecipe, unit: &str, converter: &Converter) -> Option<f64> {
+    if let Some(value) = recipe.metadata.map.get(YIELD_KEY).and_then(|v| v.as_str()) {
+        let (value, from) = parse_amount(value).ok()?;
+        return convert_value(value, from.as_deref()?, unit, converter);
+    }
+    let total = recipe
+        .ingredients
+        .iter()
+        .zip(base_quantities(recipe))
+        .filter(|(igr, _)| igr.modifiers().should_be_listed())
+        .filter_map(|(_, q)| {
+            let q = q?;
+            let Value::Number(n) = q.value() else {
+                return None;
+            };
+            convert_value(n.value(), q.unit()?, unit, converter)
+        })
+        .sum::<f64>();
+    (total > 0.0).then_some(total)
+}
+
+/// Writes a recipe for humans with the flattened ingredient list
+pub fn write_human(
+    mut w: impl std::io::Write,
+    ctx: &Context,
+    recipe: &ScaledRecipe,
+    name: &str,
+    path: Option<&Utf8Path>,
+) -> Result<()> {
+    use cooklang_to_human::HumanFormatter;
+
+    let converter = ctx.parser()?.converter();
+    let mut list = IngredientList::new();
+    add_flattened(ctx, &mut list, recipe, path)?;
+
+    HumanFormatter::builder()
+        .show_ingredients(false)
+        .show_cookware(false)
+        .show_steps(false)
+        .build()
+        .format(recipe, name, converter, &mut w)?;
+
+    writeln!(w, "Ingredients:")?;
+    let mut table = tabular::Table::new("  {:<} {:<}");
+    for (name, quantity) in list {
+        let quantity = quantity
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        table.add_row(tabular::Row::new().with_cell(name).with_cell(quantity));
+    }
+    writeln!(w, "{table}")?;
+
+    HumanFormatter::builder()
+        .show_header(false)
+        .show_metadata(false)
+        .show_ingredients(false)
+        .build()
+        .format(recipe, name, converter, &mut w)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cooklang::{CooklangParser, Quantity};
+
+    use super::*;
+
+    fn flatten(recipes: &[(&str, &str)], main: &str) -> Result<IngredientList> {
+        let parser = CooklangParser::default();
+        let parse = |text: &str| parser.parse(text).into_output().unwrap();
+        let load = |name: &str, _: Option<&Utf8Path>| -> Result<(ScalableRecipe, Utf8PathBuf)> {
+            let (_, text) = recipes
+                .iter()
+                .find(|(n, _)| *n == name)
+                .context("not found")?;
+            Ok((parse(text), format!("{name}.cook").into()))
+        };
+        let scaling = HashMap::new();
+        let flatten = Flatten {
+            converter: parser.converter(),
+            scaling: &scaling,
+            preps: Preparations::new(&Default::default()),
+            load: &load,
+        };
+        let mut list = IngredientList::new();
+        flatten.add(&mut list, &parse(main).default_scale(), None, 0)?;
+        Ok(list)
+    }
+
+    fn amount(list: &IngredientList, name: &str) -> (f64, String) {
+        let (_, grouped) = list.iter().find(|(n, _)| *n == name).unwrap();
+        let q: &Quantity = grouped.iter().next().unwrap();
+        let Value::Number(n) = q.value() else {
+            panic!("not a number");
+        };
+        (n.value(), q.unit().unwrap_or_default().to_string())
+    }
+
+    #[test]
+    fn nested_references() {
+        let recipes = [
+            ("dough", "---\nservings: 2\n---\nKnead @flour{500*%g}."),
+            (
+                "sauce",
+                "---\nyield: 400 g\n---\nCook @tomato{800*%g} with @@stock{100*%ml}.",
+            ),
+            // no yield, it makes what its ingredients add up to
+            ("stock", "Boil @water{1*%l}."),
+        ];
+        let list = flatten(&recipes, "Top @@dough{4} with @@sauce{200%g}.").unwrap();
+        // 4 servings of dough
+        assert_eq!(amount(&list, "flour"), (1.0, "kg".into()));
+        // half of the sauce
+        assert_eq!(amount(&list, "tomato"), (400.0, "g".into()));
+        // 50 ml of the stock that makes 1 l
+        let (water, unit) = amount(&list, "water");
+        assert!((water - 50.0).abs() < 1e-9 && unit == "ml");
+        assert!(list.iter().all(|(name, _)| !name.contains("sauce")));
+    }
+
+    #[test]
+    fn whole_recipe_and_factor() {
+        let recipes = [("sauce", "Cook @tomato{800*%g}.")];
+        let list = flatten(&recipes, "Top with @@sauce{}.").unwrap();
+        assert_eq!(amount(&list, "tomato"), (800.0, "g".into()));
+        // without servings the number is a factor
+        let list = flatten(&recipes, "Top with @@sauce{1.5}.").unwrap();
+        assert_eq!(amount(&list, "tomato"), (1.2, "kg".into()));
+    }
+
+    #[test]
+    fn cycle() {
+        let recipes = [("a", "Mix @@b{}."), ("b", "Mix @@a{}.")];
+        let err = flatten(&recipes, "Mix @@a{}.").unwrap_err();
+        assert!(err.to_string().contains("too deep"));
+    }
+
+    #[test]
+    fn unknown_amount() {
+        let recipes = [("sauce", "Cook @tomato{2}.")];
+        assert!(flatten(&recipes, "Top with @@sauce{200%g}.").is_err());
+    }
+}
@@ -0,0 +1,506 @@
+//! Dependency graph of a recipe
+//!
+//! The nodes are the steps, grouped in their sections, and the recipes used
+//! as ingredients. The edges go from a step to the next one in its section,
+//! from a step or section to the step that uses it with an intermediate
+//! reference, and from a referenced recipe to the step that uses it.
+
+use std::{collections::HashMap, fmt::Write as _};
+
+use cooklang::{
+    model::{IngredientReferenceTarget, Item},
+    Content, Converter, Modifiers, ScaledRecipe,
+};
+use serde::Serialize;
+
+use crate::schedule::{format_duration, schedule, ScheduledStep};
+
+#[derive(Debug, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Node {
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+    /// Id of the section of a step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Seconds of the timers of a step
+    #[serde(skip_serializing_if = "is_zero")]
+    pub duration: u64,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Section,
+    Step,
+    Recipe,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// The next step in a section
+    Next,
+    /// An intermediate reference to a step or section
+    Uses,
+    /// A recipe used as an ingredient
+    Recipe,
+}
+
+pub fn step_id(section: usize, number: u32) -> String {
+    format!("s{}_{number}", section + 1)
+}
+
+fn section_id(section: usize) -> String {
+    format!("sec{}", section + 1)
+}
+
+/// Builds the graph of a recipe
+pub fn build(recipe: &ScaledRecipe, converter: &Converter) -> Graph {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+
+    let steps = schedule(recipe, converter);
+    for (index, section) in recipe.sections.iter().enumerate() {
+        nodes.push(Node {
+            id: section_id(index),
+            kind: NodeKind::Section,
+            label: section
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("Section {}", index + 1)),
+            section: None,
+            duration: 0,
+        });
+    }
+    for step in &steps {
+        nodes.push(Node {
+            id: step_id(step.section_index, step.number),
+            kind: NodeKind::Step,
+            label: format!("{}. {}", step.number, shorten(&step.text, 40)),
+            section: Some(section_id(step.section_index)),
+            duration: step.duration,
+        });
+    }
+
+    let mut recipes = HashMap::new();
+    for (section_index, section) in recipe.sections.iter().enumerate() {
+        let mut previous = None;
+        for content in &section.content {
+            let Content::Step(step) = content else {
+                continue;
+            };
+            let id = step_id(section_index, step.number);
+            if let Some(previous) = previous.replace(id.clone()) {
+                edges.push(Edge {
+                    from: previous,
+                    to: id.clone(),
+                    kind: EdgeKind::Next,
+                    label: None,
+                });
+            }
+
+            for item in &step.items {
+                let &Item::Ingredient { index } = item else {
+                    continue;
+                };
+                let igr = &recipe.ingredients[index];
+                let from = match igr.relation.references_to() {
+                    Some((i, IngredientReferenceTarget::Step)) => match &section.content.get(i) {
+                        Some(Content::Step(s)) => Some(step_id(section_index, s.number)),
+                        _ => None,
+                    },
+                    Some((i, IngredientReferenceTarget::Section)) => last_step(recipe, i),
+                    _ => None,
+                };
+                if let Some(from) = from {
+                    edges.push(Edge {
+                        from,
+                        to: id.clone(),
+                        kind: EdgeKind::Uses,
+                        label: Some(igr.display_name().to_string()),
+                    });
+                } else if igr.modifiers().contains(Modifiers::RECIPE) {
+                    let next_id = format!("r{}", recipes.len() + 1);
+                    let recipe_id = recipes
+                        .entry(igr.name.clone())
+                        .or_insert_with(|| {
+                            nodes.push(Node {
+                                id: next_id.clone(),
+                                kind: NodeKind::Recipe,
+                                label: igr.name.clone(),
+                                section: None,
+                                duration: 0,
+                            });
+                            next_id
+                        })
+                        .clone();
+                    edges.push(Edge {
+                        from: recipe_id,
+                        to: id.clone(),
+                        kind: EdgeKind::Recipe,
+                        label: None,
+                    });
+                }
+            }
+        }
+    }
+
+    Graph { nodes, edges }
+}
+
+/// Finds the critical path of a schedule
+///
+/// A step only waits for the steps it depends on, the previous one in its
+/// section and the ones it references, so independent sections overlap, like
+/// a sauce simmering while the pasta boils. Sets the earliest start of every
+/// step and flags the longest chain, which takes the minimal total time.
+pub fn critical_path(graph: &Graph, steps: &mut [ScheduledStep]) {
+    let preds = predecessors(graph, steps);
+    let finish = |s: &ScheduledStep| s.earliest + s.duration;
+    for (i, p) in preds.iter().enumerate() {
+        steps[i].earliest = p.iter().map(|&p| finish(&steps[p])).max().unwrap_or(0);
+        steps[i].critical = false;
+    }
+
+    // walk back from the step that finishes last
+    let mut current = (0..steps.len())
+        .filter(|&i| finish(&steps[i]) > 0)
+        .max_by_key(|&i| (finish(&steps[i]), i));
+    while let Some(i) = current {
+        steps[i].critical = true;
+        current = preds[i]
+            .iter()
+            .copied()
+            .filter(|&p| finish(&steps[p]) == steps[i].earliest)
+            .max();
+    }
+}
+
+/// Indexes of the steps each step of a schedule depends on directly
+fn predecessors(graph: &Graph, steps: &[ScheduledStep]) -> Vec<Vec<usize>> {
+    let index = steps
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (step_id(s.section_index, s.number), i))
+        .collect::<HashMap<_, _>>();
+    let mut preds = vec![Vec::new(); steps.len()];
+    for edge in graph.edges.iter().filter(|e| e.kind != EdgeKind::Recipe) {
+        if let (Some(&from), Some(&to)) = (index.get(&edge.from), index.get(&edge.to)) {
+            // references only go backwards, the schedule order is a valid
+            // topological order
+            if from < to {
+                preds[to].push(from);
+            }
+        }
+    }
+    preds
+}
+
+/// A step to start while another one waits on its timers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    /// Index of the step with timers in the schedule
+    pub waiting: usize,
+    /// Index of the step that can start meanwhile
+    pub start: usize,
+}
+
+/// Finds what to do while waiting
+///
+/// For every step with timers, the first later step that doesn't depend on
+/// it, directly or not, and can start right away.
+pub fn hints(graph: &Graph, steps: &[ScheduledStep]) -> Vec<Hint> {
+    let preds = predecessors(graph, steps);
+    // ancestors[i][j], step i depends on step j
+    let mut ancestors = vec![vec![false; steps.len()]; steps.len()];
+    for (i, p) in preds.iter().enumerate() {
+        for &j in p {
+            ancestors[i][j] = true;
+            let (before, after) = ancestors.split_at_mut(i);
+            for (a, b) in after[0].iter_mut().zip(&before[j]) {
+                *a |= *b;
+            }
+        }
+    }
+
+    let mut hints = Vec::new();
+    for (waiting, step) in steps.iter().enumerate() {
+        if step.duration == 0 {
+            continue;
+        }
+        let start = (waiting + 1..steps.len())
+            .find(|&i| !ancestors[i][waiting] && preds[i].iter().all(|&p| p < waiting));
+        if let Some(start) = start {
+            hints.push(Hint { waiting, start });
+        }
+    }
+    hints
+}
+
+/// Writes the hints after the recipe
+pub fn write_hints(
+    mut w: impl std::io::Write,
+    steps: &[ScheduledStep],
+    hints: &[Hint],
+    markdown: bool,
+) -> anyhow::Result<()> {
+    use yansi::Paint;
+
+    if hints.is_empty() {
+        return Ok(());
+    }
+    if markdown {
+        writeln!(w, "\n## Meanwhile\n")?;
+    } else {
+        writeln!(w, "\n{}:", "Meanwhile".bold().underline())?;
+    }
+    for hint in hints {
+        let waiting = &steps[hint.waiting];
+        let what = match waiting.timers.iter().find_map(|t| t.name.as_deref()) {
+            Some(name) => format!("{name} ({})", step_ref(waiting)),
+            None => step_ref(waiting),
+        };
+        let text = format!(
+            "while {what} takes {}, start {}",
+            format_duration(waiting.duration),
+            step_ref(&steps[hint.start])
+        );
+        if markdown {
+            writeln!(w, "- {}", capitalize(&text))?;
+        } else {
+            writeln!(w, "  {}", capitalize(&text))?;
+        }
+    }
+    Ok(())
+}
+
+fn step_ref(step: &ScheduledStep) -> String {
+    match &step.section {
+        Some(section) => format!("step {} of {section}", step.number),
+        None => format!("step {}", step.number),
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn last_step(recipe: &ScaledRecipe, section: usize) -> Option<String> {
+    recipe
+        .sections
+        .get(section)?
+        .content
+        .iter()
+        .rev()
+        .find_map(|c| match c {
+            Content::Step(s) => Some(step_id(section, s.number)),
+            Content::Text(_) => None,
+        })
+}
+
+fn shorten(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        text.chars().take(max - 1).collect::<String>() + "…"
+    } else {
+        text.to_string()
+    }
+}
+
+impl Graph {
+    fn steps_in<'a>(&'a self, section: &'a str) -> impl Iterator<Item = &'a Node> {
+        self.nodes
+            .iter()
+            .filter(move |n| n.section.as_deref() == Some(section))
+    }
+
+    /// Graphviz DOT
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph {} {{", dot_string(name)).unwrap();
+        writeln!(out, "  rankdir=TB;").unwrap();
+        writeln!(out, "  node [shape=box];").unwrap();
+        for section in self.nodes.iter().filter(|n| n.kind == NodeKind::Section) {
+            writeln!(out, "  subgraph cluster_{} {{", section.id).unwrap();
+            writeln!(out, "    label={};", dot_string(&section.label)).unwrap();
+            for step in self.steps_in(&section.id) {
+                writeln!(out, "    {} [label={}];", step.id, dot_string(&step.label)).unwrap();
+            }
+            writeln!(out, "  }}").unwrap();
+        }
+        for recipe in self.nodes.iter().filter(|n| n.kind == NodeKind::Recipe) {
+            writeln!(
+                out,
+                "  {} [label={}, shape=folder];",
+                recipe.id,
+                dot_string(&recipe.label)
+            )
+            .unwrap();
+        }
+        for edge in &self.edges {
+            let mut attrs = Vec::new();
+            if let Some(label) = &edge.label {
+                attrs.push(format!("label={}", dot_string(label)));
+            }
+            if edge.kind == EdgeKind::Uses {
+                attrs.push("style=dashed".into());
+            }
+            if attrs.is_empty() {
+                writeln!(out, "  {} -> {};", edge.from, edge.to).unwrap();
+            } else {
+                writeln!(
+                    out,
+                    "  {} -> {} [{}];",
+                    edge.from,
+                    edge.to,
+                    attrs.join(", ")
+                )
+                .unwrap();
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    /// Mermaid flowchart
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "flowchart TD").unwrap();
+        for section in self.nodes.iter().filter(|n| n.kind == NodeKind::Section) {
+            writeln!(
+                out,
+                "  subgraph {}[{}]",
+                section.id,
+                mermaid_string(&section.label)
+            )
+            .unwrap();
+            for step in self.steps_in(&section.id) {
+                writeln!(out, "    {}[{}]", step.id, mermaid_string(&step.label)).unwrap();
+            }
+            writeln!(out, "  end").unwrap();
+        }
+        for recipe in self.nodes.iter().filter(|n| n.kind == NodeKind::Recipe) {
+            writeln!(
+                out,
+                "  {}[/{}/]",
+                recipe.id,
+                mermaid_string(&recipe.label).trim_matches('"')
+            )
+            .unwrap();
+        }
+        for edge in &self.edges {
+            let arrow = match edge.kind {
+                EdgeKind::Uses => "-.->",
+                _ => "-->",
+            };
+            match &edge.label {
+                Some(label) => writeln!(
+                    out,
+                    "  {} {arrow}|{}| {}",
+                    edge.from,
+                    mermaid_string(label),
+                    edge.to
+                ),
+                None => writeln!(out, "  {} {arrow} {}", edge.from, edge.to),
+            }
+            .unwrap();
+        }
+        out
+    }
+}
+
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn mermaid_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "#quot;"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(section_index: usize, number: u32, duration: u64) -> ScheduledStep {
+        ScheduledStep {
+            section: None,
+            section_index,
+            number,
+            text: String::new(),
+            start: 0,
+            duration,
+            earliest: 0,
+            critical: false,
+            timers: Vec::new(),
+            words: 0,
+            reading: 0,
+            pace: 0,
+        }
+    }
+
+    fn edge(from: &str, to: &str, kind: EdgeKind) -> Edge {
+        Edge {
+            from: from.into(),
+            to: to.into(),
+            kind,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn parallel_sections() {
+        // dough rests 60 while the filling cooks 20, then both are assembled
+        let mut steps = vec![
+            step(0, 1, 0),
+            step(0, 2, 60),
+            step(1, 1, 20),
+            step(2, 1, 30),
+        ];
+        let graph = Graph {
+            nodes: Vec::new(),
+            edges: vec![
+                edge("s1_1", "s1_2", EdgeKind::Next),
+                edge("s1_2", "s3_1", EdgeKind::Uses),
+                edge("s2_1", "s3_1", EdgeKind::Uses),
+            ],
+        };
+        critical_path(&graph, &mut steps);
+        let earliest = steps.iter().map(|s| s.earliest).collect::<Vec<_>>();
+        assert_eq!(earliest, [0, 0, 0, 60]);
+        let critical = steps.iter().map(|s| s.critical).collect::<Vec<_>>();
+        assert_eq!(critical, [true, true, false, true]);
+        assert_eq!(crate::schedule::parallel_total(&steps), 90);
+
+        let hints = hints(&graph, &steps);
+        assert_eq!(
+            hints,
+            [Hint {
+                waiting: 1,
+                start: 2
+            }]
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Shopping list export for grocery stores
+//!
+//! The shopping list is turned into [`GroceryItem`]s, rounded up to whole
+//! packages when the ingredient has a package size in the `[grocery.packages]`
+//! config table, and written by a [`StoreAdapter`]. Besides the built in
+//! adapters, stores with a CSV import can be added in `[grocery.stores]` by
+//! choosing the columns.
+
+use std::{collections::HashMap, io::Write};
+
+use anyhow::{bail, Result};
+use cooklang::{
+    aisle::AisleConf,
+    ingredient_list::IngredientList,
+    quantity::{GroupedQuantity, Value},
+    Converter,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::util::{convert_value, fmt_number};
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct GroceryConfig {
+    /// Package size by ingredient name
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub packages: HashMap<String, Package>,
+    /// Custom CSV stores by name
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub stores: HashMap<String, CsvStore>,
+}
+
+impl GroceryConfig {
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty() && self.stores.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Package {
+    pub quantity: f64,
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CsvStore {
+    pub columns: Vec<Column>,
+    /// Header names, defaults to the column names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<Vec<String>>,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Name,
+    Quantity,
+    Unit,
+    Packages,
+    Category,
+}
+
+impl Column {
+    fn name(self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Quantity => "quantity",
+            Column::Unit => "unit",
+            Column::Packages => "packages",
+            Column::Category => "category",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GroceryItem {
+    pub name: String,
+    pub quantity: Option<f64>,
+    pub unit: Option<String>,
+    /// Number of packages to buy, if the package size is known
+    pub packages: Option<u32>,
+    pub category: Option<String>,
+}
+
+impl GroceryItem {
+    fn field(&self, column: Column) -> String {
+        match column {
+            Column::Name => self.name.clone(),
+            Column::Quantity => self.quantity.map(fmt_number).unwrap_or_default(),
+            Column::Unit => self.unit.clone().unwrap_or_default(),
+            Column::Packages => self.packages.map(|p| p.to_string()).unwrap_or_default(),
+            Column::Category => self.category.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Writes the items in a format a store can import
+pub trait StoreAdapter {
+    fn write(&self, w: &mut dyn Write, items: &[GroceryItem]) -> Result<()>;
+}
+
+impl StoreAdapter for CsvStore {
+    fn write(&self, w: &mut dyn Write, items: &[GroceryItem]) -> Result<()> {
+        if !self.delimiter.is_ascii() {
+            bail!("CSV delimiter must be an ASCII character");
+        }
+        let mut csv = csv::WriterBuilder::new()
+            .delimiter(self.delimiter as u8)
+            .from_writer(w);
+        match &self.headers {
+            Some(headers) => csv.write_record(headers)?,
+            None => csv.write_record(self.columns.iter().map(|c| c.name()))?,
+        }
+        for item in items {
+            csv.write_record(self.columns.iter().map(|&c| item.field(c)))?;
+        }
+        csv.flush()?;
+        Ok(())
+    }
+}
+
+/// Line items for the Instacart shopping list page API
+pub struct Instacart;
+
+impl StoreAdapter for Instacart {
+    fn write(&self, w: &mut dyn Write, items: &[GroceryItem]) -> Result<()> {
+        #[derive(Serialize)]
+        struct LineItem<'a> {
+            name: &'a str,
+            quantity: f64,
+            unit: &'a str,
+        }
+
+        let line_items = items
+            .iter()
+            .map(|item| match (item.packages, item.quantity) {
+                (Some(packages), _) => LineItem {
+                    name: &item.name,
+                    quantity: packages as f64,
+                    unit: "package",
+                },
+                (None, quantity) => LineItem {
+                    name: &item.name,
+                    quantity: quantity.unwrap_or(1.0),
+                    unit: item.unit.as_deref().unwrap_or("each"),
+                },
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(
+            &mut *w,
+            &serde_json::json!({ "title": "Shopping list", "line_items": line_items }),
+        )?;
+        writeln!(w)?;
+        Ok(())
+    }
+}
+
+pub const BUILTIN_STORES: &[&str] = &["csv", "instacart"];
+
+/// Finds a store adapter by name, the config stores first
+pub fn adapter(name: &str, config: &GroceryConfig) -> Result<Box<dyn StoreAdapter>> {
+    if let Some(store) = config.stores.get(name) {
+        return Ok(Box::new(store.clone()));
+    }
+    match name {
+        "csv" => Ok(Box::new(CsvStore {
+            columns: vec![
+                Column::Name,
+                Column::Quantity,
+                Column::Unit,
+                Column::Packages,
+                Column::Category,
+            ],
+            headers: None,
+            delimiter: ',',
+        })),
+        "instacart" => Ok(Box::new(Instacart)),
+        _ => {
+            let mut known = BUILTIN_STORES
+                .iter()
+                .copied()
+                .chain(config.stores.keys().map(String::as_str))
+                .collect::<Vec<_>>();
+            known.sort_unstable();
+            bail!(
+                "Unknown store '{name}', expected one of: {}",
+                known.join(", ")
+            )
+        }
+    }
+}
+
+/// Turns the shopping list into items, one per ingredient and unit
+pub fn items(
+    list: IngredientList,
+    aisle: &AisleConf,
+    plain: bool,
+    config: &GroceryConfig,
+    converter: &Converter,
+) -> Vec<GroceryItem> {
+    let mut items = Vec::new();
+    if plain {
+        for (name, q) in list {
+            push_items(&mut items, name, &q, None, config, converter);
+        }
+    } else {
+        for (category, igrs) in list.categorize(aisle) {
+            for (name, q) in igrs {
+                push_items(&mut items, name, &q, Some(&category), config, converter);
+            }
+        }
+    }
+    items
+}
+
+fn push_items(
+    items: &mut Vec<GroceryItem>,
+    name: String,
+    quantity: &GroupedQuantity,
+    category: Option<&str>,
+    config: &GroceryConfig,
+    converter: &Converter,
+) {
+    let values = quantity
+        .iter()
+        .map(|q| {
+            let value = match q.value() {
+                Value::Number(n) => Some(n.value()),
+                Value::Range { end, .. } => Some(end.value()),
+                Value::Text(_) => None,
+            };
+            (value, q.unit())
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(package) = config.packages.get(&name) {
+        if let Some(total) = in_package_unit(&values, package, converter) {
+            let packages = (total / package.quantity).ceil().max(1.0) as u32;
+            items.push(GroceryItem {
+                name,
+                quantity: Some(packages as f64 * package.quantity),
+                unit: package.unit.clone(),
+                packages: Some(packages),
+                category: category.map(str::to_string),
+            });
+            return;
+        }
+        tracing::warn!("Can't convert '{name}' to its package unit");
+    }
+
+    if values.is_empty() {
+        items.push(GroceryItem {
+            name,
+            quantity: None,
+            unit: None,
+            packages: None,
+            category: category.map(str::to_string),
+        });
+        return;
+    }
+    for (value, unit) in values {
+        items.push(GroceryItem {
+            name: name.clone(),
+            quantity: value,
+            unit: unit.map(str::to_string),
+            packages: None,
+            category: category.map(str::to_string),
+        });
+    }
+}
+
+/// Sum of all the values in the package unit
+fn in_package_unit(
+    values: &[(Option<f64>, Option<&str>)],
+    package: &Package,
+    converter: &Converter,
+) -> Option<f64> {
+    if package.quantity <= 0.0 || values.is_empty() {
+        return None;
+    }
+    let mut total = 0.0;
+    for &(value, unit) in values {
+        let value = value?;
+        total += match (unit, package.unit.as_deref()) {
+            (None, None) => value,
+            (Some(from), Some(to)) => convert_value(value, from, to, converter)?,
+            _ => return None,
+        };
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_csv_store() {
+        let store = CsvStore {
+            columns: vec![Column::Name, Column::Packages, Column::Quantity],
+            headers: Some(vec!["Product".into(), "Count".into(), "Amount".into()]),
+            delimiter: ';',
+        };
+        let items = [GroceryItem {
+            name: "flour, white".into(),
+            quantity: Some(2.0),
+            unit: Some("kg".into()),
+            packages: Some(2),
+            category: None,
+        }];
+        let mut buf = Vec::new();
+        store.write(&mut buf, &items).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "Product;Count;Amount\nflour, white;2;2\n"
+        );
+    }
+}
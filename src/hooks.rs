@@ -0,0 +1,198 @@
+//! User commands run on events
+//!
+//! Hooks are shell commands declared in the `[hooks]` table of the global
+//! chef config, one list per event. A collection config can't set them, so
+//! a collection from someone else can't run commands. They run with the collection as working directory, the
+//! event name in `CHEF_EVENT` and the event as JSON in stdin. A failing hook is
+//! reported but never makes the command fail.
+//!
+//! Webhooks get the same events as a JSON POST, signed with HMAC-SHA256 if
+//! they have a secret.
+
+use std::{io::Write, process::Stdio, time::Duration};
+
+use camino::Utf8Path;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pantry::rfc3339,
+    util::{hex, shell_command},
+    Context,
+};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct Hooks {
+    /// A recipe was created or changed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recipe_saved: Vec<String>,
+    /// A recipe was deleted, seen by `serve`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recipe_deleted: Vec<String>,
+    /// Recipes started with `chef cook`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recipe_cooked: Vec<String>,
+    /// A shopping list was generated
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shopping_list: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<Webhook>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.recipe_saved.is_empty()
+            && self.recipe_deleted.is_empty()
+            && self.recipe_cooked.is_empty()
+            && self.shopping_list.is_empty()
+            && self.webhooks.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Webhook {
+    pub url: String,
+    /// Event names to send, all if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    /// Key to sign the payloads, in the `X-Chef-Signature` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl Webhook {
+    fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    RecipeSaved { path: &'a str },
+    RecipeDeleted { path: &'a str },
+    RecipeCooked { recipes: &'a [String] },
+    ShoppingList { recipes: &'a [String] },
+}
+
+impl Event<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::RecipeSaved { .. } => "recipe_saved",
+            Event::RecipeDeleted { .. } => "recipe_deleted",
+            Event::RecipeCooked { .. } => "recipe_cooked",
+            Event::ShoppingList { .. } => "shopping_list",
+        }
+    }
+
+    fn commands<'h>(&self, hooks: &'h Hooks) -> &'h [String] {
+        match self {
+            Event::RecipeSaved { .. } => &hooks.recipe_saved,
+            Event::RecipeDeleted { .. } => &hooks.recipe_deleted,
+            Event::RecipeCooked { .. } => &hooks.recipe_cooked,
+            Event::ShoppingList { .. } => &hooks.shopping_list,
+        }
+    }
+}
+
+/// Runs the hooks of an event and waits for them
+pub fn fire(ctx: &Context, event: Event) {
+    fire_in(&ctx.chef_config.hooks, &ctx.base_path, event)
+}
+
+/// Same as [`fire`] without a [`Context`], for the server
+pub fn fire_in(hooks: &Hooks, base_path: &Utf8Path, event: Event) {
+    let commands = event.commands(hooks);
+    let mut webhooks = hooks.webhooks.iter().filter(|w| w.wants(event.name()));
+    if commands.is_empty() && webhooks.clone().next().is_none() {
+        return;
+    }
+    let payload = serde_json::to_vec(&event).expect("event serialization");
+    for command in commands {
+        tracing::debug!("Running {} hook: {command}", event.name());
+        if let Err(e) = run(base_path, command, event.name(), &payload) {
+            tracing::warn!("Hook '{command}' failed: {e:#}");
+        }
+    }
+    if let Some(webhook) = webhooks.next() {
+        let body = webhook_payload(&event);
+        for webhook in std::iter::once(webhook).chain(webhooks) {
+            tracing::debug!("Sending {} webhook to {}", event.name(), webhook.url);
+            if let Err(e) = send(webhook, event.name(), &body) {
+                tracing::warn!("Webhook '{}' failed: {e:#}", webhook.url);
+            }
+        }
+    }
+}
+
+/// The event with the time it was sent, so receivers can reject replays
+fn webhook_payload(event: &Event) -> Vec<u8> {
+    #[derive(Serialize)]
+    struct Payload<'a, 'e> {
+        #[serde(flatten)]
+        event: &'a Event<'e>,
+        timestamp: String,
+    }
+    serde_json::to_vec(&Payload {
+        event,
+        timestamp: rfc3339(std::time::SystemTime::now()),
+    })
+    .expect("event serialization")
+}
+
+fn send(webhook: &Webhook, event: &str, body: &[u8]) -> anyhow::Result<()> {
+    let mut req = ureq::AgentBuilder::new()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .post(&webhook.url)
+        .set("Content-Type", "application/json")
+        .set("User-Agent", crate::USER_AGENT)
+        .set("X-Chef-Event", event);
+    if let Some(secret) = &webhook.secret {
+        req = req.set("X-Chef-Signature", &signature(secret, body));
+    }
+    req.send_bytes(body)?;
+    Ok(())
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of the body
+fn signature(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, body);
+    format!("sha256={}", hex(tag.as_ref()))
+}
+
+fn run(base_path: &Utf8Path, command: &str, event: &str, payload: &[u8]) -> anyhow::Result<()> {
+    let mut child = shell_command(command)
+        .current_dir(base_path)
+        .env("CHEF_EVENT", event)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the hook may not read stdin at all
+        let _ = stdin.write_all(payload);
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_signature() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
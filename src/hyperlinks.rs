@@ -0,0 +1,126 @@
+//! OSC 8 hyperlinks in the human output
+//!
+//! Most terminals without support ignore the escape sequences, but some show
+//! them, so `auto` only uses links where they are known to work.
+
+use std::{collections::HashMap, fmt::Write, io::IsTerminal};
+
+use anstream::ColorChoice;
+use camino::Utf8Path;
+use cooklang::{Modifiers, ScaledRecipe};
+use cooklang_to_human::Hyperlinks;
+
+use crate::{args::HyperlinksChoice, Context};
+
+/// Whether to write links to stdout
+pub fn enabled(choice: HyperlinksChoice) -> bool {
+    match choice {
+        HyperlinksChoice::Always => true,
+        HyperlinksChoice::Never => false,
+        HyperlinksChoice::Auto => {
+            if let Some(force) = env_var("FORCE_HYPERLINK") {
+                return force != "0";
+            }
+            std::io::stdout().is_terminal()
+                && anstream::AutoStream::choice(&std::io::stdout()) != ColorChoice::Never
+                && supported()
+        }
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Guesses from the environment, like the `supports-hyperlinks` crate
+fn supported() -> bool {
+    if env_var("CI").is_some() {
+        return false;
+    }
+    if ["DOMTERM", "WT_SESSION", "KONSOLE_VERSION"]
+        .iter()
+        .any(|v| env_var(v).is_some())
+    {
+        return true;
+    }
+    if env_var("VTE_VERSION").is_some_and(|v| v.parse::<u32>().is_ok_and(|v| v >= 5000)) {
+        return true;
+    }
+    if env_var("TERM_PROGRAM").is_some_and(|p| {
+        matches!(
+            p.as_str(),
+            "Hyper" | "iTerm.app" | "terminology" | "WezTerm" | "vscode" | "ghostty"
+        )
+    }) {
+        return true;
+    }
+    env_var("TERM").is_some_and(|t| {
+        matches!(
+            t.as_str(),
+            "xterm-kitty" | "alacritty" | "foot" | "wezterm" | "xterm-ghostty"
+        )
+    })
+}
+
+/// Links of a recipe read from `path`, with the referenced recipes that exist
+pub fn recipe_links(ctx: &Context, recipe: &ScaledRecipe, path: Option<&Utf8Path>) -> Hyperlinks {
+    let dir = path
+        .and_then(|p| p.canonicalize_utf8().ok())
+        .and_then(|p| p.parent().map(file_url));
+    let mut recipes = HashMap::new();
+    for igr in recipe
+        .ingredients
+        .iter()
+        .filter(|igr| igr.modifiers().contains(Modifiers::RECIPE))
+    {
+        let Ok(entry) = ctx
+            .recipe_index
+            .resolve(&igr.name, path.and_then(Utf8Path::parent))
+        else {
+            continue;
+        };
+        if let Ok(abs) = entry.path().canonicalize_utf8() {
+            recipes.insert(igr.display_name().to_string(), file_url(&abs));
+        }
+    }
+    Hyperlinks {
+        base_url: dir.map(|d| d + "/"),
+        recipes,
+    }
+}
+
+/// `file://` URL of an absolute path
+pub fn file_url(path: &Utf8Path) -> String {
+    let path = path.as_str().replace('\\', "/");
+    // windows verbatim paths from `canonicalize`
+    let path = path.trim_start_matches("//?/");
+    let mut url = String::from("file://");
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~:".contains(&b) {
+            url.push(b as char);
+        } else {
+            write!(url, "%{b:02X}").unwrap();
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_urls() {
+        assert_eq!(
+            file_url(Utf8Path::new("/home/me/My Recipes/Pasta.cook")),
+            "file:///home/me/My%20Recipes/Pasta.cook"
+        );
+        assert_eq!(
+            file_url(Utf8Path::new(r"\\?\C:\Recipes\Crêpes.cook")),
+            "file:///C:/Recipes/Cr%C3%AApes.cook"
+        );
+    }
+}
@@ -0,0 +1,817 @@
+//! Recipes from other formats
+//!
+//! Converts what a user brings into cooklang text: a `.cook` file as is, a
+//! `chef share` link, or a schema.org `Recipe` in JSON-LD, alone or in the
+//! HTML of a web page. JSON-LD has the ingredients as plain text, so they are
+//! written as a note to mark them up by hand.
+//!
+//! Plain text, like the one read from a photo, is converted with guesses:
+//! the ingredient lines are marked up where the steps use them and the times
+//! in the steps become timers. The result always needs a review.
+
+use anyhow::{bail, Context as _, Result};
+use serde_json::Value;
+
+use crate::{share, util::is_valid_tag};
+
+/// A recipe converted to cooklang
+#[derive(Debug)]
+pub struct Imported {
+    /// Name in the source, if it has one outside the text
+    pub name: Option<String>,
+    pub text: String,
+}
+
+/// Converts a pasted or uploaded text
+pub fn from_text(text: &str) -> Result<Imported> {
+    let trimmed = text.trim();
+    // a link is a single line, recipes can have `#` in tags
+    if !trimmed.contains(char::is_whitespace) && trimmed.contains('#') {
+        if let Some((name, text)) = share::decode(trimmed)? {
+            return Ok(Imported {
+                name: Some(name),
+                text,
+            });
+        }
+    }
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        let value: Value = serde_json::from_str(trimmed).context("Invalid JSON")?;
+        return from_json_ld(&value, None);
+    }
+    if trimmed.contains("application/ld+json") {
+        return from_html(trimmed, None);
+    }
+    Ok(Imported {
+        name: None,
+        text: text.to_string(),
+    })
+}
+
+/// Downloads a recipe and converts it
+///
+/// The URL can be a `.cook` file, a `chef share` link or a web page with a
+/// JSON-LD recipe.
+pub fn from_url(url: &str) -> Result<Imported> {
+    if let Ok(Some((name, text))) = share::decode(url) {
+        return Ok(Imported {
+            name: Some(name),
+            text,
+        });
+    }
+    let content = share::fetch(url)?;
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        let value: Value = serde_json::from_str(trimmed).context("Invalid JSON")?;
+        return from_json_ld(&value, Some(url));
+    }
+    if trimmed.starts_with('<') {
+        return from_html(&content, Some(url));
+    }
+    let name = url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .and_then(|n| n.strip_suffix(".cook"))
+        .map(String::from);
+    Ok(Imported {
+        name,
+        text: content,
+    })
+}
+
+fn from_html(html: &str, source: Option<&str>) -> Result<Imported> {
+    for script in json_ld_scripts(html) {
+        let Ok(value) = serde_json::from_str::<Value>(script) else {
+            continue;
+        };
+        if find_recipe(&value).is_some() {
+            return from_json_ld(&value, source);
+        }
+    }
+    bail!("No recipe found in the page")
+}
+
+/// Content of the `<script type="application/ld+json">` tags
+fn json_ld_scripts(html: &str) -> Vec<&str> {
+    let mut scripts = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find("application/ld+json") {
+        rest = &rest[start..];
+        let Some(open) = rest.find('>') else { break };
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find("</script>") else {
+            break;
+        };
+        scripts.push(rest[..close].trim());
+        rest = &rest[close..];
+    }
+    scripts
+}
+
+fn is_recipe(value: &Value) -> bool {
+    match value.get("@type") {
+        Some(Value::String(t)) => t == "Recipe",
+        Some(Value::Array(types)) => types.iter().any(|t| t == "Recipe"),
+        _ => false,
+    }
+}
+
+fn find_recipe(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Array(items) => items.iter().find_map(find_recipe),
+        Value::Object(_) if is_recipe(value) => Some(value),
+        Value::Object(map) => map.get("@graph").and_then(find_recipe),
+        _ => None,
+    }
+}
+
+/// Text of a value that can be a string or a list of them
+fn first_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Array(items) => items.iter().find_map(first_text),
+        Value::Object(map) => map.get("url").and_then(first_text),
+        _ => None,
+    }
+}
+
+fn from_json_ld(value: &Value, source: Option<&str>) -> Result<Imported> {
+    let Some(recipe) = find_recipe(value) else {
+        bail!("No schema.org recipe in the JSON-LD");
+    };
+    let text_of = |key: &str| recipe.get(key).and_then(first_text);
+    let name = text_of("name");
+
+    let mut metadata = serde_yaml::Mapping::new();
+    let mut set = |key: &str, value: serde_yaml::Value| {
+        metadata.insert(key.into(), value);
+    };
+    if let Some(name) = &name {
+        set("title", name.as_str().into());
+    }
+    if let Some(description) = text_of("description") {
+        set("description", description.into());
+    }
+    let servings = text_of("recipeYield").and_then(|y| {
+        let digits = y
+            .trim_start()
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>();
+        digits.parse::<u32>().ok()
+    });
+    if let Some(servings) = servings {
+        set("servings", servings.into());
+    }
+    if let Some(minutes) = text_of("totalTime").as_deref().and_then(iso_minutes) {
+        set("time", minutes.into());
+    }
+    let tags = match recipe.get("keywords") {
+        Some(Value::String(s)) => s.split(',').map(|t| t.trim().to_string()).collect(),
+        Some(Value::Array(items)) => items.iter().filter_map(first_text).collect(),
+        _ => Vec::new(),
+    };
+    let tags = tags
+        .into_iter()
+        .map(|t: String| t.to_lowercase().replace(' ', "-"))
+        .filter(|t| is_valid_tag(t))
+        .map(serde_yaml::Value::from)
+        .collect::<Vec<_>>();
+    if !tags.is_empty() {
+        set("tags", serde_yaml::Value::Sequence(tags));
+    }
+    if let Some(source) = text_of("url").or(source.map(String::from)) {
+        set("source", source.into());
+    }
+    if let Some(image) = text_of("image") {
+        set("image", image.into());
+    }
+
+    let mut text = String::new();
+    if !metadata.is_empty() {
+        text.push_str("---\n");
+        text.push_str(&serde_yaml::to_string(&metadata)?);
+        text.push_str("---\n\n");
+    }
+    let ingredients = match recipe.get("recipeIngredient") {
+        Some(Value::Array(items)) => items.iter().filter_map(first_text).collect::<Vec<_>>(),
+        Some(other) => first_text(other).into_iter().collect(),
+        None => Vec::new(),
+    };
+    if !ingredients.is_empty() {
+        text.push_str(&format!(
+            "> Ingredients: {}\n\n",
+            escape(&ingredients.join(", "))
+        ));
+    }
+    if let Some(instructions) = recipe.get("recipeInstructions") {
+        write_instructions(&mut text, instructions);
+    }
+    Ok(Imported {
+        name,
+        text: text.trim_end().to_string() + "\n",
+    })
+}
+
+fn write_instructions(out: &mut String, value: &Value) {
+    match value {
+        Value::String(s) => {
+            for paragraph in s.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+                out.push_str(&escape(paragraph));
+                out.push_str("\n\n");
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                write_instructions(out, item);
+            }
+        }
+        Value::Object(map) => {
+            let is_section = map.get("@type").is_some_and(|t| t == "HowToSection");
+            if is_section {
+                if let Some(name) = map.get("name").and_then(first_text) {
+                    out.push_str(&format!("== {} ==\n\n", escape(&name)));
+                }
+                if let Some(items) = map.get("itemListElement") {
+                    write_instructions(out, items);
+                }
+            } else if let Some(text) = map.get("text").or_else(|| map.get("name")) {
+                write_instructions(out, text);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Escapes what cooklang would read as markup and joins the lines
+fn escape(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut out = String::with_capacity(text.len());
+    let mut prev = ' ';
+    for c in text.chars() {
+        if matches!(c, '@' | '#' | '~') || (c == '-' && prev == '-') {
+            out.push('\\');
+        }
+        out.push(c);
+        prev = c;
+    }
+    out
+}
+
+/// Headings of the ingredients in plain text recipes, in lowercase
+const INGREDIENTS_HEADINGS: &[&str] = &["ingredients", "you will need", "you'll need"];
+
+/// Headings of the steps in plain text recipes, in lowercase
+const STEPS_HEADINGS: &[&str] = &[
+    "method",
+    "instructions",
+    "directions",
+    "preparation",
+    "steps",
+];
+
+/// Units known after a number in an ingredient line, in lowercase and
+/// singular
+const UNITS: &[&str] = &[
+    "g",
+    "gr",
+    "kg",
+    "mg",
+    "ml",
+    "cl",
+    "dl",
+    "l",
+    "tsp",
+    "tbsp",
+    "teaspoon",
+    "tablespoon",
+    "cup",
+    "oz",
+    "ounce",
+    "lb",
+    "pound",
+    "pinch",
+    "clove",
+    "can",
+    "slice",
+    "stick",
+    "bunch",
+    "sprig",
+    "handful",
+];
+
+/// Units of the times in the steps, in lowercase
+const TIME_UNITS: &[&str] = &[
+    "sec", "secs", "second", "seconds", "min", "mins", "minute", "minutes", "h", "hr", "hrs",
+    "hour", "hours",
+];
+
+const BULLETS: &[char] = &['-', '*', '•', '·', '–', '—', '○', '▪'];
+
+/// An ingredient line of a plain text recipe
+#[derive(Debug)]
+struct TextIngredient {
+    line: String,
+    name: String,
+    /// In cooklang, like `200%g`
+    quantity: Option<String>,
+    note: Option<String>,
+}
+
+impl TextIngredient {
+    fn markup(&self) -> String {
+        let mut out = format!("@{}", self.name);
+        match &self.quantity {
+            Some(quantity) => out.push_str(&format!("{{{quantity}}}")),
+            None if self.name.contains(' ') || self.note.is_some() => out.push_str("{}"),
+            None => {}
+        }
+        if let Some(note) = &self.note {
+            out.push_str(&format!("({note})"));
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextPart {
+    Start,
+    Ingredients,
+    Steps,
+}
+
+/// Converts a recipe in plain text, guessing the markup
+///
+/// The text may come from OCR, so lines wrapped in the middle of a step are
+/// joined. Ingredients not found in the steps are left in a note.
+pub fn from_plain_text(text: &str) -> Imported {
+    let text = ascii_fractions(text);
+    let has_headings = text.lines().any(|l| heading(l).is_some());
+    let mut part = TextPart::Start;
+    let mut name = None;
+    let mut metadata = serde_yaml::Mapping::new();
+    let mut ingredients = Vec::new();
+    let mut steps = Vec::<String>::new();
+    let mut new_step = true;
+    let mut after_ingredient = false;
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            new_step = true;
+            after_ingredient = false;
+            continue;
+        }
+        if let Some(heading) = heading(line) {
+            part = heading;
+            new_step = true;
+            continue;
+        }
+        if let Some((key, value)) = metadata_line(line) {
+            metadata.insert(key.into(), value.into());
+            continue;
+        }
+        let is_ingredient = match part {
+            TextPart::Ingredients => true,
+            TextPart::Steps => false,
+            // without a quantity, like `salt, to taste`, only in a list
+            TextPart::Start => {
+                looks_like_ingredient(line)
+                    || after_ingredient
+                        && line.split_whitespace().count() <= 5
+                        && !line.ends_with('.')
+            }
+        };
+        after_ingredient = is_ingredient;
+        if is_ingredient {
+            if let Some(ingredient) = parse_ingredient(line) {
+                ingredients.push(ingredient);
+            }
+            continue;
+        }
+        // the first line is the title, unless it's a step
+        if part == TextPart::Start
+            && name.is_none()
+            && steps.is_empty()
+            && (has_headings || line.split_whitespace().count() <= 8)
+        {
+            name = Some(line.trim_end_matches(':').to_string());
+            continue;
+        }
+        let (numbered, line) = match strip_step_number(line) {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        match steps.last_mut() {
+            Some(step) if !new_step && !numbered => {
+                // a word split at the end of the line
+                if step.ends_with('-') && !step.ends_with(" -") {
+                    step.pop();
+                } else {
+                    step.push(' ');
+                }
+                step.push_str(line);
+            }
+            _ => steps.push(line.to_string()),
+        }
+        new_step = false;
+    }
+
+    let mut steps = steps
+        .iter()
+        .map(|s| mark_timers(&escape(s)))
+        .collect::<Vec<_>>();
+    let mut unused = Vec::new();
+    for ingredient in &ingredients {
+        if !steps.iter_mut().any(|s| mark_ingredient(s, ingredient)) {
+            unused.push(ingredient.line.as_str());
+        }
+    }
+
+    if let Some(name) = &name {
+        metadata.insert("title".into(), name.as_str().into());
+    }
+    let mut out = String::new();
+    if !metadata.is_empty() {
+        out.push_str("---\n");
+        out.push_str(&serde_yaml::to_string(&metadata).unwrap_or_default());
+        out.push_str("---\n\n");
+    }
+    if !unused.is_empty() {
+        out.push_str(&format!(
+            "> Ingredients: {}\n\n",
+            escape(&unused.join(", "))
+        ));
+    }
+    for step in steps {
+        out.push_str(&step);
+        out.push_str("\n\n");
+    }
+    Imported {
+        name,
+        text: out.trim_end().to_string() + "\n",
+    }
+}
+
+/// Replaces the unicode fractions, `1½` is `1 1/2`
+fn ascii_fractions(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        let fraction = match c {
+            '½' => "1/2",
+            '⅓' => "1/3",
+            '⅔' => "2/3",
+            '¼' => "1/4",
+            '¾' => "3/4",
+            '⅛' => "1/8",
+            _ => {
+                out.push(c);
+                continue;
+            }
+        };
+        if out.ends_with(|c: char| c.is_ascii_digit()) {
+            out.push(' ');
+        }
+        out.push_str(fraction);
+    }
+    out
+}
+
+fn heading(line: &str) -> Option<TextPart> {
+    let line = line
+        .trim_matches(|c: char| c.is_whitespace() || c == ':' || c == '#')
+        .to_lowercase();
+    if INGREDIENTS_HEADINGS.contains(&line.as_str()) {
+        Some(TextPart::Ingredients)
+    } else if STEPS_HEADINGS.contains(&line.as_str()) {
+        Some(TextPart::Steps)
+    } else {
+        None
+    }
+}
+
+/// Servings and times, like `Serves 4` or `Total time: 1 hour`
+fn metadata_line(line: &str) -> Option<(&'static str, u32)> {
+    if line.split_whitespace().count() > 6 {
+        return None;
+    }
+    let lower = line.to_lowercase();
+    let starts = |prefixes: &[&str]| prefixes.iter().any(|p| lower.starts_with(p));
+    if starts(&["serves", "servings", "makes", "yield", "portions"]) {
+        let number = lower
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|n| !n.is_empty())?;
+        return Some(("servings", number.parse().ok()?));
+    }
+    let key = if starts(&["prep time", "preparation time"]) {
+        "prep time"
+    } else if starts(&["cook time", "cooking time"]) {
+        "cook time"
+    } else if starts(&["total time", "time"]) {
+        "time"
+    } else {
+        return None;
+    };
+    Some((key, text_minutes(&lower)?))
+}
+
+/// Minutes of a time in text, like `1 hour 30 min`
+fn text_minutes(text: &str) -> Option<u32> {
+    let words = text
+        .split(|c: char| c.is_whitespace() || c == ':' || c == ',')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>();
+    let mut minutes = 0.0;
+    for (i, word) in words.iter().enumerate() {
+        // also glued, like `1h` or `30min`
+        let split = word
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(word.len());
+        let (number, unit) = word.split_at(split);
+        let Ok(n) = number.parse::<f64>() else {
+            continue;
+        };
+        let unit = if unit.is_empty() {
+            words.get(i + 1).copied().unwrap_or_default()
+        } else {
+            unit
+        };
+        minutes += match unit.trim_end_matches('.') {
+            "h" | "hr" | "hrs" | "hour" | "hours" => n * 60.0,
+            "min" | "mins" | "minute" | "minutes" | "m" => n,
+            _ => 0.0,
+        };
+    }
+    let minutes = minutes.round() as u32;
+    (minutes > 0).then_some(minutes)
+}
+
+fn is_number(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '/' | '.' | ',' | '-'))
+}
+
+/// The unit as written, if it's known
+fn known_unit(word: &str) -> Option<String> {
+    let unit = word.trim_end_matches('.').to_lowercase();
+    let singular = unit
+        .strip_suffix("es")
+        .filter(|u| UNITS.contains(u))
+        .or_else(|| unit.strip_suffix('s'))
+        .unwrap_or(&unit);
+    (UNITS.contains(&unit.as_str()) || UNITS.contains(&singular)).then_some(unit)
+}
+
+fn looks_like_ingredient(line: &str) -> bool {
+    let bulleted = line.starts_with(BULLETS);
+    let line = line.trim_start_matches(BULLETS).trim_start();
+    strip_step_number(line).is_none()
+        && (bulleted || line.starts_with(|c: char| c.is_ascii_digit()))
+        && line.split_whitespace().count() <= 8
+        && !line.ends_with('.')
+}
+
+/// Removes a step number, like `1.`, `2)` or `Step 3:`
+fn strip_step_number(line: &str) -> Option<&str> {
+    let rest = line
+        .strip_prefix("Step ")
+        .or_else(|| line.strip_prefix("STEP "))
+        .unwrap_or(line);
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    let rest = &rest[digits..];
+    let rest = match rest.strip_prefix(['.', ')', ':']) {
+        Some(rest) => rest,
+        None if line.len() != rest.len() + digits => rest,
+        None => return None,
+    };
+    rest.starts_with(char::is_whitespace)
+        .then(|| rest.trim_start())
+        .filter(|r| !r.is_empty())
+}
+
+fn parse_ingredient(line: &str) -> Option<TextIngredient> {
+    let clean = line.trim_start_matches(BULLETS).trim();
+    let mut words = clean.split_whitespace().peekable();
+    let mut numbers = Vec::new();
+    let mut unit = None;
+    while let Some(word) = words.peek() {
+        if is_number(word) {
+            numbers.push(word.replace(',', "."));
+            words.next();
+            continue;
+        }
+        // glued, like `200g`
+        if numbers.is_empty() {
+            let split = word
+                .find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')
+                .unwrap_or(0);
+            if split > 0 {
+                let (number, u) = word.split_at(split);
+                if let Some(u) = known_unit(u) {
+                    numbers.push(number.replace(',', "."));
+                    unit = Some(u);
+                    words.next();
+                }
+            }
+        }
+        break;
+    }
+    if !numbers.is_empty() && unit.is_none() {
+        if let Some(u) = words.peek().and_then(|w| known_unit(w)) {
+            unit = Some(u);
+            words.next();
+        }
+    }
+    let rest = words.collect::<Vec<_>>().join(" ");
+    let rest = rest.strip_prefix("of ").unwrap_or(&rest);
+    let (name, note) = match rest.find([',', '(']) {
+        Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+        None => (rest, None),
+    };
+    let clean = |s: &str| {
+        s.chars()
+            .filter(|c| !matches!(c, '@' | '#' | '~' | '{' | '}' | '(' | ')'))
+            .collect::<String>()
+            .trim()
+            .to_string()
+    };
+    let name = clean(name);
+    if name.is_empty() {
+        return None;
+    }
+    let quantity = (!numbers.is_empty()).then(|| match &unit {
+        Some(unit) => format!("{}%{unit}", numbers.join(" ")),
+        None => numbers.join(" "),
+    });
+    Some(TextIngredient {
+        line: clean_line(line),
+        name,
+        quantity,
+        note: note.map(clean).filter(|n| !n.is_empty()),
+    })
+}
+
+fn clean_line(line: &str) -> String {
+    line.trim_start_matches(BULLETS).trim().to_string()
+}
+
+/// Turns the times in a step into timers, like `~{10%minutes}`
+fn mark_timers(step: &str) -> String {
+    let words = step.split(' ').collect::<Vec<_>>();
+    let mut out = Vec::with_capacity(words.len());
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        if let Some(next) = words.get(i + 1) {
+            let unit = next.trim_end_matches(|c: char| c.is_ascii_punctuation());
+            let punctuation = &next[unit.len()..];
+            if is_number(word) && TIME_UNITS.contains(&unit.to_lowercase().as_str()) {
+                out.push(format!("~{{{word}%{unit}}}{punctuation}"));
+                i += 2;
+                continue;
+            }
+        }
+        out.push(word.to_string());
+        i += 1;
+    }
+    out.join(" ")
+}
+
+/// Marks the first use of an ingredient in a step, returns if it was found
+///
+/// If the whole name is not in the step, the last word is tried, so
+/// `plain flour` is found in `add the flour`.
+fn mark_ingredient(step: &mut String, ingredient: &TextIngredient) -> bool {
+    let lower = step.to_ascii_lowercase();
+    let name = ingredient.name.to_ascii_lowercase();
+    let last_word = name.rsplit(' ').next().unwrap_or(&name);
+    for wanted in [name.as_str(), last_word] {
+        let mut from = 0;
+        while let Some(pos) = lower[from..].find(wanted) {
+            let start = from + pos;
+            let end = start + wanted.len();
+            let before = lower[..start].chars().next_back();
+            let after = lower[end..].chars().next();
+            let is_word = !before.is_some_and(|c| c.is_alphanumeric() || c == '@' || c == '{')
+                && !after.is_some_and(|c| c.is_alphanumeric() || c == '{' || c == '%');
+            // after a timer or ingredient that is already marked
+            let in_markup = lower[..start].rfind('{') > lower[..start].rfind('}');
+            if is_word && !in_markup {
+                step.replace_range(start..end, &ingredient.markup());
+                return true;
+            }
+            from = end;
+        }
+    }
+    false
+}
+
+/// Minutes of an ISO 8601 duration, like `PT1H30M`
+fn iso_minutes(s: &str) -> Option<u32> {
+    let s = s.strip_prefix('P')?;
+    let (days, time) = s.split_once('T').unwrap_or((s, ""));
+    let mut minutes = 0.0;
+    let mut num = String::new();
+    for (part, is_time) in [(days, false), (time, true)] {
+        for c in part.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                num.push(c);
+                continue;
+            }
+            let n: f64 = num.parse().ok()?;
+            num.clear();
+            minutes += match (c, is_time) {
+                ('D', false) => n * 24.0 * 60.0,
+                ('H', true) => n * 60.0,
+                ('M', true) => n,
+                ('S', true) => n / 60.0,
+                _ => return None,
+            };
+        }
+    }
+    let minutes = minutes.round() as u32;
+    (minutes > 0).then_some(minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(iso_minutes("PT1H30M"), Some(90));
+        assert_eq!(iso_minutes("P0DT0H45M"), Some(45));
+        assert_eq!(iso_minutes("PT0M"), None);
+        assert_eq!(iso_minutes("1 hour"), None);
+    }
+
+    #[test]
+    fn json_ld() {
+        let html = r#"<html><head><script type="application/ld+json">
+        {"@context": "https://schema.org", "@graph": [{"@type": "WebPage"}, {
+            "@type": "Recipe",
+            "name": "Pancakes",
+            "recipeYield": ["4 servings"],
+            "totalTime": "PT20M",
+            "keywords": "breakfast, Quick Meals",
+            "recipeIngredient": ["200 g flour", "2 eggs"],
+            "recipeInstructions": [
+                {"@type": "HowToStep", "text": "Mix   the flour\nand the eggs."},
+                {"@type": "HowToSection", "name": "Cook", "itemListElement": [
+                    {"@type": "HowToStep", "text": "Fry 2-3 min @ medium heat."}
+                ]}
+            ]
+        }]}
+        </script></head></html>"#;
+        let imported = from_text(html).unwrap();
+        assert_eq!(imported.name.as_deref(), Some("Pancakes"));
+        let text = imported.text;
+        assert!(text.starts_with("---\ntitle: Pancakes\n"));
+        assert!(text.contains("servings: 4\n"));
+        assert!(text.contains("time: 20\n"));
+        assert!(text.contains("- quick-meals\n"));
+        assert!(text.contains("> Ingredients: 200 g flour, 2 eggs\n"));
+        assert!(text.contains("Mix the flour and the eggs.\n\n== Cook ==\n\n"));
+        assert!(text.contains("Fry 2-3 min \\@ medium heat.\n"));
+
+        let cook = "Mix @flour{200%g}.\n";
+        assert_eq!(from_text(cook).unwrap().text, cook);
+    }
+
+    #[test]
+    fn plain_text() {
+        let text = "\
+Lemon Cake
+Serves 8
+
+INGREDIENTS
+• 200g plain flour
+• 1½ cups sugar
+- 2 eggs, beaten
+- Zest of 1 lemon
+
+Method
+1. Mix the flour and the sugar in a bowl. Add the eggs
+and mix well.
+2. Bake for 35-40 min at 180 °C.
+";
+        let imported = from_plain_text(text);
+        assert_eq!(imported.name.as_deref(), Some("Lemon Cake"));
+        let text = imported.text;
+        assert!(text.starts_with("---\nservings: 8\ntitle: Lemon Cake\n---\n"));
+        assert!(text.contains("> Ingredients: Zest of 1 lemon\n"));
+        assert!(text.contains(
+            "Mix the @plain flour{200%g} and the @sugar{1 1/2%cups} in a bowl. \
+             Add the @eggs{2}(beaten) and mix well.\n"
+        ));
+        assert!(text.contains("Bake for ~{35-40%min} at 180 °C.\n"));
+        assert_eq!(strip_step_number("Step 2: Bake"), Some("Bake"));
+        assert_eq!(strip_step_number("2.5 kg flour"), None);
+        assert_eq!(text_minutes("total time: 1 hour 30 min"), Some(90));
+    }
+}
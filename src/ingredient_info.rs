@@ -0,0 +1,107 @@
+//! Data for the extra columns of the ingredients table
+//!
+//! The category comes from the aisle file, the cost from the prices and the
+//! calories from the nutrition plugins. Only the data of the shown columns is
+//! loaded.
+
+use std::collections::HashMap;
+
+use anyhow::{Context as _, Result};
+use cooklang::{
+    ingredient_list::{GroupedIngredient, IngredientList},
+    ScaledRecipe,
+};
+use cooklang_to_human::{IngredientColumn, IngredientInfo};
+
+use crate::{plugins, prices, Context};
+
+/// Nutrient of the calories column
+const CALORIES_NUTRIENT: &str = "energy_kcal";
+
+/// Texts of the columns by ingredient display name
+#[derive(Debug, Default)]
+pub struct IngredientData {
+    categories: HashMap<String, String>,
+    costs: HashMap<String, String>,
+    calories: HashMap<String, String>,
+}
+
+impl IngredientData {
+    pub fn load(
+        ctx: &Context,
+        recipe: &ScaledRecipe,
+        columns: &[IngredientColumn],
+    ) -> Result<Self> {
+        let converter = ctx.parser()?.converter();
+        let mut data = Self::default();
+
+        if columns.contains(&IngredientColumn::Category) {
+            if let Some(path) = ctx.config.aisle(&ctx.base_path) {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Cannot read aisle file: {path}"))?;
+                match cooklang::aisle::parse(&content) {
+                    Ok(aisle) => {
+                        let mut list = IngredientList::new();
+                        for entry in recipe.group_ingredients(converter) {
+                            list.add_ingredient(
+                                entry.ingredient.display_name().to_string(),
+                                &entry.quantity,
+                                converter,
+                            );
+                        }
+                        for (category, igrs) in list.categorize(&aisle) {
+                            for (name, _) in igrs {
+                                data.categories.insert(name, category.clone());
+                            }
+                        }
+                    }
+                    Err(_) => tracing::warn!("Bad aisle file, no categories: {path}"),
+                }
+            }
+        }
+
+        let wants_cost = columns.contains(&IngredientColumn::Cost);
+        let wants_calories = columns.contains(&IngredientColumn::Calories);
+        if !wants_cost && !wants_calories {
+            return Ok(data);
+        }
+        let amounts = plugins::recipe_amounts(recipe, converter);
+        if wants_cost {
+            let costs = prices::costs(ctx, &amounts)?;
+            for (name, cost) in &costs.ingredients {
+                if let Some(cost) = cost {
+                    let text = prices::format_cost(*cost, costs.currency.as_deref());
+                    data.costs.insert(name.clone(), text);
+                }
+            }
+        }
+        if wants_calories {
+            for igr in plugins::nutrition(&ctx.chef_config.plugins, &amounts) {
+                if let Some(kcal) = igr.nutrients.get(CALORIES_NUTRIENT) {
+                    data.calories
+                        .entry(igr.name)
+                        .or_insert_with(|| format!("{kcal:.0} kcal"));
+                }
+            }
+        }
+        Ok(data)
+    }
+}
+
+impl IngredientInfo for IngredientData {
+    fn category(&self, entry: &GroupedIngredient) -> Option<String> {
+        self.categories
+            .get(&*entry.ingredient.display_name())
+            .cloned()
+    }
+
+    fn cost(&self, entry: &GroupedIngredient) -> Option<String> {
+        self.costs.get(&*entry.ingredient.display_name()).cloned()
+    }
+
+    fn calories(&self, entry: &GroupedIngredient) -> Option<String> {
+        self.calories
+            .get(&*entry.ingredient.display_name())
+            .cloned()
+    }
+}
@@ -0,0 +1,56 @@
+//! Locale of the rendered recipes
+//!
+//! The `locale` key in the config, like `locale = "de-DE"`, sets how numbers
+//! are written in every output format (`1,5 kg`) and the units the recipes
+//! are converted to when no other conversion is asked. A recipe can have its
+//! own `locale` in the metadata, which takes precedence.
+
+use cooklang::{convert::System, Metadata};
+use cooklang_to_human::NumberFormat;
+
+use crate::config::Config;
+
+/// Metadata and config key with the locale
+pub const LOCALE_KEY: &str = "locale";
+
+/// Locale of a recipe, from its metadata or the config
+pub fn recipe_locale<'a>(metadata: &'a Metadata, config: &'a Config) -> Option<&'a str> {
+    metadata
+        .map
+        .get(LOCALE_KEY)
+        .and_then(|v| v.as_str())
+        .or(config.locale.as_deref())
+}
+
+/// Separators for the numbers of a recipe
+pub fn number_format(metadata: &Metadata, config: &Config) -> NumberFormat {
+    recipe_locale(metadata, config)
+        .map(NumberFormat::from_locale)
+        .unwrap_or_default()
+}
+
+/// Unit system usual in a locale, by its region
+///
+/// Only the United States, Liberia and Myanmar use imperial units, a locale
+/// without region is metric.
+pub fn unit_system(locale: &str) -> System {
+    let region = locale.split(['-', '_']).nth(1).unwrap_or_default();
+    match region.to_uppercase().as_str() {
+        "US" | "LR" | "MM" => System::Imperial,
+        _ => System::Metric,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn systems() {
+        assert_eq!(unit_system("en-US"), System::Imperial);
+        assert_eq!(unit_system("en_us"), System::Imperial);
+        assert_eq!(unit_system("en-GB"), System::Metric);
+        assert_eq!(unit_system("en"), System::Metric);
+        assert_eq!(unit_system("es-ES"), System::Metric);
+    }
+}
@@ -0,0 +1,318 @@
+//! Statistics of the cook log
+//!
+//! How often recipes are cooked over time, the weeks in a row with something
+//! cooked, the cuisines from the `cuisine` metadata and the cost and calories
+//! of what was cooked each week. Recipes cooked before but not for a while
+//! are suggested again.
+//!
+//! The cost and calories are of the recipes at their default servings.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use camino::Utf8Path;
+use cooklang::{CooklangParser, Metadata};
+use cooklang_fs::RecipeEntry;
+use serde::Serialize;
+
+use crate::{
+    collection_file::inherit,
+    config::Config,
+    cook_log::CookLog,
+    pantry::Date,
+    plugins::{self, PluginConfig},
+    prices::PricesFile,
+    util::{map_recipe, meta_name},
+};
+
+/// Months of the cooking frequency
+const MONTHS: usize = 12;
+
+/// Nutrient of the calories
+const CALORIES_NUTRIENT: &str = "energy_kcal";
+
+/// What the stats need of a recipe
+#[derive(Debug, Default)]
+pub struct RecipeFacts {
+    pub name: Option<String>,
+    pub cuisine: Option<String>,
+    /// Cost of the ingredients with a price
+    pub cost: Option<f64>,
+    pub kcal: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    /// Weeks of the averages, up to today
+    pub weeks: u32,
+    /// Days without cooking a recipe to suggest it again
+    pub nudge_days: i64,
+    /// Max recipes suggested again
+    pub nudges: usize,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            weeks: 12,
+            nudge_days: 180,
+            nudges: 5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogStats {
+    /// Times something was cooked
+    pub cooked: usize,
+    /// Different recipes cooked
+    pub recipes: usize,
+    /// Times cooked each month, oldest first, like `2025-06`
+    pub months: Vec<(String, usize)>,
+    /// Weeks in a row with something cooked, up to this or the last week
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    /// Times cooked by cuisine, most cooked first
+    pub cuisines: Vec<(String, usize)>,
+    pub weeks: u32,
+    pub cost_per_week: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    pub kcal_per_week: Option<f64>,
+    pub nudges: Vec<Nudge>,
+}
+
+/// A recipe not cooked for a while
+#[derive(Debug, Serialize)]
+pub struct Nudge {
+    /// Key of the recipe in the log
+    pub recipe: String,
+    pub name: String,
+    pub last_cooked: Date,
+    pub times: usize,
+}
+
+/// Monday-based week number
+fn week(date: Date) -> i64 {
+    // 1970-01-01 was a thursday
+    (date.days_since(Date::from_ymd(1970, 1, 1)) + 3).div_euclid(7)
+}
+
+pub fn compute(
+    log: &CookLog,
+    facts: &HashMap<String, RecipeFacts>,
+    currency: Option<String>,
+    today: Date,
+    options: &StatsOptions,
+) -> LogStats {
+    let all = log
+        .recipes
+        .iter()
+        .flat_map(|(key, dates)| dates.iter().map(move |d| (key.as_str(), *d)))
+        .filter(|(_, d)| *d <= today)
+        .collect::<Vec<_>>();
+
+    let (year, month, _) = today.ymd();
+    let months = (0..MONTHS as i64)
+        .rev()
+        .map(|back| {
+            let index = year * 12 + month as i64 - 1 - back;
+            let (y, m) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+            let count = all
+                .iter()
+                .filter(|(_, d)| {
+                    let (dy, dm, _) = d.ymd();
+                    dy == y && dm == m
+                })
+                .count();
+            (format!("{y:04}-{m:02}"), count)
+        })
+        .collect();
+
+    let mut weeks = all.iter().map(|(_, d)| week(*d)).collect::<Vec<_>>();
+    weeks.sort_unstable();
+    weeks.dedup();
+    let mut longest_streak = 0;
+    let mut streak = 0;
+    let mut previous = None;
+    for &w in &weeks {
+        streak = if previous == Some(w - 1) {
+            streak + 1
+        } else {
+            1
+        };
+        longest_streak = longest_streak.max(streak);
+        previous = Some(w);
+    }
+    // the current week may still have no cooking
+    let this_week = week(today);
+    let current_streak = match previous {
+        Some(last) if last >= this_week - 1 => streak,
+        _ => 0,
+    };
+
+    let mut cuisines = BTreeMap::<&str, usize>::new();
+    for (key, _) in &all {
+        if let Some(cuisine) = facts.get(*key).and_then(|f| f.cuisine.as_deref()) {
+            *cuisines.entry(cuisine).or_default() += 1;
+        }
+    }
+    let mut cuisines = cuisines
+        .into_iter()
+        .map(|(c, n)| (c.to_string(), n))
+        .collect::<Vec<_>>();
+    cuisines.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+
+    let since = today.add_days(-7 * options.weeks as i64);
+    let recent = all.iter().filter(|(_, d)| *d > since).collect::<Vec<_>>();
+    let per_week = |value: fn(&RecipeFacts) -> Option<f64>| {
+        let values = recent
+            .iter()
+            .filter_map(|(key, _)| facts.get(*key).and_then(value))
+            .collect::<Vec<_>>();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / options.weeks.max(1) as f64)
+    };
+    let cost_per_week = per_week(|f| f.cost);
+    let kcal_per_week = per_week(|f| f.kcal);
+
+    let mut nudges = log
+        .recipes
+        .iter()
+        .filter_map(|(key, dates)| {
+            let last_cooked = *dates.last()?;
+            (today.days_since(last_cooked) >= options.nudge_days).then(|| Nudge {
+                recipe: key.clone(),
+                name: facts
+                    .get(key)
+                    .and_then(|f| f.name.clone())
+                    .unwrap_or_else(|| key.rsplit('/').next().unwrap_or(key).to_string()),
+                last_cooked,
+                times: dates.len(),
+            })
+        })
+        .collect::<Vec<_>>();
+    nudges.sort_by(|a, b| {
+        b.times
+            .cmp(&a.times)
+            .then(a.last_cooked.cmp(&b.last_cooked))
+    });
+    nudges.truncate(options.nudges);
+
+    LogStats {
+        cooked: all.len(),
+        recipes: log.recipes.values().filter(|d| !d.is_empty()).count(),
+        months,
+        current_streak,
+        longest_streak,
+        cuisines,
+        weeks: options.weeks,
+        cost_per_week,
+        currency,
+        kcal_per_week,
+        nudges,
+    }
+}
+
+/// Cuisine of a recipe, in lowercase
+fn cuisine(metadata: &Metadata) -> Option<String> {
+    let cuisine = metadata.map.get("cuisine")?.as_str()?.trim().to_lowercase();
+    (!cuisine.is_empty()).then_some(cuisine)
+}
+
+/// Facts of the recipes and the currency of their costs
+pub type Facts = (HashMap<String, RecipeFacts>, Option<String>);
+
+/// Facts of the recipes in the log, by their key
+///
+/// Recipes that no longer exist or have errors have no facts. The currency
+/// is the one of the costs.
+pub fn recipe_facts(
+    config: &Config,
+    plugins: &[PluginConfig],
+    base_path: &Utf8Path,
+    parser: &CooklangParser,
+    log: &CookLog,
+) -> Result<Facts> {
+    let converter = parser.converter();
+    let prices = PricesFile::load_from(config, base_path)?;
+    let mut currency = None;
+    let mut facts = HashMap::new();
+    for key in log.recipes.keys() {
+        let path = base_path.join(format!("{key}.cook"));
+        if !path.is_file() {
+            continue;
+        }
+        let Ok(content) = RecipeEntry::new(path.clone()).read() else {
+            continue;
+        };
+        // like every recipe, with the metadata of the collection files
+        let Some(recipe) = parser
+            .parse(&content.into_text())
+            .into_output()
+            .map(|r| map_recipe(inherit(r, &path, base_path)))
+        else {
+            continue;
+        };
+        let recipe = recipe.default_scale();
+        let amounts = plugins::recipe_amounts(&recipe, converter);
+        let costs = prices.costs(plugins, converter, &amounts);
+        if currency.is_none() {
+            currency = costs.currency.clone();
+        }
+        let kcal = plugins::nutrition(plugins, &amounts)
+            .iter()
+            .filter_map(|n| n.nutrients.get(CALORIES_NUTRIENT))
+            .copied()
+            .reduce(|a, b| a + b);
+        facts.insert(
+            key.clone(),
+            RecipeFacts {
+                name: meta_name(&recipe.metadata).map(str::to_string),
+                cuisine: cuisine(&recipe.metadata),
+                cost: costs
+                    .ingredients
+                    .values()
+                    .any(Option::is_some)
+                    .then(|| costs.total()),
+                kcal,
+            },
+        );
+    }
+    Ok((facts, currency))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let day = |s: &str| s.parse::<Date>().unwrap();
+        let today = day("2025-06-12"); // thursday
+        let mut log = CookLog::default();
+        for d in ["2025-05-26", "2025-06-03", "2025-06-11", "2024-01-10"] {
+            log.record("Paella".into(), day(d));
+        }
+        log.record("Soups/Gazpacho".into(), day("2024-08-01"));
+        log.record("Pasta".into(), day("2025-04-01"));
+        let facts = HashMap::from([(
+            "Paella".to_string(),
+            RecipeFacts {
+                cuisine: Some("spanish".into()),
+                cost: Some(12.0),
+                ..Default::default()
+            },
+        )]);
+        let stats = compute(&log, &facts, None, today, &StatsOptions::default());
+        assert_eq!(stats.cooked, 6);
+        assert_eq!(stats.recipes, 3);
+        assert_eq!(stats.months.last(), Some(&("2025-06".to_string(), 2)));
+        assert_eq!(stats.months.len(), 12);
+        assert_eq!(stats.current_streak, 3);
+        assert_eq!(stats.cuisines, [("spanish".to_string(), 4)]);
+        assert_eq!(stats.cost_per_week, Some(36.0 / 12.0));
+        assert_eq!(stats.kcal_per_week, None);
+        assert_eq!(stats.nudges.len(), 1);
+        assert_eq!(stats.nudges[0].name, "Gazpacho");
+    }
+}
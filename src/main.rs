@@ -1,8 +1,8 @@
 use anstream::ColorChoice;
 use anyhow::{bail, Context as _, Result};
-use args::{CliArgs, Command, GlobalArgs};
+use args::{CliArgs, Command, GlobalArgs, LogFormat};
 use camino::{Utf8Path, Utf8PathBuf};
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use config::{global_load, ChefConfig, Config, CHEF_CONFIG_FILE};
 use cooklang::{convert::ConverterBuilder, Converter, CooklangParser, ParseOptions};
 use cooklang_fs::LazyFsIndex;
@@ -13,45 +13,90 @@ use util::metadata_validator;
 mod cmd;
 
 // other modules
+mod adjust;
 mod args;
+mod backup;
+mod capacity;
+mod checklist;
+mod collection_file;
 mod config;
+mod config_layers;
+mod cook_log;
+mod course;
+mod diagnostics;
+mod diet;
+mod encryption;
+mod flatten;
+mod graph;
+mod grocery;
+mod hooks;
+mod hyperlinks;
+mod import;
+mod ingredient_info;
+mod locale;
+mod log_stats;
+mod menu;
+mod ocr;
+mod pantry;
+mod partial;
+mod pick;
+mod plugins;
+mod prep;
+mod prices;
+mod probe;
+mod recipe_notes;
+mod recipe_zip;
+mod remote;
+mod render_cache;
+mod scaling;
+mod schedule;
+mod seasonal;
+mod session;
+mod share;
+mod source_edit;
+mod substitutions;
+mod thumbnails;
+mod timers;
+mod usage;
 mod util;
+mod variables;
+mod variant_sections;
+mod yields;
 
 const COOK_DIR: &str = ".cooklang";
 const APP_NAME: &str = "cooklang-chef";
+/// Sent with every HTTP request chef makes
+const USER_AGENT: &str = concat!("chef/", env!("CARGO_PKG_VERSION"), " (cooklang-chef)");
 const UTF8_PATH_PANIC: &str = "chef only supports UTF-8 paths. If this is problem for you, file an issue in the cooklang-chef github repository";
 
 pub fn main() -> Result<()> {
-    let args = CliArgs::parse();
+    let matches = CliArgs::command().get_matches();
+    let args = CliArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let command_name = matches.subcommand_name().unwrap_or_default().to_string();
 
     let color_ctx = init_color(args.global_args.color);
-    if args.global_args.debug_trace {
-        tracing_subscriber::FmtSubscriber::builder()
-            .compact()
-            .with_max_level(tracing::Level::TRACE)
-            .with_span_events(
-                tracing_subscriber::fmt::format::FmtSpan::CLOSE
-                    | tracing_subscriber::fmt::format::FmtSpan::NEW,
-            )
-            .with_ansi(color_ctx.color_stderr)
-            .init();
-    } else {
-        tracing_subscriber::FmtSubscriber::builder()
-            .compact()
-            .with_target(false)
-            .with_ansi(color_ctx.color_stderr)
-            .init();
-    }
+    init_tracing(&args.global_args, &color_ctx);
 
     match args.command {
         Command::GenerateCompletions(args) => return cmd::generate_completions::run(args),
+        // a new machine may not have a valid config yet
+        Command::Restore(args) => return cmd::restore::run(args),
+        Command::Init(args) => return cmd::init::run(args),
+        // reports an invalid config instead of failing on it
+        Command::Doctor(doctor) => return cmd::doctor::run(doctor, args.global_args, color_ctx),
         _ => {}
     }
 
     let ctx = configure_context(args.global_args, color_ctx)?;
 
-    match args.command {
-        Command::Recipe(args) => cmd::recipe::run(&ctx, args),
+    let start = std::time::Instant::now();
+    let usage = ctx
+        .chef_config
+        .usage_stats
+        .then(|| std::sync::Arc::clone(&ctx.usage));
+    let span = tracing::debug_span!("command", name = %command_name).entered();
+    let res = match args.command {
+        Command::Recipe(args) => cmd::recipe::run(&ctx, *args),
         Command::List(args) => cmd::list::run(&ctx, args),
         #[cfg(feature = "serve")]
         Command::Serve(args) => cmd::serve::run(ctx, args),
@@ -61,8 +106,79 @@ pub fn main() -> Result<()> {
         Command::Config(args) => cmd::config::run(&ctx, args),
         Command::Collection(args) => cmd::collection::run(&ctx, args),
         Command::New(args) => cmd::new::run(args, &ctx),
+        Command::Import(args) => cmd::import::run(&ctx, args),
         Command::Edit(args) => cmd::edit::run(args, &ctx),
-        Command::GenerateCompletions(_) => unreachable!(),
+        Command::Bulk(args) => cmd::bulk::run(&ctx, args),
+        Command::Refactor(args) => cmd::refactor::run(&ctx, args),
+        Command::Dedupe(args) => cmd::dedupe::run(&ctx, args),
+        Command::Stats(args) => cmd::stats::run(&ctx, args),
+        Command::Check(args) => cmd::check::run(&ctx, args),
+        Command::Tokens(args) => cmd::tokens::run(&ctx, args),
+        Command::Cook(args) => cmd::cook::run(&ctx, args),
+        Command::Log(args) => cmd::log::run(&ctx, args),
+        #[cfg(feature = "browse")]
+        Command::Browse(args) => cmd::browse::run(&ctx, args),
+        Command::Cache(args) => cmd::cache::run(args),
+        Command::Nutrition(args) => cmd::nutrition::run(&ctx, args),
+        Command::Cost(args) => cmd::cost::run(&ctx, args),
+        Command::Pantry(args) => cmd::pantry::run(&ctx, args),
+        Command::Fork(args) => cmd::fork::run(&ctx, args),
+        Command::Substitutions(args) => cmd::substitutions::run(&ctx, args),
+        Command::Search(args) => cmd::search::run(&ctx, args),
+        Command::Random(args) => cmd::random::run(&ctx, args),
+        Command::Seasonal(args) => cmd::seasonal::run(&ctx, args),
+        Command::SeasonalIngredients(args) => cmd::seasonal_ingredients::run(&ctx, args),
+        Command::Adjust(args) => cmd::adjust::run(&ctx, args),
+        Command::Menu(args) => cmd::menu::run(&ctx, args),
+        Command::Graph(args) => cmd::graph::run(&ctx, args),
+        Command::Migrate(args) => cmd::migrate::run(&ctx, args),
+        Command::Sync(args) => cmd::sync::run(&ctx, args),
+        Command::Encrypt(args) => cmd::encrypt::run(&ctx, args),
+        Command::Share(args) => cmd::share::run(&ctx, args),
+        Command::Receive(args) => cmd::receive::run(&ctx, args),
+        Command::Backup(args) => cmd::backup::run(&ctx, args),
+        Command::Zip(args) => cmd::zip::run(&ctx, args),
+        Command::SelfTest(args) => cmd::self_test::run(&ctx, args),
+        Command::Bench(args) => cmd::bench::run(&ctx, args),
+        Command::GenerateCompletions(_)
+        | Command::Restore(_)
+        | Command::Init(_)
+        | Command::Doctor(_) => {
+            unreachable!()
+        }
+    };
+    drop(span);
+    if let Some(usage) = usage {
+        if let Err(e) = usage.record(&command_name, start.elapsed(), res.is_ok()) {
+            tracing::warn!("Could not record usage: {e:#}");
+        }
+    }
+    res
+}
+
+fn init_tracing(args: &GlobalArgs, color_ctx: &ColorContext) {
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    let (level, spans) = if args.debug_trace {
+        (tracing::Level::TRACE, FmtSpan::NEW | FmtSpan::CLOSE)
+    } else {
+        let level = tracing::Level::from(args.log_level);
+        // closed spans have the time they took
+        let spans = if level >= tracing::Level::DEBUG {
+            FmtSpan::CLOSE
+        } else {
+            FmtSpan::NONE
+        };
+        (level, spans)
+    };
+    let builder = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(level)
+        .with_span_events(spans)
+        .with_target(args.debug_trace)
+        .with_writer(std::io::stderr);
+    match args.log_format {
+        LogFormat::Human => builder.compact().with_ansi(color_ctx.color_stderr).init(),
+        LogFormat::Json => builder.json().with_current_span(true).init(),
     }
 }
 
@@ -86,6 +202,8 @@ pub struct Context {
     chef_config: config::ChefConfig,
     color: ColorContext,
     is_collection: bool,
+    /// Recipes used by the command, for the usage stats
+    usage: std::sync::Arc<usage::Usage>,
 }
 
 #[tracing::instrument(level = "debug", skip_all)]
@@ -93,9 +211,15 @@ fn configure_context(args: GlobalArgs, color_ctx: ColorContext) -> Result<Contex
     let chef_config: ChefConfig =
         global_load(CHEF_CONFIG_FILE).context("Error loading global config file")?;
 
+    let remote_dir = args
+        .remote
+        .as_deref()
+        .map(|name| remote::synced_dir(name, &chef_config.remotes))
+        .transpose()?;
     let base_path = args
         .path
         .as_deref()
+        .or(remote_dir.as_deref())
         .or_else(|| {
             Utf8Path::new(COOK_DIR)
                 .is_dir()
@@ -108,14 +232,19 @@ fn configure_context(args: GlobalArgs, color_ctx: ColorContext) -> Result<Contex
     }
 
     let mut config = if let Some(file) = &args.config_file {
-        Config::read(file)?
+        Config::read(Some(file.as_path()))?
+    } else if args.path.is_none() && remote_dir.is_some() {
+        // the config of a remote is someone else's, it could run commands
+        Config::read(None)?
     } else {
-        Config::read(&config::config_file_path(base_path))?
+        Config::read(Some(config::config_file_path(base_path).as_path()))?
     };
     config.override_with_args(&args);
+    encryption::install(config.encryption.clone(), base_path);
 
     let recipe_index = cooklang_fs::new_index(base_path, config.max_depth)?
         .config_dir(COOK_DIR.to_string())
+        .rules(config.discovery.clone())
         .lazy();
 
     Ok(Context {
@@ -127,6 +256,7 @@ fn configure_context(args: GlobalArgs, color_ctx: ColorContext) -> Result<Contex
         chef_config,
         global_args: args,
         color: color_ctx,
+        usage: Default::default(),
     })
 }
 
@@ -134,14 +264,15 @@ const RECIPE_REF_ERROR: &str = "The name must match exactly except lower and upp
 
 impl Context {
     fn parser(&self) -> Result<&CooklangParser> {
-        self.parser
-            .get_or_try_init(|| configure_parser(&self.config, &self.base_path))
+        self.parser.get_or_try_init(|| {
+            configure_parser(&self.config, &self.chef_config.plugins, &self.base_path)
+        })
     }
 
     fn checker(
         &self,
         relative_to: Option<&Utf8Path>,
-    ) -> Option<cooklang::analysis::RecipeRefCheck> {
+    ) -> Option<cooklang::analysis::RecipeRefCheck<'_>> {
         if self.config.recipe_ref_check {
             let relative_to = relative_to.map(|r| {
                 r.to_path_buf()
@@ -165,7 +296,7 @@ impl Context {
         }
     }
 
-    fn parse_options(&self, relative_to: Option<&Utf8Path>) -> ParseOptions {
+    fn parse_options(&self, relative_to: Option<&Utf8Path>) -> ParseOptions<'_> {
         ParseOptions {
             recipe_ref_check: self.checker(relative_to),
             metadata_validator: Some(Box::new(metadata_validator)),
@@ -174,9 +305,14 @@ impl Context {
 }
 
 #[tracing::instrument(level = "debug", skip_all)]
-fn configure_parser(config: &Config, base_path: &Utf8Path) -> Result<CooklangParser> {
+fn configure_parser(
+    config: &Config,
+    plugins: &[plugins::PluginConfig],
+    base_path: &Utf8Path,
+) -> Result<CooklangParser> {
     let units = config.units(base_path);
-    let converter = if config.default_units || !units.is_empty() {
+    let plugin_units = plugins::units(plugins);
+    let converter = if config.default_units || !units.is_empty() || !plugin_units.is_empty() {
         let mut builder = ConverterBuilder::new();
         if config.default_units {
             builder
@@ -190,6 +326,9 @@ fn configure_parser(config: &Config, base_path: &Utf8Path) -> Result<CooklangPar
             let units = toml::from_str(&text)?;
             builder.add_units_file(units)?;
         }
+        for units in plugin_units {
+            builder.add_units_file(units)?;
+        }
         builder.finish().context("Can't build unit configuration")?
     } else {
         Converter::empty()
@@ -0,0 +1,127 @@
+//! Menu files
+//!
+//! A menu composes some recipes into a meal, each one with its own servings:
+//!
+//! ```toml
+//! name = "Sunday dinner"
+//!
+//! [[recipes]]
+//! recipe = "Mains/Roast Chicken"
+//! servings = 6
+//!
+//! [[recipes]]
+//! recipe = "Potatoes"     # default servings
+//! ```
+//!
+//! Recipes are found like in any other command, by path or name. They are
+//! ordered by their `course` metadata, see [`crate::course`], and the ones
+//! of the same course keep the order of the file.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context as _, Result};
+use camino::Utf8Path;
+use cooklang::{ingredient_list::IngredientList, ScaledRecipe};
+use cooklang_to_human::ScaleMark;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    course::{course_key, recipe_course},
+    prep::Preparations,
+    scaling::scale_recipe,
+    util::{meta_name, resolve_recipe, Input},
+    Context,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Menu {
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub recipes: Vec<MenuRecipe>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MenuRecipe {
+    pub recipe: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub servings: Option<u32>,
+}
+
+impl Menu {
+    pub fn read(path: &Utf8Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read menu file: {path}"))?;
+        let menu: Self =
+            toml::from_str(&content).with_context(|| format!("Bad menu file: {path}"))?;
+        if menu.recipes.is_empty() {
+            anyhow::bail!("The menu has no recipes: {path}");
+        }
+        Ok(menu)
+    }
+}
+
+/// A recipe of a menu, scaled
+pub struct MenuItem {
+    pub name: String,
+    pub servings: Option<u32>,
+    pub course: Option<String>,
+    pub recipe: ScaledRecipe,
+    pub marks: HashMap<usize, ScaleMark>,
+}
+
+/// Resolves, parses and scales the recipes of a menu
+pub fn load(ctx: &Context, menu: &Menu) -> Result<Vec<MenuItem>> {
+    let converter = ctx.parser()?.converter();
+    let mut items = Vec::new();
+    for entry in &menu.recipes {
+        let input = Input::File {
+            entry: resolve_recipe(ctx, &entry.recipe)?,
+            override_name: None,
+        };
+        let recipe = input
+            .parse(ctx)
+            .with_context(|| format!("Error in menu recipe '{}'", entry.recipe))?;
+        let (recipe, marks) = scale_recipe(recipe, entry.servings, converter, &ctx.config.scaling);
+        let name = meta_name(&recipe.metadata)
+            .unwrap_or(input.name()?)
+            .to_string();
+        items.push(MenuItem {
+            name,
+            servings: entry.servings,
+            course: recipe_course(&recipe.metadata),
+            recipe,
+            marks,
+        });
+    }
+    items.sort_by_cached_key(|item| course_key(item.course.as_deref()));
+    Ok(items)
+}
+
+/// Ingredients of all the recipes, merged
+pub fn ingredients(ctx: &Context, items: &[MenuItem]) -> Result<IngredientList> {
+    let converter = ctx.parser()?.converter();
+    let preps = Preparations::new(&ctx.config.prep);
+    let mut list = IngredientList::new();
+    for item in items {
+        preps.add_recipe(&mut list, &item.recipe, converter);
+    }
+    Ok(list)
+}
+
+/// Cookware of all the recipes, with the recipes that use each one
+pub fn cookware(items: &[MenuItem]) -> BTreeMap<String, Vec<&str>> {
+    let mut cookware = BTreeMap::<String, Vec<&str>>::new();
+    for item in items {
+        for c in &item.recipe.cookware {
+            if !c.modifiers().should_be_listed() {
+                continue;
+            }
+            let used_by = cookware.entry(c.name.to_lowercase()).or_default();
+            if !used_by.contains(&item.name.as_str()) {
+                used_by.push(&item.name);
+            }
+        }
+    }
+    cookware
+}
@@ -0,0 +1,130 @@
+//! Text of photographed or scanned recipes
+//!
+//! `chef import --image` reads the text of the image with the backend in the
+//! `[ocr]` table of the global chef config, `tesseract` if not set. It's not
+//! read from the collection config, it runs a command or has tokens:
+//!
+//! ```toml
+//! [ocr]
+//! backend = "tesseract"
+//! language = "eng+spa"   # optional, the `-l` of tesseract
+//! ```
+//!
+//! or an HTTP endpoint that gets the image as the body and answers with the
+//! text, plain or as JSON with a `text` field:
+//!
+//! ```toml
+//! [ocr]
+//! backend = "http"
+//! url = "http://localhost:8884/ocr"
+//! headers = { Authorization = "Bearer ..." }
+//! ```
+
+use std::{collections::BTreeMap, process::Command, time::Duration};
+
+use anyhow::{bail, Context as _, Result};
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+
+/// OCR of a big image can be slow, but a dead endpoint shouldn't hang
+const HTTP_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum OcrConfig {
+    /// The `tesseract` program
+    Tesseract {
+        #[serde(default = "default_tesseract")]
+        command: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        language: Option<String>,
+    },
+    /// An HTTP endpoint, the image is sent with a POST
+    Http {
+        url: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+fn default_tesseract() -> String {
+    "tesseract".into()
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        OcrConfig::Tesseract {
+            command: default_tesseract(),
+            language: None,
+        }
+    }
+}
+
+/// Reads the text of an image
+pub trait Ocr {
+    fn recognize(&self, image: &Utf8Path) -> Result<String>;
+}
+
+impl Ocr for OcrConfig {
+    fn recognize(&self, image: &Utf8Path) -> Result<String> {
+        match self {
+            OcrConfig::Tesseract { command, language } => tesseract(command, language, image),
+            OcrConfig::Http { url, headers } => http(url, headers, image),
+        }
+    }
+}
+
+fn tesseract(command: &str, language: &Option<String>, image: &Utf8Path) -> Result<String> {
+    let mut cmd = Command::new(command);
+    cmd.arg(image).arg("stdout");
+    if let Some(language) = language {
+        cmd.args(["-l", language]);
+    }
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to run {command}, is it installed?"))?;
+    if !output.status.success() {
+        bail!(
+            "{command} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).context("The text of the image is not UTF-8")
+}
+
+fn http(url: &str, headers: &BTreeMap<String, String>, image: &Utf8Path) -> Result<String> {
+    let bytes = std::fs::read(image).with_context(|| format!("Failed to read {image}"))?;
+    let content_type = match image.extension().map(str::to_lowercase).as_deref() {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        Some("gif") => "image/gif",
+        Some("heic") => "image/heic",
+        Some("tif" | "tiff") => "image/tiff",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    };
+    let mut request = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(10))
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .post(url)
+        .set("Content-Type", content_type);
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    let response = request
+        .send_bytes(&bytes)
+        .with_context(|| format!("OCR request to {url} failed"))?;
+    if response.content_type() == "application/json" {
+        let value: serde_json::Value = response.into_json()?;
+        match value.get("text").and_then(|t| t.as_str()) {
+            Some(text) => Ok(text.to_string()),
+            None => bail!("The OCR answer has no `text` field"),
+        }
+    } else {
+        response
+            .into_string()
+            .context("The text of the image is not valid text")
+    }
+}
@@ -0,0 +1,419 @@
+//! What is already at home
+//!
+//! The pantry is a TOML file, `.cooklang/pantry.toml` or `load.pantry`, with
+//! one table per item:
+//!
+//! ```toml
+//! [flour]
+//! quantity = "1 kg"
+//! category = "baking"
+//! expires = "2025-06-01"
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use cooklang::{
+    ingredient_list::IngredientList,
+    quantity::{GroupedQuantity, Number},
+    Converter, Quantity, Value,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{scaling::parse_amount, util::convert_value, Context};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Pantry {
+    pub items: BTreeMap<String, PantryItem>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PantryItem {
+    /// Free text, like `500 g` or `2 cans`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub barcode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<Date>,
+}
+
+/// Items are expiring soon when they expire in this many days
+pub const EXPIRING_SOON_DAYS: i64 = 7;
+
+impl Pantry {
+    pub fn path(ctx: &Context) -> Utf8PathBuf {
+        ctx.config.pantry(&ctx.base_path)
+    }
+
+    /// Loads the pantry, empty if there is no file yet
+    pub fn load(ctx: &Context) -> Result<Self> {
+        Self::read(&Self::path(ctx))
+    }
+
+    pub fn read(path: &Utf8Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        tracing::debug!("Loading pantry {path}");
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read pantry file: {path}"))?;
+        toml::from_str(&text).with_context(|| format!("Bad pantry file: {path}"))
+    }
+
+    /// Items expiring in `days` days or less, expired ones included, sorted
+    /// by date
+    pub fn expiring(&self, days: i64) -> Vec<(&str, &PantryItem, i64)> {
+        let today = Date::today();
+        let mut items = self
+            .items
+            .iter()
+            .filter_map(|(name, item)| {
+                let left = item.expires?.days_since(today);
+                (left <= days).then_some((name.as_str(), item, left))
+            })
+            .collect::<Vec<_>>();
+        items.sort_by_key(|&(name, _, left)| (left, name));
+        items
+    }
+
+    /// Removes from a shopping list what is already in the pantry
+    ///
+    /// Names match ignoring case. An item without a quantity is enough for
+    /// any amount. A quantity is subtracted from the amounts in the list it
+    /// can be converted to, the others, like `2 cans` for `400 g`, stay.
+    pub fn subtract(&self, list: IngredientList, converter: &Converter) -> IngredientList {
+        let items = self
+            .items
+            .iter()
+            .map(|(name, item)| (name.to_lowercase(), item))
+            .collect::<HashMap<_, _>>();
+        let mut remaining = IngredientList::new();
+        for (name, grouped) in list {
+            let Some(item) = items.get(&name.to_lowercase()) else {
+                remaining.add_ingredient(name, &grouped, converter);
+                continue;
+            };
+            let Some(quantity) = &item.quantity else {
+                continue;
+            };
+            let Ok((mut have, unit)) = parse_amount(quantity) else {
+                remaining.add_ingredient(name, &grouped, converter);
+                continue;
+            };
+            let mut left = GroupedQuantity::empty();
+            for q in grouped.into_vec() {
+                if let Some(q) = use_amount(q, &mut have, unit.as_deref(), converter) {
+                    left.add(&q, converter);
+                }
+            }
+            if !left.is_empty() {
+                remaining.add_ingredient(name, &left, converter);
+            }
+        }
+        remaining
+    }
+
+    pub fn save(&self, ctx: &Context) -> Result<()> {
+        let path = Self::path(ctx);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string_pretty(self)?;
+        std::fs::write(&path, text).with_context(|| format!("Cannot write pantry file: {path}"))
+    }
+}
+
+/// Takes what it can of `have` for a quantity, and returns what is missing
+///
+/// `have` is in `unit` and is reduced by what was used.
+fn use_amount(
+    q: Quantity,
+    have: &mut f64,
+    unit: Option<&str>,
+    converter: &Converter,
+) -> Option<Quantity> {
+    let Value::Number(need) = q.value() else {
+        return Some(q);
+    };
+    let need = need.value();
+    let available = match (unit, q.unit()) {
+        (None, None) => Some(*have),
+        (Some(from), Some(to)) => convert_value(*have, from, to, converter),
+        _ => None,
+    };
+    let Some(available) = available.filter(|a| *a > 0.0) else {
+        return Some(q);
+    };
+    if available >= need {
+        *have *= 1.0 - need / available;
+        return None;
+    }
+    *have = 0.0;
+    let missing = Value::Number(Number::Regular(need - available));
+    Some(Quantity::new(missing, q.unit().map(str::to_string)))
+}
+
+/// A calendar date, written as `YYYY-MM-DD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    /// Days since 1970-01-01
+    days: i64,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        Self::from_time(std::time::SystemTime::now())
+    }
+
+    /// The day of a point in time, in UTC
+    pub fn from_time(time: std::time::SystemTime) -> Self {
+        let secs = time
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            days: (secs / 86_400) as i64,
+        }
+    }
+
+    pub fn add_days(self, days: i64) -> Self {
+        Self {
+            days: self.days + days,
+        }
+    }
+
+    /// Days from `other` to `self`, negative if `self` is before
+    pub fn days_since(self, other: Self) -> i64 {
+        self.days - other.days
+    }
+
+    /// How long before `today` it is, like `3 weeks ago`
+    pub fn ago(self, today: Self) -> String {
+        let days = today.days_since(self);
+        let (n, unit) = match days {
+            ..=0 => return "today".to_string(),
+            1 => return "yesterday".to_string(),
+            2..=13 => (days, "day"),
+            14..=59 => (days / 7, "week"),
+            60..=729 => (days / 30, "month"),
+            _ => (days / 365, "year"),
+        };
+        format!("{n} {unit}s ago")
+    }
+
+    // from <http://howardhinnant.github.io/date_algorithms.html>
+    pub fn from_ymd(y: i64, m: u32, d: u32) -> Self {
+        let y = if m <= 2 { y - 1 } else { y };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let m = m as i64;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        Self {
+            days: era * 146_097 + doe - 719_468,
+        }
+    }
+
+    pub fn ymd(self) -> (i64, u32, u32) {
+        let z = self.days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let m = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+        let y = yoe + era * 400 + i64::from(m <= 2);
+        (y, m, d)
+    }
+}
+
+impl std::str::FromStr for Date {
+    type Err = String;
+
+    /// `YYYY-MM-DD` or `+N` for N days from today
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(days) = s.strip_prefix('+') {
+            let days = days
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid number of days: '{days}'"))?;
+            return Ok(Date::today().add_days(days));
+        }
+        let err = || format!("Invalid date '{s}', expected YYYY-MM-DD");
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(err);
+        let y = next()?.parse::<i64>().map_err(|_| err())?;
+        let m = next()?.parse::<u32>().map_err(|_| err())?;
+        let d = next()?.parse::<u32>().map_err(|_| err())?;
+        let date = Date::from_ymd(y, m, d);
+        // catches days out of the month, like 02-30
+        if !(1..=12).contains(&m) || date.ymd() != (y, m, d) {
+            return Err(err());
+        }
+        Ok(date)
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (y, m, d) = self.ymd();
+        write!(f, "{y:04}-{m:02}-{d:02}")
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(value: Date) -> Self {
+        value.to_string()
+    }
+}
+
+/// Formats a time as an RFC 3339 timestamp in UTC, like feeds want
+pub fn rfc3339(time: std::time::SystemTime) -> String {
+    let date = Date::from_time(time);
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+        % 86_400;
+    format!(
+        "{date}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// A product found by its barcode
+#[derive(Debug)]
+pub struct Product {
+    pub name: String,
+    pub quantity: Option<String>,
+    pub category: Option<String>,
+}
+
+/// Looks up a product in Open Food Facts
+pub fn lookup_barcode(barcode: &str) -> Result<Product> {
+    #[derive(Deserialize)]
+    struct Response {
+        product: Option<OffProduct>,
+    }
+    #[derive(Deserialize)]
+    struct OffProduct {
+        product_name: Option<String>,
+        quantity: Option<String>,
+        #[serde(default)]
+        categories_tags: Vec<String>,
+    }
+
+    if !(8..=14).contains(&barcode.len()) || !barcode.bytes().all(|b| b.is_ascii_digit()) {
+        bail!("Invalid barcode '{barcode}', it must have 8 to 14 digits");
+    }
+
+    let url = format!(
+        "https://world.openfoodfacts.org/api/v2/product/{barcode}.json?fields=product_name,quantity,categories_tags"
+    );
+    tracing::debug!("Fetching {url}");
+    let res = match ureq::get(&url).set("User-Agent", crate::USER_AGENT).call() {
+        Ok(res) => res,
+        Err(ureq::Error::Status(404, _)) => bail!("Product {barcode} not found in Open Food Facts"),
+        Err(e) => return Err(e).context("Failed to reach Open Food Facts"),
+    };
+    let res: Response = res
+        .into_json()
+        .context("Invalid response from Open Food Facts")?;
+    let Some(product) = res.product else {
+        bail!("Product {barcode} not found in Open Food Facts");
+    };
+    let Some(name) = product.product_name.filter(|n| !n.trim().is_empty()) else {
+        bail!("Product {barcode} has no name in Open Food Facts");
+    };
+    // the last tag is the most specific, like `en:wheat-flours`
+    let category = product.categories_tags.last().map(|tag| {
+        let tag = tag.split_once(':').map_or(tag.as_str(), |(_, t)| t);
+        tag.replace('-', " ")
+    });
+    Ok(Product {
+        name: name.trim().to_lowercase(),
+        quantity: product.quantity.filter(|q| !q.trim().is_empty()),
+        category,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        let d: Date = "2024-02-29".parse().unwrap();
+        assert_eq!(d.to_string(), "2024-02-29");
+        assert_eq!(d.days_since("1970-01-01".parse().unwrap()), 19_782);
+        assert_eq!(d.add_days(1).to_string(), "2024-03-01");
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2023-13-01".parse::<Date>().is_err());
+        assert!("yesterday".parse::<Date>().is_err());
+        let t = std::time::UNIX_EPOCH + std::time::Duration::from_secs(19_782 * 86_400 + 3661);
+        assert_eq!(rfc3339(t), "2024-02-29T01:01:01Z");
+        assert_eq!(Date::from_time(t), d);
+        assert_eq!(d.ago(d), "today");
+        assert_eq!(d.ago(d.add_days(1)), "yesterday");
+        assert_eq!(d.ago(d.add_days(20)), "2 weeks ago");
+        assert_eq!(d.ago(d.add_days(400)), "13 months ago");
+        assert_eq!(d.ago(d.add_days(800)), "2 years ago");
+    }
+
+    #[test]
+    fn subtract_from_list() {
+        let parser = cooklang::CooklangParser::default();
+        let converter = parser.converter();
+        let recipe = parser
+            .parse("Mix @flour{500%g}, @Milk{2%cups}, @salt{1%tsp}, @eggs{3} and @tomatoes{400%g}.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        let mut list = IngredientList::new();
+        list.add_recipe(&recipe, converter);
+
+        let pantry: Pantry = toml::from_str(
+            r#"
+            flour.quantity = "200 g"
+            milk.quantity = "1 l"
+            salt = {}
+            eggs.quantity = "2"
+            tomatoes.quantity = "2 cans"
+            "#,
+        )
+        .unwrap();
+        let list = pantry.subtract(list, converter);
+        let left = list
+            .iter()
+            .map(|(name, q)| {
+                let q = q.iter().map(ToString::to_string).collect::<Vec<_>>();
+                (name.as_str(), q.join(", "))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            left,
+            [
+                ("eggs", "1".to_string()),
+                ("flour", "300 g".to_string()),
+                // can't tell how much is in a can
+                ("tomatoes", "400 g".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Rendering recipes with errors
+//!
+//! A recipe with errors can't be rendered, but while writing it it's useful
+//! to see the rest of it. The blocks of the text with an error, separated by
+//! blank lines, are replaced by a text step with the error and the recipe is
+//! parsed again. The valid sections and steps are kept, and the broken spots
+//! are marked where they were.
+
+use cooklang::{
+    error::{Severity, SourceReport},
+    RecipeResult, ScalableRecipe,
+};
+
+/// Start of the text steps that replace the broken blocks
+pub const ERROR_MARKER: &str = "⚠";
+
+/// Times the text is parsed again, fixing a block can uncover other errors
+const MAX_ATTEMPTS: usize = 5;
+
+/// Parses the valid parts of a recipe with errors
+///
+/// `parse` is called with the text with the broken blocks replaced. `None`
+/// if no error has a location or it's still broken after a few attempts.
+pub fn parse_partial(text: &str, parse: impl Fn(&str) -> RecipeResult) -> Option<ScalableRecipe> {
+    let mut text = text.to_string();
+    for _ in 0..MAX_ATTEMPTS {
+        let res = parse(&text);
+        if res.is_valid() {
+            return res.into_result().ok().map(|(recipe, _)| recipe);
+        }
+        let marked = mark_errors(&text, res.report())?;
+        if marked == text {
+            return None;
+        }
+        text = marked;
+    }
+    None
+}
+
+/// Replaces the blocks with errors by a text step with the messages
+///
+/// `None` if no error has a location.
+pub fn mark_errors(text: &str, report: &SourceReport) -> Option<String> {
+    let blocks = blocks(text);
+    let mut messages = vec![Vec::new(); blocks.len()];
+    let mut any = false;
+    for diag in report
+        .iter()
+        .filter(|d| matches!(d.severity, Severity::Error))
+    {
+        let Some((span, _)) = diag.labels.first() else {
+            continue;
+        };
+        let Some(block) = blocks.iter().position(|b| b.contains(&span.start())) else {
+            continue;
+        };
+        let line = text[..span.start()].matches('\n').count() + 1;
+        messages[block].push(format!("{} (line {line})", diag.message));
+        any = true;
+    }
+    if !any {
+        return None;
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (block, messages) in blocks.iter().zip(&messages) {
+        if messages.is_empty() {
+            continue;
+        }
+        out.push_str(&text[last..block.start]);
+        out.push_str(&format!(
+            "> {ERROR_MARKER} {}\n",
+            sanitize(&messages.join("; "))
+        ));
+        last = block.end;
+    }
+    out.push_str(&text[last..]);
+    Some(out)
+}
+
+/// Byte ranges of the blocks of lines separated by blank lines, the front
+/// matter is a single one
+fn blocks(text: &str) -> Vec<std::ops::Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    let mut in_front_matter = false;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let end = offset + line.len();
+        let trimmed = line.trim();
+        if i == 0 && trimmed == "---" {
+            in_front_matter = true;
+            start = Some(offset);
+        } else if in_front_matter {
+            if trimmed == "---" {
+                in_front_matter = false;
+                blocks.push(start.take().unwrap_or(0)..end);
+            }
+        } else if trimmed.is_empty() {
+            if let Some(s) = start.take() {
+                blocks.push(s..offset);
+            }
+        } else if start.is_none() {
+            start = Some(offset);
+        }
+        offset = end;
+    }
+    if let Some(s) = start {
+        blocks.push(s..text.len());
+    }
+    blocks
+}
+
+/// Removes what would be parsed as cooklang in a message
+fn sanitize(message: &str) -> String {
+    message
+        .replace(['@', '#', '~', '{', '}', '[', ']', '\n'], " ")
+        .replace("--", "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_blocks() {
+        let text = "---\ntitle: a\n\nb: c\n---\nStep one\nstill one\n\n\nStep two";
+        let blocks = blocks(text);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(&text[blocks[0].clone()], "---\ntitle: a\n\nb: c\n---\n");
+        assert_eq!(&text[blocks[1].clone()], "Step one\nstill one\n");
+        assert_eq!(&text[blocks[2].clone()], "Step two");
+        assert_eq!(sanitize("bad @ingredient{}"), "bad  ingredient  ");
+    }
+}
@@ -0,0 +1,150 @@
+//! Picking a random recipe
+//!
+//! The "what's for dinner" button: one of the recipes that match some
+//! constraints, at random or favoring the ones not cooked for a long time
+//! according to the cook log.
+
+use cooklang::{Converter, Metadata};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::pantry::Date;
+
+/// What the picked recipe has to fulfill
+#[derive(Debug, Default)]
+pub struct Constraints {
+    /// All of them
+    pub tags: Vec<String>,
+    /// Total time in minutes
+    pub max_time: Option<u32>,
+    /// Part of an ingredient name, case insensitive
+    pub exclude_ingredients: Vec<String>,
+}
+
+impl Constraints {
+    pub fn matches<'a>(
+        &self,
+        metadata: &Metadata,
+        ingredients: impl IntoIterator<Item = &'a str>,
+        converter: &Converter,
+    ) -> bool {
+        if !self.tags.is_empty() {
+            let tags = metadata.tags().unwrap_or_default();
+            if !self.tags.iter().all(|t| tags.iter().any(|tt| tt == t)) {
+                return false;
+            }
+        }
+        if let Some(max) = self.max_time {
+            if metadata.time(converter).is_none_or(|t| t.total() > max) {
+                return false;
+            }
+        }
+        if !self.exclude_ingredients.is_empty() {
+            let excluded = ingredients.into_iter().any(|name| {
+                let name = name.to_lowercase();
+                self.exclude_ingredients
+                    .iter()
+                    .any(|e| name.contains(&e.to_lowercase()))
+            });
+            if excluded {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Minutes of a time like `45m`, `1h30m`, `2h` or `45`
+pub fn parse_minutes(s: &str) -> Result<u32, String> {
+    let s = s.trim();
+    if let Ok(minutes) = s.parse() {
+        return Ok(minutes);
+    }
+    let err = || format!("Invalid time '{s}', use something like '45m' or '1h30m'");
+    let mut minutes = 0;
+    let mut num = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            num.push(c);
+            continue;
+        }
+        let n: u32 = num.parse().map_err(|_| err())?;
+        num.clear();
+        let n = match c {
+            'h' => n.checked_mul(60),
+            'm' => Some(n),
+            _ => return Err(err()),
+        };
+        minutes = n
+            .and_then(|n| n.checked_add(minutes))
+            .ok_or_else(|| format!("Time '{s}' is too long"))?;
+    }
+    if !num.is_empty() {
+        return Err(err());
+    }
+    Ok(minutes)
+}
+
+/// Days a recipe never cooked counts as
+const NEVER_COOKED_DAYS: i64 = 365;
+
+/// Weight of a recipe when favoring the ones not cooked for long
+pub fn weight(last_cooked: Option<Date>, today: Date) -> u64 {
+    let days = last_cooked.map_or(NEVER_COOKED_DAYS, |d| {
+        today.days_since(d).clamp(0, NEVER_COOKED_DAYS)
+    });
+    days as u64 + 1
+}
+
+/// Index of an item chosen at random with the weights, `None` if empty
+pub fn choose(weights: &[u64]) -> Option<usize> {
+    let mut buf = [0; 8];
+    SystemRandom::new().fill(&mut buf).ok()?;
+    choose_with(weights, u64::from_le_bytes(buf))
+}
+
+fn choose_with(weights: &[u64], random: u64) -> Option<usize> {
+    let total = weights.iter().sum::<u64>();
+    if total == 0 {
+        return None;
+    }
+    let mut n = random % total;
+    for (i, &w) in weights.iter().enumerate() {
+        if n < w {
+            return Some(i);
+        }
+        n -= w;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minutes() {
+        assert_eq!(parse_minutes("45"), Ok(45));
+        assert_eq!(parse_minutes("45m"), Ok(45));
+        assert_eq!(parse_minutes("1h30m"), Ok(90));
+        assert_eq!(parse_minutes("2h"), Ok(120));
+        assert!(parse_minutes("1h30").is_err());
+        assert!(parse_minutes("soon").is_err());
+        assert!(parse_minutes("99999999h").is_err());
+        assert!(parse_minutes("4294967295m1m").is_err());
+    }
+
+    #[test]
+    fn weighted() {
+        let today: Date = "2025-06-12".parse().unwrap();
+        assert_eq!(weight(Some(today), today), 1);
+        assert_eq!(weight(Some(today.add_days(-6)), today), 7);
+        assert_eq!(weight(None, today), 366);
+
+        let weights = [1, 0, 3];
+        assert_eq!(choose_with(&weights, 0), Some(0));
+        assert_eq!(choose_with(&weights, 1), Some(2));
+        assert_eq!(choose_with(&weights, 3), Some(2));
+        assert_eq!(choose_with(&weights, 4), Some(0));
+        assert_eq!(choose_with(&[], 4), None);
+    }
+}
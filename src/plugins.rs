@@ -0,0 +1,245 @@
+//! External data providers
+//!
+//! A plugin is a program declared in the `[[plugins]]` array of the global
+//! chef config that answers one JSON request in stdin with one JSON response
+//! in stdout. This keeps big or proprietary data sources, like nutrition
+//! databases or store prices, out of chef. A collection config can't declare
+//! plugins, so a collection from someone else can't run commands.
+//!
+//! Requests have a `version`, the `request` kind and the request data:
+//!
+//! ```json
+//! {"version":1,"request":"nutrition","ingredients":[{"name":"flour","quantity":200,"unit":"g"}]}
+//! ```
+//!
+//! See [`IngredientsResponse`] and [`UnitsResponse`] for the responses.
+//! Ingredients a plugin doesn't know are left out of the response.
+
+use std::{collections::BTreeMap, process::Stdio, time::Duration};
+
+use anyhow::{bail, Context as _, Result};
+use cooklang::quantity::{GroupedQuantity, Value};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::util::{communicate, shell_command};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A plugin that takes longer is stopped, units plugins run on every command
+const PLUGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PluginConfig {
+    pub name: String,
+    /// Shell command to run
+    pub command: String,
+    /// Requests the plugin answers
+    pub provides: Vec<Capability>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Nutrition,
+    Prices,
+    Units,
+}
+
+#[derive(Debug, Serialize)]
+struct Request<'a, T> {
+    version: u32,
+    request: Capability,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+/// An ingredient amount sent to plugins
+#[derive(Debug, Clone, Serialize)]
+pub struct IngredientAmount {
+    pub name: String,
+    /// `None` when the recipe has no numeric quantity
+    pub quantity: Option<f64>,
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct IngredientsRequest<'a> {
+    ingredients: &'a [IngredientAmount],
+}
+
+/// Answer to a request with ingredients, like the nutrients of the
+/// requested amounts
+#[derive(Debug, Deserialize)]
+pub struct IngredientsResponse<T> {
+    pub ingredients: Vec<T>,
+}
+
+/// An ingredient in a response
+trait Named {
+    fn name(&self) -> &str;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngredientNutrition {
+    pub name: String,
+    /// Nutrient name, like `energy_kcal` or `protein_g`, to amount
+    pub nutrients: BTreeMap<String, f64>,
+}
+
+impl Named for IngredientNutrition {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngredientPrice {
+    pub name: String,
+    pub price: f64,
+    pub currency: Option<String>,
+}
+
+impl Named for IngredientPrice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Extra units, in the same format as a units file
+#[derive(Debug, Deserialize)]
+pub struct UnitsResponse {
+    pub units: cooklang::convert::UnitsFile,
+}
+
+fn with_capability(
+    plugins: &[PluginConfig],
+    capability: Capability,
+) -> impl Iterator<Item = &PluginConfig> {
+    plugins
+        .iter()
+        .filter(move |p| p.provides.contains(&capability))
+}
+
+fn call<T: Serialize, R: DeserializeOwned>(
+    plugin: &PluginConfig,
+    request: Capability,
+    data: &T,
+) -> Result<R> {
+    tracing::debug!("Calling plugin {} for {request:?}", plugin.name);
+    let payload = serde_json::to_vec(&Request {
+        version: PROTOCOL_VERSION,
+        request,
+        data,
+    })?;
+    let child = shell_command(&plugin.command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to start plugin")?;
+    let output = communicate(child, payload, Some(PLUGIN_TIMEOUT))?;
+    if !output.status.success() {
+        bail!("exited with {}", output.status);
+    }
+    serde_json::from_slice(&output.stdout).context("Invalid plugin response")
+}
+
+/// Asks every plugin with a capability, the first plugin knowing an
+/// ingredient wins
+fn ask_ingredients<T: DeserializeOwned + Named>(
+    plugins: &[PluginConfig],
+    capability: Capability,
+    ingredients: &[IngredientAmount],
+) -> Vec<T> {
+    let mut found = Vec::<T>::new();
+    for plugin in with_capability(plugins, capability) {
+        match call::<_, IngredientsResponse<T>>(
+            plugin,
+            capability,
+            &IngredientsRequest { ingredients },
+        ) {
+            Ok(res) => {
+                // names answered by a previous plugin
+                let known = found.len();
+                for igr in res.ingredients {
+                    if !found[..known].iter().any(|f| f.name() == igr.name()) {
+                        found.push(igr);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Plugin {} failed: {e:#}", plugin.name),
+        }
+    }
+    found
+}
+
+/// Asks every nutrition plugin, the first plugin knowing an ingredient wins
+pub fn nutrition(
+    plugins: &[PluginConfig],
+    ingredients: &[IngredientAmount],
+) -> Vec<IngredientNutrition> {
+    ask_ingredients(plugins, Capability::Nutrition, ingredients)
+}
+
+/// Asks every prices plugin, the first plugin knowing an ingredient wins
+pub fn prices(plugins: &[PluginConfig], ingredients: &[IngredientAmount]) -> Vec<IngredientPrice> {
+    ask_ingredients(plugins, Capability::Prices, ingredients)
+}
+
+/// Units from all the units plugins
+pub fn units(plugins: &[PluginConfig]) -> Vec<cooklang::convert::UnitsFile> {
+    with_capability(plugins, Capability::Units)
+        .filter_map(|plugin| {
+            match call::<_, UnitsResponse>(plugin, Capability::Units, &serde_json::json!({})) {
+                Ok(res) => Some(res.units),
+                Err(e) => {
+                    tracing::warn!("Plugin {} failed: {e:#}", plugin.name);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Amounts of the listed ingredients of a recipe
+pub fn recipe_amounts(
+    recipe: &cooklang::ScaledRecipe,
+    converter: &cooklang::Converter,
+) -> Vec<IngredientAmount> {
+    let mut amounts = Vec::new();
+    for entry in recipe.group_ingredients(converter) {
+        if !entry.ingredient.modifiers().should_be_listed() {
+            continue;
+        }
+        amounts.extend(grouped_amounts(
+            &entry.ingredient.display_name(),
+            &entry.quantity,
+        ));
+    }
+    amounts
+}
+
+/// Amounts of an ingredient, one per unit
+///
+/// Plugins do the conversion if they want.
+pub fn grouped_amounts(name: &str, quantity: &GroupedQuantity) -> Vec<IngredientAmount> {
+    let amounts = quantity
+        .iter()
+        .map(|q| IngredientAmount {
+            name: name.to_string(),
+            quantity: match q.value() {
+                Value::Number(n) => Some(n.value()),
+                Value::Range { end, .. } => Some(end.value()),
+                Value::Text(_) => None,
+            },
+            unit: q.unit().map(str::to_string),
+        })
+        .collect::<Vec<_>>();
+    if amounts.is_empty() {
+        return vec![IngredientAmount {
+            name: name.to_string(),
+            quantity: None,
+            unit: None,
+        }];
+    }
+    amounts
+}
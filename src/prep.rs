@@ -0,0 +1,370 @@
+//! Mise en place
+//!
+//! The preparation of an ingredient, like "finely diced" in
+//! `@onion{1}(finely diced)` or `@onion, finely diced{1}`, is a task to do
+//! before the steps. The tasks are grouped by technique, so all the dicing is
+//! done at once, in the usual order of a prep: washing and peeling first,
+//! cutting after.
+//!
+//! A preparation with more than one technique, like "peeled and diced", is a
+//! task in each of them. The ones with no known technique are left out.
+//!
+//! The preparation is not part of the ingredient for the shopping lists, so
+//! `onion, diced` and `onion` are bought together. More ways to write it in
+//! the name and more techniques can be set in the config:
+//!
+//! ```toml
+//! [prep]
+//! patterns = ["{prep} {name}"]   # "diced onion"
+//! techniques = { blanch = ["blanched"], dice = ["brunoise"] }
+//! ```
+
+use std::collections::HashMap;
+
+use cooklang::{
+    ingredient_list::IngredientList, quantity::GroupedQuantity, Converter, ScaledQuantity,
+    ScaledRecipe,
+};
+use serde::{Deserialize, Serialize};
+
+/// Key, name in English and words of each technique, in the order to do them
+const TECHNIQUES: &[(&str, &str, &[&str])] = &[
+    (
+        "wash",
+        "Wash",
+        &["wash", "washed", "rinse", "rinsed", "drain", "drained"],
+    ),
+    ("soak", "Soak", &["soak", "soaked"]),
+    ("peel", "Peel", &["peel", "peeled", "skinned"]),
+    (
+        "seed",
+        "Seed",
+        &["seeded", "deseeded", "pitted", "stoned", "cored"],
+    ),
+    ("trim", "Trim", &["trim", "trimmed"]),
+    ("cut", "Cut", &["cut", "halved", "quartered", "wedges"]),
+    ("chop", "Chop", &["chop", "chopped"]),
+    ("dice", "Dice", &["dice", "diced", "cube", "cubed"]),
+    ("mince", "Mince", &["mince", "minced"]),
+    ("slice", "Slice", &["slice", "sliced"]),
+    ("julienne", "Julienne", &["julienne", "julienned"]),
+    ("grate", "Grate", &["grate", "grated", "shred", "shredded"]),
+    ("crush", "Crush", &["crush", "crushed", "smash", "smashed"]),
+    ("zest", "Zest", &["zest", "zested"]),
+    ("juice", "Juice", &["juice", "juiced", "squeezed"]),
+    ("sift", "Sift", &["sift", "sifted"]),
+    ("beat", "Beat", &["beat", "beaten", "whisk", "whisked"]),
+    ("melt", "Melt", &["melt", "melted"]),
+    ("soften", "Soften", &["soften", "softened"]),
+    ("toast", "Toast", &["toast", "toasted"]),
+];
+
+/// Ways to write the preparation in the name that are always known
+const DEFAULT_PATTERNS: &[&str] = &["{name}, {prep}"];
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct PrepConfig {
+    /// More ways to write the preparation in the name, like `{prep} {name}`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    /// Words of new techniques or more words for the known ones
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub techniques: HashMap<String, Vec<String>>,
+}
+
+/// Ingredient to prepare
+#[derive(Debug, Serialize)]
+pub struct PrepTask {
+    /// Name without the preparation
+    pub ingredient: String,
+    /// How to prepare it, as written in the recipe
+    pub preparation: String,
+    pub quantities: Vec<ScaledQuantity>,
+}
+
+/// Tasks with the same technique
+#[derive(Debug, Serialize)]
+pub struct PrepGroup {
+    /// Key of the technique, like `dice`
+    pub technique: String,
+    /// Name of the technique in English
+    pub label: String,
+    /// If it's one of the known techniques, not only in the config
+    pub builtin: bool,
+    pub tasks: Vec<PrepTask>,
+}
+
+struct Technique {
+    key: String,
+    builtin: bool,
+    words: Vec<String>,
+}
+
+/// Finds the preparations with the known techniques and the config
+pub struct Preparations {
+    patterns: Vec<String>,
+    techniques: Vec<Technique>,
+}
+
+impl Preparations {
+    pub fn new(config: &PrepConfig) -> Self {
+        let mut techniques = TECHNIQUES
+            .iter()
+            .map(|(key, _, words)| Technique {
+                key: key.to_string(),
+                builtin: true,
+                words: words.iter().map(|w| w.to_string()).collect(),
+            })
+            .collect::<Vec<_>>();
+        let mut extra = config.techniques.iter().collect::<Vec<_>>();
+        extra.sort_by_key(|(key, _)| *key);
+        for (key, words) in extra {
+            let words = words.iter().map(|w| w.to_lowercase());
+            match techniques.iter_mut().find(|t| &t.key == key) {
+                Some(t) => t.words.extend(words),
+                None => techniques.push(Technique {
+                    key: key.clone(),
+                    builtin: false,
+                    words: words.collect(),
+                }),
+            }
+        }
+        let patterns = DEFAULT_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .chain(config.patterns.iter().cloned())
+            .collect();
+        Self {
+            patterns,
+            techniques,
+        }
+    }
+
+    /// Splits a name like `onion, finely diced` in the ingredient and the
+    /// preparation, if it has a known technique
+    pub fn split<'a>(&self, name: &'a str) -> (&'a str, Option<&'a str>) {
+        self.patterns
+            .iter()
+            .find_map(|p| self.match_pattern(p, name))
+            .map_or((name, None), |(name, prep)| (name, Some(prep)))
+    }
+
+    /// Name and preparation of a text written like the pattern
+    fn match_pattern<'a>(&self, pattern: &str, text: &'a str) -> Option<(&'a str, &'a str)> {
+        const NAME: &str = "{name}";
+        const PREP: &str = "{prep}";
+        let name_at = pattern.find(NAME)?;
+        let prep_at = pattern.find(PREP)?;
+        let name_first = name_at < prep_at;
+        let (prefix, rest) = pattern.split_at(name_at.min(prep_at));
+        let (sep, suffix) = rest[NAME.len()..].split_once(if name_first { PREP } else { NAME })?;
+        if sep.is_empty() {
+            return None;
+        }
+        let body = text.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        body.match_indices(sep).find_map(|(i, _)| {
+            let (a, b) = (body[..i].trim(), body[i + sep.len()..].trim());
+            let (name, prep) = if name_first { (a, b) } else { (b, a) };
+            (!name.is_empty() && !self.techniques(prep).is_empty()).then_some((name, prep))
+        })
+    }
+
+    /// Indexes of the techniques in a preparation
+    fn techniques(&self, prep: &str) -> Vec<usize> {
+        let prep = prep.to_lowercase();
+        let words = prep
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<_>>();
+        self.techniques
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.words.iter().any(|w| words.contains(&w.as_str())))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Name of an ingredient without its preparation
+    pub fn ingredient_name<'a>(&self, name: &'a str) -> &'a str {
+        self.split(name).0
+    }
+
+    /// Adds the listed ingredients of a recipe to a list, without their
+    /// preparation
+    pub fn add_recipe(
+        &self,
+        list: &mut IngredientList,
+        recipe: &ScaledRecipe,
+        converter: &Converter,
+    ) {
+        for entry in recipe.group_ingredients(converter) {
+            let igr = entry.ingredient;
+            if !igr.modifiers().should_be_listed() {
+                continue;
+            }
+            let name = igr.display_name();
+            list.add_ingredient(
+                self.ingredient_name(&name).to_string(),
+                &entry.quantity,
+                converter,
+            );
+        }
+    }
+
+    /// Prep tasks of the listed ingredients, grouped by technique
+    ///
+    /// Each use of an ingredient is looked at, so `@onion{1}(diced)` and
+    /// `@onion{1}(sliced)` are different tasks, and the same ingredient with
+    /// the same preparation is a single one.
+    pub fn prep_list(&self, recipe: &ScaledRecipe, converter: &Converter) -> Vec<PrepGroup> {
+        let mut groups = self
+            .techniques
+            .iter()
+            .map(|_| Vec::<(String, String, GroupedQuantity)>::new())
+            .collect::<Vec<_>>();
+        for igr in &recipe.ingredients {
+            if !igr.modifiers().should_be_listed() {
+                continue;
+            }
+            let name = igr.display_name();
+            let (ingredient, prep) = match self.split(&name) {
+                (base, Some(prep)) => (base, prep),
+                (base, None) => match igr.note.as_deref() {
+                    Some(note) => (base, note.trim()),
+                    None => continue,
+                },
+            };
+            for t in self.techniques(prep) {
+                let tasks = &mut groups[t];
+                let i = match tasks
+                    .iter()
+                    .position(|(i, p, _)| i == ingredient && p == prep)
+                {
+                    Some(i) => i,
+                    None => {
+                        tasks.push((ingredient.to_string(), prep.to_string(), Default::default()));
+                        tasks.len() - 1
+                    }
+                };
+                if let Some(q) = &igr.quantity {
+                    tasks[i].2.add(q, converter);
+                }
+            }
+        }
+        self.techniques
+            .iter()
+            .zip(groups)
+            .filter(|(_, tasks)| !tasks.is_empty())
+            .map(|(t, tasks)| PrepGroup {
+                technique: t.key.clone(),
+                label: label(&t.key),
+                builtin: t.builtin,
+                tasks: tasks
+                    .into_iter()
+                    .map(|(ingredient, preparation, quantity)| PrepTask {
+                        ingredient,
+                        preparation,
+                        quantities: quantity.iter().cloned().collect(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Name of a technique in English, the key capitalized for the ones in the
+/// config
+fn label(key: &str) -> String {
+    match TECHNIQUES.iter().find(|(k, _, _)| *k == key) {
+        Some((_, label, _)) => label.to_string(),
+        None => {
+            let mut chars = key.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+    }
+}
+
+pub fn write_prep(mut w: impl std::io::Write, groups: &[PrepGroup]) -> std::io::Result<()> {
+    use yansi::Paint;
+
+    if groups.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "{}:", "Mise en place".bold().underline())?;
+    for group in groups {
+        writeln!(w, "  {}", group.label.bold())?;
+        for task in &group.tasks {
+            let quantity = task
+                .quantities
+                .iter()
+                .map(|q| q.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            if quantity.is_empty() {
+                write!(w, "    {}", task.ingredient)?;
+            } else {
+                write!(w, "    {} {}", task.ingredient, quantity.dim())?;
+            }
+            writeln!(w, " {}", format!("({})", task.preparation).italic())?;
+        }
+    }
+    writeln!(w)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preparations() {
+        let preps = Preparations::new(&PrepConfig::default());
+        assert_eq!(
+            preps.split("onion, finely diced"),
+            ("onion", Some("finely diced"))
+        );
+        assert_eq!(preps.split("salt, pepper"), ("salt, pepper", None));
+        assert_eq!(preps.split("garlic"), ("garlic", None));
+
+        let keys = |prep| {
+            preps
+                .techniques(prep)
+                .into_iter()
+                .map(|i| preps.techniques[i].key.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys("Diced and peeled"), ["peel", "dice"]);
+        assert_eq!(keys("at room temperature"), Vec::<&str>::new());
+        assert_eq!(keys("juice of"), ["juice"]);
+    }
+
+    #[test]
+    fn config() {
+        let config = PrepConfig {
+            patterns: vec!["{prep} {name}".into(), "{name} ({prep})".into()],
+            techniques: HashMap::from([
+                ("blanch".into(), vec!["blanched".into()]),
+                ("dice".into(), vec!["brunoise".into()]),
+            ]),
+        };
+        let preps = Preparations::new(&config);
+        assert_eq!(
+            preps.split("finely diced red onion"),
+            ("red onion", Some("finely diced"))
+        );
+        assert_eq!(
+            preps.split("almonds (blanched)"),
+            ("almonds", Some("blanched"))
+        );
+        assert_eq!(
+            preps.split("carrot, brunoise"),
+            ("carrot", Some("brunoise"))
+        );
+        assert_eq!(preps.split("red onion"), ("red onion", None));
+        assert_eq!(label("blanch"), "Blanch");
+    }
+}
@@ -0,0 +1,177 @@
+//! Ingredient prices and recipe costing
+//!
+//! Prices come from a prices file, set in `load.prices` or found as
+//! `prices.toml`, with the price of a purchase unit of each ingredient:
+//!
+//! ```toml
+//! currency = "EUR"
+//!
+//! [ingredients]
+//! flour = { price = 1.20, quantity = 1, unit = "kg" }
+//! egg = { price = 3.50, quantity = 12 }
+//! ```
+//!
+//! Recipe amounts are converted to the purchase unit with the converter.
+//! Ingredients not in the file are asked to the prices plugins.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context as _, Result};
+use camino::Utf8Path;
+use cooklang::Converter;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    plugins::{self, IngredientAmount, PluginConfig},
+    util::convert_value,
+    Context,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PricesFile {
+    pub currency: Option<String>,
+    pub ingredients: HashMap<String, Price>,
+}
+
+/// Price of a purchase unit
+#[derive(Debug, Deserialize)]
+pub struct Price {
+    pub price: f64,
+    #[serde(default = "one")]
+    pub quantity: f64,
+    pub unit: Option<String>,
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+impl PricesFile {
+    pub fn load(ctx: &Context) -> Result<Self> {
+        Self::load_from(&ctx.config, &ctx.base_path)
+    }
+
+    pub fn load_from(config: &Config, base_path: &Utf8Path) -> Result<Self> {
+        let Some(path) = config.prices(base_path) else {
+            return Ok(Self::default());
+        };
+        tracing::debug!("Loading prices {path}");
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read prices file: {path}"))?;
+        let mut prices: Self =
+            toml::from_str(&text).with_context(|| format!("Bad prices file: {path}"))?;
+        prices.ingredients = prices
+            .ingredients
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect();
+        Ok(prices)
+    }
+
+    /// Cost of an amount, `None` if it has no price or the units don't match
+    pub fn cost(&self, amount: &IngredientAmount, converter: &Converter) -> Option<f64> {
+        let price = self.ingredients.get(&amount.name.to_lowercase())?;
+        let value = amount.quantity?;
+        let value = match (&amount.unit, &price.unit) {
+            (None, None) => value,
+            (Some(from), Some(to)) => convert_value(value, from, to, converter)?,
+            _ => return None,
+        };
+        if price.quantity <= 0.0 {
+            return None;
+        }
+        Some(value / price.quantity * price.price)
+    }
+
+    /// Costs of the amounts, from the file and then the plugins
+    pub fn costs(
+        &self,
+        plugins: &[PluginConfig],
+        converter: &Converter,
+        amounts: &[IngredientAmount],
+    ) -> Costs {
+        let mut ingredients = BTreeMap::<String, Option<f64>>::new();
+        for amount in amounts {
+            let cost = self.cost(amount, converter);
+            ingredients
+                .entry(amount.name.clone())
+                .and_modify(|c| *c = c.zip(cost).map(|(a, b)| a + b))
+                .or_insert(cost);
+        }
+
+        let mut currency = self.currency.clone();
+        let unpriced = amounts
+            .iter()
+            .filter(|a| ingredients[&a.name].is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        if !unpriced.is_empty() {
+            // a plugin answers once per amount, so add them up. An
+            // ingredient with a price in another currency stays unpriced.
+            let mut priced = BTreeMap::<String, Option<f64>>::new();
+            for price in plugins::prices(plugins, &unpriced) {
+                if !ingredients.contains_key(&price.name) {
+                    continue;
+                }
+                if currency.is_none() {
+                    currency.clone_from(&price.currency);
+                }
+                if price.currency.is_some() && price.currency != currency {
+                    tracing::warn!(
+                        "Ignoring the price of '{}' in {}, the costs are in {}",
+                        price.name,
+                        price.currency.unwrap_or_default(),
+                        currency.as_deref().unwrap_or_default()
+                    );
+                    priced.insert(price.name, None);
+                    continue;
+                }
+                priced
+                    .entry(price.name)
+                    .and_modify(|c| *c = c.map(|c| c + price.price))
+                    .or_insert(Some(price.price));
+            }
+            ingredients.extend(priced);
+        }
+
+        Costs {
+            currency,
+            ingredients,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Costs {
+    pub currency: Option<String>,
+    /// Cost by ingredient name, `None` when some amount has no price
+    pub ingredients: BTreeMap<String, Option<f64>>,
+}
+
+impl Costs {
+    pub fn total(&self) -> f64 {
+        self.ingredients.values().flatten().sum()
+    }
+
+    pub fn missing(&self) -> impl Iterator<Item = &str> {
+        self.ingredients
+            .iter()
+            .filter(|(_, c)| c.is_none())
+            .map(|(n, _)| n.as_str())
+    }
+}
+
+/// Costs of the amounts, from the prices file and then the plugins
+pub fn costs(ctx: &Context, amounts: &[IngredientAmount]) -> Result<Costs> {
+    let file = PricesFile::load(ctx)?;
+    Ok(file.costs(&ctx.chef_config.plugins, ctx.parser()?.converter(), amounts))
+}
+
+pub fn format_cost(cost: f64, currency: Option<&str>) -> String {
+    match currency {
+        Some(c) => format!("{cost:.2} {c}"),
+        None => format!("{cost:.2}"),
+    }
+}
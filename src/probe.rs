@@ -0,0 +1,230 @@
+//! Temperature probes for cook mode
+//!
+//! Steps declare the temperature to reach in the `probe` metadata, with the
+//! same step keys as `yields`:
+//!
+//! ```yaml
+//! probe:
+//!   4: 63 °C      # step 4
+//!   2.3: 90 °C    # step 3 of section 2
+//! ```
+//!
+//! A [`Backend`] reads the probe, the one in the `[probe]` table of the
+//! global chef config. The
+//! readings are lines with a number, plain or the first one in the line, so
+//! simple JSON payloads work too.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+};
+
+use anyhow::{Context as _, Result};
+use cooklang::{Content, Converter, ScaledRecipe};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    scaling::parse_amount,
+    util::{convert_value, fmt_number, shell_command},
+};
+
+pub const PROBE_KEY: &str = "probe";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProbeConfig {
+    #[serde(flatten)]
+    pub backend: BackendConfig,
+    /// Unit of the readings
+    #[serde(default = "default_unit")]
+    pub unit: String,
+}
+
+fn default_unit() -> String {
+    "C".into()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum BackendConfig {
+    /// An MQTT topic, read with `mosquitto_sub`
+    Mqtt {
+        #[serde(default = "default_host")]
+        host: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        port: Option<u16>,
+        topic: String,
+    },
+    /// A shell command that writes a reading per line
+    Command { command: String },
+}
+
+fn default_host() -> String {
+    "localhost".into()
+}
+
+/// Source of probe readings
+pub trait Backend {
+    /// Starts reading the probe
+    fn start(&self) -> Result<Child>;
+}
+
+impl Backend for BackendConfig {
+    fn start(&self) -> Result<Child> {
+        let mut command = match self {
+            BackendConfig::Mqtt { host, port, topic } => {
+                let mut c = Command::new("mosquitto_sub");
+                c.args(["-h", host, "-t", topic]);
+                if let Some(port) = port {
+                    c.args(["-p", &port.to_string()]);
+                }
+                c
+            }
+            BackendConfig::Command { command } => shell_command(command),
+        };
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Can't start the probe backend")
+    }
+}
+
+/// A running probe, stopped when dropped
+pub struct Probe {
+    child: Child,
+    pub readings: Receiver<f64>,
+}
+
+impl Probe {
+    pub fn start(backend: &dyn Backend) -> Result<Self> {
+        let mut child = backend.start()?;
+        let stdout = child.stdout.take().expect("piped stdout");
+        let (tx, readings) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if let Some(value) = parse_reading(&line) {
+                    if tx.send(value).is_err() {
+                        break;
+                    }
+                } else {
+                    tracing::debug!("Ignored probe reading: {line}");
+                }
+            }
+        });
+        Ok(Self { child, readings })
+    }
+}
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// First number in a line
+fn parse_reading(line: &str) -> Option<f64> {
+    let mut start = line.find(|c: char| c.is_ascii_digit())?;
+    if line[..start].ends_with('-') {
+        start -= 1;
+    }
+    let rest = &line[start..];
+    let end = rest
+        .char_indices()
+        .skip(1)
+        .find(|&(_, c)| !(c.is_ascii_digit() || c == '.'))
+        .map_or(rest.len(), |(i, _)| i);
+    rest[..end].parse().ok()
+}
+
+/// Temperature a step has to reach
+#[derive(Debug, Clone, Serialize)]
+pub struct Target {
+    pub value: f64,
+    pub unit: String,
+}
+
+impl Target {
+    /// The value in another unit
+    pub fn value_in(&self, unit: &str, converter: &Converter) -> Option<f64> {
+        convert_value(self.value, &self.unit, unit, converter)
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", fmt_number(self.value), self.unit)
+    }
+}
+
+/// Probe targets by section index and step number
+pub fn targets(recipe: &ScaledRecipe) -> (HashMap<(usize, u32), Target>, Vec<String>) {
+    let mut targets = HashMap::new();
+    let mut warnings = Vec::new();
+    let Some(map) = recipe
+        .metadata
+        .map
+        .get(PROBE_KEY)
+        .and_then(|v| v.as_mapping())
+    else {
+        return (targets, warnings);
+    };
+    for (key, value) in map {
+        let key = match key {
+            serde_yaml::Value::String(s) => s.clone(),
+            serde_yaml::Value::Number(n) => n.to_string(),
+            _ => {
+                warnings.push(format!("Invalid key in `{PROBE_KEY}`: {key:?}"));
+                continue;
+            }
+        };
+        let step = match key.split_once('.') {
+            Some((section, number)) => section
+                .parse::<usize>()
+                .ok()
+                .and_then(|s| s.checked_sub(1))
+                .zip(number.parse::<u32>().ok()),
+            None => key.parse::<u32>().ok().map(|n| (0, n)),
+        };
+        let exists = step.is_some_and(|(section, number)| {
+            recipe.sections.get(section).is_some_and(|s| {
+                s.content
+                    .iter()
+                    .any(|c| matches!(c, Content::Step(s) if s.number == number))
+            })
+        });
+        let (Some(step), true) = (step, exists) else {
+            warnings.push(format!("No step '{key}' in `{PROBE_KEY}`"));
+            continue;
+        };
+        let amount = value
+            .as_str()
+            .ok_or_else(String::new)
+            .and_then(parse_amount);
+        let Ok((value, Some(unit))) = amount else {
+            warnings.push(format!(
+                "Invalid temperature for '{key}' in `{PROBE_KEY}`, expected like 63 °C"
+            ));
+            continue;
+        };
+        targets.insert(step, Target { value, unit });
+    }
+    (targets, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readings() {
+        assert_eq!(parse_reading("63.5"), Some(63.5));
+        assert_eq!(parse_reading(r#"{"temperature": 21.25}"#), Some(21.25));
+        assert_eq!(parse_reading("-4 C"), Some(-4.0));
+        assert_eq!(parse_reading("offline"), None);
+    }
+}
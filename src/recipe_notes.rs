@@ -0,0 +1,136 @@
+//! Ratings and notes of a recipe, after cooking it
+//!
+//! They are kept next to the recipe, in `<name>.notes.toml`, one `[[note]]`
+//! each time. New ones are appended so the file can also be edited by hand:
+//!
+//! ```toml
+//! [[note]]
+//! date = "2025-06-12"
+//! rating = 4
+//! text = "A bit less salt next time"
+//! ```
+
+use std::io::Write;
+
+use anyhow::{Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::pantry::Date;
+
+/// Highest rating, ratings go from 1
+pub const MAX_RATING: u8 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub date: Date,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecipeNotes {
+    #[serde(default, rename = "note")]
+    pub notes: Vec<Note>,
+}
+
+/// Path of the notes file of a recipe
+pub fn notes_path(recipe: &Utf8Path) -> Utf8PathBuf {
+    recipe.with_extension("notes.toml")
+}
+
+impl RecipeNotes {
+    /// Loads the notes of a recipe, empty if it has no notes file
+    pub fn load(recipe: &Utf8Path) -> Result<Self> {
+        let path = notes_path(recipe);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read '{path}'"))?;
+        toml::from_str(&content).with_context(|| format!("Bad notes file '{path}'"))
+    }
+
+    /// Average rating, `None` if never rated
+    pub fn rating(&self) -> Option<f64> {
+        let ratings = self
+            .notes
+            .iter()
+            .filter_map(|n| n.rating)
+            .collect::<Vec<_>>();
+        (!ratings.is_empty())
+            .then(|| ratings.iter().map(|&r| r as f64).sum::<f64>() / ratings.len() as f64)
+    }
+}
+
+/// Appends a note to the notes file of a recipe
+pub fn append(recipe: &Utf8Path, note: Note) -> Result<()> {
+    let path = notes_path(recipe);
+    let entry = toml::to_string(&RecipeNotes { notes: vec![note] })?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open '{path}'"))?;
+    let separator = if file.metadata()?.len() > 0 { "\n" } else { "" };
+    write!(file, "{separator}{entry}").with_context(|| format!("Failed to write '{path}'"))?;
+    Ok(())
+}
+
+/// Parses a rating from 1 to [`MAX_RATING`]
+pub fn parse_rating(s: &str) -> Result<u8, String> {
+    match s.trim().parse::<u8>() {
+        Ok(r) if (1..=MAX_RATING).contains(&r) => Ok(r),
+        _ => Err(format!("Expected a rating from 1 to {MAX_RATING}")),
+    }
+}
+
+/// Stars of a rating, like `★★★★☆`
+pub fn stars(rating: f64) -> String {
+    let full = (rating.round() as usize).min(MAX_RATING as usize);
+    "★".repeat(full) + &"☆".repeat(MAX_RATING as usize - full)
+}
+
+/// Multiplies a weight of [`crate::pick::weight`] by the rating of a recipe
+///
+/// Recipes never rated count as in the middle.
+pub fn rated_weight(weight: u64, rating: Option<f64>) -> u64 {
+    let rating = rating.unwrap_or((1 + MAX_RATING) as f64 / 2.0);
+    (weight as f64 * rating).round() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratings() {
+        let notes: RecipeNotes = toml::from_str(
+            r#"
+[[note]]
+date = "2025-06-01"
+rating = 5
+
+[[note]]
+date = "2025-06-12"
+text = "no rating"
+
+[[note]]
+date = "2025-06-20"
+rating = 2
+"#,
+        )
+        .unwrap();
+        assert_eq!(notes.rating(), Some(3.5));
+        assert_eq!(RecipeNotes::default().rating(), None);
+        assert_eq!(stars(3.5), "★★★★☆");
+        assert_eq!(
+            parse_rating("6"),
+            Err("Expected a rating from 1 to 5".into())
+        );
+        assert_eq!(rated_weight(10, None), 30);
+        assert_eq!(rated_weight(10, Some(1.0)), 10);
+    }
+}
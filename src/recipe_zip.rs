@@ -0,0 +1,114 @@
+//! ZIP archives of some recipes, to take them home
+//!
+//! The archive has the `.cook` files and their images in the same folders as
+//! in the collection. Recipes can also have an HTML page next to them, so they
+//! can be read without chef. Encrypted and private recipes are never added.
+
+use std::io::{Seek, Write};
+
+use anyhow::{Context as _, Result};
+use camino::Utf8Path;
+use cooklang::{Converter, Metadata, ScaledRecipe};
+use cooklang_fs::RecipeEntry;
+use cooklang_render::{RecipeRenderer, RenderInput};
+use cooklang_to_human::NumberFormat;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::encryption::is_marked_private;
+
+/// A recipe to put in the archive
+pub struct ZipRecipe<'a> {
+    pub entry: &'a RecipeEntry,
+    /// Rendered HTML page, if any
+    pub html: Option<Vec<u8>>,
+}
+
+/// Checks if a recipe can go in an archive, not encrypted nor private
+pub fn can_archive(entry: &RecipeEntry, metadata: &Metadata) -> bool {
+    !entry.is_encrypted() && !is_marked_private(metadata)
+}
+
+/// Size of the files of some recipes, before compressing them
+pub fn files_size<'a>(entries: impl IntoIterator<Item = &'a RecipeEntry>) -> u64 {
+    entries
+        .into_iter()
+        .flat_map(|e| std::iter::once(e.path()).chain(e.images().iter().map(|i| i.path.as_path())))
+        .filter_map(|p| p.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Renders the HTML page of a recipe
+pub fn render_html(
+    recipe: &ScaledRecipe,
+    name: &str,
+    converter: &Converter,
+    number_format: NumberFormat,
+) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    cooklang_render::Html.render(
+        &mut buf,
+        &RenderInput::new(recipe, name, converter).number_format(number_format),
+    )?;
+    Ok(buf)
+}
+
+/// Writes the archive, returns the writer and the number of files in it
+pub fn write<W: Write + Seek>(
+    w: W,
+    base_path: &Utf8Path,
+    recipes: &[ZipRecipe],
+) -> Result<(W, usize)> {
+    let mut zip = ZipWriter::new(w);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // images are already compressed
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut count = 0;
+
+    for recipe in recipes {
+        let path = recipe.entry.path();
+        let content = std::fs::read(path).with_context(|| format!("Failed to read {path}"))?;
+        let mut files = vec![(path.to_path_buf(), content, deflated)];
+        for image in recipe.entry.images() {
+            let content = std::fs::read(&image.path)
+                .with_context(|| format!("Failed to read {}", image.path))?;
+            files.push((image.path.clone(), content, stored));
+        }
+        if let Some(html) = &recipe.html {
+            files.push((path.with_extension("html"), html.clone(), deflated));
+        }
+        for (local, content, options) in files {
+            zip.start_file(archive_path(&local, base_path), options)?;
+            zip.write_all(&content)?;
+            count += 1;
+        }
+    }
+    let w = zip.finish()?;
+    Ok((w, count))
+}
+
+/// Path of a file in the archive, relative to the collection and with `/`
+fn archive_path(local: &Utf8Path, base_path: &Utf8Path) -> String {
+    match local.strip_prefix(base_path) {
+        Ok(relative) => relative.as_str().replace('\\', "/"),
+        Err(_) => local.file_name().unwrap_or(local.as_str()).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        let base = Utf8Path::new("/recipes");
+        assert_eq!(
+            archive_path(Utf8Path::new("/recipes/Desserts/Flan.cook"), base),
+            "Desserts/Flan.cook"
+        );
+        assert_eq!(
+            archive_path(Utf8Path::new("/elsewhere/Flan.jpg"), base),
+            "Flan.jpg"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Remote collections
+//!
+//! A collection can be read from a git repository or a web server without
+//! cloning it by hand. Remotes are set in the global config:
+//!
+//! ```toml
+//! [remotes.family]
+//! url = "https://github.com/someone/family-recipes.git"
+//!
+//! [remotes.grandma]
+//! url = "https://example.com/recipes"   # an HTTP index
+//! ```
+//!
+//! `chef sync` fetches them into the cache dir and `--remote <name>` uses one
+//! as the collection. An HTTP index is a dir served with an `index.json`
+//! listing its files, like `chef collection index` writes.
+//!
+//! The `.cooklang` dir of a remote is never synced: its config can run
+//! commands and belongs to someone else.
+
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context as _, Result};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use cooklang_fs::DiscoveryRules;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::global_cache_dir, COOK_DIR};
+
+const REMOTES_DIR: &str = "remotes";
+pub const INDEX_FILE: &str = "index.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Remote {
+    pub url: String,
+    /// How to fetch it, guessed from the URL if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<RemoteKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteKind {
+    Git,
+    Http,
+}
+
+impl Remote {
+    pub fn kind(&self) -> RemoteKind {
+        self.kind.unwrap_or_else(|| {
+            let url = self.url.trim_end_matches('/');
+            if url.ends_with(".git") || url.starts_with("git@") || url.starts_with("ssh://") {
+                RemoteKind::Git
+            } else {
+                RemoteKind::Http
+            }
+        })
+    }
+}
+
+/// Files of a collection served over HTTP
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    /// Paths relative to the index, with `/` separators
+    pub files: Vec<String>,
+}
+
+/// Local copy of a remote
+pub fn dir(name: &str) -> Result<Utf8PathBuf> {
+    if !is_safe_path(name) || name.contains(['/', '\\']) {
+        bail!("Invalid remote name: '{name}'");
+    }
+    Ok(global_cache_dir()?.join(REMOTES_DIR).join(name))
+}
+
+/// Local copy of a remote that has been synced
+pub fn synced_dir(name: &str, remotes: &BTreeMap<String, Remote>) -> Result<Utf8PathBuf> {
+    if !remotes.contains_key(name) {
+        bail!("Unknown remote '{name}', add it to the global config or with `chef sync {name} --url <URL>`");
+    }
+    let dir = dir(name)?;
+    if !dir.is_dir() {
+        bail!("Remote '{name}' is not synced yet, run `chef sync {name}`");
+    }
+    Ok(dir)
+}
+
+/// Fetches the latest version of a remote
+///
+/// Local changes in the copy are discarded, it's read-only.
+pub fn sync(name: &str, remote: &Remote) -> Result<Utf8PathBuf> {
+    let dir = dir(name)?;
+    std::fs::create_dir_all(dir.parent().unwrap())?;
+    match remote.kind() {
+        RemoteKind::Git => sync_git(&remote.url, &dir)?,
+        RemoteKind::Http => sync_http(&remote.url, &dir)?,
+    }
+    // an empty one so it's still a collection
+    let config_dir = dir.join(COOK_DIR);
+    if config_dir.exists() {
+        std::fs::remove_dir_all(&config_dir)?;
+    }
+    std::fs::create_dir_all(&config_dir)?;
+    Ok(dir)
+}
+
+fn sync_git(url: &str, dir: &Utf8Path) -> Result<()> {
+    if dir.join(".git").is_dir() {
+        git(&["-C", dir.as_str(), "fetch", "--depth", "1", "origin"])?;
+        git(&["-C", dir.as_str(), "reset", "--hard", "FETCH_HEAD"])?;
+    } else {
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)?;
+        }
+        git(&["clone", "--depth", "1", url, dir.as_str()])?;
+    }
+    Ok(())
+}
+
+fn git(args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .args(args)
+        .stdin(Stdio::null())
+        .status()
+        .context("Failed to run git, is it installed?")?;
+    if !status.success() {
+        bail!("git {} failed: {status}", args.join(" "));
+    }
+    Ok(())
+}
+
+fn sync_http(url: &str, dir: &Utf8Path) -> Result<()> {
+    let base = url.trim_end_matches('/');
+    let index: Index = get(&format!("{base}/{INDEX_FILE}"))?
+        .into_json()
+        .context("Invalid remote index")?;
+
+    // download everything before touching the current copy
+    let tmp = dir.with_extension("partial");
+    if tmp.exists() {
+        std::fs::remove_dir_all(&tmp)?;
+    }
+    for file in &index.files {
+        if !is_safe_path(file) {
+            bail!("Invalid path in remote index: '{file}'");
+        }
+        if is_config_path(file) {
+            tracing::warn!("Skipping '{file}' in remote index, the config is not synced");
+            continue;
+        }
+        let mut content = Vec::new();
+        get(&format!("{base}/{file}"))?
+            .into_reader()
+            .read_to_end(&mut content)
+            .with_context(|| format!("Failed to download '{file}'"))?;
+        let path = tmp.join(file);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, content)?;
+    }
+    std::fs::create_dir_all(&tmp)?;
+
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::rename(&tmp, dir)?;
+    Ok(())
+}
+
+fn get(url: &str) -> Result<ureq::Response> {
+    tracing::debug!("Fetching {url}");
+    ureq::get(url)
+        .set("User-Agent", crate::USER_AGENT)
+        .call()
+        .with_context(|| format!("Failed to fetch {url}"))
+}
+
+/// A relative path without `..`
+pub fn is_safe_path(path: &str) -> bool {
+    !path.is_empty()
+        && Utf8Path::new(path)
+            .components()
+            .all(|c| matches!(c, Utf8Component::Normal(_)))
+}
+
+/// A path in the `.cooklang` dir
+fn is_config_path(path: &str) -> bool {
+    Utf8Path::new(path)
+        .components()
+        .next()
+        .is_some_and(|c| c.as_str() == COOK_DIR)
+}
+
+/// Builds the index of a collection to serve it over HTTP
+///
+/// It has the recipes, their images and the `collection.toml` files. The
+/// `.cooklang` dir is left out, it's not synced.
+pub fn build_index(
+    base_path: &Utf8Path,
+    max_depth: usize,
+    rules: &DiscoveryRules,
+) -> Result<Index> {
+    let mut files = Vec::new();
+    let mut add = |path: &Utf8Path| {
+        if let Ok(relative) = path.strip_prefix(base_path) {
+            files.push(relative.as_str().replace('\\', "/"));
+        }
+    };
+    for entry in cooklang_fs::all_recipes_with_rules(base_path, max_depth, rules)? {
+        if entry
+            .path()
+            .strip_prefix(base_path)
+            .is_ok_and(|p| is_config_path(p.as_str()))
+        {
+            continue;
+        }
+        add(entry.path());
+        for image in entry.images() {
+            add(&image.path);
+        }
+        let mut dir = entry.path().parent();
+        while let Some(d) = dir.filter(|d| d.starts_with(base_path)) {
+            let collection_file = d.join(crate::collection_file::COLLECTION_FILE);
+            if collection_file.is_file() {
+                add(&collection_file);
+            }
+            dir = d.parent();
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(Index { files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_and_paths() {
+        let remote = |url: &str| Remote {
+            url: url.into(),
+            kind: None,
+        };
+        assert_eq!(remote("https://x.org/a/b.git").kind(), RemoteKind::Git);
+        assert_eq!(remote("git@github.com:a/b").kind(), RemoteKind::Git);
+        assert_eq!(remote("https://x.org/recipes/").kind(), RemoteKind::Http);
+        assert!(is_safe_path("Mains/Stew.cook"));
+        assert!(!is_safe_path("../x"));
+        assert!(!is_safe_path("/etc/passwd"));
+        assert!(is_config_path(".cooklang/config.toml"));
+        assert!(!is_config_path("Mains/.cooklang.cook"));
+    }
+}
@@ -0,0 +1,139 @@
+//! Cache of rendered recipes
+//!
+//! Rendering the same recipe twice with the same options gives the same
+//! output, so `chef recipe` stores it in the user cache dir. The key is a
+//! hash of everything that can change the output: the recipe text, where it
+//! is (inherited metadata), the configuration, the units files and the render
+//! options. It's a SHA-256, so it stays the same between builds. Entries are
+//! never invalidated, a changed input is a different key, but the oldest are
+//! removed when the cache is over [`MAX_SIZE`].
+//!
+//! Only recipes without warnings are cached, so a cache hit behaves exactly
+//! like rendering again. The web server doesn't use it, its pages depend on
+//! more than a recipe and the options.
+
+use anyhow::{Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use ring::digest::{Context as Digest, SHA256};
+
+use crate::{collection_file::inherited_metadata, config::global_cache_dir, util::hex, Context};
+
+const RENDER_DIR: &str = "render";
+
+/// Max size in bytes of all the rendered recipes
+const MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Key of a rendered recipe
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// Builds a key from the recipe and the options that affect the output
+    ///
+    /// `options` must include everything that changes the rendered output
+    /// apart from the recipe itself and the collection configuration.
+    pub fn new(ctx: &Context, text: &str, path: Option<&Utf8Path>, options: &str) -> Result<Self> {
+        let mut digest = Digest::new(&SHA256);
+        // every part goes after its length, so they can't run into each other
+        let mut add = |bytes: &[u8]| {
+            digest.update(&(bytes.len() as u64).to_le_bytes());
+            digest.update(bytes);
+        };
+        add(env!("CARGO_PKG_VERSION").as_bytes());
+        add(text.as_bytes());
+        add(&[path.is_some() as u8]);
+        add(path.map(Utf8Path::as_str).unwrap_or_default().as_bytes());
+        add(options.as_bytes());
+        add(toml::to_string(&ctx.config)?.as_bytes());
+        for file in ctx.config.units(&ctx.base_path) {
+            // a missing file will fail later when loading the parser
+            add(&std::fs::read(&file).unwrap_or_default());
+        }
+        if let Some(path) = path {
+            let inherited = inherited_metadata(path, &ctx.base_path);
+            add(serde_yaml::to_string(&inherited)?.as_bytes());
+        }
+        Ok(Self(hex(digest.finish().as_ref())))
+    }
+
+    fn path(&self) -> Result<Utf8PathBuf> {
+        Ok(render_dir()?.join(&self.0))
+    }
+}
+
+fn render_dir() -> Result<Utf8PathBuf> {
+    Ok(global_cache_dir()?.join(RENDER_DIR))
+}
+
+/// Gets a rendered recipe, if any
+pub fn get(key: &CacheKey) -> Option<Vec<u8>> {
+    let path = key.path().ok()?;
+    let content = std::fs::read(&path).ok()?;
+    tracing::debug!("Render cache hit: {path}");
+    Some(content)
+}
+
+/// Stores a rendered recipe
+///
+/// Errors are only logged, the cache is not required to work.
+pub fn put(key: &CacheKey, content: &[u8]) {
+    let store = || -> Result<()> {
+        let path = key.path()?;
+        std::fs::create_dir_all(render_dir()?)?;
+        std::fs::write(&path, content)?;
+        trim(MAX_SIZE)
+    };
+    if let Err(e) = store() {
+        tracing::warn!("Could not store rendered recipe in cache: {e:#}");
+    }
+}
+
+/// Removes the oldest rendered recipes until they take at most `max` bytes
+fn trim(max: u64) -> Result<()> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(render_dir()?)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            entries.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+    let mut total = entries.iter().map(|(_, len, _)| len).sum::<u64>();
+    if total <= max {
+        return Ok(());
+    }
+    entries.sort_by_key(|(modified, ..)| *modified);
+    for (_, len, path) in entries {
+        if total <= max {
+            break;
+        }
+        std::fs::remove_file(&path)?;
+        total -= len;
+    }
+    Ok(())
+}
+
+/// Removes all the rendered recipes
+///
+/// Returns the number of entries removed.
+pub fn clear() -> Result<usize> {
+    let dir = render_dir()?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).context("Failed to read cache dir"),
+    };
+    let mut count = 0;
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            std::fs::remove_file(entry.path()).context("Failed to remove cache entry")?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Path of the render cache
+pub fn path() -> Result<Utf8PathBuf> {
+    render_dir()
+}
@@ -0,0 +1,543 @@
+//! Ingredient scaling strategies
+//!
+//! The parser only knows how to scale linearly or keep a value fixed. Some
+//! ingredients need something else: spices don't scale linearly and eggs are
+//! better as whole numbers. A strategy can be set for an ingredient in the
+//! config:
+//!
+//! ```toml
+//! [scaling]
+//! salt = "sqrt"
+//! egg = "stepwise"
+//! ```
+//!
+//! or in the ingredient note, like `@salt{1%tsp}(scale: sqrt)`. The note takes
+//! precedence.
+
+use std::collections::HashMap;
+
+use cooklang::{
+    quantity::{Number, Quantity, ScalableValue, Value},
+    Converter, Metadata, ScalableRecipe, ScaledRecipe,
+};
+use cooklang_to_human::{approx_value, ScaleMark};
+use serde::{Deserialize, Serialize};
+
+use crate::util::{convert_value, fmt_number};
+
+/// Metadata with what a recipe makes, like `1.2 kg` or `24 cookies`
+pub const YIELD_KEY: &str = "yield";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingStrategy {
+    /// Scale proportionally to the servings, the default
+    Linear,
+    /// Never scale
+    Fixed,
+    /// Scale with the square root of the factor
+    Sqrt,
+    /// Scale linearly and round to a whole number, at least 1
+    Stepwise,
+}
+
+impl ScalingStrategy {
+    fn from_note(note: &str) -> Option<Self> {
+        note.split([',', ';']).find_map(|part| {
+            let (key, value) = part.split_once([':', '='])?;
+            if key.trim() != "scale" {
+                return None;
+            }
+            match value.trim() {
+                "linear" => Some(Self::Linear),
+                "fixed" => Some(Self::Fixed),
+                "sqrt" => Some(Self::Sqrt),
+                "stepwise" | "step" | "round" => Some(Self::Stepwise),
+                _ => None,
+            }
+        })
+    }
+
+    fn apply(self, base: f64, factor: f64) -> f64 {
+        match self {
+            Self::Linear => base * factor,
+            Self::Fixed => base,
+            Self::Sqrt => base * factor.sqrt(),
+            Self::Stepwise => (base * factor).round().max(1.0),
+        }
+    }
+
+    fn mark(self) -> Option<ScaleMark> {
+        match self {
+            Self::Linear => None,
+            Self::Fixed => Some(ScaleMark::Fixed),
+            Self::Sqrt => Some(ScaleMark::Sqrt),
+            Self::Stepwise => Some(ScaleMark::Stepwise),
+        }
+    }
+}
+
+/// Re-scales the ingredients with a strategy other than linear
+///
+/// `base` are the quantities with the default scaling, from
+/// [`base_quantities`], the values are recalculated from them. Returns the
+/// marks for the ingredients that changed.
+pub fn apply_strategies(
+    scaled: &mut ScaledRecipe,
+    base: &[Option<Quantity<Value>>],
+    config: &HashMap<String, ScalingStrategy>,
+) -> HashMap<usize, ScaleMark> {
+    let mut marks = HashMap::new();
+
+    let Some(factor) = scale_factor(scaled) else {
+        return marks;
+    };
+
+    for (index, igr) in scaled.ingredients.iter_mut().enumerate() {
+        let Some(base_qty) = base.get(index).and_then(Option::as_ref) else {
+            continue;
+        };
+        // the parser can't scale approximate values, they are text
+        let approx = is_approx(base_qty);
+        let strategy = strategy_for(igr, config).unwrap_or(ScalingStrategy::Linear);
+        if strategy == ScalingStrategy::Linear && !approx {
+            continue;
+        }
+        let Some(quantity) = scale_quantity(base_qty, strategy, factor) else {
+            continue;
+        };
+        igr.quantity = Some(quantity);
+        if let Some(mark) = strategy.mark() {
+            marks.insert(index, mark);
+        } else if approx {
+            marks.insert(index, ScaleMark::Approximate);
+        }
+    }
+
+    marks
+}
+
+/// The quantities of the ingredients with the default scaling
+///
+/// The same as [`ScalableRecipe::default_scale`] does, without consuming the
+/// recipe, so it can still be scaled.
+pub fn base_quantities(recipe: &ScalableRecipe) -> Vec<Option<Quantity<Value>>> {
+    recipe
+        .ingredients
+        .iter()
+        .map(|igr| {
+            let q = igr.quantity.as_ref()?;
+            let value = match q.value() {
+                ScalableValue::Fixed(v) | ScalableValue::Linear(v) => v.clone(),
+                ScalableValue::ByServings(values) => values.first()?.clone(),
+            };
+            Some(Quantity::new(value, q.unit().map(str::to_string)))
+        })
+        .collect()
+}
+
+fn strategy_for(
+    igr: &cooklang::Ingredient<Value>,
+    config: &HashMap<String, ScalingStrategy>,
+) -> Option<ScalingStrategy> {
+    igr.note
+        .as_deref()
+        .and_then(ScalingStrategy::from_note)
+        .or_else(|| config.get(&igr.name.to_lowercase()).copied())
+}
+
+pub fn scale_quantity(
+    base: &Quantity<Value>,
+    strategy: ScalingStrategy,
+    factor: f64,
+) -> Option<Quantity<Value>> {
+    let value = match base.value() {
+        Value::Number(n) => Value::Number(Number::Regular(strategy.apply(n.value(), factor))),
+        Value::Range { start, end } => Value::Range {
+            start: Number::Regular(strategy.apply(start.value(), factor)),
+            end: Number::Regular(strategy.apply(end.value(), factor)),
+        },
+        // approximate values scale too and stay approximate
+        Value::Text(t) => {
+            let n = approx_value(t)?;
+            Value::Text(format!("~{}", fmt_number(strategy.apply(n, factor))))
+        }
+    };
+    Some(Quantity::new(value, base.unit().map(str::to_string)))
+}
+
+/// An approximate value, like `~150`
+pub fn is_approx(quantity: &Quantity<Value>) -> bool {
+    matches!(quantity.value(), Value::Text(t) if approx_value(t).is_some())
+}
+
+/// Target of `--scale-to`, like `flour=1kg`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaleTarget {
+    pub ingredient: String,
+    pub value: f64,
+    pub unit: Option<String>,
+}
+
+impl std::str::FromStr for ScaleTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ingredient, amount) = s
+            .split_once('=')
+            .ok_or("expected INGREDIENT=AMOUNT, like flour=1kg")?;
+        let ingredient = ingredient.trim();
+        if ingredient.is_empty() {
+            return Err("missing ingredient name".into());
+        }
+        let (value, unit) = parse_amount(amount)?;
+        Ok(Self {
+            ingredient: ingredient.to_string(),
+            value,
+            unit,
+        })
+    }
+}
+
+/// Parses a positive amount with an optional unit, like `1kg` or `2 cups`
+pub fn parse_amount(amount: &str) -> Result<(f64, Option<String>), String> {
+    let amount = amount.trim();
+    let num_end = amount
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(amount.len());
+    let value = amount[..num_end]
+        .replace(',', ".")
+        .parse::<f64>()
+        .map_err(|_| format!("invalid amount: '{amount}'"))?;
+    if value <= 0.0 {
+        return Err("the amount must be positive".into());
+    }
+    let unit = amount[num_end..].trim();
+    Ok((value, (!unit.is_empty()).then(|| unit.to_string())))
+}
+
+/// Scales a recipe so an ingredient reaches a target amount
+///
+/// The ingredient is marked as [`ScaleMark::Anchor`]. Values fixed in the
+/// recipe or with a strategy keep behaving as with servings. Returns the
+/// factor too.
+pub fn scale_to_target(
+    recipe: ScalableRecipe,
+    target: &ScaleTarget,
+    converter: &Converter,
+    config: &HashMap<String, ScalingStrategy>,
+) -> anyhow::Result<(ScaledRecipe, HashMap<usize, ScaleMark>, f64)> {
+    let fixed = fixed_ingredients(&recipe);
+    let base = recipe.default_scale();
+
+    let name = target.ingredient.to_lowercase();
+    let (anchor, anchor_qty) = base
+        .ingredients
+        .iter()
+        .enumerate()
+        .filter(|(_, igr)| {
+            igr.relation.references_to().is_none() && igr.name.to_lowercase() == name
+        })
+        .find_map(|(i, igr)| Some((i, igr.quantity.as_ref()?)))
+        .ok_or_else(|| anyhow::anyhow!("No ingredient '{}' with a quantity", target.ingredient))?;
+    let Value::Number(base_value) = anchor_qty.value() else {
+        anyhow::bail!("The quantity of '{}' is not a number", target.ingredient);
+    };
+
+    let mut wanted = Quantity::new(
+        Value::Number(Number::Regular(target.value)),
+        target.unit.clone(),
+    );
+    match (anchor_qty.unit(), &target.unit) {
+        (Some(base_unit), Some(unit)) if base_unit != unit => {
+            wanted
+                .convert(base_unit, converter)
+                .map_err(|e| anyhow::anyhow!("Can't convert {unit} to {base_unit}: {e}"))?;
+        }
+        (None, Some(unit)) => {
+            anyhow::bail!("'{}' has no unit, can't use {unit}", target.ingredient)
+        }
+        _ => {}
+    }
+    let Value::Number(wanted) = wanted.value() else {
+        anyhow::bail!("Invalid amount for '{}'", target.ingredient);
+    };
+    if base_value.value() == 0.0 {
+        anyhow::bail!("The quantity of '{}' is zero", target.ingredient);
+    }
+    let factor = wanted.value() / base_value.value();
+
+    let (scaled, mut marks) = scale_base(base, &fixed, factor, config);
+    marks.insert(anchor, ScaleMark::Anchor);
+
+    Ok((scaled, marks, factor))
+}
+
+/// Amount and unit in the [`YIELD_KEY`] metadata
+pub fn recipe_yield(metadata: &Metadata) -> Option<(f64, Option<String>)> {
+    match metadata.map.get(YIELD_KEY)? {
+        serde_yaml::Value::Number(n) => Some((n.as_f64().filter(|&n| n > 0.0)?, None)),
+        serde_yaml::Value::String(s) => parse_amount(s).ok(),
+        _ => None,
+    }
+}
+
+/// Scales a recipe so it makes an amount, like `36`, `36 cookies` or `2kg`
+///
+/// The amount is compared with the [`YIELD_KEY`] metadata, in its unit when
+/// it has none. Like [`scale_by`], the values fixed in the recipe or with a
+/// strategy keep behaving as with servings. Returns the factor too, for
+/// [`scale_yield`].
+pub fn scale_to_yield(
+    recipe: ScalableRecipe,
+    amount: &str,
+    converter: &Converter,
+    config: &HashMap<String, ScalingStrategy>,
+) -> anyhow::Result<(ScaledRecipe, HashMap<usize, ScaleMark>, f64)> {
+    let (made, made_unit) = recipe_yield(&recipe.metadata)
+        .ok_or_else(|| anyhow::anyhow!("The recipe has no `{YIELD_KEY}` metadata"))?;
+    let (value, unit) = parse_amount(amount).map_err(anyhow::Error::msg)?;
+    let wanted = match (&made_unit, &unit) {
+        (_, None) => value,
+        (Some(made_unit), Some(unit)) if made_unit.eq_ignore_ascii_case(unit) => value,
+        (Some(made_unit), Some(unit)) => convert_value(value, unit, made_unit, converter)
+            .ok_or_else(|| anyhow::anyhow!("Can't convert {unit} to {made_unit}"))?,
+        (None, Some(unit)) => anyhow::bail!("The yield has no unit, can't use {unit}"),
+    };
+    let factor = wanted / made;
+    let (scaled, marks) = scale_by(recipe, factor, config);
+    Ok((scaled, marks, factor))
+}
+
+/// Multiplies the [`YIELD_KEY`] metadata, to show what a scaled recipe makes
+pub fn scale_yield(recipe: &mut ScaledRecipe, factor: f64) {
+    let Some((value, unit)) = recipe_yield(&recipe.metadata) else {
+        return;
+    };
+    let value = fmt_number(value * factor);
+    let text = match unit {
+        Some(unit) => format!("{value} {unit}"),
+        None => value,
+    };
+    recipe.metadata.map.insert(YIELD_KEY.into(), text.into());
+}
+
+/// Tags of the recipes that show bakers' percentages
+pub const BAKING_TAGS: &[&str] = &["baking", "bread", "sourdough"];
+
+/// Whether the recipe has one of the [`BAKING_TAGS`]
+pub fn is_baking(recipe: &ScaledRecipe) -> bool {
+    recipe.metadata.tags().is_some_and(|tags| {
+        tags.iter()
+            .any(|t| BAKING_TAGS.contains(&t.to_lowercase().as_str()))
+    })
+}
+
+/// Scales a recipe so the total flour weighs an amount, like `1kg`
+///
+/// The flours are the ingredients [`cooklang_to_human::is_flour`] accepts and
+/// they are marked as [`ScaleMark::Anchor`]. Returns the factor too.
+pub fn scale_to_flour(
+    recipe: ScalableRecipe,
+    amount: &str,
+    converter: &Converter,
+    config: &HashMap<String, ScalingStrategy>,
+) -> anyhow::Result<(ScaledRecipe, HashMap<usize, ScaleMark>, f64)> {
+    let (value, unit) = parse_amount(amount).map_err(anyhow::Error::msg)?;
+    let unit = unit.as_deref().unwrap_or("g");
+    let wanted = convert_value(value, unit, "g", converter)
+        .ok_or_else(|| anyhow::anyhow!("'{unit}' is not a unit of weight"))?;
+
+    let fixed = fixed_ingredients(&recipe);
+    let base = recipe.default_scale();
+    let mut flours = Vec::new();
+    let mut total = 0.0;
+    for (index, igr) in base.ingredients.iter().enumerate() {
+        if igr.relation.references_to().is_some() || !cooklang_to_human::is_flour(&igr.name) {
+            continue;
+        }
+        let Some(q) = &igr.quantity else { continue };
+        let grams = match (q.value(), q.unit()) {
+            (Value::Number(n), Some(u)) => convert_value(n.value(), u, "g", converter),
+            _ => None,
+        };
+        let Some(grams) = grams else {
+            anyhow::bail!("Can't get the weight of '{}'", igr.display_name());
+        };
+        total += grams;
+        flours.push(index);
+    }
+    if total == 0.0 {
+        anyhow::bail!("The recipe has no flour with a weight");
+    }
+    let factor = wanted / total;
+
+    let (scaled, mut marks) = scale_base(base, &fixed, factor, config);
+    for index in flours {
+        marks.insert(index, ScaleMark::Anchor);
+    }
+    Ok((scaled, marks, factor))
+}
+
+/// Scales a recipe by a factor, not by servings
+///
+/// Like [`scale_to_target`], the values fixed in the recipe or with a strategy
+/// keep behaving as with servings.
+pub fn scale_by(
+    recipe: ScalableRecipe,
+    factor: f64,
+    config: &HashMap<String, ScalingStrategy>,
+) -> (ScaledRecipe, HashMap<usize, ScaleMark>) {
+    let fixed = fixed_ingredients(&recipe);
+    scale_base(recipe.default_scale(), &fixed, factor, config)
+}
+
+fn fixed_ingredients(recipe: &ScalableRecipe) -> Vec<bool> {
+    recipe
+        .ingredients
+        .iter()
+        .map(|igr| {
+            matches!(
+                igr.quantity.as_ref().map(|q| q.value()),
+                Some(ScalableValue::Fixed(_) | ScalableValue::ByServings(_))
+            )
+        })
+        .collect()
+}
+
+fn scale_base(
+    base: ScaledRecipe,
+    fixed: &[bool],
+    factor: f64,
+    config: &HashMap<String, ScalingStrategy>,
+) -> (ScaledRecipe, HashMap<usize, ScaleMark>) {
+    let mut scaled = base;
+    let mut marks = HashMap::new();
+    for (index, igr) in scaled.ingredients.iter_mut().enumerate() {
+        let strategy = strategy_for(igr, config).unwrap_or(if fixed[index] {
+            ScalingStrategy::Fixed
+        } else {
+            ScalingStrategy::Linear
+        });
+        let Some(base_qty) = &igr.quantity else {
+            continue;
+        };
+        if let Some(quantity) = scale_quantity(base_qty, strategy, factor) {
+            igr.quantity = Some(quantity);
+        }
+        if let Some(mark) = strategy.mark() {
+            marks.insert(index, mark);
+        }
+    }
+    (scaled, marks)
+}
+
+/// Scales a recipe to some servings and applies the strategies
+///
+/// With no servings the recipe keeps its default scale and there are no marks.
+pub fn scale_recipe(
+    recipe: ScalableRecipe,
+    servings: Option<u32>,
+    converter: &Converter,
+    config: &HashMap<String, ScalingStrategy>,
+) -> (ScaledRecipe, HashMap<usize, ScaleMark>) {
+    match servings {
+        Some(servings) => {
+            let base = base_quantities(&recipe);
+            let mut scaled = recipe.scale(servings, converter);
+            let marks = apply_strategies(&mut scaled, &base, config);
+            (scaled, marks)
+        }
+        None => (recipe.default_scale(), HashMap::new()),
+    }
+}
+
+/// Divides the quantities for one of some batches
+///
+/// The strategies apply as when scaling, so eggs stay whole.
+pub fn split_batches(
+    recipe: &mut ScaledRecipe,
+    batches: u32,
+    config: &HashMap<String, ScalingStrategy>,
+) {
+    let factor = 1.0 / batches as f64;
+    for igr in &mut recipe.ingredients {
+        let strategy = strategy_for(igr, config).unwrap_or(ScalingStrategy::Linear);
+        let Some(q) = &igr.quantity else {
+            continue;
+        };
+        if let Some(quantity) = scale_quantity(q, strategy, factor) {
+            igr.quantity = Some(quantity);
+        }
+    }
+}
+
+pub fn scale_factor(scaled: &ScaledRecipe) -> Option<f64> {
+    let data = scaled.scaled_data()?;
+    let base = *scaled.metadata.servings()?.first()?;
+    if base == 0 {
+        return None;
+    }
+    Some(data.target.target_servings() as f64 / base as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategy_from_note() {
+        assert_eq!(
+            ScalingStrategy::from_note("scale: sqrt"),
+            Some(ScalingStrategy::Sqrt)
+        );
+        assert_eq!(
+            ScalingStrategy::from_note("large, scale=stepwise"),
+            Some(ScalingStrategy::Stepwise)
+        );
+        assert_eq!(ScalingStrategy::from_note("finely chopped"), None);
+        assert_eq!(ScalingStrategy::from_note("scale: whatever"), None);
+    }
+
+    #[test]
+    fn parse_scale_target() {
+        assert_eq!(
+            "flour=1kg".parse(),
+            Ok(ScaleTarget {
+                ingredient: "flour".into(),
+                value: 1.0,
+                unit: Some("kg".into())
+            })
+        );
+        assert_eq!(
+            "egg yolk = 2,5".parse(),
+            Ok(ScaleTarget {
+                ingredient: "egg yolk".into(),
+                value: 2.5,
+                unit: None
+            })
+        );
+        assert!("flour".parse::<ScaleTarget>().is_err());
+        assert!("flour=kg".parse::<ScaleTarget>().is_err());
+        assert!("flour=0g".parse::<ScaleTarget>().is_err());
+    }
+
+    #[test]
+    fn apply_strategy() {
+        assert_eq!(ScalingStrategy::Linear.apply(2.0, 2.0), 4.0);
+        assert_eq!(ScalingStrategy::Fixed.apply(2.0, 2.0), 2.0);
+        assert_eq!(ScalingStrategy::Sqrt.apply(1.0, 4.0), 2.0);
+        assert_eq!(ScalingStrategy::Stepwise.apply(3.0, 0.5), 2.0);
+        assert_eq!(ScalingStrategy::Stepwise.apply(1.0, 0.25), 1.0);
+    }
+
+    #[test]
+    fn approximate() {
+        let q = Quantity::new(Value::Text("about 150".into()), Some("g".into()));
+        assert!(is_approx(&q));
+        let scaled = scale_quantity(&q, ScalingStrategy::Linear, 1.5).unwrap();
+        assert!(matches!(scaled.value(), Value::Text(t) if t == "~225"));
+        let text = Quantity::new(Value::Text("a pinch".into()), None);
+        assert!(scale_quantity(&text, ScalingStrategy::Linear, 2.0).is_none());
+    }
+}
@@ -0,0 +1,319 @@
+//! Lay out the steps of a recipe on a timeline
+//!
+//! The duration of a step is the sum of its timers. Steps are assumed to be
+//! done one after the other, in order, but [`crate::graph::critical_path`]
+//! finds which ones can overlap.
+//!
+//! Each step also has a pace for hands-free cooking: the time to read it
+//! aloud, from its word count, plus its timers.
+
+use std::fmt::Write as _;
+
+use anyhow::{bail, Result};
+use cooklang::{
+    model::Item,
+    quantity::{Quantity, Value},
+    Content, Converter, ScaledRecipe,
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledStep {
+    pub section: Option<String>,
+    #[serde(skip)]
+    pub section_index: usize,
+    pub number: u32,
+    pub text: String,
+    /// Seconds from the start of the recipe
+    pub start: u64,
+    /// Seconds
+    pub duration: u64,
+    /// Seconds from the start of the recipe when the steps it depends on are
+    /// done, the same as `start` until the critical path is computed
+    pub earliest: u64,
+    /// In the longest chain of dependent steps
+    pub critical: bool,
+    pub timers: Vec<ScheduledTimer>,
+    /// Words in the text
+    pub words: usize,
+    /// Seconds to read the text aloud
+    pub reading: u64,
+    /// Seconds before moving to the next step hands-free, the reading and
+    /// the timers
+    pub pace: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledTimer {
+    pub name: Option<String>,
+    /// Seconds
+    pub duration: u64,
+}
+
+/// Builds the schedule of a recipe
+pub fn schedule(recipe: &ScaledRecipe, converter: &Converter) -> Vec<ScheduledStep> {
+    let mut steps = Vec::new();
+    let mut now = 0;
+    for (section_index, section) in recipe.sections.iter().enumerate() {
+        for content in &section.content {
+            let Content::Step(step) = content else {
+                continue;
+            };
+            let mut text = String::new();
+            let mut timers = Vec::new();
+            for item in &step.items {
+                match item {
+                    Item::Text { value } => text += value,
+                    &Item::Ingredient { index } => {
+                        text += &recipe.ingredients[index].display_name()
+                    }
+                    &Item::Cookware { index } => text += &recipe.cookware[index].name,
+                    &Item::Timer { index } => {
+                        let timer = &recipe.timers[index];
+                        if let Some(q) = &timer.quantity {
+                            write!(&mut text, "{q}").unwrap();
+                        } else if let Some(name) = &timer.name {
+                            text += name;
+                        }
+                        if let Some(duration) =
+                            timer.quantity.as_ref().and_then(|q| seconds(q, converter))
+                        {
+                            timers.push(ScheduledTimer {
+                                name: timer.name.clone(),
+                                duration,
+                            });
+                        }
+                    }
+                    &Item::InlineQuantity { index } => {
+                        write!(&mut text, "{}", recipe.inline_quantities[index]).unwrap()
+                    }
+                }
+            }
+            let duration = timers.iter().map(|t| t.duration).sum();
+            let words = text.split_whitespace().count();
+            let reading = reading_seconds(words);
+            steps.push(ScheduledStep {
+                section: section.name.clone(),
+                section_index,
+                number: step.number,
+                text: text.trim().to_string(),
+                start: now,
+                duration,
+                earliest: now,
+                critical: false,
+                timers,
+                words,
+                reading,
+                pace: reading + duration,
+            });
+            now += duration;
+        }
+    }
+    steps
+}
+
+/// Words per minute when reading aloud
+const READING_WPM: usize = 150;
+
+/// Seconds to read some words aloud, at least a couple
+fn reading_seconds(words: usize) -> u64 {
+    ((words * 60).div_ceil(READING_WPM) as u64).max(2)
+}
+
+/// Total duration of a schedule in seconds
+pub fn total(steps: &[ScheduledStep]) -> u64 {
+    steps.last().map(|s| s.start + s.duration).unwrap_or(0)
+}
+
+/// Total duration of a schedule in seconds, doing the independent steps at
+/// the same time
+pub fn parallel_total(steps: &[ScheduledStep]) -> u64 {
+    steps
+        .iter()
+        .map(|s| s.earliest + s.duration)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Value of a time quantity in seconds
+///
+/// For ranges the upper bound is used, it's safer to plan for longer.
+pub fn seconds(quantity: &Quantity<Value>, converter: &Converter) -> Option<u64> {
+    let mut q = quantity.clone();
+    if q.convert("s", converter).is_err() {
+        // no time units loaded, try the common ones
+        let factor = match q.unit()? {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            _ => return None,
+        };
+        return value_upper(q.value()).map(|v| (v * factor).round() as u64);
+    }
+    value_upper(q.value()).map(|v| v.round() as u64)
+}
+
+fn value_upper(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => Some(n.value()),
+        Value::Range { end, .. } => Some(end.value()),
+        Value::Text(_) => None,
+    }
+}
+
+/// Formats seconds like `1h 30min`
+pub fn format_duration(secs: u64) -> String {
+    let h = secs / 3600;
+    let m = (secs % 3600) / 60;
+    let s = secs % 60;
+    let mut out = Vec::new();
+    if h > 0 {
+        out.push(format!("{h}h"));
+    }
+    if m > 0 {
+        out.push(format!("{m}min"));
+    }
+    if s > 0 || out.is_empty() {
+        out.push(format!("{s}s"));
+    }
+    out.join(" ")
+}
+
+/// A time of the day in minutes since midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockTime(pub u32);
+
+impl std::str::FromStr for ClockTime {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((h, m)) = s.trim().split_once(':') else {
+            bail!("expected HH:MM");
+        };
+        let (h, m) = (h.parse::<u32>()?, m.parse::<u32>()?);
+        if h > 23 || m > 59 {
+            bail!("invalid time: '{s}'");
+        }
+        Ok(Self(h * 60 + m))
+    }
+}
+
+impl ClockTime {
+    /// Time `secs` before this one, wrapping around midnight
+    pub fn minus(self, secs: u64) -> Self {
+        let day = 24 * 60;
+        let mins = (secs / 60) as i64 + (!secs.is_multiple_of(60)) as i64;
+        Self((self.0 as i64 - mins).rem_euclid(day) as u32)
+    }
+}
+
+impl std::fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+/// Writes the schedule for humans
+///
+/// With `finish_at`, the start time of every step is shown so the recipe is
+/// done at that time.
+pub fn print_schedule(
+    w: &mut impl std::io::Write,
+    steps: &[ScheduledStep],
+    finish_at: Option<ClockTime>,
+) -> Result<()> {
+    use yansi::Paint;
+
+    let total = total(steps);
+    let parallel = parallel_total(steps);
+    write!(w, "{} {}", "Total time:".bold(), format_duration(total))?;
+    if parallel < total {
+        write!(
+            w,
+            " {}",
+            format!("({} doing steps in parallel)", format_duration(parallel)).dim()
+        )?;
+    }
+    writeln!(w)?;
+    if let Some(finish) = finish_at {
+        writeln!(w, "{} {}", "Start at:".bold(), finish.minus(total))?;
+    }
+    writeln!(w)?;
+
+    let mut table = tabular::Table::new("{:>}  {:>}{:<} {:<}  {:<}");
+    let mut last_section = None;
+    for step in steps {
+        if step.section.is_some() && step.section != last_section {
+            table.add_heading(format!("{}:", step.section.as_deref().unwrap_or_default()));
+        }
+        last_section = step.section.clone();
+
+        let remaining = total - step.start;
+        let when = match finish_at {
+            Some(finish) => finish.minus(remaining).to_string(),
+            None => format!("T-{}", format_duration(remaining)),
+        };
+        let duration = if step.duration > 0 {
+            format_duration(step.duration)
+        } else {
+            "-".into()
+        };
+        let mut text = step.text.clone();
+        if text.chars().count() > 60 {
+            text = text.chars().take(59).collect::<String>() + "…";
+        }
+        table.add_row(
+            tabular::Row::new()
+                .with_ansi_cell(when.green())
+                .with_cell(format!("{}.", step.number))
+                .with_ansi_cell(if step.critical {
+                    "*".red().to_string()
+                } else {
+                    String::new()
+                })
+                .with_ansi_cell(duration.cyan())
+                .with_cell(text),
+        );
+    }
+    write!(w, "{table}")?;
+    if steps.iter().any(|s| s.critical) && parallel < total {
+        writeln!(
+            w,
+            "\n{} critical path, delaying these delays the recipe",
+            "*".red()
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_and_clock() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(5400), "1h 30min");
+        assert_eq!(format_duration(90), "1min 30s");
+        let t: ClockTime = "19:00".parse().unwrap();
+        assert_eq!(t.minus(5400).to_string(), "17:30");
+        assert_eq!(t.minus(30).to_string(), "18:59");
+        assert_eq!(
+            "00:30"
+                .parse::<ClockTime>()
+                .unwrap()
+                .minus(3600)
+                .to_string(),
+            "23:30"
+        );
+        assert!("25:00".parse::<ClockTime>().is_err());
+    }
+
+    #[test]
+    fn reading_pace() {
+        assert_eq!(reading_seconds(0), 2);
+        assert_eq!(reading_seconds(150), 60);
+        assert_eq!(reading_seconds(20), 8);
+    }
+}
@@ -0,0 +1,427 @@
+//! Recipes for the time of the year
+//!
+//! A recipe is in season by its `season` metadata, like `season: jun-aug` or
+//! `season: [spring, december]`, or else by its tags: a tag with a `season`
+//! in the `[ui.tags]` config, or a tag named after a season or a month. The
+//! seasons are the ones of the northern hemisphere, set them in the config
+//! to change them.
+//!
+//! Recipes cooked around the same day in past years, by the cook log, are
+//! suggested too.
+//!
+//! The produce seasons are a bundled dataset, `data/produce.toml`, of the
+//! months each produce is in season in a region. The config `[season]`
+//! chooses the region and turns on the warnings for recipes with produce out
+//! of season.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Context as _, Result};
+use camino::Utf8Path;
+use cooklang::Metadata;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, TagProps},
+    diet::{contains_words, words},
+    pantry::Date,
+};
+
+/// A set of months
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Months(u16);
+
+const MONTH_NAMES: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+impl Months {
+    /// From month `from` to `to`, both from 1 to 12, going past december
+    fn range(from: u32, to: u32) -> Self {
+        let mut months = 0;
+        let mut m = from;
+        loop {
+            months |= 1 << (m - 1);
+            if m == to {
+                break;
+            }
+            m = m % 12 + 1;
+        }
+        Self(months)
+    }
+
+    pub fn contains(self, month: u32) -> bool {
+        (1..=12).contains(&month) && self.0 & (1 << (month - 1)) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_all(self) -> bool {
+        self.0 == 0xfff
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Parses a season, month or range of them, separated by commas
+    ///
+    /// `summer`, `december`, `dec`, `12`, `jun-aug` and `nov-feb` are valid.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut months = Self::default();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let part = part.to_lowercase();
+            let m = if let Some(season) = named_season(&part) {
+                season
+            } else if let Some((from, to)) = part.split_once('-') {
+                Self::range(month_number(from.trim())?, month_number(to.trim())?)
+            } else {
+                let m = month_number(&part)?;
+                Self::range(m, m)
+            };
+            months = months.union(m);
+        }
+        (!months.is_empty()).then_some(months)
+    }
+}
+
+impl std::fmt::Display for Months {
+    /// Ranges of months, like `jun-aug, dec`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_all() {
+            return f.write_str("all year");
+        }
+        // start after a month out, so no range is split
+        let start = (1..=12).find(|&m| !self.contains(m)).unwrap_or(1);
+        let mut ranges = Vec::new();
+        let mut current: Option<(u32, u32)> = None;
+        for i in 0..12 {
+            let m = (start - 1 + i) % 12 + 1;
+            if self.contains(m) {
+                current = Some(current.map_or((m, m), |(from, _)| (from, m)));
+            } else if let Some(range) = current.take() {
+                ranges.push(range);
+            }
+        }
+        ranges.extend(current);
+        let short = |m: u32| &MONTH_NAMES[m as usize - 1][..3];
+        let ranges = ranges
+            .into_iter()
+            .map(|(from, to)| {
+                if from == to {
+                    short(from).to_string()
+                } else {
+                    format!("{}-{}", short(from), short(to))
+                }
+            })
+            .collect::<Vec<_>>();
+        f.write_str(&ranges.join(", "))
+    }
+}
+
+/// Name of a month from 1 to 12, capitalized
+pub fn month_name(month: u32) -> String {
+    let name = MONTH_NAMES[month as usize - 1];
+    name[..1].to_uppercase() + &name[1..]
+}
+
+fn month_number(s: &str) -> Option<u32> {
+    if let Ok(n) = s.parse::<u32>() {
+        return (1..=12).contains(&n).then_some(n);
+    }
+    if s.len() < 3 {
+        return None;
+    }
+    MONTH_NAMES
+        .iter()
+        .position(|name| name.starts_with(s))
+        .map(|i| i as u32 + 1)
+}
+
+fn named_season(s: &str) -> Option<Months> {
+    let (from, to) = match s {
+        "spring" => (3, 5),
+        "summer" => (6, 8),
+        "autumn" | "fall" => (9, 11),
+        "winter" => (12, 2),
+        _ => return None,
+    };
+    Some(Months::range(from, to))
+}
+
+/// Months a recipe is in season, `None` if it's not seasonal
+pub fn recipe_months(metadata: &Metadata, tags: &HashMap<String, TagProps>) -> Option<Months> {
+    if let Some(season) = metadata.map.get("season") {
+        let parts = match season.as_sequence() {
+            Some(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+            None => season.as_str().into_iter().collect::<Vec<_>>(),
+        };
+        return Months::parse(&parts.join(","));
+    }
+    let months = metadata
+        .tags()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(
+            |tag| match tags.get(tag.as_ref()).and_then(|t| t.season.as_deref()) {
+                Some(season) => Months::parse(season),
+                None => {
+                    named_season(tag).or_else(|| month_number(tag).map(|m| Months::range(m, m)))
+                }
+            },
+        )
+        .fold(Months::default(), Months::union);
+    (!months.is_empty()).then_some(months)
+}
+
+/// Days around the same day in past years that count as an anniversary
+const ANNIVERSARY_DAYS: i64 = 3;
+
+/// Last day in a past year around the same day as `today`
+pub fn anniversary(cooked: &[Date], today: Date) -> Option<Date> {
+    let (year, _, _) = today.ymd();
+    cooked.iter().rev().copied().find(|d| {
+        let (y, m, day) = d.ymd();
+        y < year && Date::from_ymd(year, m, day).days_since(today).abs() <= ANNIVERSARY_DAYS
+    })
+}
+
+const PRODUCE: &str = include_str!("data/produce.toml");
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SeasonConfig {
+    /// Region of the produce seasons, like `north-america`
+    pub region: String,
+    /// Warn when a recipe or menu has produce out of season
+    pub warnings: bool,
+}
+
+impl Default for SeasonConfig {
+    fn default() -> Self {
+        Self {
+            region: "europe".into(),
+            warnings: false,
+        }
+    }
+}
+
+/// Months each produce is in season, by region
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProduceData {
+    /// Words of ingredients that are not fresh, like "canned"
+    processed: Vec<String>,
+    regions: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl ProduceData {
+    pub fn load(config: &Config, base_path: &Utf8Path) -> Result<Self> {
+        let mut data: Self = toml::from_str(PRODUCE).expect("bundled produce data");
+        if let Some(path) = config.produce(base_path) {
+            tracing::debug!("Loading produce data {path}");
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read produce file: {path}"))?;
+            let user: Self =
+                toml::from_str(&text).with_context(|| format!("Bad produce file: {path}"))?;
+            data.extend(user);
+        }
+        Ok(data)
+    }
+
+    fn extend(&mut self, other: Self) {
+        for word in other.processed {
+            if !self.processed.contains(&word) {
+                self.processed.push(word);
+            }
+        }
+        for (name, produce) in other.regions {
+            self.regions.entry(name).or_default().extend(produce);
+        }
+    }
+
+    pub fn region(&self, name: &str) -> Result<Region<'_>> {
+        let Some(produce) = self.regions.get(name) else {
+            let names = self.regions.keys().cloned().collect::<Vec<_>>();
+            bail!(
+                "Unknown region '{name}', expected one of {}",
+                names.join(", ")
+            );
+        };
+        let produce = produce
+            .iter()
+            .filter_map(|(produce, months)| match Months::parse(months) {
+                Some(m) => Some((produce.as_str(), m)),
+                None => {
+                    tracing::warn!("Bad months for '{produce}' in region '{name}': {months}");
+                    None
+                }
+            })
+            .collect();
+        Ok(Region {
+            produce,
+            processed: &self.processed,
+        })
+    }
+}
+
+/// The produce seasons of a region
+pub struct Region<'a> {
+    produce: Vec<(&'a str, Months)>,
+    processed: &'a [String],
+}
+
+impl<'a> Region<'a> {
+    /// The produce an ingredient is and the months it's in season
+    pub fn months_of(&self, ingredient: &str) -> Option<(&'a str, Months)> {
+        let lower = ingredient.to_lowercase();
+        let name = words(&lower);
+        if self
+            .processed
+            .iter()
+            .any(|p| contains_words(&name, &words(p)))
+        {
+            return None;
+        }
+        self.produce
+            .iter()
+            .filter(|(produce, _)| contains_words(&name, &words(produce)))
+            .max_by_key(|(produce, _)| produce.len())
+            .copied()
+    }
+
+    /// Produce in season in a month
+    pub fn in_season(&self, month: u32) -> impl Iterator<Item = (&'a str, Months)> + '_ {
+        self.produce
+            .iter()
+            .copied()
+            .filter(move |(_, months)| months.contains(month))
+    }
+
+    /// Ingredients that are produce out of season in a month, with the
+    /// months they are in season
+    pub fn out_of_season<'i>(
+        &self,
+        ingredients: impl IntoIterator<Item = &'i str>,
+        month: u32,
+    ) -> Vec<(String, Months)> {
+        let mut out = Vec::<(String, Months)>::new();
+        for ingredient in ingredients {
+            let name = ingredient.to_lowercase();
+            if out.iter().any(|(n, _)| *n == name) {
+                continue;
+            }
+            if let Some((_, months)) = self.months_of(&name) {
+                if !months.contains(month) {
+                    out.push((name, months));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Writes the produce out of season, after a rendered recipe or menu
+pub fn write_season_warnings(
+    mut w: impl std::io::Write,
+    month: u32,
+    out: &[(String, Months)],
+) -> std::io::Result<()> {
+    use yansi::Paint;
+
+    if out.is_empty() {
+        return Ok(());
+    }
+    writeln!(
+        w,
+        "\n{}:",
+        format!("Out of season in {}", month_name(month))
+            .bold()
+            .underline()
+    )?;
+    for (ingredient, months) in out {
+        writeln!(w, "  {} {}", ingredient.yellow().bold(), months.dim())?;
+    }
+    Ok(())
+}
+
+/// Writes the season warnings of some ingredients if they are on in the
+/// config, for the current month
+pub fn warn_out_of_season<'i>(
+    w: impl std::io::Write,
+    config: &Config,
+    base_path: &Utf8Path,
+    ingredients: impl IntoIterator<Item = &'i str>,
+) -> Result<()> {
+    if !config.season.warnings {
+        return Ok(());
+    }
+    let data = ProduceData::load(config, base_path)?;
+    let region = data.region(&config.season.region)?;
+    let month = Date::today().ymd().1;
+    write_season_warnings(w, month, &region.out_of_season(ingredients, month))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn months() {
+        let summer = Months::parse("summer").unwrap();
+        assert!(summer.contains(7));
+        assert!(!summer.contains(9));
+        assert_eq!(Months::parse("jun-aug"), Some(summer));
+        assert_eq!(Months::parse("June - August"), Some(summer));
+        assert_eq!(Months::parse("6,7,8"), Some(summer));
+        let winter = Months::parse("nov-feb").unwrap();
+        assert!(winter.contains(1) && winter.contains(11) && !winter.contains(3));
+        assert_eq!(Months::parse("ma"), None);
+        assert_eq!(Months::parse("13"), None);
+        assert_eq!(Months::parse("soon"), None);
+        assert_eq!(summer.to_string(), "jun-aug");
+        assert_eq!(
+            Months::parse("nov-feb,jun").unwrap().to_string(),
+            "jun, nov-feb"
+        );
+        assert_eq!(Months::parse("jan-dec").unwrap().to_string(), "all year");
+    }
+
+    #[test]
+    fn produce_seasons() {
+        let data: ProduceData = toml::from_str(PRODUCE).unwrap();
+        let europe = data.region("europe").unwrap();
+        assert_eq!(europe.months_of("Green Asparagus").unwrap().0, "asparagus");
+        assert_eq!(
+            europe.months_of("butternut squash").unwrap().0,
+            "butternut squash"
+        );
+        assert!(europe.months_of("tomato paste").is_none());
+        let out = europe.out_of_season(["asparagus", "strawberries", "flour"], 12);
+        assert_eq!(out.len(), 2);
+        assert!(data.region("atlantis").is_err());
+    }
+
+    #[test]
+    fn anniversaries() {
+        let day = |s: &str| s.parse::<Date>().unwrap();
+        let today = day("2025-06-12");
+        let cooked = [day("2023-06-10"), day("2024-06-20"), day("2025-06-12")];
+        assert_eq!(anniversary(&cooked, today), Some(day("2023-06-10")));
+        assert_eq!(anniversary(&cooked[1..], today), None);
+    }
+}
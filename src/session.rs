@@ -0,0 +1,307 @@
+//! Cooking session state for home automation
+//!
+//! In `chef cook --follow` the state of the session, the current step and the
+//! running timers, is published to an MQTT topic so Home Assistant, or any
+//! other MQTT client, can show it. The state is a retained JSON message in the
+//! topic and events go to `<topic>/event`:
+//!
+//! ```json
+//! {"active":true,"recipe":"Bread","step":3,"position":3,"steps":12,"text":"Bake...","timers":[...]}
+//! {"event":"timer_fired","recipe":"Bread","timer":"bake"}
+//! ```
+//!
+//! Messages are sent with `mosquitto_pub`. A failure is reported once and
+//! stops the publishing, but never the session.
+//!
+//! In a collection, the state is also saved to `.cooklang/cook_session.json`
+//! at every step so `chef cook --resume` can go on after an interruption.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context as _;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::{schedule::ScheduledStep, COOK_DIR};
+
+pub const SAVED_SESSION_FILE: &str = "cook_session.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionConfig {
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default = "default_topic")]
+    pub topic: String,
+}
+
+fn default_host() -> String {
+    "localhost".into()
+}
+
+fn default_topic() -> String {
+    "chef/session".into()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningTimer {
+    pub recipe: String,
+    pub name: Option<String>,
+    /// Seconds
+    pub duration: u64,
+    /// Unix timestamp, in seconds
+    pub ends_at: u64,
+    #[serde(skip, default = "Instant::now")]
+    end: Instant,
+}
+
+/// State of an interrupted `chef cook --follow`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedSession {
+    /// The `--menu` of the command
+    pub menu: Vec<String>,
+    /// Steps started, the last one is the current one
+    pub position: usize,
+    pub timers: Vec<RunningTimer>,
+}
+
+impl SavedSession {
+    pub fn path(base_path: &Utf8Path) -> Utf8PathBuf {
+        base_path.join(COOK_DIR).join(SAVED_SESSION_FILE)
+    }
+
+    pub fn load(base_path: &Utf8Path) -> anyhow::Result<Option<Self>> {
+        let path = Self::path(base_path);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read cooking session: {path}"))?;
+        let saved =
+            serde_json::from_str(&text).with_context(|| format!("Bad cooking session: {path}"))?;
+        Ok(Some(saved))
+    }
+
+    pub fn save(&self, base_path: &Utf8Path) -> anyhow::Result<()> {
+        let path = Self::path(base_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = serde_json::to_string(self)?;
+        std::fs::write(&path, text).with_context(|| format!("Cannot write cooking session: {path}"))
+    }
+
+    pub fn remove(base_path: &Utf8Path) -> anyhow::Result<()> {
+        let path = Self::path(base_path);
+        if path.is_file() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Cannot remove cooking session: {path}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct State<'a> {
+    active: bool,
+    recipe: Option<&'a str>,
+    step: Option<u32>,
+    /// Position of the step in the menu, from 1
+    position: usize,
+    steps: usize,
+    text: Option<&'a str>,
+    timers: &'a [RunningTimer],
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    StepStarted {
+        recipe: &'a str,
+        step: u32,
+    },
+    TimerFired {
+        recipe: &'a str,
+        timer: Option<&'a str>,
+    },
+    Finished,
+}
+
+/// Current step and running timers of a cooking session
+pub struct Session<'c> {
+    config: Option<&'c SessionConfig>,
+    steps: usize,
+    position: usize,
+    current: Option<(String, u32, String)>,
+    timers: Vec<RunningTimer>,
+}
+
+impl<'c> Session<'c> {
+    pub fn new(config: Option<&'c SessionConfig>, steps: usize) -> Self {
+        Self {
+            config,
+            steps,
+            position: 0,
+            current: None,
+            timers: Vec::new(),
+        }
+    }
+
+    /// Goes on from a saved session, before the step it was in
+    ///
+    /// The timers keep their end time, the ones that ended are removed by the
+    /// next [`Session::tick`].
+    pub fn resume(&mut self, position: usize, timers: Vec<RunningTimer>) {
+        self.position = position.saturating_sub(1);
+        let now = Instant::now();
+        let unix_now = unix_now();
+        self.timers = timers
+            .into_iter()
+            .map(|t| RunningTimer {
+                end: now + Duration::from_secs(t.ends_at.saturating_sub(unix_now)),
+                ..t
+            })
+            .collect();
+    }
+
+    /// Moves to the next step and starts its timers
+    pub fn start_step(&mut self, recipe: &str, step: &ScheduledStep) {
+        let now = Instant::now();
+        let unix_now = unix_now();
+        for timer in &step.timers {
+            self.timers.push(RunningTimer {
+                recipe: recipe.to_string(),
+                name: timer.name.clone(),
+                duration: timer.duration,
+                ends_at: unix_now + timer.duration,
+                end: now + Duration::from_secs(timer.duration),
+            });
+        }
+        self.enter_step(recipe, step);
+    }
+
+    /// Moves to the next step without starting its timers, when resuming
+    pub fn resume_step(&mut self, recipe: &str, step: &ScheduledStep) {
+        self.enter_step(recipe, step);
+    }
+
+    fn enter_step(&mut self, recipe: &str, step: &ScheduledStep) {
+        self.position += 1;
+        self.current = Some((recipe.to_string(), step.number, step.text.clone()));
+        self.publish_event(&Event::StepStarted {
+            recipe,
+            step: step.number,
+        });
+        self.publish_state();
+    }
+
+    /// Removes and returns the timers that have finished
+    pub fn tick(&mut self) -> Vec<RunningTimer> {
+        let now = Instant::now();
+        let (fired, running) = std::mem::take(&mut self.timers)
+            .into_iter()
+            .partition::<Vec<_>, _>(|t| t.end <= now);
+        self.timers = running;
+        if !fired.is_empty() {
+            for timer in &fired {
+                self.publish_event(&Event::TimerFired {
+                    recipe: &timer.recipe,
+                    timer: timer.name.as_deref(),
+                });
+            }
+            self.publish_state();
+        }
+        fired
+    }
+
+    pub fn has_timers(&self) -> bool {
+        !self.timers.is_empty()
+    }
+
+    /// Steps started, the last one is the current one
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn timers(&self) -> &[RunningTimer] {
+        &self.timers
+    }
+
+    pub fn finish(&mut self) {
+        self.current = None;
+        self.timers.clear();
+        self.publish_event(&Event::Finished);
+        self.publish_state();
+    }
+
+    fn publish_state(&mut self) {
+        let state = State {
+            active: self.current.is_some(),
+            recipe: self.current.as_ref().map(|(r, _, _)| r.as_str()),
+            step: self.current.as_ref().map(|(_, s, _)| *s),
+            position: self.position,
+            steps: self.steps,
+            text: self.current.as_ref().map(|(_, _, t)| t.as_str()),
+            timers: &self.timers,
+        };
+        let payload = serde_json::to_vec(&state).expect("state serialization");
+        if let Some(config) = self.config {
+            self.publish(&config.topic, &payload, true);
+        }
+    }
+
+    fn publish_event(&mut self, event: &Event) {
+        let payload = serde_json::to_vec(event).expect("event serialization");
+        if let Some(config) = self.config {
+            self.publish(&format!("{}/event", config.topic), &payload, false);
+        }
+    }
+
+    fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) {
+        let Some(config) = self.config else { return };
+        if let Err(e) = publish(config, topic, payload, retain) {
+            tracing::warn!("Publishing to '{topic}' failed, session updates stopped: {e:#}");
+            self.config = None;
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn publish(
+    config: &SessionConfig,
+    topic: &str,
+    payload: &[u8],
+    retain: bool,
+) -> anyhow::Result<()> {
+    let mut command = Command::new("mosquitto_pub");
+    command.args(["-h", &config.host, "-t", topic, "-s"]);
+    if let Some(port) = config.port {
+        command.args(["-p", &port.to_string()]);
+    }
+    if retain {
+        command.arg("-r");
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload)?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("exited with {status}");
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Recipe sharing links
+//!
+//! A link has the whole recipe in it, the name and the text compressed and
+//! encoded in base64 after `#chef.` and the format version, so nothing has to
+//! run to open it:
+//!
+//! ```text
+//! chef:recipe#chef.1AY2QQU...
+//! ```
+//!
+//! With a paste service the text is uploaded instead and the link is the one
+//! of the paste.
+
+use std::io::{Read, Write};
+
+use anyhow::{bail, Context as _, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_LINK_PREFIX: &str = "chef:recipe#";
+/// Start of the fragment with the recipe, other fragments are not links
+const DATA_MARKER: &str = "chef.";
+const FORMAT_VERSION: char = '1';
+/// Largest recipe a link can unpack to
+const MAX_TEXT_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShareConfig {
+    /// Start of the links, up to and with the `#`
+    ///
+    /// Like a web page that reads the recipe from the URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_prefix: Option<String>,
+    /// Paste service to upload recipes to with `share --paste`
+    ///
+    /// The text is sent in the body of a POST and the response has to be
+    /// the URL of the paste, like <https://paste.rs>.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paste_url: Option<String>,
+}
+
+impl ShareConfig {
+    pub fn is_empty(&self) -> bool {
+        self.link_prefix.is_none() && self.paste_url.is_none()
+    }
+}
+
+/// Builds a link with the recipe in it
+pub fn encode(prefix: &str, name: &str, text: &str) -> Result<String> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    writeln!(encoder, "{name}")?;
+    encoder.write_all(text.as_bytes())?;
+    let data = URL_SAFE_NO_PAD.encode(encoder.finish()?);
+    Ok(format!("{prefix}{DATA_MARKER}{FORMAT_VERSION}{data}"))
+}
+
+/// Gets the name and the text of a recipe from a link
+///
+/// Returns `None` if the link has no recipe in it.
+pub fn decode(link: &str) -> Result<Option<(String, String)>> {
+    let Some((_, data)) = link.trim().rsplit_once(&format!("#{DATA_MARKER}")) else {
+        return Ok(None);
+    };
+    let Some(data) = data.strip_prefix(FORMAT_VERSION) else {
+        bail!("Unknown link format, it may come from a newer chef version");
+    };
+    let bytes = URL_SAFE_NO_PAD
+        .decode(data)
+        .context("Invalid link, it may be incomplete")?;
+    let mut content = String::new();
+    DeflateDecoder::new(bytes.as_slice())
+        .take(MAX_TEXT_SIZE + 1)
+        .read_to_string(&mut content)
+        .context("Invalid link, it may be incomplete")?;
+    if content.len() as u64 > MAX_TEXT_SIZE {
+        bail!("Invalid link, the recipe is too big");
+    }
+    let Some((name, text)) = content.split_once('\n') else {
+        bail!("Invalid link, it has no recipe");
+    };
+    Ok(Some((name.to_string(), text.to_string())))
+}
+
+/// Uploads a recipe, returns the URL of the paste
+pub fn paste(url: &str, text: &str) -> Result<String> {
+    let link = ureq::post(url)
+        .set("User-Agent", crate::USER_AGENT)
+        .send_string(text)
+        .with_context(|| format!("Failed to upload to {url}"))?
+        .into_string()?;
+    let link = link.trim();
+    if !link.starts_with("http") {
+        bail!("The paste service didn't answer with a URL: {link}");
+    }
+    Ok(link.to_string())
+}
+
+/// Downloads a pasted recipe
+pub fn fetch(url: &str) -> Result<String> {
+    ureq::get(url)
+        .set("User-Agent", crate::USER_AGENT)
+        .call()
+        .with_context(|| format!("Failed to fetch {url}"))?
+        .into_string()
+        .context("The recipe is not valid text")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let text = "Mix @flour{200%g} and @water{120%ml}.\n\nBake for ~{30%min}.\n";
+        let link = encode(DEFAULT_LINK_PREFIX, "Flat bread", text).unwrap();
+        assert!(link.starts_with(DEFAULT_LINK_PREFIX));
+        let (name, decoded) = decode(&link).unwrap().unwrap();
+        assert_eq!(name, "Flat bread");
+        assert_eq!(decoded, text);
+        assert!(decode("https://paste.rs/abc").unwrap().is_none());
+        assert!(decode("https://example.com/page#section")
+            .unwrap()
+            .is_none());
+        assert!(decode("chef:recipe#chef.1AAAA").is_err());
+        assert!(decode("chef:recipe#chef.2AAAA").is_err());
+        let big = "a".repeat(MAX_TEXT_SIZE as usize + 1);
+        let link = encode(DEFAULT_LINK_PREFIX, "Big", &big).unwrap();
+        assert!(decode(&link).is_err());
+    }
+}
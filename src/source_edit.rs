@@ -0,0 +1,647 @@
+//! Edit cooklang source text
+//!
+//! These functions work on the text of a recipe instead of the parsed recipe,
+//! so everything that is not touched (comments, formatting, metadata style)
+//! is kept as is.
+
+use std::{fmt::Write as _, ops::Range};
+
+use crate::util::fmt_number;
+
+/// Splits the text into the metadata block and the body
+///
+/// The metadata block is either a YAML front-matter or the old style `>>`
+/// lines at the start of the file.
+pub fn split_metadata(text: &str) -> (&str, &str) {
+    if let Some(fm) = front_matter_range(text) {
+        return (&text[..fm.end], &text[fm.end..]);
+    }
+    let mut end = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with(">>") || (trimmed.is_empty() && end > 0) {
+            end += line.len();
+        } else {
+            break;
+        }
+    }
+    (&text[..end], &text[end..])
+}
+
+/// Range of the whole front-matter, delimiters included
+fn front_matter_range(text: &str) -> Option<Range<usize>> {
+    let first = text.split_inclusive('\n').next()?;
+    if first.trim_end() != "---" {
+        return None;
+    }
+    let mut pos = first.len();
+    for line in text[pos..].split_inclusive('\n') {
+        pos += line.len();
+        if line.trim_end() == "---" {
+            return Some(0..pos);
+        }
+    }
+    None
+}
+
+/// Sets a metadata key in the text
+///
+/// `value` is YAML. If the recipe uses old style metadata, the value has to
+/// be a plain string. If there is no metadata yet, a front-matter is created.
+pub fn set_metadata(text: &str, key: &str, value: &str) -> String {
+    if let Some(fm) = front_matter_range(text) {
+        let block = &text[fm.clone()];
+        let mut lines = block.split_inclusive('\n').collect::<Vec<_>>();
+        let new_line = format!("{key}: {value}\n");
+        if let Some((start, len)) = yaml_key_lines(&lines, key) {
+            lines.splice(start..start + len, [new_line.as_str()]);
+        } else {
+            let close = lines.len() - 1;
+            lines.insert(close, new_line.as_str());
+        }
+        let mut out = lines.concat();
+        out.push_str(&text[fm.end..]);
+        return out;
+    }
+
+    let (meta, body) = split_metadata(text);
+    if meta.is_empty() {
+        return format!("---\n{key}: {value}\n---\n\n{body}");
+    }
+    let new_line = format!(">> {key}: {value}\n");
+    let mut replaced = false;
+    let mut out = String::with_capacity(text.len() + new_line.len());
+    for line in meta.split_inclusive('\n') {
+        if old_style_key(line) == Some(key) {
+            if !replaced {
+                out.push_str(&new_line);
+                replaced = true;
+            }
+        } else {
+            out.push_str(line);
+        }
+    }
+    if !replaced {
+        let trailing = out.len() - out.trim_end().len();
+        out.truncate(out.len() - trailing);
+        out.push('\n');
+        out.push_str(&new_line);
+        out.push('\n');
+    }
+    out.push_str(body);
+    out
+}
+
+/// Removes a metadata key from the text
+pub fn remove_metadata(text: &str, key: &str) -> String {
+    if let Some(fm) = front_matter_range(text) {
+        let block = &text[fm.clone()];
+        let mut lines = block.split_inclusive('\n').collect::<Vec<_>>();
+        if let Some((start, len)) = yaml_key_lines(&lines, key) {
+            lines.drain(start..start + len);
+        }
+        let mut out = lines.concat();
+        out.push_str(&text[fm.end..]);
+        return out;
+    }
+
+    let (meta, body) = split_metadata(text);
+    let mut out = meta
+        .split_inclusive('\n')
+        .filter(|line| old_style_key(line) != Some(key))
+        .collect::<String>();
+    out.push_str(body);
+    out
+}
+
+/// Finds the lines of a top level key in a front-matter
+///
+/// `lines` includes the delimiters. Returns the first line index and the
+/// number of lines, including indented continuation lines.
+fn yaml_key_lines(lines: &[&str], key: &str) -> Option<(usize, usize)> {
+    let inner = 1..lines.len().saturating_sub(1);
+    let start = inner.clone().find(|&i| {
+        lines[i]
+            .split_once(':')
+            .is_some_and(|(k, _)| !k.starts_with([' ', '\t']) && unquote(k.trim()) == key)
+    })?;
+    let len = 1 + lines[start + 1..inner.end]
+        .iter()
+        .take_while(|l| l.starts_with([' ', '\t', '-']) || l.trim().is_empty())
+        .count();
+    Some((start, len))
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim_matches(|c| c == '"' || c == '\'')
+}
+
+fn old_style_key(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix(">>")?;
+    let (key, _) = rest.split_once(':')?;
+    Some(key.trim())
+}
+
+/// Moves the old style `>>` metadata lines to a YAML front-matter
+///
+/// Key order is kept, and the `--` comments between the metadata lines at the
+/// start become YAML comments. Directives like `>> [mode]: ingredients` stay,
+/// they change how the lines after them are parsed. Returns `None` if there is
+/// nothing to move or the recipe already has a front-matter.
+pub fn to_front_matter(text: &str) -> Option<String> {
+    if front_matter_range(text).is_some() {
+        return None;
+    }
+    let mut yaml = String::new();
+    let mut body = String::with_capacity(text.len());
+    let mut leading = true;
+    // comments and blank lines in the leading metadata block, not yet known
+    // to be part of it
+    let mut pending = Vec::new();
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if let Some((key, value)) = trimmed
+            .strip_prefix(">>")
+            .and_then(|r| r.split_once(':'))
+            .filter(|(key, _)| !key.trim().starts_with('['))
+        {
+            for comment in pending
+                .drain(..)
+                .filter_map(|l: &str| l.trim().strip_prefix("--"))
+            {
+                writeln!(yaml, "#{comment}").unwrap();
+            }
+            let (value, comment) = match value.split_once(" --") {
+                Some((value, comment)) => (value, Some(comment)),
+                None => (value, None),
+            };
+            write!(yaml, "{}: {}", key.trim(), yaml_value(value)).unwrap();
+            if let Some(comment) = comment {
+                write!(yaml, " #{comment}").unwrap();
+            }
+            yaml.push('\n');
+            continue;
+        }
+        if leading && (trimmed.is_empty() || trimmed.starts_with("--")) {
+            pending.push(line);
+            continue;
+        }
+        if leading {
+            body.extend(pending.drain(..));
+            leading = false;
+        }
+        body.push_str(line);
+    }
+    body.extend(pending);
+    if yaml.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "---\n{yaml}---\n\n{}",
+        body.trim_start_matches('\n')
+    ))
+}
+
+/// A plain string value as YAML, quoted only if needed
+fn yaml_value(value: &str) -> String {
+    let value = value.trim();
+    let plain = match serde_yaml::from_str::<serde_yaml::Value>(value) {
+        Ok(serde_yaml::Value::String(s)) => s == value,
+        Ok(serde_yaml::Value::Number(_) | serde_yaml::Value::Bool(_)) => true,
+        _ => false,
+    };
+    if plain {
+        value.to_string()
+    } else {
+        serde_yaml::to_string(value)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_else(|_| format!("{value:?}"))
+    }
+}
+
+/// Moves the YAML front-matter to old style `>>` metadata lines
+///
+/// Key order and comments are kept. Lists of plain values are written comma
+/// separated. Returns `None` if there is no front-matter, or an error with the
+/// key that can't be written in a single line, like a nested map.
+pub fn to_old_style_metadata(text: &str) -> Result<Option<String>, String> {
+    let Some(fm) = front_matter_range(text) else {
+        return Ok(None);
+    };
+    let lines = text[fm.clone()].split_inclusive('\n').collect::<Vec<_>>();
+    let inner = &lines[1..lines.len() - 1];
+    let yaml = inner.concat();
+    let map = if yaml.trim().is_empty() {
+        serde_yaml::Mapping::new()
+    } else {
+        serde_yaml::from_str::<serde_yaml::Mapping>(&yaml)
+            .map_err(|e| format!("bad front-matter: {e}"))?
+    };
+
+    let mut out = String::new();
+    for line in inner {
+        let trimmed = line.trim();
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            writeln!(out, "--{comment}").unwrap();
+            continue;
+        }
+        if trimmed.is_empty() || line.starts_with([' ', '\t', '-']) {
+            continue;
+        }
+        let Some((key, _)) = line.split_once(':') else {
+            continue;
+        };
+        let key = unquote(key.trim());
+        let Some(value) = map.get(key) else {
+            continue;
+        };
+        writeln!(out, ">> {key}: {}", old_style_value(key, value)?).unwrap();
+    }
+    out.push('\n');
+    out.push_str(text[fm.end..].trim_start_matches('\n'));
+    Ok(Some(out))
+}
+
+fn old_style_value(key: &str, value: &serde_yaml::Value) -> Result<String, String> {
+    use serde_yaml::Value;
+
+    let scalar = |v: &Value| match v {
+        Value::String(s) if !s.contains('\n') => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    };
+    let single = match value {
+        Value::Sequence(items) => items
+            .iter()
+            .map(|v| scalar(v).filter(|s| !s.contains(',')))
+            .collect::<Option<Vec<_>>>()
+            .map(|items| items.join(", ")),
+        other => scalar(other),
+    };
+    single.ok_or_else(|| format!("'{key}' can't be written in a single `>>` line"))
+}
+
+/// Name part of a component in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentName {
+    /// Byte position of the sigil in the text
+    pub start: usize,
+    /// Byte range of the name (and alias, if any) in the text
+    pub span: Range<usize>,
+    /// If the name is followed by `{`
+    pub has_braces: bool,
+}
+
+/// Finds the names of all the components of a kind in the recipe body
+///
+/// `sigil` is `@` for ingredients, `#` for cookware and `~` for timers. The
+/// metadata, comments and escaped characters are skipped.
+pub fn find_components(text: &str, sigil: char) -> Vec<ComponentName> {
+    let (meta, body) = split_metadata(text);
+    let offset = meta.len();
+    let bytes = body.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        let rest = &body[i..];
+        if rest.starts_with("--") {
+            i += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with("[-") {
+            i += rest.find("-]").map(|p| p + 2).unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with(">>") && (i == 0 || bytes[i - 1] == b'\n') {
+            i += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        let c = rest.chars().next().unwrap();
+        if c == '\\' {
+            i += 1 + rest[1..].chars().next().map(char::len_utf8).unwrap_or(0);
+            continue;
+        }
+        if c != sigil {
+            i += c.len_utf8();
+            continue;
+        }
+
+        // modifiers
+        let mut j = i + 1;
+        let mut is_ref = false;
+        while let Some(m) = body[j..].chars().next().filter(|c| "@&?+-".contains(*c)) {
+            is_ref |= m == '&';
+            j += 1;
+        }
+        // intermediate reference target
+        if is_ref && body[j..].starts_with('(') {
+            match body[j..].find(')') {
+                Some(p) => j += p + 1,
+                None => {
+                    i = j;
+                    continue;
+                }
+            }
+        }
+
+        let name_start = j;
+        let rest = &body[j..];
+        let multi_word_end = rest
+            .find(['{', '\n', '@', '#', '~'])
+            .filter(|&p| rest[p..].starts_with('{'));
+        let (name_end, has_braces) = match multi_word_end {
+            Some(p) => (name_start + p, true),
+            None => {
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '|'))
+                    .unwrap_or(rest.len());
+                (name_start + len, false)
+            }
+        };
+        if name_end > name_start {
+            found.push(ComponentName {
+                start: offset + i,
+                span: offset + name_start..offset + name_end,
+                has_braces,
+            });
+        }
+        i = name_end.max(i + 1);
+    }
+    found
+}
+
+/// Renames the ingredients called `from` to `to`
+///
+/// The comparison is case insensitive. Aliases are kept, and renamed too if
+/// they match. Returns the new text and the number of renamed components.
+pub fn rename_ingredient(text: &str, from: &str, to: &str) -> (String, usize) {
+    let from = from.trim().to_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut count = 0;
+    for comp in find_components(text, '@') {
+        let full = &text[comp.span.clone()];
+        let (name, alias) = match full.split_once('|') {
+            Some((n, a)) => (n, Some(a)),
+            None => (full, None),
+        };
+        let name_matches = name.trim().to_lowercase() == from;
+        let alias_matches = alias.is_some_and(|a| a.trim().to_lowercase() == from);
+        if !name_matches && !alias_matches {
+            continue;
+        }
+        let new_name = if name_matches { to } else { name };
+        let mut new = new_name.to_string();
+        if let Some(alias) = alias {
+            new.push('|');
+            new.push_str(if alias_matches { to } else { alias });
+        }
+        out.push_str(&text[last..comp.span.start]);
+        out.push_str(&new);
+        if !comp.has_braces && new.contains(char::is_whitespace) {
+            out.push_str("{}");
+        }
+        last = comp.span.end;
+        count += 1;
+    }
+    out.push_str(&text[last..]);
+    (out, count)
+}
+
+impl ComponentName {
+    /// End of the whole component, after the quantity and note if any
+    pub fn end(&self, text: &str) -> usize {
+        let mut end = self.span.end;
+        if self.has_braces {
+            match text[end..].find('}') {
+                Some(p) => end += p + 1,
+                None => return text.len(),
+            }
+        }
+        if text[end..].starts_with('(') {
+            if let Some(p) = text[end..].find(')') {
+                end += p + 1;
+            }
+        }
+        end
+    }
+
+    /// Byte range of the quantity value inside the braces, without spaces
+    fn value_span(&self, text: &str) -> Option<Range<usize>> {
+        if !self.has_braces {
+            return None;
+        }
+        let open = self.span.end + 1;
+        let close = open + text[open..].find('}')?;
+        let inner = &text[open..close];
+        let value = inner.split_once('%').map_or(inner, |(v, _)| v);
+        let start = open + (value.len() - value.trim_start().len());
+        let end = open + value.trim_end().len();
+        (start < end).then_some(start..end)
+    }
+
+    fn matches(&self, text: &str, name: &str) -> bool {
+        let full = &text[self.span.clone()];
+        full.split('|').any(|n| n.trim().to_lowercase() == name)
+    }
+}
+
+/// Removes the ingredients called `name`, with their quantity and note
+///
+/// The comparison is case insensitive and also checks aliases. The text
+/// around is kept, so a step may need rewording. Returns the new text and the
+/// number of removed components.
+pub fn remove_ingredient(text: &str, name: &str) -> (String, usize) {
+    let name = name.trim().to_lowercase();
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut count = 0;
+    for comp in find_components(text, '@') {
+        if !comp.matches(text, &name) {
+            continue;
+        }
+        out.push_str(&text[last..comp.start]);
+        last = comp.end(text);
+        // don't leave a double space or a space before punctuation
+        let next = text[last..].chars().next();
+        if out.ends_with(' ') && next.is_none_or(|c| c == ' ' || ",.;:!?\n".contains(c)) {
+            out.pop();
+        }
+        count += 1;
+    }
+    out.push_str(&text[last..]);
+    (out, count)
+}
+
+/// Multiplies the ingredient quantities by `factor`
+///
+/// Fixed quantities (`{=1%tsp}`), text values and values for many servings
+/// (`{1|2}`) are not changed. Returns the new text and the number of scaled
+/// quantities.
+pub fn scale_quantities(text: &str, factor: f64) -> (String, usize) {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut count = 0;
+    for comp in find_components(text, '@') {
+        let Some(span) = comp.value_span(text) else {
+            continue;
+        };
+        let Some(scaled) = scale_value(&text[span.clone()], factor) else {
+            continue;
+        };
+        out.push_str(&text[last..span.start]);
+        out.push_str(&scaled);
+        last = span.end;
+        count += 1;
+    }
+    out.push_str(&text[last..]);
+    (out, count)
+}
+
+fn scale_value(value: &str, factor: f64) -> Option<String> {
+    if value.starts_with('=') || value.contains('|') {
+        return None;
+    }
+    if let Some((start, end)) = value.split_once('-') {
+        let start = parse_number(start)? * factor;
+        let end = parse_number(end)? * factor;
+        return Some(format!("{}-{}", fmt_number(start), fmt_number(end)));
+    }
+    Some(fmt_number(parse_number(value)? * factor))
+}
+
+/// Integers, decimals, fractions and mixed numbers, like `1 1/2`
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if let Some((whole, frac)) = s.split_once(' ') {
+        return Some(whole.trim().parse::<u32>().ok()? as f64 + parse_number(frac)?);
+    }
+    if let Some((num, den)) = s.split_once('/') {
+        let den = den.trim().parse::<u32>().ok().filter(|&d| d != 0)?;
+        return Some(num.trim().parse::<u32>().ok()? as f64 / den as f64);
+    }
+    s.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_styles() {
+        let old = "-- family recipe\n>> servings: 4\n>> source: https://example.com\n>> tags: quick, easy\n\nMix @flour{}.\n>> author: Ana\n";
+        let fm = to_front_matter(old).unwrap();
+        assert_eq!(
+            fm,
+            "---\n# family recipe\nservings: 4\nsource: https://example.com\ntags: quick, easy\nauthor: Ana\n---\n\nMix @flour{}.\n"
+        );
+        assert_eq!(to_front_matter(&fm), None);
+        assert_eq!(
+            to_front_matter(">> servings: 2\n>> [mode]: ingredients\n@flour\n").unwrap(),
+            "---\nservings: 2\n---\n\n>> [mode]: ingredients\n@flour\n"
+        );
+        assert_eq!(to_front_matter(">> [mode]: ingredients\n@flour\n"), None);
+
+        let back = to_old_style_metadata(&fm).unwrap().unwrap();
+        assert_eq!(
+            back,
+            "-- family recipe\n>> servings: 4\n>> source: https://example.com\n>> tags: quick, easy\n>> author: Ana\n\nMix @flour{}.\n"
+        );
+
+        let list = "---\ntags:\n  - a\n  - b\n---\nStep.\n";
+        assert_eq!(
+            to_old_style_metadata(list).unwrap().unwrap(),
+            ">> tags: a, b\n\nStep.\n"
+        );
+        assert!(to_old_style_metadata("---\nx:\n  y: 1\n---\n").is_err());
+        assert_eq!(yaml_value(" a: b"), "'a: b'");
+    }
+
+    #[test]
+    fn split() {
+        let text = "---\nservings: 2\n---\n\nMix @flour{}.\n";
+        assert_eq!(
+            split_metadata(text),
+            ("---\nservings: 2\n---\n", "\nMix @flour{}.\n")
+        );
+        let text = ">> servings: 2\n\nMix @flour{}.\n";
+        assert_eq!(
+            split_metadata(text),
+            (">> servings: 2\n\n", "Mix @flour{}.\n")
+        );
+        assert_eq!(split_metadata("Mix @flour."), ("", "Mix @flour."));
+    }
+
+    #[test]
+    fn set_and_remove_metadata() {
+        let text = "---\nservings: 2\ntags:\n  - a\n---\nMix.\n";
+        assert_eq!(
+            set_metadata(text, "tags", "[a, b]"),
+            "---\nservings: 2\ntags: [a, b]\n---\nMix.\n"
+        );
+        assert_eq!(
+            set_metadata(text, "author", "me"),
+            "---\nservings: 2\ntags:\n  - a\nauthor: me\n---\nMix.\n"
+        );
+        assert_eq!(
+            remove_metadata(text, "tags"),
+            "---\nservings: 2\n---\nMix.\n"
+        );
+        assert_eq!(
+            set_metadata(">> servings: 2\n\nMix.\n", "servings", "4"),
+            ">> servings: 4\n\nMix.\n"
+        );
+        assert_eq!(
+            set_metadata("Mix.\n", "servings", "4"),
+            "---\nservings: 4\n---\n\nMix.\n"
+        );
+    }
+
+    #[test]
+    fn rename() {
+        let text = "Add @coriander{1%tbsp}(chopped) and @&coriander{}. -- @coriander\n";
+        assert_eq!(
+            rename_ingredient(text, "Coriander", "cilantro"),
+            (
+                "Add @cilantro{1%tbsp}(chopped) and @&cilantro{}. -- @coriander\n".to_string(),
+                2
+            )
+        );
+        assert_eq!(
+            rename_ingredient("Add @coriander.", "coriander", "fresh cilantro").0,
+            "Add @fresh cilantro{}."
+        );
+        assert_eq!(
+            rename_ingredient("Add @green onion|coriander{}.", "coriander", "cilantro").0,
+            "Add @green onion|cilantro{}."
+        );
+    }
+
+    #[test]
+    fn remove() {
+        let text = "Mix @flour{200%g} and @cilantro{1%tbsp}(chopped).\nAdd @Cilantro.\n";
+        assert_eq!(
+            remove_ingredient(text, "cilantro"),
+            ("Mix @flour{200%g} and.\nAdd.\n".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn scale() {
+        let text =
+            "Mix @flour{200%g}, @salt{=1%tsp}, @eggs{1 1/2}, @milk{1-2%cups} and @water{some}.";
+        assert_eq!(
+            scale_quantities(text, 2.0),
+            (
+                "Mix @flour{400%g}, @salt{=1%tsp}, @eggs{3}, @milk{2-4%cups} and @water{some}."
+                    .to_string(),
+                3
+            )
+        );
+        assert_eq!(
+            scale_quantities("@oil{ 1/3 % cup}", 0.5).0,
+            "@oil{ 0.17 % cup}"
+        );
+    }
+}
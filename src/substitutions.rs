@@ -0,0 +1,176 @@
+//! Ingredient substitutions
+//!
+//! A list of alternatives for common ingredients is bundled with chef. More
+//! can be added in `load.substitutions` or a `substitutions.toml` file, with
+//! the same format as the bundled one, and they are listed first:
+//!
+//! ```toml
+//! [[buttermilk]]
+//! with = { milk = 0.94, "lemon juice" = 0.06 }
+//! note = "let it rest 5 minutes"
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{plugins::IngredientAmount, util::fmt_number, Context};
+
+const BUNDLED: &str = include_str!("data/substitutions.toml");
+
+#[derive(Debug, Default)]
+pub struct Substitutions {
+    /// By lowercase ingredient name
+    map: HashMap<String, Vec<Substitute>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Substitute {
+    /// Ingredients to use instead
+    pub with: BTreeMap<String, Part>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Part {
+    /// Amount for one unit of the original, in the same unit
+    Ratio(f64),
+    /// Amount for one unit of the original, in another unit
+    WithUnit { ratio: f64, unit: String },
+}
+
+impl Substitute {
+    /// Text for the substitute, with amounts if the original amount is known,
+    /// like `235 ml milk + 15 ml lemon juice`
+    pub fn describe(&self, value: Option<f64>, unit: Option<&str>) -> String {
+        self.with
+            .iter()
+            .map(|(name, part)| match value {
+                Some(value) => match part.amount(value, unit) {
+                    (v, Some(u)) => format!("{} {u} {name}", fmt_number(v)),
+                    (v, None) => format!("{} {name}", fmt_number(v)),
+                },
+                None => format!("{name} ({})", part.describe()),
+            })
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+impl Part {
+    /// Amount of this part for an amount of the original
+    pub fn amount(&self, value: f64, unit: Option<&str>) -> (f64, Option<String>) {
+        match self {
+            Part::Ratio(r) => (value * r, unit.map(str::to_string)),
+            Part::WithUnit { ratio, unit } => (value * ratio, Some(unit.clone())),
+        }
+    }
+
+    /// Ratio text, like `x0.5` or `1 tbsp each`
+    pub fn describe(&self) -> String {
+        match self {
+            Part::Ratio(r) => format!("x{}", fmt_number(*r)),
+            Part::WithUnit { ratio, unit } => format!("{} {unit} each", fmt_number(*ratio)),
+        }
+    }
+}
+
+impl Substitutions {
+    pub fn load(ctx: &Context) -> Result<Self> {
+        let mut subs = Self::default();
+        if let Some(path) = ctx.config.substitutions(&ctx.base_path) {
+            tracing::debug!("Loading substitutions {path}");
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read substitutions file: {path}"))?;
+            subs.extend(&text)
+                .with_context(|| format!("Bad substitutions file: {path}"))?;
+        }
+        subs.extend(BUNDLED).expect("bundled substitutions");
+        Ok(subs)
+    }
+
+    fn extend(&mut self, text: &str) -> Result<()> {
+        let file: HashMap<String, Vec<Substitute>> = toml::from_str(text)?;
+        for (name, list) in file {
+            self.map
+                .entry(name.to_lowercase())
+                .or_default()
+                .extend(list);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, ingredient: &str) -> &[Substitute] {
+        self.map
+            .get(&ingredient.trim().to_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+}
+
+/// Writes the known substitutes of the recipe ingredients, after a rendered
+/// recipe
+pub fn write_annotations(
+    mut w: impl std::io::Write,
+    subs: &Substitutions,
+    amounts: &[IngredientAmount],
+    markdown: bool,
+) -> Result<()> {
+    use yansi::Paint;
+
+    let found = amounts
+        .iter()
+        .filter(|a| !subs.get(&a.name).is_empty())
+        .collect::<Vec<_>>();
+    if found.is_empty() {
+        return Ok(());
+    }
+    if markdown {
+        writeln!(w, "\n## Substitutes\n")?;
+    } else {
+        writeln!(w, "\n{}:", "Substitutes".bold().underline())?;
+    }
+    for amount in found {
+        let original = match (amount.quantity, &amount.unit) {
+            (Some(v), Some(u)) => format!("{} {u} {}", fmt_number(v), amount.name),
+            (Some(v), None) => format!("{} {}", fmt_number(v), amount.name),
+            (None, _) => amount.name.clone(),
+        };
+        for sub in subs.get(&amount.name) {
+            let text = sub.describe(amount.quantity, amount.unit.as_deref());
+            let note = sub
+                .note
+                .as_deref()
+                .map(|n| format!(" ({n})"))
+                .unwrap_or_default();
+            if markdown {
+                writeln!(w, "- {original}: {text}{note}")?;
+            } else {
+                writeln!(w, "  {original}: {text}{}", note.dim())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_data_parses() {
+        let mut subs = Substitutions::default();
+        subs.extend(BUNDLED).unwrap();
+        let egg = subs.get("Egg");
+        assert!(!egg.is_empty());
+        let flax = &egg[0].with["ground flaxseed"];
+        assert_eq!(flax.amount(2.0, None), (2.0, Some("tbsp".to_string())));
+    }
+}
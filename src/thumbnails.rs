@@ -0,0 +1,99 @@
+//! Smaller copies of the recipe images
+//!
+//! The web UI asks for images in a few widths with `srcset`. They are made on
+//! the first request, encoded as WebP and kept in the user cache dir. The key
+//! is the image path, size and modification time, so a changed photo gets new
+//! thumbnails.
+
+use anyhow::{Context as _, Result};
+#[cfg(feature = "serve")]
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+
+use crate::config::global_cache_dir;
+
+const THUMBNAILS_DIR: &str = "thumbnails";
+
+/// Widths of the thumbnails, in pixels
+#[cfg(feature = "serve")]
+pub const WIDTHS: &[u32] = &[320, 640, 1280];
+
+/// Image formats thumbnails can be made from, the rest are served as is
+#[cfg(feature = "serve")]
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["jpeg", "jpg", "png", "webp"];
+
+fn thumbnails_dir() -> Result<Utf8PathBuf> {
+    Ok(global_cache_dir()?.join(THUMBNAILS_DIR))
+}
+
+/// Path of a thumbnail of `image` in the cache
+#[cfg(feature = "serve")]
+fn cache_path(image: &Utf8Path, width: u32) -> Result<Utf8PathBuf> {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    let metadata = image
+        .metadata()
+        .with_context(|| format!("Failed to read {image}"))?;
+    let mut hasher = DefaultHasher::new();
+    image.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    metadata.modified().ok().hash(&mut hasher);
+    width.hash(&mut hasher);
+    Ok(thumbnails_dir()?.join(format!("{:016x}.webp", hasher.finish())))
+}
+
+/// Gets the thumbnail of an image, making it if it's not in the cache
+///
+/// Images narrower than `width` are not scaled up, the thumbnail has the
+/// original size.
+#[cfg(feature = "serve")]
+pub fn get_or_make(image: &Utf8Path, width: u32) -> Result<Utf8PathBuf> {
+    let path = cache_path(image, width)?;
+    if path.is_file() {
+        return Ok(path);
+    }
+    tracing::debug!("Making {width}px thumbnail of {image}");
+    let img = image::open(image).with_context(|| format!("Failed to decode {image}"))?;
+    let img = if img.width() > width {
+        img.resize(width, u32::MAX, image::imageops::FilterType::Triangle)
+    } else {
+        img
+    };
+    let dir = thumbnails_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    // write to a file of this request and rename it, so concurrent requests
+    // never write to the same file or read half of it
+    let mut tmp = tempfile::Builder::new()
+        .suffix(".tmp")
+        .tempfile_in(&dir)
+        .context("Failed to create a temp file")?;
+    let mut webp = std::io::Cursor::new(Vec::new());
+    img.to_rgba8()
+        .write_to(&mut webp, image::ImageFormat::WebP)
+        .with_context(|| format!("Failed to encode thumbnail of {image}"))?;
+    std::io::Write::write_all(&mut tmp, webp.get_ref())?;
+    tmp.persist(&path)
+        .with_context(|| format!("Failed to write {path}"))?;
+    Ok(path)
+}
+
+/// Removes all the thumbnails
+///
+/// Returns the number of files removed.
+pub fn clear() -> Result<usize> {
+    let dir = thumbnails_dir()?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).context("Failed to read thumbnails dir"),
+    };
+    let mut count = 0;
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            std::fs::remove_file(entry.path()).context("Failed to remove thumbnail")?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
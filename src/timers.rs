@@ -0,0 +1,314 @@
+//! Timers in scaled recipes
+//!
+//! The parser never scales timers, but some times do change with the amount
+//! of food: a bigger pot takes longer to simmer. A recipe can say how each of
+//! its timers scales, by name, in the `timer scaling` metadata:
+//!
+//! ```yaml
+//! timer scaling:
+//!   simmer: sqrt
+//!   bake: check
+//!   "*": fixed
+//! ```
+//!
+//! `linear`, `sqrt` and `fixed` are like for the ingredients. `check` keeps
+//! the time and flags it to be checked. `*` is for the timers without a rule.
+//!
+//! All times are rounded to friendly values, scaled or not: 1 h 28 min shows
+//! as ~1 h 30 min, and the exact value is kept in a [`TimerNote`].
+
+use std::collections::HashMap;
+
+use cooklang::{
+    quantity::{Number, Quantity, Value},
+    Converter, Metadata, ScaledRecipe,
+};
+use serde::Serialize;
+
+use crate::{schedule::format_duration, util::convert_value};
+
+/// Metadata with the rules to scale the timers
+pub const TIMER_SCALING_KEY: &str = "timer scaling";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerRule {
+    /// Scale proportionally to the recipe
+    Linear,
+    /// Scale with the square root of the factor
+    Sqrt,
+    /// Never scale, the default
+    Fixed,
+    /// Never scale, but flag the time to be checked
+    Check,
+}
+
+impl TimerRule {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "linear" => Some(Self::Linear),
+            "sqrt" => Some(Self::Sqrt),
+            "fixed" => Some(Self::Fixed),
+            "check" | "flag" => Some(Self::Check),
+            _ => None,
+        }
+    }
+}
+
+/// Rules of a recipe by timer name, lowercase
+pub fn timer_rules(metadata: &Metadata) -> HashMap<String, TimerRule> {
+    let Some(map) = metadata
+        .map
+        .get(TIMER_SCALING_KEY)
+        .and_then(|v| v.as_mapping())
+    else {
+        return HashMap::new();
+    };
+    map.iter()
+        .filter_map(|(k, v)| {
+            let rule = v.as_str().and_then(TimerRule::parse);
+            if rule.is_none() {
+                tracing::warn!("Bad timer scaling rule: {v:?}");
+            }
+            Some((k.as_str()?.trim().to_lowercase(), rule?))
+        })
+        .collect()
+}
+
+/// A timer changed or flagged by scaling
+#[derive(Debug, Clone, Serialize)]
+pub struct TimerNote {
+    pub index: usize,
+    pub name: Option<String>,
+    /// Seconds before rounding
+    pub exact: f64,
+    /// Only needs to be checked, the time is unchanged
+    pub check: bool,
+}
+
+impl TimerNote {
+    /// The exact time, like `1h 28min 12s`
+    pub fn exact_duration(&self) -> String {
+        format_duration(self.exact.round() as u64)
+    }
+}
+
+/// Rounds a time in seconds to a value easy to read and set
+pub fn friendly_seconds(secs: f64) -> f64 {
+    let step = match secs {
+        s if s < 60.0 => 5.0,
+        s if s < 5.0 * 60.0 => 15.0,
+        s if s < 20.0 * 60.0 => 60.0,
+        s if s < 3.0 * 3600.0 => 5.0 * 60.0,
+        _ => 15.0 * 60.0,
+    };
+    ((secs / step).round() * step).max(step)
+}
+
+/// Applies the timer rules of a recipe scaled by `factor`
+///
+/// Every timer is rounded with [`friendly_seconds`], even without rules or
+/// scaling. Returns a note for every timer changed or flagged.
+pub fn adjust_timers(
+    recipe: &mut ScaledRecipe,
+    factor: f64,
+    converter: &Converter,
+) -> Vec<TimerNote> {
+    let mut notes = Vec::new();
+    let rules = timer_rules(&recipe.metadata);
+
+    for (index, timer) in recipe.timers.iter_mut().enumerate() {
+        let rule = timer
+            .name
+            .as_deref()
+            .and_then(|n| rules.get(&n.trim().to_lowercase()))
+            .or_else(|| rules.get("*"))
+            .copied()
+            .unwrap_or(TimerRule::Fixed);
+        let Some(quantity) = &timer.quantity else {
+            continue;
+        };
+        let Some(unit) = quantity.unit() else {
+            continue;
+        };
+        let to_secs = |n: &Number| convert_value(n.value(), unit, "s", converter);
+        let (start, end) = match quantity.value() {
+            Value::Number(n) => (n, None),
+            Value::Range { start, end } => (start, Some(end)),
+            Value::Text(_) => continue,
+        };
+        let Some(secs) = to_secs(start) else {
+            continue;
+        };
+        let end = match end.map(to_secs) {
+            None => None,
+            Some(Some(end)) => Some(end),
+            Some(None) => continue,
+        };
+        let scale = match rule {
+            TimerRule::Linear => factor,
+            TimerRule::Sqrt => factor.sqrt(),
+            TimerRule::Fixed | TimerRule::Check => 1.0,
+        };
+        let check = rule == TimerRule::Check && factor != 1.0;
+        let exact = (secs * scale, end.map(|e| e * scale));
+        let rounded = (friendly_seconds(exact.0), exact.1.map(friendly_seconds));
+        if rounded != exact || scale != 1.0 {
+            let value = match rounded {
+                (start, None) => Value::Number(Number::Regular(start)),
+                (start, Some(end)) => Value::Range {
+                    start: Number::Regular(start),
+                    end: Number::Regular(end),
+                },
+            };
+            let new = Quantity::new(value, Some("s".to_string()));
+            timer.quantity = Some(in_friendly_unit(new, unit, converter));
+        } else if !check {
+            continue;
+        }
+        notes.push(TimerNote {
+            index,
+            name: timer.name.clone(),
+            exact: exact.0,
+            check,
+        });
+    }
+    notes
+}
+
+/// Back to the original unit if it gives round numbers, else to minutes or
+/// seconds
+fn in_friendly_unit(secs: Quantity<Value>, unit: &str, converter: &Converter) -> Quantity<Value> {
+    let round = |q: &Quantity<Value>| match q.value() {
+        Value::Number(n) => is_round(n.value()),
+        Value::Range { start, end } => is_round(start.value()) && is_round(end.value()),
+        Value::Text(_) => false,
+    };
+    let min_secs = match secs.value() {
+        Value::Number(n) => n.value(),
+        Value::Range { start, .. } => start.value(),
+        Value::Text(_) => 0.0,
+    };
+    let fallback = if min_secs >= 60.0 { "min" } else { "s" };
+    for to in [unit, fallback] {
+        let mut q = secs.clone();
+        if q.convert(to, converter).is_ok() && round(&q) {
+            return q;
+        }
+    }
+    secs
+}
+
+/// Whole or in quarters, like 1.5 h
+fn is_round(n: f64) -> bool {
+    (n * 4.0 - (n * 4.0).round()).abs() < 1e-6
+}
+
+/// Writes the scaled and flagged timers with their exact values
+pub fn write_timer_notes(mut w: impl std::io::Write, notes: &[TimerNote]) -> std::io::Result<()> {
+    use yansi::Paint;
+
+    if notes.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "\n{}:", "Timers".bold().underline())?;
+    for note in notes {
+        let name = note.name.as_deref().unwrap_or("timer");
+        if note.check {
+            writeln!(
+                w,
+                "  {} {} {}",
+                name.bold(),
+                note.exact_duration(),
+                "check it for the new amount".yellow()
+            )?;
+        } else {
+            let exact = format!("rounded from {}", note.exact_duration());
+            writeln!(w, "  {} {}", name.bold(), exact.dim())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn friendly() {
+        assert_eq!(friendly_seconds(88.0 * 60.0), 90.0 * 60.0);
+        assert_eq!(friendly_seconds(7.0 * 60.0 + 40.0), 8.0 * 60.0);
+        assert_eq!(friendly_seconds(100.0), 105.0);
+        assert_eq!(friendly_seconds(12.0), 10.0);
+        assert_eq!(friendly_seconds(1.0), 5.0);
+        assert_eq!(friendly_seconds(3.0 * 3600.0 + 500.0), 3.0 * 3600.0 + 900.0);
+        assert!(is_round(1.5) && is_round(2.0) && !is_round(1.4167));
+    }
+
+    type Timer = (f64, Option<f64>, String);
+
+    fn adjusted(src: &str, factor: f64) -> (Vec<Timer>, Vec<TimerNote>) {
+        let parser = cooklang::CooklangParser::default();
+        let mut recipe = parser.parse(src).into_output().unwrap().default_scale();
+        let notes = adjust_timers(&mut recipe, factor, parser.converter());
+        let timers = recipe
+            .timers
+            .iter()
+            .map(|t| {
+                let q = t.quantity.as_ref().unwrap();
+                let unit = q.unit().unwrap().to_string();
+                match q.value() {
+                    Value::Number(n) => (n.value(), None, unit),
+                    Value::Range { start, end } => (start.value(), Some(end.value()), unit),
+                    Value::Text(_) => panic!("text timer"),
+                }
+            })
+            .collect();
+        (timers, notes)
+    }
+
+    #[test]
+    fn unscaled_timers_are_rounded() {
+        let (timers, notes) = adjusted("Bake for ~{88%min}. Rest ~{20%min}.", 1.0);
+        assert_eq!(
+            timers,
+            [(90.0, None, "min".into()), (20.0, None, "min".into())]
+        );
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].index, 0);
+        assert_eq!(notes[0].exact, 88.0 * 60.0);
+        assert!(!notes[0].check);
+    }
+
+    #[test]
+    fn rules() {
+        let src = "---\ntimer scaling:\n  simmer: sqrt\n  bake: check\n  \"*\": linear\n---\n\n\
+            Simmer ~simmer{20%min}, bake ~bake{40%min} and fry ~fry{5-6%min}. Rest ~rest{1%h}.";
+        let (timers, notes) = adjusted(src, 4.0);
+        // sqrt(4) = 2, check keeps the time and the rest is linear
+        assert_eq!(
+            timers,
+            [
+                (40.0, None, "min".into()),
+                (40.0, None, "min".into()),
+                (20.0, Some(25.0), "min".into()),
+                (4.0, None, "h".into()),
+            ]
+        );
+        assert_eq!(notes.len(), 4);
+        assert!(notes[1].check && notes[1].exact == 40.0 * 60.0);
+        assert_eq!(notes[2].exact, 20.0 * 60.0);
+
+        let (timers, notes) = adjusted(src, 1.0);
+        assert_eq!(timers[2], (5.0, Some(6.0), "min".into()));
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn scaled_to_friendly_unit() {
+        let src = "---\ntimer scaling:\n  \"*\": linear\n---\n\nStir ~{45%s}.";
+        // 67.5 s is rounded to 1 min 15 s, in seconds
+        let (timers, notes) = adjusted(src, 1.5);
+        assert_eq!(timers, [(75.0, None, "s".into())]);
+        assert_eq!(notes[0].exact, 67.5);
+    }
+}
@@ -0,0 +1,93 @@
+//! Local usage statistics
+//!
+//! With `usage_stats = true` in the global config, every command run is
+//! appended to `usage.jsonl` in the data directory: the day, the command, the
+//! recipes it used, how long it took and if it failed. Nothing leaves the
+//! machine, `chef stats --self` reads the file and removing it resets them.
+
+use std::{io::Write, sync::Mutex, time::Duration};
+
+use anyhow::{Context as _, Result};
+use camino::Utf8PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::global_data_dir, pantry::Date};
+
+pub const USAGE_FILE: &str = "usage.jsonl";
+
+/// A command run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub date: Date,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipes: Vec<String>,
+    /// Milliseconds the command took
+    pub ms: u64,
+    pub ok: bool,
+}
+
+/// What the running command used, filled in by the commands
+#[derive(Debug, Default)]
+pub struct Usage {
+    recipes: Mutex<Vec<String>>,
+}
+
+impl Usage {
+    /// A recipe used by the command
+    pub fn recipe(&self, name: &str) {
+        let mut recipes = self.recipes.lock().unwrap();
+        if !recipes.iter().any(|r| r == name) {
+            recipes.push(name.to_string());
+        }
+    }
+
+    /// Appends the record of the command to the usage file
+    pub fn record(&self, command: &str, elapsed: Duration, ok: bool) -> Result<()> {
+        let record = Record {
+            date: Date::today(),
+            command: command.to_string(),
+            recipes: std::mem::take(&mut *self.recipes.lock().unwrap()),
+            ms: elapsed.as_millis() as u64,
+            ok,
+        };
+        let path = usage_file()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Cannot open usage file: {path}"))?;
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+pub fn usage_file() -> Result<Utf8PathBuf> {
+    Ok(global_data_dir()?.join(USAGE_FILE))
+}
+
+/// All the records, skipping the lines that can't be read
+pub fn load() -> Result<Vec<Record>> {
+    let path = usage_file()?;
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Cannot read usage file: {path}"))?;
+    Ok(text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| match serde_json::from_str(l) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                tracing::warn!("Bad usage record: {e}");
+                None
+            }
+        })
+        .collect())
+}
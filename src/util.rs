@@ -6,11 +6,19 @@ use camino::Utf8Path;
 use cooklang::{
     analysis::{CheckOptions, CheckResult},
     metadata::CooklangValueExt,
-    Metadata, ScalableRecipe,
+    Converter, Metadata, ScalableRecipe,
 };
 use cooklang_fs::{RecipeContent, RecipeEntry};
 
-use crate::Context;
+use crate::{
+    collection_file::{inherit, inherit_metadata},
+    Context,
+};
+
+/// Lowercase hex of some bytes
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
 
 /// Utility to create lazy regex
 /// from <https://docs.rs/once_cell/latest/once_cell/#lazily-compiled-regex>
@@ -40,6 +48,144 @@ where
     Ok(())
 }
 
+/// Same as [`write_to_output`], but when `to_clipboard` is set the output is
+/// copied to the clipboard instead, without colors
+pub fn write_to_output_or_clipboard<F>(
+    output: Option<&Utf8Path>,
+    to_clipboard: bool,
+    f: F,
+) -> Result<()>
+where
+    F: FnOnce(Box<dyn std::io::Write>) -> Result<()>,
+{
+    if !to_clipboard {
+        return write_to_output(output, f);
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buf = SharedBuf::default();
+    f(Box::new(buf.clone()))?;
+    let text = String::from_utf8(buf.0.take()).context("Output is not valid UTF-8")?;
+    set_clipboard(anstream::adapter::strip_str(&text).to_string())?;
+    eprintln!("Copied to the clipboard");
+    Ok(())
+}
+
+pub fn set_clipboard(text: String) -> Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut c| c.set_text(text))
+        .context("Failed to write to the clipboard")
+}
+
+/// Converts a number from one unit to another, `None` if they are not
+/// compatible
+pub fn convert_value(value: f64, from: &str, to: &str, converter: &Converter) -> Option<f64> {
+    use cooklang::{
+        convert::{ConvertTo, ConvertUnit},
+        quantity::Number,
+        Quantity, Value,
+    };
+
+    if from == to {
+        return Some(value);
+    }
+    let mut q = Quantity::new(
+        Value::Number(Number::Regular(value)),
+        Some(from.to_string()),
+    );
+    q.convert(ConvertTo::Unit(ConvertUnit::Key(to)), converter)
+        .ok()?;
+    match q.value() {
+        Value::Number(n) => Some(n.value()),
+        _ => None,
+    }
+}
+
+/// Runs a command line with the platform shell
+pub fn shell_command(command: &str) -> std::process::Command {
+    if cfg!(windows) {
+        let mut c = std::process::Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    } else {
+        let mut c = std::process::Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    }
+}
+
+/// Writes `input` to the stdin of a child and waits for its output
+///
+/// The input is written from a thread, so a child that fills its stdout
+/// before reading all of it doesn't deadlock. With a `timeout`, the child is
+/// killed if it takes longer.
+pub fn communicate(
+    mut child: std::process::Child,
+    input: Vec<u8>,
+    timeout: Option<std::time::Duration>,
+) -> Result<std::process::Output> {
+    use std::io::{Read, Write};
+
+    fn read_all(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    }
+
+    if let Some(mut stdin) = child.stdin.take() {
+        std::thread::spawn(move || {
+            // the child may not read all of it, its status tells if it failed
+            let _ = stdin.write_all(&input);
+        });
+    }
+    let stdout = read_all(child.stdout.take());
+    let stderr = read_all(child.stderr.take());
+
+    let status = match timeout {
+        None => child.wait()?,
+        Some(timeout) => {
+            let start = std::time::Instant::now();
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if start.elapsed() > timeout {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    bail!("timed out after {}s", timeout.as_secs());
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        }
+    };
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+pub fn get_clipboard() -> Result<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut c| c.get_text())
+        .context("Failed to read the clipboard")
+}
+
 pub fn map_recipe(mut r: ScalableRecipe) -> ScalableRecipe {
     if let Some(emoji_str) = r
         .metadata
@@ -61,6 +207,12 @@ pub enum Input {
         text: String,
         name: Option<String>,
     },
+    /// A recipe file with its variables replaced, see [`crate::variables`]
+    Template {
+        entry: cooklang_fs::RecipeEntry,
+        name: Option<String>,
+        text: String,
+    },
 }
 
 impl Input {
@@ -70,15 +222,39 @@ impl Input {
     }
 
     pub fn parse_result(&self, ctx: &Context) -> Result<cooklang::RecipeResult> {
+        Ok(self.parse_text(ctx.parser()?, ctx, self.text()?.as_ref()))
+    }
+
+    /// Parses what is valid of the recipe, with its errors marked
+    ///
+    /// See [`crate::partial`].
+    pub fn parse_partial(&self, ctx: &Context) -> Result<Option<cooklang::ScalableRecipe>> {
         let parser = ctx.parser()?;
+        let recipe = crate::partial::parse_partial(self.text()?.as_ref(), |text| {
+            self.parse_text(parser, ctx, text)
+        });
+        Ok(recipe)
+    }
+
+    fn parse_text(
+        &self,
+        parser: &cooklang::CooklangParser,
+        ctx: &Context,
+        text: &str,
+    ) -> cooklang::RecipeResult {
         let options = match self {
-            Input::File { entry, .. } => ctx.parse_options(Some(entry.path())),
+            Input::File { entry, .. } | Input::Template { entry, .. } => {
+                ctx.parse_options(Some(entry.path()))
+            }
             Input::Stdin { .. } => ctx.parse_options(None),
         };
-        let r = parser
-            .parse_with_options(self.text()?.as_ref(), options)
-            .map(map_recipe);
-        Ok(r)
+        parser
+            .parse_with_options(text, options)
+            .map(|r| match self.path() {
+                Some(path) => inherit(r, path, &ctx.base_path),
+                None => r,
+            })
+            .map(map_recipe)
     }
 
     pub fn name(&self) -> Result<&str> {
@@ -87,6 +263,7 @@ impl Input {
                 entry,
                 override_name,
             } => override_name.as_deref().unwrap_or(entry.name()),
+            Input::Template { entry, name, .. } => name.as_deref().unwrap_or(entry.name()),
             Input::Stdin { name, .. } => name
                 .as_deref()
                 .ok_or(anyhow::anyhow!("No name given for recipe"))?,
@@ -96,24 +273,99 @@ impl Input {
 
     pub fn file_name(&self) -> &str {
         match &self {
-            Input::File { entry: content, .. } => content.file_name(),
+            Input::File { entry: content, .. } | Input::Template { entry: content, .. } => {
+                content.file_name()
+            }
             Input::Stdin { name, .. } => name.as_deref().unwrap_or("STDIN"),
         }
     }
 
-    pub fn text(&self) -> Result<Cow<str>> {
+    pub fn text(&self) -> Result<Cow<'_, str>> {
         Ok(match self {
             Input::File { entry, .. } => entry.read()?.into_text().into(),
-            Input::Stdin { text, .. } => text.as_str().into(),
+            Input::Stdin { text, .. } | Input::Template { text, .. } => text.as_str().into(),
         })
     }
 
     pub fn path(&self) -> Option<&Utf8Path> {
         match self {
-            Input::File { entry: content, .. } => Some(content.path()),
+            Input::File { entry: content, .. } | Input::Template { entry: content, .. } => {
+                Some(content.path())
+            }
             Input::Stdin { .. } => None,
         }
     }
+
+    /// Replaces the variables of the recipe and keeps only the chosen variants
+    ///
+    /// A variant is one of the variants file, which gives values to the
+    /// variables (see [`crate::variables`]), or of the tagged sections and
+    /// steps (see [`crate::variant_sections`]). The variants are added to the
+    /// recipe name. Recipes without variables are left as they are if no
+    /// variant is chosen.
+    pub fn with_variables(self, variants: &[String], set: &[(String, String)]) -> Result<Self> {
+        let mut text = self.text()?.into_owned();
+        let has_variables = !crate::variables::variables(&text).is_empty();
+        if variants.is_empty() && !has_variables {
+            return Ok(self);
+        }
+
+        let mut file_variants = match self.path() {
+            Some(path) if !variants.is_empty() => crate::variables::load_variants(path)?,
+            _ => Default::default(),
+        };
+        let (file, sections): (Vec<&str>, Vec<&str>) = variants
+            .iter()
+            .map(String::as_str)
+            .partition(|v| file_variants.contains_key(*v));
+        if let [a, b, ..] = file.as_slice() {
+            bail!("Variants '{a}' and '{b}' both give values to the variables, choose one");
+        }
+        let groups = crate::variant_sections::groups(&text);
+        if let Some(unknown) = sections
+            .iter()
+            .find(|v| !groups.values().flatten().any(|g| g == *v))
+        {
+            let known = file_variants
+                .keys()
+                .chain(groups.values().flatten())
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            if known.is_empty() {
+                bail!("Recipe has no variants");
+            }
+            bail!(
+                "Unknown variant '{unknown}', expected one of {}",
+                known.join(", ")
+            );
+        }
+
+        if has_variables || !file.is_empty() {
+            let mut values = file
+                .first()
+                .and_then(|v| file_variants.remove(*v))
+                .unwrap_or_default();
+            values.extend(set.iter().cloned());
+            text = crate::variables::substitute(&text, &values)?;
+        }
+        if !sections.is_empty() {
+            text = crate::variant_sections::select(&text, &sections)?;
+        }
+
+        let variant = (!variants.is_empty()).then(|| variants.join(", "));
+        let input = match self {
+            Input::File {
+                entry,
+                override_name: name,
+            }
+            | Input::Template { entry, name, .. } => {
+                let name = name.or_else(|| variant.map(|v| format!("{} ({v})", entry.name())));
+                Input::Template { entry, name, text }
+            }
+            Input::Stdin { name, .. } => Input::Stdin { text, name },
+        };
+        Ok(input)
+    }
 }
 
 pub fn unwrap_recipe(
@@ -128,6 +380,14 @@ pub fn unwrap_recipe(
             report.remove_warnings();
         }
         report.eprint(file_name, text, ctx.color.color_stderr)?;
+        let disabled = cooklang_self_test::disabled_extensions(ctx.parser()?, text);
+        if !disabled.is_empty() {
+            bail!(
+                "Error parsing recipe, it uses extensions disabled in this collection: {}. \
+                 Enable them in `extensions` of the config",
+                disabled.join(", ")
+            );
+        }
         bail!("Error parsing recipe");
     } else {
         let (recipe, warnings) = r.into_result().unwrap();
@@ -138,6 +398,141 @@ pub fn unwrap_recipe(
     }
 }
 
+/// All the recipes of the collection, found with the configured rules
+pub fn all_recipes(ctx: &Context) -> Result<impl Iterator<Item = RecipeEntry> + '_> {
+    let entries = cooklang_fs::all_recipes_with_rules(
+        &ctx.base_path,
+        ctx.config.max_depth,
+        &ctx.config.discovery,
+    )?;
+    Ok(entries)
+}
+
+/// Runs the editor, returns if the file was modified
+pub fn run_editor(ctx: &Context, path: &Utf8Path) -> Result<bool> {
+    let editor = ctx
+        .chef_config
+        .editor()
+        .context("Could not determine editor")?;
+    let (cmd, args) = editor.split_first().expect("empty editor cmd");
+
+    let modified = || path.metadata().and_then(|m| m.modified()).ok();
+    let before = modified();
+
+    let ok = std::process::Command::new(cmd)
+        .args(args)
+        .arg(path)
+        .status()?
+        .success();
+
+    if !ok {
+        tracing::warn!("Editor didn't exit successfully")
+    }
+
+    Ok(modified() != before)
+}
+
+/// Resolves a recipe query, falling back to a fuzzy search by name
+///
+/// If more than one recipe matches and the terminal is interactive, a picker
+/// is shown.
+pub fn resolve_recipe(ctx: &Context, query: &str) -> Result<RecipeEntry> {
+    let entry = find_recipe(ctx, query)?;
+    ctx.usage
+        .recipe(&crate::cook_log::recipe_key(entry.path(), &ctx.base_path));
+    Ok(entry)
+}
+
+fn find_recipe(ctx: &Context, query: &str) -> Result<RecipeEntry> {
+    let not_found = match ctx.recipe_index.resolve(query, None) {
+        Ok(entry) => return Ok(entry),
+        Err(e @ cooklang_fs::Error::NotFound(_)) => e,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut matches = all_recipes(ctx)?
+        .filter_map(|entry| {
+            let name = entry
+                .relative_name()
+                .strip_prefix(ctx.base_path.as_str())
+                .unwrap_or(entry.relative_name())
+                .trim_start_matches(['/', '\\'])
+                .to_string();
+            fuzzy_score(query, &name).map(|score| (score, name, entry))
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    match matches.len() {
+        0 => Err(not_found.into()),
+        1 => Ok(matches.pop().unwrap().2),
+        _ => {
+            use std::io::IsTerminal;
+
+            let names = matches.iter().map(|m| m.1.clone()).collect::<Vec<_>>();
+            if !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+                let shown = names.iter().take(10).cloned().collect::<Vec<_>>();
+                bail!(
+                    "'{query}' matches {} recipes: {}{}",
+                    names.len(),
+                    shown.join(", "),
+                    if names.len() > shown.len() {
+                        ", ..."
+                    } else {
+                        ""
+                    }
+                );
+            }
+            let selected = inquire::Select::new("Select a recipe", names)
+                .raw_prompt()
+                .context("No recipe selected")?;
+            Ok(matches.swap_remove(selected.index).2)
+        }
+    }
+}
+
+/// Fuzzy match score of a query in a recipe name, higher is better
+///
+/// `None` if the query chars don't appear in order in the name.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i64> {
+    let query = query.trim().to_lowercase();
+    let name = name.to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+
+    if let Some(pos) = name.find(&query) {
+        let boundary = !matches!(name[..pos].chars().next_back(), Some(c) if c.is_alphanumeric());
+        let extra = (name.len() - query.len()) as i64;
+        return Some(1000 + if boundary { 100 } else { 0 } - extra);
+    }
+
+    let mut score = 0;
+    let mut q = query.chars().peekable();
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+    for c in name.chars() {
+        let Some(&qc) = q.peek() else {
+            break;
+        };
+        if c == qc {
+            score += 1;
+            if prev_matched {
+                score += 5;
+            }
+            if !matches!(prev, Some(p) if p.is_alphanumeric()) {
+                score += 3;
+            }
+            q.next();
+            prev_matched = true;
+        } else {
+            prev_matched = false;
+        }
+        prev = Some(c);
+    }
+    q.peek().is_none().then_some(score)
+}
+
 pub fn meta_name(meta: &cooklang::Metadata) -> Option<&str> {
     ["name", "title"]
         .iter()
@@ -145,6 +540,37 @@ pub fn meta_name(meta: &cooklang::Metadata) -> Option<&str> {
         .and_then(|n| n.as_str())
 }
 
+/// A number with up to 2 decimals and no trailing zeros, like `1.5`
+pub(crate) fn fmt_number(n: f64) -> String {
+    let s = format!("{n:.2}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Escapes text to put it in HTML or XML
+#[cfg(feature = "serve")]
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Percent-encodes a path to put it in a URL
+#[cfg(feature = "serve")]
+pub fn encode_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(b as char)
+            }
+            _ => out += &format!("%{b:02X}"),
+        }
+    }
+    out
+}
+
 pub struct CachedRecipeEntry {
     entry: RecipeEntry,
     metadata: once_cell::unsync::OnceCell<Box<Metadata>>,
@@ -166,17 +592,29 @@ impl CachedRecipeEntry {
 
     pub fn parsed(&self, ctx: &Context) -> Result<&cooklang::RecipeResult> {
         self.parsed
-            .get_or_try_init(|| {
-                let parser = ctx.parser()?;
-                let r = self
-                    .content()?
-                    .parse_with_options(parser, ctx.parse_options(Some(self.entry.path())))
-                    .map(map_recipe);
-                Ok(Box::new(r))
-            })
+            .get_or_try_init(|| self.parse(ctx).map(Box::new))
             .map(|r| r.as_ref())
     }
 
+    /// Parses the recipe again, without the cache, to get an owned recipe
+    pub fn parse(&self, ctx: &Context) -> Result<cooklang::RecipeResult> {
+        let parser = ctx.parser()?;
+        let r = self
+            .content()?
+            .parse_with_options(parser, ctx.parse_options(Some(self.entry.path())))
+            .map(|r| inherit(r, self.entry.path(), &ctx.base_path))
+            .map(map_recipe);
+        Ok(r)
+    }
+
+    /// Takes the cached recipe, or parses it if there is none
+    pub fn into_parsed(mut self, ctx: &Context) -> Result<cooklang::RecipeResult> {
+        match self.parsed.take() {
+            Some(r) => Ok(*r),
+            None => self.parse(ctx),
+        }
+    }
+
     pub fn metadata(&self, ctx: &Context, try_full: bool) -> Result<&Metadata> {
         // first try cached full recipe
         if let Some(m) = self
@@ -198,17 +636,120 @@ impl CachedRecipeEntry {
                         }
                     }
                 }
-                let m = self
+                let mut m = self
                     .content()?
                     .metadata_with_options(parser, ctx.parse_options(None))
                     .into_output()
                     .ok_or(anyhow::anyhow!("Can't parse metadata"))?;
+                inherit_metadata(&mut m, self.entry.path(), &ctx.base_path);
                 Ok(Box::new(m))
             })
             .map(|m| m.as_ref())
     }
 }
 
+impl CachedRecipeEntry {
+    /// Creates an entry with an already parsed recipe
+    pub fn with_parsed(entry: RecipeEntry, parsed: cooklang::RecipeResult) -> Self {
+        Self {
+            entry,
+            metadata: Default::default(),
+            parsed: once_cell::unsync::OnceCell::with_value(Box::new(parsed)),
+        }
+    }
+}
+
+/// Maps the items in parallel using all the available cores
+///
+/// The output is in the same order as the input.
+pub fn par_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if threads <= 1 || items.len() < 2 {
+        return items.iter().map(f).collect();
+    }
+    let chunk_size = items.len().div_ceil(threads);
+    let f = &f;
+    std::thread::scope(|s| {
+        let handles = items
+            .chunks(chunk_size)
+            .map(|chunk| s.spawn(move || chunk.iter().map(f).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("worker thread panicked"))
+            .collect()
+    })
+}
+
+/// What the parser threads share, [`Context`] can't be shared between threads
+struct SharedParse<'a> {
+    parser: &'a cooklang::CooklangParser,
+    base_path: &'a Utf8Path,
+    index: Option<cooklang_fs::FsIndex>,
+}
+
+impl SharedParse<'_> {
+    fn parse_options(&self, relative_to: &Utf8Path) -> cooklang::ParseOptions<'_> {
+        let recipe_ref_check = self.index.as_ref().map(|index| {
+            let relative_to = relative_to.parent().map(|p| p.to_owned());
+            Box::new(move |name: &str| {
+                if index.resolve(name, relative_to.as_deref()).is_ok() {
+                    CheckResult::Ok
+                } else {
+                    CheckResult::Warning(vec![crate::RECIPE_REF_ERROR.into()])
+                }
+            }) as cooklang::analysis::RecipeRefCheck
+        });
+        cooklang::ParseOptions {
+            recipe_ref_check,
+            metadata_validator: Some(Box::new(metadata_validator)),
+        }
+    }
+
+    fn parse(&self, entry: &RecipeEntry) -> Result<cooklang::RecipeResult> {
+        let r = entry
+            .read()?
+            .parse_with_options(self.parser, self.parse_options(entry.path()))
+            .map(|r| inherit(r, entry.path(), self.base_path))
+            .map(map_recipe);
+        Ok(r)
+    }
+}
+
+/// Parses many recipes in parallel
+///
+/// Same as calling [`CachedRecipeEntry::parsed`] on each one, but faster for
+/// big collections. The output is in the same order as the input.
+#[tracing::instrument(level = "debug", skip_all, fields(recipes = entries.len()))]
+pub fn parse_all(ctx: &Context, entries: Vec<RecipeEntry>) -> Result<Vec<CachedRecipeEntry>> {
+    let index = if ctx.config.recipe_ref_check {
+        Some(
+            cooklang_fs::new_index(&ctx.base_path, ctx.config.max_depth)?
+                .config_dir(crate::COOK_DIR.to_string())
+                .rules(ctx.config.discovery.clone())
+                .indexed()?,
+        )
+    } else {
+        None
+    };
+    let shared = SharedParse {
+        parser: ctx.parser()?,
+        base_path: &ctx.base_path,
+        index,
+    };
+    let results = par_map(&entries, |entry| shared.parse(entry));
+    entries
+        .into_iter()
+        .zip(results)
+        .map(|(entry, r)| Ok(CachedRecipeEntry::with_parsed(entry, r?)))
+        .collect()
+}
+
 impl std::ops::Deref for CachedRecipeEntry {
     type Target = RecipeEntry;
 
@@ -242,11 +783,9 @@ pub fn metadata_validator(
                 }
             }
         }
-        "emoji" => {
-            if value.as_str().and_then(get_emoji).is_none() {
-                opts.include(false);
-                return CheckResult::Warning(vec!["Value is not an emoji".into()]);
-            }
+        "emoji" if value.as_str().and_then(get_emoji).is_none() => {
+            opts.include(false);
+            return CheckResult::Warning(vec!["Value is not an emoji".into()]);
         }
         _ => {}
     }
@@ -279,6 +818,52 @@ pub fn get_emoji(s: &str) -> Option<&'static str> {
     }
 }
 
+/// Writes a line diff between two texts
+///
+/// Only the changed lines are written, with `-` and `+` prefixes and the line
+/// numbers of the old text.
+pub fn write_diff(mut w: impl std::io::Write, old: &str, new: &str) -> std::io::Result<()> {
+    use yansi::Paint;
+
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+    let (n, m) = (old_lines.len(), new_lines.len());
+
+    // longest common subsequence table, from the end
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut last_hunk = None;
+    while i < n || j < m {
+        if i < n && j < m && old_lines[i] == new_lines[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        if last_hunk != Some(i) {
+            writeln!(w, "{}", format!("@@ line {} @@", i + 1).cyan())?;
+        }
+        if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            writeln!(w, "{}", format!("+{}", new_lines[j]).green())?;
+            j += 1;
+        } else {
+            writeln!(w, "{}", format!("-{}", old_lines[i]).red())?;
+            i += 1;
+        }
+        last_hunk = Some(i);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -297,4 +882,29 @@ mod tests {
         assert!(!is_valid_tag("other/characters"));
         assert!(!is_valid_tag("other@[]chara€cters"));
     }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("meatbal", "Meatballs").is_some());
+        assert!(fuzzy_score("mtbls", "Meatballs").is_some());
+        assert!(fuzzy_score("xyz", "Meatballs").is_none());
+        assert!(
+            fuzzy_score("ball", "meat/Balls").unwrap() > fuzzy_score("ball", "Meatballs").unwrap()
+        );
+        assert!(
+            fuzzy_score("meatbal", "Meatballs").unwrap()
+                > fuzzy_score("mtbls", "Meatballs").unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serve")]
+    fn test_url_and_xml() {
+        assert_eq!(encode_path("Mains/Beef Stew"), "Mains/Beef%20Stew");
+        assert_eq!(encode_path("Crème"), "Cr%C3%A8me");
+        assert_eq!(
+            escape_xml(r#"<a href="x">&'"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;"
+        );
+    }
 }
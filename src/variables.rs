@@ -0,0 +1,159 @@
+//! Template variables in recipes
+//!
+//! A recipe can have `{{name}}` placeholders anywhere in its text, replaced
+//! before parsing with the values of `--set name=value` or of a variant. The
+//! variants are in `<recipe>.variants.toml` next to the recipe, a table of
+//! values for each one. `{{name|default}}` gives the value used when there
+//! is none.
+//!
+//! Braces that are not a valid placeholder are left as they are, so a
+//! quantity can be a variable: `@flour{{{flour}}%g}`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+};
+
+use anyhow::{bail, Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+
+struct Placeholder<'a> {
+    range: Range<usize>,
+    name: &'a str,
+    default: Option<&'a str>,
+}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn placeholders(text: &str) -> Vec<Placeholder<'_>> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = text[pos..].find("{{").map(|i| i + pos) {
+        let inner_start = start + 2;
+        let Some(end) = text[inner_start..].find("}}").map(|i| i + inner_start) else {
+            break;
+        };
+        let inner = &text[inner_start..end];
+        let (name, default) = match inner.split_once('|') {
+            Some((name, default)) => (name.trim(), Some(default.trim())),
+            None => (inner.trim(), None),
+        };
+        if !inner.contains('\n') && is_name(name) {
+            found.push(Placeholder {
+                range: start..end + 2,
+                name,
+                default,
+            });
+            pos = end + 2;
+        } else {
+            pos = start + 1;
+        }
+    }
+    found
+}
+
+/// Names of the variables of a recipe, in order of appearance
+pub fn variables(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    for p in placeholders(text) {
+        if !names.contains(&p.name) {
+            names.push(p.name);
+        }
+    }
+    names
+}
+
+/// Replaces the placeholders of the text
+///
+/// Fails if a variable has no value and no default.
+pub fn substitute(text: &str, values: &HashMap<String, String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut missing = Vec::new();
+    let mut last = 0;
+    for p in placeholders(text) {
+        out.push_str(&text[last..p.range.start]);
+        match values.get(p.name).map(String::as_str).or(p.default) {
+            Some(value) => out.push_str(value),
+            None if !missing.contains(&p.name) => missing.push(p.name),
+            None => {}
+        }
+        last = p.range.end;
+    }
+    out.push_str(&text[last..]);
+    if !missing.is_empty() {
+        bail!(
+            "No value for the recipe variables: {}. Give them with `--set NAME=VALUE` or a variant",
+            missing.join(", ")
+        );
+    }
+    Ok(out)
+}
+
+/// Variants of a recipe, the values of each one by name
+pub type Variants = BTreeMap<String, HashMap<String, String>>;
+
+/// Path of the variants file of a recipe
+pub fn variants_path(recipe: &Utf8Path) -> Utf8PathBuf {
+    recipe.with_extension("variants.toml")
+}
+
+/// Loads the variants of a recipe, empty if it has no variants file
+pub fn load_variants(recipe: &Utf8Path) -> Result<Variants> {
+    let path = variants_path(recipe);
+    if !path.is_file() {
+        return Ok(Variants::new());
+    }
+    let content =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read '{path}'"))?;
+    let variants: BTreeMap<String, BTreeMap<String, toml::Value>> =
+        toml::from_str(&content).with_context(|| format!("Bad variants file '{path}'"))?;
+    let variants = variants
+        .into_iter()
+        .map(|(variant, values)| {
+            let values = values
+                .into_iter()
+                .map(|(name, value)| match value {
+                    toml::Value::String(s) => (name, s),
+                    other => (name, other.to_string()),
+                })
+                .collect();
+            (variant, values)
+        })
+        .collect();
+    Ok(variants)
+}
+
+/// Parses a `NAME=VALUE` argument
+pub fn parse_assignment(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, found '{s}'"))?;
+    let name = name.trim();
+    if !is_name(name) {
+        return Err(format!(
+            "invalid variable name '{name}', use letters, numbers, '_' and '-'"
+        ));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitute_placeholders() {
+        let text = "Add @{{fruit}}{{{amount|150}}%g} to the {{ fruit }} muffins {{not valid}}";
+        assert_eq!(variables(text), ["fruit", "amount"]);
+        let values = HashMap::from([("fruit".to_string(), "blueberry".to_string())]);
+        assert_eq!(
+            substitute(text, &values).unwrap(),
+            "Add @blueberry{150%g} to the blueberry muffins {{not valid}}"
+        );
+        assert!(substitute("{{fruit}}", &HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Sections and steps of a recipe that belong to a variant
+//!
+//! Recipes that document more than one method tag the parts of each one with
+//! the variant in brackets, at the end of a section title or at the start of
+//! a step:
+//!
+//! ```cooklang
+//! == Bake [oven] ==
+//! Bake in the #oven{} for ~{25%minutes}.
+//!
+//! == Bake [air-fryer] ==
+//! Cook in the #air fryer{} for ~{15%minutes}.
+//!
+//! [air-fryer] Shake the basket halfway.
+//! ```
+//!
+//! Variants are in groups, like `[method: oven]`, with an unnamed group for
+//! the ones without. Choosing variants removes the parts of the others in
+//! their groups before parsing, so the ingredients of the removed parts are
+//! gone too. When choosing, each group needs exactly one.
+
+use std::{borrow::Cow, collections::BTreeMap};
+
+use anyhow::{bail, Result};
+
+/// Variants of each group, the unnamed group is `""`
+pub type Groups = BTreeMap<String, Vec<String>>;
+
+#[derive(Debug, PartialEq)]
+struct Tag<'a> {
+    group: &'a str,
+    variant: &'a str,
+}
+
+fn is_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Parses the inside of the brackets
+fn parse_tag(inner: &str) -> Option<Tag<'_>> {
+    let (group, variant) = match inner.split_once(':') {
+        Some((group, variant)) => (group.trim(), variant.trim()),
+        None => ("", inner.trim()),
+    };
+    (is_name(variant) && (group.is_empty() || is_name(group))).then_some(Tag { group, variant })
+}
+
+/// Tag at the end of a section line, and the line without it
+fn section_tag(line: &str) -> Option<(Tag<'_>, String)> {
+    let title = line.trim().trim_end_matches('=').trim_end();
+    let inner = title.strip_suffix(']')?;
+    let open = inner.rfind('[')?;
+    let tag = parse_tag(&inner[open + 1..])?;
+    let start = line.find(&title[open..])?;
+    let mut rest = line.to_string();
+    rest.replace_range(start..start + title.len() - open, "");
+    Some((tag, rest))
+}
+
+/// Tag at the start of a step line, and the line without it
+fn step_tag(line: &str) -> Option<(Tag<'_>, &str)> {
+    let inner = line.trim_start().strip_prefix('[')?;
+    let close = inner.find(']')?;
+    let tag = parse_tag(&inner[..close])?;
+    Some((tag, inner[close + 1..].trim_start()))
+}
+
+enum Line<'a> {
+    /// Not part of a step, like metadata or the frontmatter
+    Other,
+    Blank,
+    Section(Option<(Tag<'a>, String)>),
+    /// First line of a step
+    Step(Option<(Tag<'a>, &'a str)>),
+    /// Next lines of a step
+    Continuation,
+}
+
+fn lines(text: &str) -> impl Iterator<Item = (&str, Line<'_>)> {
+    let mut frontmatter = text.starts_with("---");
+    let mut first = true;
+    let mut in_step = false;
+    text.lines().map(move |line| {
+        let trimmed = line.trim();
+        if frontmatter {
+            if !first && trimmed == "---" {
+                frontmatter = false;
+            }
+            first = false;
+            return (line, Line::Other);
+        }
+        let kind = if trimmed.is_empty() {
+            in_step = false;
+            Line::Blank
+        } else if trimmed.starts_with('=') {
+            in_step = false;
+            Line::Section(section_tag(line))
+        } else if trimmed.starts_with(">>") || trimmed.starts_with("--") {
+            Line::Other
+        } else if in_step {
+            Line::Continuation
+        } else {
+            in_step = true;
+            Line::Step(step_tag(line))
+        };
+        (line, kind)
+    })
+}
+
+/// The variant groups of a recipe
+pub fn groups(text: &str) -> Groups {
+    let mut groups = Groups::new();
+    for (_, line) in lines(text) {
+        let tag = match line {
+            Line::Section(Some((tag, _))) | Line::Step(Some((tag, _))) => tag,
+            _ => continue,
+        };
+        let variants = groups.entry(tag.group.to_string()).or_default();
+        if !variants.iter().any(|v| v == tag.variant) {
+            variants.push(tag.variant.to_string());
+        }
+    }
+    groups
+}
+
+fn group_name(group: &str) -> String {
+    if group.is_empty() {
+        "variants".to_string()
+    } else {
+        format!("'{group}' variants")
+    }
+}
+
+/// Keeps only the sections and steps of the chosen variants
+///
+/// Every group needs exactly one chosen variant. The tags of the kept ones
+/// are removed.
+pub fn select(text: &str, chosen: &[&str]) -> Result<String> {
+    let groups = groups(text);
+    for (group, variants) in &groups {
+        let selected = variants
+            .iter()
+            .filter(|v| chosen.contains(&v.as_str()))
+            .collect::<Vec<_>>();
+        match selected.as_slice() {
+            [_] => {}
+            [] => bail!(
+                "Choose one of the {}: {}",
+                group_name(group),
+                variants.join(", ")
+            ),
+            [a, b, ..] => bail!(
+                "Only one of the {} can be chosen, found '{a}' and '{b}'",
+                group_name(group)
+            ),
+        }
+    }
+
+    let kept = |tag: &Tag| chosen.contains(&tag.variant);
+    let mut out = String::with_capacity(text.len());
+    let mut in_section = true;
+    let mut in_step = true;
+    for (line, kind) in lines(text) {
+        let line: Option<Cow<str>> = match kind {
+            Line::Other | Line::Blank => in_section.then_some(line.into()),
+            Line::Section(tag) => {
+                in_section = tag.as_ref().is_none_or(|(t, _)| kept(t));
+                match tag {
+                    Some((tag, rest)) if kept(&tag) => Some(rest.into()),
+                    Some(_) => None,
+                    None => Some(line.into()),
+                }
+            }
+            Line::Step(tag) => {
+                in_step = in_section && tag.as_ref().is_none_or(|(t, _)| kept(t));
+                in_step.then(|| tag.map_or(line, |(_, rest)| rest).into())
+            }
+            Line::Continuation => in_step.then_some(line.into()),
+        };
+        if let Some(line) = line {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPE: &str = "\
+>> servings: 4
+
+Mix the @flour{200%g}.
+
+== Bake [oven] ==
+Bake in the #oven{}.
+
+== Bake [air-fryer] ==
+Use the #air fryer{}.
+
+[air-fryer] Shake the @butter{10%g}
+halfway.
+
+== Serve ==
+[size: large] Serve.
+";
+
+    #[test]
+    fn variant_groups() {
+        let groups = groups(RECIPE);
+        assert_eq!(groups[""], ["oven", "air-fryer"]);
+        assert_eq!(groups["size"], ["large"]);
+    }
+
+    #[test]
+    fn select_variants() {
+        let text = select(RECIPE, &["oven", "large"]).unwrap();
+        assert!(text.contains("== Bake  =="));
+        assert!(text.contains("Bake in the #oven{}."));
+        assert!(
+            !text.contains("air fryer") && !text.contains("butter") && !text.contains("halfway")
+        );
+        assert!(text.contains("\nServe.\n"));
+
+        let text = select(RECIPE, &["air-fryer", "large"]).unwrap();
+        assert!(!text.contains("oven"));
+        assert!(text.contains("\nShake the @butter{10%g}\nhalfway.\n"));
+
+        assert!(select(RECIPE, &["oven"]).is_err());
+        assert!(select(RECIPE, &["oven", "air-fryer", "large"]).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! How much a step or section makes
+//!
+//! Steps and sections can declare what they make in the `yields` metadata,
+//! then the intermediate references to them show how much each later step
+//! uses, and it's checked that they don't use more than what is made:
+//!
+//! ```yaml
+//! yields:
+//!   1: 900 g          # step 1
+//!   2.3: 1 l          # step 3 of section 2
+//!   Dough: 1.2 kg     # the section named "Dough", or `section 2`
+//! ```
+//!
+//! The amounts are for the servings of the recipe, they scale linearly.
+
+use std::fmt::Write as _;
+
+use cooklang::{
+    model::{IngredientReferenceTarget, Item},
+    Content, Converter, ScaledRecipe, Value,
+};
+use serde::Serialize;
+
+use crate::{
+    scaling::parse_amount,
+    util::{convert_value, fmt_number},
+};
+
+pub const YIELDS_KEY: &str = "yields";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    /// Section index and content index in the section
+    Step(usize, usize),
+    Section(usize),
+}
+
+#[derive(Debug, Serialize)]
+pub struct Yield {
+    /// Like `step 1` or `section Dough`
+    pub label: String,
+    pub value: f64,
+    pub unit: Option<String>,
+    pub uses: Vec<Use>,
+    #[serde(skip)]
+    target: Target,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Use {
+    /// Step that uses it, like `step 3`
+    pub step: String,
+    /// In the unit of the yield, `None` when unknown
+    pub value: Option<f64>,
+}
+
+impl Yield {
+    pub fn used(&self) -> f64 {
+        self.uses.iter().filter_map(|u| u.value).sum()
+    }
+
+    pub fn is_overused(&self) -> bool {
+        // some margin for rounding
+        self.used() > self.value * 1.005
+    }
+
+    fn amount(&self, value: f64) -> String {
+        match &self.unit {
+            Some(u) => format!("{} {u}", fmt_number(value)),
+            None => fmt_number(value),
+        }
+    }
+}
+
+/// Yields of a recipe with their uses, and the warnings found
+pub fn yields(recipe: &ScaledRecipe, converter: &Converter) -> (Vec<Yield>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let Some(map) = recipe
+        .metadata
+        .map
+        .get(YIELDS_KEY)
+        .and_then(|v| v.as_mapping())
+    else {
+        return (Vec::new(), warnings);
+    };
+    let factor = crate::scaling::scale_factor(recipe).unwrap_or(1.0);
+
+    let mut yields = Vec::new();
+    for (key, value) in map {
+        let key = match key {
+            serde_yaml::Value::String(s) => s.clone(),
+            serde_yaml::Value::Number(n) => n.to_string(),
+            _ => {
+                warnings.push(format!("Invalid key in `{YIELDS_KEY}`: {key:?}"));
+                continue;
+            }
+        };
+        let Some((target, label)) = resolve_target(recipe, &key) else {
+            warnings.push(format!("No step or section '{key}' in `{YIELDS_KEY}`"));
+            continue;
+        };
+        let amount = match value {
+            serde_yaml::Value::String(s) => parse_amount(s.trim_start_matches('~')),
+            serde_yaml::Value::Number(n) => n.as_f64().map(|v| (v, None)).ok_or_else(String::new),
+            _ => Err(String::new()),
+        };
+        let Ok((value, unit)) = amount else {
+            warnings.push(format!("Invalid amount for '{key}' in `{YIELDS_KEY}`"));
+            continue;
+        };
+        yields.push(Yield {
+            label,
+            value: value * factor,
+            unit,
+            uses: Vec::new(),
+            target,
+        });
+    }
+
+    let many_sections = recipe.sections.len() > 1;
+    for (section_index, section) in recipe.sections.iter().enumerate() {
+        for content in &section.content {
+            let Content::Step(step) = content else {
+                continue;
+            };
+            for item in &step.items {
+                let &Item::Ingredient { index } = item else {
+                    continue;
+                };
+                let igr = &recipe.ingredients[index];
+                let target = match igr.relation.references_to() {
+                    Some((i, IngredientReferenceTarget::Step)) => Target::Step(section_index, i),
+                    Some((i, IngredientReferenceTarget::Section)) => Target::Section(i),
+                    _ => continue,
+                };
+                let Some(y) = yields.iter_mut().find(|y| y.target == target) else {
+                    continue;
+                };
+                let value = igr.quantity.as_ref().and_then(|q| {
+                    let value = match q.value() {
+                        Value::Number(n) => n.value(),
+                        Value::Range { end, .. } => end.value(),
+                        Value::Text(_) => return None,
+                    };
+                    match (q.unit(), y.unit.as_deref()) {
+                        (None, None) => Some(value),
+                        (Some(from), Some(to)) => convert_value(value, from, to, converter),
+                        _ => None,
+                    }
+                });
+                let label = step_label(many_sections, section_index, step.number);
+                if value.is_none() && igr.quantity.is_some() {
+                    warnings.push(format!(
+                        "Can't compare the amount of '{}' in {label} with what {} makes",
+                        igr.display_name(),
+                        y.label
+                    ));
+                }
+                y.uses.push(Use { step: label, value });
+            }
+        }
+    }
+
+    for y in &yields {
+        if y.is_overused() {
+            warnings.push(format!(
+                "{} makes {} but {} is used",
+                y.label,
+                y.amount(y.value),
+                y.amount(y.used())
+            ));
+        }
+    }
+
+    (yields, warnings)
+}
+
+fn resolve_target(recipe: &ScaledRecipe, key: &str) -> Option<(Target, String)> {
+    let key = key.trim();
+    let many_sections = recipe.sections.len() > 1;
+    let step = |section: usize, number: u32| {
+        let content = recipe
+            .sections
+            .get(section)?
+            .content
+            .iter()
+            .position(|c| matches!(c, Content::Step(s) if s.number == number))?;
+        Some((
+            Target::Step(section, content),
+            step_label(many_sections, section, number),
+        ))
+    };
+
+    if let Ok(number) = key.parse::<u32>() {
+        return step(0, number);
+    }
+    if let Some((section, number)) = key.split_once('.') {
+        if let (Ok(section), Ok(number)) = (section.parse::<usize>(), number.parse::<u32>()) {
+            return step(section.checked_sub(1)?, number);
+        }
+    }
+    let index = match key.strip_prefix("section ").map(str::parse::<usize>) {
+        Some(Ok(n)) => n.checked_sub(1).filter(|&i| i < recipe.sections.len())?,
+        _ => recipe.sections.iter().position(|s| {
+            s.name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(key))
+        })?,
+    };
+    let label = match &recipe.sections[index].name {
+        Some(name) => format!("section {name}"),
+        None => format!("section {}", index + 1),
+    };
+    Some((Target::Section(index), label))
+}
+
+fn step_label(many_sections: bool, section: usize, number: u32) -> String {
+    if many_sections {
+        format!("step {}.{number}", section + 1)
+    } else {
+        format!("step {number}")
+    }
+}
+
+/// Writes the yields and their uses, after a rendered recipe
+pub fn write_yields(mut w: impl std::io::Write, yields: &[Yield]) -> std::io::Result<()> {
+    use yansi::Paint;
+
+    if yields.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "\n{}:", "Yields".bold().underline())?;
+    for y in yields {
+        writeln!(w, "  {} {}", y.label.bold(), y.amount(y.value))?;
+        for u in &y.uses {
+            let mut line = format!("    {} ", u.step);
+            match u.value {
+                Some(v) if y.value > 0.0 => {
+                    let pct = v / y.value * 100.0;
+                    write!(line, "{} ({pct:.0}%)", y.amount(v)).unwrap();
+                }
+                Some(v) => line += &y.amount(v),
+                None => line += "?",
+            }
+            writeln!(w, "{}", line.dim())?;
+        }
+        if !y.uses.is_empty() {
+            let left = y.value - y.used();
+            if y.is_overused() {
+                writeln!(w, "    {}", format!("{} short", y.amount(-left)).red())?;
+            } else if left > y.value * 0.005 {
+                writeln!(w, "    {}", format!("{} left", y.amount(left)).dim())?;
+            }
+        }
+    }
+    Ok(())
+}
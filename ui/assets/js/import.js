@@ -0,0 +1,18 @@
+(function () {
+  const text = htmx.find("#import-text");
+  const file = htmx.find("#import-file");
+  if (!text) return;
+
+  async function load(f) {
+    if (f) text.value = await f.text();
+  }
+
+  text.addEventListener("dragover", (ev) => ev.preventDefault());
+  text.addEventListener("drop", (ev) => {
+    const f = ev.dataTransfer?.files[0];
+    if (!f) return;
+    ev.preventDefault();
+    load(f);
+  });
+  file?.addEventListener("change", () => load(file.files[0]));
+})();
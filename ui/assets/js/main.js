@@ -298,6 +298,22 @@ function formatTimestamp(secsFromEpoch) {
   }).format(date);
 }
 
+function formatRelativeDate(isoDate) {
+  const day = 24 * 60 * 60 * 1000;
+  const today = new Date();
+  const todayUtc = Date.UTC(
+    today.getFullYear(),
+    today.getMonth(),
+    today.getDate(),
+  );
+  const days = Math.round((Date.parse(isoDate) - todayUtc) / day);
+  const format = new Intl.RelativeTimeFormat(currentLocale, { numeric: "auto" });
+  if (Math.abs(days) < 14) return format.format(days, "day");
+  if (Math.abs(days) < 60) return format.format(Math.round(days / 7), "week");
+  if (Math.abs(days) < 730) return format.format(Math.round(days / 30), "month");
+  return format.format(Math.round(days / 365), "year");
+}
+
 function formatNumber(num) {
   return new Intl.NumberFormat(currentLocale, {
     maximumFractionDigits: 3,
@@ -321,6 +337,13 @@ function formatAllElements(rootElement) {
     }
   });
 
+  htmx.findAll(rootElement, "[format-relative]").forEach((el) => {
+    const date = el.getAttribute("datetime");
+    if (date && !Number.isNaN(Date.parse(date))) {
+      el.textContent = formatRelativeDate(date);
+    }
+  });
+
   htmx.findAll(rootElement, "[format-number]").forEach((el) => {
     const num = Number(el.textContent);
     if (Number.isFinite(num)) {
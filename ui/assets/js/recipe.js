@@ -46,6 +46,106 @@ document.querySelectorAll("a[highlight-target-on-hover]").forEach((el) => {
   });
 });
 
+/* Cooking progress, kept across reloads for a while and shared with the
+ * other devices with the recipe open */
+(function () {
+  const key = "chef-cook:" + location.pathname;
+  const maxAge = 2 * 24 * 60 * 60 * 1000;
+  const device = Math.random().toString(36).slice(2);
+  const boxes = document.querySelectorAll('input[type="checkbox"][id*=" check "]');
+  const steps = document.querySelectorAll("[data-step-key]");
+  let socket = null;
+
+  let saved = null;
+  try {
+    saved = JSON.parse(localStorage.getItem(key));
+  } catch {}
+  if (saved && Date.now() - saved.at > maxAge) {
+    localStorage.removeItem(key);
+    saved = null;
+  }
+
+  function setCurrent(stepKey) {
+    steps.forEach((el) =>
+      el.classList.toggle("border-primary-9", el.dataset.stepKey === stepKey),
+    );
+    // reaching the last step ends the session, ask how it went
+    const rate = document.querySelector("#rate[data-prompt]");
+    const last = steps[steps.length - 1];
+    if (rate && !rate.open && last && last.dataset.stepKey === stepKey) {
+      rate.open = true;
+      rate.scrollIntoView({ behavior: "smooth", block: "center" });
+    }
+  }
+
+  function currentState() {
+    const checked = [...boxes].filter((el) => el.checked).map((el) => el.id);
+    const current = document.querySelector("[data-step-key].border-primary-9");
+    return { checked, step: current ? current.dataset.stepKey : null };
+  }
+
+  function save() {
+    const state = currentState();
+    if (state.checked.length === 0 && !state.step) {
+      localStorage.removeItem(key);
+    } else {
+      localStorage.setItem(key, JSON.stringify({ ...state, at: Date.now() }));
+    }
+  }
+
+  function apply(state) {
+    boxes.forEach((el) => (el.checked = state.checked.includes(el.id)));
+    setCurrent(state.step);
+  }
+
+  function send() {
+    if (socket?.readyState !== WebSocket.OPEN) return;
+    const state = { from: device, ...currentState(), timer: currentTimerState() };
+    socket.send(JSON.stringify(state));
+  }
+
+  function changed() {
+    save();
+    send();
+  }
+
+  function connect() {
+    const protocol = location.protocol === "https:" ? "wss://" : "ws://";
+    const path = location.pathname.replace(/^\/r\//, "");
+    socket = new WebSocket(protocol + location.host + "/cook/ws/" + path);
+    socket.addEventListener("message", (ev) => {
+      const state = JSON.parse(ev.data);
+      if (state.from === device) return;
+      apply(state);
+      save();
+      applyTimerState(state.timer ?? null);
+    });
+    socket.addEventListener("close", () => setTimeout(connect, 5000));
+  }
+
+  if (saved) {
+    apply(saved);
+    if (saved.step) {
+      document
+        .querySelector(`[data-step-key="${saved.step}"]`)
+        ?.scrollIntoView({ block: "center" });
+    }
+  }
+
+  boxes.forEach((el) => el.addEventListener("change", changed));
+  // clicking a step makes it the current one, clicking it again clears it
+  steps.forEach((el) =>
+    el.addEventListener("click", (ev) => {
+      if (ev.target.closest("a, button, input")) return;
+      const isCurrent = el.classList.contains("border-primary-9");
+      setCurrent(isCurrent ? null : el.dataset.stepKey);
+      changed();
+    }),
+  );
+  document.addEventListener("chef:timer", send);
+  connect();
+})();
+
 /* Step ingredients layout */
 function setLayout(layout) {
   document.cookie = "igr_layout=" + layout + "; Path=/;max-age=31536000";
@@ -59,20 +159,80 @@ function setLayout(layout) {
   if (el) el.addEventListener("click", () => setLayout(layout));
 });
 
+/* Quantity overrides for this visit, like "I only have 400 g", optionally
+ * scaling the rest of the ingredients around it. The file is not changed. */
+const anchorKey = "chef-anchor:" + location.pathname + location.search;
+let anchor = { factor: 1, overrides: {} };
+try {
+  anchor = JSON.parse(sessionStorage.getItem(anchorKey)) ?? anchor;
+} catch {}
+
+function isAnchored() {
+  return anchor.factor !== 1 || Object.keys(anchor.overrides).length > 0;
+}
+
+function saveAnchor() {
+  if (isAnchored()) {
+    sessionStorage.setItem(anchorKey, JSON.stringify(anchor));
+  } else {
+    sessionStorage.removeItem(anchorKey);
+  }
+}
+
+function renderAnchored(el) {
+  const value = el.querySelector("[data-quantity-value]");
+  if (!value.hasAttribute("data-html")) {
+    value.setAttribute("data-html", value.innerHTML);
+  }
+  const override = anchor.overrides[el.id];
+  if (override !== undefined) {
+    value.textContent = formatNumber(override);
+  } else if (anchor.factor !== 1) {
+    const scaled = Number(value.getAttribute("data-number")) * anchor.factor;
+    value.textContent = formatNumber(scaled);
+  } else {
+    value.innerHTML = value.getAttribute("data-html");
+  }
+}
+
+function renderAllAnchored() {
+  document.querySelectorAll("[data-anchor]").forEach(renderAnchored);
+}
+
+// after the numbers are formatted
+document.addEventListener("DOMContentLoaded", () => {
+  if (isAnchored()) renderAllAnchored();
+});
+
 /* Convert popover functionality */
 function registerConvertPopover(el) {
   const q = document.getElementById(el.getAttribute("data-popover"));
   if (!q) return;
 
   function extractQuantity(el) {
-    const value = el.querySelector("[data-quantity-value]").textContent;
-    const unit = el.querySelector("[data-quantity-unit]").textContent;
-    return { value, unit };
+    const value = el.querySelector("[data-quantity-value]");
+    const unit = el.querySelector("[data-quantity-unit]");
+    return {
+      value: value.innerHTML,
+      number: value.getAttribute("data-number"),
+      unit: unit?.textContent,
+    };
   }
 
   function setQuantity(quantity) {
-    q.querySelector("[data-quantity-value]").textContent = quantity.value;
-    q.querySelector("[data-quantity-unit]").textContent = quantity.unit;
+    const value = q.querySelector("[data-quantity-value]");
+    value.innerHTML = quantity.value;
+    value.setAttribute("data-html", quantity.value);
+    if (quantity.number) {
+      value.setAttribute("data-number", quantity.number);
+    }
+    const unit = q.querySelector("[data-quantity-unit]");
+    if (unit) unit.textContent = quantity.unit;
+    if (q.hasAttribute("data-anchor")) {
+      delete anchor.overrides[q.id];
+      saveAnchor();
+      renderAnchored(q);
+    }
     el.dispatchEvent(new Event("closepopover"));
   }
 
@@ -80,12 +240,18 @@ function registerConvertPopover(el) {
   let original;
   if (q.hasAttribute("data-original-value")) {
     let value = q.getAttribute("data-original-value");
+    let number = q.getAttribute("data-original-number");
     let unit = q.getAttribute("data-original-unit");
-    original = { value, unit };
+    original = { value, number, unit };
   } else {
     original = extractQuantity(q);
+    const value = q.querySelector("[data-quantity-value]");
+    if (value.hasAttribute("data-html")) {
+      original.value = value.getAttribute("data-html");
+    }
     q.setAttribute("data-original-value", original.value);
-    q.setAttribute("data-original-unit", original.unit);
+    q.setAttribute("data-original-number", original.number ?? "");
+    q.setAttribute("data-original-unit", original.unit ?? "");
   }
 
   el.querySelectorAll("[data-conversion]").forEach((el) =>
@@ -98,6 +264,37 @@ function registerConvertPopover(el) {
   el.querySelector("#conv-reset").addEventListener("click", () => {
     setQuantity(original);
   });
+
+  if (!q.hasAttribute("data-anchor")) return;
+  const form = el.querySelector("[data-anchor-form]");
+  const resetScale = el.querySelector("#anchor-reset");
+  const value = q.querySelector("[data-quantity-value]");
+  const number = Number(value.getAttribute("data-number"));
+  const shown = anchor.overrides[q.id] ?? number * anchor.factor;
+  form.hidden = false;
+  form.elements.have.value = Math.round(shown * 1000) / 1000;
+  resetScale.hidden = !isAnchored();
+
+  form.addEventListener("submit", (ev) => {
+    ev.preventDefault();
+    const have = Number(form.elements.have.value);
+    if (form.elements.rest.checked) {
+      if (!(number > 0)) return;
+      anchor = { factor: have / number, overrides: {} };
+    } else {
+      anchor.overrides[q.id] = have;
+    }
+    saveAnchor();
+    renderAllAnchored();
+    el.dispatchEvent(new Event("closepopover"));
+  });
+
+  resetScale.addEventListener("click", () => {
+    anchor = { factor: 1, overrides: {} };
+    saveAnchor();
+    renderAllAnchored();
+    el.dispatchEvent(new Event("closepopover"));
+  });
 }
 
 htmx.onLoad((el) => {
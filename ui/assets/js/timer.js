@@ -40,6 +40,7 @@ class RecipeTimer {
     this.timeoutId = setTimeout(() => this.finish(), secs * 1000);
     this.intervalId = setInterval(() => this.updateText(), 250); // Could be optimized...
     this.state = "running";
+    saveTimer(this);
 
     this.timerPauseBtn.style.display = "";
     this.timerStartBtn.style.display = "none";
@@ -57,6 +58,7 @@ class RecipeTimer {
       this.end = null;
     }
     this.state = "paused";
+    saveTimer(this);
 
     this.timerPauseBtn.style.display = "none";
     this.timerStartBtn.style.display = "";
@@ -70,6 +72,7 @@ class RecipeTimer {
     if (this.intervalId) clearInterval(this.intervalId);
     this.intervalId = null;
     this.audio.play();
+    saveTimer(null);
     this.timerPauseBtn.style.display = "none";
     this.timerStartBtn.style.display = "none";
 
@@ -92,6 +95,7 @@ class RecipeTimer {
     this.intervalId = null;
     this.end = null;
     this.seconds = 0;
+    saveTimer(null);
   }
 
   remainingSeconds() {
@@ -140,6 +144,91 @@ function dateDiffSecs(start, end) {
 }
 
 let currentTimer = null;
+
+/* The timer is kept across reloads with its end time, and shared with the
+ * cooking session of the recipe page */
+const TIMER_KEY = "chef-timer";
+let applyingRemoteTimer = false;
+
+function saveTimer(timer) {
+  if (!timer) {
+    localStorage.removeItem(TIMER_KEY);
+  } else {
+    localStorage.setItem(
+      TIMER_KEY,
+      JSON.stringify({
+        name: timer.name,
+        state: timer.state,
+        end: timer.end ? timer.end.getTime() : null,
+        seconds: timer.seconds,
+      }),
+    );
+  }
+  if (!applyingRemoteTimer) {
+    document.dispatchEvent(new Event("chef:timer"));
+  }
+}
+
+function currentTimerState() {
+  try {
+    return JSON.parse(localStorage.getItem(TIMER_KEY));
+  } catch {
+    return null;
+  }
+}
+
+function savedSeconds(saved) {
+  if (saved.state === "running") {
+    return Math.round((saved.end - Date.now()) / 1000);
+  }
+  return saved.seconds;
+}
+
+/* Timer from another device */
+function applyTimerState(saved) {
+  if (JSON.stringify(saved) === JSON.stringify(currentTimerState())) return;
+  applyingRemoteTimer = true;
+  try {
+    const active = currentTimer && currentTimer.state !== "finished";
+    if (!saved || !(savedSeconds(saved) > 0)) {
+      if (active) currentTimer.destroy();
+      return;
+    }
+    if (!active) {
+      localStorage.setItem(TIMER_KEY, JSON.stringify(saved));
+      restoreTimer();
+      return;
+    }
+    currentTimer.pause();
+    currentTimer.name = saved.name;
+    currentTimer.timerNameEl.textContent = saved.name ?? "";
+    currentTimer.seconds = savedSeconds(saved);
+    if (saved.state === "running") {
+      currentTimer.start();
+    } else {
+      currentTimer.pause();
+      currentTimer.updateText();
+    }
+  } finally {
+    applyingRemoteTimer = false;
+  }
+}
+
+function restoreTimer() {
+  const saved = currentTimerState();
+  if (!saved) return;
+  const seconds = savedSeconds(saved);
+  if (!(seconds > 0)) {
+    saveTimer(null);
+    return;
+  }
+  currentTimer = new RecipeTimer(seconds, saved.name);
+  if (saved.state === "running") {
+    currentTimer.start();
+  } else {
+    currentTimer.pause();
+  }
+}
 function registerTimerBtn(el) {
   if (currentTimer !== null && currentTimer.state !== "finished") {
     el.disabled = true;
@@ -172,3 +261,5 @@ document.getElementById("timer-remove-btn").addEventListener("click", () => {
 htmx.onLoad((el) => {
   htmx.findAll(el, "[data-timer]").forEach(registerTimerBtn);
 });
+
+restoreTimer();